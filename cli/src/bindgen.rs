@@ -0,0 +1,466 @@
+//! TypeScript binding generation
+//!
+//! Emits fact input interfaces, per-rule result types and a small client for
+//! each document, so front-end code stays in sync with the rule definitions.
+
+use lemma::{
+    ArithmeticOperation, ConversionTarget, Expression, ExpressionKind, FactType, FactValue,
+    LemmaDoc, LemmaRule, LemmaType, TypeAnnotation,
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Language of the generated bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Lang {
+    /// TypeScript
+    Ts,
+}
+
+/// Runtime the generated client talks to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Target {
+    /// The HTTP API started with `lemma server`
+    Http,
+    /// The `WasmEngine` from the lemma wasm package
+    Wasm,
+}
+
+/// Maximum depth when following rule references to infer a result type
+const MAX_INFERENCE_DEPTH: usize = 32;
+
+const LEMMA_TYPES: &[&str] = &[
+    "text",
+    "number",
+    "date",
+    "boolean",
+    "regex",
+    "percentage",
+    "mass",
+    "length",
+    "volume",
+    "duration",
+    "temperature",
+    "power",
+    "energy",
+    "force",
+    "pressure",
+    "frequency",
+    "data",
+    "money",
+];
+
+/// Generate TypeScript bindings for the given documents
+pub fn generate_typescript(documents: &HashMap<String, LemmaDoc>, target: Target) -> String {
+    let mut doc_names: Vec<&String> = documents.keys().collect();
+    doc_names.sort();
+
+    let mut out = String::new();
+    out.push_str("// Generated by `lemma bindgen --lang ts`. Do not edit.\n\n");
+    write_prelude(&mut out, target);
+
+    for name in doc_names {
+        write_document(&mut out, &documents[name], documents);
+    }
+
+    out
+}
+
+fn write_prelude(out: &mut String, target: Target) {
+    let types: Vec<String> = LEMMA_TYPES.iter().map(|t| format!("\"{}\"", t)).collect();
+    let _ = writeln!(out, "export type LemmaType = {};\n", types.join(" | "));
+
+    out.push_str(
+        r#"export interface RuleOutcome<T extends LemmaType = LemmaType> {
+  /** Type of the value, null when the rule was vetoed or is missing facts */
+  type: T | null;
+  /** Value in Lemma notation, e.g. "100 EUR" */
+  value: string | null;
+  veto?: string;
+  missingFacts?: string[];
+}
+
+export interface LemmaTransport {
+  evaluate(
+    doc: string,
+    facts: Record<string, unknown>,
+    factTypes: Record<string, LemmaType>,
+    ruleTypes: Record<string, LemmaType | null>,
+  ): Promise<Record<string, RuleOutcome>>;
+}
+
+"#,
+    );
+
+    match target {
+        Target::Http => out.push_str(
+            r#"function toLemmaValue(value: unknown, type: LemmaType): string {
+  if (type === "text" && typeof value === "string") {
+    return JSON.stringify(value);
+  }
+  if (type === "percentage" && typeof value === "number") {
+    return `${value * 100}%`;
+  }
+  return String(value);
+}
+
+/** Transport for the HTTP API (`GET /evaluate/{doc}`) */
+export function httpTransport(baseUrl: string, init?: RequestInit): LemmaTransport {
+  return {
+    async evaluate(doc, facts, factTypes, ruleTypes) {
+      const params = new URLSearchParams();
+      for (const [name, value] of Object.entries(facts)) {
+        if (value !== undefined) {
+          params.set(name, toLemmaValue(value, factTypes[name] ?? "text"));
+        }
+      }
+      const url = `${baseUrl.replace(/\/$/, "")}/evaluate/${encodeURIComponent(doc)}?${params}`;
+      const response = await fetch(url, init);
+      const body = await response.json();
+      if (!response.ok) {
+        throw new Error(body.error ?? `HTTP ${response.status}`);
+      }
+      const outcomes: Record<string, RuleOutcome> = {};
+      for (const result of body.results) {
+        const value = result.value ?? null;
+        outcomes[result.name] = {
+          type: value === null ? null : ruleTypes[result.name] ?? null,
+          value,
+          veto: result.veto_reason ?? undefined,
+        };
+      }
+      return outcomes;
+    },
+  };
+}
+"#,
+        ),
+        Target::Wasm => out.push_str(
+            r#"/** Minimal shape of the `WasmEngine` exported by the lemma wasm package */
+export interface WasmEngineLike {
+  evaluate(docName: string, factValuesJson: string): string;
+}
+
+/** Transport for the wasm module, evaluating in-process */
+export function wasmTransport(engine: WasmEngineLike): LemmaTransport {
+  return {
+    async evaluate(doc, facts) {
+      const body = JSON.parse(engine.evaluate(doc, JSON.stringify(facts)));
+      if (!body.success) {
+        throw new Error(body.error ?? "Evaluation failed");
+      }
+      const outcomes: Record<string, RuleOutcome> = {};
+      for (const [name, rule] of Object.entries<any>(body.rules)) {
+        outcomes[name] = {
+          type: rule.result?.type ?? null,
+          value: rule.result?.value ?? null,
+          veto: rule.veto ?? undefined,
+          missingFacts: rule.missing_facts ?? undefined,
+        };
+      }
+      return outcomes;
+    },
+  };
+}
+"#,
+        ),
+    }
+}
+
+fn write_document(out: &mut String, doc: &LemmaDoc, documents: &HashMap<String, LemmaDoc>) {
+    let ident = type_name(&doc.name);
+
+    let facts: Vec<(String, LemmaType, bool)> = doc
+        .facts
+        .iter()
+        .filter_map(|fact| {
+            let FactType::Local(name) = &fact.fact_type else {
+                return None;
+            };
+            match &fact.value {
+                FactValue::Literal(lit) => Some((name.clone(), lit.to_type(), false)),
+                FactValue::TypeAnnotation(TypeAnnotation::LemmaType(t)) => {
+                    Some((name.clone(), t.clone(), true))
+                }
+                FactValue::DocumentReference(_) | FactValue::DocumentCollection(_) => None,
+            }
+        })
+        .collect();
+
+    let _ = writeln!(out, "\n// Document: {}\n", doc.name);
+    if let Some(commentary) = &doc.commentary {
+        let _ = writeln!(out, "/** {} */", commentary.replace("*/", "* /"));
+    }
+
+    let _ = writeln!(out, "export interface {}Facts {{", ident);
+    for (name, lemma_type, required) in &facts {
+        let optional = if *required { "" } else { "?" };
+        let _ = writeln!(
+            out,
+            "  /** {} */\n  {}{}: {};",
+            lemma_type,
+            name,
+            optional,
+            input_type(lemma_type)
+        );
+    }
+    out.push_str("}\n\n");
+
+    let rule_types: Vec<(&str, Option<LemmaType>)> = doc
+        .rules
+        .iter()
+        .map(|rule| (rule.name.as_str(), infer_rule_type(rule, doc, documents, 0)))
+        .collect();
+
+    let _ = writeln!(out, "export interface {}Results {{", ident);
+    for (name, lemma_type) in &rule_types {
+        let generic = lemma_type
+            .as_ref()
+            .map(|t| format!("<\"{}\">", t))
+            .unwrap_or_default();
+        let _ = writeln!(out, "  {}: RuleOutcome{};", property_name(name), generic);
+    }
+    out.push_str("}\n\n");
+
+    let _ = writeln!(
+        out,
+        "const {}FactTypes: Record<string, LemmaType> = {{",
+        lower_first(&ident)
+    );
+    for (name, lemma_type, _) in &facts {
+        let _ = writeln!(out, "  {}: \"{}\",", name, lemma_type);
+    }
+    out.push_str("};\n\n");
+
+    let _ = writeln!(
+        out,
+        "const {}RuleTypes: Record<string, LemmaType | null> = {{",
+        lower_first(&ident)
+    );
+    for (name, lemma_type) in &rule_types {
+        let value = lemma_type
+            .as_ref()
+            .map(|t| format!("\"{}\"", t))
+            .unwrap_or_else(|| "null".to_string());
+        let _ = writeln!(out, "  {}: {},", property_name(name), value);
+    }
+    out.push_str("};\n\n");
+
+    let facts_default = if facts.iter().any(|(_, _, required)| *required) {
+        ""
+    } else {
+        " = {}"
+    };
+    let _ = write!(
+        out,
+        r#"export class {ident}Client {{
+  constructor(private readonly transport: LemmaTransport) {{}}
+
+  async evaluate(facts: {ident}Facts{facts_default}): Promise<{ident}Results> {{
+    const outcomes = await this.transport.evaluate(
+      "{doc}",
+      facts as Record<string, unknown>,
+      {lower}FactTypes,
+      {lower}RuleTypes,
+    );
+    return outcomes as unknown as {ident}Results;
+  }}
+}}
+"#,
+        ident = ident,
+        facts_default = facts_default,
+        doc = doc.name,
+        lower = lower_first(&ident),
+    );
+}
+
+/// TypeScript type accepted for a fact of the given Lemma type
+fn input_type(lemma_type: &LemmaType) -> &'static str {
+    match lemma_type {
+        LemmaType::Number => "number",
+        LemmaType::Boolean => "boolean",
+        // Either a fraction (0.21) or Lemma notation ("21%")
+        LemmaType::Percentage => "number | string",
+        // Dates, regexes and values with units use Lemma notation ("100 EUR")
+        _ => "string",
+    }
+}
+
+/// Best-effort static type of a rule's result
+///
+/// Returns None when the type depends on values only known at evaluation time.
+fn infer_rule_type(
+    rule: &LemmaRule,
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    depth: usize,
+) -> Option<LemmaType> {
+    if depth > MAX_INFERENCE_DEPTH {
+        return None;
+    }
+
+    std::iter::once(&rule.expression)
+        .chain(rule.unless_clauses.iter().map(|clause| &clause.result))
+        .find_map(|expr| infer_expression_type(expr, doc, documents, depth))
+}
+
+fn infer_expression_type(
+    expr: &Expression,
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    depth: usize,
+) -> Option<LemmaType> {
+    match &expr.kind {
+        ExpressionKind::Literal(lit) => Some(lit.to_type()),
+        ExpressionKind::Comparison(..)
+        | ExpressionKind::Range(..)
+        | ExpressionKind::LogicalAnd(..)
+        | ExpressionKind::LogicalOr(..)
+        | ExpressionKind::LogicalNegation(..)
+        | ExpressionKind::FactHasAnyValue(_)
+        | ExpressionKind::RuleHasValue(_) => Some(LemmaType::Boolean),
+        ExpressionKind::MathematicalOperator(..) => Some(LemmaType::Number),
+        ExpressionKind::Veto(_) => None,
+        ExpressionKind::MissingItems(_) => Some(LemmaType::Text),
+        ExpressionKind::FunctionCall(_, _) => None,
+        // The winner is one of the candidates, which validation keeps to one type
+        ExpressionKind::Selection(_, candidates) => candidates
+            .iter()
+            .find_map(|candidate| infer_expression_type(candidate, doc, documents, depth)),
+        ExpressionKind::UnitConversion(_, target) => Some(conversion_type(target)),
+        ExpressionKind::FactReference(fact_ref) => {
+            let (doc, name) = resolve_path(&fact_ref.reference, doc, documents)?;
+            doc.facts
+                .iter()
+                .find_map(|fact| match (&fact.fact_type, &fact.value) {
+                    (FactType::Local(n), FactValue::Literal(lit)) if n == name => {
+                        Some(lit.to_type())
+                    }
+                    (
+                        FactType::Local(n),
+                        FactValue::TypeAnnotation(TypeAnnotation::LemmaType(t)),
+                    ) if n == name => Some(t.clone()),
+                    _ => None,
+                })
+        }
+        ExpressionKind::RuleReference(rule_ref) => {
+            let (doc, name) = resolve_path(&rule_ref.reference, doc, documents)?;
+            let rule = doc.rules.iter().find(|r| r.name == name)?;
+            infer_rule_type(rule, doc, documents, depth + 1)
+        }
+        ExpressionKind::Aggregate(aggregate) => {
+            let Some(rule_name) = &aggregate.rule else {
+                return Some(LemmaType::Number);
+            };
+            let (collection_doc, name) = resolve_path(&aggregate.collection, doc, documents)?;
+            let item_doc = collection_doc.facts.iter().find_map(|fact| {
+                match (&fact.fact_type, &fact.value) {
+                    (FactType::Local(n), FactValue::DocumentCollection(item)) if n == name => {
+                        documents.get(item)
+                    }
+                    _ => None,
+                }
+            })?;
+            let rule = item_doc.rules.iter().find(|r| &r.name == rule_name)?;
+            infer_rule_type(rule, item_doc, documents, depth + 1)
+        }
+        ExpressionKind::Arithmetic(left, op, right) => {
+            let left = infer_expression_type(left, doc, documents, depth)?;
+            let right = infer_expression_type(right, doc, documents, depth)?;
+            arithmetic_type(left, op, right)
+        }
+    }
+}
+
+/// Follow `fact.fact.name` through document references
+fn resolve_path<'a>(
+    path: &'a [String],
+    doc: &'a LemmaDoc,
+    documents: &'a HashMap<String, LemmaDoc>,
+) -> Option<(&'a LemmaDoc, &'a str)> {
+    let (name, prefix) = path.split_last()?;
+    let mut current = doc;
+    for segment in prefix {
+        current = current
+            .facts
+            .iter()
+            .find_map(|fact| match (&fact.fact_type, &fact.value) {
+                (FactType::Local(n), FactValue::DocumentReference(target)) if n == segment => {
+                    documents.get(target)
+                }
+                _ => None,
+            })?;
+    }
+    Some((current, name))
+}
+
+fn arithmetic_type(
+    left: LemmaType,
+    op: &ArithmeticOperation,
+    right: LemmaType,
+) -> Option<LemmaType> {
+    use LemmaType::*;
+
+    let scalar = |t: &LemmaType| matches!(t, Number | Percentage);
+    match (&left, &right) {
+        (Date, Duration) => Some(Date),
+        (Date, Date) if *op == ArithmeticOperation::Subtract => Some(Duration),
+        (Percentage, Percentage) => Some(Percentage),
+        (l, r) if scalar(l) && scalar(r) => Some(Number),
+        (l, r) if scalar(r) => Some(l.clone()),
+        (l, r) if scalar(l) && *op != ArithmeticOperation::Divide => Some(r.clone()),
+        (l, r) if l == r && *op == ArithmeticOperation::Divide => Some(Number),
+        (l, r) if l == r => Some(l.clone()),
+        _ => None,
+    }
+}
+
+fn conversion_type(target: &ConversionTarget) -> LemmaType {
+    match target {
+        ConversionTarget::Mass(_) => LemmaType::Mass,
+        ConversionTarget::Length(_) => LemmaType::Length,
+        ConversionTarget::Volume(_) => LemmaType::Volume,
+        ConversionTarget::Duration(_) => LemmaType::Duration,
+        ConversionTarget::Temperature(_) => LemmaType::Temperature,
+        ConversionTarget::Power(_) => LemmaType::Power,
+        ConversionTarget::Force(_) => LemmaType::Force,
+        ConversionTarget::Pressure(_) => LemmaType::Pressure,
+        ConversionTarget::Energy(_) => LemmaType::Energy,
+        ConversionTarget::Frequency(_) => LemmaType::Frequency,
+        ConversionTarget::Data(_) => LemmaType::Data,
+        ConversionTarget::Money(_) => LemmaType::Money,
+        ConversionTarget::Percentage => LemmaType::Percentage,
+    }
+}
+
+/// Turn a document name (`billing/invoice-lines`) into a type name (`BillingInvoiceLines`)
+fn type_name(doc_name: &str) -> String {
+    doc_name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Quote rule names that aren't identifiers, like checklist rules (`onboarding.complete`)
+fn property_name(name: &str) -> String {
+    if name.contains('.') {
+        format!("\"{}\"", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn lower_first(ident: &str) -> String {
+    let mut chars = ident.chars();
+    match chars.next() {
+        Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,268 @@
+//! Workspace checking for editors, CI and pre-commit hooks
+//!
+//! Parses and validates .lemma files without evaluating them, and reports every
+//! diagnostic in a format editors and CI systems can annotate.
+
+use crate::error_formatter;
+use lemma::evaluator::Evaluator;
+use lemma::{EvaluationOptions, FunctionRegistry, LemmaDoc, LemmaError, ResourceLimits, Validator};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use walkdir::WalkDir;
+
+/// How diagnostics are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// Annotated source snippets
+    Human,
+    /// One line per diagnostic: file:line:col: error: message
+    Short,
+    /// GitHub Actions workflow commands (::error file=...,line=...::message)
+    Github,
+}
+
+/// Result of checking a set of files
+#[derive(Debug, Default)]
+pub struct CheckReport {
+    pub files: Vec<PathBuf>,
+    pub diagnostics: Vec<LemmaError>,
+    /// Findings that don't fail the check, e.g. unreachable unless clauses
+    pub warnings: Vec<String>,
+}
+
+/// Collect .lemma files under `paths`, keeping those matching any of `globs`
+///
+/// Directories are walked recursively. Without globs every .lemma file is kept.
+pub fn collect_files(paths: &[PathBuf], globs: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+            continue;
+        }
+        for entry in WalkDir::new(path) {
+            let entry = entry?;
+            if entry.path().extension().and_then(|s| s.to_str()) == Some("lemma") {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+
+    files.retain(|file| {
+        let path = file.to_string_lossy().replace('\\', "/");
+        globs.is_empty() || globs.iter().any(|glob| glob_matches(glob, &path))
+    });
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Parse and validate files, optionally evaluating every document
+///
+/// Each file is parsed on its own so one syntax error doesn't hide the others.
+/// Validation is repeated without the offending document until the remaining
+/// documents are valid, giving one diagnostic per broken document. With
+/// `strict_units`, arithmetic across kinds of units counts as invalid too.
+pub fn check_files(
+    files: &[PathBuf],
+    evaluate: bool,
+    strict_units: bool,
+) -> anyhow::Result<CheckReport> {
+    let limits = ResourceLimits::default();
+    let mut report = CheckReport {
+        files: files.to_vec(),
+        diagnostics: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut sources: HashMap<String, String> = HashMap::new();
+    let mut docs: Vec<LemmaDoc> = Vec::new();
+
+    for file in files {
+        let source_id = file.to_string_lossy().to_string();
+        let code = std::fs::read_to_string(file)?;
+        match lemma::parse(&code, Some(source_id.clone()), &limits) {
+            Ok(parsed) => docs.extend(parsed),
+            Err(e) => report.diagnostics.push(e),
+        }
+        sources.insert(source_id, code);
+    }
+
+    let validator = Validator::new();
+    let validate = |docs: &[LemmaDoc]| {
+        let validated = validator.validate_all(docs.to_vec())?;
+        if strict_units {
+            lemma::strict_units::check_unit_arithmetic(&validated.documents)?;
+        }
+        Ok::<_, LemmaError>(())
+    };
+    while let Err(error) = validate(&docs) {
+        let failed_doc = error_details(&error).map(|details| details.doc_name.clone());
+        report.diagnostics.push(error);
+
+        match failed_doc {
+            Some(name) if docs.iter().any(|d| d.name == name) => {
+                docs.retain(|d| d.name != name);
+            }
+            // No document to blame, further validation would repeat the error
+            _ => {
+                docs.clear();
+                break;
+            }
+        }
+    }
+
+    for doc in &docs {
+        report
+            .warnings
+            .extend(validator.warnings(doc, |name| docs.iter().find(|d| d.name == name)));
+    }
+
+    if evaluate {
+        let documents: HashMap<String, LemmaDoc> =
+            docs.into_iter().map(|d| (d.name.clone(), d)).collect();
+        let mut names: Vec<&String> = documents.keys().collect();
+        names.sort();
+        for name in names {
+            let result = Evaluator::new().evaluate_document(
+                name,
+                &documents,
+                &sources,
+                Vec::new(),
+                None,
+                &limits,
+                &FunctionRegistry::new(),
+                &EvaluationOptions::default(),
+            );
+            if let Err(e) = result {
+                report.diagnostics.push(e);
+            }
+        }
+    }
+
+    report.diagnostics = report
+        .diagnostics
+        .into_iter()
+        .map(|error| attach_source(error, &sources))
+        .collect();
+    Ok(report)
+}
+
+/// Render one diagnostic
+pub fn format_diagnostic(error: &LemmaError, format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Human => error_formatter::format_error(error),
+        ErrorFormat::Short => match error_details(error) {
+            Some(details) => format!(
+                "{}:{}:{}: error: {}",
+                details.source_id,
+                details.span.line,
+                details.span.col,
+                one_line(&details.message)
+            ),
+            None => format!("error: {}", one_line(&error.to_string())),
+        },
+        ErrorFormat::Github => match error_details(error) {
+            Some(details) => format!(
+                "::error file={},line={},col={}::{}",
+                details.source_id,
+                details.span.line,
+                details.span.col,
+                github_escape(&details.message)
+            ),
+            None => format!("::error::{}", github_escape(&error.to_string())),
+        },
+    }
+}
+
+/// Render one warning
+pub fn format_warning(warning: &str, format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Human | ErrorFormat::Short => format!("warning: {}", warning),
+        ErrorFormat::Github => format!("::warning::{}", github_escape(warning)),
+    }
+}
+
+/// Flatten nested errors so each diagnostic is printed separately
+pub fn flatten(errors: Vec<LemmaError>) -> Vec<LemmaError> {
+    errors
+        .into_iter()
+        .flat_map(|error| match error {
+            LemmaError::MultipleErrors(inner) => flatten(inner),
+            other => vec![other],
+        })
+        .collect()
+}
+
+fn error_details(error: &LemmaError) -> Option<&lemma::error::ErrorDetails> {
+    match error {
+        LemmaError::Parse(details)
+        | LemmaError::Semantic(details)
+        | LemmaError::Runtime(details) => Some(details),
+        _ => None,
+    }
+}
+
+/// Semantic errors don't carry source text, fill it in for snippet rendering
+fn attach_source(error: LemmaError, sources: &HashMap<String, String>) -> LemmaError {
+    let fill = |mut details: Box<lemma::error::ErrorDetails>| {
+        if details.source_text.is_empty() {
+            if let Some(code) = sources.get(&details.source_id) {
+                details.source_text = Arc::from(code.as_str());
+            }
+        }
+        details
+    };
+    match error {
+        LemmaError::Parse(details) => LemmaError::Parse(fill(details)),
+        LemmaError::Semantic(details) => LemmaError::Semantic(fill(details)),
+        LemmaError::Runtime(details) => LemmaError::Runtime(fill(details)),
+        other => other,
+    }
+}
+
+fn one_line(message: &str) -> String {
+    message.lines().map(str::trim).collect::<Vec<_>>().join(" ")
+}
+
+/// Escape data for GitHub workflow commands
+fn github_escape(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Match a path against a glob with `*`, `?` and `**`
+///
+/// Patterns without a `/` match the file name only (`*.lemma`, `pricing_*`).
+pub fn glob_matches(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('/') {
+        let name = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return matches(pattern.as_bytes(), name.as_bytes());
+    }
+    let path = path.strip_prefix("./").unwrap_or(path);
+    let pattern = pattern.strip_prefix("./").unwrap_or(pattern);
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+fn matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some(b'*') if pattern.get(1) == Some(&b'*') => {
+            // `**/` also matches zero directories
+            let rest = &pattern[2..];
+            let rest_without_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+            (0..=text.len())
+                .any(|i| matches(rest, &text[i..]) || matches(rest_without_slash, &text[i..]))
+        }
+        Some(b'*') => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| matches(&pattern[1..], &text[i..])),
+        Some(b'?') => !text.is_empty() && text[0] != b'/' && matches(&pattern[1..], &text[1..]),
+        Some(c) => text.first() == Some(c) && matches(&pattern[1..], &text[1..]),
+    }
+}
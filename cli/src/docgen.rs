@@ -0,0 +1,554 @@
+//! Workspace documentation
+//!
+//! Renders every document to a page with its commentary, facts, rules and a
+//! dependency diagram, linking each reference to the fact or rule it names, so
+//! the rule base can be browsed without reading .lemma files. Copies of
+//! templates are documented by their template.
+
+use lemma::{
+    analysis::extract_references, ExpressionKind, FactType, FactValue, LemmaDoc, LemmaFact,
+    LemmaRule,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Format of the generated pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DocFormat {
+    /// Standalone HTML pages
+    Html,
+    /// Markdown, with Mermaid diagrams
+    Markdown,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Html => "html",
+            DocFormat::Markdown => "md",
+        }
+    }
+}
+
+/// A generated page, with its path relative to the output directory
+pub struct Page {
+    pub path: String,
+    pub content: String,
+}
+
+/// Render an index and a page per document
+pub fn generate(documents: &HashMap<String, LemmaDoc>, format: DocFormat) -> Vec<Page> {
+    let mut docs: Vec<&LemmaDoc> = documents
+        .values()
+        .filter(|doc| doc.template.is_none())
+        .collect();
+    docs.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let links = Links { documents, format };
+    let mut pages = vec![Page {
+        path: format!("index.{}", format.extension()),
+        content: index_page(&docs, &links),
+    }];
+    pages.extend(docs.iter().map(|doc| Page {
+        path: links.page(&doc.name),
+        content: document_page(doc, &docs, &links),
+    }));
+    pages
+}
+
+fn index_page(docs: &[&LemmaDoc], links: &Links) -> String {
+    let mut page = Markup::new(links.format);
+    page.heading(1, "Lemma documents", None);
+    let rows = docs
+        .iter()
+        .map(|doc| {
+            vec![
+                page.link(&doc_title(doc), &links.page(&doc.name)),
+                doc.facts.len().to_string(),
+                doc.rules.len().to_string(),
+                page.text(summary(doc)),
+            ]
+        })
+        .collect();
+    page.table(&["Document", "Facts", "Rules", "Summary"], rows);
+    page.finish("Lemma documents")
+}
+
+fn document_page(doc: &LemmaDoc, docs: &[&LemmaDoc], links: &Links) -> String {
+    let mut page = Markup::new(links.format);
+    page.paragraph(&page.link(
+        "All documents",
+        &format!("index.{}", links.format.extension()),
+    ));
+    page.heading(1, &doc_title(doc), None);
+
+    let mut details = Vec::new();
+    if let Some(source) = &doc.source {
+        details.push(format!("Source: {}", page.code(source)));
+    }
+    if let Some(currency) = &doc.currency {
+        details.push(format!("Currency: {}", page.code(&currency.to_string())));
+    }
+    if !details.is_empty() {
+        page.paragraph(&details.join(" · "));
+    }
+    if let Some(commentary) = &doc.commentary {
+        for paragraph in commentary.split("\n\n") {
+            page.paragraph(&page.text(paragraph.trim()));
+        }
+    }
+
+    let referenced_by: Vec<String> = docs
+        .iter()
+        .filter(|other| {
+            other.facts.iter().any(|fact| {
+                referenced_document(&fact.value)
+                    .is_some_and(|name| links.documented_name(name) == doc.name)
+            })
+        })
+        .map(|other| page.link(&other.name, &links.page(&other.name)))
+        .collect();
+    if !referenced_by.is_empty() {
+        page.paragraph(&format!("Referenced by {}", referenced_by.join(", ")));
+    }
+
+    if doc.is_template() {
+        let parameters: Vec<String> = doc
+            .parameters
+            .iter()
+            .map(|parameter| page.anchored(parameter, &fact_anchor(parameter)))
+            .collect();
+        page.paragraph(&format!(
+            "A template: every document instantiating it gives {}",
+            parameters.join(", ")
+        ));
+    }
+
+    if !doc.facts.is_empty() {
+        page.heading(2, "Facts", None);
+        let rows = doc
+            .facts
+            .iter()
+            .map(|fact| fact_row(&page, doc, fact, links))
+            .collect();
+        page.table(&["Fact", "Type", "Default"], rows);
+    }
+
+    if !doc.rules.is_empty() {
+        page.heading(2, "Dependencies", None);
+        page.diagram(&dependency_diagram(doc));
+
+        page.heading(2, "Rules", None);
+        for rule in &doc.rules {
+            page.heading(3, &rule.name, Some(&rule_anchor(&rule.name)));
+            if let Some(description) = &rule.description {
+                page.paragraph(&page.text(description));
+            }
+            page.code_block(&pretty_rule(rule));
+
+            let uses: Vec<String> = rule_references(rule)
+                .into_iter()
+                .map(|reference| links.reference(&page, doc, &reference))
+                .collect();
+            if !uses.is_empty() {
+                page.paragraph(&format!("Uses {}", uses.join(", ")));
+            }
+            let used_by: Vec<String> = doc
+                .rules
+                .iter()
+                .filter(|other| {
+                    rule_references(other).contains(&Reference::Rule(vec![rule.name.clone()]))
+                })
+                .map(|other| page.link(&other.name, &format!("#{}", rule_anchor(&other.name))))
+                .collect();
+            if !used_by.is_empty() {
+                page.paragraph(&format!("Used by {}", used_by.join(", ")));
+            }
+        }
+    }
+
+    page.finish(&doc.name)
+}
+
+fn fact_row(page: &Markup, doc: &LemmaDoc, fact: &LemmaFact, links: &Links) -> Vec<String> {
+    let name = match &fact.fact_type {
+        FactType::Local(name) => page.anchored(name, &fact_anchor(name)),
+        FactType::Foreign(foreign) => {
+            links.reference(page, doc, &Reference::Fact(foreign.reference.clone()))
+        }
+    };
+    let (kind, default) = match &fact.value {
+        FactValue::Literal(value) => (value.to_type().to_string(), page.code(&value.to_string())),
+        FactValue::TypeAnnotation(annotation) => (annotation.to_string(), "required".to_string()),
+        FactValue::DocumentReference(target) => (
+            format!("document {}", links.document(page, target)),
+            String::new(),
+        ),
+        FactValue::DocumentCollection(target) => (
+            format!("many {}", links.document(page, target)),
+            String::new(),
+        ),
+    };
+    vec![name, kind, default]
+}
+
+/// The rule as written, with each unless clause on its own line
+pub(crate) fn pretty_rule(rule: &LemmaRule) -> String {
+    let veto_default =
+        matches!(rule.expression.kind, ExpressionKind::Veto(_)) && !rule.unless_clauses.is_empty();
+    let mut code = format!("rule {} =", rule.name);
+    if !veto_default {
+        let _ = write!(code, " {}", rule.expression);
+    }
+    for clause in &rule.unless_clauses {
+        let _ = write!(
+            code,
+            "\n  unless {} then {}",
+            clause.condition, clause.result
+        );
+    }
+    if veto_default {
+        let _ = write!(code, "\n  else {}", rule.expression);
+    }
+    code
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Reference {
+    Fact(Vec<String>),
+    Rule(Vec<String>),
+}
+
+impl Reference {
+    fn label(&self) -> String {
+        match self {
+            Reference::Fact(path) => path.join("."),
+            Reference::Rule(path) => format!("{}?", path.join(".")),
+        }
+    }
+}
+
+fn rule_references(rule: &LemmaRule) -> BTreeSet<Reference> {
+    let mut references = BTreeSet::new();
+    let expressions = std::iter::once(&rule.expression).chain(
+        rule.unless_clauses
+            .iter()
+            .flat_map(|clause| [&clause.condition, &clause.result]),
+    );
+    for expression in expressions {
+        let found = extract_references(expression);
+        references.extend(
+            found
+                .facts
+                .into_iter()
+                .map(|fact| Reference::Fact(fact.reference)),
+        );
+        references.extend(found.rules.into_iter().map(Reference::Rule));
+    }
+    references
+}
+
+/// A Mermaid flowchart from the facts and rules each rule uses to the rule
+pub(crate) fn dependency_diagram(doc: &LemmaDoc) -> String {
+    let mut ids: HashMap<Reference, String> = HashMap::new();
+    let mut nodes = String::new();
+    let mut edges = String::new();
+    let mut node = |reference: Reference, nodes: &mut String| -> String {
+        if let Some(id) = ids.get(&reference) {
+            return id.clone();
+        }
+        let id = format!("n{}", ids.len());
+        let label = reference.label().replace('"', "#quot;");
+        let _ = match &reference {
+            Reference::Fact(_) => writeln!(nodes, "  {}([\"{}\"])", id, label),
+            Reference::Rule(_) => writeln!(nodes, "  {}[\"{}\"]", id, label),
+        };
+        ids.insert(reference, id.clone());
+        id
+    };
+
+    for rule in &doc.rules {
+        let target = node(Reference::Rule(vec![rule.name.clone()]), &mut nodes);
+        for reference in rule_references(rule) {
+            let source = node(reference, &mut nodes);
+            let _ = writeln!(edges, "  {} --> {}", source, target);
+        }
+    }
+    format!("graph LR\n{}{}", nodes, edges)
+}
+
+fn doc_title(doc: &LemmaDoc) -> String {
+    if doc.is_template() {
+        format!("{}({})", doc.name, doc.parameters.join(", "))
+    } else {
+        doc.name.clone()
+    }
+}
+
+/// The first line of the commentary
+fn summary(doc: &LemmaDoc) -> &str {
+    doc.commentary
+        .as_deref()
+        .and_then(|commentary| {
+            commentary
+                .lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty())
+        })
+        .unwrap_or("")
+}
+
+fn referenced_document(value: &FactValue) -> Option<&str> {
+    match value {
+        FactValue::DocumentReference(name) | FactValue::DocumentCollection(name) => Some(name),
+        _ => None,
+    }
+}
+
+fn fact_anchor(name: &str) -> String {
+    format!("fact-{}", name)
+}
+
+fn rule_anchor(name: &str) -> String {
+    format!("rule-{}", name.replace('.', "-"))
+}
+
+/// Where documents, facts and rules are documented
+struct Links<'a> {
+    documents: &'a HashMap<String, LemmaDoc>,
+    format: DocFormat,
+}
+
+impl Links<'_> {
+    /// The page of a document; documents named `a/b` get `a.b`
+    fn page(&self, doc_name: &str) -> String {
+        format!(
+            "{}.{}",
+            self.documented_name(doc_name).replace('/', "."),
+            self.format.extension()
+        )
+    }
+
+    /// The document a page describes, the template for its copies
+    fn documented_name<'b>(&'b self, doc_name: &'b str) -> &'b str {
+        self.documents
+            .get(doc_name)
+            .and_then(|doc| doc.template.as_deref())
+            .unwrap_or(doc_name)
+    }
+
+    fn document(&self, page: &Markup, doc_name: &str) -> String {
+        page.link(doc_name, &self.page(doc_name))
+    }
+
+    /// A link to the fact or rule `reference` of `doc` names
+    ///
+    /// Every segment but the last is a fact referencing another document.
+    fn reference(&self, page: &Markup, doc: &LemmaDoc, reference: &Reference) -> String {
+        let (path, anchor): (&[String], fn(&str) -> String) = match reference {
+            Reference::Fact(path) => (path, fact_anchor),
+            Reference::Rule(path) => (path, rule_anchor),
+        };
+        let Some((last, documents)) = path.split_last() else {
+            return page.text(&reference.label());
+        };
+
+        let mut current = doc;
+        for segment in documents {
+            let name = lemma::parse_collection_instance(segment)
+                .map(|(collection, _)| collection)
+                .unwrap_or(segment);
+            let next = current.facts.iter().find_map(|fact| match &fact.fact_type {
+                FactType::Local(local) if local == name => referenced_document(&fact.value),
+                _ => None,
+            });
+            match next.and_then(|next| self.documents.get(next)) {
+                Some(next) => current = next,
+                None => return page.text(&reference.label()),
+            }
+        }
+
+        let href = if current.name == doc.name {
+            format!("#{}", anchor(last))
+        } else {
+            format!("{}#{}", self.page(&current.name), anchor(last))
+        };
+        page.link(&reference.label(), &href)
+    }
+}
+
+/// Page content in HTML or Markdown
+///
+/// Methods returning a `String` produce inline content to pass to the
+/// methods that add blocks.
+struct Markup {
+    format: DocFormat,
+    body: String,
+    has_diagram: bool,
+}
+
+impl Markup {
+    fn new(format: DocFormat) -> Self {
+        Self {
+            format,
+            body: String::new(),
+            has_diagram: false,
+        }
+    }
+
+    fn text(&self, text: &str) -> String {
+        match self.format {
+            DocFormat::Html => escape_html(text),
+            DocFormat::Markdown => escape_markdown(text),
+        }
+    }
+
+    fn code(&self, code: &str) -> String {
+        match self.format {
+            DocFormat::Html => format!("<code>{}</code>", escape_html(code)),
+            DocFormat::Markdown => format!("`{}`", code.replace('`', "'").replace('|', "\\|")),
+        }
+    }
+
+    fn link(&self, text: &str, href: &str) -> String {
+        match self.format {
+            DocFormat::Html => format!(
+                "<a href=\"{}\">{}</a>",
+                escape_html(href),
+                escape_html(text)
+            ),
+            DocFormat::Markdown => {
+                format!("[{}]({})", escape_markdown(text), href.replace(' ', "%20"))
+            }
+        }
+    }
+
+    /// Text that links to `#anchor` can point at
+    fn anchored(&self, text: &str, anchor: &str) -> String {
+        format!("<a id=\"{}\"></a>{}", escape_html(anchor), self.text(text))
+    }
+
+    fn heading(&mut self, level: usize, text: &str, anchor: Option<&str>) {
+        let text = self.text(text);
+        let _ = match (self.format, anchor) {
+            (DocFormat::Html, Some(anchor)) => writeln!(
+                self.body,
+                "<h{level} id=\"{}\">{}</h{level}>",
+                escape_html(anchor),
+                text
+            ),
+            (DocFormat::Html, None) => writeln!(self.body, "<h{level}>{}</h{level}>", text),
+            (DocFormat::Markdown, Some(anchor)) => writeln!(
+                self.body,
+                "<a id=\"{}\"></a>\n\n{} {}\n",
+                escape_html(anchor),
+                "#".repeat(level),
+                text
+            ),
+            (DocFormat::Markdown, None) => {
+                writeln!(self.body, "{} {}\n", "#".repeat(level), text)
+            }
+        };
+    }
+
+    fn paragraph(&mut self, inline: &str) {
+        let _ = match self.format {
+            DocFormat::Html => writeln!(self.body, "<p>{}</p>", inline),
+            DocFormat::Markdown => writeln!(self.body, "{}\n", inline),
+        };
+    }
+
+    fn code_block(&mut self, code: &str) {
+        let _ = match self.format {
+            DocFormat::Html => writeln!(self.body, "<pre><code>{}</code></pre>", escape_html(code)),
+            DocFormat::Markdown => writeln!(self.body, "```lemma\n{}\n```\n", code),
+        };
+    }
+
+    fn diagram(&mut self, mermaid: &str) {
+        self.has_diagram = true;
+        let _ = match self.format {
+            DocFormat::Html => writeln!(
+                self.body,
+                "<pre class=\"mermaid\">\n{}</pre>",
+                escape_html(mermaid)
+            ),
+            DocFormat::Markdown => writeln!(self.body, "```mermaid\n{}```\n", mermaid),
+        };
+    }
+
+    fn table(&mut self, headers: &[&str], rows: Vec<Vec<String>>) {
+        match self.format {
+            DocFormat::Html => {
+                self.body.push_str("<table>\n<tr>");
+                for header in headers {
+                    let _ = write!(self.body, "<th>{}</th>", escape_html(header));
+                }
+                self.body.push_str("</tr>\n");
+                for row in rows {
+                    self.body.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(self.body, "<td>{}</td>", cell);
+                    }
+                    self.body.push_str("</tr>\n");
+                }
+                self.body.push_str("</table>\n");
+            }
+            DocFormat::Markdown => {
+                let _ = writeln!(self.body, "| {} |", headers.join(" | "));
+                let _ = writeln!(self.body, "|{}", "---|".repeat(headers.len()));
+                for row in rows {
+                    let _ = writeln!(self.body, "| {} |", row.join(" | "));
+                }
+                self.body.push('\n');
+            }
+        }
+    }
+
+    /// The complete page
+    fn finish(self, title: &str) -> String {
+        match self.format {
+            DocFormat::Markdown => self.body,
+            DocFormat::Html => {
+                let script = if self.has_diagram { MERMAID_SCRIPT } else { "" };
+                format!(
+                    "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n{}{}</body>\n</html>\n",
+                    escape_html(title),
+                    STYLE,
+                    self.body,
+                    script
+                )
+            }
+        }
+    }
+}
+
+/// Draws `<pre class="mermaid">` diagrams; without network access they show as text
+pub(crate) const MERMAID_SCRIPT: &str = "<script type=\"module\">\nimport mermaid from \"https://cdn.jsdelivr.net/npm/mermaid@10/dist/mermaid.esm.min.mjs\";\nmermaid.initialize({ startOnLoad: true });\n</script>\n";
+
+pub(crate) const STYLE: &str = "body { font-family: sans-serif; max-width: 60rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.5; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ddd; padding: 0.25rem 0.75rem; text-align: left; vertical-align: top; }
+pre { background: #f6f8fa; padding: 0.75rem; overflow-x: auto; }
+";
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
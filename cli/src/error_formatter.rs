@@ -72,6 +72,25 @@ pub fn format_error(error: &LemmaError) -> String {
                 limit_name, limit_value, actual_value, suggestion
             )
         }
+        LemmaError::FactParse(errors) => {
+            let mut result = String::new();
+            for error in errors {
+                result.push_str(&format!(
+                    "Invalid fact '{}': {}\n  {}\n  {}^\n",
+                    error.fact.as_deref().unwrap_or(&error.input),
+                    error.message,
+                    error.input,
+                    " ".repeat(error.input[..error.position].chars().count())
+                ));
+                if let Some(declared) = &error.declared_type {
+                    result.push_str(&format!("  = declared type: {}\n", declared));
+                }
+            }
+            result.trim_end().to_string()
+        }
+        LemmaError::VersionMismatch { .. }
+        | LemmaError::UnknownResult(_)
+        | LemmaError::Cancelled { .. } => error.to_string(),
         LemmaError::MultipleErrors(errors) => {
             let mut result = String::from("Multiple errors occurred:\n\n");
             for error in errors {
@@ -1,19 +1,190 @@
 use comfy_table::{presets::UTF8_FULL, Attribute, Cell, CellAlignment, ContentArrangement, Table};
 use crossterm::style::Stylize;
-use lemma::{Domain, FactReference, LemmaDoc, LemmaFact, LemmaRule, OperationRecord, Response};
+use lemma::diff::{Change, ItemKind};
+use lemma::{
+    CompatibilityReport, Domain, FactReference, LemmaDoc, LemmaFact, LemmaRule, LiteralValue,
+    MoneyUnit, NumericUnit, OperationRecord, Response, SensitivityMethod, SensitivityReport, Sweep,
+    SweepPoint, WorkspaceDiff,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
 use std::collections::HashMap;
 
+/// Width of the longest bar in a sweep chart
+const SWEEP_BAR_WIDTH: usize = 40;
+
 pub struct Formatter {
     use_colors: bool,
+    /// Number conventions for displayed values; None prints values as written in Lemma
+    locale: Option<NumberLocale>,
 }
 
 impl Default for Formatter {
     fn default() -> Self {
-        Self { use_colors: true }
+        Self {
+            use_colors: true,
+            locale: None,
+        }
+    }
+}
+
+/// How a locale writes numbers and money
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    decimal_separator: char,
+    group_separator: &'static str,
+    /// `€1.00` rather than `1.00 €`
+    symbol_first: bool,
+    /// `€ 1,00` rather than `€1,00`
+    symbol_space: bool,
+}
+
+impl NumberLocale {
+    /// Conventions for a locale tag like `nl`, `de-CH` or `en-US`
+    ///
+    /// Unknown languages use English conventions.
+    pub fn for_tag(tag: &str) -> Self {
+        let tag = tag.trim().to_lowercase().replace('_', "-");
+        let language = tag.split('-').next().unwrap_or_default();
+
+        match (language, tag.as_str()) {
+            (_, "de-ch") | (_, "it-ch") => Self::new('.', "'", true, true),
+            ("nl", _) => Self::new(',', ".", true, true),
+            ("de", _) | ("es", _) | ("it", _) | ("pt", _) | ("da", _) | ("id", _) => {
+                Self::new(',', ".", false, true)
+            }
+            ("fr", _) | ("fi", _) | ("sv", _) | ("nb", _) | ("no", _) | ("pl", _) | ("cs", _) => {
+                Self::new(',', "\u{202f}", false, true)
+            }
+            _ => Self::new('.', ",", true, false),
+        }
+    }
+
+    fn new(
+        decimal_separator: char,
+        group_separator: &'static str,
+        symbol_first: bool,
+        symbol_space: bool,
+    ) -> Self {
+        Self {
+            decimal_separator,
+            group_separator,
+            symbol_first,
+            symbol_space,
+        }
+    }
+
+    /// Format a number with digit grouping, keeping its decimals
+    pub fn format_number(&self, value: Decimal) -> String {
+        let text = value.to_string();
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(rest) => ("-", rest),
+            None => ("", text.as_str()),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+
+        let mut grouped = String::new();
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                grouped.push_str(self.group_separator);
+            }
+            grouped.push(digit);
+        }
+
+        match fraction {
+            Some(fraction) => format!("{}{}{}{}", sign, grouped, self.decimal_separator, fraction),
+            None => format!("{}{}", sign, grouped),
+        }
+    }
+
+    /// Format money with the currency symbol and the currency's minor units
+    pub fn format_money(&self, value: Decimal, currency: &MoneyUnit) -> String {
+        let minor_units = currency_minor_units(currency);
+        let mut rounded =
+            value.round_dp_with_strategy(minor_units, RoundingStrategy::MidpointAwayFromZero);
+        rounded.rescale(minor_units);
+
+        let amount = self.format_number(rounded.abs());
+        let sign = if rounded.is_sign_negative() && !rounded.is_zero() {
+            "-"
+        } else {
+            ""
+        };
+        let symbol = currency_symbol(currency);
+        // Letter codes like CHF are always set apart from the amount
+        let space = if self.symbol_space || symbol.chars().all(char::is_alphabetic) {
+            " "
+        } else {
+            ""
+        };
+
+        if self.symbol_first {
+            format!("{}{}{}{}", sign, symbol, space, amount)
+        } else {
+            format!("{}{}{}{}", sign, amount, space, symbol)
+        }
+    }
+
+    /// Format a value, falling back to its Lemma notation for non-numeric values
+    pub fn format_value(&self, value: &LiteralValue) -> String {
+        match value {
+            LiteralValue::Number(n) => self.format_number(*n),
+            LiteralValue::Percentage(p) => format!("{}%", self.format_number(*p)),
+            LiteralValue::Unit(NumericUnit::Money(v, currency)) => self.format_money(*v, currency),
+            LiteralValue::Unit(unit) => {
+                let notation = unit.to_string();
+                let unit_name = notation.split_once(' ').map(|(_, name)| name).unwrap_or("");
+                format!("{} {}", self.format_number(unit.value()), unit_name)
+            }
+            other => other.to_string(),
+        }
+    }
+}
+
+fn currency_symbol(currency: &MoneyUnit) -> &'static str {
+    match currency {
+        MoneyUnit::Eur => "€",
+        MoneyUnit::Usd => "$",
+        MoneyUnit::Gbp => "£",
+        MoneyUnit::Jpy => "¥",
+        MoneyUnit::Cny => "CN¥",
+        MoneyUnit::Chf => "CHF",
+        MoneyUnit::Cad => "CA$",
+        MoneyUnit::Aud => "A$",
+        MoneyUnit::Inr => "₹",
+    }
+}
+
+/// Number of decimals (ISO 4217 minor units) amounts in a currency are shown with
+fn currency_minor_units(currency: &MoneyUnit) -> u32 {
+    match currency {
+        MoneyUnit::Jpy => 0,
+        _ => 2,
     }
 }
 
 impl Formatter {
+    /// Formatter writing numbers and money the way `locale` does (e.g. `nl`, `en-US`)
+    pub fn with_locale(locale: Option<&str>) -> Self {
+        Self {
+            locale: locale.map(NumberLocale::for_tag),
+            ..Self::default()
+        }
+    }
+
+    pub fn format_value(&self, value: &LiteralValue) -> String {
+        if value.is_redacted() {
+            return lemma::REDACTED.to_string();
+        }
+        match &self.locale {
+            Some(locale) => locale.format_value(value),
+            None => value.to_string(),
+        }
+    }
+
     pub fn format_response(&self, response: &Response, raw: bool) -> String {
         if raw {
             self.format_raw(response)
@@ -52,7 +223,7 @@ impl Formatter {
             let rule_cell = Cell::new(&result.rule_name);
 
             let verdict_cell = if let Some(ref value) = result.result {
-                let mut content = format!("{}\n", value);
+                let mut content = format!("{}\n", self.format_value(value));
 
                 if !result.operations.is_empty() {
                     content.push('\n');
@@ -68,6 +239,11 @@ impl Formatter {
                     .set_alignment(CellAlignment::Left)
             } else if let Some(ref veto_msg) = result.veto_message {
                 Cell::new(format!("✗ {}", veto_msg)).set_alignment(CellAlignment::Left)
+            } else if let Some(ref reason) = result.unknown {
+                Cell::new(format!("? unknown ({})", reason)).set_alignment(CellAlignment::Left)
+            } else if let Some(ref limit) = result.limit_exceeded {
+                Cell::new(format!("! not evaluated ({} exceeded)", limit))
+                    .set_alignment(CellAlignment::Left)
             } else {
                 Cell::new("[no result]").set_alignment(CellAlignment::Left)
             };
@@ -78,13 +254,52 @@ impl Formatter {
         format!("{}\n", table)
     }
 
+    /// Table of scenario expectations, with expected and actual value for failures
+    pub fn format_scenario_report(&self, outcomes: &[crate::scenario::Outcome]) -> String {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic);
+
+        table.set_header(vec![
+            Cell::new("Scenario").add_attribute(Attribute::Bold),
+            Cell::new("Rule").add_attribute(Attribute::Bold),
+            Cell::new("Result").add_attribute(Attribute::Bold),
+        ]);
+
+        for outcome in outcomes {
+            let result = if outcome.passed {
+                format!("✓ {}", outcome.actual)
+            } else if outcome.expected.is_empty() {
+                format!("✗ {}", outcome.actual)
+            } else {
+                format!(
+                    "✗ expected {}\n  actual   {}",
+                    outcome.expected, outcome.actual
+                )
+            };
+            table.add_row(vec![
+                Cell::new(&outcome.scenario),
+                Cell::new(&outcome.rule),
+                Cell::new(result).set_alignment(CellAlignment::Left),
+            ]);
+        }
+
+        format!("{}\n", table)
+    }
+
     fn format_operation_step(&self, index: usize, step: &OperationRecord) -> String {
+        format!("  {:>2}. {}\n", index, self.describe_operation(step))
+    }
+
+    /// One step of a rule's trace, e.g. `fact price = 10` or `multiply(10, 3) → 30`
+    pub fn describe_operation(&self, step: &OperationRecord) -> String {
         match step {
             OperationRecord::FactUsed { name, value } => {
-                format!("  {:>2}. fact {} = {}\n", index, name, value)
+                format!("fact {} = {}", name, self.format_value(value))
             }
             OperationRecord::RuleUsed { name, value } => {
-                format!("  {:>2}. rule {} = {}\n", index, name, value)
+                format!("rule {} = {}", name, self.format_value(value))
             }
             OperationRecord::OperationExecuted {
                 operation,
@@ -94,22 +309,53 @@ impl Formatter {
             } => {
                 let inputs_str = inputs
                     .iter()
-                    .map(|v| v.to_string())
+                    .map(|v| self.format_value(v))
                     .collect::<Vec<_>>()
                     .join(", ");
+                let result = self.format_value(result);
 
                 if let Some(clause_idx) = unless_clause_index {
                     format!(
-                        "  {:>2}. unless #{}: {}({}) → {}\n",
-                        index, clause_idx, operation, inputs_str, result
+                        "unless #{}: {}({}) → {}",
+                        clause_idx, operation, inputs_str, result
                     )
                 } else {
-                    format!(
-                        "  {:>2}. {}({}) → {}\n",
-                        index, operation, inputs_str, result
-                    )
+                    format!("{}({}) → {}", operation, inputs_str, result)
                 }
             }
+            OperationRecord::ShortCircuit {
+                operation,
+                value,
+                skipped_operands,
+            } => {
+                format!(
+                    "{} short-circuited → {} (skipped {} operand{})",
+                    operation,
+                    self.format_value(value),
+                    skipped_operands,
+                    if *skipped_operands == 1 { "" } else { "s" }
+                )
+            }
+            OperationRecord::CandidateSelected {
+                operation,
+                inputs,
+                winner,
+                value,
+                ..
+            } => {
+                let inputs_str = inputs
+                    .iter()
+                    .map(|v| self.format_value(v))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                format!(
+                    "{}({}) → {} from {}",
+                    operation,
+                    inputs_str,
+                    self.format_value(value),
+                    winner
+                )
+            }
             OperationRecord::UnlessClauseEvaluated {
                 index: clause_index,
                 matched,
@@ -118,24 +364,22 @@ impl Formatter {
                 if *matched {
                     if let Some(value) = result_if_matched {
                         format!(
-                            "  {:>2}. unless clause {} matched → {}\n",
-                            index, clause_index, value
+                            "unless clause {} matched → {}",
+                            clause_index,
+                            self.format_value(value)
                         )
                     } else {
-                        format!(
-                            "  {:>2}. unless clause {} matched (veto)\n",
-                            index, clause_index
-                        )
+                        format!("unless clause {} matched (veto)", clause_index)
                     }
                 } else {
-                    format!("  {:>2}. unless clause {} skipped\n", index, clause_index)
+                    format!("unless clause {} skipped", clause_index)
                 }
             }
             OperationRecord::DefaultValue { value } => {
-                format!("  {:>2}. default = {}\n", index, value)
+                format!("default = {}", self.format_value(value))
             }
             OperationRecord::FinalResult { value } => {
-                format!("  {:>2}. result = {}\n", index, value)
+                format!("result = {}", self.format_value(value))
             }
         }
     }
@@ -182,9 +426,39 @@ impl Formatter {
                 .max()
                 .unwrap_or(0);
 
-            for fact in facts {
+            // Numeric values are right-aligned so their digits line up
+            let values: Vec<(String, bool)> = facts
+                .iter()
+                .map(|fact| match &fact.value {
+                    lemma::FactValue::Literal(_) if fact.sensitive => {
+                        (lemma::REDACTED.to_string(), false)
+                    }
+                    lemma::FactValue::Literal(lit) => (
+                        self.format_value(lit),
+                        matches!(
+                            lit,
+                            LiteralValue::Number(_)
+                                | LiteralValue::Percentage(_)
+                                | LiteralValue::Unit(_)
+                        ),
+                    ),
+                    other => (other.to_string(), false),
+                })
+                .collect();
+            let max_numeric_len = values
+                .iter()
+                .filter(|(_, numeric)| *numeric)
+                .map(|(value, _)| value.chars().count())
+                .max()
+                .unwrap_or(0);
+
+            for (fact, (value, numeric)) in facts.iter().zip(values) {
                 let name = lemma::analysis::fact_display_name(fact);
-                let value_str = fact.value.to_string();
+                let value_str = if numeric {
+                    format!("{:>width$}", value, width = max_numeric_len)
+                } else {
+                    value
+                };
 
                 let display = if self.use_colors {
                     match &fact.value {
@@ -195,20 +469,12 @@ impl Formatter {
                     value_str
                 };
 
+                // Pad before styling, escape codes don't count towards the width
+                let name = format!("{:<width$}", name, width = max_name_len);
                 if self.use_colors {
-                    output.push_str(&format!(
-                        "  {:<width$}  {}\n",
-                        name.bold(),
-                        display,
-                        width = max_name_len
-                    ));
+                    output.push_str(&format!("  {}  {}\n", name.bold(), display));
                 } else {
-                    output.push_str(&format!(
-                        "  {:<width$}  {}\n",
-                        name,
-                        display,
-                        width = max_name_len
-                    ));
+                    output.push_str(&format!("  {}  {}\n", name, display));
                 }
             }
             output.push('\n');
@@ -228,7 +494,7 @@ impl Formatter {
                     if idx < rules.len() {
                         let name = &rules[idx].name;
                         if self.use_colors {
-                            line.push_str(&format!("{:<30}", name.as_str().dark_grey()));
+                            line.push_str(&format!("{:<30}", name).dark_grey().to_string());
                         } else {
                             line.push_str(&format!("{:<30}", name));
                         }
@@ -319,6 +585,270 @@ impl Formatter {
         }
     }
 
+    /// Sweep results as a bar chart, one line per input, then where the result changes
+    ///
+    /// Bars show the magnitude of numeric results; `true` is a full bar and
+    /// `false` an empty one. Raw output is one tab-separated line per input.
+    pub fn format_sweep(&self, sweep: &Sweep, raw: bool) -> String {
+        let mut output = String::default();
+
+        if raw {
+            for point in &sweep.points {
+                output.push_str(&format!(
+                    "{}\t{}\n",
+                    point.input,
+                    self.sweep_outcome(point, true)
+                ));
+            }
+            return output;
+        }
+
+        output.push_str(&self.section_divider());
+        output.push_str(&self.style_header(&format!("  {} by {}", sweep.rule_name, sweep.fact)));
+        output.push_str(&self.section_divider());
+        output.push('\n');
+
+        let magnitudes: Vec<Option<Decimal>> = sweep
+            .points
+            .iter()
+            .map(|point| point.result.as_ref().and_then(chart_magnitude))
+            .collect();
+        let largest = magnitudes
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or_default();
+        let inputs: Vec<String> = sweep
+            .points
+            .iter()
+            .map(|point| self.format_value(&point.input))
+            .collect();
+        let input_width = inputs.iter().map(|i| i.chars().count()).max().unwrap_or(0);
+
+        for ((point, input), magnitude) in sweep.points.iter().zip(&inputs).zip(&magnitudes) {
+            let length = match magnitude {
+                Some(magnitude) if largest > Decimal::ZERO => {
+                    (*magnitude * Decimal::from(SWEEP_BAR_WIDTH) / largest)
+                        .round()
+                        .to_usize()
+                        .unwrap_or(0)
+                }
+                _ => 0,
+            };
+            output.push_str(&format!(
+                "  {:>input_width$} | {:<bar_width$} {}\n",
+                input,
+                "#".repeat(length),
+                self.sweep_outcome(point, false),
+                input_width = input_width,
+                bar_width = SWEEP_BAR_WIDTH
+            ));
+        }
+
+        let changes: Vec<(&SweepPoint, &SweepPoint)> = sweep.changes().collect();
+        output.push('\n');
+        if changes.is_empty() {
+            output.push_str(&format!(
+                "  {} does not change over this range\n",
+                sweep.rule_name
+            ));
+        } else {
+            output.push_str(&self.subsection_header("Changes"));
+            for (before, after) in changes {
+                output.push_str(&format!(
+                    "  {} {} → {}: {} → {}\n",
+                    sweep.fact,
+                    self.format_value(&before.input),
+                    self.format_value(&after.input),
+                    self.sweep_outcome(before, false),
+                    self.sweep_outcome(after, false)
+                ));
+            }
+        }
+
+        output
+    }
+
+    pub fn format_sensitivity(&self, report: &SensitivityReport) -> String {
+        let mut output = String::default();
+
+        output.push_str(&self.section_divider());
+        output.push_str(&self.style_header(&format!("  Sensitivity of {}", report.rule_name)));
+        output.push_str(&self.section_divider());
+        output.push('\n');
+
+        match &report.result {
+            Some(value) => output.push_str(&format!(
+                "  {} = {}\n\n",
+                report.rule_name,
+                self.format_value(value)
+            )),
+            None => output.push_str(&format!("  {} has no value\n\n", report.rule_name)),
+        }
+
+        let fact_width = report
+            .facts
+            .iter()
+            .map(|f| f.fact.chars().count())
+            .max()
+            .unwrap_or(0);
+        for fact in &report.facts {
+            let change = match (&fact.sensitivity, &fact.reason) {
+                (Some(sensitivity), _) => format!("{} per unit", sensitivity),
+                (None, Some(reason)) => format!("[{}]", reason),
+                (None, None) => "[no result]".to_string(),
+            };
+            let method = match fact.method {
+                SensitivityMethod::Symbolic => "exact",
+                SensitivityMethod::FiniteDifference => "estimated",
+            };
+            output.push_str(&format!(
+                "  {:<fact_width$}  at {}: {} ({})\n",
+                fact.fact,
+                self.format_value(&fact.value),
+                change,
+                method,
+                fact_width = fact_width
+            ));
+        }
+
+        output
+    }
+
+    /// Changed documents with their changed items, then the documents they affect
+    ///
+    /// Added items are marked `+`, removed ones `-` and changed ones `~`,
+    /// followed by the old and new code.
+    pub fn format_workspace_diff(&self, diff: &WorkspaceDiff) -> String {
+        let mut output = String::default();
+
+        output.push_str(&self.section_divider());
+        output.push_str(&self.style_header("  Changes"));
+        output.push_str(&self.section_divider());
+        output.push('\n');
+
+        if diff.is_empty() {
+            output.push_str("  No changes\n");
+            return output;
+        }
+
+        for document in &diff.documents {
+            let change = match document.change {
+                Change::Added => "added",
+                Change::Removed => "removed",
+                Change::Changed => "changed",
+            };
+            output.push_str(
+                &self.subsection_header(&format!("doc {} ({})", document.doc_name, change)),
+            );
+            for item in &document.items {
+                let kind = item_kind_name(item.kind);
+                let (marker, lines) = match item.change {
+                    Change::Added => ("+", vec![("+", &item.new)]),
+                    Change::Removed => ("-", vec![("-", &item.old)]),
+                    Change::Changed => ("~", vec![("-", &item.old), ("+", &item.new)]),
+                };
+                output.push_str(&format!(
+                    "    {} {} {}\n",
+                    self.diff_marker(marker),
+                    kind,
+                    item.name
+                ));
+                for (marker, code) in lines {
+                    for line in code.iter().flat_map(|code| code.lines()) {
+                        output.push_str(&format!(
+                            "        {} {}\n",
+                            self.diff_marker(marker),
+                            line
+                        ));
+                    }
+                }
+            }
+            output.push('\n');
+        }
+
+        if !diff.affected.is_empty() {
+            output.push_str(&self.subsection_header("Affected documents"));
+            for affected in &diff.affected {
+                output.push_str(&format!(
+                    "    {} (through {})\n",
+                    affected.doc_name,
+                    affected.through.join(", ")
+                ));
+            }
+        }
+
+        output
+    }
+
+    /// The version bump each changed document needs, and where its declared version falls short
+    pub fn format_compatibility(&self, reports: &[CompatibilityReport]) -> String {
+        let mut output = String::default();
+        let reports: Vec<&CompatibilityReport> =
+            reports.iter().filter(|r| r.required.is_some()).collect();
+        if reports.is_empty() {
+            return output;
+        }
+
+        output.push_str(&self.subsection_header("Compatibility"));
+        for report in reports {
+            let versions = match (report.old_version, report.new_version) {
+                (Some(old), Some(new)) => format!(" ({} -> {})", old, new),
+                (Some(old), None) => format!(" ({} -> no version)", old),
+                (None, Some(new)) => format!(" (no version -> {})", new),
+                (None, None) => String::new(),
+            };
+            output.push_str(&format!(
+                "    {}: {} change{}\n",
+                report.doc_name,
+                report
+                    .required
+                    .map(|bump| bump.to_string())
+                    .unwrap_or_default(),
+                versions
+            ));
+            for change in &report.changes {
+                output.push_str(&format!(
+                    "        {} {} '{}' {}\n",
+                    change.bump,
+                    item_kind_name(change.kind),
+                    change.name,
+                    change.reason
+                ));
+            }
+            if let Some(problem) = report.problem() {
+                let problem = if self.use_colors {
+                    problem.red().to_string()
+                } else {
+                    problem
+                };
+                output.push_str(&format!("    {}\n", problem));
+            }
+        }
+        output
+    }
+
+    fn diff_marker(&self, marker: &str) -> String {
+        if !self.use_colors {
+            return marker.to_string();
+        }
+        match marker {
+            "+" => marker.green().to_string(),
+            "-" => marker.red().to_string(),
+            _ => marker.yellow().to_string(),
+        }
+    }
+
+    fn sweep_outcome(&self, point: &SweepPoint, raw: bool) -> String {
+        match (&point.result, &point.veto_message) {
+            (Some(value), _) if raw => value.to_string(),
+            (Some(value), _) => self.format_value(value),
+            (None, Some(message)) => format!("veto: {}", message),
+            (None, None) => "[no result]".to_string(),
+        }
+    }
+
     pub fn format_inversion_result(&self, solutions: &[HashMap<FactReference, Domain>]) -> String {
         let mut output = String::default();
 
@@ -386,6 +916,117 @@ impl Formatter {
         output
     }
 
+    /// Inversion result in plain language, for `lemma invert --interactive`
+    ///
+    /// `target` describes the outcome in words, e.g. `is at least 100 EUR`.
+    pub fn format_inversion_explained(
+        &self,
+        rule: &str,
+        target: &str,
+        solutions: &[HashMap<FactReference, Domain>],
+    ) -> String {
+        let mut output = String::default();
+        output.push('\n');
+
+        if solutions.is_empty() {
+            output.push_str(&format!("  No facts make {} {}.\n", rule, target));
+            return output;
+        }
+
+        let goal = format!("{} {}", rule, target);
+        let heading = if solutions.len() == 1 {
+            format!("For {}:", goal)
+        } else {
+            format!(
+                "For {}, any of these {} options works:",
+                goal,
+                solutions.len()
+            )
+        };
+        output.push_str(&format!("  {}", self.style_header(&heading)));
+
+        for (i, solution) in solutions.iter().enumerate() {
+            let indent = if solutions.len() > 1 {
+                output.push_str(&format!("\n  Option {}:\n", i + 1));
+                "    "
+            } else {
+                "  "
+            };
+
+            if solution.is_empty() {
+                output.push_str(&format!("{}whatever the facts are\n", indent));
+                continue;
+            }
+
+            let mut facts: Vec<(String, &Domain)> = solution
+                .iter()
+                .map(|(fact, domain)| (fact.to_string(), domain))
+                .collect();
+            facts.sort_by(|a, b| a.0.cmp(&b.0));
+            for (fact, domain) in facts {
+                output.push_str(&format!(
+                    "{}{} {}\n",
+                    indent,
+                    fact,
+                    self.describe_domain(domain)
+                ));
+            }
+        }
+
+        output
+    }
+
+    /// A domain as the end of a sentence about a fact: `must be at least 18`
+    fn describe_domain(&self, domain: &Domain) -> String {
+        match domain {
+            Domain::Unconstrained => "can be anything".to_string(),
+            Domain::Complement(inner) => format!("must not be {}", self.domain_words(inner)),
+            _ => format!("must be {}", self.domain_words(domain)),
+        }
+    }
+
+    fn domain_words(&self, domain: &Domain) -> String {
+        use lemma::Bound;
+
+        let lower = |bound: &Bound| match bound {
+            Bound::Inclusive(v) => format!("at least {}", v),
+            Bound::Exclusive(v) => format!("more than {}", v),
+            Bound::Unbounded => String::new(),
+        };
+        let upper = |bound: &Bound| match bound {
+            Bound::Inclusive(v) => format!("at most {}", v),
+            Bound::Exclusive(v) => format!("less than {}", v),
+            Bound::Unbounded => String::new(),
+        };
+
+        match domain {
+            Domain::Range { min, max } => match (min, max) {
+                (Bound::Inclusive(a), Bound::Inclusive(b)) if a == b => a.to_string(),
+                (Bound::Unbounded, Bound::Unbounded) => "any value".to_string(),
+                (Bound::Inclusive(a), Bound::Inclusive(b)) => {
+                    format!("between {} and {}", a, b)
+                }
+                (min, Bound::Unbounded) => lower(min),
+                (Bound::Unbounded, max) => upper(max),
+                (min, max) => format!("{} and {}", lower(min), upper(max)),
+            },
+            Domain::Enumeration(values) => match values.as_slice() {
+                [] => "impossible to satisfy".to_string(),
+                [value] => value.to_string(),
+                values => {
+                    let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                    format!("one of {}", values.join(", "))
+                }
+            },
+            Domain::Union(domains) => {
+                let parts: Vec<String> = domains.iter().map(|d| self.domain_words(d)).collect();
+                parts.join(", or ")
+            }
+            Domain::Complement(inner) => format!("anything except {}", self.domain_words(inner)),
+            Domain::Unconstrained => "any value".to_string(),
+        }
+    }
+
     fn format_domain(&self, domain: &Domain) -> String {
         use lemma::{Bound, Domain};
 
@@ -431,3 +1072,23 @@ impl Formatter {
         }
     }
 }
+
+/// Bar length of a sweep result before scaling, None for values without a size
+fn chart_magnitude(value: &LiteralValue) -> Option<Decimal> {
+    match value {
+        LiteralValue::Number(n) | LiteralValue::Percentage(n) => Some(n.abs()),
+        LiteralValue::Unit(unit) => Some(unit.value().abs()),
+        LiteralValue::Boolean(b) => Some(if *b { Decimal::ONE } else { Decimal::ZERO }),
+        _ => None,
+    }
+}
+
+fn item_kind_name(kind: ItemKind) -> &'static str {
+    match kind {
+        ItemKind::Directive => "directive",
+        ItemKind::Fact => "fact",
+        ItemKind::Rule => "rule",
+        ItemKind::Checklist => "checklist",
+        ItemKind::Invariant => "invariant",
+    }
+}
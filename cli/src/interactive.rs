@@ -1,6 +1,9 @@
 use anyhow::{Context, Result};
 use inquire::{DateSelect, MultiSelect, Select, Text};
-use lemma::{Engine, LemmaType, TypeAnnotation};
+use lemma::{
+    Engine, ExpressionKind, LemmaFact, LemmaRule, LemmaType, LiteralValue, OperationResult, Target,
+    TargetOp, TypeAnnotation,
+};
 
 pub fn run_interactive(
     engine: &Engine,
@@ -128,69 +131,324 @@ fn prompt_facts(
     println!("\nEnter fact values:");
 
     for fact in required_facts {
-        let fact_name = lemma::analysis::fact_display_name(fact);
+        if let Some(value) = prompt_fact_value(fact)? {
+            fact_values.push(format!(
+                "{}={}",
+                lemma::analysis::fact_display_name(fact),
+                value
+            ));
+        }
+    }
 
-        let (type_ann, default_value) = match &fact.value {
-            lemma::FactValue::TypeAnnotation(type_ann) => (type_ann.clone(), None),
-            lemma::FactValue::Literal(lit) => (
-                TypeAnnotation::LemmaType(lit.to_type()),
-                Some(format!("{}", lit)),
-            ),
-            lemma::FactValue::DocumentReference(_) => continue,
-        };
+    Ok(fact_values)
+}
 
-        let type_str = type_ann.to_string();
+/// Ask for the value of a fact, with a prompt that fits its type
+///
+/// Returns None for facts that hold a document rather than a value.
+fn prompt_fact_value(fact: &LemmaFact) -> Result<Option<String>> {
+    let fact_name = lemma::analysis::fact_display_name(fact);
+
+    let (type_ann, default_value) = match &fact.value {
+        // The document's example, if any, is a reasonable starting point
+        lemma::FactValue::TypeAnnotation(type_ann) => (
+            type_ann.clone(),
+            fact.example.as_ref().map(|example| example.to_string()),
+        ),
+        lemma::FactValue::Literal(lit) => (
+            TypeAnnotation::LemmaType(lit.to_type()),
+            Some(format!("{}", lit)),
+        ),
+        lemma::FactValue::DocumentReference(_) | lemma::FactValue::DocumentCollection(_) => {
+            return Ok(None)
+        }
+    };
 
-        let value = match &type_ann {
-            TypeAnnotation::LemmaType(LemmaType::Date) => {
-                let date = DateSelect::new(&format!("{} [date]", fact_name))
-                    .with_help_message("Use arrow keys to navigate, Enter to select")
-                    .prompt()
-                    .context(format!("Failed to get date for {}", fact_name))?;
+    let type_str = type_ann.to_string();
 
-                format!("{}T00:00:00Z", date.format("%Y-%m-%d"))
-            }
-            TypeAnnotation::LemmaType(LemmaType::Boolean) => {
-                let options = vec!["true", "false"];
-
-                let default_index = if let Some(default) = &default_value {
-                    if default == "true" || default == "yes" || default == "accept" {
-                        0
-                    } else {
-                        1
-                    }
-                } else {
+    let value = match &type_ann {
+        TypeAnnotation::LemmaType(LemmaType::Date) => {
+            let date = DateSelect::new(&format!("{} [date]", fact_name))
+                .with_help_message("Use arrow keys to navigate, Enter to select")
+                .prompt()
+                .context(format!("Failed to get date for {}", fact_name))?;
+
+            format!("{}T00:00:00Z", date.format("%Y-%m-%d"))
+        }
+        TypeAnnotation::LemmaType(LemmaType::Boolean) => {
+            let options = vec!["true", "false"];
+
+            let default_index = if let Some(default) = &default_value {
+                if default == "true" || default == "yes" || default == "accept" {
                     0
-                };
+                } else {
+                    1
+                }
+            } else {
+                0
+            };
+
+            let selected = Select::new(&format!("{} [boolean]", fact_name), options)
+                .with_help_message("Use arrow keys to select, Enter to confirm")
+                .with_starting_cursor(default_index)
+                .prompt()
+                .context(format!("Failed to get boolean value for {}", fact_name))?;
 
-                let selected = Select::new(&format!("{} [boolean]", fact_name), options)
-                    .with_help_message("Use arrow keys to select, Enter to confirm")
-                    .with_starting_cursor(default_index)
+            selected.to_string()
+        }
+        _ => {
+            let prompt_message = format!("{} [{}]", fact_name, type_str);
+
+            if let Some(default) = &default_value {
+                Text::new(&prompt_message)
+                    .with_help_message(&format!("Example: {}", type_ann.example_value()))
+                    .with_default(default)
+                    .prompt()
+                    .context(format!("Failed to get value for {}", fact_name))?
+            } else {
+                Text::new(&prompt_message)
+                    .with_help_message(&format!("Example: {}", type_ann.example_value()))
                     .prompt()
-                    .context(format!("Failed to get boolean value for {}", fact_name))?;
+                    .context(format!("Failed to get value for {}", fact_name))?
+            }
+        }
+    };
+
+    Ok(Some(value))
+}
 
-                selected.to_string()
+/// A rule to invert, with the outcome to invert it for, see `run_invert_wizard`
+pub struct InversionQuery {
+    pub doc: String,
+    pub rule: String,
+    pub target: Target,
+    /// The target in words, e.g. `is 8` or `is at least 100 EUR`
+    pub target_description: String,
+    /// Given facts as `name=value`
+    pub facts: Vec<String>,
+}
+
+/// Guide the user through inverting a rule
+///
+/// Asks for the document and rule (unless given), then offers the outcomes
+/// the rule's branches can produce as targets, and asks for the facts the
+/// user already knows.
+pub fn run_invert_wizard(
+    engine: &Engine,
+    doc_name: Option<String>,
+    rule_name: Option<String>,
+) -> Result<InversionQuery> {
+    let doc = match doc_name {
+        Some(name) => name,
+        None => select_document(engine)?,
+    };
+
+    let rules = engine.get_document_rules(&doc);
+    let rule = match rule_name {
+        Some(name) => rules
+            .iter()
+            .find(|rule| rule.name == name)
+            .with_context(|| format!("Rule '{}' not found in document '{}'", name, doc))?,
+        None => {
+            if rules.is_empty() {
+                anyhow::bail!("Document '{}' has no rules to invert", doc);
             }
-            _ => {
-                let prompt_message = format!("{} [{}]", fact_name, type_str);
-
-                if let Some(default) = &default_value {
-                    Text::new(&prompt_message)
-                        .with_help_message(&format!("Example: {}", type_ann.example_value()))
-                        .with_default(default)
-                        .prompt()
-                        .context(format!("Failed to get value for {}", fact_name))?
-                } else {
-                    Text::new(&prompt_message)
-                        .with_help_message(&format!("Example: {}", type_ann.example_value()))
-                        .prompt()
-                        .context(format!("Failed to get value for {}", fact_name))?
+            let names: Vec<String> = rules.iter().map(|rule| rule.name.clone()).collect();
+            let selected = Select::new("Select a rule to invert:", names)
+                .with_help_message("Use arrow keys to navigate, Enter to select")
+                .prompt()
+                .context("Failed to get rule selection")?;
+            rules
+                .iter()
+                .find(|rule| rule.name == selected)
+                .context("Failed to find selected rule")?
+        }
+    };
+
+    let (target, target_description) = select_target(rule)?;
+    let facts = prompt_given_facts(engine, &doc, rule)?;
+
+    Ok(InversionQuery {
+        doc,
+        rule: rule.name.clone(),
+        target,
+        target_description,
+        facts,
+    })
+}
+
+/// An outcome offered as inversion target
+enum OutcomeChoice {
+    Target(Target, String),
+    /// A comparison with a value the user enters
+    Compare,
+}
+
+/// The outcomes a rule can produce: the results of its default expression
+/// and unless clauses, plus any value, any veto and comparisons
+fn rule_outcomes(rule: &LemmaRule) -> Vec<(String, OutcomeChoice)> {
+    let mut outcomes = vec![(
+        "Any value".to_string(),
+        OutcomeChoice::Target(Target::any_value(), "has a value".to_string()),
+    )];
+    let mut values = Vec::new();
+    let mut vetoes = Vec::new();
+    let mut computed = false;
+
+    let results = std::iter::once(&rule.expression)
+        .chain(rule.unless_clauses.iter().map(|clause| &clause.result));
+    for result in results {
+        match &result.kind {
+            ExpressionKind::Literal(value) => {
+                if !values.contains(value) {
+                    values.push(value.clone());
+                }
+            }
+            ExpressionKind::Veto(veto) => {
+                if let Some(message) = &veto.message {
+                    if !vetoes.contains(message) {
+                        vetoes.push(message.clone());
+                    }
                 }
             }
-        };
+            _ => computed = true,
+        }
+    }
+
+    for value in &values {
+        outcomes.push((
+            format!("Exactly {}", value),
+            OutcomeChoice::Target(Target::value(value.clone()), format!("is {}", value)),
+        ));
+    }
+    let comparable = values
+        .iter()
+        .any(|value| !matches!(value, LiteralValue::Text(_) | LiteralValue::Boolean(_)));
+    if computed || comparable {
+        outcomes.push((
+            "A value compared to an amount (at least, less than, ...)".to_string(),
+            OutcomeChoice::Compare,
+        ));
+    }
+    for message in &vetoes {
+        outcomes.push((
+            format!("Veto \"{}\"", message),
+            OutcomeChoice::Target(
+                Target::veto(Some(message.clone())),
+                format!("is vetoed with \"{}\"", message),
+            ),
+        ));
+    }
+    let vetoes_anything = rule
+        .unless_clauses
+        .iter()
+        .any(|clause| matches!(clause.result.kind, ExpressionKind::Veto(_)))
+        || matches!(rule.expression.kind, ExpressionKind::Veto(_));
+    if vetoes_anything {
+        outcomes.push((
+            "Any veto".to_string(),
+            OutcomeChoice::Target(Target::any_veto(), "is vetoed".to_string()),
+        ));
+    }
+    outcomes
+}
+
+fn select_target(rule: &LemmaRule) -> Result<(Target, String)> {
+    let outcomes = rule_outcomes(rule);
+    let labels: Vec<String> = outcomes.iter().map(|(label, _)| label.clone()).collect();
+    let selected = Select::new(&format!("What should {} be?", rule.name), labels)
+        .with_help_message("Outcomes come from the rule and its unless clauses")
+        .prompt()
+        .context("Failed to get target selection")?;
+
+    let (_, choice) = outcomes
+        .into_iter()
+        .find(|(label, _)| *label == selected)
+        .context("Failed to find selected target")?;
+    match choice {
+        OutcomeChoice::Target(target, description) => Ok((target, description)),
+        OutcomeChoice::Compare => prompt_comparison(rule),
+    }
+}
 
-        fact_values.push(format!("{}={}", fact_name, value));
+fn prompt_comparison(rule: &LemmaRule) -> Result<(Target, String)> {
+    let comparisons = [
+        ("at least", TargetOp::Gte),
+        ("more than", TargetOp::Gt),
+        ("at most", TargetOp::Lte),
+        ("less than", TargetOp::Lt),
+        ("exactly", TargetOp::Eq),
+        ("anything but", TargetOp::Neq),
+    ];
+    let words: Vec<&str> = comparisons.iter().map(|(words, _)| *words).collect();
+    let selected = Select::new(&format!("{} should be", rule.name), words)
+        .prompt()
+        .context("Failed to get comparison")?;
+    let op = comparisons
+        .iter()
+        .find(|(words, _)| *words == selected)
+        .map(|(_, op)| *op)
+        .context("Failed to find selected comparison")?;
+
+    let input = Text::new(&format!("{} should be {}", rule.name, selected))
+        .with_help_message(
+            "A number or amount, e.g. 100 or 100 EUR; plain numbers are in the rule's unit",
+        )
+        .prompt()
+        .context("Failed to get target value")?;
+    let value = lemma::parse_literal(input.trim())
+        .unwrap_or_else(|_| LiteralValue::Text(input.trim().to_string()));
+
+    Ok((
+        Target::with_op(op, OperationResult::Value(value.clone())),
+        format!("is {} {}", selected, value),
+    ))
+}
+
+/// Ask which of the facts the rule needs are known, and their values
+fn prompt_given_facts(engine: &Engine, doc_name: &str, rule: &LemmaRule) -> Result<Vec<String>> {
+    let all_rules: Vec<LemmaRule> = engine
+        .get_document_rules(doc_name)
+        .into_iter()
+        .cloned()
+        .collect();
+    let doc_facts = engine.get_document_facts(doc_name);
+    let doc_facts_vec: Vec<LemmaFact> = doc_facts.iter().map(|f| (*f).clone()).collect();
+    let required = lemma::analysis::find_required_facts_recursive(rule, &all_rules, &doc_facts_vec);
+
+    let facts: Vec<&LemmaFact> = doc_facts
+        .into_iter()
+        .filter(|fact| {
+            required.contains(&lemma::analysis::fact_display_name(fact))
+                && !matches!(
+                    fact.value,
+                    lemma::FactValue::DocumentReference(_)
+                        | lemma::FactValue::DocumentCollection(_)
+                )
+        })
+        .collect();
+    if facts.is_empty() {
+        return Ok(Vec::new());
     }
 
+    let names: Vec<String> = facts
+        .iter()
+        .map(|fact| lemma::analysis::fact_display_name(fact))
+        .collect();
+    let known = MultiSelect::new("Which of these facts do you already know?", names)
+        .with_help_message("Space to select, Enter to confirm; the others are solved for")
+        .prompt()
+        .context("Failed to get known facts")?;
+
+    let mut fact_values = Vec::new();
+    for fact in facts {
+        let name = lemma::analysis::fact_display_name(fact);
+        if !known.contains(&name) {
+            continue;
+        }
+        if let Some(value) = prompt_fact_value(fact)? {
+            fact_values.push(format!("{}={}", name, value));
+        }
+    }
     Ok(fact_values)
 }
@@ -0,0 +1,48 @@
+//! Logging of engine and server activity
+//!
+//! Logs go to stderr, so they never mix with results on stdout or with the
+//! MCP protocol. `RUST_LOG` takes precedence over the level from `--verbose`.
+
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    Text,
+    /// One JSON object per line, with the enclosing spans
+    Json,
+}
+
+/// Install the global subscriber
+///
+/// Without `--verbose` only messages at `default_level` and above are shown.
+/// `-v` shows the spans and events of the engine (parsing, validation, each
+/// evaluated rule, inversion), `-vv` everything, including how long each span took.
+pub fn init(verbose: u8, format: LogFormat, default_level: &str) {
+    let level = match verbose {
+        0 => default_level,
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(format!("lemma={},tower_http={}", level, level)));
+    let span_events = if verbose >= 2 {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(span_events)
+        .with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
+}
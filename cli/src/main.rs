@@ -1,16 +1,24 @@
+mod bindgen;
+mod check;
+mod docgen;
 mod error_formatter;
 mod formatter;
 mod interactive;
+mod logging;
 mod mcp;
+mod report;
+mod scenario;
 mod server;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use formatter::Formatter;
-use lemma::Engine;
+use lemma::{Engine, EvaluationOptions, SourceEdit, UnusedOverrides};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 
 #[derive(Parser)]
 #[command(name = "lemma")]
@@ -22,6 +30,18 @@ use walkdir::WalkDir;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Log what the engine does to stderr (-v: parsing, validation and each rule, -vv: everything)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+    /// Format of log lines
+    #[arg(long, global = true, value_enum, default_value = "text")]
+    log_format: logging::LogFormat,
+    /// Reject rules that combine different kinds of units, like `price * weight` with money and mass
+    #[arg(long, global = true)]
+    strict_units: bool,
+    /// How to read fact override dates like 31-12-2025: iso, day-month-year or month-day-year
+    #[arg(long, global = true, default_value = "iso")]
+    date_format: lemma::DateFormat,
 }
 
 #[derive(Subcommand)]
@@ -54,6 +74,26 @@ enum Commands {
         /// Enable interactive mode for document/rule/fact selection
         #[arg(short = 'i', long)]
         interactive: bool,
+        /// Locale for veto messages and number formatting (e.g. nl or nl-BE)
+        ///
+        /// Money and numbers are shown with the locale's separators and currency
+        /// symbols, e.g. `€ 1.234,50` for nl. Raw output is never localized.
+        #[arg(long)]
+        locale: Option<String>,
+        /// Fail when a fact override matches no fact in the document
+        ///
+        /// Without it, such overrides are reported as a warning with the
+        /// closest fact name, e.g. `did you mean 'quantity'?`.
+        #[arg(long)]
+        strict: bool,
+        /// Use the `example` values of typed facts that aren't given
+        #[arg(long)]
+        with_examples: bool,
+        /// Also write the results, facts, rule traces and dependencies to an HTML page
+        ///
+        /// A single file to share with people who don't run Lemma themselves.
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
     },
     /// Show document structure
     ///
@@ -65,6 +105,34 @@ enum Commands {
         /// Workspace root directory containing .lemma files
         #[arg(short = 'd', long = "dir", default_value = ".")]
         workdir: PathBuf,
+        /// Locale for number formatting (e.g. nl or en-US)
+        #[arg(long)]
+        locale: Option<String>,
+    },
+    /// Print the AST of a document as JSON
+    ///
+    /// Every fact, rule and expression with its source span and expression ID,
+    /// for analysis tools, visualizers and codemods.
+    Ast {
+        /// Name of the document to export
+        doc_name: String,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+    },
+    /// Print the protobuf message for a document's fact overrides
+    ///
+    /// A proto3 definition with one field per fact, numbered in declaration
+    /// order, for services that send facts as protobuf.
+    ProtoGen {
+        /// Name of the document to generate the message for
+        doc_name: String,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+        /// Write the definition to this file instead of stdout
+        #[arg(short = 'o', long)]
+        output: Option<PathBuf>,
     },
     /// List all documents with facts and rules counts
     ///
@@ -79,7 +147,7 @@ enum Commands {
     ///
     /// Runs a server that evaluates Lemma docs via HTTP POST requests.
     /// Useful for integrating Lemma rules into web applications and microservices.
-    /// API: POST /evaluate with {code, facts}
+    /// API: POST /evaluate with {code, document, rules, facts}
     Server {
         /// Workspace root directory containing .lemma files
         #[arg(short = 'd', long = "dir", default_value = ".")]
@@ -90,11 +158,39 @@ enum Commands {
         /// Port number to listen on
         #[arg(short, long, default_value = "3000")]
         port: u16,
+        /// Leave `internal` rules out of responses and reject requests for them
+        #[arg(long)]
+        public_rules_only: bool,
+        /// Also evaluate SHADOW whenever DOC is evaluated, and log where they differ
+        ///
+        /// Responses only have DOC's results. Repeat for more documents,
+        /// e.g. --shadow pricing=pricing_next
+        #[arg(long = "shadow", value_name = "DOC=SHADOW")]
+        shadows: Vec<String>,
+        /// Send each evaluation's rules and operations as OpenTelemetry spans to this collector
+        ///
+        /// An OTLP/HTTP endpoint, e.g. http://localhost:4318. Spans join the
+        /// trace of the request's `traceparent` header.
+        #[arg(long, value_name = "URL")]
+        otlp_endpoint: Option<String>,
+        /// How long a response to `POST /evaluate` with an `Idempotency-Key` header is kept
+        ///
+        /// A retry with the same key and request within this many seconds gets
+        /// the kept response instead of evaluating again. 0 turns keys off.
+        #[arg(long, value_name = "SECONDS", default_value = "600")]
+        idempotency_ttl: u64,
+        /// Most evaluations running at once (default: number of CPU cores)
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+        workers: Option<u32>,
+        /// Most evaluations waiting for a worker; requests beyond that get a 429 with Retry-After
+        #[arg(long, value_name = "N", default_value = "64")]
+        queue: usize,
     },
     /// Start MCP server for AI assistant integration (stdio)
     ///
     /// Runs an MCP server over stdio for AI assistant integration.
-    /// The server provides tools for adding documents, evaluating rules, and inspecting documents.
+    /// The server provides tools for adding documents, evaluating rules, and inspecting documents,
+    /// and exposes each document as a resource, with notifications when documents change.
     /// Designed for use with AI coding assistants and agents.
     Mcp {
         /// Workspace root directory containing .lemma files
@@ -105,34 +201,245 @@ enum Commands {
     ///
     /// Uses symbolic inversion to derive input constraints from rule definitions.
     /// Returns domains (valid ranges/values) for each fact that satisfies the target.
+    ///
+    /// With --interactive, a wizard asks for the document, rule, target and
+    /// known facts, and explains the result in plain language.
     Invert {
-        /// Document name
-        doc_name: String,
-        /// Rule name to invert
-        rule_name: String,
+        /// Document name (asked for with --interactive)
+        doc_name: Option<String>,
+        /// Rule name to invert (asked for with --interactive)
+        rule_name: Option<String>,
         /// Target to invert for (default: any non-veto value)
         ///
         /// Examples:
         ///   any                - any non-veto value (default)
         ///   veto               - any veto
         ///   100                - specific value
+        ///   ">=100 EUR"        - values are Lemma literals, converted to the rule's unit
         ///   >50                - greater than 50
         ///   >=50               - greater than or equal to 50
         /// > <100               - less than 100
         /// > <=100              - less than or equal to 100
+        ///
+        /// A plain number is read in the rule's unit, so >=10 on a
+        /// percentage rule means at least 10%. Likewise ">=10%" on a rule
+        /// returning plain numbers means at least 10.
         #[arg(short = 't', long, default_value = "any")]
         target: String,
+        /// Target a referenced rule must meet as well (format: rule?target)
+        ///
+        /// Examples: risk.score?<=40, tier?bulk
+        #[arg(short = 'w', long = "where")]
+        rule_targets: Vec<String>,
         /// Facts to provide as given (format: name=value)
         facts: Vec<String>,
         /// Workspace root directory containing .lemma files
         #[arg(short = 'd', long = "dir", default_value = ".")]
         workdir: PathBuf,
+        /// Choose the document, rule, target and known facts from prompts
+        #[arg(short = 'i', long)]
+        interactive: bool,
+    },
+    /// Evaluate a rule across a range of one fact and chart the results
+    ///
+    /// Evaluates the rule for every value from --from to --to in increments of
+    /// --step, with the other facts fixed, and shows where the result changes.
+    Sweep {
+        /// Document name
+        doc_name: String,
+        /// Rule name to evaluate
+        rule_name: String,
+        /// Fact to sweep (format: name or ref_doc.fact)
+        fact: String,
+        /// First value, a Lemma literal (e.g. 0, 10%, "0 kilograms")
+        #[arg(long)]
+        from: String,
+        /// Last value, in the same unit as --from
+        #[arg(long)]
+        to: String,
+        /// Increment between values, in the unit of --from
+        #[arg(long, default_value = "1")]
+        step: String,
+        /// Facts to keep fixed (format: name=value or ref_doc.fact=value)
+        facts: Vec<String>,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+        /// Output one tab-separated input and result per line
+        #[arg(short = 'r', long)]
+        raw: bool,
+    },
+    /// Show how much a rule result changes per unit change of numeric facts
+    ///
+    /// Computes the sensitivity of the rule to each fact given with --of, at the
+    /// document's fact values with the given overrides, most influential first.
+    Sensitivity {
+        /// Document name
+        doc_name: String,
+        /// Rule name to analyze
+        rule_name: String,
+        /// Numeric facts to vary, comma-separated (format: name or ref_doc.fact)
+        #[arg(long, value_delimiter = ',', required = true)]
+        of: Vec<String>,
+        /// Facts to evaluate at (format: name=value or ref_doc.fact=value)
+        facts: Vec<String>,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+    },
+    /// Rename a fact or rule everywhere it is used
+    ///
+    /// Rewrites the definition and every reference in the workspace, also from
+    /// other documents (order.price, order.total?), in overrides and in
+    /// aggregates. The workspace must load without errors.
+    Rename {
+        /// Document declaring the fact or rule
+        doc_name: String,
+        /// Current name of the fact or rule
+        old_name: String,
+        /// New name
+        new_name: String,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+        /// List the edits without changing any file
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Generate typed client bindings for a workspace
+    ///
+    /// Emits fact input interfaces, per-rule result types and a client class
+    /// for every document, targeting the HTTP API or the wasm module.
+    Bindgen {
+        /// Language of the generated bindings
+        #[arg(long, value_enum, default_value = "ts")]
+        lang: bindgen::Lang,
+        /// Runtime the generated client talks to
+        #[arg(long, value_enum, default_value = "http")]
+        target: bindgen::Target,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+        /// Write the bindings to this file instead of stdout
+        #[arg(short = 'o', long)]
+        output: Option<PathBuf>,
+    },
+    /// Render the workspace as browsable documentation
+    ///
+    /// Writes a page per document with its commentary, facts, rules, the
+    /// facts and rules each rule uses and a dependency diagram, plus an index.
+    Docgen {
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
+        /// Directory to write the pages to
+        #[arg(short = 'o', long = "out", default_value = "docs")]
+        out: PathBuf,
+        /// Format of the pages
+        #[arg(long, value_enum, default_value = "html")]
+        format: docgen::DocFormat,
+    },
+    /// Parse and validate .lemma files without evaluating them
+    ///
+    /// Reports every diagnostic and exits with a nonzero code if there are any,
+    /// so it can run as a pre-commit hook or CI step.
+    Check {
+        /// Files or directories to check
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Only check files matching this glob (repeatable, e.g. 'policies/**/*.lemma')
+        #[arg(long = "glob")]
+        globs: Vec<String>,
+        /// Diagnostic output format
+        #[arg(long, value_enum, default_value = "human")]
+        error_format: check::ErrorFormat,
+        /// Also evaluate every document with its declared facts
+        #[arg(long)]
+        evaluate: bool,
+    },
+    /// Compare two versions of a workspace fact by fact and rule by rule
+    ///
+    /// Lists added, removed and changed documents, facts, rules, checklists and
+    /// invariants with their old and new code, ignoring formatting and order,
+    /// the unchanged documents that reference changed ones, and the version
+    /// bump each changed document needs.
+    Diff {
+        /// Workspace directory with the old version, e.g. a checkout of the base branch
+        old: PathBuf,
+        /// Workspace directory with the new version
+        new: PathBuf,
+        /// Exit with code 1 when a document's `version` doesn't cover its changes
+        ///
+        /// Removing a fact or rule, for example, needs a major version bump.
+        #[arg(long)]
+        check_versions: bool,
+    },
+    /// Run scenario files and report which expectations hold
+    ///
+    /// A scenario evaluates a document with fact overrides and lists the
+    /// results or vetoes it expects. Exits with code 1 when any fails.
+    Scenario {
+        #[command(subcommand)]
+        command: ScenarioCommand,
+    },
+    /// Generate a document of facts from a CSV table
+    ///
+    /// The first row is the header and the first column the row key.
+    /// Two-column tables produce one fact per row (key = value), wider tables
+    /// produce one fact per cell named <key>_<column>.
+    ImportFacts {
+        /// CSV file to import
+        file: PathBuf,
+        /// Name of the generated document
+        #[arg(long = "doc")]
+        doc_name: String,
+        /// Write the generated document to this file instead of stdout
+        #[arg(short = 'o', long)]
+        output: Option<PathBuf>,
+    },
+    /// Generate a document declaring the facts of a JSON Schema
+    ///
+    /// Every property of the schema becomes a fact with a matching type;
+    /// nested objects become fact groups and enums get a rule vetoing
+    /// other values. Units are given with x-lemma-type or x-lemma-unit.
+    Scaffold {
+        /// JSON Schema file to scaffold from
+        #[arg(long = "from-schema")]
+        schema: PathBuf,
+        /// Name of the generated document
+        #[arg(long = "doc")]
+        doc_name: String,
+        /// Write the generated document to this file instead of stdout
+        #[arg(short = 'o', long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ScenarioCommand {
+    /// Run .lemma-scenario files against the workspace
+    Run {
+        /// Scenario files or directories containing .lemma-scenario files
+        #[arg(default_value = ".")]
+        paths: Vec<PathBuf>,
+        /// Workspace root directory containing .lemma files
+        #[arg(short = 'd', long = "dir", default_value = ".")]
+        workdir: PathBuf,
     },
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    // The servers log each request by default, other commands only problems
+    let default_level = match cli.command {
+        Commands::Server { .. } | Commands::Mcp { .. } => "info",
+        _ => "warn",
+    };
+    logging::init(cli.verbose, cli.log_format, default_level);
+    STRICT_UNITS.store(cli.strict_units, Ordering::Relaxed);
+    let _ = DATE_FORMAT.set(cli.date_format);
+
     let result = match &cli.command {
         Commands::Run {
             workdir,
@@ -140,22 +447,146 @@ fn main() {
             facts,
             raw,
             interactive,
-        } => run_command(workdir, doc_name.as_ref(), facts, *raw, *interactive),
-        Commands::Show { workdir, doc_name } => show_command(workdir, doc_name),
+            locale,
+            strict,
+            with_examples,
+            report,
+        } => run_command(
+            workdir,
+            doc_name.as_ref(),
+            facts,
+            *raw,
+            *interactive,
+            *strict,
+            *with_examples,
+            locale.as_deref(),
+            report.as_deref(),
+        ),
+        Commands::Show {
+            workdir,
+            doc_name,
+            locale,
+        } => show_command(workdir, doc_name, locale.as_deref()),
+        Commands::Ast { doc_name, workdir } => ast_command(workdir, doc_name),
+        Commands::ProtoGen {
+            doc_name,
+            workdir,
+            output,
+        } => proto_gen_command(workdir, doc_name, output.as_deref()),
         Commands::List { root } => list_command(root),
         Commands::Server {
             workdir,
             host,
             port,
-        } => server_command(workdir, host, *port),
+            public_rules_only,
+            shadows,
+            otlp_endpoint,
+            idempotency_ttl,
+            workers,
+            queue,
+        } => parse_shadows(shadows).and_then(|shadows| {
+            server_command(
+                workdir,
+                server::ServerOptions {
+                    host: host.clone(),
+                    port: *port,
+                    public_rules_only: *public_rules_only,
+                    shadows,
+                    otlp_endpoint: otlp_endpoint.clone(),
+                    idempotency_ttl: std::time::Duration::from_secs(*idempotency_ttl),
+                    workers: workers.map_or_else(
+                        || std::thread::available_parallelism().map_or(1, |n| n.get()),
+                        |n| n as usize,
+                    ),
+                    queue: *queue,
+                },
+            )
+        }),
         Commands::Mcp { workdir } => mcp_command(workdir),
         Commands::Invert {
             workdir,
             doc_name,
             rule_name,
             target,
+            rule_targets,
+            facts,
+            interactive,
+        } => {
+            if *interactive {
+                invert_interactive_command(workdir, doc_name.clone(), rule_name.clone())
+            } else {
+                match (doc_name, rule_name) {
+                    (Some(doc_name), Some(rule_name)) => {
+                        invert_command(workdir, doc_name, rule_name, target, rule_targets, facts)
+                    }
+                    _ => Err(anyhow::anyhow!(
+                        "Missing document or rule name: use `lemma invert <DOC> <RULE>`, or `lemma invert --interactive` to choose them"
+                    )),
+                }
+            }
+        }
+        Commands::Sweep {
+            workdir,
+            doc_name,
+            rule_name,
+            fact,
+            from,
+            to,
+            step,
             facts,
-        } => invert_command(workdir, doc_name, rule_name, target, facts),
+            raw,
+        } => sweep_command(
+            workdir, doc_name, rule_name, fact, from, to, step, facts, *raw,
+        ),
+        Commands::Sensitivity {
+            workdir,
+            doc_name,
+            rule_name,
+            of,
+            facts,
+        } => sensitivity_command(workdir, doc_name, rule_name, of, facts),
+        Commands::Rename {
+            doc_name,
+            old_name,
+            new_name,
+            workdir,
+            dry_run,
+        } => rename_command(workdir, doc_name, old_name, new_name, *dry_run),
+        Commands::Bindgen {
+            lang,
+            target,
+            workdir,
+            output,
+        } => bindgen_command(workdir, *lang, *target, output.as_deref()),
+        Commands::Docgen {
+            workdir,
+            out,
+            format,
+        } => docgen_command(workdir, out, *format),
+        Commands::ImportFacts {
+            file,
+            doc_name,
+            output,
+        } => import_facts_command(file, doc_name, output.as_deref()),
+        Commands::Scaffold {
+            schema,
+            doc_name,
+            output,
+        } => scaffold_command(schema, doc_name, output.as_deref()),
+        Commands::Check {
+            paths,
+            globs,
+            error_format,
+            evaluate,
+        } => check_command(paths, globs, *error_format, *evaluate, cli.strict_units),
+        Commands::Diff {
+            old,
+            new,
+            check_versions,
+        } => diff_command(old, new, *check_versions),
+        Commands::Scenario {
+            command: ScenarioCommand::Run { paths, workdir },
+        } => scenario_run_command(paths, workdir),
     };
 
     if let Err(e) = result {
@@ -169,14 +600,19 @@ fn main() {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn run_command(
     workdir: &Path,
     doc_name: Option<&String>,
     facts: &[String],
     raw: bool,
     interactive: bool,
+    strict: bool,
+    with_examples: bool,
+    locale: Option<&str>,
+    report: Option<&Path>,
 ) -> Result<()> {
-    let mut engine = Engine::new();
+    let mut engine = workspace_engine();
     load_workspace(&mut engine, workdir)?;
 
     let (doc, rules, final_facts) = if interactive || doc_name.is_none() {
@@ -216,18 +652,45 @@ fn run_command(
     };
 
     // Parse facts
-    let facts = if !final_facts.is_empty() {
+    let mut facts = if !final_facts.is_empty() {
         let refs: Vec<&str> = final_facts.iter().map(|s| s.as_str()).collect();
-        Some(lemma::parse_facts(&refs)?)
+        Some(engine.parse_facts(&doc, &refs)?)
     } else {
         None
     };
+    if with_examples {
+        let given = facts.get_or_insert_with(Vec::new);
+        let examples: Vec<_> = engine
+            .example_facts(&doc)
+            .into_iter()
+            .filter(|example| !given.iter().any(|fact| fact.fact_type == example.fact_type))
+            .collect();
+        given.extend(examples);
+    }
 
     // Evaluate
-    let response = engine.evaluate(&doc, rules, facts)?;
-    let formatter = Formatter::default();
+    let options = EvaluationOptions {
+        locales: locale.into_iter().map(str::to_string).collect(),
+        unused_overrides: if strict {
+            UnusedOverrides::Strict
+        } else {
+            UnusedOverrides::Warn
+        },
+        ..EvaluationOptions::default()
+    };
+    let given = facts.clone().unwrap_or_default();
+    let response = engine.evaluate_with_options(&doc, rules, facts, &options)?;
+    let formatter = Formatter::with_locale(locale);
     print!("{}", formatter.format_response(&response, raw));
 
+    if let (Some(path), Some(evaluated)) = (report, engine.get_document(&doc)) {
+        fs::write(
+            path,
+            report::html_report(evaluated, &response, &given, &formatter),
+        )?;
+        eprintln!("Wrote report to {}", path.display());
+    }
+
     for warning in &response.warnings {
         eprintln!("Warning: {}", warning);
     }
@@ -235,15 +698,15 @@ fn run_command(
     Ok(())
 }
 
-fn show_command(workdir: &Path, doc_name: &str) -> Result<()> {
-    let mut engine = Engine::new();
+fn show_command(workdir: &Path, doc_name: &str, locale: Option<&str>) -> Result<()> {
+    let mut engine = workspace_engine();
     load_workspace(&mut engine, workdir)?;
 
     if let Some(doc) = engine.get_document(doc_name) {
         let facts = engine.get_document_facts(doc_name);
         let rules = engine.get_document_rules(doc_name);
 
-        let formatter = Formatter::default();
+        let formatter = Formatter::with_locale(locale);
         print!(
             "{}",
             formatter.format_document_inspection(doc, &facts, &rules)
@@ -256,21 +719,36 @@ fn show_command(workdir: &Path, doc_name: &str) -> Result<()> {
     Ok(())
 }
 
+fn ast_command(workdir: &Path, doc_name: &str) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    println!("{:#}", engine.export_ast(doc_name)?);
+    Ok(())
+}
+
+fn proto_gen_command(workdir: &Path, doc_name: &str, output: Option<&Path>) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let proto = engine.proto_schema(doc_name)?;
+    match output {
+        Some(path) => {
+            fs::write(path, &proto)?;
+            println!("Wrote message for '{}' to {}", doc_name, path.display());
+        }
+        None => print!("{}", proto),
+    }
+    Ok(())
+}
+
 fn list_command(root: &PathBuf) -> Result<()> {
-    let mut engine = Engine::new();
+    let mut engine = workspace_engine();
 
     println!("Loading workspace from {}...", root.display());
 
     let mut file_count = 0;
-    for entry in WalkDir::new(root) {
-        let entry = entry?;
-        if entry.path().extension().and_then(|s| s.to_str()) == Some("lemma") {
-            file_count += 1;
-            let path = entry.path();
-            let source_id = path.to_string_lossy().to_string();
-            engine.add_lemma_code(&fs::read_to_string(path)?, &source_id)?;
-        }
-    }
+    engine.load_dir_with_progress(root, |progress| file_count = progress.total)?;
 
     let documents = engine.list_documents();
 
@@ -293,20 +771,54 @@ fn list_command(root: &PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn server_command(workdir: &Path, host: &str, port: u16) -> Result<()> {
+/// `DOC=SHADOW` pairs given with `--shadow`
+fn parse_shadows(shadows: &[String]) -> Result<std::collections::HashMap<String, String>> {
+    shadows
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((doc, shadow)) if !doc.trim().is_empty() && !shadow.trim().is_empty() => {
+                Ok((doc.trim().to_string(), shadow.trim().to_string()))
+            }
+            _ => Err(anyhow::anyhow!(
+                "Invalid --shadow '{}': expected DOC=SHADOW",
+                pair
+            )),
+        })
+        .collect()
+}
+
+fn server_command(workdir: &Path, options: server::ServerOptions) -> Result<()> {
     #[cfg(feature = "server")]
     {
         use tokio::runtime::Runtime;
         let rt = Runtime::new()?;
         rt.block_on(async {
-            let mut engine = Engine::new();
-            load_workspace(&mut engine, workdir)?;
+            if !workdir.is_dir() {
+                anyhow::bail!("Workspace directory '{}' not found", workdir.display());
+            }
+            // An invalid workspace doesn't stop the server: it reports itself not ready
+            let mut engine = workspace_engine();
+            let loaded = engine.load_dir(workdir);
+            match &loaded {
+                Ok(()) => {
+                    for name in options
+                        .shadows
+                        .iter()
+                        .flat_map(|(doc, shadow)| [doc, shadow])
+                    {
+                        if engine.get_document(name).is_none() {
+                            anyhow::bail!("Document '{}' given with --shadow not found", name);
+                        }
+                    }
+                }
+                Err(error) => eprintln!("{}", error_formatter::format_error(error)),
+            }
 
             println!(
                 "Starting HTTP server with {} document(s) loaded",
                 engine.list_documents().len()
             );
-            server::http::start_server(engine, host, port).await
+            server::http::start_server(engine, loaded, workdir.to_path_buf(), options).await
         })?;
     }
 
@@ -323,14 +835,14 @@ fn server_command(workdir: &Path, host: &str, port: u16) -> Result<()> {
 fn mcp_command(workdir: &Path) -> Result<()> {
     #[cfg(feature = "mcp")]
     {
-        let mut engine = Engine::new();
+        let mut engine = workspace_engine();
         load_workspace(&mut engine, workdir)?;
 
         println!(
             "Starting MCP server with {} document(s) loaded",
             engine.list_documents().len()
         );
-        mcp::server::start_server(engine)?;
+        mcp::server::start_server(engine, workdir)?;
     }
 
     #[cfg(not(feature = "mcp"))]
@@ -348,36 +860,26 @@ fn invert_command(
     doc_name: &str,
     rule_name: &str,
     target_str: &str,
+    rule_targets: &[String],
     facts: &[String],
 ) -> Result<()> {
-    let mut engine = Engine::new();
+    let mut engine = workspace_engine();
     load_workspace(&mut engine, workdir)?;
 
     // Parse target
-    let target = parse_target(target_str)?;
-
-    // Parse facts
-    let given_facts = if !facts.is_empty() {
-        let refs: Vec<&str> = facts.iter().map(|s| s.as_str()).collect();
-        let parsed_facts = lemma::parse_facts(&refs)?;
-
-        // Convert Vec<LemmaFact> to HashMap<String, LiteralValue>
-        let mut fact_map = std::collections::HashMap::new();
-        for fact in parsed_facts {
-            if let lemma::FactValue::Literal(value) = fact.value {
-                let fact_name = match &fact.fact_type {
-                    lemma::FactType::Local(name) => format!("{}.{}", doc_name, name),
-                    lemma::FactType::Foreign(foreign) => foreign.reference.join("."),
-                };
-                fact_map.insert(fact_name, value);
-            }
-        }
-        fact_map
-    } else {
-        std::collections::HashMap::new()
-    };
+    let mut target = parse_target(target_str)?;
+    for rule_target in rule_targets {
+        let (rule, rule_target_str) = rule_target.split_once('?').ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid rule target '{}', expected rule?target (e.g. risk.score?<=40)",
+                rule_target
+            )
+        })?;
+        target = target.with_rule_target(rule, parse_target(rule_target_str.trim())?);
+    }
 
     // Perform inversion
+    let given_facts = given_facts(doc_name, facts)?;
     let solutions = engine.invert(doc_name, rule_name, target, given_facts)?;
 
     // Format output
@@ -387,6 +889,324 @@ fn invert_command(
     Ok(())
 }
 
+fn invert_interactive_command(
+    workdir: &Path,
+    doc_name: Option<String>,
+    rule_name: Option<String>,
+) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let query = interactive::run_invert_wizard(&engine, doc_name, rule_name)?;
+    let given_facts = given_facts(&query.doc, &query.facts)?;
+    let solutions = engine.invert(&query.doc, &query.rule, query.target, given_facts)?;
+
+    let formatter = Formatter::default();
+    print!(
+        "{}",
+        formatter.format_inversion_explained(&query.rule, &query.target_description, &solutions)
+    );
+
+    Ok(())
+}
+
+/// Given facts for an inversion, keyed by their path (local facts prefixed with the document)
+fn given_facts(
+    doc_name: &str,
+    facts: &[String],
+) -> Result<std::collections::HashMap<String, lemma::LiteralValue>> {
+    let mut fact_map = std::collections::HashMap::new();
+    if facts.is_empty() {
+        return Ok(fact_map);
+    }
+
+    let refs: Vec<&str> = facts.iter().map(|s| s.as_str()).collect();
+    for fact in lemma::parse_facts_with_date_format(&refs, date_format())? {
+        if let lemma::FactValue::Literal(value) = fact.value {
+            let fact_name = match &fact.fact_type {
+                lemma::FactType::Local(name) => format!("{}.{}", doc_name, name),
+                lemma::FactType::Foreign(foreign) => foreign.reference.join("."),
+            };
+            fact_map.insert(fact_name, value);
+        }
+    }
+    Ok(fact_map)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn sweep_command(
+    workdir: &Path,
+    doc_name: &str,
+    rule_name: &str,
+    fact: &str,
+    from: &str,
+    to: &str,
+    step: &str,
+    facts: &[String],
+    raw: bool,
+) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let from = lemma::parse_literal(from)?;
+    let to = lemma::parse_literal(to)?;
+    let step = match lemma::parse_literal(step)? {
+        lemma::LiteralValue::Number(n) => n,
+        other => anyhow::bail!("--step must be a plain number, got {}", other),
+    };
+
+    let refs: Vec<&str> = facts.iter().map(|s| s.as_str()).collect();
+    let fixed = engine.parse_facts(doc_name, &refs)?;
+
+    let sweep = engine.sweep(doc_name, rule_name, fact, from..=to, step, fixed)?;
+    print!("{}", Formatter::default().format_sweep(&sweep, raw));
+
+    Ok(())
+}
+
+fn sensitivity_command(
+    workdir: &Path,
+    doc_name: &str,
+    rule_name: &str,
+    of: &[String],
+    facts: &[String],
+) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let refs: Vec<&str> = facts.iter().map(|s| s.as_str()).collect();
+    let at_facts = engine.parse_facts(doc_name, &refs)?;
+    let varied: Vec<&str> = of.iter().map(|s| s.as_str()).collect();
+
+    let report = engine.sensitivity(doc_name, rule_name, &varied, at_facts)?;
+    print!("{}", Formatter::default().format_sensitivity(&report));
+
+    Ok(())
+}
+
+fn diff_command(old: &Path, new: &Path, check_versions: bool) -> Result<()> {
+    let mut old_engine = workspace_engine();
+    load_workspace(&mut old_engine, old)?;
+    let mut new_engine = workspace_engine();
+    load_workspace(&mut new_engine, new)?;
+
+    let diff = lemma::diff_workspaces(
+        old_engine.get_all_documents(),
+        new_engine.get_all_documents(),
+    );
+    let reports: Vec<lemma::CompatibilityReport> = diff
+        .documents
+        .iter()
+        .filter_map(|document| {
+            let old_doc = old_engine.get_document(&document.doc_name)?;
+            let new_doc = new_engine.get_document(&document.doc_name)?;
+            Some(Engine::check_compatibility(old_doc, new_doc))
+        })
+        .collect();
+    let formatter = Formatter::default();
+    print!("{}", formatter.format_workspace_diff(&diff));
+    print!("{}", formatter.format_compatibility(&reports));
+
+    let problems = reports.iter().filter(|r| !r.is_compatible()).count();
+    if check_versions && problems > 0 {
+        eprintln!(
+            "{} document(s) changed without the version bump they need",
+            problems
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn rename_command(
+    workdir: &Path,
+    doc_name: &str,
+    old_name: &str,
+    new_name: &str,
+    dry_run: bool,
+) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+    let edits = engine.rename_symbol(doc_name, old_name, new_name)?;
+
+    let mut by_source: BTreeMap<&str, Vec<&SourceEdit>> = BTreeMap::new();
+    for edit in &edits {
+        by_source.entry(&edit.source).or_default().push(edit);
+    }
+
+    for (source, edits) in &by_source {
+        if dry_run {
+            for edit in edits {
+                println!(
+                    "{}:{}:{}: {} -> {}",
+                    source, edit.span.line, edit.span.col, old_name, edit.new_text
+                );
+            }
+            continue;
+        }
+        // Edits are in source order: apply them from the end so earlier offsets stay valid
+        let mut code = fs::read_to_string(source)?;
+        for edit in edits.iter().rev() {
+            code.replace_range(edit.span.start..edit.span.end, &edit.new_text);
+        }
+        fs::write(source, code)?;
+    }
+
+    println!(
+        "{} '{}' to '{}': {} edit(s) in {} file(s)",
+        if dry_run { "Would rename" } else { "Renamed" },
+        old_name,
+        new_name,
+        edits.len(),
+        by_source.len()
+    );
+    Ok(())
+}
+
+fn bindgen_command(
+    workdir: &Path,
+    lang: bindgen::Lang,
+    target: bindgen::Target,
+    output: Option<&Path>,
+) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let code = match lang {
+        bindgen::Lang::Ts => bindgen::generate_typescript(engine.get_all_documents(), target),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, &code)?;
+            println!(
+                "Wrote bindings for {} document(s) to {}",
+                engine.list_documents().len(),
+                path.display()
+            );
+        }
+        None => print!("{}", code),
+    }
+
+    Ok(())
+}
+
+fn docgen_command(workdir: &Path, out: &Path, format: docgen::DocFormat) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let pages = docgen::generate(engine.get_all_documents(), format);
+    fs::create_dir_all(out)?;
+    for page in &pages {
+        fs::write(out.join(&page.path), &page.content)?;
+    }
+    println!("Wrote {} page(s) to {}", pages.len(), out.display());
+
+    Ok(())
+}
+
+fn import_facts_command(file: &Path, doc_name: &str, output: Option<&Path>) -> Result<()> {
+    let table = lemma::FactTable::from_csv(&fs::read_to_string(file)?)?;
+
+    // Loading into an engine validates the generated code
+    let mut engine = workspace_engine();
+    let code = engine.add_facts_from_table(doc_name, &table, &file.to_string_lossy())?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, &code)?;
+            println!(
+                "Wrote {} fact(s) to {}",
+                engine.get_document_facts(doc_name).len(),
+                path.display()
+            );
+        }
+        None => print!("{}", code),
+    }
+
+    Ok(())
+}
+
+fn scaffold_command(schema: &Path, doc_name: &str, output: Option<&Path>) -> Result<()> {
+    let source = schema.to_string_lossy();
+
+    // Loading into an engine validates the generated code
+    let mut engine = workspace_engine();
+    let code = engine.add_document_from_schema(doc_name, &fs::read_to_string(schema)?, &source)?;
+
+    match output {
+        Some(path) => {
+            fs::write(path, &code)?;
+            println!(
+                "Wrote {} fact(s) to {}",
+                engine.get_document_facts(doc_name).len(),
+                path.display()
+            );
+        }
+        None => print!("{}", code),
+    }
+
+    Ok(())
+}
+
+fn check_command(
+    paths: &[PathBuf],
+    globs: &[String],
+    error_format: check::ErrorFormat,
+    evaluate: bool,
+    strict_units: bool,
+) -> Result<()> {
+    let files = check::collect_files(paths, globs)?;
+    let report = check::check_files(&files, evaluate, strict_units)?;
+    let diagnostics = check::flatten(report.diagnostics);
+
+    for diagnostic in &diagnostics {
+        println!("{}", check::format_diagnostic(diagnostic, error_format));
+    }
+    for warning in &report.warnings {
+        println!("{}", check::format_warning(warning, error_format));
+    }
+
+    if diagnostics.is_empty() {
+        eprintln!("Checked {} file(s): no problems found", report.files.len());
+        Ok(())
+    } else {
+        eprintln!(
+            "Checked {} file(s): {} problem(s) found",
+            report.files.len(),
+            diagnostics.len()
+        );
+        std::process::exit(1);
+    }
+}
+
+fn scenario_run_command(paths: &[PathBuf], workdir: &Path) -> Result<()> {
+    let mut engine = workspace_engine();
+    load_workspace(&mut engine, workdir)?;
+
+    let files = scenario::collect_files(paths)?;
+    let mut outcomes = Vec::new();
+    for file in &files {
+        for scenario in scenario::load(file)? {
+            outcomes.extend(scenario::run(&engine, &scenario));
+        }
+    }
+
+    print!("{}", Formatter::default().format_scenario_report(&outcomes));
+
+    let failed = outcomes.iter().filter(|o| !o.passed).count();
+    eprintln!(
+        "Ran {} scenario file(s): {} passed, {} failed",
+        files.len(),
+        outcomes.len() - failed,
+        failed
+    );
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
 fn parse_target(target_str: &str) -> Result<lemma::Target> {
     use lemma::{OperationResult, Target, TargetOp};
 
@@ -427,39 +1247,33 @@ fn parse_target(target_str: &str) -> Result<lemma::Target> {
     }
 }
 
+/// Parse a target value as a Lemma literal (`100 EUR`, `10%`), or text otherwise
 fn parse_literal_value(s: &str) -> Result<lemma::LiteralValue> {
-    use lemma::LiteralValue;
-    use rust_decimal::Decimal;
-
-    // Try parsing as various types
-    if s == "true" {
-        Ok(LiteralValue::Boolean(true))
-    } else if s == "false" {
-        Ok(LiteralValue::Boolean(false))
-    } else if let Ok(num) = s.parse::<Decimal>() {
-        Ok(LiteralValue::Number(num))
-    } else if let Some(s_without_percent) = s.strip_suffix('%') {
-        if let Ok(num) = s_without_percent.parse::<Decimal>() {
-            Ok(LiteralValue::Percentage(num))
-        } else {
-            Err(anyhow::anyhow!("Invalid percentage: {}", s))
-        }
-    } else {
-        Ok(LiteralValue::Text(s.to_string()))
-    }
+    let s = s.trim();
+    Ok(lemma::parse_literal(s).unwrap_or_else(|_| lemma::LiteralValue::Text(s.to_string())))
+}
+
+/// Set from `--strict-units`, read by `workspace_engine`
+static STRICT_UNITS: AtomicBool = AtomicBool::new(false);
+
+/// Set from `--date-format`, read by `workspace_engine` and `date_format`
+static DATE_FORMAT: OnceLock<lemma::DateFormat> = OnceLock::new();
+
+/// How to read dates in fact overrides, see `--date-format`
+fn date_format() -> lemma::DateFormat {
+    DATE_FORMAT.get().copied().unwrap_or_default()
+}
+
+/// An engine for the workspace, strict about units when `--strict-units` is given
+fn workspace_engine() -> Engine {
+    Engine::new()
+        .with_strict_units(STRICT_UNITS.load(Ordering::Relaxed))
+        .with_date_format(date_format())
 }
 
 /// Load all .lemma files from the workspace directory
 fn load_workspace(engine: &mut Engine, workdir: &std::path::Path) -> Result<()> {
-    for entry in WalkDir::new(workdir) {
-        let entry = entry?;
-        if entry.path().extension().and_then(|s| s.to_str()) == Some("lemma") {
-            let path = entry.path();
-            let source_id = path.to_string_lossy().to_string();
-            engine.add_lemma_code(&fs::read_to_string(path)?, &source_id)?;
-        }
-    }
-
+    engine.load_dir(workdir)?;
     Ok(())
 }
 
@@ -3,11 +3,14 @@ pub mod server {
     use anyhow::Result;
     use lemma::Engine;
     use serde::{Deserialize, Serialize};
+    use std::collections::{BTreeMap, HashSet};
     use std::io::{self, BufRead, Write};
+    use std::path::{Path, PathBuf};
     use tracing::{debug, error, info};
 
     const PROTOCOL_VERSION: &str = "2024-11-05";
     const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
+    const RESOURCE_PREFIX: &str = "lemma://documents/";
 
     #[derive(Debug, Deserialize)]
     struct McpRequest {
@@ -80,13 +83,43 @@ pub mod server {
         }
     }
 
+    /// A JSON-RPC notification sent by the server, without an id
+    #[derive(Debug, Serialize)]
+    struct McpNotification {
+        jsonrpc: String,
+        method: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        params: Option<serde_json::Value>,
+    }
+
+    impl McpNotification {
+        fn new(method: &str, params: Option<serde_json::Value>) -> Self {
+            Self {
+                jsonrpc: "2.0".to_string(),
+                method: method.to_string(),
+                params,
+            }
+        }
+    }
+
     struct McpServer {
         engine: Engine,
+        /// Workspace the engine was loaded from, reloaded by `reload_workspace`
+        workdir: PathBuf,
+        /// Resource URIs the client asked to be notified about
+        subscriptions: HashSet<String>,
+        /// Notifications to send after the current response
+        notifications: Vec<McpNotification>,
     }
 
     impl McpServer {
-        fn new(engine: Engine) -> Self {
-            Self { engine }
+        fn new(engine: Engine, workdir: &Path) -> Self {
+            Self {
+                engine,
+                workdir: workdir.to_path_buf(),
+                subscriptions: HashSet::new(),
+                notifications: Vec::new(),
+            }
         }
 
         fn handle_request(&mut self, request: McpRequest) -> McpResponse {
@@ -107,6 +140,10 @@ pub mod server {
                 "initialize" => self.initialize(),
                 "tools/list" => self.list_tools(),
                 "tools/call" => self.call_tool(request.params),
+                "resources/list" => self.list_resources(),
+                "resources/read" => self.read_resource(request.params),
+                "resources/subscribe" => self.subscribe(request.params, true),
+                "resources/unsubscribe" => self.subscribe(request.params, false),
                 _ => Err(McpError::method_not_found(request.method)),
             };
 
@@ -135,7 +172,11 @@ pub mod server {
                     "version": SERVER_VERSION
                 },
                 "capabilities": {
-                    "tools": {}
+                    "tools": {},
+                    "resources": {
+                        "subscribe": true,
+                        "listChanged": true
+                    }
                 }
             }))
         }
@@ -203,6 +244,14 @@ pub mod server {
                             "type": "object",
                             "properties": {}
                         }
+                    },
+                    {
+                        "name": "reload_workspace",
+                        "description": "Reload all .lemma files from the workspace directory, replacing the loaded documents. Subscribed resources are notified when their document changed.",
+                        "inputSchema": {
+                            "type": "object",
+                            "properties": {}
+                        }
                     }
                 ]
             }))
@@ -230,6 +279,7 @@ pub mod server {
                 "evaluate" => self.tool_evaluate(arguments),
                 "inspect" => self.tool_inspect(arguments),
                 "list_documents" => self.tool_list_documents(),
+                "reload_workspace" => self.tool_reload_workspace(),
                 _ => Err(McpError::invalid_params(format!(
                     "Unknown tool: {}",
                     tool_name
@@ -256,17 +306,40 @@ pub mod server {
                 .map(String::from)
                 .unwrap_or_else(|| format!("doc_{}", chrono::Utc::now().timestamp_millis()));
 
-            self.engine.add_lemma_code(code, &source_id).map_err(|e| {
+            let before = self.snapshot();
+            let delta = self.engine.add_lemma_code(code, &source_id).map_err(|e| {
                 error!("Failed to add document: {}", e);
                 McpError::internal_error(format!("Failed to parse document: {}", e))
             })?;
+            self.notify_changes(&before);
+
+            if delta.is_empty() {
+                info!("Document unchanged: {}", source_id);
+                return Ok(serde_json::json!({
+                    "content": [{
+                        "type": "text",
+                        "text": format!("Document unchanged\n\nSource ID: {}\n\nNo document's facts or rules changed.", source_id)
+                    }]
+                }));
+            }
 
             info!("Document added: {}", source_id);
 
+            let mut changes = String::new();
+            for (label, names) in [
+                ("Added", &delta.added),
+                ("Updated", &delta.updated),
+                ("Removed", &delta.removed),
+            ] {
+                if !names.is_empty() {
+                    changes.push_str(&format!("\n{}: {}", label, names.join(", ")));
+                }
+            }
+
             Ok(serde_json::json!({
                 "content": [{
                     "type": "text",
-                    "text": format!("Document added successfully\n\nSource ID: {}\n\nThe document has been parsed and loaded into the engine. You can now evaluate it using the 'evaluate' tool.", source_id)
+                    "text": format!("Document added successfully\n\nSource ID: {}\n{}\n\nThe document has been parsed and loaded into the engine. You can now evaluate it using the 'evaluate' tool.", source_id, changes)
                 }]
             }))
         }
@@ -429,21 +502,184 @@ pub mod server {
                 }]
             }))
         }
+
+        fn tool_reload_workspace(&mut self) -> Result<serde_json::Value, McpError> {
+            let mut engine = crate::workspace_engine();
+            engine.load_dir(&self.workdir).map_err(|e| {
+                error!("Failed to reload workspace: {}", e);
+                McpError::internal_error(format!("Failed to reload workspace: {}", e))
+            })?;
+
+            let before = self.snapshot();
+            self.engine = engine;
+            let changed = self.notify_changes(&before);
+
+            info!("Workspace reloaded, {} document(s) changed", changed);
+
+            Ok(serde_json::json!({
+                "content": [{
+                    "type": "text",
+                    "text": format!(
+                        "Workspace reloaded\n\n{} document(s) loaded, {} changed.",
+                        self.engine.list_documents().len(),
+                        changed
+                    )
+                }]
+            }))
+        }
+
+        fn list_resources(&self) -> Result<serde_json::Value, McpError> {
+            let mut names = self.engine.list_documents();
+            names.sort();
+
+            let resources: Vec<serde_json::Value> = names
+                .iter()
+                .filter_map(|name| self.engine.get_document(name))
+                .map(|doc| {
+                    let mut resource = serde_json::json!({
+                        "uri": format!("{}{}", RESOURCE_PREFIX, doc.name),
+                        "name": doc.name,
+                        "mimeType": "text/x-lemma"
+                    });
+                    if let Some(commentary) = &doc.commentary {
+                        resource["description"] = serde_json::json!(commentary.trim());
+                    }
+                    resource
+                })
+                .collect();
+
+            debug!("Listed {} resources", resources.len());
+
+            Ok(serde_json::json!({ "resources": resources }))
+        }
+
+        /// A document's source, followed by its structure as JSON
+        fn read_resource(
+            &self,
+            params: Option<serde_json::Value>,
+        ) -> Result<serde_json::Value, McpError> {
+            let uri = resource_uri(&params)?;
+            let doc = uri
+                .strip_prefix(RESOURCE_PREFIX)
+                .and_then(|name| self.engine.get_document(name))
+                .ok_or_else(|| McpError::invalid_params(format!("Unknown resource: {}", uri)))?;
+
+            let facts: Vec<serde_json::Value> = doc
+                .facts
+                .iter()
+                .map(|fact| {
+                    serde_json::json!({
+                        "name": lemma::analysis::fact_display_name(fact),
+                        "value": fact.value.to_string()
+                    })
+                })
+                .collect();
+            let rules: Vec<serde_json::Value> = doc
+                .rules
+                .iter()
+                .map(|rule| {
+                    serde_json::json!({
+                        "name": rule.name,
+                        "expression": rule.expression.to_string(),
+                        "unless_clauses": rule.unless_clauses.len()
+                    })
+                })
+                .collect();
+            let structure = serde_json::json!({
+                "name": doc.name,
+                "facts": facts,
+                "rules": rules
+            });
+
+            Ok(serde_json::json!({
+                "contents": [
+                    {
+                        "uri": uri,
+                        "mimeType": "text/x-lemma",
+                        "text": doc.to_string()
+                    },
+                    {
+                        "uri": uri,
+                        "mimeType": "application/json",
+                        "text": structure.to_string()
+                    }
+                ]
+            }))
+        }
+
+        fn subscribe(
+            &mut self,
+            params: Option<serde_json::Value>,
+            subscribe: bool,
+        ) -> Result<serde_json::Value, McpError> {
+            let uri = resource_uri(&params)?;
+            if subscribe {
+                debug!("Subscribed to {}", uri);
+                self.subscriptions.insert(uri);
+            } else {
+                debug!("Unsubscribed from {}", uri);
+                self.subscriptions.remove(&uri);
+            }
+            Ok(serde_json::json!({}))
+        }
+
+        /// Every loaded document, rendered as Lemma code, by name
+        fn snapshot(&self) -> BTreeMap<String, String> {
+            self.engine
+                .list_documents()
+                .into_iter()
+                .filter_map(|name| {
+                    let code = self.engine.get_document(&name)?.to_string();
+                    Some((name, code))
+                })
+                .collect()
+        }
+
+        /// Queue notifications for what changed since `before`
+        ///
+        /// Sends `list_changed` when documents were added or removed, and
+        /// `updated` for each subscribed document that changed or was removed.
+        /// Returns the number of documents that were added, changed or removed.
+        fn notify_changes(&mut self, before: &BTreeMap<String, String>) -> usize {
+            let after = self.snapshot();
+            let changed: Vec<&String> = before
+                .keys()
+                .chain(after.keys().filter(|name| !before.contains_key(*name)))
+                .filter(|name| before.get(*name) != after.get(*name))
+                .collect();
+
+            if !before.keys().eq(after.keys()) {
+                self.notifications.push(McpNotification::new(
+                    "notifications/resources/list_changed",
+                    None,
+                ));
+            }
+            for name in &changed {
+                let uri = format!("{}{}", RESOURCE_PREFIX, name);
+                if self.subscriptions.contains(&uri) {
+                    self.notifications.push(McpNotification::new(
+                        "notifications/resources/updated",
+                        Some(serde_json::json!({ "uri": uri })),
+                    ));
+                }
+            }
+            changed.len()
+        }
     }
 
-    pub fn start_server(engine: Engine) -> Result<()> {
-        tracing_subscriber::fmt()
-            .with_env_filter(
-                tracing_subscriber::EnvFilter::try_from_default_env()
-                    .unwrap_or_else(|_| "lemma_mcp=info".into()),
-            )
-            .with_writer(io::stderr)
-            .init();
+    fn resource_uri(params: &Option<serde_json::Value>) -> Result<String, McpError> {
+        params
+            .as_ref()
+            .and_then(|params| params["uri"].as_str())
+            .map(String::from)
+            .ok_or_else(|| McpError::invalid_params("Missing 'uri' field".to_string()))
+    }
 
+    pub fn start_server(engine: Engine, workdir: &Path) -> Result<()> {
         info!("Starting Lemma MCP server v{}", SERVER_VERSION);
         info!("Protocol version: {}", PROTOCOL_VERSION);
 
-        let mut server = McpServer::new(engine);
+        let mut server = McpServer::new(engine, workdir);
         let stdin = io::stdin();
         let mut stdout = io::stdout();
 
@@ -457,6 +693,13 @@ pub mod server {
             debug!("Received: {}", line);
 
             let response = match serde_json::from_str::<McpRequest>(&line) {
+                // Notifications from the client (`notifications/initialized`) get no reply
+                Ok(request)
+                    if request.id.is_none() && request.method.starts_with("notifications/") =>
+                {
+                    debug!("Received notification: {}", request.method);
+                    continue;
+                }
                 Ok(request) => server.handle_request(request),
                 Err(e) => {
                     error!("Parse error: {}", e);
@@ -471,6 +714,9 @@ pub mod server {
 
             let response_json = serde_json::to_string(&response)?;
             writeln!(stdout, "{}", response_json)?;
+            for notification in server.notifications.drain(..) {
+                writeln!(stdout, "{}", serde_json::to_string(&notification)?)?;
+            }
             stdout.flush()?;
 
             debug!("Sent response");
@@ -485,8 +731,9 @@ pub mod server {
 pub mod server {
     use anyhow::Result;
     use lemma::Engine;
+    use std::path::Path;
 
-    pub fn start_server(_engine: Engine) -> Result<()> {
+    pub fn start_server(_engine: Engine, _workdir: &Path) -> Result<()> {
         anyhow::bail!("MCP feature not enabled. Recompile with --features mcp")
     }
 }
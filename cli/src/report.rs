@@ -0,0 +1,219 @@
+//! Evaluation reports for sharing
+//!
+//! `lemma run --report out.html` writes one HTML page with the outcome of an
+//! evaluation, for readers who won't run the CLI: vetoes first, then every
+//! result, the facts with where their value came from, a collapsible trace
+//! per rule and the document's dependency diagram.
+
+use crate::docgen::{dependency_diagram, escape_html, pretty_rule, MERMAID_SCRIPT, STYLE};
+use crate::formatter::Formatter;
+use lemma::analysis::fact_display_name;
+use lemma::{FactValue, LemmaDoc, LemmaFact, Response, RuleResult};
+use std::fmt::Write;
+
+/// The report of evaluating `doc` with the `given` fact overrides
+pub fn html_report(
+    doc: &LemmaDoc,
+    response: &Response,
+    given: &[LemmaFact],
+    formatter: &Formatter,
+) -> String {
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "<h1>Evaluation of {}</h1>",
+        escape_html(&response.doc_name)
+    );
+    let mut details = vec![format!(
+        "Generated {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    )];
+    if let Some(source) = &doc.source {
+        details.push(format!("Source: <code>{}</code>", escape_html(source)));
+    }
+    if let Some(version) = &response.doc_version {
+        details.push(format!("Version: <code>{}</code>", escape_html(version)));
+    }
+    let _ = writeln!(body, "<p>{}</p>", details.join(" · "));
+
+    let vetoes: Vec<&RuleResult> = response
+        .results
+        .iter()
+        .filter(|result| result.veto_message.is_some())
+        .collect();
+    if !vetoes.is_empty() {
+        body.push_str("<div class=\"vetoes\">\n<h2>Vetoes</h2>\n<ul>\n");
+        for result in vetoes {
+            let _ = writeln!(
+                body,
+                "<li><a href=\"#{}\">{}</a>: {}</li>",
+                trace_anchor(&result.rule_name),
+                escape_html(&result.rule_name),
+                escape_html(result.veto_message.as_deref().unwrap_or_default())
+            );
+        }
+        body.push_str("</ul>\n</div>\n");
+    }
+
+    body.push_str(
+        "<h2>Results</h2>\n<table>\n<tr><th>Rule</th><th>Result</th><th>Description</th></tr>\n",
+    );
+    for result in &response.results {
+        let class = if result.veto_message.is_some() {
+            " class=\"veto\""
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            body,
+            "<tr{}><td><a href=\"#{}\">{}</a></td><td>{}</td><td>{}</td></tr>",
+            class,
+            trace_anchor(&result.rule_name),
+            escape_html(&result.rule_name),
+            escape_html(&outcome(result, formatter)),
+            escape_html(result.description.as_deref().unwrap_or_default())
+        );
+    }
+    body.push_str("</table>\n");
+
+    body.push_str("<h2>Facts</h2>\n<table>\n<tr><th>Fact</th><th>Value</th><th>From</th></tr>\n");
+    for (name, value, from) in fact_rows(doc, given, formatter) {
+        let _ = writeln!(
+            body,
+            "<tr><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+            escape_html(&name),
+            escape_html(&value),
+            from
+        );
+    }
+    body.push_str("</table>\n");
+
+    body.push_str("<h2>Traces</h2>\n");
+    for result in &response.results {
+        let _ = writeln!(
+            body,
+            "<details id=\"{}\"><summary><code>{}</code>: {}</summary>",
+            trace_anchor(&result.rule_name),
+            escape_html(&result.rule_name),
+            escape_html(&outcome(result, formatter))
+        );
+        if let Some(rule) = doc.rules.iter().find(|rule| rule.name == result.rule_name) {
+            let _ = writeln!(
+                body,
+                "<pre><code>{}</code></pre>",
+                escape_html(&pretty_rule(rule))
+            );
+        }
+        if !result.operations.is_empty() {
+            body.push_str("<ol>\n");
+            for operation in &result.operations {
+                let _ = writeln!(
+                    body,
+                    "<li>{}</li>",
+                    escape_html(&formatter.describe_operation(operation))
+                );
+            }
+            body.push_str("</ol>\n");
+        }
+        body.push_str("</details>\n");
+    }
+
+    if !response.warnings.is_empty() {
+        body.push_str("<h2>Warnings</h2>\n<ul>\n");
+        for warning in &response.warnings {
+            let _ = writeln!(body, "<li>{}</li>", escape_html(warning));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    let has_diagram = !doc.rules.is_empty();
+    if has_diagram {
+        let _ = writeln!(
+            body,
+            "<h2>Dependencies</h2>\n<pre class=\"mermaid\">\n{}</pre>",
+            escape_html(&dependency_diagram(doc))
+        );
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Evaluation of {}</title>\n<style>\n{}{}</style>\n</head>\n<body>\n{}{}</body>\n</html>\n",
+        escape_html(&response.doc_name),
+        STYLE,
+        REPORT_STYLE,
+        body,
+        if has_diagram { MERMAID_SCRIPT } else { "" }
+    )
+}
+
+const REPORT_STYLE: &str =
+    ".vetoes { border-left: 4px solid #d73a49; background: #ffeef0; padding: 0.25rem 1rem; }
+tr.veto td { background: #ffeef0; }
+details { margin: 0.5rem 0; }
+summary { cursor: pointer; }
+";
+
+fn trace_anchor(rule_name: &str) -> String {
+    format!("trace-{}", rule_name.replace('.', "-"))
+}
+
+/// The result of a rule in words, as `lemma run` shows it
+fn outcome(result: &RuleResult, formatter: &Formatter) -> String {
+    if let Some(value) = &result.result {
+        formatter.format_value(value)
+    } else if let Some(message) = &result.veto_message {
+        format!("Veto: {}", message)
+    } else if let Some(missing) = &result.missing_facts {
+        format!("Missing facts: {}", missing.join(", "))
+    } else if let Some(reason) = &result.unknown {
+        format!("Unknown ({})", reason)
+    } else if let Some(limit) = &result.limit_exceeded {
+        format!("Not evaluated ({} exceeded)", limit)
+    } else {
+        "No result".to_string()
+    }
+}
+
+/// Name, value and origin of the document's facts, then of other given facts
+fn fact_rows(
+    doc: &LemmaDoc,
+    given: &[LemmaFact],
+    formatter: &Formatter,
+) -> Vec<(String, String, &'static str)> {
+    let mut rows = Vec::new();
+    for fact in &doc.facts {
+        let overridden = given.iter().find(|g| g.fact_type == fact.fact_type);
+        let (value, from) = match (overridden, &fact.value) {
+            (Some(given), _) => (fact_value(&given.value, formatter), "given"),
+            (None, FactValue::Literal(value)) => (formatter.format_value(value), "document"),
+            (None, FactValue::TypeAnnotation(annotation)) => {
+                (format!("[{}]", annotation), "<strong>missing</strong>")
+            }
+            (None, value) => (fact_value(value, formatter), "document"),
+        };
+        let value = if fact.sensitive {
+            lemma::REDACTED.to_string()
+        } else {
+            value
+        };
+        rows.push((fact_display_name(fact), value, from));
+    }
+    for fact in given {
+        if !doc.facts.iter().any(|f| f.fact_type == fact.fact_type) {
+            rows.push((
+                fact_display_name(fact),
+                fact_value(&fact.value, formatter),
+                "given",
+            ));
+        }
+    }
+    rows
+}
+
+fn fact_value(value: &FactValue, formatter: &Formatter) -> String {
+    match value {
+        FactValue::Literal(value) => formatter.format_value(value),
+        FactValue::DocumentReference(name) => format!("doc {}", name),
+        FactValue::DocumentCollection(name) => format!("many doc {}", name),
+        FactValue::TypeAnnotation(annotation) => format!("[{}]", annotation),
+    }
+}
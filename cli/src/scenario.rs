@@ -0,0 +1,329 @@
+//! Scenario files for repeatable runs
+//!
+//! A `.lemma-scenario` file holds one or more scenarios. Each evaluates a
+//! document with fact overrides and lists the results it expects:
+//!
+//! ```text
+//! # Heavy parcels pay the international rate
+//! scenario "heavy international parcel"
+//! doc shipping
+//! given weight = 12 kilograms
+//! given destination = "international"
+//! expect rate = 25 USD
+//! expect total = 31.5 USD within 0.01
+//! expect valid = veto "Package too heavy for shipping"
+//! ```
+//!
+//! Expected values are Lemma literals. `within` allows an absolute difference
+//! (`within 0.01`) or a relative one (`within 1%`). `veto` without a message
+//! matches any veto.
+
+use anyhow::{anyhow, bail, Result};
+use lemma::evaluator::units::convert_unit;
+use lemma::{Engine, LiteralValue, RuleResult};
+use rust_decimal::Decimal;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// File extension of scenario files
+pub const EXTENSION: &str = "lemma-scenario";
+
+#[derive(Debug, Clone)]
+pub struct Scenario {
+    pub name: String,
+    pub doc: String,
+    /// Fact overrides, e.g. `weight=12 kilograms`
+    pub facts: Vec<String>,
+    pub expectations: Vec<Expectation>,
+    /// Where the scenario is declared, as `file:line`
+    pub location: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    pub rule: String,
+    pub expected: Expected,
+    pub tolerance: Option<Tolerance>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    Value(LiteralValue),
+    /// A veto, with the message it must have
+    Veto(Option<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+    /// Maximum difference in the result's unit
+    Absolute(Decimal),
+    /// Maximum difference as a percentage of the expected value
+    Relative(Decimal),
+}
+
+/// The outcome of checking one expectation
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub scenario: String,
+    pub rule: String,
+    pub expected: String,
+    pub actual: String,
+    pub passed: bool,
+}
+
+/// Collect scenario files under `paths`, sorted
+pub fn collect_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_file() {
+            files.push(path.clone());
+            continue;
+        }
+        for entry in WalkDir::new(path) {
+            let entry = entry?;
+            if entry.path().extension().and_then(|s| s.to_str()) == Some(EXTENSION) {
+                files.push(entry.path().to_path_buf());
+            }
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// Read and parse a scenario file
+pub fn load(path: &Path) -> Result<Vec<Scenario>> {
+    let content = std::fs::read_to_string(path)?;
+    parse(&content, &path.to_string_lossy())
+}
+
+/// Parse the scenarios in `content`, read from `source`
+pub fn parse(content: &str, source: &str) -> Result<Vec<Scenario>> {
+    let mut scenarios: Vec<Scenario> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let location = format!("{}:{}", source, index + 1);
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if keyword == "scenario" {
+            scenarios.push(Scenario {
+                name: unquote(rest)
+                    .ok_or_else(|| anyhow!("{}: expected a quoted scenario name", location))?,
+                doc: String::new(),
+                facts: Vec::new(),
+                expectations: Vec::new(),
+                location,
+            });
+            continue;
+        }
+
+        let scenario = scenarios
+            .last_mut()
+            .ok_or_else(|| anyhow!("{}: expected 'scenario \"name\"' first", location))?;
+        match keyword {
+            "doc" if !rest.is_empty() => scenario.doc = rest.to_string(),
+            "given" if rest.contains('=') => {
+                let (name, value) = rest.split_once('=').unwrap_or_default();
+                scenario
+                    .facts
+                    .push(format!("{}={}", name.trim(), value.trim()));
+            }
+            "expect" => scenario
+                .expectations
+                .push(parse_expectation(rest).map_err(|e| anyhow!("{}: {}", location, e))?),
+            _ => bail!(
+                "{}: expected 'doc <name>', 'given <fact> = <value>' or 'expect <rule> = <value>'",
+                location
+            ),
+        }
+    }
+
+    for scenario in &scenarios {
+        if scenario.doc.is_empty() {
+            bail!("{}: scenario has no 'doc' line", scenario.location);
+        }
+    }
+    Ok(scenarios)
+}
+
+fn parse_expectation(input: &str) -> Result<Expectation> {
+    let (rule, value) = input
+        .split_once('=')
+        .ok_or_else(|| anyhow!("expected 'expect <rule> = <value>'"))?;
+    let rule = rule.trim().trim_end_matches('?').to_string();
+    let value = value.trim();
+
+    if let Some(message) = value.strip_prefix("veto") {
+        let message = message.trim();
+        let expected = if message.is_empty() {
+            Expected::Veto(None)
+        } else {
+            Expected::Veto(Some(
+                unquote(message).ok_or_else(|| anyhow!("expected a quoted veto message"))?,
+            ))
+        };
+        return Ok(Expectation {
+            rule,
+            expected,
+            tolerance: None,
+        });
+    }
+
+    let (value, tolerance) = match value.rsplit_once(" within ") {
+        Some((value, tolerance)) => (value, Some(parse_tolerance(tolerance)?)),
+        None => (value, None),
+    };
+    let expected = lemma::parse_literal(value)
+        .map_err(|_| anyhow!("'{}' is not a Lemma value", value.trim()))?;
+
+    Ok(Expectation {
+        rule,
+        expected: Expected::Value(expected),
+        tolerance,
+    })
+}
+
+fn parse_tolerance(input: &str) -> Result<Tolerance> {
+    match lemma::parse_literal(input) {
+        Ok(LiteralValue::Number(n)) => Ok(Tolerance::Absolute(n.abs())),
+        Ok(LiteralValue::Percentage(p)) => Ok(Tolerance::Relative(p.abs())),
+        _ => bail!(
+            "tolerance '{}' must be a number or a percentage",
+            input.trim()
+        ),
+    }
+}
+
+fn unquote(input: &str) -> Option<String> {
+    input
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .map(str::to_string)
+}
+
+/// Evaluate a scenario and check each of its expectations
+pub fn run(engine: &Engine, scenario: &Scenario) -> Vec<Outcome> {
+    let failure = |rule: &str, expected: String, error: String| Outcome {
+        scenario: scenario.name.clone(),
+        rule: rule.to_string(),
+        expected,
+        actual: error,
+        passed: false,
+    };
+
+    let fact_refs: Vec<&str> = scenario.facts.iter().map(String::as_str).collect();
+    let response = engine
+        .parse_facts(&scenario.doc, &fact_refs)
+        .and_then(|facts| engine.evaluate(&scenario.doc, None, Some(facts)));
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => return vec![failure("", String::new(), e.to_string())],
+    };
+
+    scenario
+        .expectations
+        .iter()
+        .map(|expectation| {
+            let expected = describe_expected(expectation);
+            match response
+                .results
+                .iter()
+                .find(|r| r.rule_name == expectation.rule)
+            {
+                Some(result) => Outcome {
+                    scenario: scenario.name.clone(),
+                    rule: expectation.rule.clone(),
+                    expected,
+                    actual: describe_actual(result),
+                    passed: matches(expectation, result),
+                },
+                None => failure(
+                    &expectation.rule,
+                    expected,
+                    format!(
+                        "rule '{}' not found in '{}'",
+                        expectation.rule, scenario.doc
+                    ),
+                ),
+            }
+        })
+        .collect()
+}
+
+fn matches(expectation: &Expectation, result: &RuleResult) -> bool {
+    match (&expectation.expected, &result.result) {
+        (Expected::Veto(message), None) => {
+            result.missing_facts.is_none()
+                && message
+                    .as_ref()
+                    .is_none_or(|m| result.veto_message.as_ref() == Some(m))
+        }
+        (Expected::Value(expected), Some(actual)) => {
+            expected == actual || within(expected, actual, expectation.tolerance)
+        }
+        _ => false,
+    }
+}
+
+/// Whether two values are equal up to unit conversion and tolerance
+fn within(expected: &LiteralValue, actual: &LiteralValue, tolerance: Option<Tolerance>) -> bool {
+    let expected = match (expected, actual) {
+        (LiteralValue::Unit(e), LiteralValue::Unit(a)) if e.same_category(a) => {
+            match convert_unit(expected, &a.conversion_target()) {
+                // Converting a unit to its unit type yields a plain number
+                Ok(LiteralValue::Number(n)) => n,
+                _ => return false,
+            }
+        }
+        (LiteralValue::Number(e), LiteralValue::Number(_))
+        | (LiteralValue::Percentage(e), LiteralValue::Percentage(_)) => *e,
+        _ => return false,
+    };
+    let actual = match actual {
+        LiteralValue::Number(n) | LiteralValue::Percentage(n) => *n,
+        LiteralValue::Unit(unit) => unit.value(),
+        _ => return false,
+    };
+
+    let difference = (expected - actual).abs();
+    match tolerance {
+        None => difference.is_zero(),
+        Some(Tolerance::Absolute(max)) => difference <= max,
+        Some(Tolerance::Relative(percent)) => {
+            difference <= expected.abs() * percent / Decimal::from(100)
+        }
+    }
+}
+
+fn describe_expected(expectation: &Expectation) -> String {
+    let value = match &expectation.expected {
+        Expected::Value(value) => value.to_string(),
+        Expected::Veto(Some(message)) => format!("veto \"{}\"", message),
+        Expected::Veto(None) => "veto".to_string(),
+    };
+    match expectation.tolerance {
+        Some(Tolerance::Absolute(max)) => format!("{} ± {}", value, max),
+        Some(Tolerance::Relative(percent)) => format!("{} ± {}%", value, percent),
+        None => value,
+    }
+}
+
+fn describe_actual(result: &RuleResult) -> String {
+    if let Some(reason) = &result.unknown {
+        return format!("unknown ({})", reason);
+    }
+    if let Some(limit) = &result.limit_exceeded {
+        return format!("not evaluated ({} exceeded)", limit);
+    }
+    match (&result.result, &result.missing_facts, &result.veto_message) {
+        (Some(value), _, _) => value.to_string(),
+        (None, Some(missing), _) => format!("missing facts: {}", missing.join(", ")),
+        (None, None, Some(message)) => format!("veto \"{}\"", message),
+        (None, None, None) => "veto".to_string(),
+    }
+}
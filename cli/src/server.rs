@@ -1,69 +1,1271 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Settings of `lemma server`, one per command-line flag
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    pub host: String,
+    pub port: u16,
+    /// Leave `internal` rules out of responses and reject requests for them
+    pub public_rules_only: bool,
+    /// Shadow document per document, see `--shadow`
+    pub shadows: HashMap<String, String>,
+    pub otlp_endpoint: Option<String>,
+    /// How long responses are kept for their `Idempotency-Key`, zero turns keys off
+    pub idempotency_ttl: Duration,
+    /// Most evaluations running at once
+    pub workers: usize,
+    /// Most evaluations waiting for a worker
+    pub queue: usize,
+}
+
 #[cfg(feature = "server")]
 pub mod http {
+    use super::ServerOptions;
     use axum::{
-        extract::{Path, Query, State},
-        http::StatusCode,
+        body::Bytes,
+        extract::{FromRef, Path, Query, State},
+        http::{
+            header::{
+                ACCEPT, ACCEPT_LANGUAGE, CONTENT_TYPE, ETAG, HOST, IF_MATCH, RETRY_AFTER, VARY,
+            },
+            HeaderMap, HeaderValue, StatusCode, Uri,
+        },
         response::{IntoResponse, Json},
         routing::{get, post},
         Router,
     };
-    use lemma::{Engine, Response};
+    use http_body_util::Full;
+    use hyper_util::rt::TokioIo;
+    use lemma::error::ErrorDetails;
+    use lemma::otel::{self, OtelSpan, TraceContext};
+    use lemma::{
+        CancellationToken, Diagnostic, Engine, EvaluationOptions, FactValue, LemmaDoc, LemmaError,
+        LemmaFact, LemmaRule, LimitExceeded, OperationRecord, Response, Span, TraceLevel,
+        TypedValue,
+    };
+    use serde::de::DeserializeOwned;
     use serde::{Deserialize, Serialize};
 
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap, VecDeque};
+    use std::hash::{DefaultHasher, Hash, Hasher};
     use std::net::SocketAddr;
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
+    use std::path::{Path as FsPath, PathBuf};
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use tokio::sync::mpsc::{self, error::TrySendError};
+    use tokio::sync::{OnceCell, RwLock, Semaphore};
+    use tower_http::compression::CompressionLayer;
     use tower_http::cors::CorsLayer;
-    use tracing::{error, info};
+    use tower_http::decompression::RequestDecompressionLayer;
+    use tower_http::trace::{DefaultOnResponse, TraceLayer};
+    use tracing::{error, info, warn, Level};
 
     type SharedEngine = Arc<RwLock<Engine>>;
 
+    const MSGPACK: &str = "application/msgpack";
+
+    /// Operations returned in one response unless `max_ops` says otherwise
+    const DEFAULT_MAX_OPS: usize = 1000;
+
+    /// Number of truncated traces kept for paging, oldest are dropped first
+    const STORED_TRACES: usize = 100;
+
+    /// Number of idempotency keys kept, oldest are dropped first
+    const STORED_IDEMPOTENCY_KEYS: usize = 10_000;
+
+    /// Longest `Idempotency-Key` accepted
+    const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+    /// Seconds a client is told to wait when every worker and queue slot is taken
+    const RETRY_AFTER_SECONDS: u64 = 1;
+
+    /// Evaluations whose spans wait for the exporter; spans beyond that are dropped
+    const OTLP_QUEUED_EVALUATIONS: usize = 1024;
+
+    /// Spans that are sent without waiting for the rest of the interval
+    const OTLP_BATCH_SPANS: usize = 512;
+
+    /// Longest time spans wait to be sent
+    const OTLP_BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+    /// Longest time one export request to the collector may take
+    const OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[derive(Clone)]
+    struct AppState {
+        engine: SharedEngine,
+        traces: Arc<TraceStore>,
+        workspace: Arc<RwLock<WorkspaceStatus>>,
+        access: RuleAccess,
+        shadows: Shadows,
+        otlp: Otlp,
+        idempotency: Arc<IdempotencyCache>,
+        pool: Arc<EvaluationPool>,
+    }
+
+    /// Shadow document per document, set by `lemma server --shadow DOC=SHADOW`
+    ///
+    /// Evaluating a document also evaluates its shadow with the same facts,
+    /// see `EvaluationOptions::with_shadow`. Differences are logged; clients
+    /// only get the results of the document they asked for.
+    #[derive(Debug, Clone, Default)]
+    struct Shadows(Arc<HashMap<String, String>>);
+
+    impl Shadows {
+        /// Log where the shadow of an evaluation differs from the primary
+        fn log(response: &Response) {
+            let Some(shadow) = &response.shadow else {
+                return;
+            };
+            if let Some(error) = &shadow.error {
+                warn!(
+                    document = %response.doc_name,
+                    shadow = %shadow.doc_name,
+                    "Shadow evaluation failed: {}",
+                    error
+                );
+            }
+            for diff in &shadow.diffs {
+                let outcome = |value: &Option<lemma::LiteralValue>, veto: &Option<String>| match (
+                    value, veto,
+                ) {
+                    (Some(value), _) => value.to_string(),
+                    (None, Some(message)) => format!("veto \"{}\"", message),
+                    (None, None) => "no value".to_string(),
+                };
+                info!(
+                    document = %response.doc_name,
+                    shadow = %shadow.doc_name,
+                    rule = %diff.rule_name,
+                    primary_result = %outcome(&diff.primary, &diff.primary_veto),
+                    shadow_result = %outcome(&diff.shadow, &diff.shadow_veto),
+                    "Shadow result differs"
+                );
+            }
+        }
+    }
+
+    /// Collector evaluations are exported to, set by `lemma server --otlp-endpoint`
+    #[derive(Clone, Default)]
+    struct Otlp(Option<Arc<OtlpExporter>>);
+
+    impl Otlp {
+        /// Queue the spans of `response` for the exporter, see `lemma::otel`
+        ///
+        /// Spans carry the operations of the trace the request asked for.
+        fn export(&self, response: &Response, context: &TraceContext, started_unix_nano: u64) {
+            let Some(exporter) = &self.0 else {
+                return;
+            };
+            if !context.sampled {
+                return;
+            }
+            let spans = otel::evaluation_spans(response, context, started_unix_nano, unix_nano());
+            match exporter.spans.try_send(spans) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => warn!(
+                    "Span export queue for {} is full, dropped the spans of an evaluation",
+                    exporter.endpoint
+                ),
+                Err(TrySendError::Closed(_)) => {
+                    warn!("Span exporter for {} has stopped", exporter.endpoint)
+                }
+            }
+        }
+    }
+
+    /// Queue of spans waiting to be sent to a collector
+    ///
+    /// Evaluations put their spans in a bounded queue; one task takes them out
+    /// and sends them in batches, see `Collector::run`. When the collector
+    /// can't keep up and the queue fills, spans are dropped with a warning
+    /// instead of piling up.
+    struct OtlpExporter {
+        endpoint: String,
+        spans: mpsc::Sender<Vec<OtelSpan>>,
+    }
+
+    impl OtlpExporter {
+        /// Start exporting to `endpoint`; must be called within the runtime
+        fn start(endpoint: &str) -> anyhow::Result<Self> {
+            let collector = Collector::new(endpoint)?;
+            let (spans, queued) = mpsc::channel(OTLP_QUEUED_EVALUATIONS);
+            tokio::spawn(collector.run(queued));
+            Ok(Self {
+                endpoint: endpoint.to_string(),
+                spans,
+            })
+        }
+    }
+
+    /// Posts OTLP/HTTP JSON to a collector over plain HTTP
+    ///
+    /// Collectors usually run next to the server, so TLS is left to them.
+    struct Collector {
+        endpoint: String,
+        /// `host:port` to connect to
+        address: String,
+        host: String,
+        path: String,
+    }
+
+    impl Collector {
+        /// Collector at `http://host[:port][/path]`; the path defaults to `/v1/traces`
+        fn new(endpoint: &str) -> anyhow::Result<Self> {
+            let rest = endpoint.strip_prefix("http://").ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid --otlp-endpoint '{}': expected an http:// URL, e.g. http://localhost:4318",
+                    endpoint
+                )
+            })?;
+            let (host, path) = match rest.find('/') {
+                Some(i) => (&rest[..i], rest[i..].trim_end_matches('/')),
+                None => (rest, ""),
+            };
+            if host.is_empty() {
+                anyhow::bail!("Invalid --otlp-endpoint '{}': missing host", endpoint);
+            }
+            let path = if path.ends_with("/v1/traces") {
+                path.to_string()
+            } else {
+                format!("{}/v1/traces", path)
+            };
+            let address = if host.contains(':') {
+                host.to_string()
+            } else {
+                format!("{}:4318", host)
+            };
+            Ok(Self {
+                endpoint: endpoint.to_string(),
+                address,
+                host: host.to_string(),
+                path,
+            })
+        }
+
+        /// Send queued spans every `OTLP_BATCH_INTERVAL`, or once `OTLP_BATCH_SPANS` are waiting
+        async fn run(self, mut queued: mpsc::Receiver<Vec<OtelSpan>>) {
+            let mut batch = Vec::new();
+            let mut interval = tokio::time::interval(OTLP_BATCH_INTERVAL);
+            loop {
+                tokio::select! {
+                    spans = queued.recv() => match spans {
+                        Some(spans) => {
+                            batch.extend(spans);
+                            if batch.len() < OTLP_BATCH_SPANS {
+                                continue;
+                            }
+                        }
+                        None => {
+                            self.flush(&mut batch).await;
+                            return;
+                        }
+                    },
+                    _ = interval.tick() => {}
+                }
+                self.flush(&mut batch).await;
+            }
+        }
+
+        async fn flush(&self, batch: &mut Vec<OtelSpan>) {
+            if batch.is_empty() {
+                return;
+            }
+            let body = otel::export_request(batch, "lemma").to_string();
+            let count = batch.len();
+            batch.clear();
+            match tokio::time::timeout(OTLP_EXPORT_TIMEOUT, self.send(body)).await {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => warn!(
+                    "Failed to export {} spans to {}: {}",
+                    count, self.endpoint, e
+                ),
+                Err(_) => warn!(
+                    "Exporting {} spans to {} timed out after {} seconds",
+                    count,
+                    self.endpoint,
+                    OTLP_EXPORT_TIMEOUT.as_secs()
+                ),
+            }
+        }
+
+        async fn send(&self, body: String) -> anyhow::Result<()> {
+            let stream = tokio::net::TcpStream::connect(&self.address).await?;
+            let (mut sender, connection) =
+                hyper::client::conn::http1::handshake(TokioIo::new(stream)).await?;
+            let request = hyper::Request::post(&self.path)
+                .header(HOST, &self.host)
+                .header(CONTENT_TYPE, "application/json")
+                .body(Full::new(Bytes::from(body)))?;
+
+            // The connection is driven here rather than on a task of its own
+            tokio::select! {
+                response = sender.send_request(request) => {
+                    let status = response?.status();
+                    if status.is_success() {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("collector responded with status {}", status))
+                    }
+                }
+                Err(e) = connection => Err(e.into()),
+            }
+        }
+    }
+
+    /// The trace of the incoming request, or a new one without `traceparent`
+    fn trace_context(headers: &HeaderMap) -> TraceContext {
+        headers
+            .get("traceparent")
+            .and_then(|value| value.to_str().ok())
+            .and_then(TraceContext::from_traceparent)
+            .unwrap_or_else(TraceContext::new_trace)
+    }
+
+    fn unix_nano() -> u64 {
+        chrono::Utc::now()
+            .timestamp_nanos_opt()
+            .and_then(|nanos| u64::try_from(nanos).ok())
+            .unwrap_or(0)
+    }
+
+    /// Which rules clients see, set by `lemma server --public-rules-only`
+    #[derive(Debug, Clone, Copy)]
+    struct RuleAccess {
+        /// Leave `internal` rules out of responses and reject requests for them
+        public_only: bool,
+    }
+
+    impl RuleAccess {
+        /// Whether clients may see `rule`
+        fn exposes(self, rule: &LemmaRule) -> bool {
+            !(self.public_only && rule.is_internal())
+        }
+
+        /// Cap a full trace at the rule results, whose operations would show internal values
+        fn limit_trace(self, mut trace: TraceOptions) -> TraceOptions {
+            if self.public_only && trace.mode == TraceMode::Full {
+                trace.mode = TraceMode::Summary;
+            }
+            trace
+        }
+    }
+
+    /// State of the loaded workspace, reported by `/healthz` and `/readyz`
+    ///
+    /// Replaced with the engine when the workspace is reloaded.
+    #[derive(Debug)]
+    struct WorkspaceStatus {
+        loaded_at: chrono::DateTime<chrono::Utc>,
+        /// Why the workspace failed to load and validate, empty when it didn't
+        errors: Vec<String>,
+        warnings: Vec<String>,
+    }
+
+    impl WorkspaceStatus {
+        /// Status of `engine` after loading the workspace gave `loaded`
+        fn new(engine: &Engine, loaded: &lemma::LemmaResult<()>) -> Self {
+            let mut names = engine.list_documents();
+            names.sort();
+            let warnings = names
+                .iter()
+                .flat_map(|name| engine.document_warnings(name).to_vec())
+                .collect();
+            let errors = match loaded {
+                Ok(()) => Vec::new(),
+                Err(LemmaError::MultipleErrors(errors)) => {
+                    errors.iter().map(ToString::to_string).collect()
+                }
+                Err(error) => vec![error.to_string()],
+            };
+            Self {
+                loaded_at: chrono::Utc::now(),
+                errors,
+                warnings,
+            }
+        }
+
+        fn valid(&self) -> bool {
+            self.errors.is_empty()
+        }
+    }
+
+    impl FromRef<AppState> for SharedEngine {
+        fn from_ref(state: &AppState) -> Self {
+            state.engine.clone()
+        }
+    }
+
+    impl FromRef<AppState> for Arc<TraceStore> {
+        fn from_ref(state: &AppState) -> Self {
+            state.traces.clone()
+        }
+    }
+
+    impl FromRef<AppState> for RuleAccess {
+        fn from_ref(state: &AppState) -> Self {
+            state.access
+        }
+    }
+
+    /// How much of the operation trace an evaluation returns (`?trace=`)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum TraceMode {
+        /// No operations (default)
+        None,
+        /// Only the final result of each rule
+        Summary,
+        /// Every fact, rule reference and operation
+        Full,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct TraceOptions {
+        mode: TraceMode,
+        max_ops: usize,
+    }
+
+    impl TraceOptions {
+        /// Take `trace` and `max_ops` out of the query parameters
+        ///
+        /// What remains are the fact overrides.
+        fn from_query(params: &mut HashMap<String, String>) -> Result<Self, ApiError> {
+            let mode = match params.remove("trace").as_deref() {
+                None | Some("none") => TraceMode::None,
+                Some("summary") => TraceMode::Summary,
+                Some("full") => TraceMode::Full,
+                Some(other) => {
+                    return Err(ApiError::new(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_query",
+                        format!(
+                            "Unknown trace mode '{}', expected none, summary or full",
+                            other
+                        ),
+                    ))
+                }
+            };
+            let max_ops = match params.remove("max_ops") {
+                None => DEFAULT_MAX_OPS,
+                Some(value) => value.parse().map_err(|_| {
+                    ApiError::new(
+                        StatusCode::BAD_REQUEST,
+                        "invalid_query",
+                        format!("max_ops must be a non-negative integer, got '{}'", value),
+                    )
+                })?,
+            };
+            Ok(Self { mode, max_ops })
+        }
+
+        fn trace_level(&self) -> TraceLevel {
+            match self.mode {
+                TraceMode::None => TraceLevel::Off,
+                TraceMode::Summary => TraceLevel::RuleResultsOnly,
+                TraceMode::Full => TraceLevel::Full,
+            }
+        }
+
+        /// The operations of a rule clients asked for
+        fn shown(&self, operations: &[OperationRecord]) -> Vec<OperationRecord> {
+            match self.mode {
+                TraceMode::None => Vec::new(),
+                TraceMode::Summary => operations
+                    .iter()
+                    .filter(|operation| matches!(operation, OperationRecord::FinalResult { .. }))
+                    .cloned()
+                    .collect(),
+                TraceMode::Full => operations.to_vec(),
+            }
+        }
+    }
+
+    /// Take `date_format` out of the query parameters, see `lemma::DateFormat`
+    ///
+    /// None leaves dates to the `--date-format` the server was started with.
+    fn date_format_from_query(
+        params: &mut HashMap<String, String>,
+    ) -> Result<Option<lemma::DateFormat>, ApiError> {
+        params
+            .remove("date_format")
+            .map(|format| {
+                format.parse::<lemma::DateFormat>().map_err(|message| {
+                    ApiError::new(StatusCode::BAD_REQUEST, "invalid_query", message)
+                })
+            })
+            .transpose()
+    }
+
+    /// The `date_format` field of a request body, see `date_format_from_query`
+    fn date_format_from_body(format: Option<&str>) -> Result<Option<lemma::DateFormat>, ApiError> {
+        format
+            .map(|format| {
+                format.parse::<lemma::DateFormat>().map_err(|message| {
+                    ApiError::new(StatusCode::BAD_REQUEST, "invalid_body", message)
+                })
+            })
+            .transpose()
+    }
+
+    /// An operation of the trace, with the rule it belongs to
+    #[derive(Debug, Clone, Serialize)]
+    struct TracedOperation {
+        rule: String,
+        #[serde(flatten)]
+        operation: OperationRecord,
+    }
+
+    /// Responses of evaluations sent with an `Idempotency-Key` header
+    ///
+    /// A retry with the same key gets the kept response without evaluating
+    /// again, so a client can safely retry a decision it didn't hear back
+    /// about. The key is tied to the request it came with: reusing it for a
+    /// different path, query, body, `Accept-Language` or `If-Match` is
+    /// rejected. The entry for a key is made before evaluating, so a retry
+    /// arriving while the first request still evaluates waits for its
+    /// response. Only successful evaluations are kept, for `ttl`.
+    struct IdempotencyCache {
+        ttl: Duration,
+        entries: Mutex<HashMap<String, IdempotentEvaluation>>,
+    }
+
+    /// The evaluation for one key, running until `response` is set
+    struct IdempotentEvaluation {
+        fingerprint: u64,
+        started_at: Instant,
+        response: Arc<OnceCell<KeptResponse>>,
+    }
+
+    struct KeptResponse {
+        stored_at: Instant,
+        response: Response,
+    }
+
+    impl IdempotencyCache {
+        fn new(ttl: Duration) -> Self {
+            Self {
+                ttl,
+                entries: Mutex::new(HashMap::new()),
+            }
+        }
+
+        /// The request's `Idempotency-Key`, None without one or when keys are off
+        fn key(&self, headers: &HeaderMap) -> Result<Option<String>, ApiError> {
+            let Some(value) = headers.get("idempotency-key") else {
+                return Ok(None);
+            };
+            let key = value.to_str().unwrap_or_default().trim();
+            if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+                return Err(ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_idempotency_key",
+                    format!(
+                        "Idempotency-Key must be 1 to {} visible ASCII characters",
+                        MAX_IDEMPOTENCY_KEY_LENGTH
+                    ),
+                ));
+            }
+            Ok((!self.ttl.is_zero()).then(|| key.to_string()))
+        }
+
+        /// What makes two requests with the same key the same request
+        fn fingerprint(uri: &Uri, headers: &HeaderMap, body: &[u8]) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            uri.hash(&mut hasher);
+            for name in [ACCEPT_LANGUAGE, IF_MATCH] {
+                headers
+                    .get(name)
+                    .map(HeaderValue::as_bytes)
+                    .hash(&mut hasher);
+            }
+            body.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        /// The response for `key`: kept, still being evaluated, or to evaluate now
+        ///
+        /// Checking for the key and claiming it happen under one lock, so of
+        /// two requests with a new key only one evaluates.
+        fn evaluation(
+            &self,
+            key: &str,
+            fingerprint: u64,
+        ) -> Result<Arc<OnceCell<KeptResponse>>, ApiError> {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            entries.retain(|_, entry| {
+                entry
+                    .response
+                    .get()
+                    .is_none_or(|kept| kept.stored_at.elapsed() < self.ttl)
+            });
+            match entries.get(key) {
+                Some(entry) if entry.fingerprint == fingerprint => {
+                    return Ok(entry.response.clone());
+                }
+                Some(_) => {
+                    return Err(ApiError::new(
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "idempotency_key_reused",
+                        format!(
+                            "Idempotency-Key '{}' was already used for a different request",
+                            key
+                        ),
+                    ))
+                }
+                None => {}
+            }
+
+            if entries.len() >= STORED_IDEMPOTENCY_KEYS {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.started_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+            let response = Arc::new(OnceCell::new());
+            entries.insert(
+                key.to_string(),
+                IdempotentEvaluation {
+                    fingerprint,
+                    started_at: Instant::now(),
+                    response: response.clone(),
+                },
+            );
+            Ok(response)
+        }
+
+        /// Free `key` after its evaluation failed, unless a retry has since succeeded
+        fn forget_failed(&self, key: &str, response: &Arc<OnceCell<KeptResponse>>) {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            if entries.get(key).is_some_and(|entry| {
+                Arc::ptr_eq(&entry.response, response) && !entry.response.initialized()
+            }) {
+                entries.remove(key);
+            }
+        }
+    }
+
+    /// Bounds the evaluations running at once and those waiting for a worker
+    ///
+    /// Evaluations run on tokio's blocking threads. Without a bound, a burst of
+    /// heavy requests takes them all and piles up, and health checks and
+    /// readiness probes wait behind it. At most `workers` evaluations run, at
+    /// most `queue` more wait; any request beyond that gets a 429 with
+    /// `Retry-After`.
+    struct EvaluationPool {
+        workers: Arc<Semaphore>,
+        queue: usize,
+        waiting: AtomicUsize,
+    }
+
+    impl EvaluationPool {
+        fn new(workers: usize, queue: usize) -> Self {
+            Self {
+                workers: Arc::new(Semaphore::new(workers.max(1))),
+                queue,
+                waiting: AtomicUsize::new(0),
+            }
+        }
+
+        /// Run an evaluation once a worker is free, see `cancel_on_disconnect`
+        ///
+        /// The worker stays taken until the evaluation ends, also when the
+        /// client disconnected and the evaluation is being cancelled.
+        async fn run<T: Send + 'static>(
+            &self,
+            evaluate: impl FnOnce(CancellationToken) -> T + Send + 'static,
+        ) -> Result<T, ApiError> {
+            let worker = match self.workers.clone().try_acquire_owned() {
+                Ok(worker) => worker,
+                Err(_) => {
+                    if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.queue {
+                        self.waiting.fetch_sub(1, Ordering::SeqCst);
+                        warn!("Evaluation rejected: all workers busy and the queue is full");
+                        return Err(ApiError(
+                            StatusCode::TOO_MANY_REQUESTS,
+                            Box::new(Problem {
+                                retry_after: Some(RETRY_AFTER_SECONDS),
+                                ..Problem::new(
+                                    StatusCode::TOO_MANY_REQUESTS,
+                                    "server_busy",
+                                    "All evaluation workers are busy and the queue is full, retry later"
+                                        .to_string(),
+                                )
+                            }),
+                        ));
+                    }
+                    // Leaves the queue also when the client disconnects while waiting
+                    let _waiting = LeaveQueue(&self.waiting);
+                    self.workers.clone().acquire_owned().await.map_err(|e| {
+                        error!("Evaluation pool closed: {}", e);
+                        ApiError::new(
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            "evaluation_failed",
+                            "The server is shutting down".to_string(),
+                        )
+                    })?
+                }
+            };
+            cancel_on_disconnect(move |token| {
+                let _worker = worker;
+                evaluate(token)
+            })
+            .await
+        }
+    }
+
+    struct LeaveQueue<'a>(&'a AtomicUsize);
+
+    impl Drop for LeaveQueue<'_> {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Full traces of evaluations whose response was cut off at `max_ops`
+    #[derive(Default)]
+    struct TraceStore {
+        traces: Mutex<VecDeque<(String, Arc<Vec<TracedOperation>>)>>,
+    }
+
+    impl TraceStore {
+        /// Keep `operations` under a random 128-bit ID
+        ///
+        /// Anyone with the ID can page through the trace, so it must not be
+        /// guessable from the time or from other IDs.
+        fn insert(&self, operations: Vec<TracedOperation>) -> String {
+            let mut bytes = [0u8; 16];
+            getrandom::fill(&mut bytes).expect("the operating system has no random source");
+            let id: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let mut traces = self.traces.lock().unwrap_or_else(|e| e.into_inner());
+            if traces.len() >= STORED_TRACES {
+                traces.pop_front();
+            }
+            traces.push_back((id.clone(), Arc::new(operations)));
+            id
+        }
+
+        fn get(&self, id: &str) -> Option<Arc<Vec<TracedOperation>>> {
+            let traces = self.traces.lock().unwrap_or_else(|e| e.into_inner());
+            traces
+                .iter()
+                .find(|(stored, _)| stored == id)
+                .map(|(_, operations)| operations.clone())
+        }
+    }
+
+    /// Encoding of an evaluate request or response body
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Format {
+        Json,
+        MsgPack,
+    }
+
+    impl Format {
+        fn from_media_type(media_type: &str) -> Option<Self> {
+            let essence = media_type.split(';').next().unwrap_or("").trim();
+            if essence.eq_ignore_ascii_case("application/json") {
+                Some(Format::Json)
+            } else if [
+                "application/msgpack",
+                "application/x-msgpack",
+                "application/vnd.msgpack",
+            ]
+            .iter()
+            .any(|m| essence.eq_ignore_ascii_case(m))
+            {
+                Some(Format::MsgPack)
+            } else {
+                None
+            }
+        }
+
+        /// Format of the request body from `Content-Type`, JSON when absent
+        fn of_request(headers: &HeaderMap) -> Result<Self, ApiError> {
+            let Some(content_type) = headers.get(CONTENT_TYPE) else {
+                return Ok(Format::Json);
+            };
+            content_type
+                .to_str()
+                .ok()
+                .and_then(Format::from_media_type)
+                .ok_or_else(|| {
+                    ApiError::new(
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                        "unsupported_media_type",
+                        format!(
+                            "Expected Content-Type application/json or {}, got {:?}",
+                            MSGPACK, content_type
+                        ),
+                    )
+                })
+        }
+
+        /// Format of the response: MsgPack when `Accept` prefers it at least as much as JSON
+        fn of_response(headers: &HeaderMap) -> Self {
+            let Some(accept) = headers.get(ACCEPT).and_then(|value| value.to_str().ok()) else {
+                return Format::Json;
+            };
+
+            let (mut json, mut msgpack) = (0.0_f32, 0.0_f32);
+            for entry in accept.split(',') {
+                let mut parts = entry.split(';');
+                let media_type = parts.next().unwrap_or("");
+                let quality = parts
+                    .filter_map(|param| param.trim().strip_prefix("q="))
+                    .find_map(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                match Format::from_media_type(media_type) {
+                    Some(Format::Json) => json = json.max(quality),
+                    Some(Format::MsgPack) => msgpack = msgpack.max(quality),
+                    None => {}
+                }
+            }
+
+            if msgpack > 0.0 && msgpack >= json {
+                Format::MsgPack
+            } else {
+                Format::Json
+            }
+        }
+    }
+
+    /// A response body in the format negotiated with `Accept`
+    struct Negotiated<T>(Format, T);
+
+    impl<T: Serialize> IntoResponse for Negotiated<T> {
+        fn into_response(self) -> axum::response::Response {
+            let mut response = match self.0 {
+                Format::Json => Json(self.1).into_response(),
+                Format::MsgPack => match lemma::serializers::encode_msgpack(&self.1) {
+                    Ok(bytes) => {
+                        ([(CONTENT_TYPE, HeaderValue::from_static(MSGPACK))], bytes).into_response()
+                    }
+                    Err(e) => {
+                        error!("Failed to encode response: {}", e);
+                        return ApiError::new(
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            "encoding_failed",
+                            e.to_string(),
+                        )
+                        .into_response();
+                    }
+                },
+            };
+            response
+                .headers_mut()
+                .insert(VARY, HeaderValue::from_static("accept"));
+            response
+        }
+    }
+
     #[derive(Debug, Deserialize)]
     struct EvaluateRequest {
         code: String,
+        /// The document to evaluate, the first in `code` by default
+        #[serde(default)]
+        document: Option<String>,
+        /// The rules to return, all by default
+        #[serde(default)]
+        rules: Option<Vec<String>>,
+        /// Fact values as JSON, converted to the declared types, see
+        /// `Engine::parse_json_facts`
+        #[serde(default)]
+        facts: HashMap<String, serde_json::Value>,
+        /// How dates in `facts` are written, e.g. `day-month-year`; the
+        /// server's `--date-format` by default
+        #[serde(default)]
+        date_format: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct EvaluateManyRequest {
+        documents: Vec<String>,
         #[serde(default)]
         facts: HashMap<String, serde_json::Value>,
+        /// How dates in `facts` are written, see `EvaluateRequest::date_format`
+        #[serde(default)]
+        date_format: Option<String>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct EvaluateManyResponse {
+        documents: BTreeMap<String, EvaluateResponse>,
     }
 
     #[derive(Debug, Serialize)]
     struct EvaluateResponse {
+        /// Version of the evaluated document, see `Engine::document_version`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        doc_version: Option<String>,
         results: Vec<RuleResultJson>,
         warnings: Vec<String>,
+        diagnostics: Vec<Diagnostic>,
+        /// Where to page through the rest of a trace cut off at `max_ops`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        trace: Option<TraceLink>,
+        /// The resource limit that cut the evaluation short, see `Response::limit_exceeded`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit_exceeded: Option<LimitExceeded>,
     }
 
     #[derive(Debug, Serialize)]
     struct RuleResultJson {
         name: String,
+        /// The result tagged with its `kind`, see `TypedValue`
         #[serde(skip_serializing_if = "Option::is_none")]
-        value: Option<String>,
+        value: Option<TypedValue>,
+        /// The result as Lemma would print it, e.g. `100.00 USD`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        display: Option<String>,
         #[serde(skip_serializing_if = "Option::is_none")]
         veto_reason: Option<String>,
+        /// The rule's `"""..."""` description
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<String>,
+        /// Why the rule has no value, under `on division by zero unknown`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        unknown: Option<String>,
+        /// The resource limit hit before the rule was evaluated
+        #[serde(skip_serializing_if = "Option::is_none")]
+        limit_exceeded: Option<String>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        operations: Vec<OperationRecord>,
     }
 
     #[derive(Debug, Serialize)]
-    struct ErrorResponse {
-        error: String,
+    struct TraceLink {
+        id: String,
+        /// Operations in the whole trace
+        total: usize,
+        /// Operations included in the results
+        returned: usize,
+        next: String,
     }
 
-    pub async fn start_server(engine: Engine, host: &str, port: u16) -> anyhow::Result<()> {
-        tracing_subscriber::fmt()
-            .with_env_filter(
-                tracing_subscriber::EnvFilter::try_from_default_env()
-                    .unwrap_or_else(|_| "lemma=info,tower_http=info".into()),
-            )
-            .init();
+    #[derive(Debug, Deserialize)]
+    struct TracePageQuery {
+        #[serde(default)]
+        offset: usize,
+        limit: Option<usize>,
+    }
 
-        let shared_engine = Arc::new(RwLock::new(engine));
+    #[derive(Debug, Serialize)]
+    struct TracePage {
+        id: String,
+        total: usize,
+        offset: usize,
+        operations: Vec<TracedOperation>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        next_offset: Option<usize>,
+    }
 
-        let app = Router::new()
-            .route("/health", get(health_check))
-            .route("/evaluate/:doc_name", get(evaluate_get))
-            .route("/evaluate", post(evaluate_post))
-            .layer(CorsLayer::permissive())
-            .with_state(shared_engine);
+    /// Error body following RFC 9457, served as `application/problem+json`
+    #[derive(Debug, Serialize)]
+    struct Problem {
+        title: String,
+        status: u16,
+        detail: String,
+        /// Stable identifier for the kind of error, e.g. `semantic_error`
+        code: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        doc: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        line: Option<usize>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        column: Option<usize>,
+        /// The source lines the error points at
+        #[serde(skip_serializing_if = "Option::is_none")]
+        snippet: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        suggestion: Option<String>,
+        /// Per-fact problems, so forms can highlight the offending input
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        fields: Vec<FieldError>,
+        /// The individual problems when several errors were collected
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        errors: Vec<Problem>,
+        /// Seconds to wait before retrying, also sent as `Retry-After`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+    }
+
+    /// A problem together with the status it is served with
+    #[derive(Debug)]
+    struct ApiError(StatusCode, Box<Problem>);
+
+    #[derive(Debug, Serialize)]
+    struct FieldError {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        fact: Option<String>,
+        input: String,
+        position: usize,
+        expected: Vec<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        declared_type: Option<String>,
+        message: String,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct WorkspaceStatusJson {
+        status: &'static str,
+        ready: bool,
+        version: &'static str,
+        documents: usize,
+        loaded_at: String,
+        validation: ValidationJson,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct ValidationJson {
+        valid: bool,
+        errors: Vec<String>,
+        warnings: Vec<String>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct DocumentListResponse {
+        documents: Vec<DocumentSummaryJson>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct DocumentSummaryJson {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commentary: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        facts: usize,
+        rules: usize,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct DocumentJson {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commentary: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        version: Option<String>,
+        start_line: usize,
+        facts: Vec<FactJson>,
+        rules: Vec<RuleJson>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct FactJson {
+        name: String,
+        /// One of `value`, `type`, `document` or `collection`
+        kind: &'static str,
+        #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+        lemma_type: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        value: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        document: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        span: Option<SpanJson>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct RuleJson {
+        name: String,
+        /// The rule as written, including unless clauses
+        source: String,
+        unless_clauses: usize,
+        depends_on: DependenciesJson,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        span: Option<SpanJson>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct DependenciesJson {
+        facts: Vec<String>,
+        rules: Vec<String>,
+    }
+
+    #[derive(Debug, Serialize)]
+    struct SpanJson {
+        start: usize,
+        end: usize,
+        line: usize,
+        col: usize,
+    }
 
-        let addr: SocketAddr = format!("{}:{}", host, port).parse()?;
+    impl From<&Span> for SpanJson {
+        fn from(span: &Span) -> Self {
+            Self {
+                start: span.start,
+                end: span.end,
+                line: span.line,
+                col: span.col,
+            }
+        }
+    }
+
+    impl Problem {
+        fn new(status: StatusCode, code: &'static str, detail: String) -> Self {
+            Self {
+                title: status.canonical_reason().unwrap_or("Error").to_string(),
+                status: status.as_u16(),
+                detail,
+                code,
+                doc: None,
+                source: None,
+                line: None,
+                column: None,
+                snippet: None,
+                suggestion: None,
+                fields: Vec::new(),
+                errors: Vec::new(),
+                retry_after: None,
+            }
+        }
+
+        fn from_error(status: StatusCode, error: &LemmaError) -> Self {
+            match error {
+                LemmaError::Parse(details)
+                | LemmaError::Semantic(details)
+                | LemmaError::Runtime(details) => {
+                    let code = match error {
+                        LemmaError::Parse(_) => "parse_error",
+                        LemmaError::Semantic(_) => "semantic_error",
+                        _ => "runtime_error",
+                    };
+                    Self {
+                        // Syntax errors aren't attributed to a document
+                        doc: Some(details.doc_name.clone()).filter(|name| name != "<parse-error>"),
+                        source: Some(details.source_id.clone()),
+                        line: Some(details.span.line),
+                        column: Some(details.span.col),
+                        snippet: snippet(details),
+                        suggestion: details.suggestion.clone(),
+                        ..Self::new(status, code, details.message.clone())
+                    }
+                }
+                LemmaError::Engine(msg) => Self::new(status, "engine_error", msg.clone()),
+                LemmaError::CircularDependency(msg) => {
+                    Self::new(status, "circular_dependency", msg.clone())
+                }
+                LemmaError::ResourceLimitExceeded {
+                    limit_name,
+                    limit_value,
+                    actual_value,
+                    suggestion,
+                } => Self {
+                    suggestion: Some(suggestion.clone()),
+                    ..Self::new(
+                        status,
+                        "resource_limit_exceeded",
+                        format!(
+                            "Resource limit exceeded: {} (limit: {}, actual: {})",
+                            limit_name, limit_value, actual_value
+                        ),
+                    )
+                },
+                LemmaError::FactParse(errors) => Self {
+                    fields: errors
+                        .iter()
+                        .map(|e| FieldError {
+                            fact: e.fact.clone(),
+                            input: e.input.clone(),
+                            position: e.position,
+                            expected: e.expected.clone(),
+                            declared_type: e.declared_type.as_ref().map(|t| t.to_string()),
+                            message: e.message.clone(),
+                        })
+                        .collect(),
+                    ..Self::new(status, "invalid_facts", error.to_string())
+                },
+                LemmaError::VersionMismatch { doc_name, .. } => Self {
+                    doc: Some(doc_name.clone()),
+                    ..Self::new(status, "version_mismatch", error.to_string())
+                },
+                LemmaError::UnknownResult(_) => {
+                    Self::new(status, "unknown_result", error.to_string())
+                }
+                LemmaError::Cancelled { .. } => Self::new(status, "cancelled", error.to_string()),
+                LemmaError::MultipleErrors(errors) => Self {
+                    errors: errors.iter().map(|e| Self::from_error(status, e)).collect(),
+                    ..Self::new(
+                        status,
+                        "multiple_errors",
+                        format!("{} errors occurred", errors.len()),
+                    )
+                },
+            }
+        }
+    }
+
+    impl ApiError {
+        fn new(status: StatusCode, code: &'static str, detail: String) -> Self {
+            Self(status, Box::new(Problem::new(status, code, detail)))
+        }
+    }
+
+    impl IntoResponse for ApiError {
+        fn into_response(self) -> axum::response::Response {
+            let retry_after = self.1.retry_after;
+            let mut response = (self.0, Json(*self.1)).into_response();
+            response.headers_mut().insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/problem+json"),
+            );
+            if let Some(seconds) = retry_after {
+                response
+                    .headers_mut()
+                    .insert(RETRY_AFTER, HeaderValue::from(seconds));
+            }
+            response
+        }
+    }
+
+    /// Log a failed step and turn the error into a problem response
+    ///
+    /// A failed `If-Match` version pin is a 412, everything else a 400.
+    fn lemma_failure(context: &'static str, error: LemmaError) -> ApiError {
+        error!("{}: {}", context, error);
+        let status = match error {
+            LemmaError::VersionMismatch { .. } => StatusCode::PRECONDITION_FAILED,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        ApiError(status, Box::new(Problem::from_error(status, &error)))
+    }
+
+    /// The whole source lines covered by an error's span
+    fn snippet(details: &ErrorDetails) -> Option<String> {
+        let text = details.source_text.as_ref();
+        let start = details.span.start.min(text.len());
+        let end = details.span.end.clamp(start, text.len());
+        let line_start = text.get(..start)?.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = text.get(end..)?.find('\n').map_or(text.len(), |i| end + i);
+        let snippet = text[line_start..line_end].trim_end_matches('\r');
+        (!snippet.trim().is_empty()).then(|| snippet.to_string())
+    }
+
+    /// Serve `engine`, whose workspace in `workdir` loaded with result `loaded`
+    ///
+    /// A workspace that failed to load still starts the server, which then
+    /// reports the errors and is not ready. `SIGHUP` reloads the workspace.
+    pub async fn start_server(
+        engine: Engine,
+        loaded: lemma::LemmaResult<()>,
+        workdir: PathBuf,
+        options: ServerOptions,
+    ) -> anyhow::Result<()> {
+        let addr: SocketAddr = format!("{}:{}", options.host, options.port).parse()?;
+        let state = app_state(engine, loaded, options)?;
+        reload_on_hangup(state.clone(), workdir)?;
+        let app = router(state);
         info!("Lemma server listening on {}", addr);
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
@@ -72,6 +1274,105 @@ pub mod http {
         Ok(())
     }
 
+    /// Shared state of the handlers, with the evaluation pool sized by `options`
+    fn app_state(
+        engine: Engine,
+        loaded: lemma::LemmaResult<()>,
+        options: ServerOptions,
+    ) -> anyhow::Result<AppState> {
+        let otlp = match &options.otlp_endpoint {
+            Some(endpoint) => Otlp(Some(Arc::new(OtlpExporter::start(endpoint)?))),
+            None => Otlp::default(),
+        };
+        let state = AppState {
+            workspace: Arc::new(RwLock::new(WorkspaceStatus::new(&engine, &loaded))),
+            engine: Arc::new(RwLock::new(engine)),
+            traces: Arc::new(TraceStore::default()),
+            access: RuleAccess {
+                public_only: options.public_rules_only,
+            },
+            shadows: Shadows(Arc::new(options.shadows)),
+            otlp,
+            idempotency: Arc::new(IdempotencyCache::new(options.idempotency_ttl)),
+            pool: Arc::new(EvaluationPool::new(options.workers, options.queue)),
+        };
+        Ok(state)
+    }
+
+    /// Load the workspace in `workdir` again, replacing the served documents
+    ///
+    /// Like at startup, a workspace that fails to load replaces them too: the
+    /// server then reports the errors and is not ready.
+    async fn reload(state: &AppState, workdir: &FsPath) {
+        let mut engine = crate::workspace_engine();
+        let loaded = engine.load_dir(workdir);
+        if let Err(error) = &loaded {
+            error!("Failed to reload workspace: {}", error);
+        }
+        let status = WorkspaceStatus::new(&engine, &loaded);
+        let mut served = state.engine.write().await;
+        *served = engine;
+        *state.workspace.write().await = status;
+        info!(
+            "Workspace reloaded, {} document(s) loaded",
+            served.list_documents().len()
+        );
+    }
+
+    /// Reload the workspace in `workdir` whenever the process gets `SIGHUP`
+    fn reload_on_hangup(state: AppState, workdir: PathBuf) -> anyhow::Result<()> {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{signal, SignalKind};
+            let mut hangups = signal(SignalKind::hangup())?;
+            tokio::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    reload(&state, &workdir).await;
+                }
+            });
+        }
+        #[cfg(not(unix))]
+        let _ = (state, workdir);
+        Ok(())
+    }
+
+    /// All endpoints of the server over `state`
+    fn router(state: AppState) -> Router {
+        Router::new()
+            .route("/health", get(health_check))
+            .route("/healthz", get(healthz))
+            .route("/readyz", get(readyz))
+            .route("/docs", get(list_docs))
+            .route("/docs/:doc_name", get(get_doc))
+            .route("/docs/:doc_name/schema", get(get_doc_schema))
+            .route("/evaluate/:doc_name", get(evaluate_get))
+            .route("/evaluate", post(evaluate_post))
+            .route("/evaluate-many", post(evaluate_many))
+            .route("/evaluations/:id/trace", get(evaluation_trace))
+            .layer(RequestDecompressionLayer::new())
+            .layer(CompressionLayer::new())
+            .layer(CorsLayer::permissive())
+            .layer(
+                TraceLayer::new_for_http()
+                    .make_span_with(request_span)
+                    .on_response(DefaultOnResponse::new().level(Level::INFO)),
+            )
+            .with_state(state)
+    }
+
+    /// Span around everything logged while handling a request
+    ///
+    /// The ID is unique per server process, to tell concurrent requests apart.
+    fn request_span<B>(request: &axum::http::Request<B>) -> tracing::Span {
+        static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+        tracing::info_span!(
+            "request",
+            id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+            method = %request.method(),
+            path = %request.uri().path(),
+        )
+    }
+
     async fn health_check() -> impl IntoResponse {
         Json(serde_json::json!({
             "status": "ok",
@@ -80,174 +1381,803 @@ pub mod http {
         }))
     }
 
-    async fn evaluate_get(
+    /// Liveness: the server is up, whatever the state of the workspace
+    async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+        Json(workspace_status(&state).await)
+    }
+
+    /// Readiness: the workspace loaded and has documents to evaluate
+    async fn readyz(State(state): State<AppState>) -> impl IntoResponse {
+        let status = workspace_status(&state).await;
+        let code = if status.ready {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        (code, Json(status))
+    }
+
+    async fn workspace_status(state: &AppState) -> WorkspaceStatusJson {
+        let documents = state.engine.read().await.list_documents().len();
+        let workspace = state.workspace.read().await;
+        let ready = workspace.valid() && documents > 0;
+        WorkspaceStatusJson {
+            status: if ready { "ready" } else { "not_ready" },
+            ready,
+            version: env!("CARGO_PKG_VERSION"),
+            documents,
+            loaded_at: workspace.loaded_at.to_rfc3339(),
+            validation: ValidationJson {
+                valid: workspace.valid(),
+                errors: workspace.errors.clone(),
+                warnings: workspace.warnings.clone(),
+            },
+        }
+    }
+
+    async fn list_docs(
+        State(engine): State<SharedEngine>,
+        State(access): State<RuleAccess>,
+    ) -> impl IntoResponse {
+        let engine = engine.read().await;
+        let mut names = engine.list_documents();
+        names.sort();
+
+        let documents = names
+            .iter()
+            .filter_map(|name| engine.get_document(name))
+            .map(|doc| DocumentSummaryJson {
+                name: doc.name.clone(),
+                commentary: doc.commentary.clone(),
+                source: doc.source.clone(),
+                version: engine.document_version(&doc.name).map(str::to_string),
+                facts: doc.facts.len(),
+                rules: doc.rules.iter().filter(|rule| access.exposes(rule)).count(),
+            })
+            .collect();
+
+        Json(DocumentListResponse { documents })
+    }
+
+    async fn get_doc(
         State(engine): State<SharedEngine>,
+        State(access): State<RuleAccess>,
         Path(doc_name): Path<String>,
-        Query(params): Query<HashMap<String, String>>,
-    ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    ) -> Result<impl IntoResponse, ApiError> {
         let engine = engine.read().await;
+        let doc = engine.get_document(&doc_name).ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                "document_not_found",
+                format!("Document '{}' not found", doc_name),
+            )
+        })?;
 
+        Ok(Json(DocumentJson {
+            name: doc.name.clone(),
+            commentary: doc.commentary.clone(),
+            source: doc.source.clone(),
+            version: engine.document_version(&doc_name).map(str::to_string),
+            start_line: doc.start_line,
+            facts: engine
+                .get_document_facts(&doc_name)
+                .into_iter()
+                .map(|fact| convert_fact(fact, doc, engine.get_all_documents()))
+                .collect(),
+            rules: engine
+                .get_document_rules(&doc_name)
+                .into_iter()
+                .filter(|rule| access.exposes(rule))
+                .map(convert_rule)
+                .collect(),
+        }))
+    }
+
+    async fn get_doc_schema(
+        State(engine): State<SharedEngine>,
+        Path(doc_name): Path<String>,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let engine = engine.read().await;
         if engine.get_document(&doc_name).is_none() {
-            return Err((
+            return Err(ApiError::new(
                 StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: format!("Document '{}' not found", doc_name),
-                }),
+                "document_not_found",
+                format!("Document '{}' not found", doc_name),
+            ));
+        }
+        let schema = engine
+            .fact_schema(&doc_name)
+            .map_err(|e| lemma_failure("Failed to build schema", e))?;
+
+        Ok(([(CONTENT_TYPE, "application/schema+json")], Json(schema)))
+    }
+
+    fn convert_fact(
+        fact: &LemmaFact,
+        doc: &LemmaDoc,
+        documents: &HashMap<String, LemmaDoc>,
+    ) -> FactJson {
+        let name = lemma::analysis::fact_display_name(fact);
+        let reference: Vec<String> = name.split('.').map(str::to_string).collect();
+        let lemma_type =
+            lemma::analysis::declared_fact_type(doc, &reference, documents).map(|t| t.to_string());
+
+        let (kind, value, document) = match &fact.value {
+            FactValue::Literal(_) if fact.sensitive => {
+                ("value", Some(lemma::REDACTED.to_string()), None)
+            }
+            FactValue::Literal(lit) => ("value", Some(lit.to_string()), None),
+            FactValue::TypeAnnotation(_) => ("type", None, None),
+            FactValue::DocumentReference(target) => ("document", None, Some(target.clone())),
+            FactValue::DocumentCollection(target) => ("collection", None, Some(target.clone())),
+        };
+
+        FactJson {
+            name,
+            kind,
+            lemma_type,
+            value,
+            document,
+            span: fact.span.as_ref().map(SpanJson::from),
+        }
+    }
+
+    fn convert_rule(rule: &LemmaRule) -> RuleJson {
+        let mut facts = std::collections::BTreeSet::new();
+        let mut rules = std::collections::BTreeSet::new();
+        let expressions = std::iter::once(&rule.expression).chain(
+            rule.unless_clauses
+                .iter()
+                .flat_map(|clause| [&clause.condition, &clause.result]),
+        );
+        for expression in expressions {
+            let references = lemma::analysis::extract_references(expression);
+            facts.extend(references.facts.iter().map(|f| f.to_string()));
+            rules.extend(references.rules.iter().map(|r| r.join(".")));
+        }
+
+        RuleJson {
+            name: rule.name.clone(),
+            source: rule.to_string().trim_end().to_string(),
+            unless_clauses: rule.unless_clauses.len(),
+            depends_on: DependenciesJson {
+                facts: facts.into_iter().collect(),
+                rules: rules.into_iter().collect(),
+            },
+            span: rule.span.as_ref().map(SpanJson::from),
+        }
+    }
+
+    /// Build evaluation options from request headers
+    ///
+    /// The languages in `Accept-Language` select the veto message locale, most preferred first.
+    /// `If-Match` pins the document version, `*` matches any version.
+    fn evaluation_options(headers: &HeaderMap, access: RuleAccess) -> EvaluationOptions {
+        let locales = headers
+            .get(ACCEPT_LANGUAGE)
+            .and_then(|value| value.to_str().ok())
+            .map(lemma::localization::parse_accept_language)
+            .unwrap_or_default();
+
+        let pinned_version = headers
+            .get(IF_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().trim_start_matches("W/").trim_matches('"'))
+            .filter(|value| !value.is_empty() && *value != "*")
+            .map(str::to_string);
+
+        EvaluationOptions {
+            locales,
+            pinned_version,
+            public_rules_only: access.public_only,
+            ..EvaluationOptions::default()
+        }
+    }
+
+    async fn evaluate_get(
+        State(state): State<AppState>,
+        Path(doc_name): Path<String>,
+        Query(mut params): Query<HashMap<String, String>>,
+        headers: HeaderMap,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let AppState {
+            engine,
+            traces,
+            access,
+            shadows,
+            otlp,
+            pool,
+            ..
+        } = state;
+        let trace = access.limit_trace(TraceOptions::from_query(&mut params)?);
+        let date_format = date_format_from_query(&mut params)?;
+        let context = trace_context(&headers);
+        let started = unix_nano();
+        let engine = engine.read_owned().await;
+
+        if engine.get_document(&doc_name).is_none() {
+            return Err(ApiError::new(
+                StatusCode::NOT_FOUND,
+                "document_not_found",
+                format!("Document '{}' not found", doc_name),
             ));
         }
 
         let facts: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
         let fact_refs: Vec<&str> = facts.iter().map(|s| s.as_str()).collect();
         let parsed_facts = if !fact_refs.is_empty() {
-            match lemma::parse_facts(&fact_refs) {
-                Ok(f) => Some(f),
-                Err(e) => {
-                    error!("Failed to parse facts: {}", e);
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        Json(ErrorResponse {
-                            error: format!("Failed to parse facts: {}", e),
-                        }),
-                    ));
-                }
-            }
+            let parsed = match date_format {
+                Some(format) => engine.parse_facts_with_date_format(&doc_name, &fact_refs, format),
+                None => engine.parse_facts(&doc_name, &fact_refs),
+            };
+            Some(parsed.map_err(|e| lemma_failure("Failed to parse facts", e))?)
         } else {
             None
         };
 
-        let response: Response = engine
-            .evaluate(&doc_name, None, parsed_facts)
-            .map_err(|e| {
-                error!("Evaluation failed: {}", e);
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse {
-                        error: format!("Evaluation failed: {}", e),
-                    }),
-                )
-            })?;
+        let options = EvaluationOptions {
+            trace_level: trace.trace_level(),
+            shadow: shadows.0.get(&doc_name).cloned(),
+            ..evaluation_options(&headers, access)
+        };
+        let evaluated_doc = doc_name.clone();
+        let response: Response = pool
+            .run(move |token| {
+                engine.evaluate_cancellable(&evaluated_doc, None, parsed_facts, &options, &token)
+            })
+            .await?
+            .map_err(|e| lemma_failure("Evaluation failed", e))?;
+        Shadows::log(&response);
+        otlp.export(&response, &context, started);
 
-        let results = convert_results(&response);
+        let (results, trace_link) = convert_results(&response, trace, &traces);
         info!(
             "Evaluated document '{}' with {} results",
             doc_name,
             results.len()
         );
 
-        Ok(Json(EvaluateResponse {
-            results,
-            warnings: response.warnings,
-        }))
+        let etag = response
+            .doc_version
+            .as_ref()
+            .and_then(|version| HeaderValue::from_str(&format!("\"{}\"", version)).ok());
+        let mut http_response = Negotiated(
+            Format::of_response(&headers),
+            EvaluateResponse {
+                doc_version: response.doc_version,
+                results,
+                warnings: response.warnings,
+                diagnostics: response.diagnostics,
+                trace: trace_link,
+                limit_exceeded: response.limit_exceeded,
+            },
+        )
+        .into_response();
+        if let Some(etag) = etag {
+            http_response.headers_mut().insert(ETAG, etag);
+        }
+        Ok(http_response)
     }
 
+    /// Evaluate inline code without loading it
+    ///
+    /// The code gets the workspace's resource limits and functions, but can't
+    /// reference its documents, see `Engine::evaluate_code_with_options`.
+    /// A retry with the same `Idempotency-Key` header gets the first response,
+    /// marked `Idempotent-Replayed: true`, see `IdempotencyCache`.
     async fn evaluate_post(
-        State(_engine): State<SharedEngine>,
-        Json(payload): Json<EvaluateRequest>,
-    ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+        State(state): State<AppState>,
+        Query(mut params): Query<HashMap<String, String>>,
+        uri: Uri,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let AppState {
+            engine,
+            traces,
+            access,
+            otlp,
+            idempotency,
+            pool,
+            ..
+        } = state;
+        let trace = access.limit_trace(TraceOptions::from_query(&mut params)?);
+        let key = idempotency.key(&headers)?;
+        let fingerprint = IdempotencyCache::fingerprint(&uri, &headers, &body);
+
+        let mut response_headers = HeaderMap::new();
+        let response = match key {
+            None => evaluate_inline(&engine, &otlp, &pool, access, trace, &headers, &body).await?,
+            Some(key) => {
+                let kept = idempotency.evaluation(&key, fingerprint)?;
+                let mut evaluated = false;
+                let outcome = kept
+                    .get_or_try_init(|| {
+                        evaluated = true;
+                        async {
+                            let response = evaluate_inline(
+                                &engine, &otlp, &pool, access, trace, &headers, &body,
+                            )
+                            .await?;
+                            Ok::<_, ApiError>(KeptResponse {
+                                stored_at: Instant::now(),
+                                response,
+                            })
+                        }
+                    })
+                    .await;
+                match outcome {
+                    Ok(outcome) if evaluated => outcome.response.clone(),
+                    Ok(outcome) => {
+                        info!(
+                            "Replayed evaluation of inline document '{}' for its idempotency key",
+                            outcome.response.doc_name
+                        );
+                        response_headers
+                            .insert("idempotent-replayed", HeaderValue::from_static("true"));
+                        outcome.response.clone()
+                    }
+                    Err(e) => {
+                        idempotency.forget_failed(&key, &kept);
+                        return Err(e);
+                    }
+                }
+            }
+        };
+
+        let (results, trace_link) = convert_results(&response, trace, &traces);
+
+        Ok((
+            response_headers,
+            Negotiated(
+                Format::of_response(&headers),
+                EvaluateResponse {
+                    doc_version: response.doc_version,
+                    results,
+                    warnings: response.warnings,
+                    diagnostics: response.diagnostics,
+                    trace: trace_link,
+                    limit_exceeded: response.limit_exceeded,
+                },
+            ),
+        ))
+    }
+
+    /// Evaluate the code of an `EvaluateRequest` body
+    async fn evaluate_inline(
+        engine: &SharedEngine,
+        otlp: &Otlp,
+        pool: &EvaluationPool,
+        access: RuleAccess,
+        trace: TraceOptions,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<Response, ApiError> {
+        let context = trace_context(headers);
+        let started = unix_nano();
+        let payload: EvaluateRequest = decode_body(headers, body)?;
+        let date_format = date_format_from_body(payload.date_format.as_deref())?;
+
         if payload.code.trim().is_empty() {
-            return Err((
+            return Err(ApiError::new(
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Code cannot be empty".to_string(),
-                }),
+                "empty_code",
+                "Code cannot be empty".to_string(),
             ));
         }
 
-        let mut temp_engine = Engine::new();
-        let source_id = format!("inline_{}", chrono::Utc::now().timestamp_millis());
+        let options = EvaluationOptions {
+            trace_level: trace.trace_level(),
+            ..evaluation_options(headers, access)
+        };
+        let engine = engine.clone().read_owned().await;
+        let response: Response = pool
+            .run(move |token| {
+                let options = options.with_cancellation(token);
+                match date_format {
+                    Some(format) => engine.evaluate_code_with_json_facts_and_date_format(
+                        &payload.code,
+                        payload.document.as_deref(),
+                        payload.rules,
+                        &payload.facts,
+                        format,
+                        &options,
+                    ),
+                    None => engine.evaluate_code_with_json_facts(
+                        &payload.code,
+                        payload.document.as_deref(),
+                        payload.rules,
+                        &payload.facts,
+                        &options,
+                    ),
+                }
+            })
+            .await?
+            .map_err(|e| lemma_failure("Evaluation failed", e))?;
+        otlp.export(&response, &context, started);
 
-        temp_engine
-            .add_lemma_code(&payload.code, &source_id)
-            .map_err(|e| {
-                error!("Failed to parse code: {}", e);
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse {
-                        error: format!("Failed to parse code: {}", e),
-                    }),
-                )
-            })?;
+        info!(
+            "Evaluated inline document '{}' with {} results",
+            response.doc_name,
+            response.results.len()
+        );
+        Ok(response)
+    }
+
+    /// Cancels its token when dropped
+    struct CancelOnDrop(CancellationToken);
 
-        let documents = temp_engine.list_documents();
-        if documents.is_empty() {
-            return Err((
+    impl Drop for CancelOnDrop {
+        fn drop(&mut self) {
+            self.0.cancel();
+        }
+    }
+
+    /// Evaluate several loaded documents with one set of facts
+    ///
+    /// The facts are parsed once, each with the type of the first document
+    /// declaring it; every document gets those it declares, see
+    /// `Engine::evaluate_many_with_options`.
+    async fn evaluate_many(
+        State(state): State<AppState>,
+        Query(mut params): Query<HashMap<String, String>>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let AppState {
+            engine,
+            traces,
+            access,
+            otlp,
+            pool,
+            ..
+        } = state;
+        let trace = access.limit_trace(TraceOptions::from_query(&mut params)?);
+        let context = trace_context(&headers);
+        let started = unix_nano();
+        let payload: EvaluateManyRequest = decode_body(&headers, &body)?;
+        let date_format = date_format_from_body(payload.date_format.as_deref())?;
+        if payload.documents.is_empty() {
+            return Err(ApiError::new(
                 StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "No document found in provided code".to_string(),
-                }),
+                "no_document",
+                "List at least one document in 'documents'".to_string(),
             ));
         }
 
-        let doc_name = &documents[0];
-
-        let facts: Vec<String> = payload
-            .facts
+        let engine = engine.read_owned().await;
+        if let Some(missing) = payload
+            .documents
             .iter()
-            .map(|(k, v)| format!("{}={}", k, json_value_to_lemma(v)))
-            .collect();
-        let fact_refs: Vec<&str> = facts.iter().map(|s| s.as_str()).collect();
-        let parsed_facts = if !fact_refs.is_empty() {
-            match lemma::parse_facts(&fact_refs) {
-                Ok(f) => Some(f),
-                Err(e) => {
-                    error!("Failed to parse facts: {}", e);
-                    return Err((
-                        StatusCode::BAD_REQUEST,
-                        Json(ErrorResponse {
-                            error: format!("Failed to parse facts: {}", e),
-                        }),
-                    ));
-                }
-            }
+            .find(|name| engine.get_document(name).is_none())
+        {
+            return Err(ApiError::new(
+                StatusCode::NOT_FOUND,
+                "document_not_found",
+                format!("Document '{}' not found", missing),
+            ));
+        }
+
+        let parsed_facts = if !payload.facts.is_empty() {
+            let doc_names: Vec<&str> = payload.documents.iter().map(String::as_str).collect();
+            let parsed = match date_format {
+                Some(format) => engine.parse_json_facts_many_with_date_format(
+                    &doc_names,
+                    &payload.facts,
+                    format,
+                ),
+                None => engine.parse_json_facts_many(&doc_names, &payload.facts),
+            };
+            Some(parsed.map_err(|e| lemma_failure("Failed to parse facts", e))?)
         } else {
             None
         };
 
-        let response: Response =
-            temp_engine
-                .evaluate(doc_name, None, parsed_facts)
-                .map_err(|e| {
-                    error!("Evaluation failed: {}", e);
-                    (
-                        StatusCode::BAD_REQUEST,
-                        Json(ErrorResponse {
-                            error: format!("Evaluation failed: {}", e),
-                        }),
-                    )
-                })?;
+        // A single `If-Match` version can't pin several documents
+        let options = EvaluationOptions {
+            trace_level: trace.trace_level(),
+            pinned_version: None,
+            ..evaluation_options(&headers, access)
+        };
+        let doc_names = payload.documents.clone();
+        let responses = pool
+            .run(move |token| {
+                let doc_names: Vec<&str> = doc_names.iter().map(String::as_str).collect();
+                engine.evaluate_many_with_options(
+                    &doc_names,
+                    parsed_facts,
+                    &options.with_cancellation(token),
+                )
+            })
+            .await?
+            .map_err(|e| lemma_failure("Evaluation failed", e))?;
 
-        let results = convert_results(&response);
+        let documents: BTreeMap<String, EvaluateResponse> = responses
+            .into_iter()
+            .map(|(doc_name, response)| {
+                otlp.export(&response, &context, started);
+                let (results, trace_link) = convert_results(&response, trace, &traces);
+                let evaluated = EvaluateResponse {
+                    doc_version: response.doc_version,
+                    results,
+                    warnings: response.warnings,
+                    diagnostics: response.diagnostics,
+                    trace: trace_link,
+                    limit_exceeded: response.limit_exceeded,
+                };
+                (doc_name, evaluated)
+            })
+            .collect();
+        info!("Evaluated {} documents in one request", documents.len());
 
-        info!(
-            "Evaluated inline document '{}' with {} results",
-            doc_name,
-            results.len()
-        );
+        Ok(Negotiated(
+            Format::of_response(&headers),
+            EvaluateManyResponse { documents },
+        ))
+    }
 
-        Ok(Json(EvaluateResponse {
-            results,
-            warnings: response.warnings,
-        }))
+    /// The request body, as JSON or MessagePack per its `Content-Type`
+    fn decode_body<T: DeserializeOwned>(headers: &HeaderMap, body: &[u8]) -> Result<T, ApiError> {
+        match Format::of_request(headers)? {
+            Format::Json => serde_json::from_slice(body).map_err(|e| {
+                ApiError::new(
+                    StatusCode::BAD_REQUEST,
+                    "invalid_body",
+                    format!("JSON parse error: {}", e),
+                )
+            }),
+            Format::MsgPack => lemma::serializers::decode_msgpack(body)
+                .map_err(|e| ApiError::new(StatusCode::BAD_REQUEST, "invalid_body", e.to_string())),
+        }
     }
 
-    fn convert_results(response: &Response) -> Vec<RuleResultJson> {
-        response
+    /// Run an evaluation on the blocking thread pool
+    ///
+    /// Axum drops a handler's future when its client disconnects. The evaluation
+    /// itself can't be dropped halfway, so the dropped guard cancels it instead.
+    async fn cancel_on_disconnect<T: Send + 'static>(
+        evaluate: impl FnOnce(CancellationToken) -> T + Send + 'static,
+    ) -> Result<T, ApiError> {
+        let token = CancellationToken::new();
+        let _guard = CancelOnDrop(token.clone());
+        // Keep the engine's spans and events inside the request span
+        let span = tracing::Span::current();
+        tokio::task::spawn_blocking(move || span.in_scope(|| evaluate(token)))
+            .await
+            .map_err(|e| {
+                error!("Evaluation task failed: {}", e);
+                ApiError::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "evaluation_failed",
+                    "Evaluation stopped unexpectedly".to_string(),
+                )
+            })
+    }
+
+    /// Results with their operations, at most `max_ops` in total
+    ///
+    /// A longer trace is kept in `traces` and linked, so the rest can be paged
+    /// through with `GET /evaluations/{id}/trace`.
+    fn convert_results(
+        response: &Response,
+        trace: TraceOptions,
+        traces: &TraceStore,
+    ) -> (Vec<RuleResultJson>, Option<TraceLink>) {
+        let shown: Vec<Vec<OperationRecord>> = response
             .results
             .iter()
-            .map(|r| RuleResultJson {
-                name: r.rule_name.clone(),
-                value: r.result.as_ref().map(|v| v.to_string()),
-                veto_reason: r.veto_message.clone(),
+            .map(|r| trace.shown(&r.operations))
+            .collect();
+        let total: usize = shown.iter().map(Vec::len).sum();
+        let mut remaining = trace.max_ops;
+
+        let results = response
+            .results
+            .iter()
+            .zip(&shown)
+            .map(|(r, operations)| {
+                let count = operations.len().min(remaining);
+                remaining -= count;
+                RuleResultJson {
+                    name: r.rule_name.clone(),
+                    value: r.result.as_ref().map(TypedValue::from),
+                    display: r.result.as_ref().map(|v| v.to_string()),
+                    veto_reason: r.veto_message.clone(),
+                    description: r.description.clone(),
+                    unknown: r.unknown.clone(),
+                    limit_exceeded: r.limit_exceeded.clone(),
+                    operations: operations[..count].to_vec(),
+                }
             })
-            .collect()
+            .collect();
+
+        if total <= trace.max_ops {
+            return (results, None);
+        }
+
+        let operations = response
+            .results
+            .iter()
+            .zip(&shown)
+            .flat_map(|(r, operations)| {
+                operations.iter().map(|operation| TracedOperation {
+                    rule: r.rule_name.clone(),
+                    operation: operation.clone(),
+                })
+            })
+            .collect();
+        let id = traces.insert(operations);
+        let link = TraceLink {
+            next: format!("/evaluations/{}/trace?offset={}", id, trace.max_ops),
+            id,
+            total,
+            returned: trace.max_ops,
+        };
+        (results, Some(link))
+    }
+
+    async fn evaluation_trace(
+        State(traces): State<Arc<TraceStore>>,
+        Path(id): Path<String>,
+        Query(query): Query<TracePageQuery>,
+        headers: HeaderMap,
+    ) -> Result<impl IntoResponse, ApiError> {
+        let operations = traces.get(&id).ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                "trace_not_found",
+                format!("No stored trace '{}', it may have expired", id),
+            )
+        })?;
+
+        let total = operations.len();
+        let offset = query.offset.min(total);
+        let end = offset
+            .saturating_add(query.limit.unwrap_or(DEFAULT_MAX_OPS))
+            .min(total);
+
+        Ok(Negotiated(
+            Format::of_response(&headers),
+            TracePage {
+                id,
+                total,
+                offset,
+                operations: operations[offset..end].to_vec(),
+                next_offset: (end < total).then_some(end),
+            },
+        ))
     }
 
-    fn json_value_to_lemma(value: &serde_json::Value) -> String {
-        match value {
-            serde_json::Value::String(s) => format!("\"{}\"", s.replace("\"", "\\\"")),
-            serde_json::Value::Number(n) => n.to_string(),
-            serde_json::Value::Bool(b) => b.to_string(),
-            _ => format!("\"{}\"", value.to_string().replace("\"", "\\\"")),
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use axum::body::Body;
+        use axum::http::Request;
+        use std::sync::mpsc as std_mpsc;
+        use tower::ServiceExt;
+
+        /// Options with one worker and no queue
+        fn one_worker_options() -> ServerOptions {
+            ServerOptions {
+                host: "127.0.0.1".to_string(),
+                port: 0,
+                public_rules_only: false,
+                shadows: HashMap::new(),
+                otlp_endpoint: None,
+                idempotency_ttl: Duration::ZERO,
+                workers: 1,
+                queue: 0,
+            }
+        }
+
+        /// State over the `quick` document, with one worker and no queue
+        fn one_worker_state() -> AppState {
+            let mut engine = Engine::new();
+            engine
+                .add_lemma_code(
+                    "doc quick\nfact x = 3\nrule double = x * 2\n",
+                    "quick.lemma",
+                )
+                .unwrap();
+            app_state(engine, Ok(()), one_worker_options()).unwrap()
+        }
+
+        /// Take the only worker with an evaluation that blocks until the returned sender is dropped
+        async fn take_worker(state: &AppState) -> std_mpsc::Sender<()> {
+            let (release, released) = std_mpsc::channel::<()>();
+            let pool = state.pool.clone();
+            tokio::spawn(async move {
+                pool.run(move |_| {
+                    let _ = released.recv();
+                })
+                .await
+            });
+            while state.pool.workers.available_permits() > 0 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            release
+        }
+
+        async fn get(state: &AppState, path: &str) -> axum::response::Response {
+            router(state.clone())
+                .oneshot(Request::get(path).body(Body::empty()).unwrap())
+                .await
+                .unwrap()
+        }
+
+        async fn body_json(response: axum::response::Response) -> serde_json::Value {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            serde_json::from_slice(&bytes).unwrap()
+        }
+
+        #[tokio::test]
+        async fn test_request_beyond_workers_and_queue_gets_429_with_retry_after() {
+            let state = one_worker_state();
+            let release = take_worker(&state).await;
+
+            let rejected = get(&state, "/evaluate/quick").await;
+            assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+            assert_eq!(rejected.headers()[RETRY_AFTER], "1");
+            assert_eq!(rejected.headers()[CONTENT_TYPE], "application/problem+json");
+            let problem = body_json(rejected).await;
+            assert_eq!(problem["code"], "server_busy");
+            assert_eq!(problem["retry_after"], 1);
+
+            drop(release);
+        }
+
+        #[tokio::test]
+        async fn test_healthz_answers_while_workers_are_busy() {
+            let state = one_worker_state();
+            let release = take_worker(&state).await;
+
+            let health = get(&state, "/healthz").await;
+            assert_eq!(health.status(), StatusCode::OK);
+            assert_eq!(body_json(health).await["ready"], true);
+
+            drop(release);
+        }
+
+        #[tokio::test]
+        async fn test_evaluations_run_again_once_the_worker_is_free() {
+            let state = one_worker_state();
+            let release = take_worker(&state).await;
+            drop(release);
+
+            while state.pool.workers.available_permits() == 0 {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+            assert_eq!(
+                get(&state, "/evaluate/quick").await.status(),
+                StatusCode::OK
+            );
+        }
+
+        #[tokio::test]
+        async fn test_reload_serves_the_changed_workspace_and_its_load_time() {
+            let workdir = tempfile::tempdir().unwrap();
+            std::fs::write(
+                workdir.path().join("quick.lemma"),
+                "doc quick\nfact x = 3\n",
+            )
+            .unwrap();
+            let mut engine = crate::workspace_engine();
+            let loaded = engine.load_dir(workdir.path());
+            let state = app_state(engine, loaded, one_worker_options()).unwrap();
+            let before = body_json(get(&state, "/healthz").await).await;
+            assert_eq!(before["documents"], 1);
+
+            std::fs::write(workdir.path().join("slow.lemma"), "doc slow\nfact y = 4\n").unwrap();
+            reload(&state, workdir.path()).await;
+
+            let after = body_json(get(&state, "/healthz").await).await;
+            assert_eq!(after["documents"], 2);
+            let loaded_at = |status: &serde_json::Value| {
+                chrono::DateTime::parse_from_rfc3339(status["loaded_at"].as_str().unwrap()).unwrap()
+            };
+            assert!(loaded_at(&after) > loaded_at(&before));
         }
     }
 }
@@ -256,8 +2186,9 @@ pub mod http {
 pub mod http {
     pub async fn start_server(
         _engine: lemma::Engine,
-        _host: &str,
-        _port: u16,
+        _loaded: lemma::LemmaResult<()>,
+        _workdir: std::path::PathBuf,
+        _options: super::ServerOptions,
     ) -> anyhow::Result<()> {
         anyhow::bail!("Server feature not enabled. Recompile with --features server")
     }
@@ -0,0 +1,86 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        r#"
+doc pricing
+fact price = 100 USD
+fact quantity = 3
+fact customer = [text]
+rule subtotal = price * quantity
+rule is_bulk = quantity > 10
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_bindgen_typescript_http() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("bindgen")
+        .arg("--lang")
+        .arg("ts")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("export interface PricingFacts {"))
+        .stdout(predicate::str::contains("  price?: string;"))
+        .stdout(predicate::str::contains("  quantity?: number;"))
+        .stdout(predicate::str::contains("  customer: string;"))
+        .stdout(predicate::str::contains(
+            "  subtotal: RuleOutcome<\"money\">;",
+        ))
+        .stdout(predicate::str::contains(
+            "  is_bulk: RuleOutcome<\"boolean\">;",
+        ))
+        .stdout(predicate::str::contains("export class PricingClient"))
+        .stdout(predicate::str::contains("export function httpTransport"))
+        .stdout(predicate::str::contains("wasmTransport").not());
+}
+
+#[test]
+fn test_bindgen_typescript_wasm_to_file() {
+    let temp_dir = workspace();
+    let output = temp_dir.path().join("lemma.ts");
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("bindgen")
+        .arg("--target")
+        .arg("wasm")
+        .arg("--dir")
+        .arg(temp_dir.path())
+        .arg("-o")
+        .arg(&output);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote bindings for 1 document(s)"));
+
+    let generated = fs::read_to_string(&output).unwrap();
+    assert!(generated.contains("export function wasmTransport"));
+    assert!(!generated.contains("httpTransport"));
+}
+
+#[test]
+fn test_bindgen_rejects_unknown_language() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("bindgen")
+        .arg("--lang")
+        .arg("cobol")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert().failure();
+}
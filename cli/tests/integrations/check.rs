@@ -0,0 +1,146 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        r#"
+doc pricing
+fact price = 100 USD
+fact quantity = 3
+rule subtotal = price * quantity
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_check_valid_workspace() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check").arg(temp_dir.path());
+
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Checked 1 file(s): no problems found",
+    ));
+}
+
+#[test]
+fn test_check_reports_every_broken_file() {
+    let temp_dir = workspace();
+    fs::write(
+        temp_dir.path().join("syntax.lemma"),
+        "doc broken\nfact = 5\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("semantic.lemma"),
+        "doc semantic\nfact base = doc missing\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check")
+        .arg(temp_dir.path())
+        .arg("--error-format")
+        .arg("short");
+
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("syntax.lemma:2:"))
+        .stdout(predicate::str::contains(
+            "semantic.lemma:2:1: error: Document reference error: 'missing' does not exist",
+        ))
+        .stderr(predicate::str::contains("2 problem(s) found"));
+}
+
+#[test]
+fn test_check_github_annotations() {
+    let temp_dir = workspace();
+    fs::write(
+        temp_dir.path().join("semantic.lemma"),
+        "doc semantic\nfact base = doc missing\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check")
+        .arg(temp_dir.path())
+        .arg("--error-format")
+        .arg("github");
+
+    cmd.assert().failure().stdout(
+        predicate::str::is_match(r"::error file=.*semantic\.lemma,line=2,col=1::").unwrap(),
+    );
+}
+
+#[test]
+fn test_check_glob_filter() {
+    let temp_dir = workspace();
+    fs::create_dir(temp_dir.path().join("drafts")).unwrap();
+    fs::write(
+        temp_dir.path().join("drafts/wip.lemma"),
+        "doc wip\nfact = \n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check")
+        .arg(temp_dir.path())
+        .arg("--glob")
+        .arg("pricing*.lemma");
+
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Checked 1 file(s)"));
+}
+
+#[test]
+fn test_check_evaluate_reports_runtime_errors() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("division.lemma"),
+        "doc division\nfact amount = 10\nfact parts = 0\nrule share = amount / parts\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check").arg(temp_dir.path());
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check")
+        .arg(temp_dir.path())
+        .arg("--evaluate")
+        .arg("--error-format")
+        .arg("short");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("division.lemma:4:"));
+}
+
+#[test]
+fn test_check_strict_units() {
+    let temp_dir = workspace();
+    fs::write(
+        temp_dir.path().join("shipping.lemma"),
+        "doc shipping\nfact rate = 2 USD\nfact weight = 5 kilograms\nrule cost = rate * weight\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check").arg(temp_dir.path());
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("check").arg(temp_dir.path()).arg("--strict-units");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::contains("money * mass"))
+        .stderr(predicate::str::contains("1 problem(s) found"));
+}
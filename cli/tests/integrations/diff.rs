@@ -0,0 +1,102 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const PRICING: &str =
+    "doc pricing\nfact price = 10\nfact quantity = 2\nrule total = price * quantity\n";
+const CHECKOUT: &str = "doc checkout\nfact pricing = doc pricing\nrule due = pricing.total?\n";
+
+fn workspace(files: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for (name, code) in files {
+        fs::write(dir.path().join(name), code).unwrap();
+    }
+    dir
+}
+
+fn diff(old: &Path, new: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("diff").arg(old).arg(new);
+    cmd
+}
+
+#[test]
+fn test_diff_changed_items_and_affected_documents() {
+    let old = workspace(&[("pricing.lemma", PRICING), ("checkout.lemma", CHECKOUT)]);
+    let new = workspace(&[
+        (
+            "pricing.lemma",
+            "doc pricing\nfact price = 10\nfact quantity = 2\nfact discount = 3\nrule total = price * quantity - discount\n",
+        ),
+        ("checkout.lemma", CHECKOUT),
+    ]);
+
+    diff(old.path(), new.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("doc pricing (changed)"))
+        .stdout(predicate::str::contains("rule total = price * quantity\n"))
+        .stdout(predicate::str::contains(
+            "rule total = price * quantity - discount",
+        ))
+        .stdout(predicate::str::contains("fact discount = 3"))
+        .stdout(predicate::str::contains("checkout (through pricing)"))
+        .stdout(predicate::str::contains("fact quantity").not());
+}
+
+#[test]
+fn test_diff_ignores_formatting() {
+    let old = workspace(&[("pricing.lemma", PRICING)]);
+    let new = workspace(&[(
+        "pricing.lemma",
+        "doc pricing\n\nfact quantity = 2\nfact price   =   10\n\nrule total =\n  price * quantity\n",
+    )]);
+
+    diff(old.path(), new.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes"));
+}
+
+#[test]
+fn test_diff_added_and_removed_documents() {
+    let old = workspace(&[("pricing.lemma", PRICING)]);
+    let new = workspace(&[("checkout.lemma", "doc checkout\nfact due = 5\n")]);
+
+    diff(old.path(), new.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("doc checkout (added)"))
+        .stdout(predicate::str::contains("doc pricing (removed)"))
+        .stdout(predicate::str::contains("rule total = price * quantity"));
+}
+
+#[test]
+fn test_diff_check_versions() {
+    let old = workspace(&[(
+        "pricing.lemma",
+        "doc pricing\nversion 1.4.0\nfact price = 10\nfact quantity = 2\nrule total = price * quantity\n",
+    )]);
+    let new = workspace(&[(
+        "pricing.lemma",
+        "doc pricing\nversion 1.5.0\nfact price = 10\nrule total = price\n",
+    )]);
+
+    diff(old.path(), new.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "pricing: major change (1.4.0 -> 1.5.0)",
+        ))
+        .stdout(predicate::str::contains(
+            "major fact 'quantity' was removed or renamed",
+        ));
+
+    diff(old.path(), new.path())
+        .arg("--check-versions")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("declare version 2.0.0"));
+}
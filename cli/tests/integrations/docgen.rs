@@ -0,0 +1,88 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        r#"
+doc pricing
+"""
+Prices for orders
+
+Quantities of 10 and more get a discount.
+"""
+fact price = 100 USD
+fact quantity = [number]
+rule subtotal = price * quantity
+rule discount = 0%
+  unless quantity >= 10 then 5%
+rule total = subtotal? - discount? """Amount due after the discount"""
+
+doc order
+fact pricing = doc pricing
+rule amount = pricing.total?
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_docgen_html() {
+    let temp_dir = workspace();
+    let out = temp_dir.path().join("site");
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("docgen")
+        .arg("--dir")
+        .arg(temp_dir.path())
+        .arg("--out")
+        .arg(&out);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote 3 page(s)"));
+
+    let index = fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(index.contains("<a href=\"pricing.html\">pricing</a>"));
+    assert!(index.contains("Prices for orders"));
+
+    let pricing = fs::read_to_string(out.join("pricing.html")).unwrap();
+    assert!(pricing.contains("Quantities of 10 and more get a discount."));
+    assert!(pricing.contains("<a id=\"fact-quantity\"></a>quantity"));
+    assert!(pricing.contains("required"));
+    assert!(pricing.contains("\n  unless quantity &gt;= 10 then "));
+    assert!(pricing.contains("<pre class=\"mermaid\">"));
+    assert!(pricing.contains("<a href=\"#rule-subtotal\">subtotal?</a>"));
+    assert!(pricing.contains("Referenced by <a href=\"order.html\">order</a>"));
+    assert!(pricing.contains("<p>Amount due after the discount</p>"));
+
+    let order = fs::read_to_string(out.join("order.html")).unwrap();
+    assert!(order.contains("<a href=\"pricing.html#rule-total\">pricing.total?</a>"));
+}
+
+#[test]
+fn test_docgen_markdown() {
+    let temp_dir = workspace();
+    let out = temp_dir.path().join("site");
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("docgen")
+        .arg("--dir")
+        .arg(temp_dir.path())
+        .arg("--out")
+        .arg(&out)
+        .arg("--format")
+        .arg("markdown");
+    cmd.assert().success();
+
+    let pricing = fs::read_to_string(out.join("pricing.md")).unwrap();
+    assert!(pricing.starts_with("[All documents](index.md)"));
+    assert!(pricing.contains("# pricing\n"));
+    assert!(pricing.contains("```mermaid\ngraph LR\n"));
+    assert!(pricing.contains("```lemma\nrule discount = "));
+    assert!(pricing.contains("\n  unless quantity >= 10 then "));
+    assert!(pricing.contains("Uses [quantity](#fact-quantity)"));
+}
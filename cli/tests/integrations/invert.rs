@@ -0,0 +1,73 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        r#"
+doc pricing
+fact quantity = [number]
+rule tier = "standard"
+  unless quantity >= 100 then "bulk"
+rule price = 10
+  unless tier? is "bulk" then 8
+"#,
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_cli_invert_with_rule_target() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("invert")
+        .arg("pricing")
+        .arg("price")
+        .arg("--where")
+        .arg("tier?bulk")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("quantity"))
+        .stdout(predicate::str::contains("[100, ∞)"));
+}
+
+#[test]
+fn test_cli_invert_rejects_malformed_rule_target() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("invert")
+        .arg("pricing")
+        .arg("price")
+        .arg("--where")
+        .arg("tier")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("expected rule?target"));
+}
+
+#[test]
+fn test_cli_invert_requires_rule_without_interactive() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("invert")
+        .arg("pricing")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("lemma invert --interactive"));
+}
@@ -1,4 +1,8 @@
 use assert_cmd::Command;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdout, Stdio};
+use tempfile::TempDir;
 
 #[test]
 fn test_mcp_server_starts() {
@@ -18,3 +22,134 @@ fn test_mcp_server_starts() {
         .success()
         .stdout(predicates::str::contains("mcp"));
 }
+
+/// A running `lemma mcp` process, talked to one line at a time
+struct McpSession {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl McpSession {
+    fn start(workdir: &std::path::Path) -> Self {
+        let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("lemma"))
+            .arg("mcp")
+            .arg("-d")
+            .arg(workdir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self { child, stdout }
+    }
+
+    fn send(&mut self, request: &str) {
+        let stdin = self.child.stdin.as_mut().unwrap();
+        writeln!(stdin, "{}", request).unwrap();
+        stdin.flush().unwrap();
+    }
+
+    /// The next JSON-RPC message, skipping the startup banner
+    fn receive(&mut self) -> String {
+        loop {
+            let mut line = String::new();
+            assert!(
+                self.stdout.read_line(&mut line).unwrap() > 0,
+                "server exited"
+            );
+            if line.starts_with('{') {
+                return line;
+            }
+        }
+    }
+}
+
+impl Drop for McpSession {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\n\"\"\"\nPrices per unit\n\"\"\"\nfact price = 10\nrule total = price * 2\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_mcp_lists_and_reads_document_resources() {
+    let temp_dir = workspace();
+    let mut session = McpSession::start(temp_dir.path());
+
+    session.send(r#"{"jsonrpc":"2.0","id":1,"method":"initialize"}"#);
+    assert!(session.receive().contains(r#""listChanged":true"#));
+
+    session.send(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#);
+    session.send(r#"{"jsonrpc":"2.0","id":2,"method":"resources/list"}"#);
+    let list = session.receive();
+    assert!(list.contains(r#""id":2"#), "{}", list);
+    assert!(list.contains("lemma://documents/pricing"), "{}", list);
+    assert!(list.contains("Prices per unit"), "{}", list);
+
+    session.send(
+        r#"{"jsonrpc":"2.0","id":3,"method":"resources/read","params":{"uri":"lemma://documents/pricing"}}"#,
+    );
+    let read = session.receive();
+    assert!(read.contains("text/x-lemma"), "{}", read);
+    assert!(read.contains("application/json"), "{}", read);
+    assert!(read.contains("rule total"), "{}", read);
+
+    session.send(
+        r#"{"jsonrpc":"2.0","id":4,"method":"resources/read","params":{"uri":"lemma://documents/missing"}}"#,
+    );
+    assert!(session.receive().contains("Unknown resource"));
+}
+
+#[test]
+fn test_mcp_notifies_subscribers_on_reload() {
+    let temp_dir = workspace();
+    let mut session = McpSession::start(temp_dir.path());
+
+    session.send(
+        r#"{"jsonrpc":"2.0","id":1,"method":"resources/subscribe","params":{"uri":"lemma://documents/pricing"}}"#,
+    );
+    assert!(session.receive().contains(r#""result":{}"#));
+
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact price = 12\nrule total = price * 2\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("shipping.lemma"),
+        "doc shipping\nfact weight = 3\n",
+    )
+    .unwrap();
+
+    let reload = r#"{"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"reload_workspace","arguments":{}}}"#;
+    session.send(reload);
+    let response = session.receive();
+    assert!(response.contains("2 changed"), "{}", response);
+    assert!(session
+        .receive()
+        .contains("notifications/resources/list_changed"));
+    let updated = session.receive();
+    assert!(
+        updated.contains("notifications/resources/updated")
+            && updated.contains("lemma://documents/pricing"),
+        "{}",
+        updated
+    );
+
+    // Nothing changed since: the next reply is the response to the next request
+    session.send(&reload.replace(r#""id":2"#, r#""id":3"#));
+    assert!(session.receive().contains("0 changed"));
+    session.send(r#"{"jsonrpc":"2.0","id":4,"method":"resources/list"}"#);
+    assert!(session.receive().contains(r#""id":4"#));
+}
@@ -1,4 +1,13 @@
+mod bindgen;
+mod check;
+mod diff;
+mod docgen;
 mod interactive;
+mod invert;
 mod mcp;
+mod rename;
 mod run;
+mod scenario;
+mod sensitivity;
 mod server;
+mod sweep;
@@ -0,0 +1,70 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact price = 10\nrule total = price * 2\n",
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join("order.lemma"),
+        "doc order\nfact item = doc pricing\nfact item.price = 12\nrule due = item.total? + 5\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_cli_rename_rewrites_files() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("rename")
+        .arg("pricing")
+        .arg("price")
+        .arg("unit_price")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Renamed 'price' to 'unit_price': 3 edit(s) in 2 file(s)",
+    ));
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("pricing.lemma")).unwrap(),
+        "doc pricing\nfact unit_price = 10\nrule total = unit_price * 2\n"
+    );
+    assert_eq!(
+        fs::read_to_string(temp_dir.path().join("order.lemma")).unwrap(),
+        "doc order\nfact item = doc pricing\nfact item.unit_price = 12\nrule due = item.total? + 5\n"
+    );
+}
+
+#[test]
+fn test_cli_rename_dry_run_leaves_files() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("rename")
+        .arg("pricing")
+        .arg("total")
+        .arg("subtotal")
+        .arg("--dry-run")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "order.lemma:4:17: total -> subtotal",
+        ))
+        .stdout(predicate::str::contains(
+            "Would rename 'total' to 'subtotal'",
+        ));
+    assert!(fs::read_to_string(temp_dir.path().join("order.lemma"))
+        .unwrap()
+        .contains("item.total?"));
+}
@@ -62,6 +62,40 @@ rule doubled = base * 2
         .stdout(predicate::str::contains("14"));
 }
 
+#[test]
+fn test_cli_run_warns_about_misspelled_override() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.lemma"),
+        "doc order\nfact quantity = [number]\nrule total = quantity * 2\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("order")
+        .arg("quantity=2")
+        .arg("quanity=5")
+        .arg("--dir")
+        .arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("4"))
+        .stderr(predicate::str::contains("did you mean 'quantity'?"));
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("order")
+        .arg("quantity=2")
+        .arg("quanity=5")
+        .arg("--strict")
+        .arg("--dir")
+        .arg(temp_dir.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("quanity"));
+}
+
 #[test]
 fn test_cli_run_nonexistent_document() {
     let temp_dir = TempDir::new().unwrap();
@@ -219,3 +253,298 @@ this is not valid lemma syntax
         .failure()
         .stderr(predicate::str::contains("error").or(predicate::str::contains("Error")));
 }
+
+#[test]
+fn test_cli_run_with_locale() {
+    let temp_dir = TempDir::new().unwrap();
+    let lemma_file = temp_dir.path().join("test.lemma");
+
+    fs::write(
+        &lemma_file,
+        r#"
+doc shipping
+fact weight = 30
+rule allowed = weight <= 25
+    unless weight > 25 then veto "too heavy" @nl "te zwaar"
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("shipping")
+        .arg("--dir")
+        .arg(temp_dir.path())
+        .arg("--locale")
+        .arg("nl");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("te zwaar"))
+        .stdout(predicate::str::contains("too heavy").not());
+}
+
+#[test]
+fn test_cli_run_reports_invalid_fact_override() {
+    let temp_dir = TempDir::new().unwrap();
+    let lemma_file = temp_dir.path().join("test.lemma");
+
+    fs::write(
+        &lemma_file,
+        r#"
+doc pricing
+fact quantity = [number]
+rule total = quantity * 2
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("pricing")
+        .arg("quantity=\"many\"")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid fact 'quantity': expected number, got text",
+        ))
+        .stderr(predicate::str::contains("declared type: number"));
+}
+
+#[test]
+fn test_cli_import_facts_from_csv() {
+    let temp_dir = TempDir::new().unwrap();
+    let csv_file = temp_dir.path().join("rates.csv");
+    let output = temp_dir.path().join("fx_rates.lemma");
+
+    fs::write(&csv_file, "currency,rate\nUSD,1.08\nGBP,0.86\n").unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("import-facts")
+        .arg(&csv_file)
+        .arg("--doc")
+        .arg("fx_rates")
+        .arg("-o")
+        .arg(&output);
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote 2 fact(s)"));
+
+    let generated = fs::read_to_string(&output).unwrap();
+    assert!(generated.contains("fact usd = 1.08"));
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("show")
+        .arg("fx_rates")
+        .arg("--dir")
+        .arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("gbp"));
+}
+
+#[test]
+fn test_cli_scaffold_from_schema() {
+    let temp_dir = TempDir::new().unwrap();
+    let schema_file = temp_dir.path().join("order.json");
+
+    fs::write(
+        &schema_file,
+        r#"{
+  "type": "object",
+  "properties": {
+    "quantity": {"type": "integer"},
+    "status": {"type": "string", "enum": ["open", "paid"]}
+  }
+}"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("scaffold")
+        .arg("--from-schema")
+        .arg(&schema_file)
+        .arg("--doc")
+        .arg("order");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("fact quantity = [number]"))
+        .stdout(predicate::str::contains("rule valid_status = status"));
+}
+
+#[test]
+fn test_cli_run_formats_money_for_locale() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.lemma"),
+        r#"
+doc invoice
+fact net = 1234.5 EUR
+rule total = net * 2
+"#,
+    )
+    .unwrap();
+
+    let run = |locale: &str| {
+        let mut cmd = Command::cargo_bin("lemma").unwrap();
+        cmd.arg("run")
+            .arg("invoice")
+            .arg("--locale")
+            .arg(locale)
+            .arg("--dir")
+            .arg(temp_dir.path());
+        cmd.assert()
+    };
+
+    run("nl")
+        .success()
+        .stdout(predicate::str::contains("€ 2.469,00"));
+    run("de")
+        .success()
+        .stdout(predicate::str::contains("2.469,00 €"));
+    run("en-US")
+        .success()
+        .stdout(predicate::str::contains("€2,469.00"));
+}
+
+#[test]
+fn test_cli_run_raw_output_is_not_localized() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.lemma"),
+        r#"
+doc invoice
+fact net = 1234.5 EUR
+rule total = net * 2
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("invoice")
+        .arg("--raw")
+        .arg("--locale")
+        .arg("nl")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("2469.0 EUR"));
+}
+
+#[test]
+fn test_cli_run_verbose_logs_rules_as_json() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.lemma"),
+        "doc logged\nfact x = 10\nrule doubled = x * 2",
+    )
+    .unwrap();
+
+    let mut quiet = Command::cargo_bin("lemma").unwrap();
+    quiet
+        .env_remove("RUST_LOG")
+        .arg("run")
+        .arg("logged")
+        .arg("--dir")
+        .arg(temp_dir.path());
+    quiet.assert().success().stderr(predicate::str::is_empty());
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.env_remove("RUST_LOG")
+        .arg("run")
+        .arg("logged")
+        .arg("--dir")
+        .arg(temp_dir.path())
+        .arg("-v")
+        .arg("--log-format")
+        .arg("json");
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("20"))
+        .stderr(predicate::str::contains(r#""message":"rule evaluated""#))
+        .stderr(predicate::str::contains(r#""rule":"doubled""#));
+}
+
+#[test]
+fn test_cli_run_html_report() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("order.lemma"),
+        r#"
+doc order
+fact quantity = [number]
+fact price = 4
+rule total = quantity * price
+rule allowed = true
+  unless quantity > 10 then veto "Too many <items>"
+"#,
+    )
+    .unwrap();
+    let report = temp_dir.path().join("report.html");
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("order")
+        .arg("quantity=12")
+        .arg("--dir")
+        .arg(temp_dir.path())
+        .arg("--report")
+        .arg(&report);
+    cmd.assert().success();
+
+    let html = fs::read_to_string(&report).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h2>Vetoes</h2>"));
+    assert!(html.contains("Too many &lt;items&gt;"));
+    assert!(html.contains("<tr class=\"veto\">"));
+    assert!(html.contains("<td><code>quantity</code></td><td>12</td><td>given</td>"));
+    assert!(html.contains("<td><code>price</code></td><td>4</td><td>document</td>"));
+    assert!(html.contains("<details id=\"trace-total\">"));
+    assert!(html.contains("<li>fact quantity = 12</li>"));
+    assert!(html.contains("<pre class=\"mermaid\">"));
+}
+
+#[test]
+fn test_cli_run_with_date_format() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("test.lemma"),
+        r#"
+doc contract
+fact start = [date]
+rule started_in_february = start >= 2026-02-01 and start < 2026-03-01
+"#,
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("contract")
+        .arg("start=14-02-2026")
+        .arg("--date-format")
+        .arg("day-month-year")
+        .arg("--dir")
+        .arg(temp_dir.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("true"));
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("run")
+        .arg("contract")
+        .arg("start=14-02-2026")
+        .arg("--dir")
+        .arg(temp_dir.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not in ISO format"));
+}
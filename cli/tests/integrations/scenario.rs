@@ -0,0 +1,101 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace(scenarios: &str) -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("shipping.lemma"),
+        r#"
+doc shipping
+fact weight = 5 kilograms
+rule rate = 10 USD
+  unless weight > 10 kilograms then 15 USD
+rule share = 30%
+rule valid = weight <= 30 kilograms
+  unless weight > 30 kilograms then veto "Package too heavy"
+"#,
+    )
+    .unwrap();
+    fs::write(temp_dir.path().join("shipping.lemma-scenario"), scenarios).unwrap();
+    temp_dir
+}
+
+fn run(temp_dir: &TempDir) -> assert_cmd::assert::Assert {
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("scenario")
+        .arg("run")
+        .arg(temp_dir.path())
+        .arg("--dir")
+        .arg(temp_dir.path());
+    cmd.assert()
+}
+
+#[test]
+fn test_scenarios_pass() {
+    let temp_dir = workspace(
+        r#"
+# Light parcels use the base rate
+scenario "light parcel"
+doc shipping
+expect rate = 10 USD
+expect share = 29.9% within 1%
+
+scenario "heavy parcel"
+doc shipping
+given weight = 35000 grams
+expect rate = 15 USD
+expect valid = veto "Package too heavy"
+"#,
+    );
+
+    run(&temp_dir)
+        .success()
+        .stdout(predicate::str::contains("light parcel"))
+        .stderr(predicate::str::contains("4 passed, 0 failed"));
+}
+
+#[test]
+fn test_scenario_failure_shows_diff() {
+    let temp_dir = workspace(
+        r#"
+scenario "wrong rate"
+doc shipping
+given weight = 12 kilograms
+expect rate = 10 USD
+expect valid = veto
+"#,
+    );
+
+    run(&temp_dir)
+        .failure()
+        .stdout(predicate::str::contains("expected 10 USD"))
+        .stdout(predicate::str::contains("actual   15 USD"))
+        .stderr(predicate::str::contains("0 passed, 2 failed"));
+}
+
+#[test]
+fn test_scenario_tolerance() {
+    let temp_dir = workspace(
+        r#"
+scenario "rounded"
+doc shipping
+expect rate = 10.004 USD within 0.01
+expect rate = 9990 USD within 1%
+"#,
+    );
+
+    run(&temp_dir)
+        .failure()
+        .stderr(predicate::str::contains("1 passed, 1 failed"));
+}
+
+#[test]
+fn test_invalid_scenario_file() {
+    let temp_dir = workspace("doc shipping\n");
+
+    run(&temp_dir).failure().stderr(predicate::str::contains(
+        "expected 'scenario \"name\"' first",
+    ));
+}
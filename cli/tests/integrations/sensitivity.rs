@@ -0,0 +1,60 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_sensitivity_report() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact quantity = 10\nfact price = 4\nfact shipping = 5\nrule total = quantity * price + shipping\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("sensitivity")
+        .arg("pricing")
+        .arg("total")
+        .arg("--of")
+        .arg("quantity,price,shipping")
+        .arg("price=6")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Sensitivity of total"))
+        .stdout(predicate::str::contains(
+            "price     at 6: 10 per unit (exact)",
+        ))
+        .stdout(predicate::str::contains(
+            "quantity  at 10: 6 per unit (exact)",
+        ))
+        .stdout(predicate::str::contains(
+            "shipping  at 5: 1 per unit (exact)",
+        ));
+}
+
+#[test]
+fn test_sensitivity_unknown_fact() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact quantity = 10\nrule total = quantity * 2\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("sensitivity")
+        .arg("pricing")
+        .arg("total")
+        .arg("--of")
+        .arg("quantiy")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("did you mean 'quantity'?"));
+}
@@ -1,4 +1,11 @@
 use assert_cmd::Command;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::process::{Child, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
 
 #[test]
 fn test_server_command_available() {
@@ -21,3 +28,1008 @@ fn test_serve_requires_dir() {
         .success()
         .stdout(predicates::str::contains("Workspace root directory"));
 }
+
+#[test]
+fn test_server_has_public_rules_only_flag() {
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("server").arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("--public-rules-only"));
+}
+
+#[test]
+fn test_server_has_idempotency_ttl_flag() {
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("server").arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("--idempotency-ttl"));
+}
+
+#[test]
+fn test_server_has_worker_pool_flags() {
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("server").arg("--help");
+
+    cmd.assert()
+        .success()
+        .stdout(predicates::str::contains("--workers"))
+        .stdout(predicates::str::contains("--queue"));
+}
+
+#[test]
+fn test_server_rejects_zero_workers() {
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("server").arg("--workers").arg("0");
+
+    cmd.assert().failure();
+}
+
+/// A running `lemma server`, talked to with plain HTTP/1.1 requests
+struct ServerProcess {
+    child: Child,
+    port: u16,
+}
+
+impl ServerProcess {
+    /// Start a server on a free port and wait until it answers `/health`
+    fn start(workdir: &Path, args: &[&str]) -> Self {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let child = std::process::Command::new(assert_cmd::cargo::cargo_bin("lemma"))
+            .arg("server")
+            .arg("-d")
+            .arg(workdir)
+            .arg("--port")
+            .arg(port.to_string())
+            .args(args)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let mut server = Self { child, port };
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(
+                server.child.try_wait().unwrap().is_none(),
+                "server exited on startup"
+            );
+            assert!(Instant::now() < deadline, "server did not start");
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(server.get("/health").status, 200);
+        server
+    }
+
+    fn get(&self, path: &str) -> HttpResponse {
+        self.request("GET", path, &[], b"")
+    }
+
+    fn post_json(&self, path: &str, body: &serde_json::Value) -> HttpResponse {
+        self.request(
+            "POST",
+            path,
+            &[("Content-Type", "application/json")],
+            body.to_string().as_bytes(),
+        )
+    }
+
+    fn request(
+        &self,
+        method: &str,
+        path: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> HttpResponse {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: 127.0.0.1\r\nConnection: close\r\nContent-Length: {}\r\n",
+            method,
+            path,
+            body.len()
+        );
+        for (name, value) in headers {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).unwrap();
+        stream.write_all(body).unwrap();
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw).unwrap();
+        HttpResponse::parse(&raw)
+    }
+}
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct HttpResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl HttpResponse {
+    fn parse(raw: &[u8]) -> Self {
+        let end = raw
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .expect("response without headers");
+        let head = String::from_utf8_lossy(&raw[..end]).to_string();
+        let mut lines = head.split("\r\n");
+        let status = lines
+            .next()
+            .and_then(|line| line.split(' ').nth(1))
+            .and_then(|code| code.parse().ok())
+            .expect("response without status");
+        let headers: Vec<(String, String)> = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        let mut body = raw[end + 4..].to_vec();
+        if headers
+            .iter()
+            .any(|(name, value)| name == "transfer-encoding" && value.contains("chunked"))
+        {
+            body = dechunk(&body);
+        }
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!(
+                "invalid JSON ({}): {}",
+                e,
+                String::from_utf8_lossy(&self.body)
+            )
+        })
+    }
+}
+
+/// The body of a `Transfer-Encoding: chunked` response
+fn dechunk(mut chunked: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = chunked
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .expect("chunk without size");
+        let size_line = String::from_utf8_lossy(&chunked[..line_end]).to_string();
+        let size = usize::from_str_radix(size_line.split(';').next().unwrap().trim(), 16).unwrap();
+        if size == 0 {
+            return body;
+        }
+        let start = line_end + 2;
+        body.extend_from_slice(&chunked[start..start + size]);
+        chunked = &chunked[start + size + 2..];
+    }
+}
+
+#[test]
+fn test_healthz_and_readyz_report_a_loaded_workspace() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    for path in ["/healthz", "/readyz"] {
+        let response = server.get(path);
+        assert_eq!(response.status, 200, "{}", path);
+        let status = response.json();
+        assert_eq!(status["status"], "ready");
+        assert_eq!(status["ready"], true);
+        assert_eq!(status["documents"], 1);
+        assert!(status["loaded_at"].is_string());
+        assert_eq!(status["validation"]["valid"], true);
+        assert_eq!(status["validation"]["errors"], serde_json::json!([]));
+    }
+}
+
+#[test]
+fn test_invalid_workspace_is_reported_and_not_ready() {
+    let workspace = TempDir::new().unwrap();
+    fs::write(
+        workspace.path().join("broken.lemma"),
+        "doc broken\nrule subtotal = 10\nrule total = subtotal * 2\n",
+    )
+    .unwrap();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let health = server.get("/healthz");
+    assert_eq!(health.status, 200);
+    let status = health.json();
+    assert_eq!(status["validation"]["valid"], false);
+    let errors = status["validation"]["errors"].as_array().unwrap();
+    assert!(
+        errors
+            .iter()
+            .any(|error| error.as_str().unwrap().contains("'subtotal' is a rule")),
+        "{:?}",
+        errors
+    );
+
+    let ready = server.get("/readyz");
+    assert_eq!(ready.status, 503);
+    assert_eq!(ready.json()["status"], "not_ready");
+    assert_eq!(ready.json()["ready"], false);
+}
+
+#[test]
+fn test_empty_workspace_is_valid_but_not_ready() {
+    let workspace = TempDir::new().unwrap();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let ready = server.get("/readyz");
+    assert_eq!(ready.status, 503);
+    let status = ready.json();
+    assert_eq!(status["documents"], 0);
+    assert_eq!(status["validation"]["valid"], true);
+}
+
+/// A workspace with one small document
+fn pricing_workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact quantity = 2\nfact price = 10\nrule total = quantity * price\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+fn post_with_key(server: &ServerProcess, key: &str, body: &serde_json::Value) -> HttpResponse {
+    server.request(
+        "POST",
+        "/evaluate",
+        &[
+            ("Content-Type", "application/json"),
+            ("Idempotency-Key", key),
+        ],
+        body.to_string().as_bytes(),
+    )
+}
+
+fn inline(code: &str) -> serde_json::Value {
+    serde_json::json!({ "code": code })
+}
+
+#[test]
+fn test_retry_with_idempotency_key_is_replayed() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+    let body = inline("doc inline\nfact x = 4\nrule double = x * 2\n");
+
+    let first = post_with_key(&server, "order-1", &body);
+    assert_eq!(first.status, 200);
+    assert_eq!(first.header("idempotent-replayed"), None);
+
+    let retry = post_with_key(&server, "order-1", &body);
+    assert_eq!(retry.status, 200);
+    assert_eq!(retry.header("idempotent-replayed"), Some("true"));
+    assert_eq!(retry.json()["results"], first.json()["results"]);
+}
+
+#[test]
+fn test_idempotency_key_reused_for_other_body_is_rejected() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let first = post_with_key(&server, "order-2", &inline("doc a\nrule one = 1\n"));
+    assert_eq!(first.status, 200);
+
+    let reused = post_with_key(&server, "order-2", &inline("doc b\nrule two = 2\n"));
+    assert_eq!(reused.status, 422);
+    assert_eq!(reused.json()["code"], "idempotency_key_reused");
+}
+
+#[test]
+fn test_idempotency_key_expires_after_ttl() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &["--idempotency-ttl", "1"]);
+    let body = inline("doc inline\nrule one = 1\n");
+
+    assert_eq!(post_with_key(&server, "order-3", &body).status, 200);
+    std::thread::sleep(Duration::from_millis(1500));
+
+    let after_ttl = post_with_key(&server, "order-3", &body);
+    assert_eq!(after_ttl.status, 200);
+    assert_eq!(after_ttl.header("idempotent-replayed"), None);
+
+    // The key is free again, also for another request
+    std::thread::sleep(Duration::from_millis(1500));
+    let other = post_with_key(&server, "order-3", &inline("doc other\nrule two = 2\n"));
+    assert_eq!(other.status, 200);
+}
+
+#[test]
+fn test_concurrent_duplicates_evaluate_once() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+    let mut code = String::from("doc inline\nfact x = 3\n");
+    for i in 0..2_000 {
+        code.push_str(&format!("rule r{i} = (x + {i}) * (x - {i}) / (x + 1)\n"));
+    }
+    let body = inline(&code);
+
+    let responses: Vec<HttpResponse> = std::thread::scope(|scope| {
+        let requests: Vec<_> = (0..2)
+            .map(|_| scope.spawn(|| post_with_key(&server, "order-4", &body)))
+            .collect();
+        requests
+            .into_iter()
+            .map(|request| request.join().unwrap())
+            .collect()
+    });
+
+    assert!(responses.iter().all(|response| response.status == 200));
+    let replayed = responses
+        .iter()
+        .filter(|response| response.header("idempotent-replayed") == Some("true"))
+        .count();
+    assert_eq!(replayed, 1);
+}
+
+fn operation_types(response: &HttpResponse) -> Vec<String> {
+    response.json()["results"][0]["operations"]
+        .as_array()
+        .map(|operations| {
+            operations
+                .iter()
+                .map(|operation| operation["type"].as_str().unwrap().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[test]
+fn test_trace_none_leaves_operations_out() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    for path in ["/evaluate/pricing", "/evaluate/pricing?trace=none"] {
+        let response = server.get(path);
+        assert_eq!(response.status, 200);
+        assert!(response.json()["results"][0].get("operations").is_none());
+    }
+}
+
+#[test]
+fn test_trace_summary_has_only_final_results() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.get("/evaluate/pricing?trace=summary");
+    assert_eq!(response.status, 200);
+    assert_eq!(operation_types(&response), vec!["final_result"]);
+}
+
+#[test]
+fn test_trace_full_has_every_operation() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let types = operation_types(&server.get("/evaluate/pricing?trace=full"));
+    assert!(types.iter().any(|kind| kind == "fact_used"));
+    assert_eq!(types.last().map(String::as_str), Some("final_result"));
+    assert!(types.len() > 1);
+}
+
+#[test]
+fn test_trace_cut_off_at_max_ops_pages_to_the_end() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.get("/evaluate/pricing?trace=full&max_ops=1");
+    assert_eq!(response.status, 200);
+    let json = response.json();
+    assert_eq!(
+        json["results"][0]["operations"].as_array().unwrap().len(),
+        1
+    );
+    let link = &json["trace"];
+    let total = link["total"].as_u64().unwrap();
+    assert!(total > 1);
+    assert_eq!(link["returned"], 1);
+
+    let id = link["id"].as_str().unwrap();
+    let mut next = Some(link["next"].as_str().unwrap().to_string());
+    let mut paged = 1;
+    while let Some(path) = next {
+        let page = server.get(&format!("{}&limit=1", path));
+        assert_eq!(page.status, 200);
+        let page = page.json();
+        assert_eq!(page["id"], id);
+        assert_eq!(page["total"].as_u64(), Some(total));
+        assert_eq!(page["offset"].as_u64(), Some(paged));
+        paged += page["operations"].as_array().unwrap().len() as u64;
+        next = page["next_offset"]
+            .as_u64()
+            .map(|offset| format!("/evaluations/{}/trace?offset={}", id, offset));
+    }
+    assert_eq!(paged, total);
+
+    let past_end = server.get(&format!("/evaluations/{}/trace?offset={}", id, total));
+    assert_eq!(past_end.status, 200);
+    assert!(past_end.json()["operations"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_trace_ids_are_random_128_bit() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let ids: Vec<String> = (0..2)
+        .map(|_| {
+            let json = server.get("/evaluate/pricing?trace=full&max_ops=0").json();
+            json["trace"]["id"].as_str().unwrap().to_string()
+        })
+        .collect();
+    for id in &ids {
+        assert_eq!(id.len(), 32);
+        assert!(id.bytes().all(|byte| byte.is_ascii_hexdigit()));
+    }
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(
+        server.get("/evaluations/0000/trace").status,
+        404,
+        "unknown IDs are not found"
+    );
+}
+
+/// Spans received by a fake OpenTelemetry collector, by trace ID
+///
+/// Answers each export request with 200 until spans of every trace in
+/// `trace_ids` arrived.
+fn collect_spans(
+    collector: &TcpListener,
+    trace_ids: &[&str],
+) -> std::collections::HashMap<String, Vec<serde_json::Value>> {
+    collector.set_nonblocking(true).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut spans: std::collections::HashMap<String, Vec<serde_json::Value>> =
+        std::collections::HashMap::new();
+    while !trace_ids.iter().all(|id| spans.contains_key(*id)) {
+        assert!(
+            Instant::now() < deadline,
+            "no spans arrived at the collector"
+        );
+        let mut stream = match collector.accept() {
+            Ok((stream, _)) => stream,
+            Err(_) => {
+                std::thread::sleep(Duration::from_millis(20));
+                continue;
+            }
+        };
+        stream.set_nonblocking(false).unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+
+        let mut raw = Vec::new();
+        let mut buffer = [0u8; 8192];
+        let body_start = loop {
+            let read = stream.read(&mut buffer).unwrap();
+            assert!(read > 0, "export request ended before its body");
+            raw.extend_from_slice(&buffer[..read]);
+            if let Some(end) = raw.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&raw[..body_start]).to_ascii_lowercase();
+        assert!(head.starts_with("post /v1/traces "), "{}", head);
+        let length: usize = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|length| length.trim().parse().unwrap())
+            .expect("export request without Content-Length");
+        while raw.len() < body_start + length {
+            let read = stream.read(&mut buffer).unwrap();
+            assert!(read > 0, "export request ended before its body");
+            raw.extend_from_slice(&buffer[..read]);
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+            .unwrap();
+
+        let export: serde_json::Value =
+            serde_json::from_slice(&raw[body_start..body_start + length]).unwrap();
+        for span in export["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap()
+        {
+            spans
+                .entry(span["traceId"].as_str().unwrap().to_string())
+                .or_default()
+                .push(span.clone());
+        }
+    }
+    spans
+}
+
+#[test]
+fn test_spans_are_exported_to_collector_with_the_requested_trace() {
+    let workspace = pricing_workspace();
+    let collector = TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", collector.local_addr().unwrap());
+    let server = ServerProcess::start(workspace.path(), &["--otlp-endpoint", &endpoint]);
+
+    let untraced = "4bf92f3577b34da6a3ce929d0e0e4736";
+    let traced = "0af7651916cd43dd8448eb211c80319c";
+    let traceparent = |trace_id: &str| format!("00-{}-00f067aa0ba902b7-01", trace_id);
+    let plain = server.request(
+        "GET",
+        "/evaluate/pricing",
+        &[("traceparent", traceparent(untraced).as_str())],
+        b"",
+    );
+    assert_eq!(plain.status, 200);
+    // Exporting doesn't add a trace the client didn't ask for
+    assert!(plain.json()["results"][0].get("operations").is_none());
+    let full = server.request(
+        "GET",
+        "/evaluate/pricing?trace=full",
+        &[("traceparent", traceparent(traced).as_str())],
+        b"",
+    );
+    assert_eq!(full.status, 200);
+
+    let spans = collect_spans(&collector, &[untraced, traced]);
+    let rule_span = |trace_id: &str| {
+        let names: Vec<&str> = spans[trace_id]
+            .iter()
+            .map(|span| span["name"].as_str().unwrap())
+            .collect();
+        assert!(names.contains(&"evaluate pricing"), "{:?}", names);
+        spans[trace_id]
+            .iter()
+            .find(|span| span["name"] == "rule total")
+            .unwrap()
+            .clone()
+    };
+    assert!(rule_span(untraced)["events"].as_array().unwrap().is_empty());
+    assert!(!rule_span(traced)["events"].as_array().unwrap().is_empty());
+}
+
+/// A workspace with a document taking a date
+fn contract_workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("contract.lemma"),
+        "doc contract\nfact start = [date]\nrule begins = start\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_date_format_in_post_body() {
+    let workspace = contract_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+    let code = "doc inline\nfact start = [date]\nrule begins = start\n";
+
+    let day_first = server.post_json(
+        "/evaluate",
+        &serde_json::json!({
+            "code": code,
+            "facts": { "start": "31-12-2025" },
+            "date_format": "day-month-year",
+        }),
+    );
+    assert_eq!(day_first.status, 200);
+    let display = day_first.json()["results"][0]["display"].clone();
+    assert!(
+        display.as_str().unwrap().contains("2025-12-31"),
+        "{}",
+        display
+    );
+
+    // Without it the server's ISO default rejects the date
+    let iso = server.post_json(
+        "/evaluate",
+        &serde_json::json!({ "code": code, "facts": { "start": "31-12-2025" } }),
+    );
+    assert!((400..500).contains(&iso.status));
+    assert_eq!(iso.json()["code"], "invalid_facts");
+
+    let unknown = server.post_json(
+        "/evaluate",
+        &serde_json::json!({ "code": code, "date_format": "yy-dd-mm" }),
+    );
+    assert_eq!(unknown.status, 400);
+    assert_eq!(unknown.json()["code"], "invalid_body");
+}
+
+#[test]
+fn test_date_format_in_evaluate_many_body() {
+    let workspace = contract_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.post_json(
+        "/evaluate-many",
+        &serde_json::json!({
+            "documents": ["contract"],
+            "facts": { "start": "12/31/2025" },
+            "date_format": "mdy",
+        }),
+    );
+    assert_eq!(response.status, 200);
+    let display = response.json()["documents"]["contract"]["results"][0]["display"].clone();
+    assert!(
+        display.as_str().unwrap().contains("2025-12-31"),
+        "{}",
+        display
+    );
+}
+
+/// A workspace with an internal rule a public one uses
+fn visibility_workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact cost = 100\ninternal rule base_margin = cost * 15%\npublic rule price = cost + base_margin?\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+fn rule_names(results: &serde_json::Value) -> Vec<String> {
+    results
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|rule| rule["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_public_rules_only_omits_internal_rules() {
+    let workspace = visibility_workspace();
+    let server = ServerProcess::start(workspace.path(), &["--public-rules-only"]);
+
+    let evaluated = server.get("/evaluate/pricing");
+    assert_eq!(evaluated.status, 200);
+    let results = evaluated.json()["results"].clone();
+    assert_eq!(rule_names(&results), vec!["price"]);
+    assert!(results[0]["display"].as_str().unwrap().contains("115"));
+
+    let doc = server.get("/docs/pricing").json();
+    assert_eq!(rule_names(&doc["rules"]), vec!["price"]);
+    assert_eq!(server.get("/docs").json()["documents"][0]["rules"], 1);
+
+    // Without the flag internal rules are served like the others
+    let open = ServerProcess::start(workspace.path(), &[]);
+    let doc = open.get("/docs/pricing").json();
+    assert_eq!(rule_names(&doc["rules"]), vec!["base_margin", "price"]);
+}
+
+#[test]
+fn test_public_rules_only_rejects_requested_internal_rule() {
+    let workspace = visibility_workspace();
+    let server = ServerProcess::start(workspace.path(), &["--public-rules-only"]);
+    let code = "doc inline\nfact cost = 100\ninternal rule margin = cost * 15%\nrule price = cost + margin?\n";
+
+    let response = server.post_json(
+        "/evaluate",
+        &serde_json::json!({ "code": code, "rules": ["margin"] }),
+    );
+    assert_eq!(response.status, 400);
+    let problem = response.json();
+    assert_eq!(problem["code"], "engine_error");
+    assert!(
+        problem["detail"].as_str().unwrap().contains("internal"),
+        "{}",
+        problem
+    );
+
+    let public = server.post_json(
+        "/evaluate",
+        &serde_json::json!({ "code": code, "rules": ["price"] }),
+    );
+    assert_eq!(public.status, 200);
+}
+
+#[test]
+fn test_public_rules_only_caps_full_trace_to_summary() {
+    let workspace = visibility_workspace();
+    let server = ServerProcess::start(workspace.path(), &["--public-rules-only"]);
+
+    let response = server.get("/evaluate/pricing?trace=full");
+    assert_eq!(response.status, 200);
+    assert_eq!(operation_types(&response), vec!["final_result"]);
+}
+
+#[test]
+fn test_accept_msgpack_encodes_the_response() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.request(
+        "GET",
+        "/evaluate/pricing",
+        &[("Accept", "application/msgpack")],
+        b"",
+    );
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("content-type"), Some("application/msgpack"));
+    let decoded: serde_json::Value = lemma::serializers::decode_msgpack(&response.body).unwrap();
+    assert_eq!(decoded["results"][0]["name"], "total");
+    assert!(decoded["results"][0]["display"]
+        .as_str()
+        .unwrap()
+        .contains("20"));
+
+    // JSON when the client prefers it
+    let json = server.request(
+        "GET",
+        "/evaluate/pricing",
+        &[("Accept", "application/json, application/msgpack;q=0.5")],
+        b"",
+    );
+    assert!(json
+        .header("content-type")
+        .unwrap()
+        .starts_with("application/json"));
+    assert_eq!(json.json()["results"][0]["name"], "total");
+}
+
+#[test]
+fn test_msgpack_request_body() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let body = lemma::serializers::encode_msgpack(&serde_json::json!({
+        "code": "doc inline\nfact quantity = [number]\nrule double = quantity * 2\n",
+        "facts": { "quantity": 21 },
+    }))
+    .unwrap();
+    let response = server.request(
+        "POST",
+        "/evaluate",
+        &[("Content-Type", "application/msgpack")],
+        &body,
+    );
+    assert_eq!(response.status, 200);
+    let json = response.json();
+    assert_eq!(json["results"][0]["name"], "double");
+    assert!(json["results"][0]["display"]
+        .as_str()
+        .unwrap()
+        .contains("42"));
+}
+
+#[test]
+fn test_unknown_content_type_is_415() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.request(
+        "POST",
+        "/evaluate",
+        &[("Content-Type", "text/plain")],
+        inline("doc inline\nrule one = 1\n").to_string().as_bytes(),
+    );
+    assert_eq!(response.status, 415);
+    assert_eq!(
+        response.header("content-type"),
+        Some("application/problem+json")
+    );
+    assert_eq!(response.json()["code"], "unsupported_media_type");
+}
+
+#[test]
+fn test_accept_encoding_gzip_compresses_the_response() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.request(
+        "GET",
+        "/evaluate/pricing",
+        &[("Accept-Encoding", "gzip")],
+        b"",
+    );
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("content-encoding"), Some("gzip"));
+    let mut body = String::new();
+    flate2::read::GzDecoder::new(response.body.as_slice())
+        .read_to_string(&mut body)
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["results"][0]["name"], "total");
+}
+
+#[test]
+fn test_docs_lists_the_workspace() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.get("/docs");
+    assert_eq!(response.status, 200);
+    let documents = response.json()["documents"].clone();
+    assert_eq!(documents.as_array().unwrap().len(), 1);
+    assert_eq!(documents[0]["name"], "pricing");
+    assert_eq!(documents[0]["facts"], 2);
+    assert_eq!(documents[0]["rules"], 1);
+}
+
+#[test]
+fn test_doc_has_its_facts_and_rules() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.get("/docs/pricing");
+    assert_eq!(response.status, 200);
+    let doc = response.json();
+    assert_eq!(doc["name"], "pricing");
+    let facts = doc["facts"].as_array().unwrap();
+    assert_eq!(facts.len(), 2);
+    let quantity = facts
+        .iter()
+        .find(|fact| fact["name"] == "quantity")
+        .unwrap();
+    assert_eq!(quantity["kind"], "value");
+    assert_eq!(quantity["value"], "2");
+    assert_eq!(rule_names(&doc["rules"]), vec!["total"]);
+    assert_eq!(
+        doc["rules"][0]["depends_on"]["facts"],
+        serde_json::json!(["price", "quantity"])
+    );
+}
+
+#[test]
+fn test_unknown_doc_is_404() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.get("/docs/missing");
+    assert_eq!(response.status, 404);
+    assert_eq!(response.json()["code"], "document_not_found");
+}
+
+#[test]
+fn test_doc_schema_describes_the_facts() {
+    let workspace = contract_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let response = server.get("/docs/contract/schema");
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.header("content-type"),
+        Some("application/schema+json")
+    );
+    let schema = response.json();
+    assert_eq!(schema["title"], "contract");
+    assert_eq!(schema["properties"]["start"]["type"], "string");
+    assert_eq!(schema["required"], serde_json::json!(["start"]));
+
+    assert_eq!(server.get("/docs/missing/schema").status, 404);
+}
+
+#[test]
+fn test_evaluate_many_shares_overrides() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("salary.lemma"),
+        "doc tax\nfact income = [number]\nrule due = income * 20%\n\ndoc pension\nfact income = [number]\nrule premium = income * 5%\n",
+    )
+    .unwrap();
+    let server = ServerProcess::start(temp_dir.path(), &[]);
+
+    let response = server.post_json(
+        "/evaluate-many",
+        &serde_json::json!({ "documents": ["tax", "pension"], "facts": { "income": 1000 } }),
+    );
+    assert_eq!(response.status, 200);
+    let documents = response.json()["documents"].clone();
+    assert_eq!(documents["tax"]["results"][0]["name"], "due");
+    assert!(documents["tax"]["results"][0]["display"]
+        .as_str()
+        .unwrap()
+        .contains("200"));
+    assert_eq!(documents["pension"]["results"][0]["name"], "premium");
+    assert!(documents["pension"]["results"][0]["display"]
+        .as_str()
+        .unwrap()
+        .contains("50"));
+
+    let none = server.post_json("/evaluate-many", &serde_json::json!({ "documents": [] }));
+    assert_eq!(none.status, 400);
+    assert_eq!(none.json()["code"], "no_document");
+
+    let missing = server.post_json(
+        "/evaluate-many",
+        &serde_json::json!({ "documents": ["tax", "missing"] }),
+    );
+    assert_eq!(missing.status, 404);
+    assert_eq!(missing.json()["code"], "document_not_found");
+}
+
+#[test]
+fn test_errors_are_problem_json_pointing_into_the_source() {
+    let workspace = pricing_workspace();
+    let server = ServerProcess::start(workspace.path(), &[]);
+
+    let semantic = server.post_json(
+        "/evaluate",
+        &inline("doc inline\nrule subtotal = 10\nrule total = subtotal * 2\n"),
+    );
+    assert_eq!(semantic.status, 400);
+    assert_eq!(
+        semantic.header("content-type"),
+        Some("application/problem+json")
+    );
+    let problem = semantic.json();
+    assert_eq!(problem["status"], 400);
+    assert_eq!(problem["title"], "Bad Request");
+    assert_eq!(problem["code"], "semantic_error");
+    assert_eq!(problem["doc"], "inline");
+    assert_eq!(problem["source"], "<input>");
+    assert_eq!(problem["line"], 3);
+    assert_eq!(
+        problem["suggestion"],
+        "Use 'subtotal?' to reference the rule 'subtotal'"
+    );
+
+    let syntax = server.post_json(
+        "/evaluate",
+        &inline("doc inline\nfact quantity = 2\nrule total = quantity * * 2\n"),
+    );
+    assert_eq!(syntax.status, 400);
+    let problem = syntax.json();
+    assert_eq!(problem["code"], "parse_error");
+    assert!(problem.get("doc").is_none());
+    assert_eq!(problem["line"], 3);
+    assert_eq!(problem["snippet"], "rule total = quantity * * 2");
+
+    let facts = server.get("/evaluate/pricing?quantity=%22lots%22");
+    assert_eq!(facts.status, 400);
+    let problem = facts.json();
+    assert_eq!(problem["code"], "invalid_facts");
+    assert_eq!(problem["fields"][0]["fact"], "quantity");
+}
+
+#[test]
+fn test_accept_language_falls_back_to_the_next_locale() {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("shipping.lemma"),
+        "doc shipping\nfact weight = 30\nrule allowed = weight <= 25\n  unless weight > 25 then veto \"too heavy\" @nl \"te zwaar\"\n",
+    )
+    .unwrap();
+    let server = ServerProcess::start(temp_dir.path(), &[]);
+
+    let veto = |language: &str| {
+        let response = server.request(
+            "GET",
+            "/evaluate/shipping",
+            &[("Accept-Language", language)],
+            b"",
+        );
+        assert_eq!(response.status, 200);
+        response.json()["results"][0]["veto_reason"].clone()
+    };
+    assert_eq!(veto("fr-FR, nl;q=0.8, en;q=0.5"), "te zwaar");
+    assert_eq!(veto("fr, es"), "too heavy");
+}
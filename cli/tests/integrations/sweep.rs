@@ -0,0 +1,85 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::TempDir;
+
+fn workspace() -> TempDir {
+    let temp_dir = TempDir::new().unwrap();
+    fs::write(
+        temp_dir.path().join("pricing.lemma"),
+        "doc pricing\nfact quantity = [number]\nfact price = [number]\nrule total = quantity * price\nrule bulk = quantity >= 20\n",
+    )
+    .unwrap();
+    temp_dir
+}
+
+#[test]
+fn test_sweep_chart() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("sweep")
+        .arg("pricing")
+        .arg("bulk")
+        .arg("quantity")
+        .arg("--from")
+        .arg("0")
+        .arg("--to")
+        .arg("30")
+        .arg("--step")
+        .arg("10")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("bulk by quantity"))
+        .stdout(predicate::str::contains("########"))
+        .stdout(predicate::str::contains("quantity 10 → 20: false → true"));
+}
+
+#[test]
+fn test_sweep_raw() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("sweep")
+        .arg("pricing")
+        .arg("total")
+        .arg("quantity")
+        .arg("--from")
+        .arg("1")
+        .arg("--to")
+        .arg("3")
+        .arg("price=5")
+        .arg("--raw")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .success()
+        .stdout(predicate::eq("1\t5\n2\t10\n3\t15\n"));
+}
+
+#[test]
+fn test_sweep_rejects_non_numeric_step() {
+    let temp_dir = workspace();
+
+    let mut cmd = Command::cargo_bin("lemma").unwrap();
+    cmd.arg("sweep")
+        .arg("pricing")
+        .arg("total")
+        .arg("quantity")
+        .arg("--from")
+        .arg("1")
+        .arg("--to")
+        .arg("3")
+        .arg("--step")
+        .arg("\"one\"")
+        .arg("--dir")
+        .arg(temp_dir.path());
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("--step"));
+}
@@ -0,0 +1,32 @@
+#![no_main]
+
+use lemma::{Engine, ResourceLimits};
+use lemma_fuzz::{Case, DOC_NAME};
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+
+// Generated documents must parse, and then make it through validation,
+// evaluation and inversion without panicking. Errors are fine: a generated
+// document can still divide by zero or fail validation.
+fuzz_target!(|case: Case| {
+    let code = case.document().to_string();
+    if let Err(e) = lemma::parse(&code, None, &ResourceLimits::default()) {
+        panic!("Generated document doesn't parse: {}\n{}", e, code);
+    }
+
+    let mut engine = Engine::new();
+    if engine.add_lemma_code(&code, "fuzz.lemma").is_err() {
+        return;
+    }
+
+    let overrides = case.fact_overrides();
+    let overrides: Vec<&str> = overrides.iter().map(String::as_str).collect();
+    if let Ok(facts) = lemma::parse_facts(&overrides) {
+        let _ = engine.evaluate(DOC_NAME, None, Some(facts));
+    }
+    let _ = engine.evaluate(DOC_NAME, None, None);
+
+    for (rule, target) in case.inversions() {
+        let _ = engine.invert(DOC_NAME, &rule, target, HashMap::new());
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use lemma::ResourceLimits;
+use lemma_fuzz::Case;
+use libfuzzer_sys::fuzz_target;
+
+// Writing a parsed document out again gives the same code, so formatting
+// and `lemma diff` never change what a document means.
+fuzz_target!(|case: Case| {
+    let code = case.document().to_string();
+    let docs = lemma::parse(&code, None, &ResourceLimits::default())
+        .unwrap_or_else(|e| panic!("Generated document doesn't parse: {}\n{}", e, code));
+    assert_eq!(docs.len(), 1);
+    assert_eq!(docs[0].to_string(), code, "Parsing changed the document");
+});
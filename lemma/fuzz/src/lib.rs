@@ -0,0 +1,327 @@
+//! Structured inputs for the fuzz targets
+//!
+//! Byte-level targets like `fuzz_parser` rarely get past the grammar. A
+//! `Case` instead turns fuzzer bytes into a document built from the AST types
+//! and written out with `Display`, so every case is valid Lemma that reaches
+//! validation, evaluation and inversion.
+//!
+//! Expressions are generated for the type they must have: a `Comparison` in
+//! a number rule becomes an operand of that type, a fact reference picks a
+//! fact of the right type, and rules only reference earlier rules. Most
+//! documents therefore also pass validation. The shapes are kept small, see
+//! `MAX_FACTS`, `MAX_RULES`, `MAX_DEPTH` and the rest.
+
+use lemma::{
+    ArithmeticOperation, ComparisonOperator, Expression, ExpressionIdGenerator, ExpressionKind,
+    FactReference, FactType, FactValue, LemmaDoc, LemmaFact, LemmaRule, LemmaType, LiteralValue,
+    MoneyUnit, NegationType, NumericUnit, RuleReference, Target, TypeAnnotation, UnlessClause,
+    UnlessMode, VetoExpression,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use rust_decimal::Decimal;
+
+pub const MAX_FACTS: usize = 8;
+pub const MAX_RULES: usize = 8;
+pub const MAX_UNLESS_CLAUSES: usize = 4;
+pub const MAX_DEPTH: usize = 5;
+pub const MAX_TARGETS: usize = 2;
+
+/// Name of the generated document
+pub const DOC_NAME: &str = "fuzz";
+
+const WORDS: [&str; 4] = ["gold", "silver", "bronze", ""];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Arbitrary)]
+pub enum Ty {
+    Number,
+    Boolean,
+    Money,
+    Text,
+}
+
+#[derive(Debug, Arbitrary)]
+pub struct FactSpec {
+    pub ty: Ty,
+    /// The value, or None for a fact declared with its type only
+    pub value: Option<u16>,
+}
+
+#[derive(Debug, Arbitrary)]
+pub struct RuleSpec {
+    pub ty: Ty,
+    pub expression: ExprSpec,
+    /// Condition, result, and whether the result is a veto instead
+    pub unless_clauses: Vec<(ExprSpec, ExprSpec, bool)>,
+    pub first_match: bool,
+}
+
+/// The shape of an expression, given its type when it's built
+#[derive(Debug, Arbitrary)]
+pub enum ExprSpec {
+    Literal(u16),
+    Fact(u8),
+    Rule(u8),
+    Arithmetic(u8, Box<ExprSpec>, Box<ExprSpec>),
+    Comparison(u8, Box<ExprSpec>, Box<ExprSpec>),
+    And(Box<ExprSpec>, Box<ExprSpec>),
+    Or(Box<ExprSpec>, Box<ExprSpec>),
+    Not(Box<ExprSpec>),
+    Have(u8),
+}
+
+/// A generated document with the fact overrides and targets to run it with
+#[derive(Debug, Arbitrary)]
+pub struct Case {
+    pub facts: Vec<FactSpec>,
+    pub rules: Vec<RuleSpec>,
+    /// Fact index and value of each override
+    pub overrides: Vec<(u8, u16)>,
+    /// Values to invert the rules for
+    pub targets: Vec<u16>,
+}
+
+impl Case {
+    pub fn document(&self) -> LemmaDoc {
+        let mut builder = Builder {
+            ids: ExpressionIdGenerator::new(),
+            facts: Vec::new(),
+            rules: Vec::new(),
+        };
+        let mut doc = LemmaDoc::new(DOC_NAME.to_string());
+
+        for (index, fact) in self.facts.iter().take(MAX_FACTS).enumerate() {
+            let name = format!("f{}", index);
+            let value = match fact.value {
+                Some(value) => FactValue::Literal(literal(fact.ty, value)),
+                None => FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type(fact.ty))),
+            };
+            doc = doc.add_fact(LemmaFact::new(FactType::Local(name.clone()), value));
+            builder.facts.push((name, fact.ty));
+        }
+
+        for (index, rule) in self.rules.iter().take(MAX_RULES).enumerate() {
+            let expression = builder.expression(&rule.expression, rule.ty, 0);
+            let unless_clauses = rule
+                .unless_clauses
+                .iter()
+                .take(MAX_UNLESS_CLAUSES)
+                .map(|(condition, result, veto)| UnlessClause {
+                    condition: builder.expression(condition, Ty::Boolean, 0),
+                    result: if *veto {
+                        builder.veto()
+                    } else {
+                        builder.expression(result, rule.ty, 0)
+                    },
+                    span: None,
+                })
+                .collect();
+            let name = format!("r{}", index);
+            doc = doc.add_rule(LemmaRule {
+                unless_clauses,
+                unless_mode: if rule.first_match {
+                    UnlessMode::FirstMatch
+                } else {
+                    UnlessMode::LastMatch
+                },
+                ..LemmaRule::new(name.clone(), expression)
+            });
+            builder.rules.push((name, rule.ty));
+        }
+        doc
+    }
+
+    /// Rules with the targets to invert them for: any value, any veto and `targets`
+    pub fn inversions(&self) -> Vec<(String, Target)> {
+        let mut inversions = Vec::new();
+        for (index, rule) in self.rules.iter().take(MAX_RULES).enumerate() {
+            let name = format!("r{}", index);
+            inversions.push((name.clone(), Target::any_value()));
+            inversions.push((name.clone(), Target::any_veto()));
+            for value in self.targets.iter().take(MAX_TARGETS) {
+                inversions.push((name.clone(), Target::value(literal(rule.ty, *value))));
+            }
+        }
+        inversions
+    }
+
+    /// Overrides as `parse_facts` takes them, e.g. `f0=12`
+    pub fn fact_overrides(&self) -> Vec<String> {
+        let facts: Vec<&FactSpec> = self.facts.iter().take(MAX_FACTS).collect();
+        if facts.is_empty() {
+            return Vec::new();
+        }
+        self.overrides
+            .iter()
+            .map(|(index, value)| {
+                let index = *index as usize % facts.len();
+                format!("f{}={}", index, literal(facts[index].ty, *value))
+            })
+            .collect()
+    }
+}
+
+struct Builder {
+    ids: ExpressionIdGenerator,
+    /// Name and type of the facts, then of the rules built so far
+    facts: Vec<(String, Ty)>,
+    rules: Vec<(String, Ty)>,
+}
+
+impl Builder {
+    fn expression(&mut self, spec: &ExprSpec, ty: Ty, depth: usize) -> Expression {
+        if depth >= MAX_DEPTH {
+            return self.literal(ty, 1);
+        }
+        let depth = depth + 1;
+        let kind = match (spec, ty) {
+            (ExprSpec::Literal(value), _) => return self.literal(ty, *value),
+            (ExprSpec::Fact(index), _) => return self.fact(ty, *index),
+            (ExprSpec::Rule(index), _) => {
+                let rules: Vec<&String> = pick(&self.rules, ty);
+                if rules.is_empty() {
+                    return self.fact(ty, *index);
+                }
+                let name = rules[*index as usize % rules.len()].clone();
+                ExpressionKind::RuleReference(RuleReference {
+                    reference: vec![name],
+                })
+            }
+            (ExprSpec::Arithmetic(op, left, right), Ty::Number) => {
+                let op = [
+                    ArithmeticOperation::Add,
+                    ArithmeticOperation::Subtract,
+                    ArithmeticOperation::Multiply,
+                    ArithmeticOperation::Divide,
+                    ArithmeticOperation::Modulo,
+                ][*op as usize % 5]
+                    .clone();
+                ExpressionKind::Arithmetic(
+                    Box::new(self.expression(left, Ty::Number, depth)),
+                    op,
+                    Box::new(self.expression(right, Ty::Number, depth)),
+                )
+            }
+            (ExprSpec::Arithmetic(op, left, right), Ty::Money) => {
+                // Money adds to money, and scales by a number
+                let (op, right_ty) = [
+                    (ArithmeticOperation::Add, Ty::Money),
+                    (ArithmeticOperation::Subtract, Ty::Money),
+                    (ArithmeticOperation::Multiply, Ty::Number),
+                    (ArithmeticOperation::Divide, Ty::Number),
+                ][*op as usize % 4]
+                    .clone();
+                ExpressionKind::Arithmetic(
+                    Box::new(self.expression(left, Ty::Money, depth)),
+                    op,
+                    Box::new(self.expression(right, right_ty, depth)),
+                )
+            }
+            (
+                ExprSpec::Arithmetic(op, left, right) | ExprSpec::Comparison(op, left, right),
+                Ty::Boolean,
+            ) => {
+                let (operand, operators): (Ty, &[ComparisonOperator]) = match *op % 3 {
+                    0 => (Ty::Number, ORDERINGS),
+                    1 => (Ty::Money, ORDERINGS),
+                    _ => (
+                        Ty::Text,
+                        &[ComparisonOperator::Is, ComparisonOperator::IsNot],
+                    ),
+                };
+                let operator = operators[*op as usize % operators.len()].clone();
+                ExpressionKind::Comparison(
+                    Box::new(self.expression(left, operand, depth)),
+                    operator,
+                    Box::new(self.expression(right, operand, depth)),
+                )
+            }
+            (ExprSpec::And(left, right), Ty::Boolean) => ExpressionKind::LogicalAnd(
+                Box::new(self.expression(left, Ty::Boolean, depth)),
+                Box::new(self.expression(right, Ty::Boolean, depth)),
+            ),
+            (ExprSpec::Or(left, right), Ty::Boolean) => ExpressionKind::LogicalOr(
+                Box::new(self.expression(left, Ty::Boolean, depth)),
+                Box::new(self.expression(right, Ty::Boolean, depth)),
+            ),
+            (ExprSpec::Not(operand), Ty::Boolean) => ExpressionKind::LogicalNegation(
+                Box::new(self.expression(operand, Ty::Boolean, depth)),
+                NegationType::Not,
+            ),
+            (ExprSpec::Have(index), Ty::Boolean) if !self.facts.is_empty() => {
+                let name = self.facts[*index as usize % self.facts.len()].0.clone();
+                ExpressionKind::FactHasAnyValue(FactReference {
+                    reference: vec![name],
+                })
+            }
+            // Shapes that can't have the type stand for one of their operands
+            (ExprSpec::Arithmetic(_, operand, _) | ExprSpec::Comparison(_, operand, _), _)
+            | (ExprSpec::And(operand, _) | ExprSpec::Or(operand, _), _)
+            | (ExprSpec::Not(operand), _) => return self.expression(operand, ty, depth),
+            (ExprSpec::Have(index), _) => return self.fact(ty, *index),
+        };
+        Expression::new(kind, None, self.ids.next_id())
+    }
+
+    /// A fact of the type, or a literal when there is none
+    fn fact(&mut self, ty: Ty, index: u8) -> Expression {
+        let facts = pick(&self.facts, ty);
+        if facts.is_empty() {
+            return self.literal(ty, index as u16);
+        }
+        let name = facts[index as usize % facts.len()].clone();
+        let kind = ExpressionKind::FactReference(FactReference {
+            reference: vec![name],
+        });
+        Expression::new(kind, None, self.ids.next_id())
+    }
+
+    fn literal(&mut self, ty: Ty, value: u16) -> Expression {
+        let kind = ExpressionKind::Literal(literal(ty, value));
+        Expression::new(kind, None, self.ids.next_id())
+    }
+
+    fn veto(&mut self) -> Expression {
+        let kind = ExpressionKind::Veto(VetoExpression {
+            message: Some("fuzz".to_string()),
+            translations: Vec::new(),
+            arguments: Vec::new(),
+        });
+        Expression::new(kind, None, self.ids.next_id())
+    }
+}
+
+const ORDERINGS: &[ComparisonOperator] = &[
+    ComparisonOperator::GreaterThan,
+    ComparisonOperator::LessThan,
+    ComparisonOperator::GreaterThanOrEqual,
+    ComparisonOperator::LessThanOrEqual,
+    ComparisonOperator::Equal,
+    ComparisonOperator::NotEqual,
+];
+
+/// Names of the items of the type
+fn pick(items: &[(String, Ty)], ty: Ty) -> Vec<&String> {
+    items
+        .iter()
+        .filter(|(_, item_ty)| *item_ty == ty)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+fn literal(ty: Ty, value: u16) -> LiteralValue {
+    match ty {
+        Ty::Number => LiteralValue::Number(Decimal::from(value)),
+        Ty::Boolean => LiteralValue::Boolean(value % 2 == 0),
+        Ty::Money => LiteralValue::Unit(NumericUnit::Money(Decimal::from(value), MoneyUnit::Eur)),
+        Ty::Text => LiteralValue::Text(WORDS[value as usize % WORDS.len()].to_string()),
+    }
+}
+
+fn lemma_type(ty: Ty) -> LemmaType {
+    match ty {
+        Ty::Number => LemmaType::Number,
+        Ty::Boolean => LemmaType::Boolean,
+        Ty::Money => LemmaType::Money,
+        Ty::Text => LemmaType::Text,
+    }
+}
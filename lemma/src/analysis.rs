@@ -6,10 +6,17 @@
 //! Used by both semantic validation and evaluation.
 
 use crate::{
-    Expression, ExpressionKind, FactReference, FactType, FactValue, LemmaDoc, LemmaFact,
-    LemmaResult, LemmaRule, RulePath,
+    AggregateExpression, Expression, ExpressionKind, FactReference, FactType, FactValue, LemmaDoc,
+    LemmaError, LemmaFact, LemmaResult, LemmaRule, LemmaType, LiteralValue, MoneyUnit, NumericUnit,
+    ResourceLimits, RulePath, RulePathSegment,
 };
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Instance indices per collection fact, keyed by the fact path to the collection
+///
+/// E.g. overrides `line_items[0].quantity=1` and `line_items[2].quantity=4`
+/// give `["line_items"] -> {0, 2}`.
+pub type CollectionInstances = HashMap<Vec<String>, BTreeSet<usize>>;
 
 /// References extracted from an expression
 #[derive(Debug, Clone, Default)]
@@ -50,7 +57,7 @@ fn collect_references(
         ExpressionKind::FactReference(fact_ref) => {
             fact_refs.insert(fact_ref.clone());
         }
-        ExpressionKind::RuleReference(rule_ref) => {
+        ExpressionKind::RuleReference(rule_ref) | ExpressionKind::RuleHasValue(rule_ref) => {
             rule_refs.insert(rule_ref.reference.clone());
         }
         ExpressionKind::Arithmetic(left, _op, right) => {
@@ -61,6 +68,11 @@ fn collect_references(
             collect_references(left, fact_refs, rule_refs);
             collect_references(right, fact_refs, rule_refs);
         }
+        ExpressionKind::Range(range) => {
+            for operand in range.operands() {
+                collect_references(operand, fact_refs, rule_refs);
+            }
+        }
         ExpressionKind::LogicalAnd(left, right) => {
             collect_references(left, fact_refs, rule_refs);
             collect_references(right, fact_refs, rule_refs);
@@ -81,7 +93,26 @@ fn collect_references(
         ExpressionKind::FactHasAnyValue(fact_ref) => {
             fact_refs.insert(fact_ref.clone());
         }
-        ExpressionKind::Veto(_) | ExpressionKind::Literal(_) => {}
+        ExpressionKind::MissingItems(items)
+        | ExpressionKind::FunctionCall(_, items)
+        | ExpressionKind::Selection(_, items) => {
+            for item in items {
+                collect_references(item, fact_refs, rule_refs);
+            }
+        }
+        ExpressionKind::Aggregate(aggregate) => {
+            if let Some(rule) = &aggregate.rule {
+                let mut reference = aggregate.collection.clone();
+                reference.push(rule.clone());
+                rule_refs.insert(reference);
+            }
+        }
+        ExpressionKind::Veto(veto) => {
+            for argument in &veto.arguments {
+                collect_references(&argument.expression, fact_refs, rule_refs);
+            }
+        }
+        ExpressionKind::Literal(_) => {}
     }
 }
 
@@ -192,39 +223,505 @@ pub fn fact_display_name(fact: &LemmaFact) -> String {
     }
 }
 
+/// The group a dotted fact belongs to, e.g. `customer` for `fact customer.name = [text]`
+///
+/// A dotted fact whose first segment is not a document reference or collection
+/// declares a field of a record in its own document rather than overriding a
+/// fact elsewhere. Returns None for local facts and for such overrides.
+pub fn fact_group<'a>(fact: &'a LemmaFact, doc: &LemmaDoc) -> Option<&'a str> {
+    let FactType::Foreign(foreign) = &fact.fact_type else {
+        return None;
+    };
+    let first = foreign.reference.first()?;
+    let root = crate::parse_collection_instance(first)
+        .map(|(collection, _)| collection)
+        .unwrap_or(first);
+    let references_document = doc.facts.iter().any(|other| {
+        matches!(
+            (&other.fact_type, &other.value),
+            (FactType::Local(name), FactValue::DocumentReference(_) | FactValue::DocumentCollection(_))
+                if name == root
+        )
+    });
+    (!references_document).then_some(first.as_str())
+}
+
+/// Find the declared type of a fact path, following document references
+///
+/// `order.quantity` resolves `quantity` in the document `order` refers to, and
+/// `line_items[0].quantity` resolves it in the collection's item document.
+/// Returns None for unknown facts and for document references themselves.
+pub fn declared_fact_type(
+    doc: &LemmaDoc,
+    reference: &[String],
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> Option<LemmaType> {
+    declaring_document(doc, reference, all_documents).map(|(_, declared)| declared)
+}
+
+/// Find the document declaring a fact path, together with the declared type
+///
+/// Like `declared_fact_type`, but also returns the document the type comes from,
+/// e.g. to apply that document's currency.
+pub fn declaring_document<'a>(
+    doc: &'a LemmaDoc,
+    reference: &[String],
+    all_documents: &'a HashMap<String, LemmaDoc>,
+) -> Option<(&'a LemmaDoc, LemmaType)> {
+    let fact_ref = FactReference {
+        reference: reference.to_vec(),
+    };
+    if let Some(declared) = doc.get_fact_type(&fact_ref) {
+        return Some((doc, declared));
+    }
+
+    let (first, rest) = reference.split_first()?;
+    if rest.is_empty() {
+        return None;
+    }
+    let name = crate::parse_collection_instance(first)
+        .map(|(collection, _)| collection)
+        .unwrap_or(first);
+
+    let referenced = doc
+        .facts
+        .iter()
+        .find_map(|fact| match (&fact.fact_type, &fact.value) {
+            (FactType::Local(n), FactValue::DocumentReference(target))
+            | (FactType::Local(n), FactValue::DocumentCollection(target))
+                if n == name =>
+            {
+                all_documents.get(target)
+            }
+            _ => None,
+        })?;
+    declaring_document(referenced, rest, all_documents)
+}
+
+/// Whether the fact at a path is marked `sensitive`, following document references
+///
+/// A document can mark a fact of a referenced document sensitive by
+/// overriding it: `fact employee.salary = 5000 EUR sensitive`.
+pub fn is_sensitive_fact(
+    doc: &LemmaDoc,
+    reference: &[String],
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> bool {
+    let path = reference.join(".");
+    if doc
+        .facts
+        .iter()
+        .any(|fact| fact.sensitive && fact_display_name(fact) == path)
+    {
+        return true;
+    }
+
+    let Some((first, rest)) = reference.split_first() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return false;
+    }
+    let name = crate::parse_collection_instance(first)
+        .map(|(collection, _)| collection)
+        .unwrap_or(first);
+    doc.facts
+        .iter()
+        .find_map(|fact| match (&fact.fact_type, &fact.value) {
+            (FactType::Local(n), FactValue::DocumentReference(target))
+            | (FactType::Local(n), FactValue::DocumentCollection(target))
+                if n == name =>
+            {
+                all_documents.get(target)
+            }
+            _ => None,
+        })
+        .is_some_and(|referenced| is_sensitive_fact(referenced, rest, all_documents))
+}
+
+/// Paths of every fact that can be overridden, e.g. `quantity` or `order.price`
+///
+/// Follows document references; facts inside collections are left out.
+pub fn fact_paths(doc: &LemmaDoc, all_documents: &HashMap<String, LemmaDoc>) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_fact_paths(doc, "", all_documents, &mut paths, 0);
+    paths
+}
+
+fn collect_fact_paths(
+    doc: &LemmaDoc,
+    prefix: &str,
+    all_documents: &HashMap<String, LemmaDoc>,
+    paths: &mut Vec<String>,
+    depth: usize,
+) {
+    if depth > MAX_INSTANCE_DEPTH {
+        return;
+    }
+    for fact in &doc.facts {
+        let path = match &fact.fact_type {
+            FactType::Local(name) => format!("{}{}", prefix, name),
+            FactType::Foreign(foreign) if fact_group(fact, doc).is_some() => {
+                format!("{}{}", prefix, foreign.reference.join("."))
+            }
+            FactType::Foreign(_) => continue,
+        };
+        match &fact.value {
+            FactValue::DocumentReference(target) => {
+                if let Some(referenced) = all_documents.get(target) {
+                    let prefix = format!("{}.", path);
+                    collect_fact_paths(referenced, &prefix, all_documents, paths, depth + 1);
+                }
+            }
+            FactValue::DocumentCollection(_) => {}
+            FactValue::Literal(_) | FactValue::TypeAnnotation(_) => paths.push(path),
+        }
+    }
+}
+
+/// The candidate closest to `name`, when it is close enough to be a typo
+///
+/// Allows one edit per three characters, and at least one.
+pub fn closest_match<'a>(name: &str, candidates: &'a [String]) -> Option<&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Levenshtein distance between two strings, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The currency a money fact must use, set by its declaring document's `currency` directive
+pub fn declared_currency(
+    doc: &LemmaDoc,
+    reference: &[String],
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> Option<MoneyUnit> {
+    match declaring_document(doc, reference, all_documents)? {
+        (declaring, LemmaType::Money) => declaring.currency.clone(),
+        _ => None,
+    }
+}
+
+/// Coerce a number given for a money fact to the declaring document's currency,
+/// and a year given for a date fact (`order_date=2026`) to that whole year
+pub fn coerce_fact_value(
+    doc: &LemmaDoc,
+    reference: &[String],
+    value: &LiteralValue,
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> Option<LiteralValue> {
+    match (declaring_document(doc, reference, all_documents)?, value) {
+        ((declaring, LemmaType::Money), _) => declaring.coerce_to_currency(value),
+        ((_, LemmaType::Date), LiteralValue::Number(year)) => {
+            crate::DateTimeValue::of_year_number(*year).map(LiteralValue::Date)
+        }
+        _ => None,
+    }
+}
+
+/// Check a fact value against the currency its declaring document requires
+///
+/// Returns an error message when money in another currency is given.
+pub fn check_fact_currency(
+    doc: &LemmaDoc,
+    reference: &[String],
+    value: &LiteralValue,
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> Result<(), String> {
+    match (value, declared_currency(doc, reference, all_documents)) {
+        (LiteralValue::Unit(NumericUnit::Money(_, used)), Some(currency)) if *used != currency => {
+            Err(format!("expected money in {}, got {}", currency, used))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check a fact value against the kind of percentage its fact declares
+///
+/// `5%` and `2 percentage points` are both percentages to the type system, but
+/// a fact holding one can't be given the other: they combine differently.
+pub fn check_percentage_points(
+    doc: &LemmaDoc,
+    reference: &[String],
+    value: &LiteralValue,
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> Result<(), String> {
+    let Some((declaring, LemmaType::Percentage)) =
+        declaring_document(doc, reference, all_documents)
+    else {
+        return Ok(());
+    };
+    let points_declared = declaring.facts.iter().any(|fact| {
+        let path = match &fact.fact_type {
+            FactType::Local(name) => std::slice::from_ref(name),
+            FactType::Foreign(foreign) => foreign.reference.as_slice(),
+        };
+        reference.ends_with(path)
+            && matches!(
+                fact.value,
+                FactValue::Literal(LiteralValue::PercentagePoints(_))
+            )
+    });
+    match (value, points_declared) {
+        (LiteralValue::PercentagePoints(_), false) => {
+            Err(format!("expected a percentage like 5%, got {}", value))
+        }
+        (LiteralValue::Percentage(_), true) => Err(format!(
+            "expected percentage points like 2 percentage points, got {}",
+            value
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Extract rule paths from an expression for dependency analysis across document references.
 ///
 /// Resolves rule references to `RulePath` instances that include the full
 /// fact traversal path (e.g., `employee.salary?` where `employee` is a fact
 /// referencing another document). Used internally by dependency graph building.
-fn extract_rule_paths(
+///
+/// Paths are resolved relative to `current_doc` and prefixed with `prefix`, the
+/// segments leading from the evaluated document to `current_doc`.
+pub(crate) fn extract_rule_paths(
     expr: &Expression,
     current_doc: &LemmaDoc,
+    prefix: &[RulePathSegment],
     all_documents: &HashMap<String, LemmaDoc>,
+    instances: &CollectionInstances,
     paths: &mut HashSet<RulePath>,
 ) -> LemmaResult<()> {
     match &expr.kind {
-        ExpressionKind::RuleReference(rule_ref) => {
+        ExpressionKind::RuleReference(rule_ref) | ExpressionKind::RuleHasValue(rule_ref) => {
             let path = RulePath::from_reference(&rule_ref.reference, current_doc, all_documents)?;
-            paths.insert(path);
+            paths.insert(path.with_prefix(prefix));
+        }
+        ExpressionKind::Aggregate(aggregate) if aggregate.rule.is_some() => {
+            paths.extend(aggregate_rule_paths(
+                aggregate,
+                current_doc,
+                prefix,
+                all_documents,
+                instances,
+            )?);
         }
         ExpressionKind::LogicalAnd(left, right)
         | ExpressionKind::LogicalOr(left, right)
         | ExpressionKind::Arithmetic(left, _, right)
         | ExpressionKind::Comparison(left, _, right) => {
-            extract_rule_paths(left, current_doc, all_documents, paths)?;
-            extract_rule_paths(right, current_doc, all_documents, paths)?;
+            extract_rule_paths(left, current_doc, prefix, all_documents, instances, paths)?;
+            extract_rule_paths(right, current_doc, prefix, all_documents, instances, paths)?;
         }
         ExpressionKind::UnitConversion(inner, _)
         | ExpressionKind::LogicalNegation(inner, _)
         | ExpressionKind::MathematicalOperator(_, inner) => {
-            extract_rule_paths(inner, current_doc, all_documents, paths)?;
+            extract_rule_paths(inner, current_doc, prefix, all_documents, instances, paths)?;
+        }
+        ExpressionKind::Range(range) => {
+            for operand in range.operands() {
+                extract_rule_paths(
+                    operand,
+                    current_doc,
+                    prefix,
+                    all_documents,
+                    instances,
+                    paths,
+                )?;
+            }
+        }
+        ExpressionKind::MissingItems(items)
+        | ExpressionKind::FunctionCall(_, items)
+        | ExpressionKind::Selection(_, items) => {
+            for item in items {
+                extract_rule_paths(item, current_doc, prefix, all_documents, instances, paths)?;
+            }
+        }
+        ExpressionKind::Veto(veto) => {
+            for argument in &veto.arguments {
+                extract_rule_paths(
+                    &argument.expression,
+                    current_doc,
+                    prefix,
+                    all_documents,
+                    instances,
+                    paths,
+                )?;
+            }
         }
         _ => {}
     }
     Ok(())
 }
 
+/// Resolve the rule paths an aggregate reads, one per collection instance
+///
+/// `sum of line_items.total?` with instances 0 and 1 gives
+/// `line_items[0].total` and `line_items[1].total`. For `count` (no rule) the
+/// instance paths are returned with an empty rule name.
+pub fn aggregate_rule_paths(
+    aggregate: &AggregateExpression,
+    current_doc: &LemmaDoc,
+    prefix: &[RulePathSegment],
+    all_documents: &HashMap<String, LemmaDoc>,
+    instances: &CollectionInstances,
+) -> LemmaResult<Vec<RulePath>> {
+    let (collection_name, doc_path) = aggregate
+        .collection
+        .split_last()
+        .ok_or_else(|| LemmaError::Engine("Aggregate without collection".to_string()))?;
+
+    // Resolve document references leading to the collection (e.g. `order.line_items`)
+    let mut reference = doc_path.to_vec();
+    reference.push(String::new());
+    let to_collection =
+        RulePath::from_reference(&reference, current_doc, all_documents)?.with_prefix(prefix);
+    let collection_doc = match to_collection.segments.last() {
+        Some(segment) => all_documents
+            .get(&segment.doc)
+            .ok_or_else(|| LemmaError::Engine(format!("Document {} not found", segment.doc)))?,
+        None => current_doc,
+    };
+
+    let item_doc = collection_doc
+        .facts
+        .iter()
+        .find_map(|fact| match (&fact.fact_type, &fact.value) {
+            (FactType::Local(name), FactValue::DocumentCollection(item_doc))
+                if name == collection_name =>
+            {
+                Some(item_doc.clone())
+            }
+            _ => None,
+        })
+        .ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "Fact {} is not a document collection",
+                aggregate.collection.join(".")
+            ))
+        })?;
+
+    let mut key: Vec<String> = to_collection
+        .segments
+        .iter()
+        .map(|segment| segment.fact.clone())
+        .collect();
+    key.push(collection_name.clone());
+
+    let indices = instances.get(&key).cloned().unwrap_or_default();
+    Ok(indices
+        .into_iter()
+        .map(|index| {
+            let mut segments = to_collection.segments.clone();
+            segments.push(RulePathSegment {
+                fact: crate::collection_instance_name(collection_name, index),
+                doc: item_doc.clone(),
+            });
+            RulePath {
+                rule: aggregate.rule.clone().unwrap_or_default(),
+                segments,
+            }
+        })
+        .collect())
+}
+
+/// Find the instances of every collection reachable from a document
+///
+/// Instances are created by facts addressing them, either overrides
+/// (`line_items[0].quantity=3`) or fact overrides inside documents.
+pub fn collection_instances(
+    doc: &LemmaDoc,
+    overrides: &[LemmaFact],
+    all_documents: &HashMap<String, LemmaDoc>,
+) -> CollectionInstances {
+    let mut instances = CollectionInstances::new();
+    record_instances(overrides, &[], &mut instances);
+    collect_doc_instances(doc, &[], all_documents, &mut instances, 0);
+    instances
+}
+
+/// Maximum document nesting followed when discovering collection instances or versions
+pub(crate) const MAX_INSTANCE_DEPTH: usize = 32;
+
+fn collect_doc_instances(
+    doc: &LemmaDoc,
+    prefix: &[String],
+    all_documents: &HashMap<String, LemmaDoc>,
+    instances: &mut CollectionInstances,
+    depth: usize,
+) {
+    if depth > MAX_INSTANCE_DEPTH {
+        return;
+    }
+    record_instances(&doc.facts, prefix, instances);
+
+    for fact in &doc.facts {
+        let FactType::Local(name) = &fact.fact_type else {
+            continue;
+        };
+        let mut path = prefix.to_vec();
+        path.push(name.clone());
+
+        match &fact.value {
+            FactValue::DocumentReference(doc_name) => {
+                if let Some(referenced) = all_documents.get(doc_name) {
+                    collect_doc_instances(referenced, &path, all_documents, instances, depth + 1);
+                }
+            }
+            FactValue::DocumentCollection(doc_name) => {
+                let (Some(item_doc), Some(indices)) =
+                    (all_documents.get(doc_name), instances.get(&path).cloned())
+                else {
+                    continue;
+                };
+                for index in indices {
+                    let mut instance_path = prefix.to_vec();
+                    instance_path.push(crate::collection_instance_name(name, index));
+                    collect_doc_instances(
+                        item_doc,
+                        &instance_path,
+                        all_documents,
+                        instances,
+                        depth + 1,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn record_instances(facts: &[LemmaFact], prefix: &[String], instances: &mut CollectionInstances) {
+    for fact in facts {
+        let FactType::Foreign(foreign) = &fact.fact_type else {
+            continue;
+        };
+        for (position, segment) in foreign.reference.iter().enumerate() {
+            if let Some((collection, index)) = crate::parse_collection_instance(segment) {
+                let mut key = prefix.to_vec();
+                key.extend_from_slice(&foreign.reference[..position]);
+                key.push(collection.to_string());
+                instances.entry(key).or_default().insert(index);
+            }
+        }
+    }
+}
+
 /// Build dependency graph for all reachable rules
 ///
 /// Starting from the document being evaluated, discovers all rules
@@ -234,14 +731,55 @@ fn extract_rule_paths(
 pub fn build_dependency_graph(
     doc: &LemmaDoc,
     documents: &HashMap<String, LemmaDoc>,
+) -> LemmaResult<HashMap<RulePath, HashSet<RulePath>>> {
+    build_dependency_graph_with_instances(
+        doc,
+        documents,
+        &CollectionInstances::new(),
+        &ResourceLimits::default(),
+    )
+}
+
+/// Build dependency graph, expanding aggregates over the given collection instances
+///
+/// Fails with `ResourceLimitExceeded` as soon as more document instances are
+/// reached than `max_documents_per_evaluation` allows, and once the graph is
+/// complete if a dependency chain is longer than `max_rule_dependency_depth`.
+pub fn build_dependency_graph_with_instances(
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    instances: &CollectionInstances,
+    limits: &ResourceLimits,
+) -> LemmaResult<HashMap<RulePath, HashSet<RulePath>>> {
+    build_dependency_graph_for_rules(doc, None, documents, instances, limits)
+}
+
+/// Build the dependency graph of only the named rules of `doc`
+///
+/// Rules of referenced documents are in the graph only when a rule in it
+/// references them, so a document using `shipping.cost?` gets none of the
+/// other rules of `shipping`. Names of rules `doc` doesn't have are ignored;
+/// None starts from every rule, like `build_dependency_graph_with_instances`.
+pub fn build_dependency_graph_for_rules(
+    doc: &LemmaDoc,
+    rule_names: Option<&[String]>,
+    documents: &HashMap<String, LemmaDoc>,
+    instances: &CollectionInstances,
+    limits: &ResourceLimits,
 ) -> LemmaResult<HashMap<RulePath, HashSet<RulePath>>> {
     use std::collections::VecDeque;
 
     let mut graph = HashMap::new();
     let mut queue = VecDeque::new();
+    let mut document_instances: HashSet<Vec<RulePathSegment>> = HashSet::new();
+    document_instances.insert(Vec::new());
 
-    // Start with rules from document being evaluated
-    for rule in &doc.rules {
+    // Start with the rules to evaluate of the document being evaluated
+    let roots = doc
+        .rules
+        .iter()
+        .filter(|rule| rule_names.is_none_or(|names| names.contains(&rule.name)));
+    for rule in roots {
         let path = RulePath {
             rule: rule.name.clone(),
             segments: vec![],
@@ -257,11 +795,22 @@ pub fn build_dependency_graph(
         }
 
         // Extract dependencies for this rule (single traversal)
+        // References inside a referenced document are relative to that document
+        let prefix = &path.segments;
         let mut dependencies = HashSet::new();
-        extract_rule_paths(&rule.expression, rule_doc, documents, &mut dependencies)?;
-        for uc in &rule.unless_clauses {
-            extract_rule_paths(&uc.condition, rule_doc, documents, &mut dependencies)?;
-            extract_rule_paths(&uc.result, rule_doc, documents, &mut dependencies)?;
+        for expr in std::iter::once(&rule.expression).chain(
+            rule.unless_clauses
+                .iter()
+                .flat_map(|uc| [&uc.condition, &uc.result]),
+        ) {
+            extract_rule_paths(
+                expr,
+                rule_doc,
+                prefix,
+                documents,
+                instances,
+                &mut dependencies,
+            )?;
         }
 
         // Store in graph
@@ -270,6 +819,27 @@ pub fn build_dependency_graph(
         // Queue dependencies for discovery
         for dep_path in dependencies {
             if !graph.contains_key(&dep_path) {
+                if document_instances.insert(dep_path.segments.clone())
+                    && document_instances.len() > limits.max_documents_per_evaluation
+                {
+                    return Err(LemmaError::ResourceLimitExceeded {
+                        limit_name: "max_documents_per_evaluation".to_string(),
+                        limit_value: format!("{} documents", limits.max_documents_per_evaluation),
+                        actual_value: format!(
+                            "more than {} documents",
+                            limits.max_documents_per_evaluation
+                        ),
+                        suggestion: format!(
+                            "Document '{}' reaches document '{}' through '{}'. Check for deeply \
+                             chained or repeated document references, or raise \
+                             max_documents_per_evaluation",
+                            doc.name,
+                            dep_path.target_doc(&doc.name),
+                            dep_path
+                        ),
+                    });
+                }
+
                 let target_doc_name = dep_path.target_doc(&doc.name);
                 let target_doc = documents.get(target_doc_name).ok_or_else(|| {
                     crate::LemmaError::Engine(format!(
@@ -294,5 +864,84 @@ pub fn build_dependency_graph(
         }
     }
 
+    check_dependency_depth(&graph, limits.max_rule_dependency_depth)?;
     Ok(graph)
 }
+
+/// Reject dependency chains longer than `max_depth` rule-to-rule hops
+///
+/// Computes the longest chain below each rule without recursion, so very deep
+/// chains can't overflow the stack. Cycles are left to the topological sort.
+fn check_dependency_depth(
+    graph: &HashMap<RulePath, HashSet<RulePath>>,
+    max_depth: usize,
+) -> LemmaResult<()> {
+    // Longest chain below a rule and the dependency it continues through
+    let mut depth: HashMap<&RulePath, (usize, Option<&RulePath>)> = HashMap::new();
+    let mut on_stack: HashSet<&RulePath> = HashSet::new();
+
+    let mut roots: Vec<&RulePath> = graph.keys().collect();
+    roots.sort_by_key(|path| path.to_string());
+
+    for root in roots {
+        let mut stack = vec![(root, false)];
+        while let Some((path, expanded)) = stack.pop() {
+            if expanded {
+                on_stack.remove(path);
+                let deepest = graph
+                    .get(path)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|dep| depth.get(dep).map(|(d, _)| (d + 1, dep)))
+                    .max_by(|a, b| {
+                        a.0.cmp(&b.0)
+                            .then_with(|| b.1.to_string().cmp(&a.1.to_string()))
+                    });
+                let entry = deepest.map_or((0, None), |(d, dep)| (d, Some(dep)));
+                if entry.0 > max_depth {
+                    return Err(dependency_depth_error(path, &depth, entry, max_depth));
+                }
+                depth.insert(path, entry);
+                continue;
+            }
+            if depth.contains_key(path) || !on_stack.insert(path) {
+                continue;
+            }
+            stack.push((path, true));
+            for dep in graph.get(path).into_iter().flatten() {
+                if !depth.contains_key(dep) && !on_stack.contains(dep) {
+                    stack.push((dep, false));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn dependency_depth_error(
+    path: &RulePath,
+    depth: &HashMap<&RulePath, (usize, Option<&RulePath>)>,
+    (actual, mut next): (usize, Option<&RulePath>),
+    max_depth: usize,
+) -> LemmaError {
+    let mut chain = vec![format!("{}?", path)];
+    while let Some(dep) = next {
+        if chain.len() == 4 {
+            chain.push("...".to_string());
+            break;
+        }
+        chain.push(format!("{}?", dep));
+        next = depth.get(dep).and_then(|(_, n)| *n);
+    }
+
+    LemmaError::ResourceLimitExceeded {
+        limit_name: "max_rule_dependency_depth".to_string(),
+        limit_value: format!("{} rules", max_depth),
+        actual_value: format!("{} rules", actual),
+        suggestion: format!(
+            "Rule dependency chain {} is too deep. Combine intermediate rules or raise \
+             max_rule_dependency_depth",
+            chain.join(" -> ")
+        ),
+    }
+}
@@ -26,6 +26,21 @@ impl Span {
             col,
         }
     }
+
+    /// The span of `pair`
+    ///
+    /// Looks the line up in the index pest keeps of the input, where
+    /// `from_pest_span` scans the input from the start for every span.
+    pub fn from_pair<R: pest::RuleType>(pair: &pest::iterators::Pair<R>) -> Self {
+        let span = pair.as_span();
+        let (line, col) = pair.line_col();
+        Self {
+            start: span.start(),
+            end: span.end(),
+            line,
+            col,
+        }
+    }
 }
 
 /// Unique identifier for each expression in the AST
@@ -0,0 +1,296 @@
+//! JSON export of a parsed document
+//!
+//! Gives analysis tools, visualizers and codemods the full AST of a document,
+//! with the source span and expression ID of every node, so they don't have
+//! to re-parse the grammar. The shape (`"format": "lemma-ast"`, version 1):
+//!
+//! - document: `name`, `source`, `start_line`, `partial`, `parameters`,
+//!   `template`, `commentary`, `declared_version` (`"2.1.0"` or null),
+//!   `currency`, `text_comparison`,
+//!   `division_by_zero` (`"error"`, `"unknown"` or `{"veto": message}`),
+//!   `facts`, `rules`, `checklists`, `invariants`, `records`
+//! - fact: `path` (`["customer", "age"]`), `local` (false for fact groups and
+//!   overrides of referenced documents), `value`, `example` (a literal or
+//!   null), `sensitive` and `span`
+//! - fact value: `{"kind": "literal", "value": ...}`, `{"kind": "type", "type": "number"}`,
+//!   `{"kind": "document", "document": ...}` or `{"kind": "collection", "document": ...}`
+//! - rule: `name`, `expression`, `unless_clauses` (`condition`, `result`,
+//!   `span`), `unless_mode` (`last_match` or `first_match`), `description`,
+//!   `visibility` (`public` or `internal`) and `span`
+//! - checklist: `name`, `items` and `span`; its derived rules are in `rules`
+//! - invariant: `number`, `rule` (`invariant.1`), `condition` and `span`; its
+//!   rule is in `rules`
+//! - record: `name`, `fields` (`name` and `rule`, e.g. `quote.total`) and
+//!   `span`; its field rules are in `rules`
+//! - expression: `id`, `span` and a `kind` with its own fields, listed in
+//!   `expression_json`
+//!
+//! Literals are `TypedValue`s. Spans are `{start, end, line, col}`, with byte
+//! offsets into the source and 1-based lines and columns, or null for nodes
+//! the parser derived (e.g. checklist rules).
+
+use crate::ast::Span;
+use crate::{
+    Expression, ExpressionKind, FactType, FactValue, LemmaChecklist, LemmaDoc, LemmaFact,
+    LemmaInvariant, LemmaRecord, LemmaRule, NegationType, TypeAnnotation, TypedValue, UnlessMode,
+};
+use serde_json::{json, Value};
+
+/// Version of the exported shape, raised on incompatible changes
+pub const AST_FORMAT_VERSION: u32 = 1;
+
+/// Export the AST of `doc` as JSON
+pub fn export_ast(doc: &LemmaDoc) -> Value {
+    json!({
+        "format": "lemma-ast",
+        "version": AST_FORMAT_VERSION,
+        "document": {
+            "name": doc.name,
+            "source": doc.source,
+            "start_line": doc.start_line,
+            "partial": doc.partial,
+            "parameters": doc.parameters,
+            "template": doc.template,
+            "commentary": doc.commentary,
+            "declared_version": doc.declared_version.map(|version| version.to_string()),
+            "currency": doc.currency.as_ref().map(|currency| currency.to_string()),
+            "text_comparison": doc.text_comparison,
+            "division_by_zero": doc.division_by_zero,
+            "facts": doc.facts.iter().map(fact_json).collect::<Vec<_>>(),
+            "rules": doc.rules.iter().map(rule_json).collect::<Vec<_>>(),
+            "checklists": doc.checklists.iter().map(checklist_json).collect::<Vec<_>>(),
+            "invariants": doc.invariants.iter().map(invariant_json).collect::<Vec<_>>(),
+            "records": doc.records.iter().map(record_json).collect::<Vec<_>>(),
+        }
+    })
+}
+
+fn fact_json(fact: &LemmaFact) -> Value {
+    let (path, local) = match &fact.fact_type {
+        FactType::Local(name) => (vec![name.clone()], true),
+        FactType::Foreign(foreign) => (foreign.reference.clone(), false),
+    };
+    let value = match &fact.value {
+        FactValue::Literal(literal) => {
+            json!({"kind": "literal", "value": TypedValue::from(literal)})
+        }
+        FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => {
+            json!({"kind": "type", "type": lemma_type.to_string()})
+        }
+        FactValue::DocumentReference(document) => json!({"kind": "document", "document": document}),
+        FactValue::DocumentCollection(document) => {
+            json!({"kind": "collection", "document": document})
+        }
+    };
+    json!({
+        "path": path,
+        "local": local,
+        "value": value,
+        "example": fact.example.as_ref().map(TypedValue::from),
+        "sensitive": fact.sensitive,
+        "span": span_json(&fact.span),
+    })
+}
+
+fn rule_json(rule: &LemmaRule) -> Value {
+    let unless_clauses: Vec<Value> = rule
+        .unless_clauses
+        .iter()
+        .map(|clause| {
+            json!({
+                "condition": expression_json(&clause.condition),
+                "result": expression_json(&clause.result),
+                "span": span_json(&clause.span),
+            })
+        })
+        .collect();
+    json!({
+        "name": rule.name,
+        "expression": expression_json(&rule.expression),
+        "unless_clauses": unless_clauses,
+        "unless_mode": match rule.unless_mode {
+            UnlessMode::LastMatch => "last_match",
+            UnlessMode::FirstMatch => "first_match",
+        },
+        "description": rule.description,
+        "visibility": rule.visibility,
+        "span": span_json(&rule.span),
+    })
+}
+
+fn checklist_json(checklist: &LemmaChecklist) -> Value {
+    json!({
+        "name": checklist.name,
+        "items": checklist.items.iter().map(expression_json).collect::<Vec<_>>(),
+        "span": span_json(&checklist.span),
+    })
+}
+
+fn record_json(record: &LemmaRecord) -> Value {
+    let fields: Vec<Value> = record
+        .fields
+        .iter()
+        .map(|field| json!({"name": field, "rule": record.field_rule_name(field)}))
+        .collect();
+    json!({
+        "name": record.name,
+        "fields": fields,
+        "span": span_json(&record.span),
+    })
+}
+
+fn invariant_json(invariant: &LemmaInvariant) -> Value {
+    json!({
+        "number": invariant.number,
+        "rule": invariant.rule_name(),
+        "condition": expression_json(&invariant.condition),
+        "span": span_json(&invariant.span),
+    })
+}
+
+/// An expression node
+///
+/// Kinds and their fields:
+/// - `literal`: `value`
+/// - `fact_reference`, `rule_reference`: `reference` (path segments)
+/// - `and`, `or`: `left`, `right`
+/// - `arithmetic`, `comparison`: `operator` (as written, e.g. `+`, `is not`), `left`, `right`
+/// - `range`: `value`, `low_operator` (`>=` or `>`), `low`, `high_operator` (`<=` or `<`), `high`
+/// - `fact_has_value`, `rule_has_value`: `reference`
+/// - `missing_items`: `items`
+/// - `unit_conversion`: `value`, `target` (a unit or `percentage`)
+/// - `negation`: `negation` (`not`, `have_not` or `not_have`), `operand`
+/// - `mathematical`: `operator` (e.g. `sqrt`), `operand`
+/// - `veto`: `message`, `translations` (`locale`, `message`), `arguments` (`placeholder`, `expression`)
+/// - `aggregate`: `function`, `collection` (path segments), `rule`, `fact`
+/// - `function_call`: `name`, `arguments`
+fn expression_json(expression: &Expression) -> Value {
+    let mut node = match &expression.kind {
+        ExpressionKind::Literal(literal) => {
+            json!({"kind": "literal", "value": TypedValue::from(literal)})
+        }
+        ExpressionKind::FactReference(fact_ref) => {
+            json!({"kind": "fact_reference", "reference": fact_ref.reference})
+        }
+        ExpressionKind::RuleReference(rule_ref) => {
+            json!({"kind": "rule_reference", "reference": rule_ref.reference})
+        }
+        ExpressionKind::LogicalAnd(left, right) => json!({
+            "kind": "and",
+            "left": expression_json(left),
+            "right": expression_json(right),
+        }),
+        ExpressionKind::LogicalOr(left, right) => json!({
+            "kind": "or",
+            "left": expression_json(left),
+            "right": expression_json(right),
+        }),
+        ExpressionKind::Arithmetic(left, operation, right) => json!({
+            "kind": "arithmetic",
+            "operator": operation.to_string(),
+            "left": expression_json(left),
+            "right": expression_json(right),
+        }),
+        ExpressionKind::Comparison(left, operator, right) => json!({
+            "kind": "comparison",
+            "operator": operator.to_string(),
+            "left": expression_json(left),
+            "right": expression_json(right),
+        }),
+        ExpressionKind::Range(range) => json!({
+            "kind": "range",
+            "value": expression_json(&range.value),
+            "low_operator": range.low_operator.to_string(),
+            "low": expression_json(&range.low),
+            "high_operator": range.high_operator.to_string(),
+            "high": expression_json(&range.high),
+        }),
+        ExpressionKind::FactHasAnyValue(fact_ref) => {
+            json!({"kind": "fact_has_value", "reference": fact_ref.reference})
+        }
+        ExpressionKind::RuleHasValue(rule_ref) => {
+            json!({"kind": "rule_has_value", "reference": rule_ref.reference})
+        }
+        ExpressionKind::MissingItems(items) => json!({
+            "kind": "missing_items",
+            "items": items.iter().map(expression_json).collect::<Vec<_>>(),
+        }),
+        ExpressionKind::UnitConversion(value, target) => json!({
+            "kind": "unit_conversion",
+            "value": expression_json(value),
+            "target": target.to_string(),
+        }),
+        ExpressionKind::LogicalNegation(operand, negation) => json!({
+            "kind": "negation",
+            "negation": match negation {
+                NegationType::Not => "not",
+                NegationType::HaveNot => "have_not",
+                NegationType::NotHave => "not_have",
+            },
+            "operand": expression_json(operand),
+        }),
+        ExpressionKind::MathematicalOperator(operator, operand) => json!({
+            "kind": "mathematical",
+            "operator": operator.to_string(),
+            "operand": expression_json(operand),
+        }),
+        ExpressionKind::Veto(veto) => {
+            let translations: Vec<Value> = veto
+                .translations
+                .iter()
+                .map(|t| json!({"locale": t.locale, "message": t.message}))
+                .collect();
+            let arguments: Vec<Value> = veto
+                .arguments
+                .iter()
+                .map(|argument| {
+                    json!({
+                        "placeholder": argument.placeholder,
+                        "expression": expression_json(&argument.expression),
+                    })
+                })
+                .collect();
+            json!({
+                "kind": "veto",
+                "message": veto.message,
+                "translations": translations,
+                "arguments": arguments,
+            })
+        }
+        ExpressionKind::Aggregate(aggregate) => json!({
+            "kind": "aggregate",
+            "function": aggregate.function.to_string(),
+            "collection": aggregate.collection,
+            "rule": aggregate.rule,
+            "fact": aggregate.fact,
+        }),
+        ExpressionKind::Selection(function, candidates) => json!({
+            "kind": "selection",
+            "function": function.to_string(),
+            "candidates": candidates.iter().map(expression_json).collect::<Vec<_>>(),
+        }),
+        ExpressionKind::FunctionCall(name, arguments) => json!({
+            "kind": "function_call",
+            "name": name,
+            "arguments": arguments.iter().map(expression_json).collect::<Vec<_>>(),
+        }),
+    };
+
+    if let Value::Object(fields) = &mut node {
+        fields.insert("id".to_string(), json!(expression.id.to_string()));
+        fields.insert("span".to_string(), span_json(&expression.span));
+    }
+    node
+}
+
+fn span_json(span: &Option<Span>) -> Value {
+    match span {
+        Some(span) => json!({
+            "start": span.start,
+            "end": span.end,
+            "line": span.line,
+            "col": span.col,
+        }),
+        None => Value::Null,
+    }
+}
@@ -0,0 +1,443 @@
+//! Building documents in code instead of concatenating Lemma source
+//!
+//! `DocBuilder` assembles a `LemmaDoc` from facts and rules, and `Expr`
+//! builds rule expressions with Rust operators:
+//!
+//! ```
+//! use lemma::builder::{DocBuilder, Expr};
+//! use lemma::{expr, Engine, LemmaType};
+//!
+//! let doc = DocBuilder::new("pricing")
+//!     .fact("price", LemmaType::Money)
+//!     .fact("quantity", LemmaType::Number)
+//!     .rule("total", expr! { price * quantity })
+//!     .rule("bulk", Expr::fact("quantity").gte(100))
+//!     .build()
+//!     .unwrap();
+//!
+//! let mut engine = Engine::new();
+//! engine.add_document(&doc, "pricing").unwrap();
+//! ```
+//!
+//! Mistakes such as an invalid name or an expression that doesn't parse are
+//! kept until `build`, which returns the first of them, so the chain never
+//! has to be interrupted. Operands are parenthesized where needed when the
+//! document is written out.
+
+use crate::rename::is_label;
+use crate::{
+    ArithmeticOperation, ComparisonOperator, Expression, ExpressionId, ExpressionKind,
+    FactReference, FactType, FactValue, LemmaDoc, LemmaError, LemmaFact, LemmaResult, LemmaRule,
+    LemmaType, LiteralValue, NegationType, ResourceLimits, RuleReference, RuleVisibility,
+    TypeAnnotation, UnlessClause, VetoExpression,
+};
+use rust_decimal::Decimal;
+
+/// Build an `Expr` from Lemma syntax written as Rust tokens
+///
+/// `expr! { price * quantity }` is `Expr::parse("price * quantity")`. The
+/// tokens are turned into text with `stringify!`, which may space them
+/// differently than written; use `Expr::parse` for anything it mangles,
+/// like percentages.
+#[macro_export]
+macro_rules! expr {
+    ($($tokens:tt)*) => {
+        $crate::builder::Expr::parse(stringify!($($tokens)*))
+    };
+}
+
+/// A rule expression being built
+///
+/// Holds the error of the first invalid part instead of an expression, which
+/// `DocBuilder::build` returns.
+#[derive(Debug, Clone)]
+pub struct Expr(LemmaResult<Expression>);
+
+impl Expr {
+    fn from_kind(kind: ExpressionKind) -> Self {
+        Expr(Ok(Expression::new(kind, None, ExpressionId::new(0))))
+    }
+
+    /// A reference to a fact, `price` or `order.price` through a document reference
+    pub fn fact(path: &str) -> Self {
+        match reference_path(path) {
+            Ok(reference) => {
+                Self::from_kind(ExpressionKind::FactReference(FactReference { reference }))
+            }
+            Err(error) => Expr(Err(error)),
+        }
+    }
+
+    /// A reference to a rule, `total` or `order.total`, written `total?`
+    pub fn rule(path: &str) -> Self {
+        match reference_path(path) {
+            Ok(reference) => {
+                Self::from_kind(ExpressionKind::RuleReference(RuleReference { reference }))
+            }
+            Err(error) => Expr(Err(error)),
+        }
+    }
+
+    /// A literal value
+    pub fn value(value: LiteralValue) -> Self {
+        Self::from_kind(ExpressionKind::Literal(value))
+    }
+
+    pub fn number(value: impl Into<Decimal>) -> Self {
+        Self::value(LiteralValue::Number(value.into()))
+    }
+
+    pub fn text(value: &str) -> Self {
+        Self::value(LiteralValue::Text(value.to_string()))
+    }
+
+    /// `veto "message"`, for rule results and unless results
+    pub fn veto(message: &str) -> Self {
+        Self::from_kind(ExpressionKind::Veto(VetoExpression {
+            message: Some(message.to_string()),
+            translations: Vec::new(),
+            arguments: Vec::new(),
+        }))
+    }
+
+    /// Parse an expression written in Lemma, e.g. `price * quantity in EUR`
+    pub fn parse(code: &str) -> Self {
+        Expr(parse_expression(code))
+    }
+
+    /// The built expression, or the error of its first invalid part
+    pub fn into_expression(self) -> LemmaResult<Expression> {
+        self.0
+    }
+
+    fn binary(
+        self,
+        other: impl Into<Expr>,
+        kind: impl FnOnce(Box<Expression>, Box<Expression>) -> ExpressionKind,
+    ) -> Self {
+        match (self.0, other.into().0) {
+            (Ok(left), Ok(right)) => Self::from_kind(kind(Box::new(left), Box::new(right))),
+            (Err(error), _) | (_, Err(error)) => Expr(Err(error)),
+        }
+    }
+
+    fn arithmetic(self, op: ArithmeticOperation, other: impl Into<Expr>) -> Self {
+        self.binary(other, |left, right| {
+            ExpressionKind::Arithmetic(left, op, right)
+        })
+    }
+
+    fn comparison(self, op: ComparisonOperator, other: impl Into<Expr>) -> Self {
+        self.binary(other, |left, right| {
+            ExpressionKind::Comparison(left, op, right)
+        })
+    }
+
+    pub fn pow(self, exponent: impl Into<Expr>) -> Self {
+        self.arithmetic(ArithmeticOperation::Power, exponent)
+    }
+
+    pub fn gt(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::GreaterThan, other)
+    }
+
+    pub fn gte(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::GreaterThanOrEqual, other)
+    }
+
+    pub fn lt(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::LessThan, other)
+    }
+
+    pub fn lte(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::LessThanOrEqual, other)
+    }
+
+    /// `==`
+    pub fn equals(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::Equal, other)
+    }
+
+    /// `!=`
+    pub fn not_equals(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::NotEqual, other)
+    }
+
+    pub fn is(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::Is, other)
+    }
+
+    pub fn is_not(self, other: impl Into<Expr>) -> Self {
+        self.comparison(ComparisonOperator::IsNot, other)
+    }
+
+    pub fn and(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, ExpressionKind::LogicalAnd)
+    }
+
+    pub fn or(self, other: impl Into<Expr>) -> Self {
+        self.binary(other, ExpressionKind::LogicalOr)
+    }
+}
+
+macro_rules! arithmetic_operator {
+    ($trait:ident, $method:ident, $op:expr) => {
+        impl<T: Into<Expr>> std::ops::$trait<T> for Expr {
+            type Output = Expr;
+
+            fn $method(self, other: T) -> Expr {
+                self.arithmetic($op, other)
+            }
+        }
+    };
+}
+
+arithmetic_operator!(Add, add, ArithmeticOperation::Add);
+arithmetic_operator!(Sub, sub, ArithmeticOperation::Subtract);
+arithmetic_operator!(Mul, mul, ArithmeticOperation::Multiply);
+arithmetic_operator!(Div, div, ArithmeticOperation::Divide);
+arithmetic_operator!(Rem, rem, ArithmeticOperation::Modulo);
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        match self.0 {
+            Ok(expr) => Self::from_kind(ExpressionKind::LogicalNegation(
+                Box::new(expr),
+                NegationType::Not,
+            )),
+            Err(error) => Expr(Err(error)),
+        }
+    }
+}
+
+impl From<LiteralValue> for Expr {
+    fn from(value: LiteralValue) -> Self {
+        Expr::value(value)
+    }
+}
+
+impl From<i32> for Expr {
+    fn from(value: i32) -> Self {
+        Expr::number(value)
+    }
+}
+
+impl From<i64> for Expr {
+    fn from(value: i64) -> Self {
+        Expr::number(value)
+    }
+}
+
+impl From<Decimal> for Expr {
+    fn from(value: Decimal) -> Self {
+        Expr::number(value)
+    }
+}
+
+impl From<bool> for Expr {
+    fn from(value: bool) -> Self {
+        Expr::value(LiteralValue::Boolean(value))
+    }
+}
+
+impl From<Expression> for Expr {
+    fn from(expression: Expression) -> Self {
+        Expr(Ok(expression))
+    }
+}
+
+/// A rule with unless clauses, a description or internal visibility
+#[derive(Debug, Clone)]
+pub struct RuleBuilder {
+    name: String,
+    expression: Expr,
+    unless: Vec<(Expr, Expr)>,
+    description: Option<String>,
+    visibility: RuleVisibility,
+}
+
+impl RuleBuilder {
+    pub fn new(name: &str, expression: impl Into<Expr>) -> Self {
+        Self {
+            name: name.to_string(),
+            expression: expression.into(),
+            unless: Vec::new(),
+            description: None,
+            visibility: RuleVisibility::Public,
+        }
+    }
+
+    /// `unless condition then result`; the last clause that holds wins
+    pub fn unless(mut self, condition: impl Into<Expr>, result: impl Into<Expr>) -> Self {
+        self.unless.push((condition.into(), result.into()));
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+
+    /// Hide the rule from responses, see `RuleVisibility::Internal`
+    pub fn internal(mut self) -> Self {
+        self.visibility = RuleVisibility::Internal;
+        self
+    }
+
+    fn build(self) -> LemmaResult<LemmaRule> {
+        check_name(&self.name)?;
+        let mut rule =
+            LemmaRule::new(self.name, self.expression.0?).with_visibility(self.visibility);
+        if let Some(description) = self.description {
+            rule = rule.with_description(description);
+        }
+        for (condition, result) in self.unless {
+            rule = rule.add_unless_clause(UnlessClause {
+                condition: condition.0?,
+                result: result.0?,
+                span: None,
+            });
+        }
+        Ok(rule)
+    }
+}
+
+/// A document being built, see the module documentation
+#[derive(Debug, Clone)]
+pub struct DocBuilder {
+    doc: LemmaDoc,
+    error: Option<LemmaError>,
+}
+
+impl DocBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            doc: LemmaDoc::new(name.to_string()),
+            error: check_name(name).err(),
+        }
+    }
+
+    pub fn commentary(mut self, commentary: &str) -> Self {
+        self.doc = self.doc.set_commentary(commentary.to_string());
+        self
+    }
+
+    /// A fact to be provided, `fact price = [money]`
+    pub fn fact(self, name: &str, lemma_type: LemmaType) -> Self {
+        self.add_fact(
+            name,
+            FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)),
+        )
+    }
+
+    /// A fact with a value, `fact quantity = 5`
+    pub fn fact_value(self, name: &str, value: LiteralValue) -> Self {
+        self.add_fact(name, FactValue::Literal(value))
+    }
+
+    /// A reference to another document, `fact order = doc order`
+    pub fn fact_doc(self, name: &str, doc_name: &str) -> Self {
+        let builder = self.add_fact(name, FactValue::DocumentReference(doc_name.to_string()));
+        builder.check(check_name(doc_name))
+    }
+
+    /// A rule computing `expression`
+    pub fn rule(self, name: &str, expression: impl Into<Expr>) -> Self {
+        self.rule_with(RuleBuilder::new(name, expression))
+    }
+
+    /// A rule with unless clauses or other settings
+    pub fn rule_with(mut self, rule: RuleBuilder) -> Self {
+        let rule = self.check_unique(&rule.name).and_then(|_| rule.build());
+        if let Some(rule) = self.keep(rule) {
+            self.doc = self.doc.add_rule(rule);
+        }
+        self
+    }
+
+    /// The document, or the first mistake made building it
+    pub fn build(self) -> LemmaResult<LemmaDoc> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.doc),
+        }
+    }
+
+    fn add_fact(mut self, name: &str, value: FactValue) -> Self {
+        let checked = check_name(name).and_then(|_| self.check_unique(name));
+        if self.keep(checked).is_some() {
+            let fact = LemmaFact::new(FactType::Local(name.to_string()), value);
+            self.doc = self.doc.add_fact(fact);
+        }
+        self
+    }
+
+    fn check_unique(&self, name: &str) -> LemmaResult<()> {
+        let taken =
+            self.doc.facts.iter().any(
+                |fact| matches!(&fact.fact_type, FactType::Local(fact_name) if fact_name == name),
+            ) || self.doc.rules.iter().any(|rule| rule.name == name);
+        if taken {
+            return Err(LemmaError::Engine(format!(
+                "Document '{}' already has a fact or rule named '{}'",
+                self.doc.name, name
+            )));
+        }
+        Ok(())
+    }
+
+    fn check(mut self, result: LemmaResult<()>) -> Self {
+        self.keep(result);
+        self
+    }
+
+    /// The value, or None after keeping the error if it's the first
+    fn keep<T>(&mut self, result: LemmaResult<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.error.get_or_insert(error);
+                None
+            }
+        }
+    }
+}
+
+fn check_name(name: &str) -> LemmaResult<()> {
+    if is_label(name) {
+        Ok(())
+    } else {
+        Err(LemmaError::Engine(format!(
+            "'{}' is not a valid name: use a letter followed by letters, digits or underscores, and no keyword",
+            name
+        )))
+    }
+}
+
+fn reference_path(path: &str) -> LemmaResult<Vec<String>> {
+    let segments: Vec<String> = path.split('.').map(str::to_string).collect();
+    for segment in &segments {
+        check_name(segment)?;
+    }
+    Ok(segments)
+}
+
+/// Parse `code` as the expression of a rule in a throwaway document
+fn parse_expression(code: &str) -> LemmaResult<Expression> {
+    let wrapped = format!("doc expression\nrule expression = {}\n", code);
+    let mut docs = crate::parse(&wrapped, None, &ResourceLimits::default())
+        .map_err(|e| LemmaError::Engine(format!("Invalid expression '{}': {}", code, e)))?;
+    let single_rule = docs.len() == 1
+        && docs[0].facts.is_empty()
+        && docs[0].rules.len() == 1
+        && docs[0].rules[0].unless_clauses.is_empty();
+    if !single_rule {
+        return Err(LemmaError::Engine(format!(
+            "Invalid expression '{}': expected a single expression",
+            code
+        )));
+    }
+    Ok(docs.remove(0).rules.remove(0).expression)
+}
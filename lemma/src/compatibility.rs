@@ -0,0 +1,389 @@
+//! Compatibility of document versions
+//!
+//! A document's interface is what callers rely on: the facts they give and the
+//! rules they read. `check_compatibility` classifies each change between two
+//! versions of a document by the version bump it needs:
+//!
+//! - major: a fact or public rule is removed or renamed, a fact changes type,
+//!   a public rule becomes internal, or the currency changes
+//! - minor: a fact or public rule is added, a fact's value changes, or the
+//!   `compare text` or `on division by zero` directive changes
+//! - patch: a rule's expression, a checklist item, an invariant, an internal
+//!   rule or the commentary changes
+//!
+//! When both versions declare `version MAJOR.MINOR.PATCH`, the report also
+//! tells whether the new version number covers the changes, so CI can reject
+//! breaking changes released as a minor or patch version.
+
+use crate::diff::{Change, ItemKind};
+use crate::{FactValue, LemmaDoc, LemmaFact, LemmaRule, SemanticVersion, TypeAnnotation};
+use serde::Serialize;
+
+/// The part of a version number a change needs to increase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// The version after `version` with this bump: 2.1.3 becomes 3.0.0, 2.2.0 or 2.1.4
+    pub fn apply(self, version: SemanticVersion) -> SemanticVersion {
+        match self {
+            Bump::Major => SemanticVersion::new(version.major + 1, 0, 0),
+            Bump::Minor => SemanticVersion::new(version.major, version.minor + 1, 0),
+            Bump::Patch => SemanticVersion::new(version.major, version.minor, version.patch + 1),
+        }
+    }
+
+    /// The bump from `old` to `new`, None when `new` isn't higher
+    pub fn between(old: SemanticVersion, new: SemanticVersion) -> Option<Bump> {
+        if new <= old {
+            None
+        } else if new.major > old.major {
+            Some(Bump::Major)
+        } else if new.minor > old.minor {
+            Some(Bump::Minor)
+        } else {
+            Some(Bump::Patch)
+        }
+    }
+}
+
+impl std::fmt::Display for Bump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Bump::Patch => write!(f, "patch"),
+            Bump::Minor => write!(f, "minor"),
+            Bump::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// One change to a document's interface
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatibilityChange {
+    pub kind: ItemKind,
+    /// The fact path, rule name, directive, or the invariant's condition
+    pub name: String,
+    pub change: Change,
+    pub bump: Bump,
+    /// Why the change needs this bump, e.g. `changed type from number to money`
+    pub reason: String,
+}
+
+/// The changes between two versions of a document and the bump they need
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CompatibilityReport {
+    pub doc_name: String,
+    pub old_version: Option<SemanticVersion>,
+    pub new_version: Option<SemanticVersion>,
+    /// The largest bump any change needs, None when nothing changed
+    pub required: Option<Bump>,
+    pub changes: Vec<CompatibilityChange>,
+}
+
+impl CompatibilityReport {
+    /// Why the declared versions don't cover the changes, None when they do
+    ///
+    /// Documents declaring no version in either version aren't checked.
+    /// Dropping the declaration is a problem, as is lowering the version.
+    pub fn problem(&self) -> Option<String> {
+        let required = self.required?;
+        let old = self.old_version?;
+        let Some(new) = self.new_version else {
+            return Some(format!(
+                "Document '{}' no longer declares a version, it was {}",
+                self.doc_name, old
+            ));
+        };
+        if new < old {
+            return Some(format!(
+                "Document '{}' went from version {} down to {}",
+                self.doc_name, old, new
+            ));
+        }
+        match Bump::between(old, new) {
+            Some(declared) if declared >= required => None,
+            _ => {
+                let reasons: Vec<String> = self
+                    .changes
+                    .iter()
+                    .filter(|change| change.bump == required)
+                    .map(|change| {
+                        format!(
+                            "{} '{}' {}",
+                            kind_name(change.kind),
+                            change.name,
+                            change.reason
+                        )
+                    })
+                    .collect();
+                Some(format!(
+                    "Document '{}' needs a {} version bump ({}), but went from {} to {}: declare version {}",
+                    self.doc_name,
+                    required,
+                    reasons.join(", "),
+                    old,
+                    new,
+                    required.apply(old)
+                ))
+            }
+        }
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.problem().is_none()
+    }
+}
+
+/// Classify the changes from `old` to `new`, two versions of one document
+pub fn check_compatibility(old: &LemmaDoc, new: &LemmaDoc) -> CompatibilityReport {
+    let mut changes = Vec::new();
+    directive_changes(old, new, &mut changes);
+    fact_changes(old, new, &mut changes);
+    rule_changes(old, new, &mut changes);
+    invariant_changes(old, new, &mut changes);
+
+    CompatibilityReport {
+        doc_name: new.name.clone(),
+        old_version: old.declared_version,
+        new_version: new.declared_version,
+        required: changes.iter().map(|change| change.bump).max(),
+        changes,
+    }
+}
+
+fn push(
+    changes: &mut Vec<CompatibilityChange>,
+    kind: ItemKind,
+    name: &str,
+    change: Change,
+    bump: Bump,
+    reason: impl Into<String>,
+) {
+    changes.push(CompatibilityChange {
+        kind,
+        name: name.to_string(),
+        change,
+        bump,
+        reason: reason.into(),
+    });
+}
+
+fn change_of<T: PartialEq>(old: &Option<T>, new: &Option<T>) -> Option<Change> {
+    match (old, new) {
+        (None, Some(_)) => Some(Change::Added),
+        (Some(_), None) => Some(Change::Removed),
+        (Some(old), Some(new)) if old != new => Some(Change::Changed),
+        _ => None,
+    }
+}
+
+fn directive_changes(old: &LemmaDoc, new: &LemmaDoc, changes: &mut Vec<CompatibilityChange>) {
+    if let Some(change) = change_of(&old.currency, &new.currency) {
+        push(
+            changes,
+            ItemKind::Directive,
+            "currency",
+            change,
+            Bump::Major,
+            "changes the currency of money facts and results",
+        );
+    }
+    if old.text_comparison != new.text_comparison {
+        push(
+            changes,
+            ItemKind::Directive,
+            "compare text",
+            Change::Changed,
+            Bump::Minor,
+            "changes how text compares",
+        );
+    }
+    if old.division_by_zero != new.division_by_zero {
+        push(
+            changes,
+            ItemKind::Directive,
+            "on division by zero",
+            Change::Changed,
+            Bump::Minor,
+            "changes what rules give when dividing by zero",
+        );
+    }
+    if let Some(change) = change_of(&old.commentary, &new.commentary) {
+        push(
+            changes,
+            ItemKind::Directive,
+            "commentary",
+            change,
+            Bump::Patch,
+            "is documentation only",
+        );
+    }
+}
+
+fn fact_changes(old: &LemmaDoc, new: &LemmaDoc, changes: &mut Vec<CompatibilityChange>) {
+    for fact in &new.facts {
+        let name = crate::analysis::fact_display_name(fact);
+        match find_fact(old, &name) {
+            None => push(
+                changes,
+                ItemKind::Fact,
+                &name,
+                Change::Added,
+                Bump::Minor,
+                "was added",
+            ),
+            Some(previous) if previous.value != fact.value => {
+                let (old_type, new_type) = (fact_type(&previous.value), fact_type(&fact.value));
+                if old_type != new_type {
+                    push(
+                        changes,
+                        ItemKind::Fact,
+                        &name,
+                        Change::Changed,
+                        Bump::Major,
+                        format!("changed type from {} to {}", old_type, new_type),
+                    );
+                } else {
+                    push(
+                        changes,
+                        ItemKind::Fact,
+                        &name,
+                        Change::Changed,
+                        Bump::Minor,
+                        "changed value",
+                    );
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    for fact in &old.facts {
+        let name = crate::analysis::fact_display_name(fact);
+        if find_fact(new, &name).is_none() {
+            push(
+                changes,
+                ItemKind::Fact,
+                &name,
+                Change::Removed,
+                Bump::Major,
+                "was removed or renamed",
+            );
+        }
+    }
+}
+
+fn find_fact<'a>(doc: &'a LemmaDoc, name: &str) -> Option<&'a LemmaFact> {
+    doc.facts
+        .iter()
+        .find(|fact| crate::analysis::fact_display_name(fact) == name)
+}
+
+/// The type of a fact's value, `doc pricing` for document references
+fn fact_type(value: &FactValue) -> String {
+    match value {
+        FactValue::Literal(literal) => literal.to_type().to_string(),
+        FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => lemma_type.to_string(),
+        FactValue::DocumentReference(name) => format!("doc {}", name),
+        FactValue::DocumentCollection(name) => format!("many doc {}", name),
+    }
+}
+
+/// Rules, including those derived from checklists and records
+///
+/// Internal rules aren't part of the interface: adding or removing them is a
+/// patch. Invariant rules are numbered by position, so they are compared by
+/// condition instead.
+fn rule_changes(old: &LemmaDoc, new: &LemmaDoc, changes: &mut Vec<CompatibilityChange>) {
+    let (old_rules, new_rules) = (interface_rules(old), interface_rules(new));
+    for rule in &new_rules {
+        let (change, bump, reason) =
+            match old_rules.iter().find(|previous| previous.name == rule.name) {
+                None if rule.is_internal() => (Change::Added, Bump::Patch, "was added as internal"),
+                None => (Change::Added, Bump::Minor, "was added"),
+                Some(previous) if !previous.is_internal() && rule.is_internal() => {
+                    (Change::Changed, Bump::Major, "became internal")
+                }
+                Some(previous) if previous.is_internal() && !rule.is_internal() => {
+                    (Change::Changed, Bump::Minor, "became public")
+                }
+                Some(previous) if previous.to_string() != rule.to_string() => {
+                    (Change::Changed, Bump::Patch, "was changed")
+                }
+                Some(_) => continue,
+            };
+        push(changes, ItemKind::Rule, &rule.name, change, bump, reason);
+    }
+    for rule in &old_rules {
+        if !new_rules.iter().any(|current| current.name == rule.name) {
+            let (bump, reason) = if rule.is_internal() {
+                (Bump::Patch, "was removed, it was internal")
+            } else {
+                (Bump::Major, "was removed or renamed")
+            };
+            push(
+                changes,
+                ItemKind::Rule,
+                &rule.name,
+                Change::Removed,
+                bump,
+                reason,
+            );
+        }
+    }
+}
+
+fn interface_rules(doc: &LemmaDoc) -> Vec<&LemmaRule> {
+    doc.rules
+        .iter()
+        .filter(|rule| doc.invariant_of(&rule.name).is_none())
+        .collect()
+}
+
+fn invariant_changes(old: &LemmaDoc, new: &LemmaDoc, changes: &mut Vec<CompatibilityChange>) {
+    let conditions = |doc: &'_ LemmaDoc| -> Vec<String> {
+        doc.invariants
+            .iter()
+            .map(|invariant| invariant.condition.to_string())
+            .collect()
+    };
+    let (old_conditions, new_conditions) = (conditions(old), conditions(new));
+    for condition in &new_conditions {
+        if !old_conditions.contains(condition) {
+            push(
+                changes,
+                ItemKind::Invariant,
+                condition,
+                Change::Added,
+                Bump::Patch,
+                "was added",
+            );
+        }
+    }
+    for condition in &old_conditions {
+        if !new_conditions.contains(condition) {
+            push(
+                changes,
+                ItemKind::Invariant,
+                condition,
+                Change::Removed,
+                Bump::Patch,
+                "was removed",
+            );
+        }
+    }
+}
+
+fn kind_name(kind: ItemKind) -> &'static str {
+    match kind {
+        ItemKind::Directive => "directive",
+        ItemKind::Fact => "fact",
+        ItemKind::Rule => "rule",
+        ItemKind::Checklist => "checklist",
+        ItemKind::Invariant => "invariant",
+    }
+}
@@ -0,0 +1,268 @@
+//! Semantic differences between two versions of a workspace
+//!
+//! Documents are compared item by item: directives, facts, rules, checklists
+//! and invariants, each written out as Lemma code. Whether the changes keep
+//! the document's interface compatible is up to `crate::compatibility`. Formatting, comments and
+//! the order of items don't count as changes. Documents that aren't changed
+//! themselves but reference a changed document, directly or through others,
+//! are listed as affected, since their results may change too.
+
+use crate::{FactValue, LemmaDoc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// What happened to a document or item between the old and new version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    /// Commentary, `version`, `currency`, `compare text` or `on division by zero`
+    Directive,
+    Fact,
+    Rule,
+    Checklist,
+    Invariant,
+}
+
+/// One changed item of a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ItemDiff {
+    pub kind: ItemKind,
+    /// The fact path, rule or checklist name, directive, or the invariant's condition
+    pub name: String,
+    pub change: Change,
+    /// The item as Lemma code in the old version, None when added
+    pub old: Option<String>,
+    /// The item as Lemma code in the new version, None when removed
+    pub new: Option<String>,
+}
+
+/// A document that was added, removed or changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocumentDiff {
+    pub doc_name: String,
+    pub change: Change,
+    /// Changed items in the order of the new version, then removed ones
+    pub items: Vec<ItemDiff>,
+}
+
+/// An unchanged document whose results may change with the documents it references
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AffectedDocument {
+    pub doc_name: String,
+    /// Changed documents it references, directly or through other documents
+    pub through: Vec<String>,
+}
+
+/// Differences between two versions of a workspace, see `diff_workspaces`
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct WorkspaceDiff {
+    /// Changed documents, by name
+    pub documents: Vec<DocumentDiff>,
+    /// Unchanged documents of the new version referencing changed ones, by name
+    pub affected: Vec<AffectedDocument>,
+}
+
+impl WorkspaceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+}
+
+/// Compare the documents of an old and a new version of a workspace
+pub fn diff_workspaces(
+    old: &HashMap<String, LemmaDoc>,
+    new: &HashMap<String, LemmaDoc>,
+) -> WorkspaceDiff {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    let documents: Vec<DocumentDiff> = names
+        .into_iter()
+        .filter_map(|name| diff_document(name, old.get(name), new.get(name)))
+        .collect();
+
+    let changed: BTreeSet<&str> = documents.iter().map(|d| d.doc_name.as_str()).collect();
+    let mut affected: Vec<AffectedDocument> = new
+        .keys()
+        .filter(|name| !changed.contains(name.as_str()))
+        .filter_map(|name| {
+            let through: Vec<String> = referenced_documents(name, new)
+                .into_iter()
+                .filter(|referenced| changed.contains(referenced.as_str()))
+                .collect();
+            (!through.is_empty()).then(|| AffectedDocument {
+                doc_name: name.clone(),
+                through,
+            })
+        })
+        .collect();
+    affected.sort_by(|a, b| a.doc_name.cmp(&b.doc_name));
+
+    WorkspaceDiff {
+        documents,
+        affected,
+    }
+}
+
+fn diff_document(
+    name: &str,
+    old: Option<&LemmaDoc>,
+    new: Option<&LemmaDoc>,
+) -> Option<DocumentDiff> {
+    let old_items = old.map(items).unwrap_or_default();
+    let new_items = new.map(items).unwrap_or_default();
+    let find = |items: &[Item], item: &Item| {
+        items
+            .iter()
+            .find(|other| other.kind == item.kind && other.name == item.name)
+            .map(|other| other.code.clone())
+    };
+
+    let mut diffs = Vec::new();
+    for item in &new_items {
+        let change = match find(&old_items, item) {
+            None => Change::Added,
+            Some(code) if code != item.code => Change::Changed,
+            Some(_) => continue,
+        };
+        diffs.push(ItemDiff {
+            kind: item.kind,
+            name: item.name.clone(),
+            change,
+            old: find(&old_items, item),
+            new: Some(item.code.clone()),
+        });
+    }
+    for item in &old_items {
+        if find(&new_items, item).is_none() {
+            diffs.push(ItemDiff {
+                kind: item.kind,
+                name: item.name.clone(),
+                change: Change::Removed,
+                old: Some(item.code.clone()),
+                new: None,
+            });
+        }
+    }
+
+    let change = match (old, new) {
+        (None, Some(_)) => Change::Added,
+        (Some(_), None) => Change::Removed,
+        _ if diffs.is_empty() => return None,
+        _ => Change::Changed,
+    };
+    Some(DocumentDiff {
+        doc_name: name.to_string(),
+        change,
+        items: diffs,
+    })
+}
+
+/// A directive, fact, rule, checklist or invariant written out as code
+struct Item {
+    kind: ItemKind,
+    name: String,
+    code: String,
+}
+
+fn items(doc: &LemmaDoc) -> Vec<Item> {
+    let item = |kind, name: &str, code: String| Item {
+        kind,
+        name: name.to_string(),
+        code: code.trim_end().to_string(),
+    };
+    let mut items = Vec::new();
+
+    if let Some(commentary) = &doc.commentary {
+        items.push(item(
+            ItemKind::Directive,
+            "commentary",
+            format!("\"\"\"{}\n\"\"\"", commentary),
+        ));
+    }
+    if let Some(version) = &doc.declared_version {
+        items.push(item(
+            ItemKind::Directive,
+            "version",
+            format!("version {}", version),
+        ));
+    }
+    if let Some(currency) = &doc.currency {
+        items.push(item(
+            ItemKind::Directive,
+            "currency",
+            format!("currency {}", currency),
+        ));
+    }
+    if !doc.text_comparison.is_exact() {
+        items.push(item(
+            ItemKind::Directive,
+            "compare text",
+            doc.text_comparison.to_string(),
+        ));
+    }
+    if doc.division_by_zero != crate::DivisionByZero::Error {
+        items.push(item(
+            ItemKind::Directive,
+            "on division by zero",
+            doc.division_by_zero.to_string(),
+        ));
+    }
+
+    for fact in &doc.facts {
+        let name = crate::analysis::fact_display_name(fact);
+        items.push(item(ItemKind::Fact, &name, fact.to_string()));
+    }
+    for rule in &doc.rules {
+        // Derived rules change with their checklist, invariant or record
+        if !doc.is_derived_rule(&rule.name) {
+            items.push(item(ItemKind::Rule, &rule.name, rule.to_string()));
+        }
+    }
+    for record in &doc.records {
+        items.push(item(ItemKind::Rule, &record.name, doc.record_code(record)));
+    }
+    for checklist in &doc.checklists {
+        items.push(item(
+            ItemKind::Checklist,
+            &checklist.name,
+            checklist.to_string(),
+        ));
+    }
+    for invariant in &doc.invariants {
+        let condition = invariant.condition.to_string();
+        items.push(item(
+            ItemKind::Invariant,
+            &condition,
+            format!("invariant {}", condition),
+        ));
+    }
+    items
+}
+
+/// Names of the documents `doc_name` references, directly or through others, sorted
+fn referenced_documents(doc_name: &str, documents: &HashMap<String, LemmaDoc>) -> Vec<String> {
+    let mut found = BTreeMap::new();
+    let mut pending = vec![doc_name.to_string()];
+    while let Some(name) = pending.pop() {
+        let Some(doc) = documents.get(&name) else {
+            continue;
+        };
+        for fact in &doc.facts {
+            if let FactValue::DocumentReference(target) | FactValue::DocumentCollection(target) =
+                &fact.value
+            {
+                if target != doc_name && found.insert(target.clone(), ()).is_none() {
+                    pending.push(target.clone());
+                }
+            }
+        }
+    }
+    found.into_keys().collect()
+}
@@ -1,6 +1,34 @@
 use crate::evaluator::Evaluator;
-use crate::{parse, LemmaDoc, LemmaError, LemmaResult, ResourceLimits, Response, Validator};
-use std::collections::HashMap;
+use crate::functions::FunctionRegistry;
+use crate::{
+    parse, CancellationToken, DocumentLoader, EvaluationOptions, FactParseError, FactTable,
+    LemmaDoc, LemmaError, LemmaResult, LoadedCode, ResourceLimits, Response, Validator,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// What adding code changed, returned by `Engine::add_lemma_code`
+///
+/// Adding code only replaces the documents it declares: documents other code
+/// added under the same source stay loaded. So `removed` lists template copies
+/// no longer referenced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadDelta {
+    /// Documents that weren't loaded before
+    pub added: Vec<String>,
+    /// Documents whose facts or rules, or those of a document they reference, changed
+    pub updated: Vec<String>,
+    /// Documents that are no longer loaded
+    pub removed: Vec<String>,
+}
+
+impl LoadDelta {
+    /// Whether nothing changed, as when adding code that is already loaded
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
 
 /// Engine for evaluating Lemma rules
 ///
@@ -11,6 +39,42 @@ pub struct Engine {
     validator: Validator,
     evaluator: Evaluator,
     limits: ResourceLimits,
+    /// Content hash of each document, refreshed whenever documents change
+    versions: HashMap<String, String>,
+    /// Non-fatal findings per document from the last validation, added to
+    /// every response so evaluations don't look for them again
+    warnings: HashMap<String, Vec<String>>,
+    /// Documents each source declared when its code was last loaded
+    declared: HashMap<String, Vec<String>>,
+    /// Parts of partial documents per source, merged again whenever a source
+    /// is loaded, edited or removed
+    partial_parts: HashMap<String, Vec<LemmaDoc>>,
+    functions: FunctionRegistry,
+    /// Most sources kept, see `with_retained_sources`
+    max_sources: Option<usize>,
+    /// When each retained source was last loaded or evaluated
+    source_uses: HashMap<String, AtomicU64>,
+    source_clock: AtomicU64,
+    /// Sources whose code was dropped to stay within `max_sources`
+    evicted_sources: BTreeSet<String>,
+    /// Earlier states of the documents, see `tag_version`
+    tags: Vec<crate::version::TaggedVersion>,
+    /// Fetches referenced documents that aren't loaded, see `with_document_loader`
+    loader: Option<Arc<dyn DocumentLoader>>,
+    /// Supplies facts the evaluation needs but isn't given, see `with_fact_provider`
+    fact_provider: Option<Arc<dyn crate::FactProvider>>,
+    /// Reject arithmetic across kinds of units, see `with_strict_units`
+    strict_units: bool,
+    /// How dates in fact overrides are read, see `with_date_format`
+    date_format: crate::DateFormat,
+}
+
+/// The documents an evaluation runs against: the loaded ones or a tagged version
+struct DocumentSet<'a> {
+    documents: &'a HashMap<String, LemmaDoc>,
+    sources: &'a HashMap<String, String>,
+    versions: &'a HashMap<String, String>,
+    warnings: &'a HashMap<String, Vec<String>>,
 }
 
 impl Default for Engine {
@@ -21,6 +85,20 @@ impl Default for Engine {
             validator: Validator,
             evaluator: Evaluator,
             limits: ResourceLimits::default(),
+            versions: HashMap::new(),
+            warnings: HashMap::new(),
+            declared: HashMap::new(),
+            partial_parts: HashMap::new(),
+            functions: FunctionRegistry::new(),
+            max_sources: None,
+            source_uses: HashMap::new(),
+            source_clock: AtomicU64::new(0),
+            evicted_sources: BTreeSet::new(),
+            tags: Vec::new(),
+            loader: None,
+            fact_provider: None,
+            strict_units: false,
+            date_format: crate::DateFormat::Iso,
         }
     }
 }
@@ -33,11 +111,94 @@ impl Engine {
     /// Create an engine with custom resource limits
     pub fn with_limits(limits: ResourceLimits) -> Self {
         Self {
-            documents: HashMap::new(),
-            sources: HashMap::new(),
-            validator: Validator,
-            evaluator: Evaluator,
             limits,
+            ..Self::default()
+        }
+    }
+
+    /// Round and overflow number arithmetic as `policy` says, see `NumericPolicy`
+    pub fn with_numeric_policy(mut self, policy: crate::NumericPolicy) -> Self {
+        self.limits.numeric = policy;
+        self
+    }
+
+    /// Reject rules combining different kinds of units, like `money * mass`, when they are loaded
+    ///
+    /// Such arithmetic otherwise gives a plain number with a `unit_degraded`
+    /// diagnostic. See `crate::strict_units`.
+    pub fn with_strict_units(mut self, strict: bool) -> Self {
+        self.strict_units = strict;
+        self
+    }
+
+    /// Read dates in fact overrides like `31-12-2025` in `format`
+    ///
+    /// Applies to `parse_facts` and to date strings in `parse_json_facts`.
+    /// Without it only year-first dates are read, and day-first or
+    /// month-first ones are rejected with an error naming the formats.
+    pub fn with_date_format(mut self, format: crate::DateFormat) -> Self {
+        self.date_format = format;
+        self
+    }
+
+    /// Keep the code of at most `max_sources` sources, dropping the least recently used
+    ///
+    /// Documents stay loaded when their source is dropped. The code is only
+    /// needed to show the lines of runtime errors, to skip adding unchanged
+    /// code, and for `update_document_range` and `rename_symbol`: add the code
+    /// again to use those. A source is used when its code is added and when a
+    /// document from it is evaluated.
+    pub fn with_retained_sources(mut self, max_sources: usize) -> Self {
+        self.max_sources = Some(max_sources);
+        self.evict_sources();
+        self
+    }
+
+    /// Load referenced documents that aren't loaded yet through `loader`
+    ///
+    /// Adding code then also loads the documents it references, and those
+    /// they reference, as far as the loader has them. See `crate::loader`.
+    pub fn with_document_loader(mut self, loader: impl DocumentLoader + 'static) -> Self {
+        self.loader = Some(Arc::new(loader));
+        self
+    }
+
+    /// Fetch facts an evaluation needs but isn't given from `provider`
+    ///
+    /// Before each evaluation, every fact without a value that the requested
+    /// rules reference is requested at once. See `crate::fact_provider`.
+    pub fn with_fact_provider(mut self, provider: impl crate::FactProvider + 'static) -> Self {
+        self.fact_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Estimated memory taken by the loaded documents and retained sources
+    pub fn memory_stats(&self) -> crate::MemoryStats {
+        let mut documents: Vec<crate::DocumentMemory> = self
+            .documents
+            .values()
+            .map(|doc| crate::DocumentMemory {
+                name: doc.name.clone(),
+                source: doc.source.clone(),
+                ast_bytes: crate::memory::document_size(doc),
+            })
+            .collect();
+        documents.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut sources: Vec<crate::SourceMemory> = self
+            .sources
+            .iter()
+            .map(|(source, code)| crate::SourceMemory {
+                source: source.clone(),
+                bytes: code.len(),
+            })
+            .collect();
+        sources.sort_by(|a, b| a.source.cmp(&b.source));
+        crate::MemoryStats {
+            ast_bytes: documents.iter().map(|doc| doc.ast_bytes).sum(),
+            source_bytes: sources.iter().map(|source| source.bytes).sum(),
+            documents,
+            sources,
+            evicted_sources: self.evicted_sources.iter().cloned().collect(),
         }
     }
 
@@ -46,28 +207,669 @@ impl Engine {
         &self.limits
     }
 
-    pub fn add_lemma_code(&mut self, lemma_code: &str, source: &str) -> LemmaResult<()> {
+    /// Parse, validate and load the documents in `lemma_code`
+    ///
+    /// Documents it declares replace the ones of the same name added under
+    /// `source` before. Adding the code `source` is already loaded with does
+    /// nothing. Referenced documents that aren't loaded are fetched from the
+    /// document loader, if there is one. Returns which documents were added,
+    /// updated and removed.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(source = source), err(level = "debug"))
+    )]
+    pub fn add_lemma_code(&mut self, lemma_code: &str, source: &str) -> LemmaResult<LoadDelta> {
+        // Sessions add the same files over and over: skip code that is still loaded
+        if self.is_loaded(lemma_code, source) {
+            return Ok(LoadDelta::default());
+        }
+
         let new_docs = parse(lemma_code, Some(source.to_owned()), &self.limits)?;
+        let mut loaded = self.fetch_referenced(&new_docs)?;
+        loaded.insert(
+            0,
+            (
+                LoadedCode {
+                    source: source.to_owned(),
+                    code: lemma_code.to_owned(),
+                },
+                new_docs,
+            ),
+        );
+        let declared: Vec<(String, Vec<String>)> = loaded
+            .iter()
+            .map(|(code, docs)| {
+                let names = docs
+                    .iter()
+                    .map(|doc| doc.name.clone())
+                    .collect::<BTreeSet<_>>();
+                (code.source.clone(), names.into_iter().collect())
+            })
+            .collect();
+
+        // Adding a source again replaces the documents it declared before
+        let mut all_docs: Vec<crate::LemmaDoc> = self
+            .documents
+            .values()
+            .filter(|doc| {
+                !doc.partial
+                    && !loaded
+                        .iter()
+                        .flat_map(|(_, docs)| docs)
+                        .any(|new| new.name == doc.name && new.source == doc.source)
+            })
+            .cloned()
+            .collect();
+        all_docs.extend(
+            self.partial_parts_except(|source| {
+                loaded.iter().any(|(code, _)| code.source == source)
+            }),
+        );
+        let mut codes = Vec::with_capacity(loaded.len());
+        let mut parts = Vec::with_capacity(loaded.len());
+        for (code, docs) in loaded {
+            parts.push((code.source.clone(), partial_parts_of(&docs)));
+            all_docs.extend(docs);
+            codes.push(code);
+        }
+
+        let validated = self.validator.validate_all(all_docs)?;
+        self.functions.check_calls(&validated.documents)?;
+        if self.strict_units {
+            crate::strict_units::check_unit_arithmetic(&validated.documents)?;
+        }
 
-        for doc in &new_docs {
-            let source_id = doc.source.clone().unwrap_or_else(|| "<input>".to_owned());
-            self.sources.insert(source_id, lemma_code.to_owned());
+        let before = self.versions.clone();
+        self.warnings = validated.warnings;
+        // Template copies are made again from the references that remain
+        self.documents.retain(|_, doc| doc.template.is_none());
+        for doc in validated.documents {
+            self.documents.insert(doc.name.clone(), doc);
+        }
+        for code in codes {
+            self.retain_source(code.source, code.code);
+        }
+        self.store_partial_parts(parts);
+        self.refresh_versions();
+
+        let mut delta = LoadDelta::default();
+        for name in declared.iter().flat_map(|(_, names)| names) {
+            match before.get(name) {
+                None => delta.added.push(name.clone()),
+                Some(version) if self.versions.get(name) != Some(version) => {
+                    delta.updated.push(name.clone())
+                }
+                Some(_) => {}
+            }
+        }
+        let mut removed: Vec<String> = before
+            .keys()
+            .filter(|name| !self.documents.contains_key(*name))
+            .cloned()
+            .collect();
+        removed.sort();
+        delta.removed = removed;
+        self.declared.extend(declared);
+
+        Ok(delta)
+    }
+
+    /// Load the document `doc_name` through the document loader, unless it is loaded
+    ///
+    /// Loads the documents it references too, like `add_lemma_code`. Fails
+    /// when there is no loader or it doesn't have the document.
+    pub fn load_document(&mut self, doc_name: &str) -> LemmaResult<LoadDelta> {
+        if self.documents.contains_key(doc_name) {
+            return Ok(LoadDelta::default());
+        }
+        let loaded = match &self.loader {
+            Some(loader) => loader.load(doc_name)?,
+            None => None,
+        };
+        let Some(loaded) = loaded else {
+            return Err(LemmaError::Engine(format!(
+                "Document '{}' not found",
+                doc_name
+            )));
+        };
+        self.add_lemma_code(&loaded.code, &loaded.source)
+    }
+
+    /// Parse the code of every document `docs` reference, directly or through
+    /// each other, that isn't loaded and the document loader has
+    ///
+    /// References the loader doesn't have are left for validation to report.
+    fn fetch_referenced(
+        &self,
+        docs: &[crate::LemmaDoc],
+    ) -> LemmaResult<Vec<(LoadedCode, Vec<crate::LemmaDoc>)>> {
+        let Some(loader) = &self.loader else {
+            return Ok(Vec::new());
+        };
+        let mut known: HashSet<String> = self.documents.keys().cloned().collect();
+        known.extend(docs.iter().map(|doc| doc.name.clone()));
+        let mut pending: Vec<String> = docs
+            .iter()
+            .flat_map(crate::loader::referenced_documents)
+            .map(str::to_string)
+            .collect();
+
+        let mut fetched = Vec::new();
+        while let Some(name) = pending.pop() {
+            if !known.insert(name.clone()) {
+                continue;
+            }
+            let Some(code) = loader.load(&name)? else {
+                continue;
+            };
+            let docs = parse(&code.code, Some(code.source.clone()), &self.limits)?;
+            if !docs.iter().any(|doc| doc.name == name) {
+                return Err(LemmaError::Engine(format!(
+                    "Document loader returned {} for document '{}', which it doesn't declare",
+                    code.source, name
+                )));
+            }
+            known.extend(docs.iter().map(|doc| doc.name.clone()));
+            pending.extend(
+                docs.iter()
+                    .flat_map(crate::loader::referenced_documents)
+                    .filter(|reference| !known.contains(*reference))
+                    .map(str::to_string),
+            );
+            fetched.push((code, docs));
+        }
+        Ok(fetched)
+    }
+
+    /// Whether `lemma_code` is what `source` was last loaded with, and all
+    /// documents it declared are still loaded from it
+    fn is_loaded(&self, lemma_code: &str, source: &str) -> bool {
+        self.sources.get(source).map(String::as_str) == Some(lemma_code)
+            && self.declared.get(source).is_some_and(|names| {
+                names.iter().all(|name| {
+                    self.documents
+                        .get(name)
+                        .is_some_and(|doc| doc.source.as_deref() == Some(source))
+                })
+            })
+    }
+
+    /// The stored parts of partial documents, except those of the sources `reloaded`
+    ///
+    /// Partial documents are validated from their parts rather than as merged,
+    /// so loading a source again replaces its parts instead of clashing with
+    /// the earlier merge. Parts are in the order of their sources.
+    fn partial_parts_except(&self, reloaded: impl Fn(&str) -> bool) -> Vec<LemmaDoc> {
+        let mut sources: Vec<&String> = self
+            .partial_parts
+            .keys()
+            .filter(|source| !reloaded(source))
+            .collect();
+        sources.sort();
+        sources
+            .into_iter()
+            .flat_map(|source| self.partial_parts[source].iter().cloned())
+            .collect()
+    }
+
+    /// Keep the parts of partial documents the sources declared, once they validated
+    fn store_partial_parts(&mut self, parts: Vec<(String, Vec<LemmaDoc>)>) {
+        for (source, parts) in parts {
+            if parts.is_empty() {
+                self.partial_parts.remove(&source);
+            } else {
+                self.partial_parts.insert(source, parts);
+            }
+        }
+    }
+
+    /// Keep the code of `source`, dropping the least recently used beyond `max_sources`
+    fn retain_source(&mut self, source: String, code: String) {
+        let stamp = self.source_clock.fetch_add(1, Ordering::Relaxed) + 1;
+        self.evicted_sources.remove(&source);
+        self.source_uses
+            .insert(source.clone(), AtomicU64::new(stamp));
+        self.sources.insert(source, code);
+        self.evict_sources();
+    }
+
+    fn evict_sources(&mut self) {
+        let Some(max_sources) = self.max_sources else {
+            return;
+        };
+        while self.sources.len() > max_sources {
+            let Some(oldest) = self
+                .source_uses
+                .iter()
+                .min_by_key(|(_, stamp)| stamp.load(Ordering::Relaxed))
+                .map(|(source, _)| source.clone())
+            else {
+                return;
+            };
+            self.sources.remove(&oldest);
+            self.source_uses.remove(&oldest);
+            self.evicted_sources.insert(oldest);
+        }
+    }
+
+    /// Mark the source of a document as used, so it is dropped last
+    fn touch_source(&self, doc_name: &str) {
+        let used = self
+            .documents
+            .get(doc_name)
+            .and_then(|doc| doc.source.as_ref())
+            .and_then(|source| self.source_uses.get(source));
+        if let Some(used) = used {
+            let stamp = self.source_clock.fetch_add(1, Ordering::Relaxed) + 1;
+            used.store(stamp, Ordering::Relaxed);
+        }
+    }
+
+    /// Make a function callable from Lemma code as `name(argument, ...)`
+    ///
+    /// Register functions before loading the documents that call them: calls
+    /// of unknown functions or with the wrong number of arguments are rejected
+    /// when a document is loaded. `implementation` receives the evaluated
+    /// arguments; an `Err` fails the evaluation of the calling rule.
+    pub fn register_function(
+        &mut self,
+        name: &str,
+        arity: usize,
+        implementation: impl Fn(&[crate::LiteralValue]) -> Result<crate::LiteralValue, String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> LemmaResult<()> {
+        self.functions
+            .register(name, arity, None, std::sync::Arc::new(implementation))
+    }
+
+    /// Like `register_function`, with a type for each parameter
+    ///
+    /// Arguments of another type fail the evaluation before `implementation`
+    /// is called.
+    pub fn register_typed_function(
+        &mut self,
+        name: &str,
+        parameter_types: Vec<crate::LemmaType>,
+        implementation: impl Fn(&[crate::LiteralValue]) -> Result<crate::LiteralValue, String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> LemmaResult<()> {
+        let arity = parameter_types.len();
+        self.functions.register(
+            name,
+            arity,
+            Some(parameter_types),
+            std::sync::Arc::new(implementation),
+        )
+    }
+
+    /// Load every `.lemma` file below `dir`
+    ///
+    /// Files are read and parsed in parallel and validated together once.
+    /// Errors are reported in file path order. On error the engine is left
+    /// unchanged.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_dir(&mut self, dir: impl AsRef<std::path::Path>) -> LemmaResult<()> {
+        self.load_dir_with_progress(dir, |_| {})
+    }
+
+    /// Like `load_dir`, calling `progress` after each file is parsed
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_dir_with_progress(
+        &mut self,
+        dir: impl AsRef<std::path::Path>,
+        mut progress: impl FnMut(&crate::LoadProgress),
+    ) -> LemmaResult<()> {
+        let files = crate::workspace::lemma_files(dir.as_ref())?;
+        let parsed = crate::workspace::parse_files(&files, &self.limits, &mut progress);
+
+        let mut new_docs = Vec::new();
+        let mut sources = Vec::new();
+        let mut declared = Vec::new();
+        let mut parts = Vec::new();
+        let mut errors = Vec::new();
+        for file in parsed {
+            match file.docs {
+                Ok(docs) => {
+                    let names = docs
+                        .iter()
+                        .map(|doc| doc.name.clone())
+                        .collect::<BTreeSet<_>>();
+                    declared.push((file.source.clone(), names.into_iter().collect()));
+                    parts.push((file.source.clone(), partial_parts_of(&docs)));
+                    new_docs.extend(docs);
+                    sources.push((file.source, file.code));
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        if errors.len() == 1 {
+            return Err(errors.remove(0));
+        }
+        if !errors.is_empty() {
+            return Err(LemmaError::MultipleErrors(errors));
+        }
+
+        // Validation reports the first problem it finds, so keep the order stable
+        // Files loaded again replace the documents they declared before
+        let mut all_docs: Vec<crate::LemmaDoc> = self
+            .documents
+            .values()
+            .filter(|doc| {
+                !doc.partial
+                    && !sources
+                        .iter()
+                        .any(|(source, _)| doc.source.as_deref() == Some(source.as_str()))
+            })
+            .cloned()
+            .collect();
+        all_docs.extend(
+            self.partial_parts_except(|source| sources.iter().any(|(loaded, _)| loaded == source)),
+        );
+        all_docs.sort_by(|a, b| (&a.source, a.start_line).cmp(&(&b.source, b.start_line)));
+        all_docs.extend(new_docs);
+
+        let validated = self.validator.validate_all(all_docs)?;
+        self.functions.check_calls(&validated.documents)?;
+        if self.strict_units {
+            crate::strict_units::check_unit_arithmetic(&validated.documents)?;
+        }
+
+        self.warnings = validated.warnings;
+        // Template copies are made again from the references that remain
+        self.documents.retain(|_, doc| doc.template.is_none());
+        for doc in validated.documents {
+            self.documents.insert(doc.name.clone(), doc);
+        }
+        for (source, code) in sources {
+            self.retain_source(source, code);
         }
+        self.declared.extend(declared);
+        self.store_partial_parts(parts);
+        self.refresh_versions();
 
-        let mut all_docs: Vec<crate::LemmaDoc> = self.documents.values().cloned().collect();
+        Ok(())
+    }
+
+    /// Add a document generated from tabular data
+    ///
+    /// Each row becomes one or more typed facts in `doc_name`, see `FactTable::to_lemma`.
+    /// Returns the generated Lemma code.
+    pub fn add_facts_from_table(
+        &mut self,
+        doc_name: &str,
+        table: &FactTable,
+        source: &str,
+    ) -> LemmaResult<String> {
+        let code = table.to_lemma(doc_name)?;
+        self.add_lemma_code(&code, source)?;
+        Ok(code)
+    }
+
+    /// Add a document built in code, see `builder::DocBuilder`
+    ///
+    /// The document is written out as Lemma code and added like
+    /// `add_lemma_code`, so it is validated and retained as any other source.
+    pub fn add_document(&mut self, doc: &crate::LemmaDoc, source: &str) -> LemmaResult<LoadDelta> {
+        self.add_lemma_code(&doc.to_string(), source)
+    }
+
+    /// Add a document generated from a JSON Schema
+    ///
+    /// `schema` is the JSON text of the schema. Each property becomes a fact
+    /// declaration in `doc_name`, see `scaffold_document`.
+    /// Returns the generated Lemma code.
+    pub fn add_document_from_schema(
+        &mut self,
+        doc_name: &str,
+        schema: &str,
+        source: &str,
+    ) -> LemmaResult<String> {
+        let schema: serde_json::Value = serde_json::from_str(schema)
+            .map_err(|e| LemmaError::Engine(format!("Invalid JSON Schema: {}", e)))?;
+        let code = crate::scaffold_document(&schema, doc_name)?;
+        self.add_lemma_code(&code, source)?;
+        Ok(code)
+    }
+
+    /// Replace lines of an added source, re-parsing only the document they belong to
+    ///
+    /// `lines` are 1-based line numbers (end exclusive) in the code added as
+    /// `source`, and `new_text` replaces them, including its trailing newline.
+    /// An empty range inserts `new_text` before `lines.start`.
+    ///
+    /// Edits that add or remove a `doc` line re-parse the whole source. On
+    /// error the engine is left unchanged.
+    pub fn update_document_range(
+        &mut self,
+        source: &str,
+        lines: std::ops::Range<usize>,
+        new_text: &str,
+    ) -> LemmaResult<()> {
+        let code = self.sources.get(source).ok_or_else(|| {
+            if self.evicted_sources.contains(source) {
+                LemmaError::Engine(format!(
+                    "The code of source '{}' was dropped to save memory: add it again",
+                    source
+                ))
+            } else {
+                LemmaError::Engine(format!("Source '{}' not found", source))
+            }
+        })?;
+        let mut old_docs: Vec<crate::LemmaDoc> = self
+            .documents
+            .values()
+            .filter(|doc| !doc.partial && doc.source.as_deref() == Some(source))
+            .cloned()
+            .collect();
+        old_docs.extend(
+            self.partial_parts
+                .get(source)
+                .into_iter()
+                .flatten()
+                .cloned(),
+        );
+
+        let (edited, new_docs) = crate::parser::incremental::reparse_edit(
+            &old_docs,
+            code,
+            lines,
+            new_text,
+            source,
+            &self.limits,
+        )?;
+
+        let mut all_docs: Vec<crate::LemmaDoc> = self
+            .documents
+            .values()
+            .filter(|doc| !doc.partial && doc.source.as_deref() != Some(source))
+            .cloned()
+            .collect();
+        all_docs.extend(self.partial_parts_except(|other| other == source));
+        let parts = vec![(source.to_owned(), partial_parts_of(&new_docs))];
+        let declared: Vec<String> = new_docs
+            .iter()
+            .map(|doc| doc.name.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
         all_docs.extend(new_docs);
 
         let validated = self.validator.validate_all(all_docs)?;
+        self.functions.check_calls(&validated.documents)?;
+        if self.strict_units {
+            crate::strict_units::check_unit_arithmetic(&validated.documents)?;
+        }
 
+        for doc in &old_docs {
+            self.documents.remove(&doc.name);
+        }
+        self.warnings = validated.warnings;
+        // Template copies are made again from the references that remain
+        self.documents.retain(|_, doc| doc.template.is_none());
         for doc in validated.documents {
             self.documents.insert(doc.name.clone(), doc);
         }
+        self.retain_source(source.to_owned(), edited);
+        self.declared.insert(source.to_owned(), declared);
+        self.store_partial_parts(parts);
+        self.refresh_versions();
 
         Ok(())
     }
 
     pub fn remove_document(&mut self, doc_name: &str) {
         self.documents.remove(doc_name);
+        for parts in self.partial_parts.values_mut() {
+            parts.retain(|part| part.name != doc_name);
+        }
+        self.partial_parts.retain(|_, parts| !parts.is_empty());
+        self.warnings.remove(doc_name);
+        self.refresh_versions();
+    }
+
+    /// Content hash of a document and the documents it references
+    ///
+    /// Changes whenever the document's facts, rules or any referenced document
+    /// change, but not with layout. Pin it with `EvaluationOptions::with_pinned_version`
+    /// to make sure a long-running session evaluates the rules it was built against.
+    pub fn document_version(&self, doc_name: &str) -> Option<&str> {
+        self.versions.get(doc_name).map(String::as_str)
+    }
+
+    /// Non-fatal findings in a loaded document, found when it was validated
+    ///
+    /// Every evaluation of the document lists them in `Response::warnings`.
+    pub fn document_warnings(&self, doc_name: &str) -> &[String] {
+        self.warnings.get(doc_name).map_or(&[], Vec::as_slice)
+    }
+
+    fn refresh_versions(&mut self) {
+        self.versions = crate::version::document_versions(&self.documents);
+    }
+
+    /// Keep the loaded documents as the version tagged `label`
+    ///
+    /// Evaluations with `EvaluationOptions::with_as_of` run against a tagged
+    /// version instead of the loaded documents, so past decisions can be
+    /// recalculated with the rules that were in force at the time. Tag with a
+    /// date (`2024-06`) or any other label (`v2`), typically right after
+    /// loading the rules that take effect then. Tagging a label again replaces
+    /// its version.
+    pub fn tag_version(&mut self, label: impl Into<String>) {
+        let label = label.into();
+        self.tags.retain(|tag| tag.label != label);
+        self.tags.push(crate::version::TaggedVersion {
+            label,
+            documents: self.documents.clone(),
+            sources: self.sources.clone(),
+            versions: self.versions.clone(),
+            warnings: self.warnings.clone(),
+        });
+    }
+
+    /// Labels of the tagged versions, sorted
+    pub fn version_tags(&self) -> Vec<&str> {
+        let mut labels: Vec<&str> = self.tags.iter().map(|tag| tag.label.as_str()).collect();
+        labels.sort_unstable();
+        labels
+    }
+
+    /// Forget the version tagged `label`, returning whether there was one
+    pub fn remove_version_tag(&mut self, label: &str) -> bool {
+        let count = self.tags.len();
+        self.tags.retain(|tag| tag.label != label);
+        self.tags.len() != count
+    }
+
+    /// The documents, sources, versions and warnings to evaluate with `options`
+    fn documents_as_of(&self, options: &EvaluationOptions) -> LemmaResult<DocumentSet<'_>> {
+        let Some(as_of) = &options.as_of else {
+            return Ok(DocumentSet {
+                documents: &self.documents,
+                sources: &self.sources,
+                versions: &self.versions,
+                warnings: &self.warnings,
+            });
+        };
+        let tag = crate::version::select_tag(&self.tags, as_of).ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "No version tagged '{}'{}",
+                as_of,
+                if self.tags.is_empty() {
+                    String::new()
+                } else {
+                    format!(" (tagged: {})", self.version_tags().join(", "))
+                }
+            ))
+        })?;
+        Ok(DocumentSet {
+            documents: &tag.documents,
+            sources: &tag.sources,
+            versions: &tag.versions,
+            warnings: &tag.warnings,
+        })
+    }
+
+    /// JSON Schema for the facts of a document that can be overridden
+    ///
+    /// Describes the JSON object `serializers::json` accepts, so forms and
+    /// validation can be generated from the document. See `crate::schema`.
+    pub fn fact_schema(&self, doc_name: &str) -> LemmaResult<serde_json::Value> {
+        let doc = self
+            .documents
+            .get(doc_name)
+            .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+        Ok(crate::schema::fact_schema(doc, &self.documents))
+    }
+
+    /// The proto3 message for the fact overrides of a document
+    ///
+    /// Services sending facts as protobuf compile this definition, the engine
+    /// reads their payloads with `serializers::from_protobuf`.
+    pub fn proto_schema(&self, doc_name: &str) -> LemmaResult<String> {
+        let doc = self
+            .documents
+            .get(doc_name)
+            .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+        Ok(crate::serializers::proto_schema(doc, &self.documents))
+    }
+
+    /// The full AST of a document as JSON, with spans and expression IDs
+    ///
+    /// Lets external tools analyze or rewrite documents without re-parsing
+    /// them. See `crate::ast_export` for the shape.
+    pub fn export_ast(&self, doc_name: &str) -> LemmaResult<serde_json::Value> {
+        let doc = self
+            .documents
+            .get(doc_name)
+            .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+        Ok(crate::ast_export::export_ast(doc))
+    }
+
+    /// The text edits that rename a fact or rule of a document across the workspace
+    ///
+    /// Covers the definition and every reference to it, also from other
+    /// documents (`order.price`, `order.total?`), in overrides and in
+    /// aggregates. The engine is not changed: apply the edits to the sources
+    /// and load them again. See `crate::rename`.
+    pub fn rename_symbol(
+        &self,
+        doc_name: &str,
+        old_name: &str,
+        new_name: &str,
+    ) -> LemmaResult<Vec<crate::SourceEdit>> {
+        crate::rename::rename_edits(
+            &self.documents,
+            &self.sources,
+            &self.limits,
+            doc_name,
+            old_name,
+            new_name,
+        )
     }
 
     pub fn list_documents(&self) -> Vec<String> {
@@ -78,6 +880,17 @@ impl Engine {
         self.documents.get(doc_name)
     }
 
+    /// Classify the changes from `old_doc` to `new_doc` by the version bump they need
+    ///
+    /// `CompatibilityReport::problem` tells whether their `version` declarations
+    /// cover the changes. See `crate::compatibility`.
+    pub fn check_compatibility(
+        old_doc: &crate::LemmaDoc,
+        new_doc: &crate::LemmaDoc,
+    ) -> crate::CompatibilityReport {
+        crate::compatibility::check_compatibility(old_doc, new_doc)
+    }
+
     pub fn get_document_facts(&self, doc_name: &str) -> Vec<&crate::LemmaFact> {
         if let Some(doc) = self.documents.get(doc_name) {
             doc.facts.iter().collect()
@@ -86,6 +899,22 @@ impl Engine {
         }
     }
 
+    /// Overrides that set each typed fact of a document to its `example`
+    ///
+    /// Evaluating with these demonstrates the document without real input.
+    pub fn example_facts(&self, doc_name: &str) -> Vec<crate::LemmaFact> {
+        self.get_document_facts(doc_name)
+            .into_iter()
+            .filter_map(|fact| {
+                let example = fact.example.clone()?;
+                Some(crate::LemmaFact::new(
+                    fact.fact_type.clone(),
+                    crate::FactValue::Literal(example),
+                ))
+            })
+            .collect()
+    }
+
     pub fn get_document_rules(&self, doc_name: &str) -> Vec<&crate::LemmaRule> {
         if let Some(doc) = self.documents.get(doc_name) {
             doc.rules.iter().collect()
@@ -94,11 +923,264 @@ impl Engine {
         }
     }
 
+    /// Parse fact overrides for a document
+    ///
+    /// Like `parse_facts`, but each `FactParseError` carries the type the
+    /// document declares for the fact, and values of the wrong type are
+    /// rejected up front with the same structured error.
+    pub fn parse_facts(
+        &self,
+        doc_name: &str,
+        fact_strings: &[&str],
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        self.parse_facts_with_date_format(doc_name, fact_strings, self.date_format)
+    }
+
+    /// Parse fact overrides for a document, reading dates in `date_format`
+    /// instead of the engine's, e.g. for one HTTP request
+    pub fn parse_facts_with_date_format(
+        &self,
+        doc_name: &str,
+        fact_strings: &[&str],
+        date_format: crate::DateFormat,
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        let doc = self
+            .documents
+            .get(doc_name)
+            .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+        let declared = |reference: &[String]| {
+            crate::analysis::declared_fact_type(doc, reference, &self.documents)
+        };
+
+        let mut facts = Vec::new();
+        let mut errors = Vec::new();
+
+        for fact_str in fact_strings {
+            let fact = match crate::parse_facts_with_date_format(&[fact_str], date_format) {
+                Ok(mut parsed) => parsed.remove(0),
+                Err(LemmaError::FactParse(parse_errors)) => {
+                    errors.extend(parse_errors.into_iter().map(|mut error| {
+                        error.declared_type = error.fact.as_ref().and_then(|name| {
+                            let reference: Vec<String> =
+                                name.split('.').map(str::to_string).collect();
+                            declared(&reference)
+                        });
+                        error
+                    }));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            match self.check_override(doc, fact, fact_str) {
+                Ok(fact) => facts.push(fact),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        // Values of sensitive facts stay out of error messages and logs
+        for error in &mut errors {
+            let Some(name) = &error.fact else { continue };
+            let reference: Vec<String> = name.split('.').map(str::to_string).collect();
+            if crate::analysis::is_sensitive_fact(doc, &reference, &self.documents) {
+                error.input = format!("{}={}", name, crate::REDACTED);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(facts)
+        } else {
+            Err(LemmaError::FactParse(errors))
+        }
+    }
+
+    /// Parse fact overrides given as a JSON object for a document
+    ///
+    /// Keys are fact names, dotted for facts of referenced documents. Values
+    /// are converted to the declared types by `serializers::literal_from_json`
+    /// without going through Lemma syntax, so numbers stay numbers and money
+    /// can be given as `{"amount": 100, "currency": "USD"}`. They are then
+    /// checked like the overrides of `parse_facts`.
+    pub fn parse_json_facts(
+        &self,
+        doc_name: &str,
+        facts: &HashMap<String, serde_json::Value>,
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        self.parse_json_facts_many_with_date_format(&[doc_name], facts, self.date_format)
+    }
+
+    /// Parse JSON fact overrides for a document, reading dates in `date_format`
+    /// instead of the engine's, see `parse_facts_with_date_format`
+    pub fn parse_json_facts_with_date_format(
+        &self,
+        doc_name: &str,
+        facts: &HashMap<String, serde_json::Value>,
+        date_format: crate::DateFormat,
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        self.parse_json_facts_many_with_date_format(&[doc_name], facts, date_format)
+    }
+
+    /// Parse JSON fact overrides for several documents, see `evaluate_many`
+    ///
+    /// Each fact gets the type declared by the first of `doc_names` that
+    /// declares it.
+    pub fn parse_json_facts_many(
+        &self,
+        doc_names: &[&str],
+        facts: &HashMap<String, serde_json::Value>,
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        self.parse_json_facts_many_with_date_format(doc_names, facts, self.date_format)
+    }
+
+    /// Parse JSON fact overrides for several documents, reading dates in `date_format`
+    pub fn parse_json_facts_many_with_date_format(
+        &self,
+        doc_names: &[&str],
+        facts: &HashMap<String, serde_json::Value>,
+        date_format: crate::DateFormat,
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        let docs = doc_names
+            .iter()
+            .map(|name| {
+                self.documents
+                    .get(*name)
+                    .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", name)))
+            })
+            .collect::<LemmaResult<Vec<_>>>()?;
+        let first = *docs
+            .first()
+            .ok_or_else(|| LemmaError::Engine("No document given for the facts".to_string()))?;
+
+        let mut names: Vec<&String> = facts.keys().collect();
+        names.sort();
+        let mut parsed = Vec::new();
+        let mut errors = Vec::new();
+        for name in names {
+            let reference: Vec<String> = name.split('.').map(str::to_string).collect();
+            let declaring = |doc: &&LemmaDoc| {
+                crate::analysis::declared_fact_type(doc, &reference, &self.documents)
+            };
+            let doc = docs
+                .iter()
+                .copied()
+                .find(|doc| declaring(doc).is_some())
+                .unwrap_or(first);
+            let declared = declaring(&doc);
+            let input = format!("{}={}", name, facts[name]);
+            let fact_type = if reference.len() == 1 {
+                crate::FactType::Local(name.clone())
+            } else {
+                crate::FactType::Foreign(crate::ForeignFact {
+                    reference: reference.clone(),
+                })
+            };
+
+            let value = match (&facts[name], &declared) {
+                (serde_json::Value::String(date), Some(crate::LemmaType::Date)) => {
+                    crate::parser::dates::parse_date(date, date_format)
+                        .map(|date| date.map(crate::LiteralValue::Date))
+                }
+                _ => None,
+            };
+            let fact = value
+                .unwrap_or_else(|| {
+                    crate::serializers::literal_from_json(&facts[name], declared.as_ref())
+                })
+                .map_err(|message| FactParseError {
+                    input: input.clone(),
+                    fact: Some(name.clone()),
+                    position: crate::parser::value_offset(&input),
+                    expected: declared.iter().map(ToString::to_string).collect(),
+                    message,
+                    declared_type: declared.clone(),
+                })
+                .and_then(|value| {
+                    let fact = crate::LemmaFact::new(fact_type, crate::FactValue::Literal(value));
+                    self.check_override(doc, fact, &input)
+                });
+            match fact {
+                Ok(fact) => parsed.push(fact),
+                Err(error) => errors.push(error),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(parsed)
+        } else {
+            Err(LemmaError::FactParse(errors))
+        }
+    }
+
+    /// Coerce a parsed override and check it against the type `doc` declares
+    fn check_override(
+        &self,
+        doc: &LemmaDoc,
+        mut fact: crate::LemmaFact,
+        input: &str,
+    ) -> Result<crate::LemmaFact, FactParseError> {
+        let declared = |reference: &[String]| {
+            crate::analysis::declared_fact_type(doc, reference, &self.documents)
+        };
+        let reference = match &fact.fact_type {
+            crate::FactType::Local(name) => vec![name.clone()],
+            crate::FactType::Foreign(foreign) => foreign.reference.clone(),
+        };
+        if let crate::FactValue::Literal(lit) = &fact.value {
+            if let Some(coerced) =
+                crate::analysis::coerce_fact_value(doc, &reference, lit, &self.documents)
+            {
+                fact.value = crate::FactValue::Literal(coerced);
+            }
+        }
+        if let crate::FactValue::Literal(lit) = &fact.value {
+            if let Err(message) =
+                crate::analysis::check_fact_currency(doc, &reference, lit, &self.documents)
+            {
+                return Err(FactParseError {
+                    input: input.to_string(),
+                    fact: Some(reference.join(".")),
+                    position: crate::parser::value_offset(input),
+                    expected: vec!["money".to_string()],
+                    message,
+                    declared_type: Some(crate::LemmaType::Money),
+                });
+            }
+            if let Err(message) =
+                crate::analysis::check_percentage_points(doc, &reference, lit, &self.documents)
+            {
+                return Err(FactParseError {
+                    input: input.to_string(),
+                    fact: Some(reference.join(".")),
+                    position: crate::parser::value_offset(input),
+                    expected: vec!["percentage".to_string()],
+                    message,
+                    declared_type: Some(crate::LemmaType::Percentage),
+                });
+            }
+        }
+        if let (crate::FactValue::Literal(lit), Some(declared_type)) =
+            (&fact.value, declared(&reference))
+        {
+            let actual_type = lit.to_type();
+            if actual_type != declared_type {
+                return Err(FactParseError {
+                    input: input.to_string(),
+                    fact: Some(reference.join(".")),
+                    position: crate::parser::value_offset(input),
+                    expected: vec![declared_type.to_string()],
+                    message: format!("expected {}, got {}", declared_type, actual_type),
+                    declared_type: Some(declared_type),
+                });
+            }
+        }
+        Ok(fact)
+    }
+
     /// Evaluate rules in a document with optional fact overrides
     ///
     /// If `rule_names` is None, evaluates all rules.
-    /// If `rule_names` is Some, only returns results for the specified rules,
-    /// but still computes their dependencies.
+    /// If `rule_names` is Some, only evaluates the specified rules and the
+    /// rules they depend on, and only returns results for the specified rules.
     ///
     /// Fact overrides must be pre-parsed using `parse_facts()`.
     pub fn evaluate(
@@ -106,10 +1188,397 @@ impl Engine {
         doc_name: &str,
         rule_names: Option<Vec<String>>,
         fact_overrides: Option<Vec<crate::LemmaFact>>,
+    ) -> LemmaResult<Response> {
+        self.evaluate_with_options(
+            doc_name,
+            rule_names,
+            fact_overrides,
+            &EvaluationOptions::default(),
+        )
+    }
+
+    /// Evaluate rules in a document with per-evaluation options
+    ///
+    /// Behaves like `evaluate`, but e.g. selects the locale of veto messages
+    /// returned in each `RuleResult`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(doc = doc_name, rules = ?rule_names),
+            err(level = "debug")
+        )
+    )]
+    pub fn evaluate_with_options(
+        &self,
+        doc_name: &str,
+        rule_names: Option<Vec<String>>,
+        fact_overrides: Option<Vec<crate::LemmaFact>>,
+        options: &EvaluationOptions,
     ) -> LemmaResult<Response> {
         let overrides = fact_overrides.unwrap_or_default();
+        let shadow_inputs = options
+            .shadow
+            .as_ref()
+            .map(|shadow| (shadow.clone(), rule_names.clone(), overrides.clone()));
+        let DocumentSet {
+            documents,
+            sources,
+            versions,
+            warnings,
+        } = self.documents_as_of(options)?;
+
+        let version = versions.get(doc_name).cloned();
+        if let (Some(expected), Some(actual)) = (&options.pinned_version, &version) {
+            if expected != actual {
+                return Err(LemmaError::VersionMismatch {
+                    doc_name: doc_name.to_string(),
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                });
+            }
+        }
+
+        self.check_fact_sizes(&overrides)?;
+        self.touch_source(doc_name);
+
+        if let Some(template) = documents.get(doc_name).filter(|doc| doc.is_template()) {
+            return Err(LemmaError::Engine(format!(
+                "Document '{}' is a template with parameters ({}): evaluate a document that instantiates it",
+                doc_name,
+                template.parameters.join(", ")
+            )));
+        }
+
+        let overrides =
+            self.provide_facts(doc_name, documents, rule_names.as_deref(), overrides)?;
+        let mut response = self.evaluator.evaluate_document(
+            doc_name,
+            documents,
+            sources,
+            overrides,
+            rule_names,
+            &self.limits,
+            &self.functions,
+            options,
+        )?;
+
+        for warning in warnings.get(doc_name).into_iter().flatten() {
+            response.add_warning(warning.clone());
+        }
+        response.doc_version = version;
+
+        if let Some((shadow, rule_names, overrides)) = shadow_inputs {
+            response.shadow = Some(self.evaluate_shadow(
+                &shadow,
+                rule_names,
+                overrides,
+                options,
+                &response.results,
+            ));
+        }
+
+        Ok(response)
+    }
 
-        for fact in &overrides {
+    /// Add the values the fact provider has for facts the evaluation needs
+    ///
+    /// All missing facts are fetched at once, before evaluating, so lookups
+    /// don't wait for each other rule by rule.
+    fn provide_facts(
+        &self,
+        doc_name: &str,
+        documents: &HashMap<String, LemmaDoc>,
+        rule_names: Option<&[String]>,
+        mut overrides: Vec<crate::LemmaFact>,
+    ) -> LemmaResult<Vec<crate::LemmaFact>> {
+        let (Some(provider), Some(doc)) = (&self.fact_provider, documents.get(doc_name)) else {
+            return Ok(overrides);
+        };
+        let requests = crate::fact_provider::unsupplied_facts(
+            doc,
+            documents,
+            &overrides,
+            rule_names,
+            &self.limits,
+        )?;
+        if requests.is_empty() {
+            return Ok(overrides);
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(facts = requests.len(), "fetching provided facts");
+        for (request, value) in requests.iter().zip(provider.fetch_many(&requests)) {
+            if let Some(value) = value? {
+                overrides.push(crate::fact_provider::provided_fact(&request.path, value));
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Evaluate the shadow document of `options` and compare it with the primary results
+    fn evaluate_shadow(
+        &self,
+        doc_name: &str,
+        rule_names: Option<Vec<String>>,
+        overrides: Vec<crate::LemmaFact>,
+        options: &EvaluationOptions,
+        primary: &[crate::RuleResult],
+    ) -> crate::ShadowResponse {
+        // The pinned version is the primary's, and the shadow has no shadow
+        let options = EvaluationOptions {
+            shadow: None,
+            pinned_version: None,
+            ..options.clone()
+        };
+        match self.evaluate_with_options(doc_name, rule_names, Some(overrides), &options) {
+            Ok(response) => crate::ShadowResponse::compare(primary, response),
+            Err(error) => crate::ShadowResponse::failed(doc_name, &error),
+        }
+    }
+
+    /// Parse, validate and evaluate `lemma_code` in one call, without loading it
+    ///
+    /// For per-request use where no workspace is kept. The code is checked
+    /// with this engine's resource limits and registered functions, then
+    /// discarded: the engine is not changed, and its documents can't be
+    /// referenced from the code. `doc_name` defaults to the first document in
+    /// the code. Fact overrides are parsed as by `parse_facts`.
+    pub fn evaluate_code(
+        &self,
+        lemma_code: &str,
+        doc_name: Option<&str>,
+        rule_names: Option<Vec<String>>,
+        fact_overrides: &[&str],
+    ) -> LemmaResult<Response> {
+        self.evaluate_code_with_options(
+            lemma_code,
+            doc_name,
+            rule_names,
+            fact_overrides,
+            &EvaluationOptions::default(),
+        )
+    }
+
+    /// Evaluate `lemma_code` in one call with per-evaluation options, see `evaluate_code`
+    pub fn evaluate_code_with_options(
+        &self,
+        lemma_code: &str,
+        doc_name: Option<&str>,
+        rule_names: Option<Vec<String>>,
+        fact_overrides: &[&str],
+        options: &EvaluationOptions,
+    ) -> LemmaResult<Response> {
+        self.evaluate_scratch(lemma_code, doc_name, rule_names, options, |scratch, doc| {
+            scratch.parse_facts(doc, fact_overrides)
+        })
+    }
+
+    /// Evaluate `lemma_code` in one call with fact overrides given as JSON
+    ///
+    /// The overrides are parsed by `parse_json_facts` against the code's
+    /// documents, see `evaluate_code`.
+    pub fn evaluate_code_with_json_facts(
+        &self,
+        lemma_code: &str,
+        doc_name: Option<&str>,
+        rule_names: Option<Vec<String>>,
+        fact_overrides: &HashMap<String, serde_json::Value>,
+        options: &EvaluationOptions,
+    ) -> LemmaResult<Response> {
+        self.evaluate_code_with_json_facts_and_date_format(
+            lemma_code,
+            doc_name,
+            rule_names,
+            fact_overrides,
+            self.date_format,
+            options,
+        )
+    }
+
+    /// Evaluate `lemma_code` in one call with JSON fact overrides whose dates
+    /// are in `date_format`, see `parse_json_facts_with_date_format`
+    pub fn evaluate_code_with_json_facts_and_date_format(
+        &self,
+        lemma_code: &str,
+        doc_name: Option<&str>,
+        rule_names: Option<Vec<String>>,
+        fact_overrides: &HashMap<String, serde_json::Value>,
+        date_format: crate::DateFormat,
+        options: &EvaluationOptions,
+    ) -> LemmaResult<Response> {
+        self.evaluate_scratch(lemma_code, doc_name, rule_names, options, |scratch, doc| {
+            scratch.parse_json_facts_with_date_format(doc, fact_overrides, date_format)
+        })
+    }
+
+    /// Load `lemma_code` into a scratch engine and evaluate it with the overrides `parse_facts` gives
+    fn evaluate_scratch(
+        &self,
+        lemma_code: &str,
+        doc_name: Option<&str>,
+        rule_names: Option<Vec<String>>,
+        options: &EvaluationOptions,
+        parse_facts: impl FnOnce(&Engine, &str) -> LemmaResult<Vec<crate::LemmaFact>>,
+    ) -> LemmaResult<Response> {
+        let mut scratch = Engine {
+            limits: self.limits.clone(),
+            functions: self.functions.clone(),
+            strict_units: self.strict_units,
+            date_format: self.date_format,
+            ..Engine::default()
+        };
+        scratch.add_lemma_code(lemma_code, "<input>")?;
+
+        let doc_name = match doc_name {
+            Some(name) => name.to_string(),
+            None => scratch
+                .documents
+                .values()
+                .filter(|doc| doc.template.is_none() && !doc.is_template())
+                .min_by_key(|doc| doc.start_line)
+                .map(|doc| doc.name.clone())
+                .ok_or_else(|| LemmaError::Engine("No document found in the code".to_string()))?,
+        };
+        let facts = parse_facts(&scratch, &doc_name)?;
+        scratch.evaluate_with_options(&doc_name, rule_names, Some(facts), options)
+    }
+
+    /// Evaluate several documents with one set of fact overrides
+    ///
+    /// Returns the response of each document by name. Every document gets the
+    /// overrides for the facts it declares, so an override is given once for
+    /// all documents with that fact. See `evaluate_many_with_options`.
+    pub fn evaluate_many(
+        &self,
+        doc_names: &[&str],
+        fact_overrides: Option<Vec<crate::LemmaFact>>,
+    ) -> LemmaResult<HashMap<String, Response>> {
+        self.evaluate_many_with_options(doc_names, fact_overrides, &EvaluationOptions::default())
+    }
+
+    /// Evaluate several documents with one set of fact overrides and per-evaluation options
+    ///
+    /// Decision services often need the results of related documents (e.g.
+    /// pricing, eligibility and compliance) for one request. Overrides that no
+    /// document declares are passed to every document, which reports them per
+    /// `EvaluationOptions::unused_overrides`. Fails with the error of the first
+    /// document that fails.
+    pub fn evaluate_many_with_options(
+        &self,
+        doc_names: &[&str],
+        fact_overrides: Option<Vec<crate::LemmaFact>>,
+        options: &EvaluationOptions,
+    ) -> LemmaResult<HashMap<String, Response>> {
+        let overrides = fact_overrides.unwrap_or_default();
+        self.check_fact_sizes(&overrides)?;
+        let documents = self.documents_as_of(options)?.documents;
+
+        let docs = doc_names
+            .iter()
+            .map(|name| {
+                documents
+                    .get(*name)
+                    .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", name)))
+            })
+            .collect::<LemmaResult<Vec<_>>>()?;
+        let declares = |doc: &LemmaDoc, fact: &crate::LemmaFact| {
+            let reference = match &fact.fact_type {
+                crate::FactType::Local(name) => vec![name.clone()],
+                crate::FactType::Foreign(foreign) => foreign.reference.clone(),
+            };
+            crate::analysis::declared_fact_type(doc, &reference, documents).is_some()
+        };
+        let unclaimed: Vec<bool> = overrides
+            .iter()
+            .map(|fact| !docs.iter().any(|doc| declares(doc, fact)))
+            .collect();
+
+        let mut responses = HashMap::new();
+        for doc in docs {
+            let doc_overrides = overrides
+                .iter()
+                .zip(&unclaimed)
+                .filter(|(fact, unclaimed)| **unclaimed || declares(doc, fact))
+                .map(|(fact, _)| fact.clone())
+                .collect();
+            let response =
+                self.evaluate_with_options(&doc.name, None, Some(doc_overrides), options)?;
+            responses.insert(doc.name.clone(), response);
+        }
+        Ok(responses)
+    }
+
+    /// Evaluate rules in a document, stopping early when `token` is cancelled
+    ///
+    /// Behaves like `evaluate_with_options`, but fails with
+    /// `LemmaError::Cancelled` once another thread calls `token.cancel()`,
+    /// e.g. when the client waiting for the response has disconnected.
+    pub fn evaluate_cancellable(
+        &self,
+        doc_name: &str,
+        rule_names: Option<Vec<String>>,
+        fact_overrides: Option<Vec<crate::LemmaFact>>,
+        options: &EvaluationOptions,
+        token: &CancellationToken,
+    ) -> LemmaResult<Response> {
+        let options = options.clone().with_cancellation(token.clone());
+        self.evaluate_with_options(doc_name, rule_names, fact_overrides, &options)
+    }
+
+    /// Check which vetoes a set of inputs triggers, without evaluating rule values
+    ///
+    /// Meant for form validation, where only "is this input acceptable" matters.
+    /// Only the unless clauses that veto are checked, and only the rules their
+    /// conditions depend on are computed. Fact overrides must be pre-parsed
+    /// using `parse_facts()`.
+    pub fn validate_inputs(
+        &self,
+        doc_name: &str,
+        fact_overrides: Vec<crate::LemmaFact>,
+    ) -> LemmaResult<crate::InputValidation> {
+        self.check_fact_sizes(&fact_overrides)?;
+        self.evaluator.validate_inputs(
+            doc_name,
+            &self.documents,
+            &self.sources,
+            fact_overrides,
+            &self.limits,
+            &self.functions,
+            &EvaluationOptions::default(),
+        )
+    }
+
+    /// The facts to ask for next to get further with the given rules
+    ///
+    /// Meant for conversational frontends that ask one question at a time:
+    /// evaluates as far as `partial_facts` allow and returns, per rule that
+    /// could not finish, the first fact it needs. Facts behind a condition
+    /// that is already decided are not asked for. An empty list means every
+    /// requested rule has a result. Fact overrides must be pre-parsed using
+    /// `parse_facts()`.
+    pub fn next_missing_facts(
+        &self,
+        doc_name: &str,
+        rule_names: Option<Vec<String>>,
+        partial_facts: Vec<crate::LemmaFact>,
+    ) -> LemmaResult<Vec<String>> {
+        self.check_fact_sizes(&partial_facts)?;
+        self.evaluator.next_missing_facts(
+            doc_name,
+            &self.documents,
+            &self.sources,
+            partial_facts,
+            rule_names,
+            &self.limits,
+            &self.functions,
+            &EvaluationOptions::default(),
+        )
+    }
+
+    fn check_fact_sizes(&self, overrides: &[crate::LemmaFact]) -> LemmaResult<()> {
+        for fact in overrides {
             if let crate::FactValue::Literal(lit) = &fact.value {
                 let size = lit.byte_size();
                 if size > self.limits.max_fact_value_bytes {
@@ -125,15 +1594,7 @@ impl Engine {
                 }
             }
         }
-
-        self.evaluator.evaluate_document(
-            doc_name,
-            &self.documents,
-            &self.sources,
-            overrides,
-            rule_names,
-            &self.limits,
-        )
+        Ok(())
     }
 
     /// Get all documents (needed by serializers for schema resolution)
@@ -155,13 +1616,144 @@ impl Engine {
         target: crate::Target,
         given_facts: HashMap<String, crate::LiteralValue>,
     ) -> LemmaResult<Vec<HashMap<crate::FactReference, crate::Domain>>> {
-        let shape = crate::inversion::inverter::invert(
+        let shape = self.invert_shape(document, rule, target, given_facts)?;
+        crate::inversion::domain_extraction::shape_to_domains(&shape)
+    }
+
+    /// The values of each fact that give `rule` a value, one summary per fact
+    ///
+    /// Merges the branches `invert` returns with `Target::any_value()` into a
+    /// normalized domain per fact, listed as intervals and values, for
+    /// validating form fields. Facts compared with each other, like
+    /// `start < end`, name each other in `depends_jointly_on`.
+    pub fn valid_domains(
+        &self,
+        document: &str,
+        rule: &str,
+        given_facts: HashMap<String, crate::LiteralValue>,
+    ) -> LemmaResult<HashMap<crate::FactReference, crate::DomainSummary>> {
+        let shape = self.invert_shape(document, rule, crate::Target::any_value(), given_facts)?;
+        crate::inversion::summary::summarize(&shape)
+    }
+
+    /// Invert a rule, keeping the branches with their conditions and origins
+    ///
+    /// Serializes to JSON that links each branch back to the unless clause it
+    /// came from, see `Shape`.
+    pub fn invert_shape(
+        &self,
+        document: &str,
+        rule: &str,
+        target: crate::Target,
+        given_facts: HashMap<String, crate::LiteralValue>,
+    ) -> LemmaResult<crate::Shape> {
+        crate::inversion::inverter::invert(document, rule, target, given_facts, &self.documents)
+    }
+
+    /// Try to prove the invariants of every document in the workspace
+    ///
+    /// Each invariant's rule is inverted for `false`: it is proven when no
+    /// facts can make it false, see `ProofOutcome`. Templates are proven
+    /// through their instances. Proofs are ordered by document name.
+    pub fn prove_invariants(&self) -> Vec<crate::InvariantProof> {
+        let mut docs: Vec<&crate::LemmaDoc> = self
+            .documents
+            .values()
+            .filter(|doc| !doc.is_template())
+            .collect();
+        docs.sort_by(|a, b| a.name.cmp(&b.name));
+        docs.into_iter()
+            .flat_map(|doc| crate::invariants::prove(doc, &self.documents))
+            .collect()
+    }
+
+    /// Translate a boolean rule to a SQL condition for a `WHERE` clause
+    ///
+    /// Facts declared with a type become columns of the same name. Only
+    /// comparisons and boolean logic translate, see `transpile`.
+    pub fn to_sql(
+        &self,
+        document: &str,
+        rule: &str,
+        dialect: crate::SqlDialect,
+    ) -> LemmaResult<String> {
+        crate::transpile::to_sql(document, rule, dialect, &self.documents)
+    }
+
+    /// Evaluate a rule for evenly spaced values of one fact
+    ///
+    /// `fact` is a fact path such as `quantity` or `order.quantity`, swept
+    /// from the start of `range` to its end in increments of `step`.
+    /// `fact_overrides` fix the other facts; an override for the swept fact
+    /// itself is ignored.
+    pub fn sweep(
+        &self,
+        document: &str,
+        rule: &str,
+        fact: &str,
+        range: std::ops::RangeInclusive<crate::LiteralValue>,
+        step: rust_decimal::Decimal,
+        fact_overrides: Vec<crate::LemmaFact>,
+    ) -> LemmaResult<crate::Sweep> {
+        crate::sweep::sweep(self, document, rule, fact, range, step, fact_overrides)
+    }
+
+    /// How much a rule result changes per unit change of each of `facts`
+    ///
+    /// Each fact is a numeric fact path such as `quantity` or `item.price`.
+    /// The sensitivities are computed at the document's fact values with
+    /// `at_facts` applied, and reported with the most influential fact first.
+    /// Rules of plain arithmetic over numbers are differentiated exactly,
+    /// others numerically, see `sensitivity`.
+    pub fn sensitivity(
+        &self,
+        document: &str,
+        rule: &str,
+        facts: &[&str],
+        at_facts: Vec<crate::LemmaFact>,
+    ) -> LemmaResult<crate::SensitivityReport> {
+        crate::sensitivity::sensitivity(self, document, rule, facts, at_facts)
+    }
+
+    /// Random fact sets for which a rule meets `target`
+    ///
+    /// Values are drawn from the domains inverting the rule gives, and each
+    /// set is evaluated to check it meets the target, so the sets can be used
+    /// as realistic test payloads. Returns fewer than `count` sets when too
+    /// few draws meet the target. Uses seed 0, see `generate_inputs_with_options`.
+    pub fn generate_inputs(
+        &self,
+        document: &str,
+        rule: &str,
+        target: crate::Target,
+        count: usize,
+    ) -> LemmaResult<Vec<Vec<crate::LemmaFact>>> {
+        self.generate_inputs_with_options(
             document,
             rule,
             target,
-            given_facts,
-            &self.documents,
-        )?;
-        crate::inversion::domain_extraction::shape_to_domains(&shape)
+            count,
+            &crate::GenerateOptions::default(),
+        )
     }
+
+    /// Random fact sets for a rule with a seed, or that miss `target`
+    ///
+    /// With `options.violating` every set misses the target, for negative
+    /// tests: one fact per set is drawn from outside its domain.
+    pub fn generate_inputs_with_options(
+        &self,
+        document: &str,
+        rule: &str,
+        target: crate::Target,
+        count: usize,
+        options: &crate::GenerateOptions,
+    ) -> LemmaResult<Vec<Vec<crate::LemmaFact>>> {
+        crate::generate::generate_inputs(self, document, rule, target, count, options)
+    }
+}
+
+/// The parts of partial documents among `docs`
+fn partial_parts_of(docs: &[LemmaDoc]) -> Vec<LemmaDoc> {
+    docs.iter().filter(|doc| doc.partial).cloned().collect()
 }
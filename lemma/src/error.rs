@@ -1,4 +1,5 @@
 use crate::ast::Span;
+use crate::LemmaType;
 use std::fmt;
 use std::sync::Arc;
 
@@ -14,6 +15,35 @@ pub struct ErrorDetails {
     pub suggestion: Option<String>,
 }
 
+/// A fact override that could not be parsed or does not match its declared type
+///
+/// One error is produced per offending input, so callers (forms, CLIs) can point
+/// at the exact field and character that was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FactParseError {
+    /// The raw input, e.g. `price=abc`
+    pub input: String,
+    /// Name of the fact, when the input got far enough to tell (`price`, `order.total`)
+    pub fact: Option<String>,
+    /// Byte offset into `input` where the problem starts
+    pub position: usize,
+    /// What would have been accepted at `position` (e.g. "number", "text")
+    pub expected: Vec<String>,
+    /// Type declared for the fact in the document, when known
+    pub declared_type: Option<LemmaType>,
+    pub message: String,
+}
+
+impl fmt::Display for FactParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to parse fact '{}' at position {}: {}",
+            self.input, self.position, self.message
+        )
+    }
+}
+
 /// Error types for the Lemma system with source location tracking
 #[derive(Debug, Clone)]
 pub enum LemmaError {
@@ -40,6 +70,28 @@ pub enum LemmaError {
         suggestion: String,
     },
 
+    /// One or more fact overrides could not be parsed
+    FactParse(Vec<FactParseError>),
+
+    /// The document changed since the version an evaluation was pinned to
+    VersionMismatch {
+        doc_name: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// A rule has no value, with the reason, e.g. a division by zero under
+    /// `on division by zero unknown`
+    ///
+    /// The evaluator reports the rule, and rules referencing it, as unknown.
+    UnknownResult(String),
+
+    /// Evaluation was stopped through a `CancellationToken`
+    Cancelled {
+        /// The rule being evaluated when the cancellation was noticed
+        rule: Option<String>,
+    },
+
     /// Multiple errors collected together
     MultipleErrors(Vec<LemmaError>),
 }
@@ -178,6 +230,24 @@ impl fmt::Display for LemmaError {
                     limit_name, limit_value, actual_value, suggestion
                 )
             }
+            LemmaError::FactParse(errors) => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("; "))
+            }
+            LemmaError::VersionMismatch {
+                doc_name,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Document '{}' changed: expected version {}, found {}",
+                doc_name, expected, actual
+            ),
+            LemmaError::UnknownResult(reason) => write!(f, "Unknown result: {}", reason),
+            LemmaError::Cancelled { rule: Some(rule) } => {
+                write!(f, "Evaluation cancelled while evaluating rule '{}'", rule)
+            }
+            LemmaError::Cancelled { rule: None } => write!(f, "Evaluation cancelled"),
             LemmaError::MultipleErrors(errors) => {
                 writeln!(f, "Multiple errors:")?;
                 for (i, error) in errors.iter().enumerate() {
@@ -0,0 +1,129 @@
+use crate::evaluator::timeout::CancellationToken;
+
+/// Per-evaluation options
+///
+/// Unlike `ResourceLimits`, which are fixed for an engine, these options can
+/// differ for every call to `Engine::evaluate_with_options`.
+#[derive(Debug, Clone, Default)]
+pub struct EvaluationOptions {
+    /// Preferred locales for veto messages, most preferred first (e.g. "nl-BE", "en")
+    /// The first locale with a translation is used, the default message when none has one.
+    pub locales: Vec<String>,
+
+    /// How much of the operation trace to record in each `RuleResult`
+    pub trace_level: TraceLevel,
+
+    /// What to do with overrides for facts the document doesn't have
+    pub unused_overrides: UnusedOverrides,
+
+    /// Version the document must have, see `Engine::document_version`
+    /// Evaluation fails with `LemmaError::VersionMismatch` when it changed.
+    pub pinned_version: Option<String>,
+
+    /// Evaluate a tagged version of the documents, see `Engine::tag_version`
+    /// A date selects the latest version tagged on or before it.
+    pub as_of: Option<String>,
+
+    /// Token to stop the evaluation early, see `Engine::evaluate_cancellable`
+    pub cancellation: Option<CancellationToken>,
+
+    /// Leave `internal` rules out of the response, and fail when one is requested
+    /// They are still evaluated for the public rules that use them.
+    pub public_rules_only: bool,
+
+    /// Document evaluated alongside with the same inputs, see `Response::shadow`
+    /// Its results are only compared, never authoritative.
+    pub shadow: Option<String>,
+}
+
+/// Amount of operation trace recorded during evaluation
+///
+/// Recording every `OperationRecord` dominates runtime for hot rules;
+/// lower levels skip building the records altogether.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TraceLevel {
+    /// Record every fact, rule reference, operation and unless clause
+    #[default]
+    Full,
+    /// Record only the final result of each rule
+    RuleResultsOnly,
+    /// Record nothing
+    Off,
+}
+
+/// Handling of fact overrides that match no fact in the document
+///
+/// Such overrides are usually typos (`quanity=5`) and would otherwise be
+/// ignored silently. Both modes suggest the closest fact name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnusedOverrides {
+    /// Evaluate anyway and report an `unused_override` diagnostic
+    #[default]
+    Warn,
+    /// Fail with a `LemmaError::FactParse` for each unused override
+    Strict,
+}
+
+impl EvaluationOptions {
+    /// Create options with default values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Select the locale used for veto messages
+    pub fn with_locale(mut self, locale: impl Into<String>) -> Self {
+        self.locales = vec![locale.into()];
+        self
+    }
+
+    /// Select the locales used for veto messages, most preferred first
+    pub fn with_locales(mut self, locales: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.locales = locales.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Select how much of the operation trace to record
+    pub fn with_trace_level(mut self, trace_level: TraceLevel) -> Self {
+        self.trace_level = trace_level;
+        self
+    }
+
+    /// Select what to do with overrides for unknown facts
+    pub fn with_unused_overrides(mut self, unused_overrides: UnusedOverrides) -> Self {
+        self.unused_overrides = unused_overrides;
+        self
+    }
+
+    /// Only return public rules, see `RuleVisibility`
+    pub fn with_public_rules_only(mut self, public_rules_only: bool) -> Self {
+        self.public_rules_only = public_rules_only;
+        self
+    }
+
+    /// Fail instead of evaluating a document whose version is not `version`
+    pub fn with_pinned_version(mut self, version: impl Into<String>) -> Self {
+        self.pinned_version = Some(version.into());
+        self
+    }
+
+    /// Evaluate the documents as tagged `label`, or as in force on a date like `2024-06`
+    pub fn with_as_of(mut self, label: impl Into<String>) -> Self {
+        self.as_of = Some(label.into());
+        self
+    }
+
+    /// Also evaluate `doc_name` with the same inputs and report where it differs
+    ///
+    /// Meant for trialling a changed copy of a document, e.g. `pricing_next`
+    /// next to `pricing`, on real inputs before switching over.
+    pub fn with_shadow(mut self, doc_name: impl Into<String>) -> Self {
+        self.shadow = Some(doc_name.into());
+        self
+    }
+
+    /// Stop the evaluation with `LemmaError::Cancelled` once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
@@ -2,13 +2,16 @@
 //!
 //! Contains all state needed during evaluation of a single document.
 
+use crate::analysis::{collection_instances, CollectionInstances};
 use crate::{
-    FactReference, FactType, FactValue, LemmaDoc, LemmaError, LemmaFact, LiteralValue,
-    OperationRecord, OperationResult, ResourceLimits,
+    collection_instance_name, Diagnostic, DiagnosticKind, EvaluationOptions, FactReference,
+    FactType, FactValue, LemmaDoc, LemmaError, LemmaFact, LiteralValue, OperationRecord,
+    OperationResult, ResourceLimits, RulePathSegment, TraceLevel,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::timeout::TimeoutTracker;
+use crate::functions::FunctionRegistry;
 
 /// Context for evaluating a Lemma document
 ///
@@ -39,16 +42,49 @@ pub struct EvaluationContext<'a> {
     /// Resource limits including timeout
     pub limits: &'a ResourceLimits,
 
+    /// Per-evaluation options (e.g. locale for veto messages)
+    pub options: &'a EvaluationOptions,
+
+    /// Functions registered by the host application
+    pub functions: &'a FunctionRegistry,
+
     /// Rule results computed so far (populated during execution)
     /// Maps RulePath -> operation result (either Value or Veto)
     pub rule_results: HashMap<crate::RulePath, OperationResult>,
 
-    /// Operation records - records every operation
+    /// Rules that could not be evaluated because a fact was missing.
+    /// Referencing one fails the referencing rule the same way, but only when
+    /// the reference is actually evaluated.
+    pub failed_rules: HashSet<crate::RulePath>,
+
+    /// Rules that are unknown under `on division by zero unknown`, with the
+    /// reason. Referencing one makes the referencing rule unknown too.
+    pub unknown_rules: HashMap<crate::RulePath, String>,
+
+    /// Operation records, filtered by the trace level in `options`
     pub operations: Vec<OperationRecord>,
+
+    /// Path segments of the rule being evaluated (empty for local rules)
+    /// Rule references are resolved relative to the rule's own document.
+    pub rule_segments: Vec<RulePathSegment>,
+
+    /// Instances of each collection fact, used to expand aggregates
+    pub collection_instances: CollectionInstances,
+
+    /// Diagnostics for the rule being evaluated, moved to the response after it
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// Whether the trace masks values derived from sensitive facts: only a
+    /// full trace records them, and only when some fact is sensitive
+    pub masks_sensitive: bool,
+
+    /// Rules whose value derives from a sensitive fact, masked where they are used
+    pub sensitive_rules: HashSet<crate::RulePath>,
 }
 
 impl<'a> EvaluationContext<'a> {
     /// Create a new evaluation context
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         current_doc: &'a LemmaDoc,
         all_documents: &'a HashMap<String, LemmaDoc>,
@@ -56,6 +92,8 @@ impl<'a> EvaluationContext<'a> {
         facts: HashMap<FactReference, LiteralValue>,
         timeout_tracker: &'a TimeoutTracker,
         limits: &'a ResourceLimits,
+        functions: &'a FunctionRegistry,
+        options: &'a EvaluationOptions,
     ) -> Self {
         Self {
             current_doc,
@@ -63,9 +101,87 @@ impl<'a> EvaluationContext<'a> {
             sources,
             facts,
             rule_results: HashMap::new(),
+            failed_rules: HashSet::new(),
+            unknown_rules: HashMap::new(),
             operations: Vec::new(),
+            rule_segments: Vec::new(),
+            collection_instances: CollectionInstances::new(),
+            diagnostics: Vec::new(),
+            masks_sensitive: options.trace_level == TraceLevel::Full
+                && std::iter::once(current_doc)
+                    .chain(all_documents.values())
+                    .any(|doc| doc.facts.iter().any(|fact| fact.sensitive)),
+            sensitive_rules: HashSet::new(),
             timeout_tracker,
             limits,
+            functions,
+            options,
+        }
+    }
+
+    /// Record an operation if the trace level asks for it
+    ///
+    /// The record is built lazily, so with a reduced trace level nothing is
+    /// cloned or allocated for skipped steps.
+    pub fn push_operation(&mut self, record: impl FnOnce() -> OperationRecord) {
+        if self.options.trace_level == TraceLevel::Full {
+            self.operations.push(record());
+        }
+    }
+
+    /// Record the final value of a rule unless tracing is off
+    pub fn push_final_result(&mut self, value: &LiteralValue) {
+        if self.options.trace_level != TraceLevel::Off {
+            self.operations.push(OperationRecord::FinalResult {
+                value: value.clone(),
+            });
+        }
+    }
+
+    /// Note a non-fatal issue in the rule being evaluated
+    pub fn warn(&mut self, kind: DiagnosticKind, message: String) {
+        self.diagnostics.push(Diagnostic {
+            kind,
+            rule: None,
+            fact: None,
+            message,
+        });
+    }
+
+    /// Result of a rule evaluated earlier
+    ///
+    /// A rule that failed on a missing fact reports itself as missing, so the
+    /// caller fails the same way. An unknown rule makes the caller unknown.
+    pub fn rule_result(&self, path: &crate::RulePath) -> Result<&OperationResult, LemmaError> {
+        if self.failed_rules.contains(path) {
+            return Err(LemmaError::Engine(format!("Missing fact: {}", path)));
+        }
+        if let Some(reason) = self.unknown_rules.get(path) {
+            return Err(LemmaError::UnknownResult(reason.clone()));
+        }
+        self.rule_results
+            .get(path)
+            .ok_or_else(|| LemmaError::Engine(format!("Rule {} not found", path)))
+    }
+
+    /// Whether the fact at a path of the evaluated document is marked `sensitive`
+    pub fn is_sensitive_fact(&self, fact: &FactReference) -> bool {
+        self.masks_sensitive
+            && crate::analysis::is_sensitive_fact(
+                self.current_doc,
+                &fact.reference,
+                self.all_documents,
+            )
+    }
+
+    /// Document containing the rule being evaluated
+    pub fn rule_doc(&self) -> Result<&'a LemmaDoc, LemmaError> {
+        match self.rule_segments.last() {
+            Some(segment) => self
+                .all_documents
+                .get(&segment.doc)
+                .ok_or_else(|| LemmaError::Engine(format!("Document {} not found", segment.doc))),
+            None => Ok(self.current_doc),
         }
     }
 
@@ -79,6 +195,8 @@ impl<'a> EvaluationContext<'a> {
 ///
 /// Includes facts with concrete values (FactValue::Literal) and expands
 /// DocumentReference facts by importing all facts from the referenced document.
+/// DocumentCollection facts import the item document's facts once per instance
+/// (`line_items[0].quantity`, `line_items[1].quantity`, ...).
 /// Facts with TypeAnnotation are missing and will cause evaluation errors.
 ///
 /// Validates that fact overrides match the expected types declared in the document.
@@ -88,43 +206,30 @@ pub fn build_fact_map(
     overrides: &[LemmaFact],
     all_documents: &HashMap<String, LemmaDoc>,
 ) -> Result<HashMap<FactReference, LiteralValue>, LemmaError> {
+    let instances = collection_instances(doc, overrides, all_documents);
     let mut facts = HashMap::new();
 
     // Add document facts
-    for fact in doc_facts {
-        match &fact.value {
-            FactValue::Literal(lit) => {
-                let path = get_fact_path(fact);
-                facts.insert(path, lit.clone());
-            }
-            FactValue::DocumentReference(doc_name) => {
-                // Resolve document reference by recursively importing all facts from referenced doc
-                if let Some(referenced_doc) = all_documents.get(doc_name) {
-                    let fact_prefix = get_fact_path(fact);
-                    // Recursively build fact map for the referenced document
-                    let referenced_facts =
-                        build_fact_map(referenced_doc, &referenced_doc.facts, &[], all_documents)?;
-                    for (ref_fact_path, lit) in referenced_facts {
-                        // Prepend the prefix to create the qualified path
-                        let mut qualified_reference = fact_prefix.reference.clone();
-                        qualified_reference.extend_from_slice(&ref_fact_path.reference);
-                        let qualified_path = FactReference {
-                            reference: qualified_reference,
-                        };
-                        facts.insert(qualified_path, lit);
-                    }
-                }
-            }
-            FactValue::TypeAnnotation(_) => {
-                // Skip type annotations
-            }
-        }
-    }
+    insert_doc_facts(doc_facts, &[], &instances, all_documents, &mut facts);
 
     // Apply overrides with type validation
     for fact in overrides {
         if let FactValue::Literal(lit) = &fact.value {
             let path = get_fact_path(fact);
+            let lit = &crate::analysis::coerce_fact_value(doc, &path.reference, lit, all_documents)
+                .unwrap_or_else(|| lit.clone());
+            crate::analysis::check_fact_currency(doc, &path.reference, lit, all_documents)
+                .and_then(|()| {
+                    crate::analysis::check_percentage_points(
+                        doc,
+                        &path.reference,
+                        lit,
+                        all_documents,
+                    )
+                })
+                .map_err(|message| {
+                    LemmaError::Engine(format!("Invalid value for fact '{}': {}", path, message))
+                })?;
 
             // Check if this fact exists in the document and validate type
             if let Some(expected_type) = doc.get_fact_type(&path) {
@@ -144,8 +249,63 @@ pub fn build_fact_map(
     Ok(facts)
 }
 
+/// Insert facts of a document, qualified by `prefix`, recursing into referenced documents
+fn insert_doc_facts(
+    doc_facts: &[LemmaFact],
+    prefix: &[String],
+    instances: &CollectionInstances,
+    all_documents: &HashMap<String, LemmaDoc>,
+    facts: &mut HashMap<FactReference, LiteralValue>,
+) {
+    for fact in doc_facts {
+        let mut reference = prefix.to_vec();
+        reference.extend(get_fact_path(fact).reference);
+
+        match &fact.value {
+            FactValue::Literal(lit) => {
+                facts.insert(FactReference { reference }, lit.clone());
+            }
+            FactValue::DocumentReference(doc_name) => {
+                // Resolve document reference by recursively importing all facts from referenced doc
+                if let Some(referenced_doc) = all_documents.get(doc_name) {
+                    insert_doc_facts(
+                        &referenced_doc.facts,
+                        &reference,
+                        instances,
+                        all_documents,
+                        facts,
+                    );
+                }
+            }
+            FactValue::DocumentCollection(doc_name) => {
+                let (Some(item_doc), Some(indices)) =
+                    (all_documents.get(doc_name), instances.get(&reference))
+                else {
+                    continue;
+                };
+                if let Some((collection, parent)) = reference.split_last() {
+                    for index in indices {
+                        let mut instance_path = parent.to_vec();
+                        instance_path.push(collection_instance_name(collection, *index));
+                        insert_doc_facts(
+                            &item_doc.facts,
+                            &instance_path,
+                            instances,
+                            all_documents,
+                            facts,
+                        );
+                    }
+                }
+            }
+            FactValue::TypeAnnotation(_) => {
+                // Skip type annotations
+            }
+        }
+    }
+}
+
 /// Get the fact reference for a fact (handles local and foreign facts)
-fn get_fact_path(fact: &LemmaFact) -> FactReference {
+pub(crate) fn get_fact_path(fact: &LemmaFact) -> FactReference {
     match &fact.fact_type {
         FactType::Local(name) => FactReference {
             reference: vec![name.clone()],
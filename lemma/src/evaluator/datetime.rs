@@ -3,8 +3,8 @@
 //! Handles arithmetic and comparisons with dates and datetimes.
 
 use crate::{
-    ArithmeticOperation, ComparisonOperator, DateTimeValue, LemmaError, LemmaResult, LiteralValue,
-    TimeValue, TimezoneValue,
+    ArithmeticOperation, ComparisonOperator, DateGranularity, DateTimeValue, LemmaError,
+    LemmaResult, LiteralValue, TimeValue, TimezoneValue,
 };
 use chrono::{
     DateTime, Datelike, Duration as ChronoDuration, FixedOffset, NaiveDate, NaiveDateTime,
@@ -43,6 +43,17 @@ fn create_timezone_offset(timezone: &Option<TimezoneValue>) -> LemmaResult<Fixed
 }
 
 /// Perform date/datetime arithmetic
+///
+/// - `date + duration` and `duration + date` give a date, `date - duration`
+///   the date before. Months and years are calendar units: they move the
+///   month (a year is 12 months) and keep the day, clamped to the end of the
+///   month: `2024-01-31 + 1 month` is `2024-02-29`. They must be whole.
+///   Other units are exact amounts of time.
+/// - `date - date` gives the exact duration between them in seconds; it is
+///   negative when the left date is earlier. See `calendar_difference` for
+///   whole months or years between dates.
+///
+/// A year, month or week counts as its first day, and the result is an exact date.
 pub fn datetime_arithmetic(
     left: &LiteralValue,
     op: &ArithmeticOperation,
@@ -54,72 +65,19 @@ pub fn datetime_arithmetic(
             LiteralValue::Date(date),
             LiteralValue::Unit(crate::NumericUnit::Duration(value, unit)),
             ArithmeticOperation::Add,
-        ) => {
-            let dt = datetime_value_to_chrono(date)?;
-
-            let new_dt = match unit {
-                crate::DurationUnit::Month => {
-                    let months = value
-                        .to_i32()
-                        .ok_or_else(|| LemmaError::Engine("Month value too large".to_string()))?;
-                    dt.checked_add_months(chrono::Months::new(months as u32))
-                        .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
-                }
-                crate::DurationUnit::Year => {
-                    let years = value
-                        .to_i32()
-                        .ok_or_else(|| LemmaError::Engine("Year value too large".to_string()))?;
-                    dt.checked_add_months(chrono::Months::new(
-                        (years * MONTHS_PER_YEAR as i32) as u32,
-                    ))
-                    .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
-                }
-                _ => {
-                    let seconds = crate::parser::units::duration_to_seconds(*value, unit);
-                    let duration = seconds_to_chrono_duration(seconds)?;
-                    dt.checked_add_signed(duration)
-                        .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
-                }
-            };
-
-            Ok(LiteralValue::Date(chrono_to_datetime_value(new_dt)))
-        }
+        )
+        | (
+            LiteralValue::Unit(crate::NumericUnit::Duration(value, unit)),
+            LiteralValue::Date(date),
+            ArithmeticOperation::Add,
+        ) => shift_date(date, *value, unit),
 
         // Date - Duration
         (
             LiteralValue::Date(date),
             LiteralValue::Unit(crate::NumericUnit::Duration(value, unit)),
             ArithmeticOperation::Subtract,
-        ) => {
-            let dt = datetime_value_to_chrono(date)?;
-
-            let new_dt = match unit {
-                crate::DurationUnit::Month => {
-                    let months = value
-                        .to_i32()
-                        .ok_or_else(|| LemmaError::Engine("Month value too large".to_string()))?;
-                    dt.checked_sub_months(chrono::Months::new(months as u32))
-                        .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
-                }
-                crate::DurationUnit::Year => {
-                    let years = value
-                        .to_i32()
-                        .ok_or_else(|| LemmaError::Engine("Year value too large".to_string()))?;
-                    dt.checked_sub_months(chrono::Months::new(
-                        (years * MONTHS_PER_YEAR as i32) as u32,
-                    ))
-                    .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
-                }
-                _ => {
-                    let seconds = crate::parser::units::duration_to_seconds(*value, unit);
-                    let duration = seconds_to_chrono_duration(seconds)?;
-                    dt.checked_sub_signed(duration)
-                        .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
-                }
-            };
-
-            Ok(LiteralValue::Date(chrono_to_datetime_value(new_dt)))
-        }
+        ) => shift_date(date, -*value, unit),
 
         // Date - Date = Duration (in seconds)
         (
@@ -145,6 +103,97 @@ pub fn datetime_arithmetic(
     }
 }
 
+/// Move `date` by `value` units, backwards for a negative value
+fn shift_date(
+    date: &DateTimeValue,
+    value: Decimal,
+    unit: &crate::DurationUnit,
+) -> LemmaResult<LiteralValue> {
+    let dt = datetime_value_to_chrono(date)?;
+    let new_dt = match calendar_months(value, unit)? {
+        Some(months) => add_months(dt, months)?,
+        None => {
+            let seconds = crate::parser::units::duration_to_seconds(value, unit);
+            let duration = seconds_to_chrono_duration(seconds)?;
+            dt.checked_add_signed(duration)
+                .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))?
+        }
+    };
+    Ok(LiteralValue::Date(chrono_to_datetime_value(new_dt)))
+}
+
+/// The number of months in a duration of months or years, None for other units
+fn calendar_months(value: Decimal, unit: &crate::DurationUnit) -> LemmaResult<Option<i64>> {
+    let months = match unit {
+        crate::DurationUnit::Month => value,
+        crate::DurationUnit::Year => value * Decimal::from(MONTHS_PER_YEAR),
+        _ => return Ok(None),
+    };
+    if !months.fract().is_zero() {
+        return Err(LemmaError::Engine(format!(
+            "Cannot move a date by {} {}s: months and years must be whole numbers",
+            value.abs().normalize(),
+            unit
+        )));
+    }
+    months
+        .to_i64()
+        .map(Some)
+        .ok_or_else(|| LemmaError::Engine(format!("{} value too large", unit)))
+}
+
+/// Add (or, when negative, subtract) calendar months, clamping the day to the end of the month
+fn add_months(dt: DateTime<FixedOffset>, months: i64) -> LemmaResult<DateTime<FixedOffset>> {
+    let amount = u32::try_from(months.unsigned_abs())
+        .map(chrono::Months::new)
+        .map_err(|_| LemmaError::Engine("Date overflow".to_string()))?;
+    if months >= 0 {
+        dt.checked_add_months(amount)
+    } else {
+        dt.checked_sub_months(amount)
+    }
+    .ok_or_else(|| LemmaError::Engine("Date overflow".to_string()))
+}
+
+/// Whole months or years from `start` to `end`, as for an age or a tenure
+///
+/// Counts the units completed: a unit is complete once `start` plus that many
+/// units (with the same end-of-month clamping as `date + duration`) is not
+/// after `end`. Someone born on 2004-02-29 is 18 years old on 2022-02-28.
+/// The difference is negative when `end` is before `start`.
+pub fn calendar_difference(
+    end: &DateTimeValue,
+    start: &DateTimeValue,
+    unit: &crate::DurationUnit,
+) -> LemmaResult<Decimal> {
+    let end_dt = datetime_value_to_chrono(end)?;
+    let start_dt = datetime_value_to_chrono(start)?;
+    let (earlier, later, sign) = if start_dt <= end_dt {
+        (start_dt, end_dt, 1)
+    } else {
+        (end_dt, start_dt, -1)
+    };
+
+    let mut months = i64::from(later.year() - earlier.year()) * i64::from(MONTHS_PER_YEAR)
+        + i64::from(later.month())
+        - i64::from(earlier.month());
+    if add_months(earlier, months)? > later {
+        months -= 1;
+    }
+
+    let whole = match unit {
+        crate::DurationUnit::Month => months,
+        crate::DurationUnit::Year => months / i64::from(MONTHS_PER_YEAR),
+        other => {
+            return Err(LemmaError::Engine(format!(
+                "Calendar difference in {}s is not supported, only in months or years",
+                other
+            )))
+        }
+    };
+    Ok(Decimal::from(whole * sign))
+}
+
 /// Convert DateTimeValue to chrono DateTime, handling timezone if present
 fn datetime_value_to_chrono(date: &DateTimeValue) -> LemmaResult<DateTime<FixedOffset>> {
     let naive_date = NaiveDate::from_ymd_opt(date.year, date.month, date.day).ok_or_else(|| {
@@ -188,6 +237,7 @@ fn chrono_to_datetime_value(dt: DateTime<FixedOffset>) -> DateTimeValue {
             offset_hours,
             offset_minutes,
         }),
+        granularity: DateGranularity::Exact,
     }
 }
 
@@ -203,28 +253,31 @@ fn seconds_to_chrono_duration(seconds: Decimal) -> LemmaResult<ChronoDuration> {
 }
 
 /// Perform date/datetime comparisons
+///
+/// Every date covers a period: a year, month or week for `2026`, `2026-02`
+/// and `2026-W08`, or the second it names for an exact date. `is` holds when
+/// one period lies within the other, `<` and `>` when one ends before the
+/// other starts; `<=` and `>=` are their negations. For exact dates this is
+/// the usual comparison of moments. Both sides are compared in UTC.
 pub fn datetime_comparison(
     left: &LiteralValue,
     op: &ComparisonOperator,
     right: &LiteralValue,
 ) -> LemmaResult<bool> {
     match (left, right) {
-        // Date comparisons - convert both to UTC for fair comparison
         (LiteralValue::Date(l), LiteralValue::Date(r)) => {
-            let l_dt = datetime_value_to_chrono(l)?;
-            let r_dt = datetime_value_to_chrono(r)?;
-
-            // Convert to UTC for comparison
-            let l_utc = l_dt.naive_utc();
-            let r_utc = r_dt.naive_utc();
+            let (l_start, l_end) = period_bounds(l)?;
+            let (r_start, r_end) = period_bounds(r)?;
+            let within =
+                (l_start >= r_start && l_end <= r_end) || (r_start >= l_start && r_end <= l_end);
 
             Ok(match op {
-                ComparisonOperator::GreaterThan => l_utc > r_utc,
-                ComparisonOperator::LessThan => l_utc < r_utc,
-                ComparisonOperator::GreaterThanOrEqual => l_utc >= r_utc,
-                ComparisonOperator::LessThanOrEqual => l_utc <= r_utc,
-                ComparisonOperator::Equal | ComparisonOperator::Is => l_utc == r_utc,
-                ComparisonOperator::NotEqual | ComparisonOperator::IsNot => l_utc != r_utc,
+                ComparisonOperator::GreaterThan => l_start >= r_end,
+                ComparisonOperator::LessThan => l_end <= r_start,
+                ComparisonOperator::GreaterThanOrEqual => l_end > r_start,
+                ComparisonOperator::LessThanOrEqual => l_start < r_end,
+                ComparisonOperator::Equal | ComparisonOperator::Is => within,
+                ComparisonOperator::NotEqual | ComparisonOperator::IsNot => !within,
             })
         }
 
@@ -234,6 +287,18 @@ pub fn datetime_comparison(
     }
 }
 
+/// The UTC start and (exclusive) end of the period a date covers
+fn period_bounds(date: &DateTimeValue) -> LemmaResult<(NaiveDateTime, NaiveDateTime)> {
+    let start = datetime_value_to_chrono(date)?;
+    let end = match date.granularity {
+        DateGranularity::Exact => start + ChronoDuration::seconds(1),
+        DateGranularity::Week => start + ChronoDuration::weeks(1),
+        DateGranularity::Month => add_months(start, 1)?,
+        DateGranularity::Year => add_months(start, i64::from(MONTHS_PER_YEAR))?,
+    };
+    Ok((start.naive_utc(), end.naive_utc()))
+}
+
 /// Perform time arithmetic operations
 pub fn time_arithmetic(
     left: &LiteralValue,
@@ -4,8 +4,10 @@
 
 use super::context::EvaluationContext;
 use crate::{
-    ast::Span, ArithmeticOperation, Expression, ExpressionKind, FactReference, LemmaError,
-    LiteralValue, MathematicalOperator, OperationRecord, OperationResult,
+    ast::Span, AggregateExpression, AggregateFunction, ArithmeticOperation, ComparisonOperator,
+    ConversionTarget, DiagnosticKind, DivisionByZero, DurationUnit, Expression, ExpressionKind,
+    FactReference, LemmaError, LiteralValue, MathematicalOperator, MoneyUnit, NumericUnit,
+    OperationRecord, OperationResult, RangeCondition, SelectionFunction,
 };
 use rust_decimal::Decimal;
 use std::sync::Arc;
@@ -28,107 +30,70 @@ pub fn evaluate_expression(
 
     match &expr.kind {
         ExpressionKind::Literal(lit) => {
+            if let LiteralValue::Boolean(_) = lit {
+                check_boolean_alias(expr, context);
+            }
             // Literals evaluate to themselves
             Ok(OperationResult::Value(lit.clone()))
         }
 
         ExpressionKind::FactReference(fact_ref) => {
             // Look up fact in context, prepending the prefix when evaluating a rule from a referenced document
-            let lookup_ref = if !fact_prefix.is_empty() {
-                // Evaluating a rule from a document referenced by a fact: prepend the fact path
-                // E.g., if `employee` references `doc hr_doc` and we're evaluating `employee.salary?`,
-                // fact references within that rule need the `employee` prefix
-                let mut qualified_reference = fact_prefix.to_vec();
-                qualified_reference.extend_from_slice(&fact_ref.reference);
-                FactReference {
-                    reference: qualified_reference,
-                }
-            } else {
-                // Local rule: use fact reference as-is
-                fact_ref.clone()
-            };
+            let lookup_ref = qualify_fact_reference(fact_ref, fact_prefix);
 
             let value = context.facts.get(&lookup_ref).ok_or_else(|| {
                 LemmaError::Engine(format!("Missing fact: {}", lookup_ref.reference.join(".")))
             })?;
 
             // Record operation (convert path to string for display)
-            context.operations.push(OperationRecord::FactUsed {
+            let value = value.clone();
+            let sensitive = context.is_sensitive_fact(&lookup_ref);
+            context.push_operation(|| OperationRecord::FactUsed {
                 name: lookup_ref.reference.join("."),
-                value: value.clone(),
+                value: traced(&value, sensitive),
             });
 
-            Ok(OperationResult::Value(value.clone()))
+            Ok(OperationResult::Value(value))
         }
         ExpressionKind::RuleReference(rule_ref) => {
             // Look up already-computed rule result
             // Topological sort ensures this rule was computed before us
+            // Resolved relative to the document of the rule being evaluated
             let rule_path = crate::RulePath::from_reference(
                 &rule_ref.reference,
-                context.current_doc,
+                context.rule_doc()?,
                 context.all_documents,
-            )?;
+            )?
+            .with_prefix(&context.rule_segments);
 
-            // Check if rule has a result
-            if let Some(result) = context.rule_results.get(&rule_path) {
-                match result {
-                    OperationResult::Veto(msg) => {
-                        // Rule was vetoed - the veto applies to this rule too
-                        return Ok(OperationResult::Veto(msg.clone()));
-                    }
-                    OperationResult::Value(value) => {
-                        // Record operation
-                        context.operations.push(OperationRecord::RuleUsed {
-                            name: rule_path.to_string(),
-                            value: value.clone(),
-                        });
-                        return Ok(OperationResult::Value(value.clone()));
-                    }
+            match context.rule_result(&rule_path)? {
+                OperationResult::Veto(msg) => {
+                    // Rule was vetoed - the veto applies to this rule too
+                    Ok(OperationResult::Veto(msg.clone()))
+                }
+                OperationResult::Value(value) => {
+                    // Record operation
+                    let value = value.clone();
+                    let sensitive = context.sensitive_rules.contains(&rule_path);
+                    context.push_operation(|| OperationRecord::RuleUsed {
+                        name: rule_path.to_string(),
+                        value: traced(&value, sensitive),
+                    });
+                    Ok(OperationResult::Value(value))
                 }
             }
+        }
 
-            // Rule not computed yet
-            Err(LemmaError::Engine(format!("Rule {} not found", rule_path)))
+        ExpressionKind::Aggregate(aggregate) => evaluate_aggregate(aggregate, context),
+
+        ExpressionKind::Selection(function, candidates) => {
+            evaluate_selection(*function, candidates, context, fact_prefix)
         }
 
         ExpressionKind::Arithmetic(left, op, right) => {
             let left_result = evaluate_expression(left, context, fact_prefix)?;
             let right_result = evaluate_expression(right, context, fact_prefix)?;
-
-            // If either operand is vetoed, propagate the veto
-            if let OperationResult::Veto(msg) = left_result {
-                return Ok(OperationResult::Veto(msg));
-            }
-            if let OperationResult::Veto(msg) = right_result {
-                return Ok(OperationResult::Veto(msg));
-            }
-
-            // Both operands must have values at this point
-            let left_val = left_result.expect_value("arithmetic left operand")?;
-            let right_val = right_result.expect_value("arithmetic right operand")?;
-
-            // Convert Engine errors to Runtime errors with source location
-            let result = super::operations::arithmetic_operation(left_val, op, right_val)
-                .map_err(|e| convert_engine_error_to_runtime(e, expr, context))?;
-
-            // Record operation
-            let op_name = match op {
-                ArithmeticOperation::Add => "add",
-                ArithmeticOperation::Subtract => "subtract",
-                ArithmeticOperation::Multiply => "multiply",
-                ArithmeticOperation::Divide => "divide",
-                ArithmeticOperation::Modulo => "modulo",
-                ArithmeticOperation::Power => "power",
-            };
-
-            context.operations.push(OperationRecord::OperationExecuted {
-                operation: op_name.to_string(),
-                inputs: vec![left_val.clone(), right_val.clone()],
-                result: result.clone(),
-                unless_clause_index: None,
-            });
-
-            Ok(OperationResult::Value(result))
+            evaluate_arithmetic(expr, left_result, op, right_result, context, fact_prefix)
         }
 
         ExpressionKind::Comparison(left, op, right) => {
@@ -147,7 +112,14 @@ pub fn evaluate_expression(
             let left_val = left_result.expect_value("comparison left operand")?;
             let right_val = right_result.expect_value("comparison right operand")?;
 
-            let result = super::operations::comparison_operation(left_val, op, right_val)?;
+            check_comparison_units(left_val, op, right_val, context);
+            let text_comparison = context.rule_doc()?.text_comparison;
+            let result = super::operations::comparison_operation_with_text(
+                left_val,
+                op,
+                right_val,
+                text_comparison,
+            )?;
 
             // Record operation
             let op_name = match op {
@@ -161,69 +133,25 @@ pub fn evaluate_expression(
                 crate::ComparisonOperator::IsNot => "is_not",
             };
 
-            context.operations.push(OperationRecord::OperationExecuted {
+            let sensitive = [
+                is_sensitive(left, context, fact_prefix),
+                is_sensitive(right, context, fact_prefix),
+            ];
+            context.push_operation(|| OperationRecord::OperationExecuted {
                 operation: op_name.to_string(),
-                inputs: vec![left_val.clone(), right_val.clone()],
-                result: LiteralValue::Boolean(result),
+                inputs: traced_inputs(&[left_val, right_val], &sensitive),
+                result: traced(&LiteralValue::Boolean(result), sensitive.contains(&true)),
                 unless_clause_index: None,
             });
 
             Ok(OperationResult::Value(LiteralValue::Boolean(result)))
         }
 
-        ExpressionKind::LogicalAnd(left, right) => {
-            let left_result = evaluate_expression(left, context, fact_prefix)?;
-            let right_result = evaluate_expression(right, context, fact_prefix)?;
-
-            // If either operand is vetoed, propagate the veto
-            if let OperationResult::Veto(msg) = left_result {
-                return Ok(OperationResult::Veto(msg));
-            }
-            if let OperationResult::Veto(msg) = right_result {
-                return Ok(OperationResult::Veto(msg));
-            }
+        ExpressionKind::Range(range) => evaluate_range(range, context, fact_prefix),
 
-            // Both operands must have boolean values at this point
-            let left_val = left_result.expect_value("logical AND left operand")?;
-            let right_val = right_result.expect_value("logical AND right operand")?;
+        ExpressionKind::LogicalAnd(..) => evaluate_logical_chain(expr, true, context, fact_prefix),
 
-            match (left_val, right_val) {
-                (LiteralValue::Boolean(l), LiteralValue::Boolean(r)) => {
-                    // No operation record for logical operations - only record sub-expressions
-                    Ok(OperationResult::Value(LiteralValue::Boolean(*l && *r)))
-                }
-                _ => Err(LemmaError::Engine(
-                    "Logical AND requires boolean operands".to_string(),
-                )),
-            }
-        }
-
-        ExpressionKind::LogicalOr(left, right) => {
-            let left_result = evaluate_expression(left, context, fact_prefix)?;
-            let right_result = evaluate_expression(right, context, fact_prefix)?;
-
-            // If either operand is vetoed, propagate the veto
-            if let OperationResult::Veto(msg) = left_result {
-                return Ok(OperationResult::Veto(msg));
-            }
-            if let OperationResult::Veto(msg) = right_result {
-                return Ok(OperationResult::Veto(msg));
-            }
-
-            // Both operands must have boolean values at this point
-            let left_val = left_result.expect_value("logical OR left operand")?;
-            let right_val = right_result.expect_value("logical OR right operand")?;
-
-            match (left_val, right_val) {
-                (LiteralValue::Boolean(l), LiteralValue::Boolean(r)) => {
-                    // No operation record for logical operations - only record sub-expressions
-                    Ok(OperationResult::Value(LiteralValue::Boolean(*l || *r)))
-                }
-                _ => Err(LemmaError::Engine(
-                    "Logical OR requires boolean operands".to_string(),
-                )),
-            }
-        }
+        ExpressionKind::LogicalOr(..) => evaluate_logical_chain(expr, false, context, fact_prefix),
 
         ExpressionKind::LogicalNegation(operand, _negation_type) => {
             let result = evaluate_expression(operand, context, fact_prefix)?;
@@ -245,6 +173,55 @@ pub fn evaluate_expression(
         }
 
         ExpressionKind::UnitConversion(value_expr, target) => {
+            // `(end - start) in years` counts calendar years between dates
+            if let (
+                ExpressionKind::Arithmetic(left, ArithmeticOperation::Subtract, right),
+                ConversionTarget::Duration(unit @ (DurationUnit::Month | DurationUnit::Year)),
+            ) = (&value_expr.kind, target)
+            {
+                let left_result = evaluate_expression(left, context, fact_prefix)?;
+                let right_result = evaluate_expression(right, context, fact_prefix)?;
+                if let (
+                    OperationResult::Value(LiteralValue::Date(end)),
+                    OperationResult::Value(LiteralValue::Date(start)),
+                ) = (&left_result, &right_result)
+                {
+                    let whole = super::datetime::calendar_difference(end, start, unit)
+                        .map_err(|e| convert_engine_error_to_runtime(e, value_expr, context))?;
+                    let sensitive = [
+                        is_sensitive(left, context, fact_prefix),
+                        is_sensitive(right, context, fact_prefix),
+                    ];
+                    context.push_operation(|| OperationRecord::OperationExecuted {
+                        operation: format!("{}s between", unit),
+                        inputs: traced_inputs(
+                            &[
+                                &LiteralValue::Date(end.clone()),
+                                &LiteralValue::Date(start.clone()),
+                            ],
+                            &sensitive,
+                        ),
+                        result: traced(&LiteralValue::Number(whole), sensitive.contains(&true)),
+                        unless_clause_index: None,
+                    });
+                    return Ok(OperationResult::Value(LiteralValue::Number(whole)));
+                }
+                let result = evaluate_arithmetic(
+                    value_expr,
+                    left_result,
+                    &ArithmeticOperation::Subtract,
+                    right_result,
+                    context,
+                    fact_prefix,
+                )?;
+                return match result {
+                    OperationResult::Value(value) => Ok(OperationResult::Value(
+                        super::units::convert_unit(&value, target)?,
+                    )),
+                    veto => Ok(veto),
+                };
+            }
+
             let result = evaluate_expression(value_expr, context, fact_prefix)?;
 
             // If the value is vetoed, propagate the veto
@@ -262,23 +239,633 @@ pub fn evaluate_expression(
             evaluate_mathematical_operator(op, operand, context, fact_prefix)
         }
 
-        ExpressionKind::Veto(veto_expr) => Ok(OperationResult::Veto(veto_expr.message.clone())),
+        ExpressionKind::FunctionCall(name, arguments) => {
+            let function = context
+                .functions
+                .get(name)
+                .ok_or_else(|| LemmaError::Engine(format!("Unknown function '{}'", name)))?;
+
+            let mut values = Vec::with_capacity(arguments.len());
+            for argument in arguments {
+                match evaluate_expression(argument, context, fact_prefix)? {
+                    OperationResult::Value(value) => values.push(value),
+                    veto => return Ok(veto),
+                }
+            }
+
+            let result = function.call(&values)?;
+            let sensitive: Vec<bool> = arguments
+                .iter()
+                .map(|argument| is_sensitive(argument, context, fact_prefix))
+                .collect();
+            context.push_operation(|| OperationRecord::OperationExecuted {
+                operation: name.clone(),
+                inputs: traced_inputs(&values.iter().collect::<Vec<_>>(), &sensitive),
+                result: traced(&result, sensitive.contains(&true)),
+                unless_clause_index: None,
+            });
+            Ok(OperationResult::Value(result))
+        }
+
+        ExpressionKind::Veto(veto_expr) => {
+            let message = veto_expr.message_for(&context.options.locales);
+            let mut values = std::collections::HashMap::new();
+            for argument in &veto_expr.arguments {
+                match evaluate_expression(&argument.expression, context, fact_prefix)? {
+                    OperationResult::Value(value) => {
+                        values.insert(argument.placeholder.as_str(), value.to_string());
+                    }
+                    // A message can't be built from a vetoed rule, that veto wins
+                    veto => return Ok(veto),
+                }
+            }
+            Ok(OperationResult::Veto(message.map(|message| {
+                crate::render_message(&message, |name| values.get(name).cloned())
+            })))
+        }
 
         ExpressionKind::FactHasAnyValue(fact_ref) => {
             // Check if fact exists and has a value, with path prefix applied
-            let lookup_ref = if !fact_prefix.is_empty() {
-                let mut qualified_reference = fact_prefix.to_vec();
-                qualified_reference.extend_from_slice(&fact_ref.reference);
-                FactReference {
-                    reference: qualified_reference,
+            let lookup_ref = qualify_fact_reference(fact_ref, fact_prefix);
+            let has_value = context.facts.contains_key(&lookup_ref);
+            Ok(OperationResult::Value(LiteralValue::Boolean(has_value)))
+        }
+
+        ExpressionKind::MissingItems(items) => {
+            let mut missing = Vec::new();
+            for item in items {
+                let result = evaluate_expression(item, context, fact_prefix)?;
+
+                // If an item is vetoed, propagate the veto
+                if let OperationResult::Veto(msg) = result {
+                    return Ok(OperationResult::Veto(msg));
+                }
+
+                match result.expect_value("checklist item")? {
+                    LiteralValue::Boolean(true) => {}
+                    LiteralValue::Boolean(false) => missing.push(crate::checklist_item_name(item)),
+                    _ => {
+                        return Err(LemmaError::Engine(
+                            "Checklist items must be boolean".to_string(),
+                        ))
+                    }
+                }
+            }
+            Ok(OperationResult::Value(LiteralValue::Text(
+                missing.join(", "),
+            )))
+        }
+
+        ExpressionKind::RuleHasValue(rule_ref) => {
+            // Check whether the rule produced a value, catching its veto instead of propagating it
+            let rule_path = crate::RulePath::from_reference(
+                &rule_ref.reference,
+                context.rule_doc()?,
+                context.all_documents,
+            )?
+            .with_prefix(&context.rule_segments);
+
+            let result = context.rule_result(&rule_path)?;
+            Ok(OperationResult::Value(LiteralValue::Boolean(
+                !result.is_vetoed(),
+            )))
+        }
+    }
+}
+
+/// The path of a fact reference made in a rule evaluated under `fact_prefix`
+///
+/// E.g., if `employee` references `doc hr_doc` and we're evaluating `employee.salary?`,
+/// fact references within that rule need the `employee` prefix. `caller.x` drops the
+/// last segment instead, naming `x` of the document that holds the reference; at the
+/// top level it stays `caller.x`, which is then a missing fact.
+pub(crate) fn qualify_fact_reference(
+    fact_ref: &FactReference,
+    fact_prefix: &[String],
+) -> FactReference {
+    let (prefix, path) = match (fact_ref.caller_fact(), fact_prefix.split_last()) {
+        (Some(path), Some((_, parent))) => (parent, path),
+        _ => (fact_prefix, fact_ref.reference.as_slice()),
+    };
+    let mut reference = prefix.to_vec();
+    reference.extend_from_slice(path);
+    FactReference { reference }
+}
+
+/// Whether the value of `expr` derives from a sensitive fact, directly or through a rule
+///
+/// Such values are masked in the trace, see `LemmaFact::sensitive`. Whether a
+/// fact or rule has a value at all isn't masked.
+pub fn is_sensitive(
+    expr: &Expression,
+    context: &EvaluationContext,
+    fact_prefix: &[String],
+) -> bool {
+    if !context.masks_sensitive {
+        return false;
+    }
+    match &expr.kind {
+        ExpressionKind::FactReference(fact_ref) => {
+            context.is_sensitive_fact(&qualify_fact_reference(fact_ref, fact_prefix))
+        }
+        ExpressionKind::RuleReference(rule_ref) => context.rule_doc().is_ok_and(|doc| {
+            crate::RulePath::from_reference(&rule_ref.reference, doc, context.all_documents)
+                .is_ok_and(|path| {
+                    let path = path.with_prefix(&context.rule_segments);
+                    context.sensitive_rules.contains(&path)
+                })
+        }),
+        ExpressionKind::Aggregate(aggregate) => context.rule_doc().is_ok_and(|doc| {
+            crate::analysis::aggregate_rule_paths(
+                aggregate,
+                doc,
+                &context.rule_segments,
+                context.all_documents,
+                &context.collection_instances,
+            )
+            .is_ok_and(|paths| aggregate_sensitivity(aggregate, &paths, context).contains(&true))
+        }),
+        _ => crate::rename::children(expr)
+            .into_iter()
+            .any(|child| is_sensitive(child, context, fact_prefix)),
+    }
+}
+
+/// `value` as the trace shows it: masked when it derives from a sensitive fact
+pub fn traced(value: &LiteralValue, sensitive: bool) -> LiteralValue {
+    if sensitive {
+        LiteralValue::redacted()
+    } else {
+        value.clone()
+    }
+}
+
+fn traced_inputs(values: &[&LiteralValue], sensitive: &[bool]) -> Vec<LiteralValue> {
+    values
+        .iter()
+        .zip(sensitive)
+        .map(|(value, sensitive)| traced(value, *sensitive))
+        .collect()
+}
+
+/// For each value an aggregate reads, in the order it reads them, whether it is sensitive
+fn aggregate_sensitivity(
+    aggregate: &AggregateExpression,
+    paths: &[crate::RulePath],
+    context: &EvaluationContext,
+) -> Vec<bool> {
+    let mut sensitive = Vec::new();
+    if aggregate.rule.is_some() {
+        sensitive.extend(
+            paths
+                .iter()
+                .map(|path| context.sensitive_rules.contains(path)),
+        );
+    }
+    if let Some(fact) = &aggregate.fact {
+        sensitive.extend(
+            paths
+                .iter()
+                .map(|path| context.is_sensitive_fact(&aggregate_fact_reference(path, fact))),
+        );
+    }
+    sensitive
+}
+
+/// The fact `fact` of the collection instance at `path`
+fn aggregate_fact_reference(path: &crate::RulePath, fact: &str) -> FactReference {
+    let mut reference: Vec<String> = path.segments.iter().map(|s| s.fact.clone()).collect();
+    reference.push(fact.to_string());
+    FactReference { reference }
+}
+
+/// Apply an arithmetic operation to evaluated operands
+fn evaluate_arithmetic(
+    expr: &Expression,
+    left_result: OperationResult,
+    op: &ArithmeticOperation,
+    right_result: OperationResult,
+    context: &mut EvaluationContext,
+    fact_prefix: &[String],
+) -> Result<OperationResult, LemmaError> {
+    // If either operand is vetoed, propagate the veto
+    if let OperationResult::Veto(msg) = left_result {
+        return Ok(OperationResult::Veto(msg));
+    }
+    if let OperationResult::Veto(msg) = right_result {
+        return Ok(OperationResult::Veto(msg));
+    }
+
+    // Both operands must have values at this point
+    let left_val = left_result.expect_value("arithmetic left operand")?;
+    let right_val = right_result.expect_value("arithmetic right operand")?;
+
+    if matches!(
+        op,
+        ArithmeticOperation::Divide | ArithmeticOperation::Modulo
+    ) && is_zero(right_val)
+    {
+        match &context.rule_doc()?.division_by_zero {
+            DivisionByZero::Error => {}
+            DivisionByZero::Veto(message) => return Ok(OperationResult::Veto(message.clone())),
+            DivisionByZero::Unknown => {
+                return Err(LemmaError::UnknownResult("division by zero".to_string()))
+            }
+        }
+    }
+
+    let sensitive = match &expr.kind {
+        ExpressionKind::Arithmetic(left, _, right) => [
+            is_sensitive(left, context, fact_prefix),
+            is_sensitive(right, context, fact_prefix),
+        ],
+        _ => [false, false],
+    };
+    check_arithmetic_units(left_val, op, right_val, context);
+    record_percentage_change(left_val, op, right_val, &sensitive, context);
+
+    // Convert Engine errors to Runtime errors with source location
+    let result = super::operations::arithmetic_operation_with_policy(
+        left_val,
+        op,
+        right_val,
+        &context.limits.numeric,
+    )
+    .map_err(|e| convert_engine_error_to_runtime(e, expr, context))?;
+
+    // Record operation
+    let op_name = match op {
+        ArithmeticOperation::Add => "add",
+        ArithmeticOperation::Subtract => "subtract",
+        ArithmeticOperation::Multiply => "multiply",
+        ArithmeticOperation::Divide => "divide",
+        ArithmeticOperation::Modulo => "modulo",
+        ArithmeticOperation::Power => "power",
+    };
+
+    context.push_operation(|| OperationRecord::OperationExecuted {
+        operation: op_name.to_string(),
+        inputs: traced_inputs(&[left_val, right_val], &sensitive),
+        result: traced(&result, sensitive.contains(&true)),
+        unless_clause_index: None,
+    });
+
+    Ok(OperationResult::Value(result))
+}
+
+/// Whether a divisor is zero, whatever its unit
+fn is_zero(value: &LiteralValue) -> bool {
+    match value {
+        LiteralValue::Number(n)
+        | LiteralValue::Percentage(n)
+        | LiteralValue::PercentagePoints(n) => n.is_zero(),
+        LiteralValue::Unit(unit) => unit.value().is_zero(),
+        _ => false,
+    }
+}
+
+/// Evaluate a chain of `and` (or `or`) operands from left to right
+///
+/// `a and b and c` parses as nested binary expressions; the chain is
+/// flattened so long conditions don't recurse once per operand. Evaluation
+/// stops at the first operand that decides the result (`false` for `and`,
+/// `true` for `or`): the remaining operands are not evaluated, so a missing
+/// fact or veto in them doesn't affect the result. Skipping is recorded as
+/// a `ShortCircuit` operation.
+fn evaluate_logical_chain(
+    expr: &Expression,
+    is_and: bool,
+    context: &mut EvaluationContext,
+    fact_prefix: &[String],
+) -> Result<OperationResult, LemmaError> {
+    let operands = logical_operands(expr, is_and);
+    let (operator, decisive) = if is_and { ("and", false) } else { ("or", true) };
+
+    for (index, operand) in operands.iter().enumerate() {
+        let result = evaluate_expression(operand, context, fact_prefix)?;
+        if let OperationResult::Veto(msg) = result {
+            return Ok(OperationResult::Veto(msg));
+        }
+        let value = match result.expect_value("logical operand")? {
+            LiteralValue::Boolean(b) => *b,
+            _ => {
+                return Err(LemmaError::Engine(format!(
+                    "Logical {} requires boolean operands",
+                    operator.to_uppercase()
+                )))
+            }
+        };
+
+        if value == decisive {
+            let skipped = operands.len() - index - 1;
+            if skipped > 0 {
+                let sensitive = is_sensitive(operand, context, fact_prefix);
+                context.push_operation(|| OperationRecord::ShortCircuit {
+                    operation: operator.to_string(),
+                    value: traced(&LiteralValue::Boolean(value), sensitive),
+                    skipped_operands: skipped,
+                });
+            }
+            return Ok(OperationResult::Value(LiteralValue::Boolean(value)));
+        }
+    }
+
+    // No operation record for logical operations - only record sub-expressions
+    Ok(OperationResult::Value(LiteralValue::Boolean(!decisive)))
+}
+
+/// Evaluate `value between low and high`, recorded as a single operation
+///
+/// The value and both bounds are evaluated, so a veto in any of them vetoes
+/// the range.
+fn evaluate_range(
+    range: &RangeCondition,
+    context: &mut EvaluationContext,
+    fact_prefix: &[String],
+) -> Result<OperationResult, LemmaError> {
+    let mut values = Vec::with_capacity(3);
+    for operand in [&range.value, &range.low, &range.high] {
+        match evaluate_expression(operand, context, fact_prefix)? {
+            OperationResult::Value(value) => values.push(value),
+            veto => return Ok(veto),
+        }
+    }
+    let (value, low, high) = (&values[0], &values[1], &values[2]);
+
+    check_comparison_units(value, &range.low_operator, low, context);
+    check_comparison_units(value, &range.high_operator, high, context);
+    let text_comparison = context.rule_doc()?.text_comparison;
+    let result = super::operations::comparison_operation_with_text(
+        value,
+        &range.low_operator,
+        low,
+        text_comparison,
+    )? && super::operations::comparison_operation_with_text(
+        value,
+        &range.high_operator,
+        high,
+        text_comparison,
+    )?;
+
+    let sensitive: Vec<bool> = [&range.value, &range.low, &range.high]
+        .into_iter()
+        .map(|operand| is_sensitive(operand, context, fact_prefix))
+        .collect();
+    context.push_operation(|| OperationRecord::OperationExecuted {
+        operation: range.operation().to_string(),
+        inputs: traced_inputs(&values.iter().collect::<Vec<_>>(), &sensitive),
+        result: traced(&LiteralValue::Boolean(result), sensitive.contains(&true)),
+        unless_clause_index: None,
+    });
+    Ok(OperationResult::Value(LiteralValue::Boolean(result)))
+}
+
+/// Operands of a chain of the same logical operator, in source order
+fn logical_operands(expr: &Expression, is_and: bool) -> Vec<&Expression> {
+    let mut operands = Vec::new();
+    let mut pending = vec![expr];
+    while let Some(current) = pending.pop() {
+        match (&current.kind, is_and) {
+            (ExpressionKind::LogicalAnd(left, right), true)
+            | (ExpressionKind::LogicalOr(left, right), false) => {
+                // Right first, so the left operand is popped (and evaluated) first
+                pending.push(right);
+                pending.push(left);
+            }
+            _ => operands.push(current),
+        }
+    }
+    operands
+}
+
+/// Evaluate `sum`, `count`, `min` or `max` over the instances of a collection
+///
+/// A vetoed instance vetoes the aggregate. The sum of an empty collection is 0,
+/// min and max of an empty collection veto.
+fn evaluate_aggregate(
+    aggregate: &AggregateExpression,
+    context: &mut EvaluationContext,
+) -> Result<OperationResult, LemmaError> {
+    let paths = crate::analysis::aggregate_rule_paths(
+        aggregate,
+        context.rule_doc()?,
+        &context.rule_segments,
+        context.all_documents,
+        &context.collection_instances,
+    )?;
+
+    let sensitive = aggregate_sensitivity(aggregate, &paths, context);
+    let mut values = Vec::with_capacity(paths.len());
+    if aggregate.rule.is_some() {
+        for path in &paths {
+            match context.rule_result(path)? {
+                OperationResult::Veto(msg) => return Ok(OperationResult::Veto(msg.clone())),
+                OperationResult::Value(value) => values.push(value.clone()),
+            }
+        }
+    }
+    if let Some(fact) = &aggregate.fact {
+        for path in &paths {
+            let fact_ref = aggregate_fact_reference(path, fact);
+            let value = context.facts.get(&fact_ref).cloned().ok_or_else(|| {
+                LemmaError::Engine(format!("Missing fact: {}", fact_ref.reference.join(".")))
+            })?;
+            let fact_sensitive = sensitive[values.len()];
+            context.push_operation(|| OperationRecord::FactUsed {
+                name: fact_ref.reference.join("."),
+                value: traced(&value, fact_sensitive),
+            });
+            values.push(value);
+        }
+    }
+
+    let result = match aggregate.function {
+        AggregateFunction::Count => LiteralValue::Number(Decimal::from(paths.len())),
+        AggregateFunction::Total => {
+            match total_money(aggregate, &values, &paths, &sensitive, context)? {
+                Some(total) => total,
+                None => {
+                    return Ok(OperationResult::Veto(Some(format!(
+                        "{} has no instances to total",
+                        aggregate.collection.join(".")
+                    ))))
                 }
+            }
+        }
+        AggregateFunction::Sum => {
+            let mut total: Option<LiteralValue> = None;
+            for value in &values {
+                total = Some(match total {
+                    Some(sum) => super::operations::arithmetic_operation_with_policy(
+                        &sum,
+                        &ArithmeticOperation::Add,
+                        value,
+                        &context.limits.numeric,
+                    )?,
+                    None => value.clone(),
+                });
+            }
+            total.unwrap_or(LiteralValue::Number(Decimal::ZERO))
+        }
+        AggregateFunction::Min | AggregateFunction::Max => {
+            let op = if aggregate.function == AggregateFunction::Min {
+                ComparisonOperator::LessThan
             } else {
-                fact_ref.clone()
+                ComparisonOperator::GreaterThan
             };
-            let has_value = context.facts.contains_key(&lookup_ref);
-            Ok(OperationResult::Value(LiteralValue::Boolean(has_value)))
+            let mut best: Option<&LiteralValue> = None;
+            for value in &values {
+                best = match best {
+                    Some(current)
+                        if !super::operations::comparison_operation(value, &op, current)? =>
+                    {
+                        Some(current)
+                    }
+                    _ => Some(value),
+                };
+            }
+            match best {
+                Some(value) => value.clone(),
+                None => {
+                    return Ok(OperationResult::Veto(Some(format!(
+                        "{} has no instances",
+                        aggregate.collection.join(".")
+                    ))))
+                }
+            }
+        }
+    };
+
+    let counted = aggregate.function == AggregateFunction::Count;
+    context.push_operation(|| OperationRecord::OperationExecuted {
+        operation: aggregate.function.to_string(),
+        inputs: traced_inputs(&values.iter().collect::<Vec<_>>(), &sensitive),
+        result: traced(&result, !counted && sensitive.contains(&true)),
+        unless_clause_index: None,
+    });
+
+    Ok(OperationResult::Value(result))
+}
+
+/// Pick the winning candidate of `lowest of a?, b?` and the like
+///
+/// Every candidate is evaluated, from left to right, and a vetoed candidate
+/// vetoes the selection. Ties go to the first candidate. The trace records
+/// all candidate values and which candidate won.
+fn evaluate_selection(
+    function: SelectionFunction,
+    candidates: &[Expression],
+    context: &mut EvaluationContext,
+    fact_prefix: &[String],
+) -> Result<OperationResult, LemmaError> {
+    let op = match function {
+        SelectionFunction::Lowest | SelectionFunction::Earliest => ComparisonOperator::LessThan,
+        SelectionFunction::Highest | SelectionFunction::Latest => ComparisonOperator::GreaterThan,
+    };
+
+    let mut values = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let value = match evaluate_expression(candidate, context, fact_prefix)? {
+            OperationResult::Veto(msg) => return Ok(OperationResult::Veto(msg)),
+            OperationResult::Value(value) => value,
+        };
+        let is_date = matches!(value, LiteralValue::Date(_) | LiteralValue::Time(_));
+        if is_date != function.selects_dates() {
+            let expected = if function.selects_dates() {
+                "dates and times"
+            } else {
+                "numbers, money and units (use 'earliest' or 'latest' for dates)"
+            };
+            return Err(LemmaError::Engine(format!(
+                "'{} of' compares {}, but {} is {}",
+                function, expected, candidate, value
+            )));
         }
+        values.push(value);
     }
+
+    let mut winner = 0;
+    for (index, value) in values.iter().enumerate().skip(1) {
+        if super::operations::comparison_operation(value, &op, &values[winner])? {
+            winner = index;
+        }
+    }
+
+    let value = values[winner].clone();
+    let sensitive: Vec<bool> = candidates
+        .iter()
+        .map(|candidate| is_sensitive(candidate, context, fact_prefix))
+        .collect();
+    context.push_operation(|| OperationRecord::CandidateSelected {
+        operation: function.to_string(),
+        inputs: traced_inputs(&values.iter().collect::<Vec<_>>(), &sensitive),
+        winner: candidates[winner].to_string(),
+        index: winner,
+        value: traced(&value, sensitive.contains(&true)),
+    });
+    Ok(OperationResult::Value(value))
+}
+
+/// Add up the money values of `total of`, one trace entry per instance
+///
+/// All values must be money in the same currency. An empty collection totals
+/// zero in the currency of the rule's document, and has no total when the
+/// document doesn't declare one.
+fn total_money(
+    aggregate: &AggregateExpression,
+    values: &[LiteralValue],
+    paths: &[crate::RulePath],
+    sensitive: &[bool],
+    context: &mut EvaluationContext,
+) -> Result<Option<LiteralValue>, LemmaError> {
+    let mut total: Option<(Decimal, MoneyUnit)> = None;
+    let mut sum_sensitive = false;
+    for ((value, path), value_sensitive) in values.iter().zip(paths).zip(sensitive) {
+        let instance = path.segments.last().map(|s| s.fact.as_str()).unwrap_or("");
+        let LiteralValue::Unit(NumericUnit::Money(amount, currency)) = value else {
+            return Err(LemmaError::Engine(format!(
+                "'{}' adds money, but {} is {}",
+                aggregate, instance, value
+            )));
+        };
+        let sum = match &total {
+            None => Decimal::ZERO,
+            Some((sum, total_currency)) if total_currency == currency => *sum,
+            Some((_, total_currency)) => {
+                return Err(LemmaError::Engine(format!(
+                    "'{}' cannot add {} of {} to a total in {}",
+                    aggregate, value, instance, total_currency
+                )))
+            }
+        };
+
+        let inputs = [sum_sensitive, *value_sensitive];
+        sum_sensitive |= *value_sensitive;
+        context.push_operation(|| OperationRecord::OperationExecuted {
+            operation: format!("add {}", instance),
+            inputs: traced_inputs(
+                &[
+                    &LiteralValue::Unit(NumericUnit::Money(sum, currency.clone())),
+                    value,
+                ],
+                &inputs,
+            ),
+            result: traced(
+                &LiteralValue::Unit(NumericUnit::Money(sum + amount, currency.clone())),
+                sum_sensitive,
+            ),
+            unless_clause_index: None,
+        });
+        total = Some((sum + amount, currency.clone()));
+    }
+
+    let currency = match total {
+        Some((sum, currency)) => {
+            return Ok(Some(LiteralValue::Unit(NumericUnit::Money(sum, currency))))
+        }
+        None => context.rule_doc()?.currency.clone(),
+    };
+    Ok(currency.map(|currency| LiteralValue::Unit(NumericUnit::Money(Decimal::ZERO, currency))))
 }
 
 /// Evaluate a mathematical operator (sqrt, sin, cos, etc.)
@@ -389,7 +976,7 @@ fn convert_engine_error_to_runtime(
 
             let suggestion = if msg.contains("division") || msg.contains("zero") {
                 Some(
-                    "Consider using an 'unless' clause to guard against division by zero"
+                    "Guard the division with an 'unless' clause, or choose what rules give with 'on division by zero veto' or 'on division by zero unknown'"
                         .to_string(),
                 )
             } else if msg.contains("type") || msg.contains("mismatch") {
@@ -411,3 +998,147 @@ fn convert_engine_error_to_runtime(
         other => other,
     }
 }
+
+/// Warn about `accept` and `reject`, deprecated spellings of `true` and `false`
+fn check_boolean_alias(expr: &Expression, context: &mut EvaluationContext) {
+    let (Some(span), Ok(doc)) = (&expr.span, context.rule_doc()) else {
+        return;
+    };
+    let sources = context.sources;
+    let written = doc
+        .source
+        .as_ref()
+        .and_then(|source| sources.get(source))
+        .and_then(|text| text.get(span.start..span.end))
+        .map(str::trim);
+    let replacement = match written {
+        Some("accept") => "true",
+        Some("reject") => "false",
+        _ => return,
+    };
+    context.warn(
+        DiagnosticKind::DeprecatedAlias,
+        format!(
+            "'{}' is deprecated, use '{}'",
+            written.unwrap_or_default(),
+            replacement
+        ),
+    );
+}
+
+/// Warn when arithmetic drops units or reads a plain number in a unit
+fn check_arithmetic_units(
+    left: &LiteralValue,
+    op: &ArithmeticOperation,
+    right: &LiteralValue,
+    context: &mut EvaluationContext,
+) {
+    match (left, right) {
+        (LiteralValue::Unit(l), LiteralValue::Unit(r)) if !l.same_category(r) => context.warn(
+            DiagnosticKind::UnitDegraded,
+            format!(
+                "{} {} {} combines different kinds of units, the result is a plain number",
+                left, op, right
+            ),
+        ),
+        (LiteralValue::Unit(unit), LiteralValue::Number(n))
+        | (LiteralValue::Number(n), LiteralValue::Unit(unit))
+            if matches!(op, ArithmeticOperation::Add | ArithmeticOperation::Subtract)
+                && !in_document_currency(unit, context) =>
+        {
+            context.warn(
+                DiagnosticKind::ImplicitCoercion,
+                format!(
+                    "{} {} {} reads {} as {}",
+                    left,
+                    op,
+                    right,
+                    n,
+                    unit.conversion_target()
+                ),
+            )
+        }
+        _ => {}
+    }
+}
+
+/// Whether `unit` is money in the currency of the rule's document
+///
+/// A document with a `currency` directive reads plain numbers next to its
+/// money in that currency on purpose, so there is nothing to warn about.
+fn in_document_currency(unit: &NumericUnit, context: &EvaluationContext) -> bool {
+    let NumericUnit::Money(_, currency) = unit else {
+        return false;
+    };
+    context
+        .rule_doc()
+        .is_ok_and(|doc| doc.currency.as_ref() == Some(currency))
+}
+
+/// Record the percentage points a relative percentage change comes down to
+///
+/// `5% + 2%` changes 5% by 2% of itself: the trace shows the 0.1 percentage
+/// points that are added, and a diagnostic points at `percentage points`.
+fn record_percentage_change(
+    left: &LiteralValue,
+    op: &ArithmeticOperation,
+    right: &LiteralValue,
+    sensitive: &[bool; 2],
+    context: &mut EvaluationContext,
+) {
+    let (LiteralValue::Percentage(l), LiteralValue::Percentage(r)) = (left, right) else {
+        return;
+    };
+    if !matches!(op, ArithmeticOperation::Add | ArithmeticOperation::Subtract) {
+        return;
+    }
+    let points = LiteralValue::PercentagePoints(super::operations::relative_change(*l, *r));
+    context.warn(
+        DiagnosticKind::AmbiguousPercentage,
+        format!(
+            "{} {} {} changes {} by {} of itself ({}), write {} percentage points for an absolute change",
+            left, op, right, left, right, points, r
+        ),
+    );
+    context.push_operation(|| OperationRecord::OperationExecuted {
+        operation: "to_percentage_points".to_string(),
+        inputs: traced_inputs(&[left, right], sensitive),
+        result: traced(&points, sensitive.contains(&true)),
+        unless_clause_index: None,
+    });
+}
+
+/// Warn when a comparison ignores units
+fn check_comparison_units(
+    left: &LiteralValue,
+    op: &ComparisonOperator,
+    right: &LiteralValue,
+    context: &mut EvaluationContext,
+) {
+    match (left, right) {
+        (LiteralValue::Unit(l), LiteralValue::Unit(r)) if !l.same_category(r) => context.warn(
+            DiagnosticKind::UnitDegraded,
+            format!(
+                "{} {} {} compares different kinds of units as plain numbers",
+                left, op, right
+            ),
+        ),
+        (LiteralValue::Unit(unit), LiteralValue::Number(n))
+        | (LiteralValue::Number(n), LiteralValue::Unit(unit))
+            if !in_document_currency(unit, context) =>
+        {
+            context.warn(
+                DiagnosticKind::ImplicitCoercion,
+                format!(
+                    "{} {} {} reads {} as {}",
+                    left,
+                    op,
+                    right,
+                    n,
+                    unit.conversion_target()
+                ),
+            )
+        }
+        _ => {}
+    }
+}
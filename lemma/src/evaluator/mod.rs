@@ -16,9 +16,14 @@ pub mod rules;
 pub mod timeout;
 pub mod units;
 
-use crate::{LemmaDoc, LemmaError, LemmaFact, LemmaResult, ResourceLimits, Response, RuleResult};
-use context::{build_fact_map, EvaluationContext};
-use std::collections::HashMap;
+use crate::functions::FunctionRegistry;
+use crate::{
+    Diagnostic, DiagnosticKind, EvaluationOptions, ExpressionKind, FactParseError, FactValue,
+    InputValidation, LemmaDoc, LemmaError, LemmaFact, LemmaResult, LemmaRule, LimitExceeded,
+    LiteralValue, ResourceLimits, Response, RulePath, RuleResult, TriggeredVeto, UnusedOverrides,
+};
+use context::{build_fact_map, get_fact_path, EvaluationContext};
+use std::collections::{HashMap, HashSet};
 use timeout::TimeoutTracker;
 
 /// Evaluates Lemma rules within their document context
@@ -32,8 +37,10 @@ impl Evaluator {
 
     /// Evaluate a Lemma doc
     ///
-    /// Executes all rules in the doc in topological order,
-    /// applying fact overrides if provided.
+    /// Executes the rules in the doc in topological order,
+    /// applying fact overrides if provided. With `requested_rules`, only
+    /// those rules and the rules they depend on are executed.
+    #[allow(clippy::too_many_arguments)]
     pub fn evaluate_document(
         &self,
         doc_name: &str,
@@ -42,78 +49,113 @@ impl Evaluator {
         fact_overrides: Vec<LemmaFact>,
         requested_rules: Option<Vec<String>>,
         limits: &ResourceLimits,
+        functions: &FunctionRegistry,
+        options: &EvaluationOptions,
     ) -> LemmaResult<Response> {
-        let timeout_tracker = TimeoutTracker::new();
+        let timeout_tracker = TimeoutTracker::new().with_cancellation(options.cancellation.clone());
 
         let doc = documents
             .get(doc_name)
             .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
 
+        // Overrides for facts the document doesn't have are most likely typos
+        let unused = unused_overrides(doc, &fact_overrides, documents);
+        if options.unused_overrides == UnusedOverrides::Strict && !unused.is_empty() {
+            return Err(LemmaError::FactParse(
+                unused
+                    .into_iter()
+                    .map(|unused| FactParseError {
+                        input: unused.input,
+                        fact: Some(unused.path),
+                        position: 0,
+                        expected: unused.suggestion.into_iter().collect(),
+                        declared_type: None,
+                        message: unused.reason,
+                    })
+                    .collect(),
+            ));
+        }
+
+        if options.public_rules_only {
+            let internal = requested_rules.iter().flatten().find(|name| {
+                doc.rules
+                    .iter()
+                    .any(|rule| &rule.name == *name && rule.is_internal())
+            });
+            if let Some(name) = internal {
+                return Err(LemmaError::Engine(format!(
+                    "Rule '{}' in document '{}' is internal",
+                    name, doc_name
+                )));
+            }
+        }
+
         // Phase 1: Build dependency graph and execution plan
-        let graph = crate::analysis::build_dependency_graph(doc, documents)?;
+        let instances = crate::analysis::collection_instances(doc, &fact_overrides, documents);
+        let graph = evaluation_graph(
+            doc,
+            documents,
+            &instances,
+            requested_rules.as_deref(),
+            limits,
+        )?;
         let execution_order = topological_sort(&graph)?;
 
         // Phase 2: Build fact map (resolving document references and validating types)
         let facts = build_fact_map(doc, &doc.facts, &fact_overrides, documents)?;
 
         // Phase 3: Build evaluation context
-        let mut context =
-            EvaluationContext::new(doc, documents, sources, facts, &timeout_tracker, limits);
+        let mut context = EvaluationContext::new(
+            doc,
+            documents,
+            sources,
+            facts,
+            &timeout_tracker,
+            limits,
+            functions,
+            options,
+        );
+        context.collection_instances = instances;
 
         // Phase 4: Execute rules in dependency order
         let mut response = Response::new(doc_name.to_string());
-        let mut failed_rules: std::collections::HashSet<crate::RulePath> =
-            std::collections::HashSet::new();
-
-        for rule_path in execution_order {
+        for unused in unused {
+            response.add_diagnostic(Diagnostic {
+                kind: DiagnosticKind::UnusedOverride,
+                rule: None,
+                message: format!(
+                    "Override for '{}' is not used: {}",
+                    unused.path, unused.reason
+                ),
+                fact: Some(unused.path),
+            });
+        }
+        let mut pending = execution_order.into_iter();
+        while let Some(rule_path) = pending.next() {
             let target_doc_name = rule_path.target_doc(doc_name);
-            let rule_doc = documents.get(target_doc_name).ok_or_else(|| {
-                LemmaError::Engine(format!("Document {} not found", target_doc_name))
-            })?;
-
-            let rule = rule_doc
-                .rules
-                .iter()
-                .find(|r| r.name == rule_path.rule)
-                .ok_or_else(|| {
-                    LemmaError::Engine(format!(
-                        "Rule {} not found in document {}",
-                        rule_path.rule, target_doc_name
-                    ))
-                })?;
-
-            // Check if any dependencies have failed
-            let all_rule_deps = graph.get(&rule_path).cloned().unwrap_or_default();
-
-            let missing_deps: Vec<String> = all_rule_deps
-                .iter()
-                .filter(|dep| failed_rules.contains(dep))
-                .map(|dep| dep.to_string())
-                .collect();
-
-            if !missing_deps.is_empty() {
-                // This rule depends on failed rules - mark it as missing dependencies
-                failed_rules.insert(rule_path.clone());
-                if target_doc_name == doc_name {
-                    response.add_result(RuleResult::missing_facts(rule.name.clone(), missing_deps));
-                }
-                continue;
-            }
+            let (rule, path_prefix) = find_rule(&rule_path, doc_name, documents)?;
 
             // Clear operation records for this rule
             context.operations.clear();
-
-            // Evaluate the rule with path prefix when the rule is from a document referenced by a fact
-            let path_prefix: Vec<String> = if target_doc_name != doc_name {
-                // Rule from referenced document: use the fact path as prefix
-                // E.g., if evaluating `employee.salary?` where `employee = doc hr_doc`,
-                // the prefix is ["employee"] so facts in the rule are looked up as ["employee", "field"]
-                rule_path.segments.iter().map(|s| s.fact.clone()).collect()
-            } else {
-                // Local rule: empty prefix
-                Vec::new()
-            };
+            context.rule_segments = rule_path.segments.clone();
+            timeout_tracker.enter_rule(&rule_path.to_string());
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("rule", rule = %rule_path).entered();
             let eval_result = rules::evaluate_rule(rule, &mut context, &path_prefix);
+            #[cfg(feature = "tracing")]
+            match &eval_result {
+                Ok(crate::OperationResult::Value(value)) => {
+                    tracing::debug!(%value, "rule evaluated")
+                }
+                Ok(crate::OperationResult::Veto(message)) => {
+                    tracing::debug!(veto = ?message, "rule vetoed")
+                }
+                Err(error) => tracing::debug!(%error, "rule failed"),
+            }
+            for mut diagnostic in context.diagnostics.drain(..) {
+                diagnostic.rule = Some(rule_path.to_string());
+                response.add_diagnostic(diagnostic);
+            }
 
             match eval_result {
                 Ok(result) => {
@@ -121,30 +163,70 @@ impl Evaluator {
                     context
                         .rule_results
                         .insert(rule_path.clone(), result.clone());
+                    if rules::is_sensitive_rule(rule, &context, &path_prefix) {
+                        context.sensitive_rules.insert(rule_path.clone());
+                    }
 
                     // Add to response only for main document rules
                     if target_doc_name == doc_name {
-                        match result {
+                        let rule_result = match result {
                             crate::OperationResult::Value(value) => {
-                                response.add_result(RuleResult::success_with_operations(
+                                RuleResult::success_with_operations(
                                     rule.name.clone(),
                                     value.clone(),
                                     HashMap::new(),
-                                    context.operations.clone(),
-                                ));
+                                    std::mem::take(&mut context.operations),
+                                )
                             }
                             crate::OperationResult::Veto(msg) => {
-                                response.add_result(RuleResult::veto(rule.name.clone(), msg));
+                                RuleResult::veto(rule.name.clone(), msg)
                             }
-                        }
+                        };
+                        response.add_result(rule_result.with_description(rule.description.clone()));
                     }
                 }
                 Err(LemmaError::Engine(msg)) if msg.starts_with("Missing fact:") => {
-                    failed_rules.insert(rule_path.clone());
+                    // Rules referencing this one fail too, if they get to the reference
+                    context.failed_rules.insert(rule_path.clone());
                     if target_doc_name == doc_name {
                         let missing = vec![msg.replace("Missing fact: ", "")];
-                        response.add_result(RuleResult::missing_facts(rule.name.clone(), missing));
+                        response.add_result(
+                            RuleResult::missing_facts(rule.name.clone(), missing)
+                                .with_description(rule.description.clone()),
+                        );
+                    }
+                }
+                Err(LemmaError::UnknownResult(reason)) => {
+                    if target_doc_name == doc_name {
+                        response.add_result(
+                            RuleResult::unknown(rule.name.clone(), reason.clone())
+                                .with_description(rule.description.clone()),
+                        );
+                    }
+                    context.unknown_rules.insert(rule_path.clone(), reason);
+                }
+                Err(LemmaError::ResourceLimitExceeded {
+                    limit_name,
+                    suggestion,
+                    ..
+                }) => {
+                    // Keep what was evaluated; this rule and the rest get no value
+                    let in_progress = rule_path.to_string();
+                    for rule_path in std::iter::once(rule_path).chain(pending.by_ref()) {
+                        if rule_path.target_doc(doc_name) != doc_name {
+                            continue;
+                        }
+                        let (rule, _) = find_rule(&rule_path, doc_name, documents)?;
+                        response.add_result(
+                            RuleResult::limit_exceeded(rule.name.clone(), limit_name.clone())
+                                .with_description(rule.description.clone()),
+                        );
                     }
+                    response.limit_exceeded = Some(LimitExceeded {
+                        limit_name,
+                        rule: Some(in_progress),
+                        message: suggestion,
+                    });
                 }
                 Err(e) => {
                     return Err(e);
@@ -152,13 +234,388 @@ impl Evaluator {
             }
         }
 
+        check_invariants(doc, &mut response);
+
         // Filter response to only requested rules if specified
         if let Some(rule_names) = requested_rules {
             response.filter_rules(&rule_names);
         }
+        if options.public_rules_only {
+            response.results.retain(|result| {
+                !doc.rules
+                    .iter()
+                    .any(|rule| rule.name == result.rule_name && rule.is_internal())
+            });
+        }
 
         Ok(response)
     }
+
+    /// Evaluate only the vetoes of a document's rules, see `Engine::validate_inputs`
+    ///
+    /// Rules of the document with a vetoing unless clause are checked with
+    /// `rules::evaluate_vetoes`. Other rules are only evaluated when a condition
+    /// of those rules depends on them.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(doc = doc_name), err(level = "debug"))
+    )]
+    pub fn validate_inputs(
+        &self,
+        doc_name: &str,
+        documents: &HashMap<String, LemmaDoc>,
+        sources: &HashMap<String, String>,
+        fact_overrides: Vec<LemmaFact>,
+        limits: &ResourceLimits,
+        functions: &FunctionRegistry,
+        options: &EvaluationOptions,
+    ) -> LemmaResult<InputValidation> {
+        let timeout_tracker = TimeoutTracker::new().with_cancellation(options.cancellation.clone());
+
+        let doc = documents
+            .get(doc_name)
+            .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+
+        let instances = crate::analysis::collection_instances(doc, &fact_overrides, documents);
+        let graph = crate::analysis::build_dependency_graph_with_instances(
+            doc, documents, &instances, limits,
+        )?;
+        let execution_order = topological_sort(&graph)?;
+
+        // Rules the veto checks need the value of, directly or through other rules
+        let mut needed = HashSet::new();
+        for rule in doc.rules.iter().filter(|rule| rules::has_veto_clause(rule)) {
+            for clause in &rule.unless_clauses {
+                crate::analysis::extract_rule_paths(
+                    &clause.condition,
+                    doc,
+                    &[],
+                    documents,
+                    &instances,
+                    &mut needed,
+                )?;
+                if matches!(clause.result.kind, ExpressionKind::Veto(_)) {
+                    crate::analysis::extract_rule_paths(
+                        &clause.result,
+                        doc,
+                        &[],
+                        documents,
+                        &instances,
+                        &mut needed,
+                    )?;
+                }
+            }
+        }
+        let needed = dependency_closure(&graph, needed);
+
+        let facts = build_fact_map(doc, &doc.facts, &fact_overrides, documents)?;
+        let mut context = EvaluationContext::new(
+            doc,
+            documents,
+            sources,
+            facts,
+            &timeout_tracker,
+            limits,
+            functions,
+            options,
+        );
+        context.collection_instances = instances;
+
+        let mut validation = InputValidation {
+            doc_name: doc_name.to_string(),
+            ..InputValidation::default()
+        };
+        for rule_path in execution_order {
+            let (rule, path_prefix) = find_rule(&rule_path, doc_name, documents)?;
+            let checks_vetoes = rule_path.segments.is_empty() && rules::has_veto_clause(rule);
+            if !needed.contains(&rule_path) && !checks_vetoes {
+                continue;
+            }
+
+            context.operations.clear();
+            context.rule_segments = rule_path.segments.clone();
+            timeout_tracker.enter_rule(&rule_path.to_string());
+            #[cfg(feature = "tracing")]
+            let _span = tracing::debug_span!("rule", rule = %rule_path).entered();
+            let eval_result = if needed.contains(&rule_path) {
+                rules::evaluate_rule(rule, &mut context, &path_prefix).map(Some)
+            } else {
+                rules::evaluate_vetoes(rule, &mut context, &path_prefix)
+            };
+            context.diagnostics.clear();
+
+            match eval_result {
+                Ok(result) => {
+                    if let (true, Some(crate::OperationResult::Veto(message))) =
+                        (checks_vetoes, &result)
+                    {
+                        validation.vetoes.push(TriggeredVeto {
+                            rule: rule.name.clone(),
+                            message: message.clone(),
+                        });
+                    }
+                    if let Some(result) = result {
+                        context.rule_results.insert(rule_path, result);
+                    }
+                }
+                Err(LemmaError::Engine(msg)) if msg.starts_with("Missing fact:") => {
+                    // Rules failing on a failed rule name that rule; its fact is listed already
+                    let fact = msg.replace("Missing fact: ", "");
+                    let failed_rule = context
+                        .failed_rules
+                        .iter()
+                        .any(|failed| failed.to_string() == fact);
+                    if !failed_rule && !validation.missing_facts.contains(&fact) {
+                        validation.missing_facts.push(fact);
+                    }
+                    context.failed_rules.insert(rule_path);
+                }
+                Err(LemmaError::UnknownResult(reason)) => {
+                    context.unknown_rules.insert(rule_path, reason);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(validation)
+    }
+
+    /// The facts that block the requested rules, see `Engine::next_missing_facts`
+    ///
+    /// Rules are evaluated as far as the given facts allow. Each rule that
+    /// fails contributes the first fact its evaluation needed; rules failing
+    /// on another failed rule add nothing, as that rule's fact is listed
+    /// already. Without `requested_rules` all rules of the document count.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(doc = doc_name), err(level = "debug"))
+    )]
+    pub fn next_missing_facts(
+        &self,
+        doc_name: &str,
+        documents: &HashMap<String, LemmaDoc>,
+        sources: &HashMap<String, String>,
+        fact_overrides: Vec<LemmaFact>,
+        requested_rules: Option<Vec<String>>,
+        limits: &ResourceLimits,
+        functions: &FunctionRegistry,
+        options: &EvaluationOptions,
+    ) -> LemmaResult<Vec<String>> {
+        let timeout_tracker = TimeoutTracker::new().with_cancellation(options.cancellation.clone());
+
+        let doc = documents
+            .get(doc_name)
+            .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+
+        let instances = crate::analysis::collection_instances(doc, &fact_overrides, documents);
+        let graph = crate::analysis::build_dependency_graph_for_rules(
+            doc,
+            requested_rules.as_deref(),
+            documents,
+            &instances,
+            limits,
+        )?;
+        let execution_order = topological_sort(&graph)?;
+
+        let facts = build_fact_map(doc, &doc.facts, &fact_overrides, documents)?;
+        let mut context = EvaluationContext::new(
+            doc,
+            documents,
+            sources,
+            facts,
+            &timeout_tracker,
+            limits,
+            functions,
+            options,
+        );
+        context.collection_instances = instances;
+
+        let mut missing = Vec::new();
+        for rule_path in execution_order {
+            let (rule, path_prefix) = find_rule(&rule_path, doc_name, documents)?;
+            context.operations.clear();
+            context.rule_segments = rule_path.segments.clone();
+            timeout_tracker.enter_rule(&rule_path.to_string());
+            let eval_result = rules::evaluate_rule(rule, &mut context, &path_prefix);
+            context.diagnostics.clear();
+
+            match eval_result {
+                Ok(result) => {
+                    context.rule_results.insert(rule_path, result);
+                }
+                Err(LemmaError::Engine(msg)) if msg.starts_with("Missing fact:") => {
+                    let fact = msg.replace("Missing fact: ", "");
+                    let failed_rule = context
+                        .failed_rules
+                        .iter()
+                        .any(|failed| failed.to_string() == fact);
+                    if !failed_rule && !missing.contains(&fact) {
+                        missing.push(fact);
+                    }
+                    context.failed_rules.insert(rule_path);
+                }
+                Err(LemmaError::UnknownResult(reason)) => {
+                    context.unknown_rules.insert(rule_path, reason);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(missing)
+    }
+}
+
+/// The rule a rule path points at, with the fact path prefix to evaluate it with
+///
+/// Rules from a document referenced by a fact look up their facts under that
+/// fact: for `employee.salary?` where `employee = doc hr_doc` the prefix is
+/// `["employee"]`, so facts are looked up as `["employee", "field"]`. Local
+/// rules get an empty prefix.
+fn find_rule<'a>(
+    rule_path: &RulePath,
+    doc_name: &str,
+    documents: &'a HashMap<String, LemmaDoc>,
+) -> LemmaResult<(&'a LemmaRule, Vec<String>)> {
+    let target_doc_name = rule_path.target_doc(doc_name);
+    let rule_doc = documents
+        .get(target_doc_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Document {} not found", target_doc_name)))?;
+
+    let rule = rule_doc
+        .rules
+        .iter()
+        .find(|r| r.name == rule_path.rule)
+        .ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "Rule {} not found in document {}",
+                rule_path.rule, target_doc_name
+            ))
+        })?;
+
+    let path_prefix = if target_doc_name != doc_name {
+        rule_path.segments.iter().map(|s| s.fact.clone()).collect()
+    } else {
+        Vec::new()
+    };
+    Ok((rule, path_prefix))
+}
+
+/// An override naming a fact the document doesn't have
+struct UnusedOverride {
+    path: String,
+    /// The override as `name=value`
+    input: String,
+    /// The closest fact name, when one is close enough to be a typo
+    suggestion: Option<String>,
+    reason: String,
+}
+
+fn unused_overrides(
+    doc: &LemmaDoc,
+    overrides: &[LemmaFact],
+    documents: &HashMap<String, LemmaDoc>,
+) -> Vec<UnusedOverride> {
+    let mut fact_paths = None;
+    overrides
+        .iter()
+        .filter_map(|fact| {
+            let reference = get_fact_path(fact).reference;
+            if crate::analysis::declared_fact_type(doc, &reference, documents).is_some() {
+                return None;
+            }
+            let path = reference.join(".");
+            let candidates =
+                fact_paths.get_or_insert_with(|| crate::analysis::fact_paths(doc, documents));
+            let suggestion = crate::analysis::closest_match(&path, candidates).map(str::to_string);
+            let mut reason = format!("document '{}' has no fact '{}'", doc.name, path);
+            if let Some(suggestion) = &suggestion {
+                reason.push_str(&format!(", did you mean '{}'?", suggestion));
+            }
+            let input = match &fact.value {
+                FactValue::Literal(value) => format!("{}={}", path, value),
+                _ => path.clone(),
+            };
+            Some(UnusedOverride {
+                path,
+                input,
+                suggestion,
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Move the results of the document's invariants into diagnostics
+///
+/// An invariant that evaluated to false is reported as `invariant_violated`.
+/// One that couldn't be evaluated, e.g. for a missing fact, isn't reported.
+fn check_invariants(doc: &LemmaDoc, response: &mut Response) {
+    for invariant in &doc.invariants {
+        let rule_name = invariant.rule_name();
+        let Some(position) = response
+            .results
+            .iter()
+            .position(|result| result.rule_name == rule_name)
+        else {
+            continue;
+        };
+        let result = response.results.remove(position);
+        if result.result == Some(LiteralValue::Boolean(false)) {
+            response.add_diagnostic(Diagnostic {
+                kind: DiagnosticKind::InvariantViolated,
+                rule: Some(rule_name),
+                fact: None,
+                message: format!("Invariant '{}' does not hold", invariant.condition),
+            });
+        }
+    }
+}
+
+/// The dependency graph of the rules to evaluate
+///
+/// With `rule_names`, the graph holds only those rules and the rules they
+/// reference, directly or through others, also in referenced documents.
+/// Invariants are checked whichever rules are requested, so their rules are included.
+pub(crate) fn evaluation_graph(
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    instances: &crate::analysis::CollectionInstances,
+    rule_names: Option<&[String]>,
+    limits: &ResourceLimits,
+) -> LemmaResult<HashMap<RulePath, HashSet<RulePath>>> {
+    let roots: Option<Vec<String>> = rule_names.map(|names| {
+        names
+            .iter()
+            .cloned()
+            .chain(doc.invariants.iter().map(|invariant| invariant.rule_name()))
+            .collect()
+    });
+    crate::analysis::build_dependency_graph_for_rules(
+        doc,
+        roots.as_deref(),
+        documents,
+        instances,
+        limits,
+    )
+}
+
+/// `roots` and every rule they depend on, directly or through other rules
+fn dependency_closure(
+    graph: &HashMap<RulePath, HashSet<RulePath>>,
+    roots: HashSet<RulePath>,
+) -> HashSet<RulePath> {
+    let mut needed = roots;
+    let mut pending: Vec<RulePath> = needed.iter().cloned().collect();
+    while let Some(path) = pending.pop() {
+        for dependency in graph.get(&path).into_iter().flatten() {
+            if needed.insert(dependency.clone()) {
+                pending.push(dependency.clone());
+            }
+        }
+    }
+    needed
 }
 
 /// Topological sort of rules to get execution order.
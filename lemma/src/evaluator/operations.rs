@@ -2,8 +2,12 @@
 //!
 //! Handles operations on different types: Number, Money, Percentage, Duration, etc.
 
-use crate::{ArithmeticOperation, ComparisonOperator, LemmaError, LemmaResult, LiteralValue};
+use crate::numeric_policy::{NumericPolicy, Overflow};
+use crate::{
+    ArithmeticOperation, ComparisonOperator, LemmaError, LemmaResult, LiteralValue, TextComparison,
+};
 use rust_decimal::Decimal;
+use std::borrow::Cow;
 
 // Percentage calculations: percentages are stored as numbers (e.g., 20 for 20%)
 // To apply a percentage, divide by 100 (e.g., 20% of 100 = 100 * 20 / 100 = 20)
@@ -15,6 +19,8 @@ const PERCENT_DENOMINATOR: i32 = 100;
 /// - Number + Number = Number
 /// - Money + Money = Money (same currency)
 /// - Number * Percentage = Number (applies percentage)
+/// - Percentage + Percentage = Percentage (relative change)
+/// - Percentage + PercentagePoints = Percentage (additive change)
 /// - Date + Duration = Date
 /// - Time + Duration = Time
 ///
@@ -24,17 +30,29 @@ const PERCENT_DENOMINATOR: i32 = 100;
 /// $50 + $30 = $80
 /// 100 * 20% = 20
 /// 100 + 20% = 120
+/// 5% + 2% = 5.1%
+/// 5% + 2 percentage points = 7%
 /// 2024-01-15 + 5 days = 2024-01-20
 /// ```
 pub fn arithmetic_operation(
     left: &LiteralValue,
     op: &ArithmeticOperation,
     right: &LiteralValue,
+) -> LemmaResult<LiteralValue> {
+    arithmetic_operation_with_policy(left, op, right, &NumericPolicy::default())
+}
+
+/// Like `arithmetic_operation`, rounding and overflowing as `policy` says
+pub fn arithmetic_operation_with_policy(
+    left: &LiteralValue,
+    op: &ArithmeticOperation,
+    right: &LiteralValue,
+    policy: &NumericPolicy,
 ) -> LemmaResult<LiteralValue> {
     match (left, right) {
         // Number arithmetic
         (LiteralValue::Number(l), LiteralValue::Number(r)) => {
-            Ok(LiteralValue::Number(number_arithmetic(*l, op, *r)?))
+            Ok(LiteralValue::Number(number_arithmetic(*l, op, *r, policy)?))
         }
 
         // Unit arithmetic - unified handling for all unit types
@@ -54,7 +72,7 @@ pub fn arithmetic_operation(
                         ));
                     }
                 };
-                let result_value = number_arithmetic(l_unit.value(), op, r_value)?;
+                let result_value = number_arithmetic(l_unit.value(), op, r_value, policy)?;
                 Ok(LiteralValue::Unit(l_unit.with_value(result_value)))
             } else {
                 // Different categories: produce dimensionless number
@@ -62,19 +80,20 @@ pub fn arithmetic_operation(
                     l_unit.value(),
                     op,
                     r_unit.value(),
+                    policy,
                 )?))
             }
         }
 
         // Unit op Number: produce unit
         (LiteralValue::Unit(unit), LiteralValue::Number(n)) => {
-            let result_value = number_arithmetic(unit.value(), op, *n)?;
+            let result_value = number_arithmetic(unit.value(), op, *n, policy)?;
             Ok(LiteralValue::Unit(unit.with_value(result_value)))
         }
 
         // Number op Unit: produce unit
         (LiteralValue::Number(n), LiteralValue::Unit(unit)) => {
-            let result_value = number_arithmetic(*n, op, unit.value())?;
+            let result_value = number_arithmetic(*n, op, unit.value(), policy)?;
             Ok(LiteralValue::Unit(unit.with_value(result_value)))
         }
 
@@ -83,9 +102,7 @@ pub fn arithmetic_operation(
             match op {
                 ArithmeticOperation::Multiply => {
                     // 20% * 100 = 20 (apply percentage)
-                    Ok(LiteralValue::Number(
-                        p * n / Decimal::from(PERCENT_DENOMINATOR),
-                    ))
+                    Ok(LiteralValue::Number(percent_of(*n, *p, policy)?))
                 }
                 _ => Err(LemmaError::Engine(format!(
                     "Operation {:?} not supported for percentage and number",
@@ -97,21 +114,21 @@ pub fn arithmetic_operation(
             match op {
                 ArithmeticOperation::Multiply => {
                     // 100 * 20% = 20 (apply percentage)
-                    Ok(LiteralValue::Number(
-                        n * p / Decimal::from(PERCENT_DENOMINATOR),
-                    ))
+                    Ok(LiteralValue::Number(percent_of(*n, *p, policy)?))
                 }
                 ArithmeticOperation::Add => {
                     // 100 + 20% = 120 (increase by percentage)
-                    Ok(LiteralValue::Number(
-                        n + (n * p / Decimal::from(PERCENT_DENOMINATOR)),
-                    ))
+                    let increase = percent_of(*n, *p, policy)?;
+                    Ok(LiteralValue::Number(number_arithmetic(
+                        *n, op, increase, policy,
+                    )?))
                 }
                 ArithmeticOperation::Subtract => {
                     // 100 - 20% = 80 (decrease by percentage)
-                    Ok(LiteralValue::Number(
-                        n - (n * p / Decimal::from(PERCENT_DENOMINATOR)),
-                    ))
+                    let decrease = percent_of(*n, *p, policy)?;
+                    Ok(LiteralValue::Number(number_arithmetic(
+                        *n, op, decrease, policy,
+                    )?))
                 }
                 _ => Err(LemmaError::Engine(format!(
                     "Operation {:?} not supported for number and percentage",
@@ -120,23 +137,85 @@ pub fn arithmetic_operation(
             }
         }
 
+        // Percentage + Percentage changes the left percentage by the right one,
+        // like Number + Percentage does
+        (LiteralValue::Percentage(l), LiteralValue::Percentage(r)) => match op {
+            ArithmeticOperation::Add | ArithmeticOperation::Subtract => {
+                let change = relative_change(*l, *r);
+                Ok(LiteralValue::Percentage(number_arithmetic(
+                    *l, op, change, policy,
+                )?))
+            }
+            _ => Err(LemmaError::Engine(format!(
+                "Operation {:?} not supported for percentages",
+                op
+            ))),
+        },
+
+        // Percentage points add to and subtract from percentages directly
+        (LiteralValue::Percentage(l), LiteralValue::PercentagePoints(r)) => match op {
+            ArithmeticOperation::Add | ArithmeticOperation::Subtract => Ok(
+                LiteralValue::Percentage(number_arithmetic(*l, op, *r, policy)?),
+            ),
+            _ => Err(LemmaError::Engine(format!(
+                "Operation {:?} not supported for percentage and percentage points",
+                op
+            ))),
+        },
+        (LiteralValue::PercentagePoints(l), LiteralValue::Percentage(r)) => match op {
+            ArithmeticOperation::Add => Ok(LiteralValue::Percentage(number_arithmetic(
+                *l, op, *r, policy,
+            )?)),
+            _ => Err(LemmaError::Engine(format!(
+                "Operation {:?} not supported for percentage points and percentage",
+                op
+            ))),
+        },
+        (LiteralValue::PercentagePoints(l), LiteralValue::PercentagePoints(r)) => match op {
+            ArithmeticOperation::Add | ArithmeticOperation::Subtract => Ok(
+                LiteralValue::PercentagePoints(number_arithmetic(*l, op, *r, policy)?),
+            ),
+            _ => Err(LemmaError::Engine(format!(
+                "Operation {:?} not supported for percentage points",
+                op
+            ))),
+        },
+        (LiteralValue::PercentagePoints(p), LiteralValue::Number(n)) => match op {
+            ArithmeticOperation::Multiply | ArithmeticOperation::Divide => Ok(
+                LiteralValue::PercentagePoints(number_arithmetic(*p, op, *n, policy)?),
+            ),
+            _ => Err(LemmaError::Engine(format!(
+                "Operation {:?} not supported for percentage points and number",
+                op
+            ))),
+        },
+        (LiteralValue::Number(n), LiteralValue::PercentagePoints(p)) => match op {
+            ArithmeticOperation::Multiply => Ok(LiteralValue::PercentagePoints(number_arithmetic(
+                *n, op, *p, policy,
+            )?)),
+            _ => Err(LemmaError::Engine(format!(
+                "Operation {:?} not supported for number and percentage points",
+                op
+            ))),
+        },
+
         (LiteralValue::Percentage(p), LiteralValue::Unit(unit))
         | (LiteralValue::Unit(unit), LiteralValue::Percentage(p)) => match op {
             ArithmeticOperation::Multiply => {
                 // Unit * Percentage = Unit scaled by percentage (e.g., 100 eur * 20% = 20 eur)
-                let result_value = unit.value() * p / Decimal::from(PERCENT_DENOMINATOR);
+                let result_value = percent_of(unit.value(), *p, policy)?;
                 Ok(LiteralValue::Unit(unit.with_value(result_value)))
             }
             ArithmeticOperation::Add => {
                 // Unit + Percentage = Unit increased by percentage (e.g., 100 eur + 20% = 120 eur)
-                let increase = unit.value() * p / Decimal::from(PERCENT_DENOMINATOR);
-                let result_value = unit.value() + increase;
+                let increase = percent_of(unit.value(), *p, policy)?;
+                let result_value = number_arithmetic(unit.value(), op, increase, policy)?;
                 Ok(LiteralValue::Unit(unit.with_value(result_value)))
             }
             ArithmeticOperation::Subtract => {
                 // Unit - Percentage = Unit decreased by percentage (e.g., 100 eur - 20% = 80 eur)
-                let decrease = unit.value() * p / Decimal::from(PERCENT_DENOMINATOR);
-                let result_value = unit.value() - decrease;
+                let decrease = percent_of(unit.value(), *p, policy)?;
+                let result_value = number_arithmetic(unit.value(), op, decrease, policy)?;
                 Ok(LiteralValue::Unit(unit.with_value(result_value)))
             }
             _ => Err(LemmaError::Engine(format!(
@@ -164,25 +243,30 @@ pub fn arithmetic_operation(
     }
 }
 
+/// `change` percent of `percentage`, in percentage points: 2% of 5% is 0.1
+pub(crate) fn relative_change(percentage: Decimal, change: Decimal) -> Decimal {
+    percentage * change / Decimal::from(PERCENT_DENOMINATOR)
+}
+
 /// Perform basic number arithmetic, returning the numeric result
 fn number_arithmetic(
     left: Decimal,
     op: &ArithmeticOperation,
     right: Decimal,
+    policy: &NumericPolicy,
 ) -> LemmaResult<Decimal> {
     use rust_decimal::prelude::ToPrimitive;
 
     let result = match op {
-        ArithmeticOperation::Add => left + right,
-        ArithmeticOperation::Subtract => left - right,
-        ArithmeticOperation::Multiply => left * right,
-        ArithmeticOperation::Divide => {
-            if right == Decimal::ZERO {
-                return Err(LemmaError::Engine("Division by zero".to_string()));
-            }
-            left / right
+        ArithmeticOperation::Add => left.checked_add(right),
+        ArithmeticOperation::Subtract => left.checked_sub(right),
+        ArithmeticOperation::Multiply => left.checked_mul(right),
+        ArithmeticOperation::Divide | ArithmeticOperation::Modulo if right == Decimal::ZERO => {
+            return Err(LemmaError::Engine("Division by zero".to_string()));
         }
-        ArithmeticOperation::Modulo => left % right,
+        // The quotient can carry trailing zeros, 10 / 4 would print as 2.50
+        ArithmeticOperation::Divide => left.checked_div(right).map(|q| policy.round(q.normalize())),
+        ArithmeticOperation::Modulo => left.checked_rem(right),
         ArithmeticOperation::Power => {
             let base = left
                 .to_f64()
@@ -191,29 +275,92 @@ fn number_arithmetic(
                 LemmaError::Engine("Cannot convert exponent to float".to_string())
             })?;
             let result = base.powf(exp);
-            Decimal::from_f64_retain(result).ok_or_else(|| {
-                LemmaError::Engine("Power result cannot be represented".to_string())
-            })?
+            if result.is_nan() {
+                return Err(LemmaError::Engine(format!(
+                    "{} ^ {} is not a real number",
+                    left, right
+                )));
+            }
+            Decimal::from_f64_retain(result).map(|power| policy.round(power))
         }
     };
 
-    Ok(result)
+    match result {
+        Some(result) => Ok(result),
+        None => {
+            let positive = match op {
+                ArithmeticOperation::Add => left.is_sign_positive(),
+                ArithmeticOperation::Subtract => left > right,
+                ArithmeticOperation::Power => {
+                    // A negative base to an even power is positive
+                    left.is_sign_positive()
+                        || (right.fract().is_zero() && right % Decimal::TWO == Decimal::ZERO)
+                }
+                _ => left.is_sign_positive() == right.is_sign_positive(),
+            };
+            overflowed(left, op, right, positive, policy)
+        }
+    }
+}
+
+/// `percent` percent of `value`: 20% of 150 is 30
+fn percent_of(value: Decimal, percent: Decimal, policy: &NumericPolicy) -> LemmaResult<Decimal> {
+    let denominator = Decimal::from(PERCENT_DENOMINATOR);
+    let fraction = percent / denominator;
+    // `value * percent / 100` keeps the scale of the operands; scaling the percentage down
+    // first only matters when the product alone would overflow
+    let result = value
+        .checked_mul(percent)
+        .and_then(|product| product.checked_div(denominator))
+        .or_else(|| value.checked_mul(fraction).map(|result| result.normalize()));
+    result.map_or_else(
+        || {
+            let positive = value.is_sign_positive() == percent.is_sign_positive();
+            overflowed(
+                value,
+                &ArithmeticOperation::Multiply,
+                fraction,
+                positive,
+                policy,
+            )
+        },
+        Ok,
+    )
+}
+
+/// The result of `left op right` beyond the largest decimal, under `policy`
+fn overflowed(
+    left: Decimal,
+    op: &ArithmeticOperation,
+    right: Decimal,
+    positive: bool,
+    policy: &NumericPolicy,
+) -> LemmaResult<Decimal> {
+    match policy.overflow {
+        Overflow::Error => Err(LemmaError::Engine(format!(
+            "Arithmetic overflow: {} {} {} is beyond the largest number, {}",
+            left,
+            op,
+            right,
+            Decimal::MAX
+        ))),
+        Overflow::Saturate if positive => Ok(Decimal::MAX),
+        Overflow::Saturate => Ok(Decimal::MIN),
+    }
 }
 
 /// Perform type-aware comparison.
 ///
-/// Handles comparisons between compatible types:
-/// - Numbers can be compared with numbers
-/// - Strings can be compared with strings
-/// - Dates can be compared with dates (timezone-aware)
-/// - Booleans can be compared with booleans
-/// - Money can only be compared within the same currency
+/// The semantics, shared with inversion and validation, are documented in
+/// `semantic::value_ops`.
 ///
 /// # Examples
 /// ```text
 /// 100 > 50 = true
-/// "apple" < "banana" = true
+/// 10 is 10% = true
+/// 1 kilogram is 1000 grams = true
 /// 2024-01-15 > 2024-01-10 = true
+/// 2024-01-15 is 2024-01 = true
 /// $100 > $50 = true (same currency)
 /// ```
 pub fn comparison_operation(
@@ -221,107 +368,93 @@ pub fn comparison_operation(
     op: &ComparisonOperator,
     right: &LiteralValue,
 ) -> LemmaResult<bool> {
-    match (left, right) {
-        // Number comparisons
-        (LiteralValue::Number(l), LiteralValue::Number(r)) => Ok(compare_decimals(*l, op, r)),
-
-        // Unit > Unit
-        (LiteralValue::Unit(l_unit), LiteralValue::Unit(r_unit)) => {
-            // Validate currency compatibility
-            l_unit.validate_same_currency(r_unit)?;
+    comparison_operation_with_text(left, op, right, TextComparison::default())
+}
 
-            if l_unit.same_category(r_unit) {
-                // Convert right to left's unit, then compare
-                let right_converted = convert_to_matching_unit(right, l_unit)?;
-                let r_value = match &right_converted {
-                    LiteralValue::Unit(u) => u.value(),
-                    LiteralValue::Number(n) => *n, // Duration conversion returns Number
-                    _ => return Err(LemmaError::Engine("Invalid unit conversion".to_string())),
-                };
-                Ok(compare_decimals(l_unit.value(), op, &r_value))
-            } else {
-                // Different categories: compare numeric values directly
-                Ok(compare_decimals(l_unit.value(), op, &r_unit.value()))
-            }
-        }
+/// Perform a comparison, ignoring the text differences the document asks for
+///
+/// Only text comparisons are affected; other types compare as in
+/// `comparison_operation`.
+pub fn comparison_operation_with_text(
+    left: &LiteralValue,
+    op: &ComparisonOperator,
+    right: &LiteralValue,
+    text_comparison: TextComparison,
+) -> LemmaResult<bool> {
+    crate::semantic::value_ops::compare(left, op, right, text_comparison)
+}
 
-        // Unit > Number: extract value and compare
-        (LiteralValue::Unit(unit), LiteralValue::Number(n))
-        | (LiteralValue::Number(n), LiteralValue::Unit(unit)) => {
-            Ok(compare_decimals(unit.value(), op, n))
-        }
+/// Remove the differences `text_comparison` ignores
+///
+/// Case is folded with Unicode lowercase mapping, so `"ÉCOLE"` and `"école"`
+/// fold alike. Accents are removed from Latin letters, both precomposed
+/// (`é`) and written with combining marks (`e` + U+0301).
+pub fn fold_text(text: &str, text_comparison: TextComparison) -> Cow<'_, str> {
+    if text_comparison.is_exact() {
+        return Cow::Borrowed(text);
+    }
 
-        // Percentage comparisons
-        (LiteralValue::Percentage(l), LiteralValue::Percentage(r)) => {
-            Ok(compare_decimals(*l, op, r))
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        let c = if text_comparison.ignore_accents {
+            if is_combining_mark(c) {
+                continue;
+            }
+            strip_accent(c)
+        } else {
+            c
+        };
+        if text_comparison.ignore_case {
+            folded.extend(c.to_lowercase());
+        } else {
+            folded.push(c);
         }
+    }
+    Cow::Owned(folded)
+}
 
-        // Boolean comparisons
-        (LiteralValue::Boolean(l), LiteralValue::Boolean(r)) => match op {
-            ComparisonOperator::Equal | ComparisonOperator::Is => Ok(l == r),
-            ComparisonOperator::NotEqual | ComparisonOperator::IsNot => Ok(l != r),
-            _ => Err(LemmaError::Engine(
-                "Can only use == and != with booleans".to_string(),
-            )),
-        },
-
-        // Text comparisons
-        (LiteralValue::Text(l), LiteralValue::Text(r)) => match op {
-            ComparisonOperator::Equal | ComparisonOperator::Is => Ok(l == r),
-            ComparisonOperator::NotEqual | ComparisonOperator::IsNot => Ok(l != r),
-            _ => Err(LemmaError::Engine(
-                "Can only use == and != with text".to_string(),
-            )),
-        },
-
-        // Date comparisons
-        (LiteralValue::Date(_), LiteralValue::Date(_)) => {
-            super::datetime::datetime_comparison(left, op, right)
-        }
+/// Base letters of Latin-1 Supplement (U+00C0..U+00FF) and Latin Extended-A
+/// (U+0100..U+017F); `-` marks letters without an accent-free form (æ, ß, ŋ)
+const LATIN_BASE_LETTERS: &str = concat!(
+    "AAAAAA-CEEEEIIII",
+    "-NOOOOO-OUUUUY--",
+    "aaaaaa-ceeeeiiii",
+    "-nooooo-ouuuuy-y",
+    "AaAaAaCcCcCcCcDd",
+    "DdEeEeEeEeEeGgGg",
+    "GgGgHhHhIiIiIiIi",
+    "Ii--JjKk-LlLlLlL",
+    "lLlNnNnNn---OoOo",
+    "Oo--RrRrRrSsSsSs",
+    "SsTtTtTtUuUuUuUu",
+    "UuUuWwYyYZzZzZzs",
+);
 
-        _ => Err(LemmaError::Engine(format!(
-            "Comparison {:?} not supported for types {:?} and {:?}",
-            op,
-            type_name(left),
-            type_name(right)
-        ))),
+fn strip_accent(c: char) -> char {
+    let code = c as usize;
+    if !(0xC0..=0x17F).contains(&code) {
+        return c;
+    }
+    match LATIN_BASE_LETTERS.as_bytes()[code - 0xC0] {
+        b'-' => c,
+        base => base as char,
     }
 }
 
+/// Combining diacritical marks (U+0300..U+036F), as left by NFD normalization
+fn is_combining_mark(c: char) -> bool {
+    ('\u{0300}'..='\u{036F}').contains(&c)
+}
+
 /// Convert a Unit value to match the target Unit's type
 fn convert_to_matching_unit(
     value: &LiteralValue,
     target: &crate::NumericUnit,
 ) -> LemmaResult<LiteralValue> {
-    let conversion_target = match target {
-        crate::NumericUnit::Mass(_, u) => crate::ConversionTarget::Mass(u.clone()),
-        crate::NumericUnit::Length(_, u) => crate::ConversionTarget::Length(u.clone()),
-        crate::NumericUnit::Volume(_, u) => crate::ConversionTarget::Volume(u.clone()),
-        crate::NumericUnit::Duration(_, unit) => crate::ConversionTarget::Duration(unit.clone()),
-        crate::NumericUnit::Temperature(_, u) => crate::ConversionTarget::Temperature(u.clone()),
-        crate::NumericUnit::Power(_, u) => crate::ConversionTarget::Power(u.clone()),
-        crate::NumericUnit::Force(_, u) => crate::ConversionTarget::Force(u.clone()),
-        crate::NumericUnit::Pressure(_, u) => crate::ConversionTarget::Pressure(u.clone()),
-        crate::NumericUnit::Energy(_, u) => crate::ConversionTarget::Energy(u.clone()),
-        crate::NumericUnit::Frequency(_, u) => crate::ConversionTarget::Frequency(u.clone()),
-        crate::NumericUnit::Data(_, u) => crate::ConversionTarget::Data(u.clone()),
-        crate::NumericUnit::Money(_, u) => crate::ConversionTarget::Money(u.clone()),
-    };
+    let conversion_target = target.conversion_target();
     super::units::convert_unit_for_arithmetic(value, &conversion_target)
 }
 
-/// Helper to compare two decimal values
-fn compare_decimals(left: Decimal, op: &ComparisonOperator, right: &Decimal) -> bool {
-    match op {
-        ComparisonOperator::GreaterThan => left > *right,
-        ComparisonOperator::LessThan => left < *right,
-        ComparisonOperator::GreaterThanOrEqual => left >= *right,
-        ComparisonOperator::LessThanOrEqual => left <= *right,
-        ComparisonOperator::Equal | ComparisonOperator::Is => left == *right,
-        ComparisonOperator::NotEqual | ComparisonOperator::IsNot => left != *right,
-    }
-}
-
 /// Helper to get a human-readable type name
 fn type_name(value: &LiteralValue) -> String {
     value.to_type().to_string()
@@ -3,12 +3,13 @@
 //! Handles evaluation of rules including default expressions and unless clauses.
 
 use super::context::EvaluationContext;
-use super::expression::evaluate_expression;
-use crate::{LemmaError, LemmaRule, OperationResult};
+use super::expression::{evaluate_expression, is_sensitive, traced};
+use crate::{ExpressionKind, LemmaError, LemmaRule, OperationResult};
 
 /// Evaluate a rule to produce its final result
 ///
-/// Unless clauses are evaluated in reverse order (last matching wins).
+/// Unless clauses are evaluated in reverse order (last matching wins), or in
+/// declaration order for `first match` rules.
 /// If no unless clause matches, evaluate the default expression.
 ///
 /// When evaluating a rule from a document referenced by a fact, pass the fact path
@@ -20,8 +21,9 @@ pub fn evaluate_rule(
 ) -> Result<OperationResult, LemmaError> {
     use crate::OperationRecord;
 
-    // Evaluate unless clauses in reverse order (last matching wins)
-    for (index, unless_clause) in rule.unless_clauses.iter().enumerate().rev() {
+    // Evaluate unless clauses in priority order (last matching wins by default)
+    for index in rule.unless_mode.evaluation_order(rule.unless_clauses.len()) {
+        let unless_clause = &rule.unless_clauses[index];
         let condition_result = evaluate_expression(&unless_clause.condition, context, fact_prefix)?;
 
         // If condition is vetoed, the veto applies to this rule
@@ -48,25 +50,20 @@ pub fn evaluate_rule(
             }
 
             let result_value = result.value().unwrap().clone();
-            context
-                .operations
-                .push(OperationRecord::UnlessClauseEvaluated {
-                    index,
-                    matched: true,
-                    result_if_matched: Some(result_value.clone()),
-                });
-            context.operations.push(OperationRecord::FinalResult {
-                value: result_value.clone(),
+            let sensitive = is_sensitive(&unless_clause.result, context, fact_prefix);
+            context.push_operation(|| OperationRecord::UnlessClauseEvaluated {
+                index,
+                matched: true,
+                result_if_matched: Some(traced(&result_value, sensitive)),
             });
+            context.push_final_result(&result_value);
             return Ok(OperationResult::Value(result_value));
         } else {
-            context
-                .operations
-                .push(OperationRecord::UnlessClauseEvaluated {
-                    index,
-                    matched: false,
-                    result_if_matched: None,
-                });
+            context.push_operation(|| OperationRecord::UnlessClauseEvaluated {
+                index,
+                matched: false,
+                result_if_matched: None,
+            });
         }
     }
 
@@ -79,11 +76,70 @@ pub fn evaluate_rule(
     }
 
     let default_value = default_result.value().unwrap().clone();
-    context.operations.push(OperationRecord::DefaultValue {
-        value: default_value.clone(),
-    });
-    context.operations.push(OperationRecord::FinalResult {
-        value: default_value.clone(),
+    let sensitive = is_sensitive(&rule.expression, context, fact_prefix);
+    context.push_operation(|| OperationRecord::DefaultValue {
+        value: traced(&default_value, sensitive),
     });
+    context.push_final_result(&default_value);
     Ok(OperationResult::Value(default_value))
 }
+
+/// Whether the rule's value derives from a sensitive fact
+///
+/// Conditions count too: a value picked because a sensitive fact is over some
+/// limit gives that away.
+pub fn is_sensitive_rule(
+    rule: &LemmaRule,
+    context: &EvaluationContext,
+    fact_prefix: &[String],
+) -> bool {
+    std::iter::once(&rule.expression)
+        .chain(
+            rule.unless_clauses
+                .iter()
+                .flat_map(|clause| [&clause.condition, &clause.result]),
+        )
+        .any(|expression| is_sensitive(expression, context, fact_prefix))
+}
+
+/// Whether any unless clause of the rule vetoes, e.g. `unless age < 18 then veto`
+pub fn has_veto_clause(rule: &LemmaRule) -> bool {
+    rule.unless_clauses
+        .iter()
+        .any(|clause| matches!(clause.result.kind, ExpressionKind::Veto(_)))
+}
+
+/// Find the veto a rule's unless clauses give, without computing its value
+///
+/// Walks the clauses in the same order as `evaluate_rule`. The deciding clause
+/// is evaluated only when it vetoes; when it gives a value, or no clause
+/// matches, the rule doesn't veto and neither that value nor the default
+/// expression is evaluated. A veto from a condition applies as well.
+pub fn evaluate_vetoes(
+    rule: &LemmaRule,
+    context: &mut EvaluationContext,
+    fact_prefix: &[String],
+) -> Result<Option<OperationResult>, LemmaError> {
+    for index in rule.unless_mode.evaluation_order(rule.unless_clauses.len()) {
+        let unless_clause = &rule.unless_clauses[index];
+        let condition_result = evaluate_expression(&unless_clause.condition, context, fact_prefix)?;
+        let matched = match condition_result {
+            OperationResult::Veto(_) => return Ok(Some(condition_result)),
+            OperationResult::Value(crate::LiteralValue::Boolean(b)) => b,
+            OperationResult::Value(_) => {
+                return Err(LemmaError::Engine(
+                    "Unless condition must evaluate to boolean".to_string(),
+                ));
+            }
+        };
+
+        if matched {
+            if !matches!(unless_clause.result.kind, ExpressionKind::Veto(_)) {
+                return Ok(None);
+            }
+            let result = evaluate_expression(&unless_clause.result, context, fact_prefix)?;
+            return Ok(matches!(result, OperationResult::Veto(_)).then_some(result));
+        }
+    }
+    Ok(None)
+}
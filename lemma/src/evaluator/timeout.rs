@@ -1,64 +1,142 @@
-//! Timeout tracking for evaluation
+//! Timeout tracking and cancellation for evaluation
 //!
 //! Provides platform-specific timeout tracking. On native targets, uses std::time::Instant
 //! to track elapsed time. On WASM, timeout checking is a no-op since std::time::Instant
-//! is not available in the wasm32 target.
+//! is not available in the wasm32 target. Cancellation through a `CancellationToken`
+//! works on every target.
 
 use crate::{LemmaError, ResourceLimits};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+#[cfg(not(target_arch = "wasm32"))]
+use std::cell::Cell;
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::Instant;
 
+/// Handle to cancel an evaluation from another thread
+///
+/// Clones share the same state: cancelling one cancels all of them. Evaluation
+/// checks the token before every expression, so it stops soon after
+/// `cancel` with `LemmaError::Cancelled`.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask evaluations using this token to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
 /// Timeout tracker for evaluation
 ///
 /// On native platforms, tracks actual elapsed time using Instant.
 /// On WASM, this is a zero-cost abstraction with no-op timeout checks.
+/// The rule being evaluated is tracked so errors can name it.
 pub struct TimeoutTracker {
     #[cfg(not(target_arch = "wasm32"))]
     start_time: Instant,
+    #[cfg(not(target_arch = "wasm32"))]
+    rule_start_time: Cell<Instant>,
+    current_rule: RefCell<Option<String>>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl TimeoutTracker {
     /// Create a new timeout tracker
     #[cfg(not(target_arch = "wasm32"))]
     pub fn new() -> Self {
+        let now = Instant::now();
         Self {
-            start_time: Instant::now(),
+            start_time: now,
+            rule_start_time: Cell::new(now),
+            current_rule: RefCell::new(None),
+            cancellation: None,
         }
     }
 
     /// Create a new timeout tracker (WASM version)
     #[cfg(target_arch = "wasm32")]
     pub fn new() -> Self {
-        Self {}
+        Self {
+            current_rule: RefCell::new(None),
+            cancellation: None,
+        }
+    }
+
+    /// Also stop evaluation once `cancellation` is cancelled
+    pub fn with_cancellation(mut self, cancellation: Option<CancellationToken>) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Record that evaluation of `rule` starts
+    pub fn enter_rule(&self, rule: &str) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.rule_start_time.set(Instant::now());
+        *self.current_rule.borrow_mut() = Some(rule.to_string());
+    }
+
+    fn check_cancelled(&self) -> Result<(), LemmaError> {
+        match &self.cancellation {
+            Some(token) if token.is_cancelled() => Err(LemmaError::Cancelled {
+                rule: self.current_rule.borrow().clone(),
+            }),
+            _ => Ok(()),
+        }
     }
 
-    /// Check if evaluation has exceeded the timeout limit
+    /// Check if evaluation was cancelled or has exceeded the timeout limit
     ///
     /// On native platforms, returns an error if elapsed time exceeds max_evaluation_time_ms.
-    /// On WASM, always returns Ok (timeout checking not available).
+    /// The error names the rule being evaluated and how long that rule took.
     #[cfg(not(target_arch = "wasm32"))]
     pub fn check_timeout(&self, limits: &ResourceLimits) -> Result<(), LemmaError> {
+        self.check_cancelled()?;
+
         let elapsed_ms = self.start_time.elapsed().as_millis() as u64;
         if elapsed_ms > limits.max_evaluation_time_ms {
+            let suggestion = match self.current_rule.borrow().as_deref() {
+                Some(rule) => format!(
+                    "Rule '{}' exceeded {}ms: evaluation took {}ms, {}ms of which in this rule. Simplify the rule or increase the timeout.",
+                    rule,
+                    limits.max_evaluation_time_ms,
+                    elapsed_ms,
+                    self.rule_start_time.get().elapsed().as_millis()
+                ),
+                None => format!(
+                    "Evaluation took {}ms, exceeding the limit of {}ms. Simplify the document or increase the timeout.",
+                    elapsed_ms, limits.max_evaluation_time_ms
+                ),
+            };
             return Err(LemmaError::ResourceLimitExceeded {
                 limit_name: "max_evaluation_time_ms".to_string(),
                 limit_value: limits.max_evaluation_time_ms.to_string(),
                 actual_value: elapsed_ms.to_string(),
-                suggestion: format!(
-                    "Evaluation took {}ms, exceeding the limit of {}ms. Simplify the document or increase the timeout.",
-                    elapsed_ms, limits.max_evaluation_time_ms
-                ),
+                suggestion,
             });
         }
         Ok(())
     }
 
-    /// Check if evaluation has exceeded the timeout limit (WASM version - no-op)
+    /// Check if evaluation was cancelled (WASM version)
+    ///
+    /// Timeouts are not checked: there is no std::time::Instant on WASM.
     #[cfg(target_arch = "wasm32")]
     pub fn check_timeout(&self, _limits: &ResourceLimits) -> Result<(), LemmaError> {
-        // Timeout checking not available on WASM (no std::time::Instant)
-        Ok(())
+        self.check_cancelled()
     }
 }
 
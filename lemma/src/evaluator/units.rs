@@ -289,13 +289,20 @@ pub(crate) fn convert_duration(
         return Ok(value);
     }
 
-    // Month and Year are calendar units, not time durations
-    if matches!(from, DurationUnit::Month | DurationUnit::Year)
-        || matches!(to, DurationUnit::Month | DurationUnit::Year)
-    {
-        return Err(LemmaError::Engine(
-            "Cannot convert calendar units (month/year) to other duration units. Use date arithmetic instead.".to_string()
-        ));
+    // Month and Year are calendar units, not time durations: a year is always
+    // 12 months, but neither has a fixed number of seconds
+    match (from, to) {
+        (DurationUnit::Year, DurationUnit::Month) => return Ok(value * Decimal::from(12)),
+        (DurationUnit::Month, DurationUnit::Year) => return Ok(value / Decimal::from(12)),
+        (DurationUnit::Month | DurationUnit::Year, _)
+        | (_, DurationUnit::Month | DurationUnit::Year) => {
+            return Err(LemmaError::Engine(format!(
+                "Cannot convert between {}s and {}s: calendar units (month/year) have no fixed length. \
+                Add them to a date instead (`start + 1 year`), or convert a difference of dates directly (`(end - start) in years`).",
+                from, to
+            )))
+        }
+        _ => {}
     }
 
     // Convert to base unit (seconds)
@@ -0,0 +1,188 @@
+//! Facts fetched from outside the engine
+//!
+//! A `FactProvider` supplies the values of typed facts that neither the
+//! document nor the caller gives, e.g. an exchange rate from a service or a
+//! customer's tier from a database:
+//!
+//! ```no_run
+//! use lemma::{Engine, FactProvider, FactRequest, LemmaResult, LiteralValue};
+//!
+//! struct Rates;
+//!
+//! impl FactProvider for Rates {
+//!     fn fetch(&self, request: &FactRequest) -> LemmaResult<Option<LiteralValue>> {
+//!         // Look up request.path, e.g. over HTTP
+//!         Ok(None)
+//!     }
+//! }
+//!
+//! let engine = Engine::new().with_fact_provider(Rates);
+//! ```
+//!
+//! Before evaluating, the engine follows the dependency graph of the
+//! requested rules to find every fact they reference that has no value, and
+//! asks for all of them at once with `FactProvider::fetch_many`. Its default
+//! fetches on separate threads, so slow lookups overlap instead of adding up;
+//! a provider with an asynchronous or batch client can override it to send
+//! the lookups its own way. Facts the provider doesn't know stay missing.
+
+use crate::analysis::{collection_instances, declared_fact_type, extract_references};
+use crate::evaluator::context::build_fact_map;
+use crate::evaluator::evaluation_graph;
+use crate::{
+    FactReference, FactType, FactValue, ForeignFact, LemmaDoc, LemmaFact, LemmaResult, LemmaType,
+    LiteralValue, ResourceLimits,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Most lookups `FactProvider::fetch_many` runs at the same time by default
+pub const MAX_CONCURRENT_FETCHES: usize = 16;
+
+/// A fact the engine asks a `FactProvider` for
+#[derive(Debug, Clone, PartialEq)]
+pub struct FactRequest {
+    /// The document being evaluated
+    pub doc_name: String,
+    /// Path of the fact from that document, e.g. `tier` or `customer.tier`
+    pub path: String,
+    /// The declared type, which the value must have
+    pub fact_type: LemmaType,
+}
+
+/// Supplies the values of facts from outside the engine, see `crate::fact_provider`
+pub trait FactProvider: Send + Sync {
+    /// The value of the requested fact, or `None` when the provider doesn't know it
+    ///
+    /// Errors are for failures such as an unreachable service; they fail the evaluation.
+    fn fetch(&self, request: &FactRequest) -> LemmaResult<Option<LiteralValue>>;
+
+    /// Values of all `requests`, in the same order
+    ///
+    /// Runs up to `MAX_CONCURRENT_FETCHES` calls of `fetch` at a time.
+    fn fetch_many(&self, requests: &[FactRequest]) -> Vec<LemmaResult<Option<LiteralValue>>> {
+        fetch_concurrently(self, requests)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_concurrently<P: FactProvider + ?Sized>(
+    provider: &P,
+    requests: &[FactRequest],
+) -> Vec<LemmaResult<Option<LiteralValue>>> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    if requests.len() < 2 {
+        return requests
+            .iter()
+            .map(|request| provider.fetch(request))
+            .collect();
+    }
+    let workers = requests.len().min(MAX_CONCURRENT_FETCHES);
+    let next = AtomicUsize::new(0);
+    let mut values: Vec<Option<LemmaResult<Option<LiteralValue>>>> =
+        requests.iter().map(|_| None).collect();
+
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(request) = requests.get(index) else {
+                    break;
+                };
+                if sender.send((index, provider.fetch(request))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        for (index, value) in receiver {
+            values[index] = Some(value);
+        }
+    });
+
+    values.into_iter().flatten().collect()
+}
+
+// No threads in the browser: lookups run one after the other
+#[cfg(target_arch = "wasm32")]
+fn fetch_concurrently<P: FactProvider + ?Sized>(
+    provider: &P,
+    requests: &[FactRequest],
+) -> Vec<LemmaResult<Option<LiteralValue>>> {
+    requests
+        .iter()
+        .map(|request| provider.fetch(request))
+        .collect()
+}
+
+/// Facts the rules to evaluate reference that have neither a value nor an override
+///
+/// With `rule_names`, only those rules and the rules they depend on are
+/// looked at, like the evaluation itself. Requests are sorted by path.
+pub(crate) fn unsupplied_facts(
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    overrides: &[LemmaFact],
+    rule_names: Option<&[String]>,
+    limits: &ResourceLimits,
+) -> LemmaResult<Vec<FactRequest>> {
+    let instances = collection_instances(doc, overrides, documents);
+    let graph = evaluation_graph(doc, documents, &instances, rule_names, limits)?;
+    let values = build_fact_map(doc, &doc.facts, overrides, documents)?;
+
+    let mut paths = HashSet::new();
+    for rule_path in graph.into_keys() {
+        let Some(rule) = documents
+            .get(rule_path.target_doc(&doc.name))
+            .and_then(|rule_doc| rule_doc.rules.iter().find(|r| r.name == rule_path.rule))
+        else {
+            continue;
+        };
+        let prefix: Vec<String> = rule_path.segments.iter().map(|s| s.fact.clone()).collect();
+        let expressions = std::iter::once(&rule.expression).chain(
+            rule.unless_clauses
+                .iter()
+                .flat_map(|clause| [&clause.condition, &clause.result]),
+        );
+        for expression in expressions {
+            for fact in extract_references(expression).facts {
+                let reference: Vec<String> =
+                    prefix.iter().chain(&fact.reference).cloned().collect();
+                paths.insert(reference);
+            }
+        }
+    }
+
+    let mut requests: Vec<FactRequest> = paths
+        .into_iter()
+        .filter(|reference| {
+            !values.contains_key(&FactReference {
+                reference: reference.clone(),
+            })
+        })
+        .filter_map(|reference| {
+            let fact_type = declared_fact_type(doc, &reference, documents)?;
+            Some(FactRequest {
+                doc_name: doc.name.clone(),
+                path: reference.join("."),
+                fact_type,
+            })
+        })
+        .collect();
+    requests.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(requests)
+}
+
+/// An override giving the fact at `path` the fetched `value`
+pub(crate) fn provided_fact(path: &str, value: LiteralValue) -> LemmaFact {
+    let reference: Vec<String> = path.split('.').map(str::to_string).collect();
+    let fact_type = match reference.as_slice() {
+        [name] => FactType::Local(name.clone()),
+        _ => FactType::Foreign(ForeignFact { reference }),
+    };
+    LemmaFact::new(fact_type, FactValue::Literal(value))
+}
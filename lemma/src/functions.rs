@@ -0,0 +1,199 @@
+//! Functions registered by the host application
+//!
+//! Domain calculations the language doesn't offer (`creditgrade(score)`) can
+//! be provided by the application embedding Lemma. A function has a fixed
+//! number of parameters, optionally with a type each, and is called with the
+//! evaluated argument values. Calls are checked against the registered
+//! functions when documents are loaded, argument types when they are called.
+
+use crate::parser::{LemmaParser, Rule};
+use crate::{
+    Expression, ExpressionKind, LemmaDoc, LemmaError, LemmaResult, LemmaType, LiteralValue,
+};
+use pest::Parser;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Implementation of a registered function
+///
+/// Receives one value per parameter and returns the result, or a message
+/// explaining why the arguments can't be handled.
+pub type FunctionImpl = Arc<dyn Fn(&[LiteralValue]) -> Result<LiteralValue, String> + Send + Sync>;
+
+/// A function callable from Lemma code
+#[derive(Clone)]
+pub struct CustomFunction {
+    pub name: String,
+    pub arity: usize,
+    /// Type of each parameter; `None` accepts values of any type
+    pub parameter_types: Option<Vec<LemmaType>>,
+    implementation: FunctionImpl,
+}
+
+impl CustomFunction {
+    /// Call the function, checking the argument types first
+    pub fn call(&self, arguments: &[LiteralValue]) -> LemmaResult<LiteralValue> {
+        if arguments.len() != self.arity {
+            return Err(LemmaError::Engine(format!(
+                "Function '{}' takes {} argument{}, got {}",
+                self.name,
+                self.arity,
+                if self.arity == 1 { "" } else { "s" },
+                arguments.len()
+            )));
+        }
+        if let Some(types) = &self.parameter_types {
+            for (index, (argument, expected)) in arguments.iter().zip(types).enumerate() {
+                let actual = argument.to_type();
+                if actual != *expected {
+                    return Err(LemmaError::Engine(format!(
+                        "Function '{}' expects {} as argument {}, got {}",
+                        self.name,
+                        expected,
+                        index + 1,
+                        actual
+                    )));
+                }
+            }
+        }
+        (self.implementation)(arguments).map_err(|message| {
+            LemmaError::Engine(format!("Function '{}' failed: {}", self.name, message))
+        })
+    }
+}
+
+impl fmt::Debug for CustomFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .field("parameter_types", &self.parameter_types)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Functions registered with an engine, by name
+#[derive(Debug, Clone, Default)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, CustomFunction>,
+}
+
+impl FunctionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a function, replacing an earlier one with the same name
+    ///
+    /// The name must be usable as a Lemma name: a letter followed by letters,
+    /// digits or underscores, and not a keyword such as `sqrt` or `round`.
+    pub fn register(
+        &mut self,
+        name: &str,
+        arity: usize,
+        parameter_types: Option<Vec<LemmaType>>,
+        implementation: FunctionImpl,
+    ) -> LemmaResult<()> {
+        let valid = LemmaParser::parse(Rule::function_name, name)
+            .map(|mut pairs| pairs.next().is_some_and(|pair| pair.as_str() == name))
+            .unwrap_or(false);
+        if !valid {
+            return Err(LemmaError::Engine(format!(
+                "Invalid function name '{}': use a letter followed by letters, digits or underscores, not a keyword",
+                name
+            )));
+        }
+
+        self.functions.insert(
+            name.to_string(),
+            CustomFunction {
+                name: name.to_string(),
+                arity,
+                parameter_types,
+                implementation,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&CustomFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+
+    /// Check every function call in `docs` names a registered function with
+    /// the right number of arguments
+    pub fn check_calls(&self, docs: &[LemmaDoc]) -> LemmaResult<()> {
+        for doc in docs {
+            for rule in &doc.rules {
+                let expressions = std::iter::once(&rule.expression).chain(
+                    rule.unless_clauses
+                        .iter()
+                        .flat_map(|clause| [&clause.condition, &clause.result]),
+                );
+                for expression in expressions {
+                    self.check_expression(expression).map_err(|message| {
+                        LemmaError::Engine(format!(
+                            "{} in rule '{}' of document '{}'",
+                            message, rule.name, doc.name
+                        ))
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_expression(&self, expr: &Expression) -> Result<(), String> {
+        match &expr.kind {
+            ExpressionKind::FunctionCall(name, arguments) => {
+                let function = self
+                    .get(name)
+                    .ok_or_else(|| format!("Unknown function '{}'", name))?;
+                if arguments.len() != function.arity {
+                    return Err(format!(
+                        "Function '{}' takes {} argument{}, got {}",
+                        name,
+                        function.arity,
+                        if function.arity == 1 { "" } else { "s" },
+                        arguments.len()
+                    ));
+                }
+                arguments
+                    .iter()
+                    .try_for_each(|argument| self.check_expression(argument))
+            }
+            ExpressionKind::LogicalAnd(left, right)
+            | ExpressionKind::LogicalOr(left, right)
+            | ExpressionKind::Arithmetic(left, _, right)
+            | ExpressionKind::Comparison(left, _, right) => {
+                self.check_expression(left)?;
+                self.check_expression(right)
+            }
+            ExpressionKind::UnitConversion(inner, _)
+            | ExpressionKind::LogicalNegation(inner, _)
+            | ExpressionKind::MathematicalOperator(_, inner) => self.check_expression(inner),
+            ExpressionKind::Range(range) => range
+                .operands()
+                .into_iter()
+                .try_for_each(|operand| self.check_expression(operand)),
+            ExpressionKind::MissingItems(items) | ExpressionKind::Selection(_, items) => items
+                .iter()
+                .try_for_each(|item| self.check_expression(item)),
+            ExpressionKind::Veto(veto) => veto
+                .arguments
+                .iter()
+                .try_for_each(|argument| self.check_expression(&argument.expression)),
+            ExpressionKind::Literal(_)
+            | ExpressionKind::FactReference(_)
+            | ExpressionKind::RuleReference(_)
+            | ExpressionKind::FactHasAnyValue(_)
+            | ExpressionKind::RuleHasValue(_)
+            | ExpressionKind::Aggregate(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! Random fact sets that meet, or miss, a rule target
+//!
+//! Inverting the rule gives, per solution, the domain each fact must lie in.
+//! Values are drawn from those domains, facts the rule needs that no domain
+//! constrains get a value of their declared type, and every fact set is
+//! evaluated to make sure it meets the target. For negative tests one fact
+//! per set is drawn from outside its domain instead, and only sets that miss
+//! the target are kept. Drawing is seeded, so the same options give the same
+//! fact sets.
+
+use crate::inversion::domain_ops::{lit_cmp, negate_domain};
+use crate::{
+    Bound, ComparisonOperator, Domain, Engine, FactReference, FactType, FactValue, ForeignFact,
+    LemmaError, LemmaFact, LemmaResult, LemmaType, LiteralValue, OperationResult, Target, TargetOp,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+/// Most fact sets a single call generates
+pub const MAX_GENERATED_INPUTS: usize = 10_000;
+
+/// Fact sets drawn per requested one before giving up
+const ATTEMPTS_PER_INPUT: usize = 20;
+
+/// Rounds of filling in facts the rule still needs
+const MAX_FILL_ROUNDS: usize = 64;
+
+/// Width of the range drawn from when a domain has only one bound
+const OPEN_RANGE_WIDTH: i64 = 100;
+
+/// How `Engine::generate_inputs_with_options` draws fact sets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GenerateOptions {
+    /// Seed of the random draws
+    pub seed: u64,
+    /// Generate fact sets that miss the target instead of meeting it
+    pub violating: bool,
+}
+
+impl GenerateOptions {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_violating(mut self, violating: bool) -> Self {
+        self.violating = violating;
+        self
+    }
+}
+
+/// Generate up to `count` fact sets for `rule_name`, see `Engine::generate_inputs`
+pub fn generate_inputs(
+    engine: &Engine,
+    doc_name: &str,
+    rule_name: &str,
+    target: Target,
+    count: usize,
+    options: &GenerateOptions,
+) -> LemmaResult<Vec<Vec<LemmaFact>>> {
+    if count > MAX_GENERATED_INPUTS {
+        return Err(LemmaError::Engine(format!(
+            "Cannot generate {} fact sets, at most {} are allowed",
+            count, MAX_GENERATED_INPUTS
+        )));
+    }
+    let doc = engine
+        .get_document(doc_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+    if !doc.rules.iter().any(|r| r.name == rule_name) {
+        return Err(LemmaError::Engine(format!(
+            "Rule '{}' not found in document '{}'",
+            rule_name, doc_name
+        )));
+    }
+    let documents = engine.get_all_documents();
+
+    let mut solutions: Vec<Vec<(FactReference, Domain)>> = engine
+        .invert(doc_name, rule_name, target.clone(), Default::default())?
+        .into_iter()
+        .map(|solution| {
+            let mut domains: Vec<_> = solution.into_iter().collect();
+            domains.sort_by(|a, b| a.0.reference.cmp(&b.0.reference));
+            domains
+        })
+        .collect();
+    if solutions.is_empty() {
+        if !options.violating {
+            return Err(LemmaError::Engine(format!(
+                "Rule '{}' can never meet the target",
+                rule_name
+            )));
+        }
+        // Nothing meets the target, so any fact set misses it
+        solutions.push(Vec::new());
+    }
+
+    // A value of the fact's declared type, money in the declaring document's currency
+    let any_of = |reference: &[String], rng: &mut SplitMix64| {
+        let declared_type = crate::analysis::declared_fact_type(doc, reference, documents)?;
+        let value = any_value(&declared_type, rng)?;
+        match crate::analysis::coerce_fact_value(doc, reference, &value, documents) {
+            Some(coerced) => Some(coerced),
+            None => match value {
+                LiteralValue::Number(amount) if declared_type == LemmaType::Money => {
+                    crate::parser::units::resolve_unit(amount, "EUR").ok()
+                }
+                value => Some(value),
+            },
+        }
+    };
+    let mut rng = SplitMix64(options.seed);
+    let mut inputs = Vec::new();
+    let mut attempts = 0;
+    while inputs.len() < count && attempts < count * ATTEMPTS_PER_INPUT {
+        attempts += 1;
+        let solution = &solutions[rng.below(solutions.len())];
+        let violated = match (options.violating, solution.len()) {
+            (true, len) if len > 0 => Some(rng.below(len)),
+            _ => None,
+        };
+
+        let mut facts = Vec::new();
+        let mut drawn = true;
+        for (index, (fact_ref, domain)) in solution.iter().enumerate() {
+            let domain = if violated == Some(index) {
+                negate_domain(domain.clone())
+            } else {
+                domain.clone()
+            };
+            let any = |rng: &mut SplitMix64| any_of(&fact_ref.reference, rng);
+            match draw(&domain, &any, &mut rng) {
+                Some(value) => facts.push(fact(&fact_ref.reference, value)),
+                None => drawn = false,
+            }
+        }
+        if !drawn {
+            continue;
+        }
+
+        // Facts the rule reaches that no domain constrains
+        for _ in 0..MAX_FILL_ROUNDS {
+            let missing = engine.next_missing_facts(
+                doc_name,
+                Some(vec![rule_name.to_string()]),
+                facts.clone(),
+            )?;
+            if missing.is_empty() {
+                break;
+            }
+            for path in missing {
+                let reference: Vec<String> = path.split('.').map(str::to_string).collect();
+                match any_of(&reference, &mut rng) {
+                    Some(value) => facts.push(fact(&reference, value)),
+                    None => drawn = false,
+                }
+            }
+            if !drawn {
+                break;
+            }
+        }
+        if !drawn {
+            continue;
+        }
+
+        let response = engine.evaluate(
+            doc_name,
+            Some(vec![rule_name.to_string()]),
+            Some(facts.clone()),
+        )?;
+        let outcome = response
+            .results
+            .into_iter()
+            .find(|r| r.rule_name == rule_name)
+            .and_then(|r| match (r.result, r.veto_message, r.missing_facts) {
+                (_, _, Some(_)) => None,
+                (Some(value), _, _) => Some(OperationResult::Value(value)),
+                (None, message, _) => Some(OperationResult::Veto(message)),
+            });
+        let Some(outcome) = outcome else {
+            continue;
+        };
+        if meets_target(&outcome, &target) != options.violating {
+            inputs.push(facts);
+        }
+    }
+
+    if inputs.len() < count {
+        return Err(LemmaError::Engine(format!(
+            "Could only generate {} of {} fact sets for rule '{}' in {} attempts",
+            inputs.len(),
+            count,
+            rule_name,
+            attempts
+        )));
+    }
+    Ok(inputs)
+}
+
+/// Whether a rule outcome meets a target, leaving out its rule targets
+fn meets_target(outcome: &OperationResult, target: &Target) -> bool {
+    match (&target.outcome, outcome) {
+        (None, OperationResult::Value(_)) => true,
+        (Some(OperationResult::Veto(None)), OperationResult::Veto(_)) => true,
+        (Some(OperationResult::Veto(Some(wanted))), OperationResult::Veto(Some(message))) => {
+            wanted == message
+        }
+        (Some(OperationResult::Value(wanted)), OperationResult::Value(value)) => {
+            let op = match target.op {
+                TargetOp::Eq => ComparisonOperator::Equal,
+                TargetOp::Neq => ComparisonOperator::NotEqual,
+                TargetOp::Lt => ComparisonOperator::LessThan,
+                TargetOp::Lte => ComparisonOperator::LessThanOrEqual,
+                TargetOp::Gt => ComparisonOperator::GreaterThan,
+                TargetOp::Gte => ComparisonOperator::GreaterThanOrEqual,
+            };
+            crate::evaluator::operations::comparison_operation(value, &op, wanted).unwrap_or(false)
+        }
+        _ => false,
+    }
+}
+
+fn fact(reference: &[String], value: LiteralValue) -> LemmaFact {
+    let fact_type = match reference {
+        [name] => FactType::Local(name.clone()),
+        _ => FactType::Foreign(ForeignFact {
+            reference: reference.to_vec(),
+        }),
+    };
+    LemmaFact::new(fact_type, FactValue::Literal(value))
+}
+
+/// Draws a value of a fact's type, for domains that don't tell
+type AnyValue<'a> = dyn Fn(&mut SplitMix64) -> Option<LiteralValue> + 'a;
+
+/// A random value within `domain`, None when none could be found
+fn draw(domain: &Domain, any: &AnyValue, rng: &mut SplitMix64) -> Option<LiteralValue> {
+    match domain {
+        Domain::Unconstrained => any(rng),
+        Domain::Enumeration(values) if !values.is_empty() => {
+            Some(values[rng.below(values.len())].clone())
+        }
+        Domain::Enumeration(_) => None,
+        Domain::Union(parts) if !parts.is_empty() => draw(&parts[rng.below(parts.len())], any, rng),
+        Domain::Union(_) => None,
+        Domain::Range { min, max } => draw_range(min, max, any, rng),
+        Domain::Complement(inner) => match inner.as_ref() {
+            Domain::Unconstrained => None,
+            Domain::Enumeration(excluded) => {
+                if let [LiteralValue::Boolean(b)] = excluded.as_slice() {
+                    return Some(LiteralValue::Boolean(!b));
+                }
+                (0..ATTEMPTS_PER_INPUT)
+                    .filter_map(|_| any(rng))
+                    .find(|value| excluded.iter().all(|e| lit_cmp(value, e) != 0))
+            }
+            other => draw(&negate_domain(other.clone()), any, rng),
+        },
+    }
+}
+
+/// A random value between two bounds, preferring whole numbers
+fn draw_range(
+    min: &Bound,
+    max: &Bound,
+    any: &AnyValue,
+    rng: &mut SplitMix64,
+) -> Option<LiteralValue> {
+    let bound_value = |bound: &Bound| match bound {
+        Bound::Inclusive(value) | Bound::Exclusive(value) => Some(value.clone()),
+        Bound::Unbounded => None,
+    };
+    let Some(template) = bound_value(min).or_else(|| bound_value(max)) else {
+        return any(rng);
+    };
+    let rebuild = |value: Decimal| match &template {
+        LiteralValue::Number(_) => Some(LiteralValue::Number(value)),
+        LiteralValue::Percentage(_) => Some(LiteralValue::Percentage(value)),
+        LiteralValue::Unit(unit) => Some(LiteralValue::Unit(unit.with_value(value))),
+        _ => None,
+    };
+    let magnitude = |value: Option<LiteralValue>| match value? {
+        LiteralValue::Number(n) | LiteralValue::Percentage(n) => Some(n),
+        LiteralValue::Unit(unit)
+            if rebuild(unit.value()) == Some(LiteralValue::Unit(unit.clone())) =>
+        {
+            Some(unit.value())
+        }
+        _ => None,
+    };
+
+    if rebuild(Decimal::ZERO).is_none() {
+        // Dates and other bounds that can't be stepped through: use an included bound
+        return match (min, max) {
+            (Bound::Inclusive(value), _) | (_, Bound::Inclusive(value)) => Some(value.clone()),
+            _ => None,
+        };
+    }
+    let width = Decimal::from(OPEN_RANGE_WIDTH);
+    let (low, high) = match (magnitude(bound_value(min)), magnitude(bound_value(max))) {
+        (Some(low), Some(high)) => (low, high),
+        (Some(low), None) => (low, low + width.max(low.abs())),
+        (None, Some(high)) => (high - width.max(high.abs()), high),
+        (None, None) => return None,
+    };
+    if low > high {
+        return None;
+    }
+
+    let within = |value: &LiteralValue| crate::inversion::domain_ops::value_within(value, min, max);
+    let (first, last) = (low.ceil().to_i64()?, high.floor().to_i64()?);
+    for _ in 0..ATTEMPTS_PER_INPUT {
+        if first > last {
+            break;
+        }
+        let span = (last - first) as u64 + 1;
+        let whole = Decimal::from(first) + Decimal::from(rng.next() % span);
+        let value = rebuild(whole)?;
+        if within(&value) {
+            return Some(value);
+        }
+    }
+    // No whole number inside: the middle of the range
+    let middle = rebuild(((low + high) / Decimal::from(2)).normalize())?;
+    within(&middle).then_some(middle)
+}
+
+/// A random value of a declared type, None for regexes
+///
+/// Money is a plain number, to be given the document's currency.
+fn any_value(declared_type: &LemmaType, rng: &mut SplitMix64) -> Option<LiteralValue> {
+    let amount = Decimal::from(rng.below(1001));
+    let value = match declared_type {
+        LemmaType::Number | LemmaType::Money => LiteralValue::Number(amount),
+        LemmaType::Percentage => LiteralValue::Percentage(Decimal::from(rng.below(101))),
+        LemmaType::Boolean => LiteralValue::Boolean(rng.below(2) == 1),
+        LemmaType::Text => LiteralValue::Text(format!("text {}", rng.below(1000))),
+        LemmaType::Date => crate::parse_literal(&format!(
+            "{}-{:02}-{:02}",
+            2000 + rng.below(30),
+            1 + rng.below(12),
+            1 + rng.below(28)
+        ))
+        .ok()?,
+        LemmaType::Regex => return None,
+        unit_type => {
+            let units = crate::parser::units::unit_names(unit_type);
+            crate::parser::units::resolve_unit(amount, units.first()?).ok()?
+        }
+    };
+    Some(value)
+}
+
+/// Small seeded generator, so generated fact sets can be reproduced
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 up to, but not including, `n`
+    fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next() % n as u64) as usize
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! Proving invariants with inversion
+//!
+//! An invariant holds when no facts make its rule false. Inverting the rule
+//! for `false` finds the facts that would: when no branch of the rule can be
+//! false the invariant is proven, otherwise the domains of those facts are
+//! returned. Conditions inversion can't reason about leave the proof unknown,
+//! and the invariant is still checked each time its document is evaluated.
+
+use crate::inversion::domain_extraction::shape_to_domains;
+use crate::inversion::inverter;
+use crate::{
+    Domain, ExpressionKind, FactReference, LemmaDoc, LemmaError, LemmaInvariant, LiteralValue,
+    Target,
+};
+use std::collections::HashMap;
+
+/// The outcome of trying to prove one invariant
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvariantProof {
+    pub doc_name: String,
+    /// The invariant's condition, e.g. `pricing.total? >= 0 EUR`
+    pub invariant: String,
+    /// The rule holding the condition, e.g. `invariant.1`
+    pub rule_name: String,
+    pub outcome: ProofOutcome,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProofOutcome {
+    /// No facts make the invariant false
+    Proven,
+    /// Facts in these domains may make it false, one map per way it can fail
+    Violable(Vec<HashMap<FactReference, Domain>>),
+    /// Inversion couldn't decide, with the reason
+    Unknown(String),
+}
+
+/// Try to prove every invariant of `doc`
+pub(crate) fn prove(doc: &LemmaDoc, documents: &HashMap<String, LemmaDoc>) -> Vec<InvariantProof> {
+    doc.invariants
+        .iter()
+        .map(|invariant| InvariantProof {
+            doc_name: doc.name.clone(),
+            invariant: invariant.condition.to_string(),
+            rule_name: invariant.rule_name(),
+            outcome: prove_invariant(doc, invariant, documents),
+        })
+        .collect()
+}
+
+fn prove_invariant(
+    doc: &LemmaDoc,
+    invariant: &LemmaInvariant,
+    documents: &HashMap<String, LemmaDoc>,
+) -> ProofOutcome {
+    let violated = Target::value(LiteralValue::Boolean(false));
+    let shape = match inverter::invert(
+        &doc.name,
+        &invariant.rule_name(),
+        violated,
+        HashMap::new(),
+        documents,
+    ) {
+        Ok(shape) => shape,
+        // No branch of the rule can give false
+        Err(LemmaError::Engine(message)) if message.starts_with("Cannot invert rule") => {
+            return ProofOutcome::Proven
+        }
+        Err(error) => return ProofOutcome::Unknown(error.to_string()),
+    };
+
+    let impossible = shape.branches.iter().all(|branch| {
+        matches!(
+            branch.condition.kind,
+            ExpressionKind::Literal(LiteralValue::Boolean(false))
+        )
+    });
+    if impossible {
+        return ProofOutcome::Proven;
+    }
+    match shape_to_domains(&shape) {
+        Ok(domains) => ProofOutcome::Violable(domains),
+        Err(error) => ProofOutcome::Unknown(error.to_string()),
+    }
+}
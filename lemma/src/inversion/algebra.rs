@@ -227,6 +227,15 @@ pub fn contains_unknown(
         | ExpressionKind::MathematicalOperator(_, inner) => {
             contains_unknown(inner, unknown, fact_matcher)
         }
+        ExpressionKind::FunctionCall(_, arguments) | ExpressionKind::Selection(_, arguments) => {
+            arguments
+                .iter()
+                .any(|argument| contains_unknown(argument, unknown, fact_matcher))
+        }
+        ExpressionKind::Range(range) => range
+            .operands()
+            .into_iter()
+            .any(|operand| contains_unknown(operand, unknown, fact_matcher)),
         _ => false,
     }
 }
@@ -74,7 +74,13 @@ fn to_bool_expr(
             let ibe = to_bool_expr(inner, atoms, expr_eq)?;
             Some(BExpr::not(ibe))
         }
-        EK::Comparison(_, _, _) | EK::FactHasAnyValue(_) => {
+        // Its two comparisons are separate atoms
+        EK::Range(range) => to_bool_expr(
+            &range.to_conjunction(expr.span.clone(), expr.id),
+            atoms,
+            expr_eq,
+        ),
+        EK::Comparison(_, _, _) | EK::FactHasAnyValue(_) | EK::RuleHasValue(_) => {
             let mut idx_opt = None;
             for (i, a) in atoms.iter().enumerate() {
                 if expr_eq(a, expr) {
@@ -97,7 +103,11 @@ fn to_bool_expr(
         | EK::MathematicalOperator(_, _)
         | EK::FactReference(_)
         | EK::RuleReference(_)
-        | EK::Veto(_) => None,
+        | EK::Veto(_)
+        | EK::Aggregate(_)
+        | EK::MissingItems(_)
+        | EK::FunctionCall(_, _)
+        | EK::Selection(_, _) => None,
     }
 }
 
@@ -28,6 +28,7 @@ pub fn shape_to_domains(shape: &Shape) -> LemmaResult<Vec<HashMap<FactReference,
         // Extract constraints for each free variable from the branch condition
         for var in &shape.free_variables {
             let domain = extract_domain_for_variable(&branch.condition, var)?
+                .map(|domain| text_enumeration(&domain).unwrap_or(domain))
                 .unwrap_or(Domain::Unconstrained);
             domains.insert(var.clone(), domain);
         }
@@ -70,6 +71,12 @@ fn extract_domain_for_variable(
             extract_comparison_constraint(lhs, op, rhs, var)
         }
 
+        // Range: the intersection of its two comparisons
+        ExpressionKind::Range(range) => extract_domain_for_variable(
+            &range.to_conjunction(condition.span.clone(), condition.id),
+            var,
+        ),
+
         // Logical AND: intersection of constraints
         ExpressionKind::LogicalAnd(lhs, rhs) => {
             let left_domain = extract_domain_for_variable(lhs, var)?;
@@ -93,6 +100,11 @@ fn extract_domain_for_variable(
             }
         }
 
+        // A boolean fact as the whole condition: `unless express then ...`
+        ExpressionKind::FactReference(fr) if fr.reference == var.reference => {
+            Ok(Some(Domain::Enumeration(vec![LiteralValue::Boolean(true)])))
+        }
+
         // Other expressions: can't extract constraints
         _ => Ok(None),
     }
@@ -147,7 +159,7 @@ fn comparison_to_domain(
         ComparisonOperator::Equal | ComparisonOperator::Is => {
             Ok(Domain::Enumeration(vec![value.clone()]))
         }
-        ComparisonOperator::NotEqual => {
+        ComparisonOperator::NotEqual | ComparisonOperator::IsNot => {
             Ok(Domain::Complement(Box::new(Domain::Enumeration(vec![
                 value.clone(),
             ]))))
@@ -168,10 +180,6 @@ fn comparison_to_domain(
             min: Bound::Inclusive(value.clone()),
             max: Bound::Unbounded,
         }),
-        _ => Err(LemmaError::Engine(format!(
-            "Unsupported comparison operator for domain extraction: {:?}",
-            effective_op
-        ))),
     }
 }
 
@@ -189,11 +197,16 @@ fn flip_operator(op: &ComparisonOperator) -> ComparisonOperator {
 }
 
 /// Intersect two optional domains
-/// Uses De Morgan's law: A ∩ B = ¬(¬A ∪ ¬B)
+/// Ranges intersect to a range, other domains use De Morgan's law: A ∩ B = ¬(¬A ∪ ¬B)
 fn intersect_domains(a: Option<Domain>, b: Option<Domain>) -> Option<Domain> {
     match (a, b) {
         (None, None) => None,
         (Some(d), None) | (None, Some(d)) => Some(d),
+        // `x between 18 and 65` is the closed interval [18, 65]
+        (Some(a @ Domain::Range { .. }), Some(b @ Domain::Range { .. })) => Some(
+            super::domain_ops::domain_intersection(a, b)
+                .unwrap_or_else(|| Domain::Enumeration(vec![])),
+        ),
         (Some(a), Some(b)) => {
             // Intersection: complement of union of complements
             Some(Domain::Complement(Box::new(Domain::Union(vec![
@@ -212,3 +225,97 @@ fn union_domains(a: Option<Domain>, b: Option<Domain>) -> Option<Domain> {
         (Some(a), Some(b)) => Some(Domain::Union(vec![a, b])),
     }
 }
+
+/// The allowed or disallowed values of a text fact, as a flat enumeration
+///
+/// A text fact compared only with `is` and `is not` against literals has a
+/// domain of nested unions and complements, e.g. `not (tier is "gold") and
+/// not (tier is "silver")`. It is the same as `{"gold", "silver"}` allowed or,
+/// here, disallowed, which forms can render as a dropdown. Returns None for
+/// domains with ranges or values other than text.
+fn text_enumeration(domain: &Domain) -> Option<Domain> {
+    let mut literals = Vec::new();
+    if !collect_literals(domain, &mut literals)
+        || literals.is_empty()
+        || !literals
+            .iter()
+            .all(|literal| matches!(literal, LiteralValue::Text(_)))
+    {
+        return None;
+    }
+
+    Some(match value_set(domain)? {
+        (values, false) => Domain::Enumeration(values),
+        (values, true) if values.is_empty() => Domain::Unconstrained,
+        (values, true) => Domain::Complement(Box::new(Domain::Enumeration(values))),
+    })
+}
+
+/// Collect the literals of an enumerable domain; false if it has ranges
+fn collect_literals<'a>(domain: &'a Domain, literals: &mut Vec<&'a LiteralValue>) -> bool {
+    match domain {
+        Domain::Enumeration(values) => {
+            literals.extend(values);
+            true
+        }
+        Domain::Complement(inner) => collect_literals(inner, literals),
+        Domain::Union(parts) => parts.iter().all(|part| collect_literals(part, literals)),
+        Domain::Unconstrained => true,
+        Domain::Range { .. } => false,
+    }
+}
+
+/// A domain as a set of values: `(values, false)` is exactly `values`,
+/// `(values, true)` every value except them
+fn value_set(domain: &Domain) -> Option<(Vec<LiteralValue>, bool)> {
+    match domain {
+        Domain::Enumeration(values) => {
+            let mut set = Vec::new();
+            for value in values {
+                if !set.contains(value) {
+                    set.push(value.clone());
+                }
+            }
+            Some((set, false))
+        }
+        Domain::Unconstrained => Some((Vec::new(), true)),
+        Domain::Complement(inner) => {
+            let (values, excluded) = value_set(inner)?;
+            Some((values, !excluded))
+        }
+        Domain::Union(parts) => {
+            parts
+                .iter()
+                .try_fold((Vec::new(), false), |(acc, acc_excluded), part| {
+                    let (values, excluded) = value_set(part)?;
+                    Some(match (acc_excluded, excluded) {
+                        // a ∪ b
+                        (false, false) => {
+                            let mut union = acc;
+                            for value in values {
+                                if !union.contains(&value) {
+                                    union.push(value);
+                                }
+                            }
+                            (union, false)
+                        }
+                        // everything but the excluded values not in the other set
+                        (false, true) => (
+                            values.into_iter().filter(|v| !acc.contains(v)).collect(),
+                            true,
+                        ),
+                        (true, false) => (
+                            acc.into_iter().filter(|v| !values.contains(v)).collect(),
+                            true,
+                        ),
+                        // excluded only when excluded from both
+                        (true, true) => (
+                            acc.into_iter().filter(|v| values.contains(v)).collect(),
+                            true,
+                        ),
+                    })
+                })
+        }
+        Domain::Range { .. } => None,
+    }
+}
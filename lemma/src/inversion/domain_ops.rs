@@ -1,18 +1,16 @@
 //! Domain operations for constraint solving
 
-use crate::evaluator::operations::comparison_operation;
+use crate::semantic::value_ops::{ordering, values_equal};
 use crate::{Bound, ComparisonOperator, Domain, LiteralValue};
 use std::cmp::Ordering;
 
+/// -1, 0 or 1 as `a` is below, equal to or above `b`; 1 when they can't be ordered
 pub fn lit_cmp(a: &LiteralValue, b: &LiteralValue) -> i8 {
-    use ComparisonOperator as Cmp;
-    if let Ok(true) = comparison_operation(a, &Cmp::LessThan, b) {
-        return -1;
-    }
-    if let Ok(true) = comparison_operation(a, &Cmp::Equal, b) {
-        return 0;
+    match ordering(a, b) {
+        Some(Ordering::Less) => -1,
+        Some(Ordering::Equal) => 0,
+        _ => 1,
     }
-    1
 }
 
 pub fn value_within(v: &LiteralValue, min: &Bound, max: &Bound) -> bool {
@@ -78,9 +76,10 @@ pub fn domain_from_comparison(
                 Some(make_range(Bound::Inclusive(val), Bound::Unbounded))
             }
         }
-        Cmp::Equal => Some(Domain::Enumeration(vec![val])),
-        Cmp::NotEqual => Some(Domain::Complement(Box::new(Domain::Enumeration(vec![val])))),
-        _ => None,
+        Cmp::Equal | Cmp::Is => Some(Domain::Enumeration(vec![val])),
+        Cmp::NotEqual | Cmp::IsNot => {
+            Some(Domain::Complement(Box::new(Domain::Enumeration(vec![val]))))
+        }
     }
 }
 
@@ -186,7 +185,7 @@ pub fn domain_intersection(a: Domain, b: Domain) -> Option<Domain> {
             }
         }
         (D::Enumeration(mut v1), D::Enumeration(v2)) => {
-            v1.retain(|x| v2.contains(x));
+            v1.retain(|x| v2.iter().any(|y| values_equal(x, y)));
             if v1.is_empty() {
                 None
             } else {
@@ -323,7 +322,7 @@ pub fn normalize_domain(d: Domain) -> Domain {
                         0 => Ordering::Equal,
                         _ => Ordering::Greater,
                     });
-                    values.dedup();
+                    values.dedup_by(|a, b| values_equal(a, b));
                 }
             }
 
@@ -343,7 +342,7 @@ pub fn normalize_domain(d: Domain) -> Domain {
                 0 => Ordering::Equal,
                 _ => Ordering::Greater,
             });
-            values.dedup();
+            values.dedup_by(|a, b| values_equal(a, b));
             D::Enumeration(values)
         }
         other => other,
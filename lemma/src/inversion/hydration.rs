@@ -80,10 +80,78 @@ pub fn substitute_fact_with_expr(
             expr.span.clone(),
             expr.id,
         ),
+        EK::Range(range) => Expression::new(
+            EK::Range(Box::new(crate::RangeCondition {
+                value: substitute_fact_with_expr(&range.value, fact_path, replacement),
+                low: substitute_fact_with_expr(&range.low, fact_path, replacement),
+                high: substitute_fact_with_expr(&range.high, fact_path, replacement),
+                ..(**range).clone()
+            })),
+            expr.span.clone(),
+            expr.id,
+        ),
         _ => expr.clone(),
     }
 }
 
+/// Qualify the fact and rule references of `expr` with `prefix`
+///
+/// Conditions from a referenced document name its facts as that document
+/// does; qualified with the path of the reference, e.g. `risk`, they name the
+/// same facts as seen from the referencing document (`risk.points`).
+pub fn qualify_references(expr: &Expression, prefix: &[String]) -> Expression {
+    use ExpressionKind as EK;
+    let qualify = |e: &Expression| Box::new(qualify_references(e, prefix));
+    let qualify_rule = |rule_ref: &crate::RuleReference| crate::RuleReference {
+        reference: prefix.iter().chain(&rule_ref.reference).cloned().collect(),
+    };
+    let kind = match &expr.kind {
+        EK::Literal(_) | EK::Veto(_) | EK::Aggregate(_) => return expr.clone(),
+        EK::FactReference(fr) => EK::FactReference(
+            crate::evaluator::expression::qualify_fact_reference(fr, prefix),
+        ),
+        EK::FactHasAnyValue(fr) => EK::FactHasAnyValue(
+            crate::evaluator::expression::qualify_fact_reference(fr, prefix),
+        ),
+        EK::RuleReference(rule_ref) => EK::RuleReference(qualify_rule(rule_ref)),
+        EK::RuleHasValue(rule_ref) => EK::RuleHasValue(qualify_rule(rule_ref)),
+        EK::MissingItems(items) => EK::MissingItems(
+            items
+                .iter()
+                .map(|item| qualify_references(item, prefix))
+                .collect(),
+        ),
+        EK::Arithmetic(l, op, r) => EK::Arithmetic(qualify(l), op.clone(), qualify(r)),
+        EK::Comparison(l, op, r) => EK::Comparison(qualify(l), op.clone(), qualify(r)),
+        EK::Range(range) => EK::Range(Box::new(crate::RangeCondition {
+            value: qualify_references(&range.value, prefix),
+            low: qualify_references(&range.low, prefix),
+            high: qualify_references(&range.high, prefix),
+            ..(**range).clone()
+        })),
+        EK::LogicalAnd(l, r) => EK::LogicalAnd(qualify(l), qualify(r)),
+        EK::LogicalOr(l, r) => EK::LogicalOr(qualify(l), qualify(r)),
+        EK::LogicalNegation(inner, nt) => EK::LogicalNegation(qualify(inner), nt.clone()),
+        EK::UnitConversion(inner, tgt) => EK::UnitConversion(qualify(inner), tgt.clone()),
+        EK::MathematicalOperator(op, inner) => EK::MathematicalOperator(op.clone(), qualify(inner)),
+        EK::FunctionCall(name, arguments) => EK::FunctionCall(
+            name.clone(),
+            arguments
+                .iter()
+                .map(|argument| qualify_references(argument, prefix))
+                .collect(),
+        ),
+        EK::Selection(function, candidates) => EK::Selection(
+            *function,
+            candidates
+                .iter()
+                .map(|candidate| qualify_references(candidate, prefix))
+                .collect(),
+        ),
+    };
+    Expression::new(kind, expr.span.clone(), expr.id)
+}
+
 /// Hydrate an expression by replacing fact references with their values
 ///
 /// This function:
@@ -103,7 +171,11 @@ where
 {
     use ExpressionKind as EK;
     match &expr.kind {
-        EK::Literal(_) | EK::Veto(_) => expr.clone(),
+        EK::Literal(_)
+        | EK::Veto(_)
+        | EK::Aggregate(_)
+        | EK::RuleHasValue(_)
+        | EK::MissingItems(_) => expr.clone(),
         EK::FactReference(fref) => {
             // Build keys to try: fully-qualified and local
             let local = fref.reference.join(".");
@@ -168,6 +240,14 @@ where
             expr.span.clone(),
             expr.id,
         ),
+        // Inversion reasons about the two comparisons of a range
+        EK::Range(range) => hydrate_expression(
+            &range.to_conjunction(expr.span.clone(), expr.id),
+            doc_name,
+            given,
+            get_rule,
+            is_simple,
+        ),
         EK::LogicalAnd(l, r) => Expression::new(
             EK::LogicalAnd(
                 Box::new(hydrate_expression(l, doc_name, given, get_rule, is_simple)),
@@ -214,6 +294,32 @@ where
             expr.span.clone(),
             expr.id,
         ),
+        EK::FunctionCall(name, arguments) => Expression::new(
+            EK::FunctionCall(
+                name.clone(),
+                arguments
+                    .iter()
+                    .map(|argument| {
+                        hydrate_expression(argument, doc_name, given, get_rule, is_simple)
+                    })
+                    .collect(),
+            ),
+            expr.span.clone(),
+            expr.id,
+        ),
+        EK::Selection(function, candidates) => Expression::new(
+            EK::Selection(
+                *function,
+                candidates
+                    .iter()
+                    .map(|candidate| {
+                        hydrate_expression(candidate, doc_name, given, get_rule, is_simple)
+                    })
+                    .collect(),
+            ),
+            expr.span.clone(),
+            expr.id,
+        ),
         EK::FactHasAnyValue(fref) => {
             // If a given fact is present, this reduces to true; otherwise keep symbolic
             let local = fref.reference.join(".");
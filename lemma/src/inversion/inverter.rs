@@ -1,6 +1,6 @@
 use crate::{
-    BranchOutcome, Expression, ExpressionId, ExpressionKind, LemmaError, LemmaResult, LiteralValue,
-    OperationResult, ShapeBranch, Target, TargetOp,
+    BranchOrigin, BranchOutcome, Expression, ExpressionId, ExpressionKind, LemmaError, LemmaResult,
+    LiteralValue, OperationResult, ShapeBranch, Target, TargetOp,
 };
 use std::collections::HashMap;
 
@@ -17,6 +17,7 @@ fn expressions_semantically_equal(a: &Expression, b: &Expression) -> bool {
         (EK::Literal(lit_a), EK::Literal(lit_b)) => lit_a == lit_b,
         (EK::FactReference(ref_a), EK::FactReference(ref_b)) => ref_a.reference == ref_b.reference,
         (EK::RuleReference(ref_a), EK::RuleReference(ref_b)) => ref_a.reference == ref_b.reference,
+        (EK::RuleHasValue(ref_a), EK::RuleHasValue(ref_b)) => ref_a.reference == ref_b.reference,
         (EK::Arithmetic(l1, op1, r1), EK::Arithmetic(l2, op2, r2)) => {
             op1 == op2
                 && expressions_semantically_equal(l1, l2)
@@ -31,6 +32,15 @@ fn expressions_semantically_equal(a: &Expression, b: &Expression) -> bool {
                 && expressions_semantically_equal(l1, l2)
                 && expressions_semantically_equal(r1, r2)
         }
+        (EK::Range(range1), EK::Range(range2)) => {
+            range1.low_operator == range2.low_operator
+                && range1.high_operator == range2.high_operator
+                && range1
+                    .operands()
+                    .into_iter()
+                    .zip(range2.operands())
+                    .all(|(a, b)| expressions_semantically_equal(a, b))
+        }
         (EK::LogicalNegation(e1, _), EK::LogicalNegation(e2, _)) => {
             expressions_semantically_equal(e1, e2)
         }
@@ -40,11 +50,28 @@ fn expressions_semantically_equal(a: &Expression, b: &Expression) -> bool {
         (EK::UnitConversion(e1, target1), EK::UnitConversion(e2, target2)) => {
             target1 == target2 && expressions_semantically_equal(e1, e2)
         }
+        (EK::FunctionCall(name1, args1), EK::FunctionCall(name2, args2)) => {
+            name1 == name2
+                && args1.len() == args2.len()
+                && args1
+                    .iter()
+                    .zip(args2)
+                    .all(|(a, b)| expressions_semantically_equal(a, b))
+        }
         (EK::Veto(v1), EK::Veto(v2)) => v1.message == v2.message,
         _ => false,
     }
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(doc = document, rule = rule, target = ?target, given = given_facts.len()),
+        err(level = "debug")
+    )
+)]
 pub fn invert(
     document: &str,
     rule: &str,
@@ -81,6 +108,7 @@ pub fn invert(
         .ok_or_else(|| LemmaError::Engine(format!("Rule not found: {}.{}", doc_name, rule)))?;
 
     let rule_path = format!("{}.{}", doc_name, rule);
+    let target = target.in_result_unit(rule, doc, documents)?;
 
     let literal_expr = |val: LiteralValue| {
         Expression::new(ExpressionKind::Literal(val), None, ExpressionId::new(0))
@@ -107,6 +135,17 @@ pub fn invert(
         )
     };
 
+    // Conditions under which the referenced rules meet their targets
+    let rule_target_condition = target
+        .rule_targets
+        .iter()
+        .map(|rule_target| {
+            rule_target_condition(rule_target, doc, &given_facts, documents, &logical_or)
+        })
+        .collect::<LemmaResult<Vec<_>>>()?
+        .into_iter()
+        .reduce(logical_and);
+
     // Build unified piecewise
     let mut all_branches: Vec<(Expression, Expression)> = Vec::new();
     all_branches.push((
@@ -117,11 +156,18 @@ pub fn invert(
         all_branches.push((br.condition.clone(), br.result.clone()));
     }
 
-    // Compute last-wins effective conditions
-    let mut suffix_or: Vec<Option<Expression>> = vec![None; all_branches.len()];
+    // Compute effective conditions: a branch applies only when no branch with
+    // higher priority matches. Index 0 (the default) always has the lowest priority.
+    let priority = rule
+        .unless_mode
+        .evaluation_order(rule.unless_clauses.len())
+        .into_iter()
+        .map(|clause| clause + 1)
+        .chain(std::iter::once(0));
+    let mut higher_or: Vec<Option<Expression>> = vec![None; all_branches.len()];
     let mut acc: Option<Expression> = None;
-    for i in (0..all_branches.len()).rev() {
-        suffix_or[i] = acc.clone();
+    for i in priority {
+        higher_or[i] = acc.clone();
         let cond = &all_branches[i].0;
         acc = Some(match acc {
             None => cond.clone(),
@@ -135,8 +181,11 @@ pub fn invert(
 
     for (idx, (raw_cond, raw_res)) in all_branches.iter().enumerate() {
         let mut eff_cond = raw_cond.clone();
-        if let Some(later_or) = &suffix_or[idx] {
-            eff_cond = logical_and(eff_cond, logical_not(later_or.clone()));
+        if let Some(higher) = &higher_or[idx] {
+            eff_cond = logical_and(eff_cond, logical_not(higher.clone()));
+        }
+        if let Some(constraint) = &rule_target_condition {
+            eff_cond = logical_and(eff_cond, constraint.clone());
         }
 
         let cond_h = crate::inversion::hydration::hydrate_and_simplify(
@@ -177,7 +226,7 @@ pub fn invert(
             available_outcomes.push(outcome_desc);
         }
 
-        if let Some(branch) = filter_branch(
+        if let Some(mut branch) = filter_branch(
             cond_h,
             outcome,
             &target,
@@ -190,30 +239,25 @@ pub fn invert(
             &logical_not,
             &logical_or,
         )? {
+            branch.origins.push(match idx {
+                0 => BranchOrigin {
+                    clause: None,
+                    span: rule.expression.span.clone(),
+                },
+                _ => BranchOrigin {
+                    clause: Some(idx - 1),
+                    span: rule.unless_clauses[idx - 1].span.clone(),
+                },
+            });
             branches_out.push(branch);
         }
     }
 
     if branches_out.is_empty() {
-        let target_desc = match &target.outcome {
-            None => "any value".to_owned(),
-            Some(OperationResult::Value(v)) => format!("value {}", v),
-            Some(OperationResult::Veto(Some(msg))) => format!("veto '{}'", msg),
-            Some(OperationResult::Veto(None)) => "any veto".to_owned(),
-        };
-
         let mut error_msg = format!(
-            "Cannot invert rule '{}' for target {} {}.\n",
+            "Cannot invert rule '{}' for target {}.\n",
             rule_path,
-            match target.op {
-                TargetOp::Eq => "=",
-                TargetOp::Neq => "≠",
-                TargetOp::Lt => "<",
-                TargetOp::Lte => "≤",
-                TargetOp::Gt => ">",
-                TargetOp::Gte => "≥",
-            },
-            target_desc
+            describe_target(&target)
         );
 
         if !available_outcomes.is_empty() {
@@ -229,7 +273,9 @@ pub fn invert(
     }
 
     // Handle single branch case
+    // (the shortcuts below keep only the solved equation, so they can't carry rule targets)
     if rule.unless_clauses.is_empty()
+        && target.rule_targets.is_empty()
         && branches_out.len() == 1
         && matches!(target.op, TargetOp::Eq)
     {
@@ -274,6 +320,7 @@ pub fn invert(
                                 vec![crate::ShapeBranch {
                                     condition: comparison,
                                     outcome: branches_out[0].outcome.clone(),
+                                    origins: branches_out[0].origins.clone(),
                                 }],
                                 Vec::new(),
                             ));
@@ -284,20 +331,13 @@ pub fn invert(
                 let mut free_vars = collect_free_vars_expr(expr_h, doc_name, &get_rule);
                 dedup_and_remove_given(&mut free_vars, doc_name, &given_facts);
 
-                let condition = Expression::new(
-                    crate::ExpressionKind::Comparison(
-                        Box::new(expr_h.clone()),
-                        crate::ComparisonOperator::Equal,
-                        Box::new(literal_expr(val.clone())),
-                    ),
-                    None,
-                    crate::ExpressionId::new(0),
-                );
+                let condition = build_value_target_guard(expr_h, &target, &literal_expr);
 
                 return Ok(crate::Shape::new(
                     vec![crate::ShapeBranch {
                         condition,
                         outcome: branches_out[0].outcome.clone(),
+                        origins: branches_out[0].origins.clone(),
                     }],
                     free_vars,
                 ));
@@ -307,7 +347,7 @@ pub fn invert(
 
     let unified_branches = unify_branches(branches_out, &try_fold, &logical_or);
 
-    if unified_branches.len() == 1 {
+    if unified_branches.len() == 1 && target.rule_targets.is_empty() {
         if let Some(OperationResult::Value(ref val)) = target.outcome {
             if matches!(target.op, TargetOp::Eq) {
                 if let BranchOutcome::Value(ref _expr) = unified_branches[0].outcome {
@@ -359,6 +399,7 @@ pub fn invert(
                             vec![crate::ShapeBranch {
                                 condition: eq_condition,
                                 outcome: unified_branches[0].outcome.clone(),
+                                origins: unified_branches[0].origins.clone(),
                             }],
                             free_vars_eq,
                         ));
@@ -371,9 +412,103 @@ pub fn invert(
     let mut free_vars = collect_free_vars_piecewise(&unified_branches, doc_name, &get_rule);
     dedup_and_remove_given(&mut free_vars, doc_name, &given_facts);
 
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        branches = unified_branches.len(),
+        free_variables = free_vars.len(),
+        "inverted"
+    );
     Ok(crate::Shape::new(unified_branches, free_vars))
 }
 
+/// Describe a target like `= value 100` or `≤ value 40` for error messages
+fn describe_target(target: &Target) -> String {
+    let outcome = match &target.outcome {
+        None => "any value".to_owned(),
+        Some(OperationResult::Value(v)) => format!("value {}", v),
+        Some(OperationResult::Veto(Some(msg))) => format!("veto '{}'", msg),
+        Some(OperationResult::Veto(None)) => "any veto".to_owned(),
+    };
+    let op = match target.op {
+        TargetOp::Eq => "=",
+        TargetOp::Neq => "≠",
+        TargetOp::Lt => "<",
+        TargetOp::Lte => "≤",
+        TargetOp::Gt => ">",
+        TargetOp::Gte => "≥",
+    };
+    format!("{} {}", op, outcome)
+}
+
+/// Condition on facts under which a referenced rule meets its target
+///
+/// The referenced rule is inverted on its own; the disjunction of the resulting
+/// branch conditions is the constraint added to every branch of the outer rule.
+fn rule_target_condition(
+    rule_target: &crate::RuleTarget,
+    doc: &crate::LemmaDoc,
+    given_facts: &HashMap<String, LiteralValue>,
+    documents: &HashMap<String, crate::LemmaDoc>,
+    logical_or: &impl Fn(Expression, Expression) -> Expression,
+) -> LemmaResult<Expression> {
+    let (target_doc, rule_name, prefix) = match rule_target.rule.as_slice() {
+        [rule_name] => (doc.name.clone(), rule_name.clone(), Vec::new()),
+        [reference, rule_name] => {
+            // A document reference fact, or a document name as in hydration
+            let target_doc =
+                doc.facts
+                    .iter()
+                    .find_map(|fact| match (&fact.fact_type, &fact.value) {
+                        (
+                            crate::FactType::Local(name),
+                            crate::FactValue::DocumentReference(target),
+                        ) if name == reference => Some(target.clone()),
+                        _ => None,
+                    })
+                    .unwrap_or_else(|| reference.clone());
+            (target_doc, rule_name.clone(), vec![reference.clone()])
+        }
+        _ => {
+            return Err(LemmaError::Engine(format!(
+                "Unsupported rule reference in rule target: {}?",
+                rule_target.rule.join(".")
+            )))
+        }
+    };
+
+    // Given facts of the referenced document, as it names them
+    let given_facts: HashMap<String, LiteralValue> = match prefix.first() {
+        None => given_facts.clone(),
+        Some(reference) => given_facts
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.strip_prefix(reference.as_str())?.strip_prefix('.')?;
+                Some((name.to_string(), value.clone()))
+            })
+            .collect(),
+    };
+    let shape = invert(
+        &target_doc,
+        &rule_name,
+        rule_target.target.clone(),
+        given_facts,
+        documents,
+    )?;
+
+    shape
+        .branches
+        .into_iter()
+        .map(|branch| crate::inversion::hydration::qualify_references(&branch.condition, &prefix))
+        .reduce(logical_or)
+        .ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "Rule target {}? {} cannot be met",
+                rule_target.rule.join("."),
+                describe_target(&rule_target.target)
+            ))
+        })
+}
+
 #[allow(clippy::too_many_arguments)]
 fn filter_branch<'a, F>(
     cond_h: Expression,
@@ -405,6 +540,7 @@ where
                 Ok(Some(ShapeBranch {
                     condition: cond_simpl,
                     outcome,
+                    origins: Vec::new(),
                 }))
             }
         }
@@ -492,6 +628,7 @@ where
                                 return Ok(Some(ShapeBranch {
                                     condition: conj_simpl,
                                     outcome,
+                                    origins: Vec::new(),
                                 }));
                             }
                         }
@@ -518,13 +655,15 @@ where
                 Ok(Some(ShapeBranch {
                     condition: conj_simpl,
                     outcome,
+                    origins: Vec::new(),
                 }))
             }
         }
         (BranchOutcome::Veto(msg), Some(OperationResult::Veto(query_msg))) => {
             let matches = match (query_msg, msg) {
                 (None, _) => true,
-                (Some(q), Some(m)) => q == m,
+                // Parameterized messages match any values filled in
+                (Some(q), Some(m)) => crate::message_matches(m, q),
                 _ => false,
             };
             if !matches {
@@ -541,6 +680,7 @@ where
                 Ok(Some(ShapeBranch {
                     condition: cond_simpl,
                     outcome,
+                    origins: Vec::new(),
                 }))
             }
         }
@@ -553,8 +693,12 @@ fn build_value_target_guard(
     target: &Target,
     literal_expr: &impl Fn(LiteralValue) -> Expression,
 ) -> Expression {
-    let rhs = match &target.outcome {
-        Some(OperationResult::Value(v)) => literal_expr(v.clone()),
+    let rhs = match (&target.outcome, &expr.kind) {
+        // A branch with a plain number meets a percentage target by its number of percent
+        (Some(OperationResult::Value(v)), ExpressionKind::Literal(branch)) => {
+            literal_expr(crate::inversion::target::percentage_like(v, branch))
+        }
+        (Some(OperationResult::Value(v)), _) => literal_expr(v.clone()),
         _ => unreachable!("build_value_target_guard called with non-value target"),
     };
     let op = match target.op {
@@ -565,6 +709,33 @@ fn build_value_target_guard(
         TargetOp::Gt => crate::ComparisonOperator::GreaterThan,
         TargetOp::Gte => crate::ComparisonOperator::GreaterThanOrEqual,
     };
+    // A condition meets a boolean target as itself or negated, so its comparisons
+    // still constrain the facts; rule references are expanded by the caller
+    if let ExpressionKind::Literal(LiteralValue::Boolean(wanted)) = &rhs.kind {
+        let is_condition = !matches!(
+            expr.kind,
+            ExpressionKind::Literal(_) | ExpressionKind::RuleReference(_)
+        );
+        let negated = match op {
+            crate::ComparisonOperator::Equal | crate::ComparisonOperator::Is => Some(!wanted),
+            crate::ComparisonOperator::NotEqual | crate::ComparisonOperator::IsNot => Some(*wanted),
+            _ => None,
+        };
+        match negated {
+            Some(false) if is_condition => return expr.clone(),
+            Some(true) if is_condition => {
+                return Expression::new(
+                    ExpressionKind::LogicalNegation(
+                        Box::new(expr.clone()),
+                        crate::NegationType::Not,
+                    ),
+                    None,
+                    ExpressionId::new(0),
+                )
+            }
+            _ => {}
+        }
+    }
     Expression::new(
         ExpressionKind::Comparison(Box::new(expr.clone()), op, Box::new(rhs)),
         None,
@@ -655,6 +826,10 @@ fn unify_branches(
         result.push(ShapeBranch {
             condition: unified_condition,
             outcome: branches[i].outcome.clone(),
+            origins: matching_indices
+                .iter()
+                .flat_map(|&idx| branches[idx].origins.iter().cloned())
+                .collect(),
         });
     }
 
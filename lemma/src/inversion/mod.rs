@@ -9,7 +9,9 @@ pub mod domain_ops;
 pub mod hydration;
 pub mod inverter;
 pub mod shape;
+pub mod summary;
 pub mod target;
 
-pub use shape::{Bound, BranchOutcome, Domain, Shape, ShapeBranch};
-pub use target::{Target, TargetOp};
+pub use shape::{Bound, BranchOrigin, BranchOutcome, Domain, Shape, ShapeBranch};
+pub use summary::{DomainSummary, Interval};
+pub use target::{RuleTarget, Target, TargetOp};
@@ -1,6 +1,6 @@
 //! Shape representation for inversion results
 
-use crate::{Expression, FactReference, LiteralValue};
+use crate::{ArithmeticOperation, Expression, ExpressionKind, FactReference, LiteralValue, Span};
 use serde::ser::{Serialize, SerializeMap, SerializeStruct, Serializer};
 use std::fmt;
 
@@ -8,6 +8,9 @@ use std::fmt;
 ///
 /// Contains one or more branches, each representing a solution.
 /// Each branch specifies conditions and the corresponding outcome.
+///
+/// In JSON, each branch has its `condition` as Lemma text and as a
+/// `condition_ast` tree, its `outcome`, and the `origins` it was derived from.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Shape {
     /// Solution branches - each branch is a valid solution
@@ -25,6 +28,20 @@ pub struct ShapeBranch {
 
     /// Outcome when condition is met (value expression or veto)
     pub outcome: BranchOutcome,
+
+    /// The parts of the rule this branch was derived from
+    ///
+    /// Branches with the same outcome are merged, so there can be several.
+    pub origins: Vec<BranchOrigin>,
+}
+
+/// Where in the rule a branch comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchOrigin {
+    /// Index of the unless clause, `None` for the rule's default expression
+    pub clause: Option<usize>,
+    /// Source location of the clause or default expression
+    pub span: Option<Span>,
 }
 
 /// Outcome of a piecewise branch
@@ -33,6 +50,9 @@ pub enum BranchOutcome {
     /// Produces a value defined by an expression
     Value(Expression),
     /// Produces a veto with an optional message
+    ///
+    /// The message is kept as written, so placeholders like `{weight}` show
+    /// which values it is parameterized by.
     Veto(Option<String>),
 }
 
@@ -195,27 +215,115 @@ impl Serialize for Shape {
     }
 }
 
-impl Serialize for FactReference {
+impl Serialize for ShapeBranch {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.reference.join("."))
+        let mut st = serializer.serialize_struct("shape_branch", 4)?;
+        st.serialize_field("condition", &lemma_text(&self.condition))?;
+        st.serialize_field("condition_ast", &self.condition)?;
+        st.serialize_field("outcome", &self.outcome)?;
+        st.serialize_field("origins", &self.origins)?;
+        st.end()
     }
 }
 
-impl Serialize for ShapeBranch {
+impl Serialize for BranchOrigin {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut st = serializer.serialize_struct("shape_branch", 2)?;
-        st.serialize_field("condition", &self.condition.to_string())?;
-        st.serialize_field("outcome", &self.outcome)?;
+        let mut st = serializer.serialize_struct("branch_origin", 2)?;
+        st.serialize_field("clause", &self.clause)?;
+        st.serialize_field("span", &self.span.as_ref().map(SourceSpan))?;
         st.end()
     }
 }
 
+/// A span as `{start, end, line, column}`
+struct SourceSpan<'a>(&'a Span);
+
+impl Serialize for SourceSpan<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut st = serializer.serialize_struct("span", 4)?;
+        st.serialize_field("start", &self.0.start)?;
+        st.serialize_field("end", &self.0.end)?;
+        st.serialize_field("line", &self.0.line)?;
+        st.serialize_field("column", &self.0.col)?;
+        st.end()
+    }
+}
+
+/// Binding strength of an expression, higher binds tighter
+fn precedence(expr: &Expression) -> u8 {
+    match &expr.kind {
+        // The last candidate takes whatever follows, so it is grouped wherever it appears
+        ExpressionKind::Selection(..) => 0,
+        ExpressionKind::LogicalOr(..) => 1,
+        ExpressionKind::LogicalAnd(..) => 2,
+        ExpressionKind::LogicalNegation(..) => 3,
+        ExpressionKind::Comparison(..) | ExpressionKind::Range(..) => 4,
+        ExpressionKind::UnitConversion(..) => 5,
+        ExpressionKind::Arithmetic(_, ArithmeticOperation::Add, _)
+        | ExpressionKind::Arithmetic(_, ArithmeticOperation::Subtract, _) => 6,
+        ExpressionKind::Arithmetic(_, ArithmeticOperation::Power, _) => 8,
+        ExpressionKind::Arithmetic(..) => 7,
+        _ => 9,
+    }
+}
+
+/// Lemma source for an expression, with the parentheses it needs to parse back
+///
+/// `Display` leaves grouping out, which reads well for simple conditions but
+/// changes the meaning of hydrated ones like `(a or b) and c`.
+fn lemma_text(expr: &Expression) -> String {
+    let operand = |child: &Expression, min: u8| {
+        if precedence(child) < min {
+            format!("({})", lemma_text(child))
+        } else {
+            lemma_text(child)
+        }
+    };
+    let level = precedence(expr);
+    match &expr.kind {
+        ExpressionKind::LogicalAnd(left, right) => {
+            format!("{} and {}", operand(left, level), operand(right, level))
+        }
+        ExpressionKind::LogicalOr(left, right) => {
+            format!("{} or {}", operand(left, level), operand(right, level))
+        }
+        ExpressionKind::Comparison(left, op, right) => {
+            format!(
+                "{} {} {}",
+                operand(left, level + 1),
+                op,
+                operand(right, level + 1)
+            )
+        }
+        ExpressionKind::Arithmetic(left, op, right) => {
+            format!(
+                "{} {} {}",
+                operand(left, level),
+                op,
+                operand(right, level + 1)
+            )
+        }
+        ExpressionKind::UnitConversion(value, target) => {
+            format!("{} in {}", operand(value, level + 1), target)
+        }
+        ExpressionKind::LogicalNegation(inner, _)
+            if !matches!(inner.kind, ExpressionKind::RuleHasValue(_)) =>
+        {
+            format!("not {}", operand(inner, 9))
+        }
+        _ => expr.to_string(),
+    }
+}
+
 impl Serialize for BranchOutcome {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -223,16 +331,27 @@ impl Serialize for BranchOutcome {
     {
         match self {
             BranchOutcome::Value(expr) => {
-                let mut st = serializer.serialize_map(Some(2))?;
+                let mut st = serializer.serialize_map(Some(3))?;
                 st.serialize_entry("type", "value")?;
-                st.serialize_entry("expression", &expr.to_string())?;
+                st.serialize_entry("expression", &lemma_text(expr))?;
+                st.serialize_entry("expression_ast", expr)?;
                 st.end()
             }
             BranchOutcome::Veto(msg) => {
-                let mut st = serializer.serialize_map(Some(2))?;
+                let mut st = serializer.serialize_map(Some(3))?;
                 st.serialize_entry("type", "veto")?;
                 if let Some(m) = msg {
                     st.serialize_entry("message", m)?;
+                    let parameters: Vec<&str> = crate::message_parts(m)
+                        .into_iter()
+                        .filter_map(|part| match part {
+                            crate::MessagePart::Placeholder(name) => Some(name),
+                            crate::MessagePart::Text(_) => None,
+                        })
+                        .collect();
+                    if !parameters.is_empty() {
+                        st.serialize_entry("parameters", &parameters)?;
+                    }
                 }
                 st.end()
             }
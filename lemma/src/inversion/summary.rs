@@ -0,0 +1,223 @@
+//! Per-fact summaries of the values that give a rule a value
+//!
+//! `shape_to_domains` returns one map of domains per branch, which is what a
+//! solver needs but not what a form does: a form validates one field at a
+//! time. `summarize` merges the branches into one normalized domain per fact
+//! and lists it as intervals, allowed values and excluded values. Facts that
+//! are compared with each other, like `start < end`, can't be checked on their
+//! own; their summaries name the facts they depend jointly on.
+
+use crate::analysis::extract_references;
+use crate::inversion::domain_extraction::shape_to_domains;
+use crate::inversion::domain_ops::{negate_domain, normalize_domain, value_within};
+use crate::inversion::{Bound, Domain, Shape};
+use crate::{Expression, ExpressionKind, FactReference, LemmaResult, LiteralValue};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// The values of one fact that give the rule a value
+///
+/// A value is valid when it lies in one of the `intervals` or is one of the
+/// `values`, and is not one of the `excluded` values. An unconstrained fact
+/// has the single interval (-∞, +∞).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainSummary {
+    /// The normalized union of the fact's domain in every branch
+    pub domain: Domain,
+    pub intervals: Vec<Interval>,
+    pub values: Vec<LiteralValue>,
+    pub excluded: Vec<LiteralValue>,
+    /// Facts compared or combined with this one, so no domain of this fact
+    /// alone says whether a value is valid
+    pub depends_jointly_on: Vec<FactReference>,
+}
+
+impl DomainSummary {
+    /// Whether every value of the fact is valid, as far as this fact alone goes
+    pub fn is_unconstrained(&self) -> bool {
+        self.domain == Domain::Unconstrained
+    }
+
+    /// Whether `value` is valid for this fact, ignoring joint constraints
+    pub fn allows(&self, value: &LiteralValue) -> bool {
+        (self.values.contains(value)
+            || self
+                .intervals
+                .iter()
+                .any(|interval| value_within(value, &interval.min, &interval.max)))
+            && !self.excluded.contains(value)
+    }
+}
+
+/// A range of values, written like `[18, 65)` or `(-∞, 100]`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Interval {
+    pub min: Bound,
+    pub max: Bound,
+}
+
+impl Interval {
+    /// (-∞, +∞)
+    pub fn everything() -> Self {
+        Self {
+            min: Bound::Unbounded,
+            max: Bound::Unbounded,
+        }
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = Domain::Range {
+            min: self.min.clone(),
+            max: self.max.clone(),
+        };
+        write!(f, "{}", range)
+    }
+}
+
+/// Summarize the domain of each free variable of `shape`
+pub fn summarize(shape: &Shape) -> LemmaResult<HashMap<FactReference, DomainSummary>> {
+    let solutions = shape_to_domains(shape)?;
+    let joint = joint_dependencies(shape);
+
+    let mut summaries = HashMap::new();
+    for var in &shape.free_variables {
+        let branch_domains: Vec<Domain> = solutions
+            .iter()
+            .map(|solution| solution.get(var).cloned().unwrap_or(Domain::Unconstrained))
+            .collect();
+        let domain = if branch_domains.contains(&Domain::Unconstrained) {
+            Domain::Unconstrained
+        } else {
+            simplify(Domain::Union(branch_domains))
+        };
+
+        let mut summary = DomainSummary {
+            domain: domain.clone(),
+            intervals: Vec::new(),
+            values: Vec::new(),
+            excluded: Vec::new(),
+            depends_jointly_on: joint.get(var).cloned().unwrap_or_default(),
+        };
+        let mut excluded = None;
+        describe(&domain, &mut summary, &mut excluded);
+        if let Some(excluded) = excluded {
+            // A value another part allows isn't excluded
+            summary.excluded = excluded
+                .into_iter()
+                .filter(|value| !summary.allows(value))
+                .collect();
+            summary.intervals = vec![Interval::everything()];
+            summary.values.clear();
+        }
+        summaries.insert(var.clone(), summary);
+    }
+    Ok(summaries)
+}
+
+/// Rewrite complements of ranges and double complements into plain domains
+fn simplify(domain: Domain) -> Domain {
+    match domain {
+        Domain::Union(parts) => {
+            normalize_domain(Domain::Union(parts.into_iter().map(simplify).collect()))
+        }
+        Domain::Complement(inner) => match simplify(*inner) {
+            Domain::Complement(twice) => *twice,
+            range @ Domain::Range { .. } => negate_domain(range),
+            Domain::Union(parts)
+                if parts
+                    .iter()
+                    .all(|part| matches!(part, Domain::Range { .. })) =>
+            {
+                normalize_domain(negate_domain(Domain::Union(parts)))
+            }
+            other => Domain::Complement(Box::new(other)),
+        },
+        other => other,
+    }
+}
+
+/// Split `domain` into the summary's intervals and values
+///
+/// `excluded` becomes Some when a part allows every value but some: it holds
+/// the values every such part leaves out. Complements that aren't of a set of
+/// values, like `not (a ∪ {5})`, leave out nothing here, so `domain` is the
+/// exact answer for them.
+fn describe(
+    domain: &Domain,
+    summary: &mut DomainSummary,
+    excluded: &mut Option<Vec<LiteralValue>>,
+) {
+    match domain {
+        Domain::Unconstrained => *excluded = Some(Vec::new()),
+        Domain::Range { min, max } => summary.intervals.push(Interval {
+            min: min.clone(),
+            max: max.clone(),
+        }),
+        Domain::Enumeration(values) => summary.values.extend(values.iter().cloned()),
+        Domain::Union(parts) => {
+            for part in parts {
+                describe(part, summary, excluded);
+            }
+        }
+        Domain::Complement(inner) => match inner.as_ref() {
+            Domain::Unconstrained => {}
+            Domain::Enumeration(values) => {
+                *excluded = Some(match excluded.take() {
+                    None => values.clone(),
+                    Some(previous) => previous
+                        .into_iter()
+                        .filter(|value| values.contains(value))
+                        .collect(),
+                });
+            }
+            _ => *excluded = Some(Vec::new()),
+        },
+    }
+}
+
+/// For each fact, the other free facts it shares a comparison or calculation with
+fn joint_dependencies(shape: &Shape) -> HashMap<FactReference, Vec<FactReference>> {
+    let mut joint: HashMap<FactReference, Vec<FactReference>> = HashMap::new();
+    for branch in &shape.branches {
+        let mut atoms = Vec::new();
+        collect_atoms(&branch.condition, &mut atoms);
+        for atom in atoms {
+            let mut facts: Vec<FactReference> = extract_references(atom)
+                .facts
+                .into_iter()
+                .filter(|fact| shape.free_variables.contains(fact))
+                .collect();
+            if facts.len() < 2 {
+                continue;
+            }
+            facts.sort_by(|a, b| a.reference.cmp(&b.reference));
+            for fact in &facts {
+                let others = joint.entry(fact.clone()).or_default();
+                for other in &facts {
+                    if other != fact && !others.contains(other) {
+                        others.push(other.clone());
+                    }
+                }
+            }
+        }
+    }
+    for others in joint.values_mut() {
+        others.sort_by(|a, b| a.reference.cmp(&b.reference));
+    }
+    joint
+}
+
+/// The conditions joined by `and`, `or` and `not` in `condition`
+fn collect_atoms<'a>(condition: &'a Expression, atoms: &mut Vec<&'a Expression>) {
+    match &condition.kind {
+        ExpressionKind::LogicalAnd(left, right) | ExpressionKind::LogicalOr(left, right) => {
+            collect_atoms(left, atoms);
+            collect_atoms(right, atoms);
+        }
+        ExpressionKind::LogicalNegation(inner, _) => collect_atoms(inner, atoms),
+        _ => atoms.push(condition),
+    }
+}
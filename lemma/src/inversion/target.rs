@@ -1,4 +1,13 @@
-use crate::{LiteralValue, OperationResult};
+use crate::evaluator::units::{convert_unit, convert_unit_for_arithmetic};
+use crate::{
+    ArithmeticOperation, ConversionTarget, Expression, ExpressionKind, FactType, FactValue,
+    LemmaDoc, LemmaError, LemmaResult, LemmaRule, LemmaType, LiteralValue, MathematicalOperator,
+    OperationResult, TypeAnnotation,
+};
+use std::collections::HashMap;
+
+/// How many rule references to follow when looking for a rule's result unit
+const MAX_UNIT_DEPTH: usize = 32;
 
 /// Desired outcome for an inversion query
 #[derive(Debug, Clone, PartialEq)]
@@ -9,6 +18,17 @@ pub struct Target {
     /// The desired outcome (value or veto)
     /// None means "any value" (wildcard for non-veto results)
     pub outcome: Option<OperationResult>,
+
+    /// Targets the rules referenced by the inverted rule must meet as well
+    pub rule_targets: Vec<RuleTarget>,
+}
+
+/// A target on an intermediate rule, e.g. `risk.score? <= 40`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleTarget {
+    /// Rule reference segments, e.g. `["risk", "score"]` or `["score"]`
+    pub rule: Vec<String>,
+    pub target: Target,
 }
 
 /// Comparison operators for targets
@@ -34,6 +54,7 @@ impl Target {
         Self {
             op: TargetOp::Eq,
             outcome: Some(OperationResult::Value(value)),
+            rule_targets: Vec::new(),
         }
     }
 
@@ -42,6 +63,7 @@ impl Target {
         Self {
             op: TargetOp::Eq,
             outcome: Some(OperationResult::Veto(message)),
+            rule_targets: Vec::new(),
         }
     }
 
@@ -55,6 +77,7 @@ impl Target {
         Self {
             op: TargetOp::Eq,
             outcome: None,
+            rule_targets: Vec::new(),
         }
     }
 
@@ -63,6 +86,198 @@ impl Target {
         Self {
             op,
             outcome: Some(outcome),
+            rule_targets: Vec::new(),
+        }
+    }
+
+    /// Also require a rule referenced by the inverted rule to meet `target`
+    ///
+    /// `rule` is a rule reference like `score`, `risk.score` or `risk.score?`.
+    /// The constraint is applied while hydrating, so the referenced rule's
+    /// logic doesn't have to be restated as given facts.
+    pub fn with_rule_target(mut self, rule: &str, target: Target) -> Self {
+        self.rule_targets.push(RuleTarget {
+            rule: rule
+                .trim()
+                .trim_end_matches('?')
+                .split('.')
+                .map(str::to_string)
+                .collect(),
+            target,
+        });
+        self
+    }
+
+    /// Express the target value in the unit of the rule's result
+    ///
+    /// `>= 220 pounds` on a rule computing kilograms becomes `>= 99.79 kilograms`,
+    /// and a plain number is read in the rule's unit (`10` on a percentage rule
+    /// is `10%`, see `percentage_like`). Values that can't be
+    /// compared with the result, like money for a mass, are rejected. When the
+    /// result unit can't be told from the document the target is left as is.
+    pub(crate) fn in_result_unit(
+        self,
+        rule: &LemmaRule,
+        doc: &LemmaDoc,
+        documents: &HashMap<String, LemmaDoc>,
+    ) -> LemmaResult<Self> {
+        let Some(OperationResult::Value(value)) = &self.outcome else {
+            return Ok(self);
+        };
+        let Some(unit) = rule_result_unit(rule, doc, documents, 0) else {
+            return Ok(self);
+        };
+        let mismatch = || {
+            LemmaError::Engine(format!(
+                "Target {} cannot be compared with rule '{}', which is in {}",
+                value, rule.name, unit
+            ))
+        };
+
+        let converted = match (value, &unit) {
+            (LiteralValue::Number(n), ConversionTarget::Percentage) => LiteralValue::Percentage(*n),
+            (LiteralValue::Number(_), _) => convert_unit(value, &unit)?,
+            (LiteralValue::Percentage(_), ConversionTarget::Percentage) => value.clone(),
+            (LiteralValue::Unit(_), ConversionTarget::Percentage)
+            | (LiteralValue::Percentage(_), _) => return Err(mismatch()),
+            (LiteralValue::Unit(_), _) => {
+                convert_unit_for_arithmetic(value, &unit).map_err(|_| mismatch())?
+            }
+            _ => return Ok(self),
+        };
+
+        Ok(Self {
+            outcome: Some(OperationResult::Value(converted)),
+            ..self
+        })
+    }
+}
+
+/// The unit of a rule's result, taken from the first branch that tells
+fn rule_result_unit(
+    rule: &LemmaRule,
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    depth: usize,
+) -> Option<ConversionTarget> {
+    if depth > MAX_UNIT_DEPTH {
+        return None;
+    }
+    std::iter::once(&rule.expression)
+        .chain(rule.unless_clauses.iter().map(|clause| &clause.result))
+        .find_map(|expr| expression_unit(expr, doc, documents, depth))
+}
+
+/// The unit an expression evaluates to, `None` for plain numbers and when unknown
+fn expression_unit(
+    expr: &Expression,
+    doc: &LemmaDoc,
+    documents: &HashMap<String, LemmaDoc>,
+    depth: usize,
+) -> Option<ConversionTarget> {
+    let unit_of = |e: &Expression| expression_unit(e, doc, documents, depth);
+    match &expr.kind {
+        ExpressionKind::Literal(value) => literal_unit(value),
+        ExpressionKind::FactReference(fact_ref) => {
+            let (doc, name) = resolve_reference(doc, &fact_ref.reference, documents)?;
+            doc.facts
+                .iter()
+                .find_map(|fact| match (&fact.fact_type, &fact.value) {
+                    (FactType::Local(n), FactValue::Literal(value)) if n == name => {
+                        Some(literal_unit(value))
+                    }
+                    (
+                        FactType::Local(n),
+                        FactValue::TypeAnnotation(TypeAnnotation::LemmaType(LemmaType::Percentage)),
+                    ) if n == name => Some(Some(ConversionTarget::Percentage)),
+                    _ => None,
+                })
+                .flatten()
+        }
+        ExpressionKind::RuleReference(rule_ref) => {
+            let (doc, name) = resolve_reference(doc, &rule_ref.reference, documents)?;
+            let rule = doc.rules.iter().find(|rule| rule.name == name)?;
+            rule_result_unit(rule, doc, documents, depth + 1)
+        }
+        ExpressionKind::Arithmetic(left, op, right) => {
+            let (left, right) = (unit_of(left), unit_of(right));
+            let is_percentage =
+                |u: &Option<ConversionTarget>| matches!(u, Some(ConversionTarget::Percentage));
+            match op {
+                ArithmeticOperation::Add | ArithmeticOperation::Subtract => {
+                    if is_percentage(&left) {
+                        right.or(left)
+                    } else {
+                        left.or(right)
+                    }
+                }
+                // A percentage scales the other side, two units make a ratio
+                ArithmeticOperation::Multiply => match (left, right) {
+                    (Some(ConversionTarget::Percentage), other)
+                    | (other, Some(ConversionTarget::Percentage)) => other,
+                    (Some(unit), None) | (None, Some(unit)) => Some(unit),
+                    _ => None,
+                },
+                ArithmeticOperation::Divide => match (left, right) {
+                    (Some(unit), None | Some(ConversionTarget::Percentage)) => Some(unit),
+                    _ => None,
+                },
+                ArithmeticOperation::Modulo => left,
+                ArithmeticOperation::Power => None,
+            }
+        }
+        ExpressionKind::MathematicalOperator(
+            MathematicalOperator::Abs
+            | MathematicalOperator::Floor
+            | MathematicalOperator::Ceil
+            | MathematicalOperator::Round,
+            inner,
+        ) => unit_of(inner),
+        _ => None,
+    }
+}
+
+/// `value` as a percentage or plain number, whichever `like` is
+///
+/// Inversion compares percentages and plain numbers by their number of
+/// percent, so `10%` and `10` are the same target, as `in_result_unit` reads a
+/// plain number on a percentage rule. Other values are returned as they are.
+pub(crate) fn percentage_like(value: &LiteralValue, like: &LiteralValue) -> LiteralValue {
+    match (value, like) {
+        (LiteralValue::Percentage(n), LiteralValue::Number(_)) => LiteralValue::Number(*n),
+        (LiteralValue::Number(n), LiteralValue::Percentage(_)) => LiteralValue::Percentage(*n),
+        _ => value.clone(),
+    }
+}
+
+fn literal_unit(value: &LiteralValue) -> Option<ConversionTarget> {
+    match value {
+        LiteralValue::Unit(unit) => Some(unit.conversion_target()),
+        LiteralValue::Percentage(_) => Some(ConversionTarget::Percentage),
+        _ => None,
+    }
+}
+
+/// The document and name a fact or rule reference points at
+fn resolve_reference<'a>(
+    doc: &'a LemmaDoc,
+    reference: &'a [String],
+    documents: &'a HashMap<String, LemmaDoc>,
+) -> Option<(&'a LemmaDoc, &'a str)> {
+    match reference {
+        [name] => Some((doc, name)),
+        [fact, name] => {
+            let target = doc
+                .facts
+                .iter()
+                .find_map(|f| match (&f.fact_type, &f.value) {
+                    (FactType::Local(n), FactValue::DocumentReference(target)) if n == fact => {
+                        Some(target)
+                    }
+                    _ => None,
+                })?;
+            Some((documents.get(target)?, name))
         }
+        _ => None,
     }
 }
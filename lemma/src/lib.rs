@@ -48,34 +48,94 @@
 
 pub mod analysis;
 pub mod ast;
+pub mod ast_export;
+pub mod builder;
+pub mod compatibility;
+pub mod diff;
 pub mod engine;
 pub mod error;
+pub mod evaluation_options;
 pub mod evaluator;
+pub mod fact_provider;
+pub mod functions;
+pub mod generate;
+pub mod invariants;
 pub mod inversion;
+pub mod loader;
+pub mod localization;
+pub mod memory;
+pub mod numeric_policy;
 pub mod operation_result;
+pub mod otel;
 pub mod parser;
+#[cfg(feature = "playground")]
+pub mod playground;
+pub mod rename;
 pub mod resource_limits;
 pub mod response;
+pub mod scaffold;
+pub mod schema;
 pub mod semantic;
+pub mod sensitivity;
 pub mod serializers;
+pub mod strict_units;
+pub mod sweep;
+pub mod table;
+pub mod templates;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
+pub mod transpile;
+pub mod typed_value;
 pub mod validator;
+pub mod version;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod workspace;
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
 pub use ast::{ExpressionId, ExpressionIdGenerator, Span};
-pub use engine::Engine;
+pub use compatibility::{Bump, CompatibilityReport};
+pub use diff::{diff_workspaces, WorkspaceDiff};
+pub use engine::{Engine, LoadDelta};
 /// Temporary alias to align with the Inversion plan's unified naming.
 /// Workspace is functionally identical to Engine and will eventually replace it.
 pub type Workspace = Engine;
-pub use error::LemmaError;
-pub use inversion::{Bound, BranchOutcome, Domain, Shape, ShapeBranch, Target, TargetOp};
+pub use error::{FactParseError, LemmaError};
+pub use evaluation_options::{EvaluationOptions, TraceLevel, UnusedOverrides};
+pub use evaluator::timeout::CancellationToken;
+pub use fact_provider::{FactProvider, FactRequest};
+pub use functions::{CustomFunction, FunctionRegistry};
+pub use generate::GenerateOptions;
+pub use invariants::{InvariantProof, ProofOutcome};
+pub use inversion::{
+    Bound, BranchOrigin, BranchOutcome, Domain, DomainSummary, Interval, RuleTarget, Shape,
+    ShapeBranch, Target, TargetOp,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use loader::DirectoryLoader;
+pub use loader::{DocumentLoader, LoadedCode};
+pub use memory::{DocumentMemory, MemoryStats, SourceMemory};
+pub use numeric_policy::{NumericPolicy, Overflow, Rounding};
 pub use operation_result::OperationResult;
-pub use parser::{parse, parse_facts};
+pub use parser::dates::DateFormat;
+pub use parser::{parse, parse_facts, parse_facts_with_date_format, parse_literal};
+pub use rename::SourceEdit;
 pub use resource_limits::ResourceLimits;
-pub use response::{OperationRecord, Response, RuleResult};
+pub use response::{
+    Diagnostic, DiagnosticKind, InputValidation, LimitExceeded, OperationRecord, Response,
+    RuleResult, ShadowDiff, ShadowResponse, TriggeredVeto,
+};
+pub use scaffold::scaffold_document;
 pub use semantic::*;
+pub use sensitivity::{FactSensitivity, SensitivityMethod, SensitivityReport};
+pub use sweep::{Sweep, SweepPoint};
+pub use table::FactTable;
+pub use transpile::SqlDialect;
+pub use typed_value::TypedValue;
 pub use validator::{ValidatedDocuments, Validator};
+#[cfg(not(target_arch = "wasm32"))]
+pub use workspace::LoadProgress;
 
 /// Result type for Lemma operations
 pub type LemmaResult<T> = Result<T, LemmaError>;
@@ -0,0 +1,95 @@
+//! Loading referenced documents on demand
+//!
+//! Large rule libraries don't have to be parsed up front. An engine with a
+//! `DocumentLoader` asks it for every document that loaded code references
+//! but that isn't loaded yet, and for the documents those reference in turn:
+//!
+//! ```no_run
+//! use lemma::{DirectoryLoader, Engine};
+//!
+//! let mut engine = Engine::new().with_document_loader(DirectoryLoader::new("rules"));
+//! engine.add_lemma_code("doc order\nfact pricing = doc pricing/standard", "order.lemma").unwrap();
+//! // rules/pricing/standard.lemma was loaded for the reference
+//! ```
+//!
+//! `Engine::load_document` loads a document by name the same way, e.g.
+//! before evaluating it. Loaders may fetch from anywhere: a directory, an
+//! HTTP registry or a database.
+
+use crate::{FactValue, LemmaDoc, LemmaResult};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+/// Code fetched by a `DocumentLoader`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedCode {
+    /// Name the code is loaded under, like the `source` of `Engine::add_lemma_code`
+    pub source: String,
+    /// Lemma code declaring the requested document, and possibly others
+    pub code: String,
+}
+
+/// Fetches the code of documents the engine doesn't have yet
+pub trait DocumentLoader: Send + Sync {
+    /// The code declaring `doc_name`, or `None` when the loader doesn't know it
+    ///
+    /// Errors are for failures such as an unreachable registry; an unknown
+    /// document is reported by validation as a missing reference.
+    fn load(&self, doc_name: &str) -> LemmaResult<Option<LoadedCode>>;
+}
+
+/// Loads document `a/b` from the file `a/b.lemma` below a directory
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone)]
+pub struct DirectoryLoader {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DirectoryLoader {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Path of the file expected to declare `doc_name`
+    pub fn path(&self, doc_name: &str) -> PathBuf {
+        self.dir.join(format!("{}.lemma", doc_name))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DocumentLoader for DirectoryLoader {
+    fn load(&self, doc_name: &str) -> LemmaResult<Option<LoadedCode>> {
+        // Names are paths below the directory, never out of it
+        if doc_name
+            .split('/')
+            .any(|segment| segment.is_empty() || segment == "..")
+        {
+            return Ok(None);
+        }
+        let path = self.path(doc_name);
+        match std::fs::read_to_string(&path) {
+            Ok(code) => Ok(Some(LoadedCode {
+                source: path.display().to_string(),
+                code,
+            })),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(crate::LemmaError::Engine(format!(
+                "Failed to read {}: {}",
+                path.display(),
+                error
+            ))),
+        }
+    }
+}
+
+/// Names of the documents `doc` references, templates by their own name
+pub(crate) fn referenced_documents(doc: &LemmaDoc) -> impl Iterator<Item = &str> {
+    doc.facts.iter().filter_map(|fact| match &fact.value {
+        FactValue::DocumentReference(name) | FactValue::DocumentCollection(name) => {
+            // `tariff("EU", EUR)` instantiates the template `tariff`
+            Some(name.split('(').next().unwrap_or(name).trim())
+        }
+        _ => None,
+    })
+}
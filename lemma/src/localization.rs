@@ -0,0 +1,84 @@
+//! Locale selection for veto messages
+//!
+//! Veto messages may carry translations (`veto "too heavy" @nl "te zwaar"`).
+//! This module picks the best matching translation for a requested locale and
+//! parses HTTP `Accept-Language` headers into an ordered list of locales.
+
+use crate::LocalizedMessage;
+
+/// Select the message that best matches `locale`
+///
+/// Matching is case-insensitive. An exact tag match wins (`nl-BE` → `nl-BE`),
+/// otherwise the primary language subtag is compared (`nl-BE` → `nl`, `nl` → `nl-NL`).
+/// Returns None when no translation matches, so callers can fall back to the
+/// default message.
+pub fn select<'a>(messages: &'a [LocalizedMessage], locale: &str) -> Option<&'a str> {
+    let requested = locale.trim();
+    if requested.is_empty() {
+        return None;
+    }
+
+    if let Some(exact) = messages
+        .iter()
+        .find(|m| m.locale.eq_ignore_ascii_case(requested))
+    {
+        return Some(&exact.message);
+    }
+
+    let language = primary_language(requested);
+    messages
+        .iter()
+        .find(|m| primary_language(&m.locale).eq_ignore_ascii_case(language))
+        .map(|m| m.message.as_str())
+}
+
+/// Select the message for the first of `locales` that has one
+///
+/// `locales` are ordered by preference, as `parse_accept_language` returns
+/// them, so `["fr", "nl"]` gives the Dutch message when there is no French one.
+pub fn select_preferred<'a>(
+    messages: &'a [LocalizedMessage],
+    locales: &[String],
+) -> Option<&'a str> {
+    locales.iter().find_map(|locale| select(messages, locale))
+}
+
+/// Parse an `Accept-Language` header into locales ordered by preference
+///
+/// Quality values are honored (`nl;q=0.8`), entries with `q=0` and the `*`
+/// wildcard are dropped. Entries with equal quality keep their header order.
+///
+/// # Examples
+/// ```text
+/// "nl-BE,nl;q=0.9,en;q=0.8" -> ["nl-BE", "nl", "en"]
+/// ```
+pub fn parse_accept_language(header: &str) -> Vec<String> {
+    let mut weighted: Vec<(String, f32)> = Vec::new();
+
+    for entry in header.split(',') {
+        let mut parts = entry.split(';');
+        let tag = parts.next().unwrap_or("").trim();
+        if tag.is_empty() || tag == "*" {
+            continue;
+        }
+
+        let mut quality = 1.0_f32;
+        for param in parts {
+            if let Some(q) = param.trim().strip_prefix("q=") {
+                quality = q.trim().parse().unwrap_or(0.0);
+            }
+        }
+
+        if quality > 0.0 {
+            weighted.push((tag.to_string(), quality));
+        }
+    }
+
+    // Stable sort keeps header order for equal weights
+    weighted.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    weighted.into_iter().map(|(tag, _)| tag).collect()
+}
+
+fn primary_language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or(locale)
+}
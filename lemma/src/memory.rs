@@ -0,0 +1,139 @@
+//! What loaded documents and retained sources take in memory
+//!
+//! Sizes are estimates in bytes: the AST nodes of a document plus the names,
+//! texts and literal values they own. Allocator overhead and spare capacity
+//! are not counted, so the numbers are meant for comparing documents and
+//! watching growth, not for exact accounting.
+
+use crate::{Expression, ExpressionKind, FactType, FactValue, LemmaDoc, LemmaRule};
+use serde::Serialize;
+use std::mem::size_of;
+
+/// Memory taken by an engine's documents and sources, see `Engine::memory_stats`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MemoryStats {
+    /// Loaded documents, by name
+    pub documents: Vec<DocumentMemory>,
+    /// Retained source code, by source
+    pub sources: Vec<SourceMemory>,
+    pub ast_bytes: usize,
+    pub source_bytes: usize,
+    /// Sources dropped to stay within `Engine::with_retained_sources`
+    pub evicted_sources: Vec<String>,
+}
+
+/// Estimated size of one document's AST
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DocumentMemory {
+    pub name: String,
+    pub source: Option<String>,
+    pub ast_bytes: usize,
+}
+
+/// Size of the code retained for one source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceMemory {
+    pub source: String,
+    pub bytes: usize,
+}
+
+/// Estimated bytes a document's AST takes
+pub fn document_size(doc: &LemmaDoc) -> usize {
+    let texts = doc.name.len()
+        + doc.source.as_ref().map_or(0, String::len)
+        + doc.parameters.iter().map(String::len).sum::<usize>()
+        + doc.template.as_ref().map_or(0, String::len)
+        + doc.commentary.as_ref().map_or(0, String::len);
+    let facts: usize = doc
+        .facts
+        .iter()
+        .map(|fact| {
+            let name = match &fact.fact_type {
+                FactType::Local(name) => name.len(),
+                FactType::Foreign(foreign) => path_size(&foreign.reference),
+            };
+            let value = match &fact.value {
+                FactValue::Literal(value) => value.byte_size(),
+                FactValue::DocumentReference(doc) | FactValue::DocumentCollection(doc) => doc.len(),
+                FactValue::TypeAnnotation(_) => 0,
+            };
+            size_of::<crate::LemmaFact>() + name + value
+        })
+        .sum();
+    let rules: usize = doc.rules.iter().map(rule_size).sum();
+    let checklists: usize = doc
+        .checklists
+        .iter()
+        .map(|checklist| {
+            size_of::<crate::LemmaChecklist>()
+                + checklist.name.len()
+                + checklist.items.iter().map(expression_size).sum::<usize>()
+        })
+        .sum();
+    let invariants: usize = doc
+        .invariants
+        .iter()
+        .map(|invariant| size_of::<crate::LemmaInvariant>() + expression_size(&invariant.condition))
+        .sum();
+    let records: usize = doc
+        .records
+        .iter()
+        .map(|record| {
+            size_of::<crate::LemmaRecord>()
+                + record.name.len()
+                + record.fields.iter().map(String::len).sum::<usize>()
+        })
+        .sum();
+    size_of::<LemmaDoc>() + texts + facts + rules + checklists + invariants + records
+}
+
+fn rule_size(rule: &LemmaRule) -> usize {
+    let clauses: usize = rule
+        .unless_clauses
+        .iter()
+        .map(|clause| {
+            size_of::<crate::UnlessClause>()
+                + expression_size(&clause.condition)
+                + expression_size(&clause.result)
+        })
+        .sum();
+    size_of::<LemmaRule>()
+        + rule.name.len()
+        + rule.description.as_ref().map_or(0, String::len)
+        + expression_size(&rule.expression)
+        + clauses
+}
+
+fn expression_size(expression: &Expression) -> usize {
+    let owned = match &expression.kind {
+        ExpressionKind::Literal(value) => value.byte_size(),
+        ExpressionKind::FactReference(fact_ref) | ExpressionKind::FactHasAnyValue(fact_ref) => {
+            path_size(&fact_ref.reference)
+        }
+        ExpressionKind::RuleReference(rule_ref) | ExpressionKind::RuleHasValue(rule_ref) => {
+            path_size(&rule_ref.reference)
+        }
+        ExpressionKind::FunctionCall(name, _) => name.len(),
+        ExpressionKind::Veto(veto) => {
+            veto.message.as_ref().map_or(0, String::len)
+                + veto
+                    .translations
+                    .iter()
+                    .map(|t| t.locale.len() + t.message.len())
+                    .sum::<usize>()
+        }
+        _ => 0,
+    };
+    size_of::<Expression>()
+        + owned
+        + crate::rename::children(expression)
+            .into_iter()
+            .map(expression_size)
+            .sum::<usize>()
+}
+
+fn path_size(path: &[String]) -> usize {
+    path.iter()
+        .map(|segment| size_of::<String>() + segment.len())
+        .sum()
+}
@@ -0,0 +1,74 @@
+//! Precision and overflow of number arithmetic
+//!
+//! Numbers are decimals of at most 28 significant digits. By default
+//! division keeps as many digits as fit and arithmetic beyond the largest
+//! decimal (about 7.9 × 10^28) fails the rule. A `NumericPolicy`, set on
+//! `ResourceLimits::numeric`, changes both: finance may want division rounded
+//! to fewer digits, engineering may prefer results that saturate.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// How arithmetic on numbers rounds and overflows
+///
+/// Applies to numbers, the amounts of units and percentages alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct NumericPolicy {
+    /// Significant digits kept by division and `^`, None keeps all that fit
+    pub significant_digits: Option<u32>,
+    /// How results are rounded to `significant_digits`
+    pub rounding: Rounding,
+    /// What happens to results beyond the largest decimal
+    pub overflow: Overflow,
+}
+
+/// Rounding of results to the significant digits of a `NumericPolicy`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// Halfway rounds to the even digit: 2.5 is 2, 3.5 is 4
+    #[default]
+    HalfEven,
+    /// Halfway rounds away from zero: 2.5 is 3
+    HalfUp,
+    /// Digits are dropped: 2.9 is 2, -2.9 is -2
+    TowardZero,
+}
+
+/// What arithmetic beyond the largest decimal gives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// The rule fails with an error naming the operation
+    #[default]
+    Error,
+    /// The result is the largest or smallest decimal
+    Saturate,
+}
+
+impl NumericPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep `digits` significant digits in division and `^` results
+    pub fn with_significant_digits(mut self, digits: u32, rounding: Rounding) -> Self {
+        self.significant_digits = Some(digits);
+        self.rounding = rounding;
+        self
+    }
+
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// `value` rounded to the significant digits, if there is a limit
+    pub(crate) fn round(&self, value: Decimal) -> Decimal {
+        let strategy = match self.rounding {
+            Rounding::HalfEven => RoundingStrategy::MidpointNearestEven,
+            Rounding::HalfUp => RoundingStrategy::MidpointAwayFromZero,
+            Rounding::TowardZero => RoundingStrategy::ToZero,
+        };
+        self.significant_digits
+            .and_then(|digits| value.round_sf_with_strategy(digits, strategy))
+            .unwrap_or(value)
+    }
+}
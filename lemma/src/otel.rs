@@ -0,0 +1,385 @@
+//! Operation traces as OpenTelemetry spans
+//!
+//! Turns an evaluation's `OperationRecord`s into spans that tracing backends
+//! such as Jaeger, Tempo or Honeycomb can show next to the rest of a request:
+//!
+//! - one span for the evaluation (`evaluate pricing`), a child of the caller's
+//!   span when a `TraceContext` from a W3C `traceparent` header is given
+//! - one child span per rule result (`rule total`), with its value, veto or
+//!   the reason it has no value as attributes
+//! - one event per operation of the rule, named after the record
+//!   (`fact_used`, `operation_executed`, ...) with its fields as attributes
+//!
+//! Rules aren't timed one by one, so every span covers the whole evaluation.
+//! `export_request` wraps spans in the OTLP/HTTP JSON body collectors accept
+//! at `/v1/traces`.
+
+use crate::{OperationRecord, Response, RuleResult};
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The trace an evaluation belongs to, usually taken from the incoming request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// 32 lowercase hex digits
+    pub trace_id: String,
+    /// The caller's span, 16 lowercase hex digits; None starts a new trace
+    pub parent_span_id: Option<String>,
+    /// Whether the caller records this trace; unsampled traces shouldn't be exported
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// A new trace without a parent
+    pub fn new_trace() -> Self {
+        Self {
+            trace_id: format!("{:016x}{:016x}", random_id(), random_id()),
+            parent_span_id: None,
+            sampled: true,
+        }
+    }
+
+    /// Read a W3C `traceparent` header, e.g. `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+    ///
+    /// Returns None for malformed headers, which the W3C spec says to ignore.
+    pub fn from_traceparent(header: &str) -> Option<Self> {
+        let parts: Vec<&str> = header.trim().split('-').collect();
+        let [version, trace_id, parent_id, flags, ..] = parts.as_slice() else {
+            return None;
+        };
+        let is_hex = |s: &str, len: usize| {
+            s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        };
+        let is_zero = |s: &str| s.bytes().all(|b| b == b'0');
+        if !is_hex(version, 2)
+            || *version == "ff"
+            || (*version == "00" && parts.len() != 4)
+            || !is_hex(trace_id, 32)
+            || is_zero(trace_id)
+            || !is_hex(parent_id, 16)
+            || is_zero(parent_id)
+            || !is_hex(flags, 2)
+        {
+            return None;
+        }
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            parent_span_id: Some(parent_id.to_string()),
+            sampled: flags & 1 == 1,
+        })
+    }
+}
+
+/// A span of an evaluation, see `evaluation_spans`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtelSpan {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub name: String,
+    pub start_unix_nano: u64,
+    pub end_unix_nano: u64,
+    pub attributes: Vec<(String, AttributeValue)>,
+    pub events: Vec<OtelEvent>,
+    /// Set when the span failed, e.g. a rule cut short by a resource limit
+    pub error: Option<String>,
+}
+
+/// An operation of a rule, at the start of its span
+#[derive(Debug, Clone, PartialEq)]
+pub struct OtelEvent {
+    pub name: String,
+    pub time_unix_nano: u64,
+    pub attributes: Vec<(String, AttributeValue)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Text(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl From<&str> for AttributeValue {
+    fn from(text: &str) -> Self {
+        AttributeValue::Text(text.to_string())
+    }
+}
+
+impl From<String> for AttributeValue {
+    fn from(text: String) -> Self {
+        AttributeValue::Text(text)
+    }
+}
+
+impl From<usize> for AttributeValue {
+    fn from(n: usize) -> Self {
+        AttributeValue::Int(i64::try_from(n).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(b: bool) -> Self {
+        AttributeValue::Bool(b)
+    }
+}
+
+/// Spans of an evaluation that ran from `start_unix_nano` to `end_unix_nano`
+///
+/// The first span is the evaluation, the others its rules in result order.
+/// Rule spans have events only when the evaluation recorded a full trace.
+pub fn evaluation_spans(
+    response: &Response,
+    context: &TraceContext,
+    start_unix_nano: u64,
+    end_unix_nano: u64,
+) -> Vec<OtelSpan> {
+    let span = |span_id: String, parent_span_id: Option<String>, name: String| OtelSpan {
+        trace_id: context.trace_id.clone(),
+        span_id,
+        parent_span_id,
+        name,
+        start_unix_nano,
+        end_unix_nano,
+        attributes: Vec::new(),
+        events: Vec::new(),
+        error: None,
+    };
+
+    let evaluation_id = format!("{:016x}", random_id());
+    let mut evaluation = span(
+        evaluation_id.clone(),
+        context.parent_span_id.clone(),
+        format!("evaluate {}", response.doc_name),
+    );
+    evaluation.attributes.push((
+        "lemma.document".to_string(),
+        response.doc_name.as_str().into(),
+    ));
+    if let Some(version) = &response.doc_version {
+        evaluation.attributes.push((
+            "lemma.document.version".to_string(),
+            version.as_str().into(),
+        ));
+    }
+    if !response.diagnostics.is_empty() {
+        evaluation.attributes.push((
+            "lemma.diagnostics".to_string(),
+            response.diagnostics.len().into(),
+        ));
+    }
+    evaluation.error = response
+        .limit_exceeded
+        .as_ref()
+        .map(|limit| limit.message.clone());
+
+    let mut spans = vec![evaluation];
+    for result in &response.results {
+        let mut rule = span(
+            format!("{:016x}", random_id()),
+            Some(evaluation_id.clone()),
+            format!("rule {}", result.rule_name),
+        );
+        rule.attributes = rule_attributes(result);
+        rule.error = result
+            .limit_exceeded
+            .as_ref()
+            .map(|limit| format!("Resource limit '{}' exceeded", limit));
+        rule.events = result
+            .operations
+            .iter()
+            .map(|operation| operation_event(operation, start_unix_nano))
+            .collect();
+        spans.push(rule);
+    }
+    spans
+}
+
+fn rule_attributes(result: &RuleResult) -> Vec<(String, AttributeValue)> {
+    let mut attributes = vec![("lemma.rule".to_string(), result.rule_name.as_str().into())];
+    if let Some(value) = &result.result {
+        attributes.push(("lemma.result".to_string(), value.to_string().into()));
+    }
+    if let Some(message) = &result.veto_message {
+        attributes.push(("lemma.veto".to_string(), message.as_str().into()));
+    }
+    if let Some(reason) = &result.unknown {
+        attributes.push(("lemma.unknown".to_string(), reason.as_str().into()));
+    }
+    if let Some(missing) = &result.missing_facts {
+        attributes.push(("lemma.missing_facts".to_string(), missing.join(", ").into()));
+    }
+    attributes
+}
+
+fn operation_event(operation: &OperationRecord, time_unix_nano: u64) -> OtelEvent {
+    let list = |values: &[crate::LiteralValue]| {
+        let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+        AttributeValue::Text(values.join(", "))
+    };
+    let (name, attributes): (&str, Vec<(&str, AttributeValue)>) = match operation {
+        OperationRecord::FactUsed { name, value } => (
+            "fact_used",
+            vec![
+                ("lemma.fact", name.as_str().into()),
+                ("lemma.value", value.to_string().into()),
+            ],
+        ),
+        OperationRecord::RuleUsed { name, value } => (
+            "rule_used",
+            vec![
+                ("lemma.rule", name.as_str().into()),
+                ("lemma.value", value.to_string().into()),
+            ],
+        ),
+        OperationRecord::OperationExecuted {
+            operation,
+            inputs,
+            result,
+            unless_clause_index,
+        } => {
+            let mut attributes = vec![
+                ("lemma.operation", operation.as_str().into()),
+                ("lemma.inputs", list(inputs)),
+                ("lemma.result", result.to_string().into()),
+            ];
+            if let Some(index) = unless_clause_index {
+                attributes.push(("lemma.unless_clause", (*index).into()));
+            }
+            ("operation_executed", attributes)
+        }
+        OperationRecord::ShortCircuit {
+            operation,
+            value,
+            skipped_operands,
+        } => (
+            "short_circuit",
+            vec![
+                ("lemma.operation", operation.as_str().into()),
+                ("lemma.value", value.to_string().into()),
+                ("lemma.skipped_operands", (*skipped_operands).into()),
+            ],
+        ),
+        OperationRecord::CandidateSelected {
+            operation,
+            inputs,
+            winner,
+            index,
+            value,
+        } => (
+            "candidate_selected",
+            vec![
+                ("lemma.operation", operation.as_str().into()),
+                ("lemma.inputs", list(inputs)),
+                ("lemma.winner", winner.as_str().into()),
+                ("lemma.index", (*index).into()),
+                ("lemma.value", value.to_string().into()),
+            ],
+        ),
+        OperationRecord::UnlessClauseEvaluated {
+            index,
+            matched,
+            result_if_matched,
+        } => {
+            let mut attributes = vec![
+                ("lemma.unless_clause", (*index).into()),
+                ("lemma.matched", (*matched).into()),
+            ];
+            if let Some(result) = result_if_matched {
+                attributes.push(("lemma.result", result.to_string().into()));
+            }
+            ("unless_clause_evaluated", attributes)
+        }
+        OperationRecord::DefaultValue { value } => (
+            "default_value",
+            vec![("lemma.value", value.to_string().into())],
+        ),
+        OperationRecord::FinalResult { value } => (
+            "final_result",
+            vec![("lemma.value", value.to_string().into())],
+        ),
+    };
+    OtelEvent {
+        name: name.to_string(),
+        time_unix_nano,
+        attributes: attributes
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect(),
+    }
+}
+
+/// The OTLP/HTTP JSON body exporting `spans`, as sent to a collector's `/v1/traces`
+pub fn export_request(spans: &[OtelSpan], service_name: &str) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": attributes_json(&[(
+                    "service.name".to_string(),
+                    service_name.into(),
+                )]),
+            },
+            "scopeSpans": [{
+                "scope": {
+                    "name": "lemma",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "spans": spans.iter().map(span_json).collect::<Vec<_>>(),
+            }],
+        }],
+    })
+}
+
+fn span_json(span: &OtelSpan) -> Value {
+    // Status codes: 1 is ok, 2 is error
+    let status = match &span.error {
+        Some(message) => json!({ "code": 2, "message": message }),
+        None => json!({ "code": 1 }),
+    };
+    let mut json = json!({
+        "traceId": span.trace_id,
+        "spanId": span.span_id,
+        "name": span.name,
+        // Internal: the evaluation runs inside the caller's server span
+        "kind": 1,
+        "startTimeUnixNano": span.start_unix_nano.to_string(),
+        "endTimeUnixNano": span.end_unix_nano.to_string(),
+        "attributes": attributes_json(&span.attributes),
+        "events": span.events.iter().map(|event| json!({
+            "name": event.name,
+            "timeUnixNano": event.time_unix_nano.to_string(),
+            "attributes": attributes_json(&event.attributes),
+        })).collect::<Vec<_>>(),
+        "status": status,
+    });
+    if let Some(parent) = &span.parent_span_id {
+        json["parentSpanId"] = json!(parent);
+    }
+    json
+}
+
+fn attributes_json(attributes: &[(String, AttributeValue)]) -> Vec<Value> {
+    attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                AttributeValue::Text(text) => json!({ "stringValue": text }),
+                // OTLP JSON writes 64-bit integers as strings
+                AttributeValue::Int(n) => json!({ "intValue": n.to_string() }),
+                AttributeValue::Bool(b) => json!({ "boolValue": b }),
+            };
+            json!({ "key": key, "value": value })
+        })
+        .collect()
+}
+
+/// A random, nonzero 64-bit ID
+fn random_id() -> u64 {
+    static SEQUENCE: AtomicU64 = AtomicU64::new(1);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(SEQUENCE.fetch_add(1, Ordering::Relaxed));
+    hasher.finish().max(1)
+}
@@ -0,0 +1,167 @@
+//! Dates in fact overrides as other systems write them
+//!
+//! Lemma code only has ISO 8601 dates, but forms, spreadsheets and other
+//! integrations send `31-12-2025`, `12/31/2025` or `2025-12-31 14:30`. A
+//! `DateFormat` says which order of day and month such dates are in; dates
+//! that would read differently in the other order are rejected unless it is
+//! given.
+
+use crate::{DateGranularity, DateTimeValue};
+use std::fmt;
+use std::str::FromStr;
+
+/// How to read dates in fact overrides that aren't ISO 8601
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// Year first only: `2025-12-31`, `2025/12/31` or `2025-12-31 14:30`
+    #[default]
+    Iso,
+    /// Also day first, as in most of Europe: `31-12-2025`, `31/12/2025` or `31.12.2025`
+    DayMonthYear,
+    /// Also month first, as in the US: `12/31/2025`
+    MonthDayYear,
+}
+
+impl fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateFormat::Iso => write!(f, "iso"),
+            DateFormat::DayMonthYear => write!(f, "day-month-year"),
+            DateFormat::MonthDayYear => write!(f, "month-day-year"),
+        }
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "iso" => Ok(DateFormat::Iso),
+            "day-month-year" | "dmy" => Ok(DateFormat::DayMonthYear),
+            "month-day-year" | "mdy" => Ok(DateFormat::MonthDayYear),
+            _ => Err(format!(
+                "Unknown date format '{}', expected iso, day-month-year or month-day-year",
+                s
+            )),
+        }
+    }
+}
+
+/// Read `input` as a date written in `format`
+///
+/// Returns None for input that isn't a date in one of these forms, plain ISO
+/// dates included, which the grammar reads. Returns an error for input that
+/// is one of these forms but not a date `format` allows.
+pub fn parse_date(input: &str, format: DateFormat) -> Option<Result<DateTimeValue, String>> {
+    let input = input.trim();
+    let (date, time) = match input.split_once(' ') {
+        Some((date, time)) => (date, Some(parse_time(time.trim())?)),
+        None => (input, None),
+    };
+
+    let separator = date.chars().find(|c| matches!(c, '-' | '/' | '.'))?;
+    let parts: Vec<&str> = date.split(separator).collect();
+    if parts.len() != 3
+        || parts
+            .iter()
+            .any(|part| part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+
+    let (first, second, third) = (parts[0], parts[1], parts[2]);
+    let number = |part: &str| part.parse::<u32>().ok();
+    let (year, month, day) = if first.len() == 4 && second.len() <= 2 && third.len() <= 2 {
+        // The grammar reads `2025-12-31` itself
+        if separator == '-' && time.is_none() {
+            return None;
+        }
+        (first.parse().ok()?, number(second)?, number(third)?)
+    } else if first.len() <= 2 && second.len() <= 2 && third.len() == 2 {
+        return Some(Err(format!(
+            "Two-digit year in date '{}' is ambiguous, write the year in full",
+            input
+        )));
+    } else if first.len() <= 2 && second.len() <= 2 && third.len() == 4 {
+        let year = third.parse().ok()?;
+        let (first, second) = (number(first)?, number(second)?);
+        match format {
+            DateFormat::DayMonthYear => (year, second, first),
+            DateFormat::MonthDayYear => (year, first, second),
+            DateFormat::Iso => return Some(Err(day_first_error(input, year, first, second))),
+        }
+    } else {
+        return None;
+    };
+
+    if chrono::NaiveDate::from_ymd_opt(year, month, day).is_none() {
+        let hint = match format {
+            DateFormat::DayMonthYear if valid(year, day, month) => {
+                ", it looks month-day-year but the date format is day-month-year"
+            }
+            DateFormat::MonthDayYear if valid(year, day, month) => {
+                ", it looks day-month-year but the date format is month-day-year"
+            }
+            _ => "",
+        };
+        return Some(Err(format!(
+            "Invalid date '{}': there is no day {} in month {} of {}{}",
+            input, day, month, year, hint
+        )));
+    }
+
+    let (hour, minute, second) = time.unwrap_or((0, 0, 0));
+    if hour > 23 || minute > 59 || second > 59 {
+        return Some(Err(format!(
+            "Invalid time in '{}': hours must be 0-23, minutes and seconds 0-59",
+            input
+        )));
+    }
+
+    Some(Ok(DateTimeValue {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        timezone: None,
+        granularity: DateGranularity::Exact,
+    }))
+}
+
+fn valid(year: i32, month: u32, day: u32) -> bool {
+    chrono::NaiveDate::from_ymd_opt(year, month, day).is_some()
+}
+
+/// `14:30` or `14:30:15`
+fn parse_time(time: &str) -> Option<(u32, u32, u32)> {
+    let parts: Vec<&str> = time.split(':').collect();
+    if !(2..=3).contains(&parts.len())
+        || parts
+            .iter()
+            .any(|part| part.len() != 2 || !part.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return None;
+    }
+    let number = |i: usize| parts.get(i).map_or(Some(0), |part| part.parse().ok());
+    Some((number(0)?, number(1)?, number(2)?))
+}
+
+/// Why a day-first or month-first date needs a date format
+fn day_first_error(input: &str, year: i32, first: u32, second: u32) -> String {
+    let day_month = valid(year, second, first);
+    let month_day = valid(year, first, second);
+    if day_month && month_day && first != second {
+        format!(
+            "Date '{}' is ambiguous: it is {:04}-{:02}-{:02} as day-month-year and {:04}-{:02}-{:02} as month-day-year; set the date format or write it as YYYY-MM-DD",
+            input, year, second, first, year, first, second
+        )
+    } else {
+        format!(
+            "Date '{}' is not in ISO format; set the date format to day-month-year or month-day-year, or write it as YYYY-MM-DD",
+            input
+        )
+    }
+}
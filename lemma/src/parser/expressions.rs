@@ -10,11 +10,7 @@ fn traceable_expr(
     pair: &Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Expression {
-    Expression::new(
-        kind,
-        Some(Span::from_pest_span(pair.as_span())),
-        id_gen.next_id(),
-    )
+    Expression::new(kind, Some(Span::from_pair(pair)), id_gen.next_id())
 }
 
 /// Helper function to parse any literal rule into an Expression.
@@ -61,6 +57,15 @@ fn parse_primary(
             Rule::reference_expression => {
                 return parse_reference_expression(inner, id_gen);
             }
+            Rule::aggregate_expression => {
+                return parse_aggregate_expression(inner, id_gen);
+            }
+            Rule::selection_expression => {
+                return parse_selection_expression(inner, id_gen);
+            }
+            Rule::function_call => {
+                return parse_function_call(inner, id_gen);
+            }
             Rule::rule_reference => {
                 let rule_ref = parse_rule_reference(inner.clone())?;
                 return Ok(traceable_expr(
@@ -228,7 +233,7 @@ fn parse_expression_impl(
     )))
 }
 
-fn parse_reference_expression(
+pub(crate) fn parse_reference_expression(
     pair: Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Result<Expression, LemmaError> {
@@ -258,6 +263,161 @@ fn parse_reference_expression(
     ))
 }
 
+fn parse_function_call(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    // Grammar: function_name ~ "(" ~ (expression_group ~ ("," ~ expression_group)*)? ~ ")"
+    let mut name = None;
+    let mut arguments = Vec::new();
+
+    for inner in pair.clone().into_inner() {
+        match inner.as_rule() {
+            Rule::function_name => name = Some(inner.as_str().to_string()),
+            Rule::expression_group => arguments.push(parse_or_expression(inner, id_gen)?),
+            _ => {}
+        }
+    }
+
+    let name = name.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: function_call missing function_name".to_string())
+    })?;
+    Ok(traceable_expr(
+        ExpressionKind::FunctionCall(name, arguments),
+        &pair,
+        id_gen,
+    ))
+}
+
+fn parse_selection_expression(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    // Grammar: selection_function ~ ^"of" ~ arithmetic_expression ~ ("," ~ arithmetic_expression)+
+    let mut function = None;
+    let mut candidates = Vec::new();
+
+    for inner in pair.clone().into_inner() {
+        match inner.as_rule() {
+            Rule::selection_function => {
+                function = Some(match inner.as_str().to_lowercase().as_str() {
+                    "lowest" => SelectionFunction::Lowest,
+                    "highest" => SelectionFunction::Highest,
+                    "earliest" => SelectionFunction::Earliest,
+                    "latest" => SelectionFunction::Latest,
+                    other => {
+                        return Err(LemmaError::Engine(format!(
+                            "Unknown selection function: {}",
+                            other
+                        )))
+                    }
+                });
+            }
+            Rule::arithmetic_expression => candidates.push(parse_expression(inner, id_gen)?),
+            _ => {}
+        }
+    }
+
+    let function = function.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: selection_expression missing function".to_string())
+    })?;
+    Ok(traceable_expr(
+        ExpressionKind::Selection(function, candidates),
+        &pair,
+        id_gen,
+    ))
+}
+
+fn parse_aggregate_expression(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    // Grammar: aggregate_function ~ SPACE+ ~ ^"of" ~ SPACE+ ~ (rule_reference | fact_reference)
+    let mut function = None;
+    let mut collection = Vec::new();
+    let mut rule = None;
+
+    for inner in pair.clone().into_inner() {
+        match inner.as_rule() {
+            Rule::aggregate_function => {
+                function = Some(match inner.as_str().to_lowercase().as_str() {
+                    "sum" => AggregateFunction::Sum,
+                    "count" => AggregateFunction::Count,
+                    "min" => AggregateFunction::Min,
+                    "max" => AggregateFunction::Max,
+                    "total" => AggregateFunction::Total,
+                    other => {
+                        return Err(LemmaError::Engine(format!(
+                            "Unknown aggregate function: {}",
+                            other
+                        )))
+                    }
+                });
+            }
+            Rule::rule_reference => {
+                let mut reference = parse_rule_reference(inner)?.reference;
+                rule = reference.pop();
+                collection = reference;
+            }
+            Rule::fact_reference => {
+                collection = parse_fact_reference(inner)?.reference;
+            }
+            _ => {}
+        }
+    }
+
+    let function = function.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: aggregate_expression missing function".to_string())
+    })?;
+
+    // `total of line_items.price` reads a fact of every instance
+    let mut fact = None;
+    if function == AggregateFunction::Total && rule.is_none() {
+        if collection.len() < 2 {
+            return Err(LemmaError::Engine(format!(
+                "'total of' takes a money fact or rule of the collection, e.g. 'total of {}.price'",
+                collection.join(".")
+            )));
+        }
+        fact = collection.pop();
+    }
+
+    match (&function, &rule) {
+        (AggregateFunction::Count, Some(_)) => {
+            return Err(LemmaError::Engine(format!(
+                "'count of' takes a collection, not a rule: use 'count of {}'",
+                collection.join(".")
+            )));
+        }
+        (AggregateFunction::Sum | AggregateFunction::Min | AggregateFunction::Max, None) => {
+            return Err(LemmaError::Engine(format!(
+                "'{} of' takes a rule of the collection, e.g. '{} of {}.total?'",
+                function,
+                function,
+                collection.join(".")
+            )));
+        }
+        _ => {}
+    }
+    if collection.is_empty() {
+        return Err(LemmaError::Engine(format!(
+            "'{} of' needs a collection, e.g. '{} of line_items.total?'",
+            function, function
+        )));
+    }
+
+    Ok(traceable_expr(
+        ExpressionKind::Aggregate(AggregateExpression {
+            function,
+            collection,
+            rule,
+            fact,
+        }),
+        &pair,
+        id_gen,
+    ))
+}
+
 fn parse_fact_reference(pair: Pair<Rule>) -> Result<FactReference, LemmaError> {
     let mut reference = Vec::new();
     for inner_pair in pair.into_inner() {
@@ -271,7 +431,8 @@ fn parse_fact_reference(pair: Pair<Rule>) -> Result<FactReference, LemmaError> {
 fn parse_rule_reference(pair: Pair<Rule>) -> Result<RuleReference, LemmaError> {
     let mut reference = Vec::new();
     for inner_pair in pair.into_inner() {
-        if inner_pair.as_rule() == Rule::label {
+        // `quote?.total` is the field rule `quote.total?`
+        if matches!(inner_pair.as_rule(), Rule::label | Rule::record_field_name) {
             reference.push(inner_pair.as_str().to_string());
         }
     }
@@ -282,7 +443,8 @@ fn parse_and_operand(
     pair: Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Result<Expression, LemmaError> {
-    // Grammar: boolean_expression | comparable_base ~ (SPACE* ~ comp_operator ~ SPACE* ~ comparable_base)?
+    // Grammar: boolean_expression | comparable_base ~ (range_condition | SPACE* ~ comp_operator ~ SPACE* ~ comparable_base ~ chained_comparison?)?
+    let operand = pair.clone();
     let mut pairs = pair.into_inner();
     let first = pairs
         .next()
@@ -298,35 +460,20 @@ fn parse_and_operand(
 
     // Check for comparison operator
     if let Some(op_pair) = pairs.next() {
+        if op_pair.as_rule() == Rule::range_condition {
+            return parse_range_condition(left, op_pair, &operand, id_gen);
+        }
         if op_pair.as_rule() == Rule::comp_operator {
-            // Parse the specific operator from within comp_operator
-            let inner_pair = op_pair
-                .clone()
-                .into_inner()
-                .next()
-                .ok_or_else(|| LemmaError::Engine("Empty comparison operator".to_string()))?;
-            let operator = match inner_pair.as_rule() {
-                Rule::comp_gt => ComparisonOperator::GreaterThan,
-                Rule::comp_lt => ComparisonOperator::LessThan,
-                Rule::comp_gte => ComparisonOperator::GreaterThanOrEqual,
-                Rule::comp_lte => ComparisonOperator::LessThanOrEqual,
-                Rule::comp_eq => ComparisonOperator::Equal,
-                Rule::comp_ne => ComparisonOperator::NotEqual,
-                Rule::comp_is => ComparisonOperator::Is,
-                Rule::comp_is_not => ComparisonOperator::IsNot,
-                _ => {
-                    return Err(LemmaError::Engine(format!(
-                        "Invalid comparison operator: {:?}",
-                        inner_pair.as_rule()
-                    )))
-                }
-            };
+            let operator = parse_comparison_operator(&op_pair)?;
             let right = parse_expression(
                 pairs.next().ok_or_else(|| {
                     LemmaError::Engine("Missing right operand in comparison".to_string())
                 })?,
                 id_gen,
             )?;
+            if let Some(chained) = pairs.next() {
+                return parse_chained_comparison(left, operator, right, chained, &operand, id_gen);
+            }
             let kind = ExpressionKind::Comparison(Box::new(left), operator, Box::new(right));
             return Ok(traceable_expr(kind, &op_pair, id_gen));
         }
@@ -336,6 +483,116 @@ fn parse_and_operand(
     Ok(left)
 }
 
+fn parse_comparison_operator(pair: &Pair<Rule>) -> Result<ComparisonOperator, LemmaError> {
+    // Parse the specific operator from within comp_operator
+    let inner_pair = pair
+        .clone()
+        .into_inner()
+        .next()
+        .ok_or_else(|| LemmaError::Engine("Empty comparison operator".to_string()))?;
+    Ok(match inner_pair.as_rule() {
+        Rule::comp_gt => ComparisonOperator::GreaterThan,
+        Rule::comp_lt => ComparisonOperator::LessThan,
+        Rule::comp_gte => ComparisonOperator::GreaterThanOrEqual,
+        Rule::comp_lte => ComparisonOperator::LessThanOrEqual,
+        Rule::comp_eq => ComparisonOperator::Equal,
+        Rule::comp_ne => ComparisonOperator::NotEqual,
+        Rule::comp_is => ComparisonOperator::Is,
+        Rule::comp_is_not => ComparisonOperator::IsNot,
+        _ => {
+            return Err(LemmaError::Engine(format!(
+                "Invalid comparison operator: {:?}",
+                inner_pair.as_rule()
+            )))
+        }
+    })
+}
+
+/// `low <= value < high` and `high >= value > low`, as a range with the
+/// lower bound first
+fn parse_chained_comparison(
+    first: Expression,
+    first_operator: ComparisonOperator,
+    value: Expression,
+    chained: Pair<Rule>,
+    operand: &Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    use ComparisonOperator::*;
+
+    let mut pairs = chained.into_inner();
+    let second_operator = parse_comparison_operator(&pairs.next().ok_or_else(|| {
+        LemmaError::Engine("Missing operator in chained comparison".to_string())
+    })?)?;
+    let second = parse_expression(
+        pairs.next().ok_or_else(|| {
+            LemmaError::Engine("Missing right operand in chained comparison".to_string())
+        })?,
+        id_gen,
+    )?;
+
+    let flipped = |operator: &ComparisonOperator| match operator {
+        LessThan => GreaterThan,
+        LessThanOrEqual => GreaterThanOrEqual,
+        GreaterThan => LessThan,
+        _ => LessThanOrEqual,
+    };
+    let ((low_operator, low), (high_operator, high)) = match (&first_operator, &second_operator) {
+        (LessThan | LessThanOrEqual, LessThan | LessThanOrEqual) => {
+            ((flipped(&first_operator), first), (second_operator, second))
+        }
+        (GreaterThan | GreaterThanOrEqual, GreaterThan | GreaterThanOrEqual) => {
+            ((second_operator, second), (flipped(&first_operator), first))
+        }
+        _ => {
+            return Err(LemmaError::Engine(format!(
+            "Chained comparison '{}' must compare in one direction, e.g. `0 <= quantity <= 100`",
+            operand.as_str().trim()
+        )))
+        }
+    };
+
+    let kind = ExpressionKind::Range(Box::new(RangeCondition {
+        value,
+        low_operator,
+        low,
+        high_operator,
+        high,
+    }));
+    Ok(traceable_expr(kind, operand, id_gen))
+}
+
+/// `value between low and high` and `value in low..high`, bounds included
+fn parse_range_condition(
+    value: Expression,
+    range: Pair<Rule>,
+    operand: &Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    let mut bounds = range.into_inner();
+    let low = parse_expression(
+        bounds
+            .next()
+            .ok_or_else(|| LemmaError::Engine("Missing lower bound in range".to_string()))?,
+        id_gen,
+    )?;
+    let high = parse_expression(
+        bounds
+            .next()
+            .ok_or_else(|| LemmaError::Engine("Missing upper bound in range".to_string()))?,
+        id_gen,
+    )?;
+
+    let kind = ExpressionKind::Range(Box::new(RangeCondition {
+        value,
+        low_operator: ComparisonOperator::GreaterThanOrEqual,
+        low,
+        high_operator: ComparisonOperator::LessThanOrEqual,
+        high,
+    }));
+    Ok(traceable_expr(kind, operand, id_gen))
+}
+
 fn parse_and_expression(
     pair: Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
@@ -682,11 +939,17 @@ fn parse_logical_expression(
                 for inner in node.clone().into_inner() {
                     if inner.as_rule() == Rule::reference_expression {
                         let ref_expr = parse_reference_expression(inner.clone(), id_gen)?;
-                        if let ExpressionKind::FactReference(f) = &ref_expr.kind {
-                            let kind = ExpressionKind::FactHasAnyValue(f.clone());
-                            return Ok(traceable_expr(kind, &node, id_gen));
+                        match &ref_expr.kind {
+                            ExpressionKind::FactReference(f) => {
+                                let kind = ExpressionKind::FactHasAnyValue(f.clone());
+                                return Ok(traceable_expr(kind, &node, id_gen));
+                            }
+                            ExpressionKind::RuleReference(r) => {
+                                let kind = ExpressionKind::RuleHasValue(r.clone());
+                                return Ok(traceable_expr(kind, &node, id_gen));
+                            }
+                            _ => return Ok(ref_expr),
                         }
-                        return Ok(ref_expr);
                     }
                 }
                 return Err(LemmaError::Engine("have: missing reference".to_string()));
@@ -695,13 +958,24 @@ fn parse_logical_expression(
                 let rule_type = node.as_rule();
                 for inner in node.clone().into_inner() {
                     if inner.as_rule() == Rule::reference_expression {
-                        let negated_expr = parse_reference_expression(inner, id_gen)?;
+                        let mut negated_expr = parse_reference_expression(inner, id_gen)?;
                         let negation_type = match rule_type {
                             Rule::not_expr => NegationType::Not,
                             Rule::have_not_expr => NegationType::HaveNot,
                             Rule::not_have_expr => NegationType::NotHave,
                             _ => NegationType::Not,
                         };
+                        // `have not rule?` asks whether the rule was vetoed
+                        if negation_type != NegationType::Not {
+                            if let ExpressionKind::RuleReference(r) = &negated_expr.kind {
+                                let kind = ExpressionKind::RuleHasValue(r.clone());
+                                negated_expr = Expression::new(
+                                    kind,
+                                    negated_expr.span.clone(),
+                                    id_gen.next_id(),
+                                );
+                            }
+                        }
                         let kind =
                             ExpressionKind::LogicalNegation(Box::new(negated_expr), negation_type);
                         return Ok(traceable_expr(kind, &node, id_gen));
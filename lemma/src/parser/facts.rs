@@ -1,17 +1,22 @@
 use crate::error::LemmaError;
 use crate::parser::Rule;
 use crate::semantic::*;
+use crate::templates::TemplateArgument;
 use pest::iterators::Pair;
 
 pub(crate) fn parse_fact_definition(pair: Pair<Rule>) -> Result<LemmaFact, LemmaError> {
-    let span = crate::ast::Span::from_pest_span(pair.as_span());
+    let span = crate::ast::Span::from_pair(&pair);
     let mut fact_name = None;
     let mut fact_value = None;
+    let mut example = None;
+    let mut sensitive = false;
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
-            Rule::fact_name => fact_name = Some(inner_pair.as_str().to_string()),
+            Rule::fact_name => fact_name = Some(parse_fact_name(inner_pair)?),
             Rule::fact_value => fact_value = Some(parse_fact_value(inner_pair)?),
+            Rule::fact_example => example = Some(parse_fact_example(inner_pair)?),
+            Rule::fact_sensitive => sensitive = true,
             _ => {}
         }
     }
@@ -23,13 +28,16 @@ pub(crate) fn parse_fact_definition(pair: Pair<Rule>) -> Result<LemmaFact, Lemma
         LemmaError::Engine("Grammar error: fact_definition missing fact_value".to_string())
     })?;
 
-    Ok(LemmaFact::new(crate::FactType::Local(name), value).with_span(span))
+    let fact = LemmaFact::new(crate::FactType::Local(name), value).with_span(span);
+    mark_sensitive(attach_example(fact, example)?, sensitive)
 }
 
 pub(crate) fn parse_fact_override(pair: Pair<Rule>) -> Result<LemmaFact, LemmaError> {
-    let span = crate::ast::Span::from_pest_span(pair.as_span());
+    let span = crate::ast::Span::from_pair(&pair);
     let mut fact_override_name = None;
     let mut fact_value = None;
+    let mut example = None;
+    let mut sensitive = false;
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
@@ -37,6 +45,8 @@ pub(crate) fn parse_fact_override(pair: Pair<Rule>) -> Result<LemmaFact, LemmaEr
                 fact_override_name = Some(parse_fact_override_name(inner_pair)?)
             }
             Rule::fact_value => fact_value = Some(parse_fact_value(inner_pair)?),
+            Rule::fact_example => example = Some(parse_fact_example(inner_pair)?),
+            Rule::fact_sensitive => sensitive = true,
             _ => {}
         }
     }
@@ -48,13 +58,77 @@ pub(crate) fn parse_fact_override(pair: Pair<Rule>) -> Result<LemmaFact, LemmaEr
         LemmaError::Engine("Grammar error: fact_override missing fact_value".to_string())
     })?;
 
-    Ok(LemmaFact::new(crate::FactType::Foreign(override_ref), value).with_span(span))
+    let fact = LemmaFact::new(crate::FactType::Foreign(override_ref), value).with_span(span);
+    mark_sensitive(attach_example(fact, example)?, sensitive)
+}
+
+/// The name of a new fact or template parameter, which can't be the reserved `caller`
+pub(crate) fn parse_fact_name(pair: Pair<Rule>) -> Result<String, LemmaError> {
+    let name = pair.as_str().to_string();
+    if pair
+        .into_inner()
+        .any(|inner| inner.as_rule() == Rule::caller_keyword)
+    {
+        return Err(LemmaError::Engine(format!(
+            "'{}' is reserved: '{}.x' reads fact 'x' of the referencing document; rename it",
+            name, name
+        )));
+    }
+    Ok(name)
+}
+
+/// Give a typed fact its `example`, which must be of the declared type
+fn attach_example(fact: LemmaFact, example: Option<LiteralValue>) -> Result<LemmaFact, LemmaError> {
+    let Some(example) = example else {
+        return Ok(fact);
+    };
+    match &fact.value {
+        FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => {
+            if example.to_type() != *lemma_type {
+                return Err(LemmaError::Engine(format!(
+                    "Example for fact '{}' must be a {}, got {}",
+                    fact.fact_type,
+                    lemma_type,
+                    example.to_type()
+                )));
+            }
+            if let LiteralValue::PercentagePoints(_) = example {
+                return Err(LemmaError::Engine(format!(
+                    "Example for fact '{}' must be a percentage like 5%, got {}",
+                    fact.fact_type, example
+                )));
+            }
+        }
+        _ => {
+            return Err(LemmaError::Engine(format!(
+                "Fact '{}' has a value; only typed facts like [number] can have an example",
+                fact.fact_type
+            )))
+        }
+    }
+    Ok(fact.with_example(example))
+}
+
+/// Mark a fact `sensitive`; facts holding documents have no value to mask
+fn mark_sensitive(fact: LemmaFact, sensitive: bool) -> Result<LemmaFact, LemmaError> {
+    if sensitive
+        && matches!(
+            fact.value,
+            FactValue::DocumentReference(_) | FactValue::DocumentCollection(_)
+        )
+    {
+        return Err(LemmaError::Engine(format!(
+            "Fact '{}' refers to a document and can't be sensitive; mark its facts instead",
+            fact.fact_type
+        )));
+    }
+    Ok(fact.with_sensitive(sensitive))
 }
 
 fn parse_fact_override_name(pair: Pair<Rule>) -> Result<crate::ForeignFact, LemmaError> {
     let mut reference = Vec::new();
     for inner_pair in pair.into_inner() {
-        if inner_pair.as_rule() == Rule::label {
+        if inner_pair.as_rule() == Rule::fact_path_segment {
             reference.push(inner_pair.as_str().to_string());
         }
     }
@@ -71,17 +145,22 @@ fn parse_fact_value(pair: Pair<Rule>) -> Result<FactValue, LemmaError> {
         match inner_pair.as_rule() {
             Rule::type_annotation => return parse_fact_type_annotation(inner_pair),
             Rule::document_reference => return parse_fact_document_reference(inner_pair),
+            Rule::collection_reference => {
+                return Ok(FactValue::DocumentCollection(parse_referenced_doc_name(
+                    inner_pair,
+                )?))
+            }
             Rule::literal => return parse_fact_literal(inner_pair),
             _ => {}
         }
     }
     Err(LemmaError::Engine(
-        "Grammar error: fact_value must contain literal, type_annotation, or document_reference"
+        "Grammar error: fact_value must contain literal, type_annotation, document_reference or collection_reference"
             .to_string(),
     ))
 }
 
-fn parse_fact_type_annotation(pair: Pair<Rule>) -> Result<FactValue, LemmaError> {
+pub(crate) fn parse_fact_type_annotation(pair: Pair<Rule>) -> Result<FactValue, LemmaError> {
     for inner_pair in pair.into_inner() {
         if inner_pair.as_rule() == Rule::type_name {
             if let Some(type_inner) = inner_pair.into_inner().next() {
@@ -123,16 +202,72 @@ fn parse_fact_type_annotation(pair: Pair<Rule>) -> Result<FactValue, LemmaError>
 }
 
 fn parse_fact_document_reference(pair: Pair<Rule>) -> Result<FactValue, LemmaError> {
-    let doc_name = pair
-        .into_inner()
+    Ok(FactValue::DocumentReference(parse_referenced_doc_name(
+        pair,
+    )?))
+}
+
+/// The referenced document, named after its arguments when it instantiates a template
+fn parse_referenced_doc_name(pair: Pair<Rule>) -> Result<String, LemmaError> {
+    let mut inner = pair.into_inner();
+    let name = inner
         .next()
         .ok_or_else(|| {
             LemmaError::Engine("Grammar error: document_reference must contain label".to_string())
         })?
         .as_str()
         .to_string();
+    match inner.next() {
+        Some(arguments) => {
+            let arguments: Vec<String> = parse_template_arguments(arguments)?
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            Ok(format!("{}({})", name, arguments.join(", ")))
+        }
+        None => Ok(name),
+    }
+}
+
+pub(crate) fn parse_template_arguments(
+    pair: Pair<Rule>,
+) -> Result<Vec<TemplateArgument>, LemmaError> {
+    let mut arguments = Vec::new();
+    for argument in pair.into_inner() {
+        let value = argument.into_inner().next().ok_or_else(|| {
+            LemmaError::Engine("Grammar error: template_argument must contain a value".to_string())
+        })?;
+        if value.as_rule() == Rule::unit_word {
+            let code = value.as_str();
+            let currency = crate::parser::units::try_parse_money_unit(&code.to_lowercase())
+                .ok_or_else(|| {
+                    LemmaError::Engine(format!(
+                        "Unknown currency '{}' in template arguments: use a literal or an ISO 4217 currency code such as EUR",
+                        code
+                    ))
+                })?;
+            arguments.push(TemplateArgument::Currency(currency));
+            continue;
+        }
+        let literal = value.into_inner().next().ok_or_else(|| {
+            LemmaError::Engine("Grammar error: literal must contain a literal value".to_string())
+        })?;
+        arguments.push(TemplateArgument::Value(
+            crate::parser::literals::parse_literal(literal)?,
+        ));
+    }
+    Ok(arguments)
+}
 
-    Ok(FactValue::DocumentReference(doc_name))
+fn parse_fact_example(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
+    let literal = pair
+        .into_inner()
+        .next()
+        .and_then(|literal| literal.into_inner().next())
+        .ok_or_else(|| {
+            LemmaError::Engine("Grammar error: fact_example must contain a literal".to_string())
+        })?;
+    crate::parser::literals::parse_literal(literal)
 }
 
 fn parse_fact_literal(pair: Pair<Rule>) -> Result<FactValue, LemmaError> {
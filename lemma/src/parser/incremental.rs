@@ -0,0 +1,229 @@
+//! Incremental re-parsing of edited sources
+//!
+//! Facts and rules are line-oriented, so an edit that doesn't add or remove a
+//! `doc` line stays within one document section. Only that section is parsed
+//! again; documents below it keep their AST and just have their spans moved.
+
+use crate::ast::Span;
+use crate::error::LemmaError;
+use crate::resource_limits::ResourceLimits;
+use crate::semantic::*;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Apply a line edit to a source and re-parse the documents it affects
+///
+/// `docs` are the documents previously parsed from `code`. `lines` are 1-based
+/// line numbers (end exclusive) replaced by `new_text`. Returns the edited
+/// source and the documents replacing `docs`.
+pub fn reparse_edit(
+    docs: &[LemmaDoc],
+    code: &str,
+    lines: Range<usize>,
+    new_text: &str,
+    source: &str,
+    limits: &ResourceLimits,
+) -> Result<(String, Vec<LemmaDoc>), LemmaError> {
+    let line_starts = line_starts(code);
+    if lines.start == 0 || lines.start > lines.end || lines.end > line_starts.len() + 1 {
+        return Err(LemmaError::Engine(format!(
+            "Line range {}..{} is outside source '{}' with {} lines",
+            lines.start,
+            lines.end,
+            source,
+            line_starts.len()
+        )));
+    }
+    let byte_at = |line: usize| line_starts.get(line - 1).copied().unwrap_or(code.len());
+
+    let (start, end) = (byte_at(lines.start), byte_at(lines.end));
+    let edited = format!("{}{}{}", &code[..start], new_text, &code[end..]);
+    super::check_file_size(&edited, limits)?;
+
+    let byte_delta = new_text.len() as isize - (end - start) as isize;
+    let line_delta = newlines(new_text) - newlines(&code[start..end]);
+
+    let mut docs: Vec<&LemmaDoc> = docs.iter().collect();
+    docs.sort_by_key(|doc| doc.start_line);
+
+    // The edit must stay below one doc line and may not add or remove one
+    let section = docs.iter().rposition(|doc| doc.start_line < lines.start);
+    let touches_doc_line =
+        docs.iter().any(|doc| lines.contains(&doc.start_line)) || new_text.lines().any(is_doc_line);
+    let index = match section {
+        Some(index) if !touches_doc_line => index,
+        _ => {
+            let reparsed = super::parse(&edited, Some(source.to_string()), limits)?;
+            return Ok((edited, reparsed));
+        }
+    };
+
+    let section_line = docs[index].start_line;
+    let section_start = byte_at(section_line);
+    let section_end = match docs.get(index + 1) {
+        Some(next) => byte_at(next.start_line).saturating_add_signed(byte_delta),
+        None => edited.len(),
+    };
+    let offset = Shift {
+        bytes: section_start as isize,
+        lines: section_line as isize - 1,
+    };
+
+    let section_docs = super::parse(
+        &edited[section_start..section_end],
+        Some(source.to_string()),
+        limits,
+    )
+    .map_err(|error| offset.error(error, &edited))?;
+
+    let below = Shift {
+        bytes: byte_delta,
+        lines: line_delta,
+    };
+    let mut result: Vec<LemmaDoc> = docs[..index].iter().map(|doc| (*doc).clone()).collect();
+    result.extend(section_docs.into_iter().map(|mut doc| {
+        offset.doc(&mut doc);
+        doc
+    }));
+    result.extend(docs[index + 1..].iter().map(|doc| {
+        let mut doc = (*doc).clone();
+        below.doc(&mut doc);
+        doc
+    }));
+
+    Ok((edited, result))
+}
+
+/// Byte offset of the start of every line
+fn line_starts(code: &str) -> Vec<usize> {
+    std::iter::once(0)
+        .chain(code.match_indices('\n').map(|(i, _)| i + 1))
+        .collect()
+}
+
+fn newlines(text: &str) -> isize {
+    text.matches('\n').count() as isize
+}
+
+/// Whether a line declares a document (`doc name` or `partial doc name`)
+fn is_doc_line(line: &str) -> bool {
+    let mut line = line.trim_start();
+    if line
+        .get(..7)
+        .is_some_and(|word| word.eq_ignore_ascii_case("partial"))
+        && line[7..].starts_with(char::is_whitespace)
+    {
+        line = line[7..].trim_start();
+    }
+    line.get(..3)
+        .is_some_and(|word| word.eq_ignore_ascii_case("doc"))
+        && (line.len() == 3 || line[3..].starts_with(char::is_whitespace))
+}
+
+/// Distance to move spans by, in bytes and lines
+struct Shift {
+    bytes: isize,
+    lines: isize,
+}
+
+impl Shift {
+    fn span(&self, span: &mut Span) {
+        span.start = span.start.saturating_add_signed(self.bytes);
+        span.end = span.end.saturating_add_signed(self.bytes);
+        span.line = span.line.saturating_add_signed(self.lines);
+    }
+
+    fn doc(&self, doc: &mut LemmaDoc) {
+        doc.start_line = doc.start_line.saturating_add_signed(self.lines);
+        for fact in &mut doc.facts {
+            if let Some(span) = &mut fact.span {
+                self.span(span);
+            }
+        }
+        for rule in &mut doc.rules {
+            if let Some(span) = &mut rule.span {
+                self.span(span);
+            }
+            self.expression(&mut rule.expression);
+            for clause in &mut rule.unless_clauses {
+                if let Some(span) = &mut clause.span {
+                    self.span(span);
+                }
+                self.expression(&mut clause.condition);
+                self.expression(&mut clause.result);
+            }
+        }
+        for checklist in &mut doc.checklists {
+            if let Some(span) = &mut checklist.span {
+                self.span(span);
+            }
+            for item in &mut checklist.items {
+                self.expression(item);
+            }
+        }
+        for record in &mut doc.records {
+            if let Some(span) = &mut record.span {
+                self.span(span);
+            }
+        }
+        for invariant in &mut doc.invariants {
+            if let Some(span) = &mut invariant.span {
+                self.span(span);
+            }
+            self.expression(&mut invariant.condition);
+        }
+    }
+
+    fn expression(&self, expr: &mut Expression) {
+        if let Some(span) = &mut expr.span {
+            self.span(span);
+        }
+        match &mut expr.kind {
+            ExpressionKind::LogicalAnd(left, right)
+            | ExpressionKind::LogicalOr(left, right)
+            | ExpressionKind::Arithmetic(left, _, right)
+            | ExpressionKind::Comparison(left, _, right) => {
+                self.expression(left);
+                self.expression(right);
+            }
+            ExpressionKind::UnitConversion(inner, _)
+            | ExpressionKind::LogicalNegation(inner, _)
+            | ExpressionKind::MathematicalOperator(_, inner) => self.expression(inner),
+            ExpressionKind::Range(range) => {
+                for operand in range.operands_mut() {
+                    self.expression(operand);
+                }
+            }
+            ExpressionKind::MissingItems(items)
+            | ExpressionKind::FunctionCall(_, items)
+            | ExpressionKind::Selection(_, items) => {
+                for item in items {
+                    self.expression(item);
+                }
+            }
+            ExpressionKind::Veto(veto) => {
+                for argument in &mut veto.arguments {
+                    self.expression(&mut argument.expression);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Point an error from a section parse at the whole source
+    fn error(&self, error: LemmaError, code: &str) -> LemmaError {
+        match error {
+            LemmaError::Parse(mut details) => {
+                self.span(&mut details.span);
+                // Syntax errors aren't attributed to a document and count from line 1
+                if details.doc_name != "<parse-error>" {
+                    details.doc_start_line =
+                        details.doc_start_line.saturating_add_signed(self.lines);
+                }
+                details.source_text = Arc::from(code);
+                LemmaError::Parse(details)
+            }
+            other => other,
+        }
+    }
+}
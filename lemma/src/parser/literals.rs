@@ -69,10 +69,15 @@ fn parse_boolean_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
 }
 
 fn parse_percentage_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
+    let points = pair
+        .clone()
+        .into_inner()
+        .any(|inner_pair| inner_pair.as_rule() == Rule::percentage_points);
     for inner_pair in pair.into_inner() {
         if inner_pair.as_rule() == Rule::number_literal {
             let percentage = parse_number_literal(inner_pair)?;
             match percentage {
+                LiteralValue::Number(n) if points => return Ok(LiteralValue::PercentagePoints(n)),
                 LiteralValue::Number(n) => return Ok(LiteralValue::Percentage(n)),
                 _ => {
                     return Err(LemmaError::Engine(
@@ -113,9 +118,14 @@ fn parse_regex_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
 fn parse_unit_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
     let mut number = None;
     let mut unit_str = None;
+    let mut currency = None;
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
+            Rule::duration_compound => return parse_duration_compound(inner_pair),
+            Rule::currency_symbol => {
+                currency = super::units::currency_of_symbol(inner_pair.as_str());
+            }
             Rule::number_literal => {
                 let lit = parse_number_literal(inner_pair)?;
                 match lit {
@@ -136,6 +146,9 @@ fn parse_unit_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
 
     let value =
         number.ok_or_else(|| LemmaError::Engine("Missing number in unit literal".to_string()))?;
+    if let Some(currency) = currency {
+        return Ok(LiteralValue::Unit(NumericUnit::Money(value, currency)));
+    }
     let unit =
         unit_str.ok_or_else(|| LemmaError::Engine("Missing unit in unit literal".to_string()))?;
 
@@ -143,14 +156,38 @@ fn parse_unit_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
     super::units::resolve_unit(value, unit)
 }
 
+fn parse_duration_compound(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
+    let mut parts = Vec::new();
+    for part in pair.into_inner() {
+        let text = part.as_str();
+        let digits = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        parts.push((parse_decimal_number(&text[..digits])?, &text[digits..]));
+    }
+    super::units::resolve_compound_duration(&parts)
+}
+
 /// Parse date/time literals with comprehensive error messages.
 /// Supports formats:
 /// - Date only: YYYY-MM-DD (e.g., 2024-01-15)
 /// - DateTime: YYYY-MM-DDTHH:MM:SS (e.g., 2024-01-15T14:30:00)
 /// - With timezone: YYYY-MM-DDTHH:MM:SSZ or YYYY-MM-DDTHH:MM:SS+HH:MM
+/// - ISO week: YYYY-Www (e.g., 2026-W08)
+/// - Month: YYYY-MM (e.g., 2026-02)
 fn parse_datetime_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError> {
     let datetime_str = pair.as_str();
 
+    if let Some(period) = parse_period(datetime_str) {
+        return period.map(LiteralValue::Date).ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "Invalid date: '{}'\n\
+                 Months must be 01-12, and weeks 01-52 (or 53 in years with 53 ISO weeks)",
+                datetime_str
+            ))
+        });
+    }
+
     // Try datetime with timezone first
     if let Ok(dt) = datetime_str.parse::<chrono::DateTime<chrono::FixedOffset>>() {
         let offset = dt.offset().local_minus_utc();
@@ -165,6 +202,7 @@ fn parse_datetime_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError>
                 offset_hours: (offset / 3600) as i8,
                 offset_minutes: ((offset % 3600) / 60) as u8,
             }),
+            granularity: DateGranularity::Exact,
         }));
     }
 
@@ -178,6 +216,7 @@ fn parse_datetime_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError>
             minute: dt.minute(),
             second: dt.second(),
             timezone: None,
+            granularity: DateGranularity::Exact,
         }));
     }
 
@@ -191,6 +230,7 @@ fn parse_datetime_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError>
             minute: 0,
             second: 0,
             timezone: None,
+            granularity: DateGranularity::Exact,
         }));
     }
 
@@ -206,6 +246,21 @@ fn parse_datetime_literal(pair: Pair<Rule>) -> Result<LiteralValue, LemmaError>
     )))
 }
 
+/// A whole ISO week (`2026-W08`) or month (`2026-02`)
+///
+/// Returns None for other dates, and Some(None) for a week or month that doesn't exist.
+fn parse_period(text: &str) -> Option<Option<DateTimeValue>> {
+    let (year, rest) = text.split_once('-')?;
+    let year: i32 = year.parse().ok()?;
+    if let Some(week) = rest.strip_prefix('W') {
+        return Some(DateTimeValue::of_week(year, week.parse().ok()?));
+    }
+    if rest.len() == 2 {
+        return Some(DateTimeValue::of_month(year, rest.parse().ok()?));
+    }
+    None
+}
+
 /// Parse time literals with comprehensive error messages.
 /// Supports formats:
 /// - Time: HH:MM or HH:MM:SS (e.g., 14:30 or 14:30:00)
@@ -1,14 +1,18 @@
 use crate::ast::{ExpressionIdGenerator, Span};
-use crate::error::LemmaError;
+use crate::error::{FactParseError, LemmaError};
 use crate::resource_limits::ResourceLimits;
 use crate::semantic::*;
+use dates::DateFormat;
+use pest::error::{ErrorVariant, InputLocation};
 use pest::iterators::Pair;
 use pest::Parser;
 use pest_derive::Parser;
 use std::sync::Arc;
 
+pub mod dates;
 pub mod expressions;
 pub mod facts;
+pub mod incremental;
 pub mod literals;
 pub mod rules;
 pub mod units;
@@ -17,28 +21,21 @@ pub mod units;
 #[grammar = "src/parser/lemma.pest"]
 pub struct LemmaParser;
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip_all,
+        fields(source = filename.as_deref().unwrap_or("<input>"), bytes = content.len()),
+        err(level = "debug")
+    )
+)]
 pub fn parse(
     content: &str,
     filename: Option<String>,
     limits: &ResourceLimits,
 ) -> Result<Vec<LemmaDoc>, LemmaError> {
-    // Check file size limit
-    if content.len() > limits.max_file_size_bytes {
-        return Err(LemmaError::ResourceLimitExceeded {
-            limit_name: "max_file_size_bytes".to_string(),
-            limit_value: format!(
-                "{} bytes ({} MB)",
-                limits.max_file_size_bytes,
-                limits.max_file_size_bytes / (1024 * 1024)
-            ),
-            actual_value: format!(
-                "{} bytes ({:.2} MB)",
-                content.len(),
-                content.len() as f64 / (1024.0 * 1024.0)
-            ),
-            suggestion: "Reduce file size or split into multiple documents".to_string(),
-        });
-    }
+    check_file_size(content, limits)?;
 
     let mut id_gen = ExpressionIdGenerator::with_max_depth(limits.max_expression_depth);
     let filename = filename.unwrap_or_else(|| "<input>".to_string());
@@ -55,19 +52,25 @@ pub fn parse(
                     }
                 }
             }
+            #[cfg(feature = "tracing")]
+            tracing::debug!(documents = docs.len(), "parsed");
             Ok(docs)
         }
         Err(e) => {
+            let (start, end) = match e.location {
+                pest::error::InputLocation::Pos(pos) => (pos, pos),
+                pest::error::InputLocation::Span(span) => span,
+            };
             let pest_span = match e.line_col {
                 pest::error::LineColLocation::Pos((line, col)) => Span {
-                    start: 0,
-                    end: 0,
+                    start,
+                    end,
                     line,
                     col,
                 },
                 pest::error::LineColLocation::Span((start_line, start_col), (_, _)) => Span {
-                    start: 0,
-                    end: 0,
+                    start,
+                    end,
                     line: start_line,
                     col: start_col,
                 },
@@ -85,67 +88,346 @@ pub fn parse(
     }
 }
 
+/// Reject sources larger than `max_file_size_bytes`
+fn check_file_size(content: &str, limits: &ResourceLimits) -> Result<(), LemmaError> {
+    if content.len() > limits.max_file_size_bytes {
+        return Err(LemmaError::ResourceLimitExceeded {
+            limit_name: "max_file_size_bytes".to_string(),
+            limit_value: format!(
+                "{} bytes ({} MB)",
+                limits.max_file_size_bytes,
+                limits.max_file_size_bytes / (1024 * 1024)
+            ),
+            actual_value: format!(
+                "{} bytes ({:.2} MB)",
+                content.len(),
+                content.len() as f64 / (1024.0 * 1024.0)
+            ),
+            suggestion: "Reduce file size or split into multiple documents".to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Parse fact overrides like `price=100` or `order.quantity=3`
+///
+/// Every input is parsed; failures are collected into a single
+/// `LemmaError::FactParse` holding one `FactParseError` per rejected input.
 pub fn parse_facts(fact_strings: &[&str]) -> Result<Vec<LemmaFact>, LemmaError> {
+    parse_facts_with_date_format(fact_strings, DateFormat::Iso)
+}
+
+/// Parse fact overrides, reading dates like `31-12-2025` in `date_format`
+///
+/// Dates with the time after a space, `2025-12-31 14:30`, are read in any
+/// format. See `dates::parse_date` for the forms and the errors.
+pub fn parse_facts_with_date_format(
+    fact_strings: &[&str],
+    date_format: DateFormat,
+) -> Result<Vec<LemmaFact>, LemmaError> {
     let mut facts = Vec::new();
+    let mut errors = Vec::new();
 
     for fact_str in fact_strings {
-        let fact_input = format!("fact {}", fact_str);
-        let pairs = LemmaParser::parse(Rule::fact, &fact_input).map_err(|e| {
-            LemmaError::Engine(format!("Failed to parse fact '{}': {}", fact_str, e))
-        })?;
-
-        let fact_pair = pairs.into_iter().next().ok_or_else(|| {
-            LemmaError::Engine(format!("No parse result for fact '{}'", fact_str))
-        })?;
-
-        let inner_pair = fact_pair
-            .into_inner()
-            .next()
-            .ok_or_else(|| LemmaError::Engine(format!("No inner rule for fact '{}'", fact_str)))?;
-
-        let fact = match inner_pair.as_rule() {
-            Rule::fact_definition => crate::parser::facts::parse_fact_definition(inner_pair)?,
-            Rule::fact_override => crate::parser::facts::parse_fact_override(inner_pair)?,
-            _ => {
-                return Err(LemmaError::Engine(format!(
-                    "Unexpected rule type for fact '{}'",
-                    fact_str
-                )))
-            }
+        match parse_fact(fact_str, date_format) {
+            Ok(fact) => facts.push(fact),
+            Err(error) => errors.push(error),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(facts)
+    } else {
+        Err(LemmaError::FactParse(errors))
+    }
+}
+
+/// Parse a single literal like `100 EUR`, `10%` or `true`
+pub fn parse_literal(input: &str) -> Result<LiteralValue, LemmaError> {
+    let input = input.trim();
+    LemmaParser::parse(Rule::literal, input)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .filter(|pair| pair.as_str().len() == input.len())
+        .and_then(|pair| pair.into_inner().next())
+        .ok_or_else(|| LemmaError::Engine(format!("Invalid literal: {}", input)))
+        .and_then(literals::parse_literal)
+}
+
+/// Split the name of a template instance, `tariff("EU", EUR)`, into the template and its arguments
+pub(crate) fn parse_template_instance(
+    name: &str,
+) -> Result<(String, Vec<crate::templates::TemplateArgument>), LemmaError> {
+    let pair = LemmaParser::parse(Rule::template_instance, name)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .ok_or_else(|| LemmaError::Engine(format!("Invalid template instance: {}", name)))?;
+    let mut template = String::new();
+    let mut arguments = Vec::new();
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::doc_name => template = inner.as_str().to_string(),
+            Rule::template_arguments => arguments = facts::parse_template_arguments(inner)?,
+            _ => {}
+        }
+    }
+    Ok((template, arguments))
+}
+
+/// Prefix turning a fact override into a `fact` statement for the grammar
+const FACT_PREFIX: &str = "fact ";
+
+fn parse_fact(fact_str: &str, date_format: DateFormat) -> Result<LemmaFact, FactParseError> {
+    let Some((name, value)) = fact_str.split_once('=') else {
+        return parse_fact_syntax(fact_str);
+    };
+    match dates::parse_date(value, date_format) {
+        None => parse_fact_syntax(fact_str),
+        Some(Err(message)) => Err(fact_parse_error(
+            fact_str,
+            value_offset(fact_str),
+            Vec::new(),
+            message,
+        )),
+        // Handed to the grammar in ISO form; errors in the name keep their position
+        Some(Ok(date)) => parse_fact_syntax(&format!("{}={}", name, date)).map_err(|mut error| {
+            error.input = fact_str.to_string();
+            error
+        }),
+    }
+}
+
+fn parse_fact_syntax(fact_str: &str) -> Result<LemmaFact, FactParseError> {
+    if !fact_str.contains('=') {
+        let name_end = fact_str
+            .trim_start()
+            .find(char::is_whitespace)
+            .map(|end| end + fact_str.len() - fact_str.trim_start().len())
+            .unwrap_or(fact_str.len());
+        return Err(fact_parse_error(
+            fact_str,
+            name_end,
+            vec!["=".to_string()],
+            "expected '=' between fact name and value".to_string(),
+        ));
+    }
+
+    let fact_input = format!("{}{}", FACT_PREFIX, fact_str);
+    let pairs = LemmaParser::parse(Rule::fact, &fact_input).map_err(|e| {
+        let position = match e.location {
+            InputLocation::Pos(pos) => pos,
+            InputLocation::Span((start, _)) => start,
+        };
+        let expected = match &e.variant {
+            ErrorVariant::ParsingError { positives, .. } => describe_expected(positives),
+            ErrorVariant::CustomError { .. } => Vec::new(),
         };
+        let message = if expected.is_empty() {
+            e.variant.message().to_string()
+        } else {
+            format!("expected {}", expected.join(", "))
+        };
+        fact_parse_error(
+            fact_str,
+            position.saturating_sub(FACT_PREFIX.len()),
+            expected,
+            message,
+        )
+    })?;
+
+    let fact_pair = pairs
+        .into_iter()
+        .next()
+        .ok_or_else(|| fact_parse_error(fact_str, 0, Vec::new(), "no parse result".to_string()))?;
 
-        facts.push(fact);
+    // The grammar accepts a prefix, anything after the value is an error
+    let consumed = fact_pair.as_str().len().saturating_sub(FACT_PREFIX.len());
+    if consumed < fact_str.trim_end().len() {
+        return Err(fact_parse_error(
+            fact_str,
+            consumed,
+            Vec::new(),
+            format!("unexpected input '{}'", fact_str[consumed..].trim()),
+        ));
     }
 
-    Ok(facts)
+    let inner_pair = fact_pair
+        .into_inner()
+        .next()
+        .ok_or_else(|| fact_parse_error(fact_str, 0, Vec::new(), "no inner rule".to_string()))?;
+
+    let fact = match inner_pair.as_rule() {
+        Rule::fact_definition => crate::parser::facts::parse_fact_definition(inner_pair),
+        Rule::fact_override => crate::parser::facts::parse_fact_override(inner_pair),
+        _ => Err(LemmaError::Engine("unexpected rule type".to_string())),
+    };
+
+    // The grammar matched, so the value itself was rejected (e.g. an unknown unit)
+    fact.map_err(|e| {
+        let message = match e {
+            LemmaError::Engine(msg) => msg,
+            other => other.to_string(),
+        };
+        fact_parse_error(fact_str, value_offset(fact_str), Vec::new(), message)
+    })
+}
+
+fn fact_parse_error(
+    fact_str: &str,
+    position: usize,
+    expected: Vec<String>,
+    message: String,
+) -> FactParseError {
+    let fact = fact_str
+        .split_once('=')
+        .map(|(name, _)| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+
+    FactParseError {
+        input: fact_str.to_string(),
+        fact,
+        position: position.min(fact_str.len()),
+        expected,
+        declared_type: None,
+        message,
+    }
+}
+
+/// Byte offset of the value in `name = value`
+pub(crate) fn value_offset(fact_str: &str) -> usize {
+    match fact_str.find('=') {
+        Some(eq) => {
+            let value = &fact_str[eq + 1..];
+            eq + 1 + (value.len() - value.trim_start().len())
+        }
+        None => 0,
+    }
+}
+
+/// Describe grammar rules the parser expected in user-facing terms
+fn describe_expected(rules: &[Rule]) -> Vec<String> {
+    let mut expected: Vec<String> = Vec::new();
+    for rule in rules {
+        let descriptions: &[&str] = match rule {
+            Rule::literal | Rule::fact_value => &[
+                "number",
+                "text",
+                "boolean",
+                "percentage",
+                "unit value",
+                "date",
+                "time",
+                "regex",
+            ],
+            Rule::number_literal => &["number"],
+            Rule::string_literal => &["text"],
+            Rule::boolean_literal => &["boolean"],
+            Rule::percentage_literal => &["percentage"],
+            Rule::unit_literal => &["unit value"],
+            Rule::date_time_literal => &["date"],
+            Rule::time_literal => &["time"],
+            Rule::regex_literal => &["regex"],
+            Rule::type_annotation => &["type annotation"],
+            Rule::document_reference => &["document reference"],
+            Rule::collection_reference => &["document collection"],
+            Rule::label | Rule::fact_name | Rule::fact_override_name | Rule::fact_path_segment => {
+                &["fact name"]
+            }
+            _ => &[],
+        };
+        for description in descriptions {
+            if !expected.iter().any(|e| e == description) {
+                expected.push(description.to_string());
+            }
+        }
+    }
+    expected
 }
 
 fn parse_doc(
     pair: Pair<Rule>,
     filename: &str,
-    _source: &str,
+    source: &str,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Result<LemmaDoc, LemmaError> {
-    let doc_start_line = pair.as_span().start_pos().line_col().0;
+    let doc_start_line = pair.line_col().0;
 
     let mut doc_name: Option<String> = None;
+    let mut partial = false;
+    let mut parameters = Vec::new();
     let mut commentary: Option<String> = None;
+    let mut declared_version: Option<SemanticVersion> = None;
+    let mut currency: Option<MoneyUnit> = None;
+    let mut text_comparison = TextComparison::default();
+    let mut division_by_zero = DivisionByZero::default();
     let mut facts = Vec::new();
     let mut rules = Vec::new();
+    let mut checklists = Vec::new();
+    let mut invariants = Vec::new();
+    let mut records = Vec::new();
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
             Rule::doc_declaration => {
                 for decl_inner in inner_pair.into_inner() {
-                    if decl_inner.as_rule() == Rule::doc_name {
-                        doc_name = Some(parse_doc_name(decl_inner)?);
-                        break;
+                    match decl_inner.as_rule() {
+                        Rule::partial_marker => partial = true,
+                        Rule::doc_name => doc_name = Some(parse_doc_name(decl_inner)?),
+                        Rule::template_parameters => {
+                            parameters = decl_inner
+                                .into_inner()
+                                .map(crate::parser::facts::parse_fact_name)
+                                .collect::<Result<_, _>>()?;
+                        }
+                        _ => {}
                     }
                 }
             }
             Rule::commentary_content => {
                 commentary = Some(inner_pair.as_str().trim().to_string());
             }
+            Rule::version_directive => {
+                declared_version = Some(parse_version_directive(
+                    inner_pair,
+                    filename,
+                    source,
+                    doc_name.as_deref().unwrap_or("default"),
+                    doc_start_line,
+                )?);
+            }
+            Rule::currency_directive => {
+                currency = Some(parse_currency_directive(
+                    inner_pair,
+                    filename,
+                    source,
+                    doc_name.as_deref().unwrap_or("default"),
+                    doc_start_line,
+                )?);
+            }
+            Rule::text_directive => {
+                for folding in inner_pair.into_inner() {
+                    if folding.as_str().eq_ignore_ascii_case("case") {
+                        text_comparison.ignore_case = true;
+                    } else {
+                        text_comparison.ignore_accents = true;
+                    }
+                }
+            }
+            Rule::division_directive => {
+                if let Some(policy) = inner_pair.into_inner().next() {
+                    division_by_zero = match policy.as_rule() {
+                        Rule::division_veto => DivisionByZero::Veto(
+                            policy
+                                .into_inner()
+                                .next()
+                                .map(|message| rules::unquote(message.as_str())),
+                        ),
+                        Rule::division_unknown => DivisionByZero::Unknown,
+                        _ => DivisionByZero::Error,
+                    };
+                }
+            }
             Rule::fact_definition => {
                 let fact = crate::parser::facts::parse_fact_definition(inner_pair)?;
                 facts.push(fact);
@@ -158,6 +440,31 @@ fn parse_doc(
                 let rule = crate::parser::rules::parse_rule_definition(inner_pair, id_gen)?;
                 rules.push(rule);
             }
+            Rule::record_definition => {
+                let (record, derived) =
+                    crate::parser::rules::parse_record_definition(inner_pair, id_gen)?;
+                records.push(record);
+                rules.extend(derived);
+            }
+            Rule::checklist_definition => {
+                let (checklist, derived) =
+                    crate::parser::rules::parse_checklist_definition(inner_pair, id_gen)?;
+                checklists.push(checklist);
+                rules.extend(derived);
+            }
+            Rule::check_definition => {
+                let rule = crate::parser::rules::parse_check_definition(inner_pair, id_gen)?;
+                rules.push(rule);
+            }
+            Rule::invariant_definition => {
+                let (invariant, rule) = crate::parser::rules::parse_invariant_definition(
+                    inner_pair,
+                    invariants.len() + 1,
+                    id_gen,
+                )?;
+                invariants.push(invariant);
+                rules.push(rule);
+            }
             _ => {}
         }
     }
@@ -165,11 +472,22 @@ fn parse_doc(
     let name = doc_name.unwrap_or_else(|| "default".to_string());
     let mut doc = LemmaDoc::new(name)
         .with_source(filename.to_string())
-        .with_start_line(doc_start_line);
+        .with_start_line(doc_start_line)
+        .with_partial(partial)
+        .with_parameters(parameters);
 
     if let Some(commentary_text) = commentary {
         doc = doc.set_commentary(commentary_text);
     }
+    if let Some(version) = declared_version {
+        doc = doc.with_declared_version(version);
+    }
+    if let Some(currency) = currency {
+        doc = doc.with_currency(currency);
+    }
+    doc = doc
+        .with_text_comparison(text_comparison)
+        .with_division_by_zero(division_by_zero);
 
     for fact in facts {
         doc = doc.add_fact(fact);
@@ -177,10 +495,73 @@ fn parse_doc(
     for rule in rules {
         doc = doc.add_rule(rule);
     }
+    for checklist in checklists {
+        doc = doc.add_checklist(checklist);
+    }
+    for invariant in invariants {
+        doc = doc.add_invariant(invariant);
+    }
+    for record in records {
+        doc = doc.add_record(record);
+    }
 
     Ok(doc)
 }
 
+fn parse_currency_directive(
+    pair: Pair<Rule>,
+    filename: &str,
+    source: &str,
+    doc_name: &str,
+    doc_start_line: usize,
+) -> Result<MoneyUnit, LemmaError> {
+    let span = Span::from_pair(&pair);
+    let code = pair
+        .into_inner()
+        .next()
+        .map(|unit| unit.as_str().to_string())
+        .unwrap_or_default();
+
+    units::try_parse_money_unit(&code.to_lowercase()).ok_or_else(|| {
+        LemmaError::parse_with_suggestion(
+            format!("Unknown currency '{}' in currency directive", code),
+            span,
+            filename,
+            Arc::from(source),
+            doc_name,
+            doc_start_line,
+            "Use an ISO 4217 currency code such as EUR or USD",
+        )
+    })
+}
+
+fn parse_version_directive(
+    pair: Pair<Rule>,
+    filename: &str,
+    source: &str,
+    doc_name: &str,
+    doc_start_line: usize,
+) -> Result<SemanticVersion, LemmaError> {
+    let span = Span::from_pair(&pair);
+    let text = pair
+        .into_inner()
+        .next()
+        .map(|version| version.as_str().to_string())
+        .unwrap_or_default();
+
+    text.parse().map_err(|message| {
+        LemmaError::parse_with_suggestion(
+            message,
+            span,
+            filename,
+            Arc::from(source),
+            doc_name,
+            doc_start_line,
+            "Write the version as MAJOR.MINOR.PATCH, e.g. version 2.1.0",
+        )
+    })
+}
+
 fn parse_doc_name(pair: Pair<Rule>) -> Result<String, LemmaError> {
     Ok(pair.as_str().to_string())
 }
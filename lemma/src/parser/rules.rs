@@ -1,23 +1,37 @@
 use crate::ast::ExpressionIdGenerator;
 use crate::error::LemmaError;
-use crate::parser::Rule;
+use crate::parser::{LemmaParser, Rule};
 use crate::semantic::*;
 use pest::iterators::Pair;
+use pest::Parser;
 
 pub(crate) fn parse_rule_definition(
     pair: Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Result<LemmaRule, LemmaError> {
-    let span = crate::ast::Span::from_pest_span(pair.as_span());
+    let span = crate::ast::Span::from_pair(&pair);
     let mut rule_name = None;
     let mut rule_expression = None;
+    let mut explicit_mode = None;
+    let mut lookup = false;
+    let mut description = None;
+    let mut visibility = RuleVisibility::default();
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
+            Rule::rule_visibility => visibility = parse_rule_visibility(inner_pair),
             Rule::rule_name => rule_name = Some(inner_pair.as_str().to_string()),
+            Rule::unless_mode => explicit_mode = Some(parse_unless_mode(inner_pair)),
             Rule::rule_expression => {
+                lookup = inner_pair
+                    .clone()
+                    .into_inner()
+                    .any(|p| p.as_rule() == Rule::lookup_expression);
                 rule_expression = Some(parse_rule_expression(inner_pair, id_gen)?)
             }
+            Rule::rule_description_content => {
+                description = Some(inner_pair.as_str().trim().to_string())
+            }
             _ => {}
         }
     }
@@ -29,20 +43,273 @@ pub(crate) fn parse_rule_definition(
         LemmaError::Engine("Grammar error: rule_definition missing rule_expression".to_string())
     })?;
 
+    // The ranges of a lookup are tried in order
+    let unless_mode = match (lookup, explicit_mode) {
+        (true, Some(UnlessMode::LastMatch)) => {
+            return Err(LemmaError::Engine(format!(
+                "Rule '{}' uses 'last match', but a lookup always uses the first matching range",
+                name
+            )))
+        }
+        (true, _) => UnlessMode::FirstMatch,
+        (false, mode) => mode.unwrap_or_default(),
+    };
+
     Ok(LemmaRule {
         name,
         expression,
         unless_clauses,
+        unless_mode,
+        description,
+        visibility,
         span: Some(span),
     })
 }
 
+/// Parse a checklist and the `complete` and `missing` rules derived from it
+pub(crate) fn parse_checklist_definition(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<(LemmaChecklist, Vec<LemmaRule>), LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let mut name = None;
+    let mut items = Vec::new();
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::checklist_name => name = Some(inner_pair.as_str().to_string()),
+            Rule::reference_expression => {
+                items.push(crate::parser::expressions::parse_reference_expression(
+                    inner_pair, id_gen,
+                )?);
+            }
+            _ => {}
+        }
+    }
+
+    let name = name.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: checklist_definition missing checklist_name".to_string())
+    })?;
+    let checklist = LemmaChecklist {
+        name,
+        items,
+        span: Some(span.clone()),
+    };
+
+    // `complete` is a plain conjunction, so it evaluates and inverts like any rule
+    let mut conjunction = checklist.items.iter().cloned();
+    let first = conjunction.next().ok_or_else(|| {
+        LemmaError::Engine("Grammar error: checklist_definition missing items".to_string())
+    })?;
+    let complete = conjunction.fold(first, |left, right| {
+        let kind = ExpressionKind::LogicalAnd(Box::new(left), Box::new(right));
+        Expression::new(kind, Some(span.clone()), id_gen.next_id())
+    });
+    let missing = Expression::new(
+        ExpressionKind::MissingItems(checklist.items.clone()),
+        Some(span.clone()),
+        id_gen.next_id(),
+    );
+
+    let derived = vec![
+        LemmaRule {
+            span: Some(span.clone()),
+            ..LemmaRule::new(checklist.complete_rule_name(), complete)
+        },
+        LemmaRule {
+            span: Some(span),
+            ..LemmaRule::new(checklist.missing_rule_name(), missing)
+        },
+    ];
+    Ok((checklist, derived))
+}
+
+/// Parse a record rule and the rule of each of its fields
+pub(crate) fn parse_record_definition(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<(LemmaRecord, Vec<LemmaRule>), LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let mut name = None;
+    let mut fields = Vec::new();
+    let mut description = None;
+    let mut visibility = RuleVisibility::default();
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::rule_visibility => visibility = parse_rule_visibility(inner_pair),
+            Rule::rule_name => name = Some(inner_pair.as_str().to_string()),
+            Rule::record_field => {
+                let mut field_name = None;
+                let mut expression = None;
+                for field_pair in inner_pair.into_inner() {
+                    match field_pair.as_rule() {
+                        Rule::record_field_name => {
+                            field_name = Some(field_pair.as_str().to_string())
+                        }
+                        Rule::expression_group => {
+                            expression = Some(crate::parser::expressions::parse_or_expression(
+                                field_pair, id_gen,
+                            )?)
+                        }
+                        _ => {}
+                    }
+                }
+                let field_name = field_name.ok_or_else(|| {
+                    LemmaError::Engine(
+                        "Grammar error: record_field missing record_field_name".to_string(),
+                    )
+                })?;
+                let expression = expression.ok_or_else(|| {
+                    LemmaError::Engine("Grammar error: record_field missing expression".to_string())
+                })?;
+                fields.push((field_name, expression));
+            }
+            Rule::rule_description_content => {
+                description = Some(inner_pair.as_str().trim().to_string())
+            }
+            _ => {}
+        }
+    }
+
+    let name = name.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: record_definition missing rule_name".to_string())
+    })?;
+    let record = LemmaRecord {
+        name,
+        fields: fields.iter().map(|(field, _)| field.clone()).collect(),
+        span: Some(span.clone()),
+    };
+    let derived = fields
+        .into_iter()
+        .map(|(field, expression)| LemmaRule {
+            description: description.clone(),
+            visibility,
+            span: Some(span.clone()),
+            ..LemmaRule::new(record.field_rule_name(&field), expression)
+        })
+        .collect();
+    Ok((record, derived))
+}
+
+/// Parse a check into the rule it stands for
+///
+/// `check adult = age >= 18 else veto "must be 18+"` is the rule
+/// `rule adult = unless age >= 18 then true else veto "must be 18+"`, so it
+/// evaluates, inverts and prints like one. Without `else` the veto has no message.
+pub(crate) fn parse_check_definition(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<LemmaRule, LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let mut name = None;
+    let mut condition = None;
+    let mut veto = None;
+    let mut visibility = RuleVisibility::default();
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::rule_visibility => visibility = parse_rule_visibility(inner_pair),
+            Rule::rule_name => name = Some(inner_pair.as_str().to_string()),
+            Rule::expression_group => {
+                condition = Some(crate::parser::expressions::parse_or_expression(
+                    inner_pair, id_gen,
+                )?);
+            }
+            Rule::veto_expression => veto = Some(parse_veto_expression(inner_pair, id_gen)?),
+            _ => {}
+        }
+    }
+
+    let name = name.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: check_definition missing rule_name".to_string())
+    })?;
+    let condition = condition.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: check_definition missing expression_group".to_string())
+    })?;
+    let veto = veto.unwrap_or_else(|| {
+        Expression::new(
+            ExpressionKind::Veto(VetoExpression {
+                message: None,
+                translations: Vec::new(),
+                arguments: Vec::new(),
+            }),
+            Some(span.clone()),
+            id_gen.next_id(),
+        )
+    });
+    let passed = Expression::new(
+        ExpressionKind::Literal(LiteralValue::Boolean(true)),
+        condition.span.clone(),
+        id_gen.next_id(),
+    );
+
+    Ok(LemmaRule {
+        span: Some(span.clone()),
+        visibility,
+        unless_clauses: vec![UnlessClause {
+            condition,
+            result: passed,
+            span: Some(span),
+        }],
+        ..LemmaRule::new(name, veto)
+    })
+}
+
+/// Parse an invariant and the internal rule holding its condition
+pub(crate) fn parse_invariant_definition(
+    pair: Pair<Rule>,
+    number: usize,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<(LemmaInvariant, LemmaRule), LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let condition = pair
+        .into_inner()
+        .find(|inner_pair| inner_pair.as_rule() == Rule::expression_group)
+        .map(|inner_pair| crate::parser::expressions::parse_or_expression(inner_pair, id_gen))
+        .transpose()?
+        .ok_or_else(|| {
+            LemmaError::Engine(
+                "Grammar error: invariant_definition missing expression_group".to_string(),
+            )
+        })?;
+
+    let invariant = LemmaInvariant {
+        number,
+        condition,
+        span: Some(span.clone()),
+    };
+    let rule = LemmaRule {
+        span: Some(span),
+        ..LemmaRule::new(invariant.rule_name(), invariant.condition.clone())
+            .with_visibility(RuleVisibility::Internal)
+    };
+    Ok((invariant, rule))
+}
+
+fn parse_rule_visibility(pair: Pair<Rule>) -> RuleVisibility {
+    if pair.as_str().eq_ignore_ascii_case("internal") {
+        RuleVisibility::Internal
+    } else {
+        RuleVisibility::Public
+    }
+}
+
+fn parse_unless_mode(pair: Pair<Rule>) -> UnlessMode {
+    match pair.into_inner().next().map(|p| p.as_rule()) {
+        Some(Rule::first_match) => UnlessMode::FirstMatch,
+        _ => UnlessMode::LastMatch,
+    }
+}
+
 fn parse_rule_expression(
     pair: Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Result<(Expression, Vec<UnlessClause>), LemmaError> {
     let mut expression = None;
+    let mut else_branch = None;
     let mut unless_clauses = Vec::new();
+    let mut lookup_default = None;
 
     for inner_pair in pair.into_inner() {
         match inner_pair.as_rule() {
@@ -55,10 +322,31 @@ fn parse_rule_expression(
                 let unless_clause = parse_unless_statement(inner_pair, id_gen)?;
                 unless_clauses.push(unless_clause);
             }
+            Rule::lookup_expression => {
+                let (ranges, outside) = parse_lookup_expression(inner_pair, id_gen)?;
+                unless_clauses.extend(ranges);
+                lookup_default = Some(outside);
+            }
+            Rule::else_branch => {
+                let default = inner_pair.into_inner().next().ok_or_else(|| {
+                    LemmaError::Engine("Grammar error: else_branch missing expression".to_string())
+                })?;
+                else_branch = Some(match default.as_rule() {
+                    Rule::veto_expression => parse_veto_expression(default, id_gen)?,
+                    _ => crate::parser::expressions::parse_or_expression(default, id_gen)?,
+                });
+            }
             _ => {}
         }
     }
 
+    // A trailing `else` is the default value, written last; it wins over a leading one
+    if let Some(default) = else_branch {
+        expression = Some(default);
+    }
+    // Values outside every range of a lookup are vetoed, unless there's an `else`
+    let expression = expression.or(lookup_default);
+
     let expr = expression.ok_or_else(|| {
         LemmaError::Engine("Grammar error: rule_expression missing expression_group".to_string())
     })?;
@@ -69,7 +357,7 @@ fn parse_unless_statement(
     pair: Pair<Rule>,
     id_gen: &mut ExpressionIdGenerator,
 ) -> Result<UnlessClause, LemmaError> {
-    let span = crate::ast::Span::from_pest_span(pair.as_span());
+    let span = crate::ast::Span::from_pair(&pair);
     let mut condition = None;
     let mut result = None;
 
@@ -87,19 +375,7 @@ fn parse_unless_statement(
                 }
             }
             Rule::veto_expression => {
-                let veto_span = crate::ast::Span::from_pest_span(inner_pair.as_span());
-                // Pest grammar: ^"veto" ~ (SPACE+ ~ string_literal)?
-                // If string_literal child exists, extract the string content (without quotes)
-                let message = inner_pair
-                    .clone()
-                    .into_inner()
-                    .find(|p| p.as_rule() == Rule::string_literal)
-                    .map(|string_pair| {
-                        let content = string_pair.as_str();
-                        content[1..content.len() - 1].to_string()
-                    });
-                let kind = ExpressionKind::Veto(VetoExpression { message });
-                result = Some(Expression::new(kind, Some(veto_span), id_gen.next_id()));
+                result = Some(parse_veto_expression(inner_pair, id_gen)?);
             }
             _ => {}
         }
@@ -118,3 +394,218 @@ fn parse_unless_statement(
         span: Some(span),
     })
 }
+
+fn parse_veto_expression(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    let veto_span = crate::ast::Span::from_pair(&pair);
+    // Pest grammar: ^"veto" ~ (SPACE+ ~ string_literal ~ (SPACE+ ~ veto_translation)*)?
+    // The direct string_literal child is the default message, each
+    // veto_translation holds a locale tag and its translated message
+    let mut message = None;
+    let mut translations = Vec::new();
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::string_literal => message = Some(unquote(part.as_str())),
+            Rule::veto_translation => {
+                let mut locale = String::new();
+                let mut text = String::new();
+                for p in part.into_inner() {
+                    match p.as_rule() {
+                        Rule::locale_tag => locale = p.as_str().to_string(),
+                        Rule::string_literal => text = unquote(p.as_str()),
+                        _ => {}
+                    }
+                }
+                translations.push(LocalizedMessage {
+                    locale,
+                    message: text,
+                });
+            }
+            _ => {}
+        }
+    }
+    let messages = message
+        .iter()
+        .chain(translations.iter().map(|t| &t.message));
+    let arguments = parse_message_arguments(messages, &veto_span, id_gen)?;
+    let kind = ExpressionKind::Veto(VetoExpression {
+        message,
+        translations,
+        arguments,
+    });
+    Ok(Expression::new(kind, Some(veto_span), id_gen.next_id()))
+}
+
+/// Desugar a lookup into one unless clause per range, and the veto for values
+/// outside every range
+///
+/// `lookup age in (18..64 -> "adult")` becomes
+/// `unless age >= 18 and age <= 64 then "adult"`; the rule tries the clauses
+/// in order (`first match`).
+fn parse_lookup_expression(
+    pair: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<(Vec<UnlessClause>, Expression), LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let mut subject = None;
+    let mut clauses = Vec::new();
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::reference_expression => subject = Some(inner_pair),
+            Rule::lookup_entry => {
+                let subject = subject.clone().ok_or_else(|| {
+                    LemmaError::Engine(
+                        "Grammar error: lookup_expression missing reference".to_string(),
+                    )
+                })?;
+                clauses.push(parse_lookup_entry(inner_pair, subject, id_gen)?);
+            }
+            _ => {}
+        }
+    }
+
+    let subject = subject.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: lookup_expression missing reference".to_string())
+    })?;
+    let outside = Expression::new(
+        ExpressionKind::Veto(VetoExpression {
+            message: Some(format!("{} is outside the lookup ranges", subject.as_str())),
+            translations: Vec::new(),
+            arguments: Vec::new(),
+        }),
+        Some(span),
+        id_gen.next_id(),
+    );
+    Ok((clauses, outside))
+}
+
+fn parse_lookup_entry(
+    pair: Pair<Rule>,
+    subject: Pair<Rule>,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<UnlessClause, LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let mut condition = None;
+    let mut result = None;
+
+    for inner_pair in pair.into_inner() {
+        match inner_pair.as_rule() {
+            Rule::lookup_range => {
+                let range_span = crate::ast::Span::from_pair(&inner_pair);
+                let mut bounds = Vec::new();
+                for bound in inner_pair.into_inner() {
+                    let op = match bound.as_rule() {
+                        Rule::lookup_from => ComparisonOperator::GreaterThanOrEqual,
+                        _ => ComparisonOperator::LessThanOrEqual,
+                    };
+                    bounds.push(parse_lookup_bound(bound, subject.clone(), op, id_gen)?);
+                }
+                let mut bounds = bounds.into_iter();
+                condition = Some(match (bounds.next(), bounds.next()) {
+                    (Some(bound), None) => bound,
+                    (Some(lower), Some(upper)) => Expression::new(
+                        ExpressionKind::LogicalAnd(Box::new(lower), Box::new(upper)),
+                        Some(range_span),
+                        id_gen.next_id(),
+                    ),
+                    // `..` contains every value
+                    _ => Expression::new(
+                        ExpressionKind::Literal(LiteralValue::Boolean(true)),
+                        Some(range_span),
+                        id_gen.next_id(),
+                    ),
+                });
+            }
+            Rule::expression_group => {
+                result = Some(crate::parser::expressions::parse_or_expression(
+                    inner_pair, id_gen,
+                )?);
+            }
+            _ => {}
+        }
+    }
+
+    let condition = condition.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: lookup_entry missing range".to_string())
+    })?;
+    let result = result.ok_or_else(|| {
+        LemmaError::Engine("Grammar error: lookup_entry missing value".to_string())
+    })?;
+    Ok(UnlessClause {
+        condition,
+        result,
+        span: Some(span),
+    })
+}
+
+/// `subject >= bound` or `subject <= bound` for one end of a lookup range
+fn parse_lookup_bound(
+    pair: Pair<Rule>,
+    subject: Pair<Rule>,
+    op: ComparisonOperator,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Expression, LemmaError> {
+    let span = crate::ast::Span::from_pair(&pair);
+    let literal = pair.into_inner().next().ok_or_else(|| {
+        LemmaError::Engine("Grammar error: lookup range missing bound".to_string())
+    })?;
+    let value = Expression::new(
+        ExpressionKind::Literal(crate::parser::literals::parse_literal(literal)?),
+        Some(span.clone()),
+        id_gen.next_id(),
+    );
+    // Parsed again for every bound, so each comparison has its own expression ids
+    let reference = crate::parser::expressions::parse_reference_expression(subject, id_gen)?;
+    Ok(Expression::new(
+        ExpressionKind::Comparison(Box::new(reference), op, Box::new(value)),
+        Some(span),
+        id_gen.next_id(),
+    ))
+}
+
+/// Parse the placeholders of veto messages into the references they name
+///
+/// Each distinct placeholder becomes one argument, pointing at the veto.
+fn parse_message_arguments<'a>(
+    messages: impl Iterator<Item = &'a String>,
+    veto_span: &crate::ast::Span,
+    id_gen: &mut ExpressionIdGenerator,
+) -> Result<Vec<MessageArgument>, LemmaError> {
+    let mut arguments: Vec<MessageArgument> = Vec::new();
+    for message in messages {
+        for part in message_parts(message) {
+            let MessagePart::Placeholder(placeholder) = part else {
+                continue;
+            };
+            if arguments.iter().any(|a| a.placeholder == placeholder) {
+                continue;
+            }
+            let pair = LemmaParser::parse(Rule::reference_expression, placeholder)
+                .ok()
+                .and_then(|mut pairs| pairs.next())
+                .filter(|pair| pair.as_str().len() == placeholder.len())
+                .ok_or_else(|| {
+                    LemmaError::Engine(format!(
+                        "Invalid placeholder '{{{}}}' in veto message: expected a fact or rule reference, write '{{{{{}}}}}' for the braces themselves",
+                        placeholder, placeholder
+                    ))
+                })?;
+            let mut expression =
+                crate::parser::expressions::parse_reference_expression(pair, id_gen)?;
+            expression.span = Some(veto_span.clone());
+            arguments.push(MessageArgument {
+                placeholder: placeholder.to_string(),
+                expression,
+            });
+        }
+    }
+    Ok(arguments)
+}
+
+/// Strip the surrounding quotes from a string literal
+pub(crate) fn unquote(content: &str) -> String {
+    content[1..content.len() - 1].to_string()
+}
@@ -3,6 +3,7 @@
 use crate::error::LemmaError;
 use crate::semantic::*;
 use rust_decimal::Decimal;
+use std::fmt;
 
 /// Resolve a unit string and value to a LiteralValue
 pub fn resolve_unit(value: Decimal, unit_str: &str) -> Result<LiteralValue, LemmaError> {
@@ -65,73 +66,187 @@ pub fn resolve_unit(value: Decimal, unit_str: &str) -> Result<LiteralValue, Lemm
     )))
 }
 
+/// Units of one type, each with the lowercase names it can be written as
+type UnitTable<U> = &'static [(U, &'static [&'static str])];
+
+fn lookup<U: Clone>(table: UnitTable<U>, s: &str) -> Option<U> {
+    table
+        .iter()
+        .find(|(_, names)| names.contains(&s))
+        .map(|(unit, _)| unit.clone())
+}
+
+fn names<U: fmt::Display>(table: UnitTable<U>) -> Vec<String> {
+    table.iter().map(|(unit, _)| unit.to_string()).collect()
+}
+
 // Mass Units
+const MASS_UNITS: UnitTable<MassUnit> = &[
+    (MassUnit::Kilogram, &["kilogram", "kilograms", "kg", "kgs"]),
+    (MassUnit::Gram, &["gram", "grams", "g"]),
+    (MassUnit::Milligram, &["milligram", "milligrams", "mg"]),
+    (MassUnit::Ton, &["ton", "tons", "tonne", "tonnes", "t"]),
+    (MassUnit::Pound, &["pound", "pounds", "lb", "lbs"]),
+    (MassUnit::Ounce, &["ounce", "ounces", "oz"]),
+];
+
 fn try_parse_mass_unit(s: &str) -> Option<MassUnit> {
-    match s {
-        "kilogram" | "kilograms" => Some(MassUnit::Kilogram),
-        "gram" | "grams" => Some(MassUnit::Gram),
-        "milligram" | "milligrams" => Some(MassUnit::Milligram),
-        "ton" | "tons" | "tonne" | "tonnes" => Some(MassUnit::Ton),
-        "pound" | "pounds" => Some(MassUnit::Pound),
-        "ounce" | "ounces" => Some(MassUnit::Ounce),
-        _ => None,
-    }
+    lookup(MASS_UNITS, s)
 }
 
 // Length Units
+const LENGTH_UNITS: UnitTable<LengthUnit> = &[
+    (
+        LengthUnit::Kilometer,
+        &["kilometer", "kilometers", "kilometre", "kilometres", "km"],
+    ),
+    (LengthUnit::Mile, &["mile", "miles", "mi"]),
+    (
+        LengthUnit::NauticalMile,
+        &[
+            "nautical_mile",
+            "nautical_miles",
+            "nauticalmile",
+            "nauticalmiles",
+            "nmi",
+        ],
+    ),
+    (
+        LengthUnit::Meter,
+        &["meter", "meters", "metre", "metres", "m"],
+    ),
+    (
+        LengthUnit::Decimeter,
+        &["decimeter", "decimeters", "decimetre", "decimetres", "dm"],
+    ),
+    (
+        LengthUnit::Centimeter,
+        &[
+            "centimeter",
+            "centimeters",
+            "centimetre",
+            "centimetres",
+            "cm",
+        ],
+    ),
+    (
+        LengthUnit::Millimeter,
+        &[
+            "millimeter",
+            "millimeters",
+            "millimetre",
+            "millimetres",
+            "mm",
+        ],
+    ),
+    (LengthUnit::Yard, &["yard", "yards", "yd"]),
+    (LengthUnit::Foot, &["foot", "feet", "ft"]),
+    (LengthUnit::Inch, &["inch", "inches"]),
+];
+
 fn try_parse_length_unit(s: &str) -> Option<LengthUnit> {
-    match s {
-        "kilometer" | "kilometers" | "kilometre" | "kilometres" => Some(LengthUnit::Kilometer),
-        "mile" | "miles" => Some(LengthUnit::Mile),
-        "nautical_mile" | "nautical_miles" | "nauticalmile" | "nauticalmiles" => {
-            Some(LengthUnit::NauticalMile)
-        }
-        "meter" | "meters" | "metre" | "metres" => Some(LengthUnit::Meter),
-        "decimeter" | "decimeters" | "decimetre" | "decimetres" => Some(LengthUnit::Decimeter),
-        "centimeter" | "centimeters" | "centimetre" | "centimetres" => Some(LengthUnit::Centimeter),
-        "millimeter" | "millimeters" | "millimetre" | "millimetres" => Some(LengthUnit::Millimeter),
-        "yard" | "yards" => Some(LengthUnit::Yard),
-        "foot" | "feet" => Some(LengthUnit::Foot),
-        "inch" | "inches" => Some(LengthUnit::Inch),
-        _ => None,
-    }
+    lookup(LENGTH_UNITS, s)
 }
 
 // Volume Units
+const VOLUME_UNITS: UnitTable<VolumeUnit> = &[
+    (
+        VolumeUnit::CubicMeter,
+        &[
+            "cubic_meter",
+            "cubic_meters",
+            "cubic_metre",
+            "cubic_metres",
+            "cubicmeter",
+            "cubicmeters",
+            "cubicmetre",
+            "cubicmetres",
+            "m3",
+        ],
+    ),
+    (
+        VolumeUnit::CubicCentimeter,
+        &[
+            "cubic_centimeter",
+            "cubic_centimeters",
+            "cubic_centimetre",
+            "cubic_centimetres",
+            "cubiccentimeter",
+            "cubiccentimeters",
+            "cm3",
+        ],
+    ),
+    (
+        VolumeUnit::Liter,
+        &["liter", "liters", "litre", "litres", "l"],
+    ),
+    (
+        VolumeUnit::Deciliter,
+        &["deciliter", "deciliters", "decilitre", "decilitres", "dl"],
+    ),
+    (
+        VolumeUnit::Centiliter,
+        &[
+            "centiliter",
+            "centiliters",
+            "centilitre",
+            "centilitres",
+            "cl",
+        ],
+    ),
+    (
+        VolumeUnit::Milliliter,
+        &[
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+            "ml",
+        ],
+    ),
+    (VolumeUnit::Gallon, &["gallon", "gallons", "gal"]),
+    (VolumeUnit::Quart, &["quart", "quarts", "qt"]),
+    (VolumeUnit::Pint, &["pint", "pints", "pt"]),
+    (
+        VolumeUnit::FluidOunce,
+        &[
+            "fluid_ounce",
+            "fluid_ounces",
+            "fluidounce",
+            "fluidounces",
+            "floz",
+        ],
+    ),
+];
+
 fn try_parse_volume_unit(s: &str) -> Option<VolumeUnit> {
-    match s {
-        "cubic_meter" | "cubic_meters" | "cubic_metre" | "cubic_metres" | "cubicmeter"
-        | "cubicmeters" | "cubicmetre" | "cubicmetres" => Some(VolumeUnit::CubicMeter),
-        "cubic_centimeter" | "cubic_centimeters" | "cubic_centimetre" | "cubic_centimetres"
-        | "cubiccentimeter" | "cubiccentimeters" => Some(VolumeUnit::CubicCentimeter),
-        "liter" | "liters" | "litre" | "litres" => Some(VolumeUnit::Liter),
-        "deciliter" | "deciliters" | "decilitre" | "decilitres" => Some(VolumeUnit::Deciliter),
-        "centiliter" | "centiliters" | "centilitre" | "centilitres" => Some(VolumeUnit::Centiliter),
-        "milliliter" | "milliliters" | "millilitre" | "millilitres" => Some(VolumeUnit::Milliliter),
-        "gallon" | "gallons" => Some(VolumeUnit::Gallon),
-        "quart" | "quarts" => Some(VolumeUnit::Quart),
-        "pint" | "pints" => Some(VolumeUnit::Pint),
-        "fluid_ounce" | "fluid_ounces" | "fluidounce" | "fluidounces" => {
-            Some(VolumeUnit::FluidOunce)
-        }
-        _ => None,
-    }
+    lookup(VOLUME_UNITS, s)
 }
 
 // Duration Units
+const DURATION_UNITS: UnitTable<DurationUnit> = &[
+    (DurationUnit::Year, &["year", "years", "yr", "yrs"]),
+    (DurationUnit::Month, &["month", "months"]),
+    (DurationUnit::Week, &["week", "weeks", "wk", "wks"]),
+    (DurationUnit::Day, &["day", "days", "d"]),
+    (DurationUnit::Hour, &["hour", "hours", "h", "hr", "hrs"]),
+    (DurationUnit::Minute, &["minute", "minutes", "min", "mins"]),
+    (
+        DurationUnit::Second,
+        &["second", "seconds", "s", "sec", "secs"],
+    ),
+    (
+        DurationUnit::Millisecond,
+        &["millisecond", "milliseconds", "ms"],
+    ),
+    (
+        DurationUnit::Microsecond,
+        &["microsecond", "microseconds", "us"],
+    ),
+];
+
 fn try_parse_duration_unit(s: &str) -> Option<DurationUnit> {
-    match s {
-        "year" | "years" => Some(DurationUnit::Year),
-        "month" | "months" => Some(DurationUnit::Month),
-        "week" | "weeks" => Some(DurationUnit::Week),
-        "day" | "days" => Some(DurationUnit::Day),
-        "hour" | "hours" => Some(DurationUnit::Hour),
-        "minute" | "minutes" => Some(DurationUnit::Minute),
-        "second" | "seconds" => Some(DurationUnit::Second),
-        "millisecond" | "milliseconds" => Some(DurationUnit::Millisecond),
-        "microsecond" | "microseconds" => Some(DurationUnit::Microsecond),
-        _ => None,
-    }
+    lookup(DURATION_UNITS, s)
 }
 
 // Duration conversion constants (all relative to seconds)
@@ -165,108 +280,205 @@ pub(crate) fn duration_to_seconds(value: Decimal, unit: &DurationUnit) -> Decima
     }
 }
 
+const TEMPERATURE_UNITS: UnitTable<TemperatureUnit> = &[
+    (TemperatureUnit::Celsius, &["celsius"]),
+    (TemperatureUnit::Fahrenheit, &["fahrenheit"]),
+    (TemperatureUnit::Kelvin, &["kelvin"]),
+];
+
 fn try_parse_temperature_unit(s: &str) -> Option<TemperatureUnit> {
-    match s {
-        "celsius" => Some(TemperatureUnit::Celsius),
-        "fahrenheit" => Some(TemperatureUnit::Fahrenheit),
-        "kelvin" => Some(TemperatureUnit::Kelvin),
-        _ => None,
-    }
+    lookup(TEMPERATURE_UNITS, s)
 }
 
 // Power Units
+const POWER_UNITS: UnitTable<PowerUnit> = &[
+    (PowerUnit::Megawatt, &["megawatt", "megawatts"]),
+    (PowerUnit::Kilowatt, &["kilowatt", "kilowatts", "kw"]),
+    (PowerUnit::Watt, &["watt", "watts", "w"]),
+    (PowerUnit::Milliwatt, &["milliwatt", "milliwatts"]),
+    (PowerUnit::Horsepower, &["horsepower", "hp"]),
+];
+
 fn try_parse_power_unit(s: &str) -> Option<PowerUnit> {
-    match s {
-        "megawatt" | "megawatts" => Some(PowerUnit::Megawatt),
-        "kilowatt" | "kilowatts" => Some(PowerUnit::Kilowatt),
-        "watt" | "watts" => Some(PowerUnit::Watt),
-        "milliwatt" | "milliwatts" => Some(PowerUnit::Milliwatt),
-        "horsepower" => Some(PowerUnit::Horsepower),
-        _ => None,
-    }
+    lookup(POWER_UNITS, s)
 }
 
 // Force Units
+const FORCE_UNITS: UnitTable<ForceUnit> = &[
+    (ForceUnit::Newton, &["newton", "newtons", "n"]),
+    (ForceUnit::Kilonewton, &["kilonewton", "kilonewtons", "kn"]),
+    (ForceUnit::Lbf, &["lbf", "poundforce"]),
+];
+
 fn try_parse_force_unit(s: &str) -> Option<ForceUnit> {
-    match s {
-        "newton" | "newtons" => Some(ForceUnit::Newton),
-        "kilonewton" | "kilonewtons" => Some(ForceUnit::Kilonewton),
-        "lbf" | "poundforce" => Some(ForceUnit::Lbf),
-        _ => None,
-    }
+    lookup(FORCE_UNITS, s)
 }
 
 // Pressure Units
+const PRESSURE_UNITS: UnitTable<PressureUnit> = &[
+    (
+        PressureUnit::Megapascal,
+        &["megapascal", "megapascals", "mpa"],
+    ),
+    (
+        PressureUnit::Kilopascal,
+        &["kilopascal", "kilopascals", "kpa"],
+    ),
+    (PressureUnit::Pascal, &["pascal", "pascals", "pa"]),
+    (
+        PressureUnit::Atmosphere,
+        &["atmosphere", "atmospheres", "atm"],
+    ),
+    (PressureUnit::Bar, &["bar"]),
+    (PressureUnit::Psi, &["psi"]),
+    (PressureUnit::Torr, &["torr"]),
+    (PressureUnit::Mmhg, &["mmhg"]),
+];
+
 fn try_parse_pressure_unit(s: &str) -> Option<PressureUnit> {
-    match s {
-        "megapascal" | "megapascals" => Some(PressureUnit::Megapascal),
-        "kilopascal" | "kilopascals" => Some(PressureUnit::Kilopascal),
-        "pascal" | "pascals" => Some(PressureUnit::Pascal),
-        "atmosphere" | "atmospheres" => Some(PressureUnit::Atmosphere),
-        "bar" => Some(PressureUnit::Bar),
-        "psi" => Some(PressureUnit::Psi),
-        "torr" => Some(PressureUnit::Torr),
-        "mmhg" => Some(PressureUnit::Mmhg),
-        _ => None,
-    }
+    lookup(PRESSURE_UNITS, s)
 }
 
 // Energy Units
+const ENERGY_UNITS: UnitTable<EnergyUnit> = &[
+    (EnergyUnit::Megajoule, &["megajoule", "megajoules", "mj"]),
+    (EnergyUnit::Kilojoule, &["kilojoule", "kilojoules", "kj"]),
+    (EnergyUnit::Joule, &["joule", "joules", "j"]),
+    (
+        EnergyUnit::Kilowatthour,
+        &["kilowatthour", "kilowatthours", "kwh"],
+    ),
+    (EnergyUnit::Watthour, &["watthour", "watthours", "wh"]),
+    (
+        EnergyUnit::Kilocalorie,
+        &["kilocalorie", "kilocalories", "kcal"],
+    ),
+    (EnergyUnit::Calorie, &["calorie", "calories", "cal"]),
+    (EnergyUnit::Btu, &["btu"]),
+];
+
 fn try_parse_energy_unit(s: &str) -> Option<EnergyUnit> {
-    match s {
-        "megajoule" | "megajoules" => Some(EnergyUnit::Megajoule),
-        "kilojoule" | "kilojoules" => Some(EnergyUnit::Kilojoule),
-        "joule" | "joules" => Some(EnergyUnit::Joule),
-        "kilowatthour" | "kilowatthours" => Some(EnergyUnit::Kilowatthour),
-        "watthour" | "watthours" => Some(EnergyUnit::Watthour),
-        "kilocalorie" | "kilocalories" => Some(EnergyUnit::Kilocalorie),
-        "calorie" | "calories" => Some(EnergyUnit::Calorie),
-        "btu" => Some(EnergyUnit::Btu),
-        _ => None,
-    }
+    lookup(ENERGY_UNITS, s)
 }
 
 // Frequency Units
+const FREQUENCY_UNITS: UnitTable<FrequencyUnit> = &[
+    (FrequencyUnit::Hertz, &["hertz", "hz"]),
+    (FrequencyUnit::Kilohertz, &["kilohertz", "khz"]),
+    (FrequencyUnit::Megahertz, &["megahertz", "mhz"]),
+    (FrequencyUnit::Gigahertz, &["gigahertz", "ghz"]),
+];
+
 fn try_parse_frequency_unit(s: &str) -> Option<FrequencyUnit> {
-    match s {
-        "hertz" => Some(FrequencyUnit::Hertz),
-        "kilohertz" => Some(FrequencyUnit::Kilohertz),
-        "megahertz" => Some(FrequencyUnit::Megahertz),
-        "gigahertz" => Some(FrequencyUnit::Gigahertz),
-        _ => None,
-    }
+    lookup(FREQUENCY_UNITS, s)
 }
 
 // Data Size Units
+const DATA_UNITS: UnitTable<DataUnit> = &[
+    (DataUnit::Petabyte, &["petabyte", "petabytes", "pb"]),
+    (DataUnit::Terabyte, &["terabyte", "terabytes", "tb"]),
+    (DataUnit::Gigabyte, &["gigabyte", "gigabytes", "gb"]),
+    (DataUnit::Megabyte, &["megabyte", "megabytes", "mb"]),
+    (DataUnit::Kilobyte, &["kilobyte", "kilobytes", "kb"]),
+    (DataUnit::Byte, &["byte", "bytes", "b"]),
+    (DataUnit::Tebibyte, &["tebibyte", "tebibytes", "tib"]),
+    (DataUnit::Gibibyte, &["gibibyte", "gibibytes", "gib"]),
+    (DataUnit::Mebibyte, &["mebibyte", "mebibytes", "mib"]),
+    (DataUnit::Kibibyte, &["kibibyte", "kibibytes", "kib"]),
+];
+
 fn try_parse_data_size_unit(s: &str) -> Option<DataUnit> {
-    match s {
-        "petabyte" | "petabytes" => Some(DataUnit::Petabyte),
-        "terabyte" | "terabytes" => Some(DataUnit::Terabyte),
-        "gigabyte" | "gigabytes" => Some(DataUnit::Gigabyte),
-        "megabyte" | "megabytes" => Some(DataUnit::Megabyte),
-        "kilobyte" | "kilobytes" => Some(DataUnit::Kilobyte),
-        "byte" | "bytes" => Some(DataUnit::Byte),
-        "tebibyte" | "tebibytes" => Some(DataUnit::Tebibyte),
-        "gibibyte" | "gibibytes" => Some(DataUnit::Gibibyte),
-        "mebibyte" | "mebibytes" => Some(DataUnit::Mebibyte),
-        "kibibyte" | "kibibytes" => Some(DataUnit::Kibibyte),
+    lookup(DATA_UNITS, s)
+}
+
+/// The currency of a symbol written before an amount: `€100`, `$100`, `£50`
+pub(crate) fn currency_of_symbol(symbol: &str) -> Option<MoneyUnit> {
+    match symbol {
+        "€" => Some(MoneyUnit::Eur),
+        "$" => Some(MoneyUnit::Usd),
+        "£" => Some(MoneyUnit::Gbp),
         _ => None,
     }
 }
 
+/// The duration of `5m30s`-style parts, in the smallest unit among them
+///
+/// In a compound duration `m` is minutes, as in `1h30m`.
+pub(crate) fn resolve_compound_duration(
+    parts: &[(Decimal, &str)],
+) -> Result<LiteralValue, LemmaError> {
+    let mut seconds = Decimal::ZERO;
+    let mut smallest: Option<(DurationUnit, Decimal)> = None;
+    for (value, symbol) in parts {
+        let unit = match symbol.to_lowercase().as_str() {
+            "d" => DurationUnit::Day,
+            "h" => DurationUnit::Hour,
+            "m" => DurationUnit::Minute,
+            "s" => DurationUnit::Second,
+            "ms" => DurationUnit::Millisecond,
+            other => {
+                return Err(LemmaError::Engine(format!(
+                    "Unknown duration part: '{}'",
+                    other
+                )))
+            }
+        };
+        let unit_seconds = duration_to_seconds(Decimal::ONE, &unit);
+        seconds += value * unit_seconds;
+        if !matches!(&smallest, Some((_, smallest)) if *smallest <= unit_seconds) {
+            smallest = Some((unit, unit_seconds));
+        }
+    }
+    let (unit, unit_seconds) =
+        smallest.ok_or_else(|| LemmaError::Engine("Empty compound duration".to_string()))?;
+    let value = seconds / unit_seconds;
+    Ok(LiteralValue::Unit(NumericUnit::Duration(
+        value.normalize(),
+        unit,
+    )))
+}
+
 // Money Units (ISO 4217 3-character currency codes only)
-fn try_parse_money_unit(s: &str) -> Option<MoneyUnit> {
-    match s {
-        "eur" => Some(MoneyUnit::Eur),
-        "usd" => Some(MoneyUnit::Usd),
-        "gbp" => Some(MoneyUnit::Gbp),
-        "jpy" => Some(MoneyUnit::Jpy),
-        "cny" => Some(MoneyUnit::Cny),
-        "chf" => Some(MoneyUnit::Chf),
-        "cad" => Some(MoneyUnit::Cad),
-        "aud" => Some(MoneyUnit::Aud),
-        "inr" => Some(MoneyUnit::Inr),
-        _ => None,
+const MONEY_UNITS: UnitTable<MoneyUnit> = &[
+    (MoneyUnit::Eur, &["eur"]),
+    (MoneyUnit::Usd, &["usd"]),
+    (MoneyUnit::Gbp, &["gbp"]),
+    (MoneyUnit::Jpy, &["jpy"]),
+    (MoneyUnit::Cny, &["cny"]),
+    (MoneyUnit::Chf, &["chf"]),
+    (MoneyUnit::Cad, &["cad"]),
+    (MoneyUnit::Aud, &["aud"]),
+    (MoneyUnit::Inr, &["inr"]),
+];
+
+pub(crate) fn try_parse_money_unit(s: &str) -> Option<MoneyUnit> {
+    lookup(MONEY_UNITS, s)
+}
+
+/// Unit names accepted for values of a unit type, one per unit
+///
+/// Plurals, spelling variants and abbreviations (`kg`, `km`, `h`) are
+/// accepted too but not listed. Types without units return an empty list.
+pub fn unit_names(lemma_type: &LemmaType) -> Vec<String> {
+    match lemma_type {
+        LemmaType::Mass => names(MASS_UNITS),
+        LemmaType::Length => names(LENGTH_UNITS),
+        LemmaType::Volume => names(VOLUME_UNITS),
+        LemmaType::Duration => names(DURATION_UNITS),
+        LemmaType::Temperature => names(TEMPERATURE_UNITS),
+        LemmaType::Power => names(POWER_UNITS),
+        LemmaType::Force => names(FORCE_UNITS),
+        LemmaType::Pressure => names(PRESSURE_UNITS),
+        LemmaType::Energy => names(ENERGY_UNITS),
+        LemmaType::Frequency => names(FREQUENCY_UNITS),
+        LemmaType::Data => names(DATA_UNITS),
+        LemmaType::Money => names(MONEY_UNITS),
+        LemmaType::Text
+        | LemmaType::Number
+        | LemmaType::Date
+        | LemmaType::Boolean
+        | LemmaType::Regex
+        | LemmaType::Percentage => Vec::new(),
     }
 }
 
@@ -346,9 +558,6 @@ fn find_closest_unit(s: &str) -> String {
         ("kilogramme", "kilograms"),
         ("gramme", "grams"),
         ("litre", "liters"),
-        ("sec", "seconds"),
-        ("min", "minutes"),
-        ("hr", "hours"),
     ];
 
     for (typo, correct) in &suggestions {
@@ -357,10 +566,5 @@ fn find_closest_unit(s: &str) -> String {
         }
     }
 
-    // Check for common abbreviation issues
-    if s.len() <= 3 {
-        return "Try using the full unit name (e.g., 'kilometers' instead of 'km')".to_string();
-    }
-
     "Check the unit name spelling".to_string()
 }
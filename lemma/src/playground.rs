@@ -0,0 +1,326 @@
+//! State behind the browser playground
+//!
+//! The playground is a set of files being edited, the document picked for
+//! evaluation and the facts filled in. It is kept as a `PlaygroundState`,
+//! which round-trips through a permalink so a page can be shared:
+//!
+//! ```
+//! use lemma::playground::{examples, PlaygroundState};
+//!
+//! let state = examples()[0].state();
+//! let link = state.to_permalink();
+//! assert_eq!(PlaygroundState::from_permalink(&link).unwrap(), state);
+//! ```
+//!
+//! Errors are turned into JSON with a line and column, see `errors_json`,
+//! for the editor to underline. The `wasm` module exposes all of this to
+//! JavaScript as `WasmPlayground`.
+
+use crate::{DocumentLoader, Engine, LemmaError, LemmaResult, LoadedCode, Response};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the permalink fragment, as in `https://lemma.dev/play#w=...`
+pub const PERMALINK_PREFIX: &str = "w=";
+
+/// Version of the serialized state, so old links keep working
+const STATE_VERSION: u32 = 1;
+
+/// A file in the playground
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaygroundFile {
+    pub name: String,
+    pub code: String,
+}
+
+/// Everything a shared playground link restores
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaygroundState {
+    pub files: Vec<PlaygroundFile>,
+    /// The document picked for evaluation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<String>,
+    /// Fact values filled in, in the JSON form `Engine::parse_json_facts` takes
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub facts: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VersionedState {
+    v: u32,
+    #[serde(flatten)]
+    state: PlaygroundState,
+}
+
+impl PlaygroundState {
+    /// Add a file, replacing the one of the same name
+    pub fn set_file(&mut self, name: &str, code: &str) {
+        match self.files.iter_mut().find(|file| file.name == name) {
+            Some(file) => file.code = code.to_string(),
+            None => self.files.push(PlaygroundFile {
+                name: name.to_string(),
+                code: code.to_string(),
+            }),
+        }
+    }
+
+    /// Remove a file; returns whether there was one
+    pub fn remove_file(&mut self, name: &str) -> bool {
+        let before = self.files.len();
+        self.files.retain(|file| file.name != name);
+        self.files.len() != before
+    }
+
+    /// The state as a URL fragment, `w=` followed by the state as base64url JSON
+    pub fn to_permalink(&self) -> String {
+        let versioned = VersionedState {
+            v: STATE_VERSION,
+            state: self.clone(),
+        };
+        let json = serde_json::to_vec(&versioned).unwrap_or_default();
+        format!("{}{}", PERMALINK_PREFIX, base64url_encode(&json))
+    }
+
+    /// Restore the state from a permalink, a fragment or a whole URL
+    pub fn from_permalink(link: &str) -> LemmaResult<Self> {
+        let fragment = link.rsplit('#').next().unwrap_or(link);
+        let encoded = fragment
+            .strip_prefix(PERMALINK_PREFIX)
+            .ok_or_else(|| invalid_link(format!("it doesn't start with '{}'", PERMALINK_PREFIX)))?;
+        let json = base64url_decode(encoded)
+            .ok_or_else(|| invalid_link("it isn't base64url".to_string()))?;
+        let versioned: VersionedState =
+            serde_json::from_slice(&json).map_err(|e| invalid_link(e.to_string()))?;
+        if versioned.v > STATE_VERSION {
+            return Err(invalid_link(format!(
+                "it was made by a newer playground (version {})",
+                versioned.v
+            )));
+        }
+        Ok(versioned.state)
+    }
+
+    /// An engine with every file that loads, and the errors of the others
+    ///
+    /// Files may reference documents in files added after them.
+    pub fn load(&self) -> (Engine, Vec<LemmaError>) {
+        let mut engine = Engine::new().with_document_loader(FilesLoader {
+            files: self.files.clone(),
+        });
+        let errors = self
+            .files
+            .iter()
+            .filter_map(|file| engine.add_lemma_code(&file.code, &file.name).err())
+            .collect();
+        (engine, errors)
+    }
+
+    /// Evaluate the picked document with the facts filled in
+    pub fn evaluate(&self, engine: &Engine) -> LemmaResult<Response> {
+        let doc_name = self
+            .document
+            .as_deref()
+            .ok_or_else(|| LemmaError::Engine("Pick a document to evaluate".to_string()))?;
+        let facts = if self.facts.is_empty() {
+            None
+        } else {
+            let facts = self.facts.clone().into_iter().collect();
+            Some(engine.parse_json_facts(doc_name, &facts)?)
+        };
+        engine.evaluate(doc_name, None, facts)
+    }
+}
+
+/// Serves the playground's files to the engine, for references between them
+struct FilesLoader {
+    files: Vec<PlaygroundFile>,
+}
+
+impl DocumentLoader for FilesLoader {
+    fn load(&self, doc_name: &str) -> LemmaResult<Option<LoadedCode>> {
+        let limits = crate::ResourceLimits::default();
+        Ok(self
+            .files
+            .iter()
+            .find(|file| {
+                crate::parse(&file.code, Some(file.name.clone()), &limits)
+                    .is_ok_and(|docs| docs.iter().any(|doc| doc.name == doc_name))
+            })
+            .map(|file| LoadedCode {
+                source: file.name.clone(),
+                code: file.code.clone(),
+            }))
+    }
+}
+
+/// A bundled example to start from
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    /// Short name, e.g. `tax_calculation`
+    pub name: &'static str,
+    pub files: &'static [(&'static str, &'static str)],
+}
+
+impl Example {
+    /// The example as playground state, with its first document picked
+    pub fn state(&self) -> PlaygroundState {
+        let mut state = PlaygroundState::default();
+        for (name, code) in self.files {
+            state.set_file(name, code);
+        }
+        state.document = state.files.first().and_then(|file| {
+            let limits = crate::ResourceLimits::default();
+            crate::parse(&file.code, Some(file.name.clone()), &limits)
+                .ok()?
+                .first()
+                .map(|doc| doc.name.clone())
+        });
+        state
+    }
+}
+
+macro_rules! example {
+    ($name:literal, $file:literal) => {
+        Example {
+            name: $name,
+            files: &[(
+                $file,
+                include_str!(concat!("../../documentation/examples/", $file)),
+            )],
+        }
+    };
+}
+
+const EXAMPLES: &[Example] = &[
+    example!("simple_facts", "01_simple_facts.lemma"),
+    example!("rules_and_unless", "02_rules_and_unless.lemma"),
+    example!("document_references", "03_document_references.lemma"),
+    example!("unit_conversions", "04_unit_conversions.lemma"),
+    example!("date_handling", "05_date_handling.lemma"),
+    example!("tax_calculation", "06_tax_calculation.lemma"),
+    example!("shipping_policy", "07_shipping_policy.lemma"),
+    example!("rule_references", "08_rule_references.lemma"),
+    example!("compensation_policy", "10_compensation_policy.lemma"),
+];
+
+/// The examples the playground offers, from `documentation/examples`
+pub fn examples() -> &'static [Example] {
+    EXAMPLES
+}
+
+/// The example called `name`
+pub fn example(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+/// Errors as a JSON array of `{kind, message, ...}` objects
+///
+/// Errors in the code carry `file`, `line` and `column` (both 1-based) and
+/// `document`; a `suggestion` is included when there is one. Fact errors
+/// name the `fact`. Collected errors are flattened into the array.
+pub fn errors_json(errors: &[LemmaError]) -> serde_json::Value {
+    let mut entries = Vec::new();
+    for error in errors {
+        push_error_json(error, &mut entries);
+    }
+    serde_json::Value::Array(entries)
+}
+
+fn push_error_json(error: &LemmaError, entries: &mut Vec<serde_json::Value>) {
+    let (kind, details) = match error {
+        LemmaError::Parse(details) => ("parse", details),
+        LemmaError::Semantic(details) => ("semantic", details),
+        LemmaError::Runtime(details) => ("runtime", details),
+        LemmaError::FactParse(fact_errors) => {
+            for fact_error in fact_errors {
+                entries.push(serde_json::json!({
+                    "kind": "fact",
+                    "message": fact_error.message,
+                    "fact": fact_error.fact,
+                    "input": fact_error.input,
+                }));
+            }
+            return;
+        }
+        LemmaError::MultipleErrors(errors) => {
+            for error in errors {
+                push_error_json(error, entries);
+            }
+            return;
+        }
+        LemmaError::Engine(message) => {
+            entries.push(serde_json::json!({ "kind": "engine", "message": message }));
+            return;
+        }
+        other => {
+            entries.push(serde_json::json!({ "kind": "engine", "message": other.to_string() }));
+            return;
+        }
+    };
+    let mut entry = serde_json::json!({
+        "kind": kind,
+        "message": details.message,
+        "file": details.source_id,
+        "document": details.doc_name,
+        "line": details.span.line,
+        "column": details.span.col,
+    });
+    if let Some(suggestion) = &details.suggestion {
+        entry["suggestion"] = serde_json::Value::String(suggestion.clone());
+    }
+    entries.push(entry);
+}
+
+fn invalid_link(reason: String) -> LemmaError {
+    LemmaError::Engine(format!("Invalid playground link: {}", reason))
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Base64 with the URL-safe alphabet and no padding
+fn base64url_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, byte)| n | (u32::from(*byte) << (16 - 8 * i)));
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64URL[((n >> (18 - 6 * i)) & 0x3F) as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn base64url_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut n = 0u32;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        let value = BASE64URL.iter().position(|&b| b == c)? as u32;
+        n = (n << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((n >> bits) as u8);
+            n &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64url_round_trips() {
+        for text in ["", "a", "ab", "abc", "abcd", "doc d\nfact x = 1 ~?"] {
+            let encoded = base64url_encode(text.as_bytes());
+            assert!(!encoded.contains(['+', '/', '=']));
+            assert_eq!(base64url_decode(&encoded).unwrap(), text.as_bytes());
+        }
+        assert_eq!(base64url_encode(b"Man"), "TWFu");
+        assert_eq!(base64url_encode(b"Ma"), "TWE");
+        assert!(base64url_decode("not base64!").is_none());
+    }
+}
@@ -0,0 +1,305 @@
+//! Renaming facts and rules across the workspace
+//!
+//! `rename_edits` finds every place a fact or rule is named: its definition,
+//! local references (`price`, `total?`), references through document
+//! references (`order.price`, `order.total?`), overrides
+//! (`fact order.price = 10`) and aggregates (`sum of items.total?`). Every
+//! source is parsed again, so the edits point into the text each part came
+//! from, also for the parts of a partial document. Names are resolved against
+//! the validated documents.
+
+use crate::parser::{LemmaParser, Rule};
+use crate::{
+    parse, Expression, ExpressionKind, FactType, FactValue, LemmaDoc, LemmaError, LemmaResult,
+    ResourceLimits, Span,
+};
+use pest::Parser;
+use std::collections::HashMap;
+
+/// A replacement of source text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceEdit {
+    /// The source to edit, as passed to `Engine::add_lemma_code` (the file path for `load_dir`)
+    pub source: String,
+    /// The byte range to replace, with the line and column it starts at
+    pub span: Span,
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolKind {
+    Fact,
+    Rule,
+}
+
+/// The edits renaming fact or rule `old_name` of `doc_name` to `new_name`
+///
+/// Fails when the document has no such fact or rule, when `new_name` isn't a
+/// valid name or is taken by another fact or rule of the document. Edits are
+/// ordered by source and position.
+pub fn rename_edits(
+    documents: &HashMap<String, LemmaDoc>,
+    sources: &HashMap<String, String>,
+    limits: &ResourceLimits,
+    doc_name: &str,
+    old_name: &str,
+    new_name: &str,
+) -> LemmaResult<Vec<SourceEdit>> {
+    let doc = documents
+        .get(doc_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+    let kind = symbol_kind(doc, old_name).ok_or_else(|| {
+        LemmaError::Engine(format!(
+            "Document '{}' has no fact or rule named '{}'",
+            doc_name, old_name
+        ))
+    })?;
+    if !is_label(new_name) {
+        return Err(LemmaError::Engine(format!(
+            "'{}' is not a valid name: use a letter followed by letters, digits or underscores, and no keyword",
+            new_name
+        )));
+    }
+    if new_name == old_name {
+        return Ok(Vec::new());
+    }
+    if symbol_kind(doc, new_name).is_some() {
+        return Err(LemmaError::Engine(format!(
+            "Document '{}' already has a fact or rule named '{}'",
+            doc_name, new_name
+        )));
+    }
+
+    let renamer = Renamer {
+        documents,
+        doc_name,
+        kind,
+        old_name,
+        new_name,
+    };
+    let mut source_ids: Vec<&String> = sources.keys().collect();
+    source_ids.sort();
+
+    let mut edits = Vec::new();
+    for source in source_ids {
+        let code = &sources[source];
+        for part in parse(code, Some(source.clone()), limits)? {
+            if documents.contains_key(&part.name) {
+                renamer.collect(&part, source, code, &mut edits)?;
+            }
+        }
+    }
+    edits.sort_by(|a, b| (&a.source, a.span.start).cmp(&(&b.source, b.span.start)));
+    edits.dedup();
+    Ok(edits)
+}
+
+/// Whether `name` is a rule or a local fact of `doc`
+fn symbol_kind(doc: &LemmaDoc, name: &str) -> Option<SymbolKind> {
+    if doc.rules.iter().any(|rule| rule.name == name) {
+        return Some(SymbolKind::Rule);
+    }
+    doc.facts
+        .iter()
+        .any(|fact| matches!(&fact.fact_type, FactType::Local(local) if local == name))
+        .then_some(SymbolKind::Fact)
+}
+
+pub(crate) fn is_label(name: &str) -> bool {
+    LemmaParser::parse(Rule::label, name)
+        .map(|mut pairs| pairs.next().is_some_and(|pair| pair.as_str() == name))
+        .unwrap_or(false)
+}
+
+struct Renamer<'a> {
+    documents: &'a HashMap<String, LemmaDoc>,
+    doc_name: &'a str,
+    kind: SymbolKind,
+    old_name: &'a str,
+    new_name: &'a str,
+}
+
+impl Renamer<'_> {
+    fn collect(
+        &self,
+        part: &LemmaDoc,
+        source: &str,
+        code: &str,
+        edits: &mut Vec<SourceEdit>,
+    ) -> LemmaResult<()> {
+        let mut rename = |span: &Option<Span>, path: &[String], is_rule: bool| {
+            for index in self.renamed_segments(&part.name, path, is_rule) {
+                if let Some(span) = span {
+                    edits.push(self.edit(source, code, span, path, index)?);
+                }
+            }
+            Ok::<(), LemmaError>(())
+        };
+
+        for fact in &part.facts {
+            let path = match &fact.fact_type {
+                FactType::Local(name) => vec![name.clone()],
+                FactType::Foreign(foreign) => foreign.reference.clone(),
+            };
+            rename(&fact.span, &path, false)?;
+        }
+
+        let mut expressions = Vec::new();
+        for rule in &part.rules {
+            rename(&rule.span, std::slice::from_ref(&rule.name), true)?;
+            expressions.push(&rule.expression);
+            for clause in &rule.unless_clauses {
+                expressions.push(&clause.condition);
+                expressions.push(&clause.result);
+            }
+        }
+        for checklist in &part.checklists {
+            expressions.extend(&checklist.items);
+        }
+        while let Some(expression) = expressions.pop() {
+            match &expression.kind {
+                ExpressionKind::FactReference(reference)
+                | ExpressionKind::FactHasAnyValue(reference) => {
+                    rename(&expression.span, &reference.reference, false)?
+                }
+                ExpressionKind::RuleReference(reference)
+                | ExpressionKind::RuleHasValue(reference) => {
+                    rename(&expression.span, &reference.reference, true)?
+                }
+                ExpressionKind::Aggregate(aggregate) => {
+                    let mut path = aggregate.collection.clone();
+                    path.extend(aggregate.rule.iter().chain(&aggregate.fact).cloned());
+                    rename(&expression.span, &path, aggregate.rule.is_some())?
+                }
+                _ => expressions.extend(children(expression)),
+            }
+        }
+        Ok(())
+    }
+
+    /// The positions in `path`, read in document `doc`, that name the renamed symbol
+    ///
+    /// Every segment but the last is a fact referencing a document; the last
+    /// names a rule when `last_is_rule`.
+    fn renamed_segments(&self, doc: &str, path: &[String], last_is_rule: bool) -> Vec<usize> {
+        let mut positions = Vec::new();
+        let mut current = doc;
+        for (index, segment) in path.iter().enumerate() {
+            let name = crate::parse_collection_instance(segment)
+                .map(|(collection, _)| collection)
+                .unwrap_or(segment);
+            let is_last = index + 1 == path.len();
+            let kind = if is_last && last_is_rule {
+                SymbolKind::Rule
+            } else {
+                SymbolKind::Fact
+            };
+            if current == self.doc_name && name == self.old_name && kind == self.kind {
+                positions.push(index);
+            }
+            if is_last {
+                break;
+            }
+            match self.referenced_document(current, name) {
+                Some(referenced) => current = referenced,
+                None => break,
+            }
+        }
+        positions
+    }
+
+    /// The document fact `fact` of `doc` references, or the item document of a collection
+    ///
+    /// Copies of a template are named by their template, whose source they share.
+    fn referenced_document(&self, doc: &str, fact: &str) -> Option<&str> {
+        let referenced = self.documents.get(doc)?.facts.iter().find_map(|declared| {
+            match (&declared.fact_type, &declared.value) {
+                (FactType::Local(name), FactValue::DocumentReference(target))
+                | (FactType::Local(name), FactValue::DocumentCollection(target))
+                    if name == fact =>
+                {
+                    Some(target.as_str())
+                }
+                _ => None,
+            }
+        })?;
+        let template = self.documents.get(referenced)?.template.as_deref();
+        Some(template.unwrap_or(referenced))
+    }
+
+    /// The edit replacing segment `index` of `path`, written within `span`
+    fn edit(
+        &self,
+        source: &str,
+        code: &str,
+        span: &Span,
+        path: &[String],
+        index: usize,
+    ) -> LemmaResult<SourceEdit> {
+        let written = path.join(".");
+        let start = find_path(code, span, &written).ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "Cannot find '{}' in {} at line {}",
+                written, source, span.line
+            ))
+        })?;
+        let start = start
+            + path[..index]
+                .iter()
+                .map(|segment| segment.len() + 1)
+                .sum::<usize>();
+        let end = start + self.old_name.len();
+        let line_start = code[..start].rfind('\n').map_or(0, |i| i + 1);
+
+        Ok(SourceEdit {
+            source: source.to_string(),
+            span: Span {
+                start,
+                end,
+                line: code[..start].matches('\n').count() + 1,
+                col: code[line_start..start].chars().count() + 1,
+            },
+            new_text: self.new_name.to_string(),
+        })
+    }
+}
+
+/// Byte offset of the first whole occurrence of `path` within `span`
+fn find_path(code: &str, span: &Span, path: &str) -> Option<usize> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let text = code.get(span.start..span.end.min(code.len()))?;
+    text.match_indices(path).find_map(|(offset, _)| {
+        let before = text[..offset].chars().next_back();
+        let after = text[offset + path.len()..].chars().next();
+        let whole = !before.is_some_and(|c| is_name_char(c) || c == '.')
+            && !after.is_some_and(|c| is_name_char(c) || c == '[');
+        whole.then_some(span.start + offset)
+    })
+}
+
+pub(crate) fn children(expression: &Expression) -> Vec<&Expression> {
+    match &expression.kind {
+        ExpressionKind::LogicalAnd(left, right)
+        | ExpressionKind::LogicalOr(left, right)
+        | ExpressionKind::Arithmetic(left, _, right)
+        | ExpressionKind::Comparison(left, _, right) => vec![left.as_ref(), right.as_ref()],
+        ExpressionKind::UnitConversion(operand, _)
+        | ExpressionKind::LogicalNegation(operand, _)
+        | ExpressionKind::MathematicalOperator(_, operand) => vec![operand.as_ref()],
+        ExpressionKind::Range(range) => range.operands().to_vec(),
+        ExpressionKind::MissingItems(items)
+        | ExpressionKind::FunctionCall(_, items)
+        | ExpressionKind::Selection(_, items) => items.iter().collect(),
+        ExpressionKind::Veto(veto) => veto
+            .arguments
+            .iter()
+            .map(|argument| &argument.expression)
+            .collect(),
+        ExpressionKind::Literal(_)
+        | ExpressionKind::FactReference(_)
+        | ExpressionKind::RuleReference(_)
+        | ExpressionKind::FactHasAnyValue(_)
+        | ExpressionKind::RuleHasValue(_)
+        | ExpressionKind::Aggregate(_) => Vec::new(),
+    }
+}
@@ -1,3 +1,5 @@
+use crate::numeric_policy::NumericPolicy;
+
 /// Resource limits to prevent abuse and enable predictable memory usage
 ///
 /// These limits protect against malicious inputs while being generous enough
@@ -20,6 +22,18 @@ pub struct ResourceLimits {
     /// Maximum evaluation time in milliseconds
     /// Real usage: ~1-10ms, Limit: 1000ms (100-1000x)
     pub max_evaluation_time_ms: u64,
+
+    /// Maximum length of a rule dependency chain (rule → referenced rule → ...)
+    /// Real usage: ~5 hops, Limit: 100 (20x)
+    pub max_rule_dependency_depth: usize,
+
+    /// Maximum number of document instances reached by one evaluation
+    /// Each reference path counts separately, as does each collection instance
+    /// Real usage: ~1-10, Limit: 1000 (100x+)
+    pub max_documents_per_evaluation: usize,
+
+    /// Precision of division and what arithmetic beyond the largest number gives
+    pub numeric: NumericPolicy,
 }
 
 impl Default for ResourceLimits {
@@ -29,6 +43,9 @@ impl Default for ResourceLimits {
             max_expression_depth: 100,
             max_fact_value_bytes: 1024,   // 1 KB
             max_evaluation_time_ms: 1000, // 1 second
+            max_rule_dependency_depth: 100,
+            max_documents_per_evaluation: 1000,
+            numeric: NumericPolicy::default(),
         }
     }
 }
@@ -9,8 +9,186 @@ use std::collections::HashMap;
 #[derive(Debug, Clone, Serialize)]
 pub struct Response {
     pub doc_name: String,
+    /// Content hash of the evaluated document, see `Engine::document_version`
+    pub doc_version: Option<String>,
     pub results: Vec<RuleResult>,
+    /// Every warning as text, including the diagnostics below
     pub warnings: Vec<String>,
+    /// Data-quality issues noticed during evaluation
+    pub diagnostics: Vec<Diagnostic>,
+    /// The shadow document evaluated alongside, see `EvaluationOptions::with_shadow`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow: Option<ShadowResponse>,
+    /// The resource limit that cut the evaluation short, if one did
+    ///
+    /// The rules evaluated before keep their results; the others are marked
+    /// with `RuleResult::limit_exceeded`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_exceeded: Option<LimitExceeded>,
+}
+
+/// A resource limit hit partway through an evaluation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LimitExceeded {
+    /// The limit, e.g. `max_evaluation_time_ms`
+    pub limit_name: String,
+    /// The rule being evaluated when the limit was hit, e.g. `total` or `order.total`
+    pub rule: Option<String>,
+    pub message: String,
+}
+
+/// A shadow document evaluated with the same inputs as the primary one
+///
+/// Only the primary results are authoritative. The shadow is there to trial
+/// a rule change: a shadow that fails is reported in `error`, it never fails
+/// the evaluation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowResponse {
+    pub doc_name: String,
+    pub doc_version: Option<String>,
+    pub results: Vec<RuleResult>,
+    /// Rules whose value or veto differs from the primary's
+    pub diffs: Vec<ShadowDiff>,
+    /// Why the shadow couldn't be evaluated; there are no results then
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A rule with a different outcome in the shadow document
+///
+/// A rule only one of the documents has counts as having no value in the other.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShadowDiff {
+    pub rule_name: String,
+    pub primary: Option<LiteralValue>,
+    pub shadow: Option<LiteralValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_veto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow_veto: Option<String>,
+}
+
+impl ShadowResponse {
+    /// The shadow's `response`, compared with the primary `results`
+    pub(crate) fn compare(primary: &[RuleResult], response: Response) -> Self {
+        let find = |results: &[RuleResult], name: &str| {
+            results
+                .iter()
+                .find(|result| result.rule_name == name)
+                .map(|result| (result.result.clone(), result.veto_message.clone()))
+                .unwrap_or_default()
+        };
+        let mut names: Vec<&str> = primary.iter().map(|r| r.rule_name.as_str()).collect();
+        for result in &response.results {
+            if !names.contains(&result.rule_name.as_str()) {
+                names.push(&result.rule_name);
+            }
+        }
+
+        let diffs = names
+            .into_iter()
+            .filter_map(|name| {
+                let (primary, primary_veto) = find(primary, name);
+                let (shadow, shadow_veto) = find(&response.results, name);
+                let same_value = match (&primary, &shadow) {
+                    (Some(a), Some(b)) => crate::semantic::value_ops::values_equal(a, b),
+                    (a, b) => a == b,
+                };
+                (!same_value || primary_veto != shadow_veto).then(|| ShadowDiff {
+                    rule_name: name.to_string(),
+                    primary,
+                    shadow,
+                    primary_veto,
+                    shadow_veto,
+                })
+            })
+            .collect();
+
+        Self {
+            doc_name: response.doc_name,
+            doc_version: response.doc_version,
+            results: response.results,
+            diffs,
+            error: None,
+        }
+    }
+
+    /// A shadow that failed to evaluate
+    pub(crate) fn failed(doc_name: &str, error: &crate::LemmaError) -> Self {
+        Self {
+            doc_name: doc_name.to_string(),
+            doc_version: None,
+            results: Vec::new(),
+            diffs: Vec::new(),
+            error: Some(error.to_string()),
+        }
+    }
+}
+
+/// A non-fatal issue noticed during evaluation
+///
+/// Diagnostics never change results; they point at inputs or rules that
+/// probably don't do what was intended.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    /// The rule being evaluated, e.g. `total` or `employee.salary`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// The fact the issue is about, for unused overrides
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fact: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// A deprecated spelling was used, e.g. `reject` for `false`
+    DeprecatedAlias,
+    /// A plain number was read in the unit of the value it meets
+    ImplicitCoercion,
+    /// Units of different kinds were combined into a plain number
+    UnitDegraded,
+    /// An override was given for a fact the document doesn't have
+    UnusedOverride,
+    /// Two percentages were added or subtracted, changing the first relatively
+    AmbiguousPercentage,
+    /// An invariant of the document is false for these facts
+    InvariantViolated,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.rule {
+            Some(rule) => write!(f, "Rule '{}': {}", rule, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Vetoes triggered by a set of inputs, see `Engine::validate_inputs`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InputValidation {
+    pub doc_name: String,
+    /// Rules of the document that vetoed, in evaluation order
+    pub vetoes: Vec<TriggeredVeto>,
+    /// Facts a veto condition needed but no value was given for
+    pub missing_facts: Vec<String>,
+}
+
+/// A rule vetoed by the inputs, with its message if the veto has one
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TriggeredVeto {
+    pub rule: String,
+    pub message: Option<String>,
+}
+
+impl InputValidation {
+    /// Whether the inputs are acceptable: nothing vetoed and nothing missing
+    pub fn is_valid(&self) -> bool {
+        self.vetoes.is_empty() && self.missing_facts.is_empty()
+    }
 }
 
 /// A record of a single operation during evaluation
@@ -34,6 +212,20 @@ pub enum OperationRecord {
         result: LiteralValue,
         unless_clause_index: Option<usize>,
     },
+    /// Remaining operands of an `and`/`or` chain skipped once `value` decided it
+    ShortCircuit {
+        operation: String,
+        value: LiteralValue,
+        skipped_operands: usize,
+    },
+    /// `lowest of` and the like picked `winner`, the candidate at `index` of `inputs`
+    CandidateSelected {
+        operation: String,
+        inputs: Vec<LiteralValue>,
+        winner: String,
+        index: usize,
+        value: LiteralValue,
+    },
     UnlessClauseEvaluated {
         index: usize,
         matched: bool,
@@ -59,14 +251,27 @@ pub struct RuleResult {
     pub missing_facts: Option<Vec<String>>,
     pub veto_message: Option<String>,
     pub operations: Vec<OperationRecord>,
+    /// The rule's description, if it has one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Why the rule has no value when it is unknown, e.g. `division by zero`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unknown: Option<String>,
+    /// The resource limit hit before the rule got a value, see `Response::limit_exceeded`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_exceeded: Option<String>,
 }
 
 impl Response {
     pub fn new(doc_name: String) -> Self {
         Self {
             doc_name,
+            doc_version: None,
             results: Vec::new(),
             warnings: Vec::new(),
+            diagnostics: Vec::new(),
+            shadow: None,
+            limit_exceeded: None,
         }
     }
 
@@ -78,6 +283,14 @@ impl Response {
         self.warnings.push(warning);
     }
 
+    /// Add a diagnostic, also listed in `warnings`; repeats are dropped
+    pub fn add_diagnostic(&mut self, diagnostic: Diagnostic) {
+        if !self.diagnostics.contains(&diagnostic) {
+            self.warnings.push(diagnostic.to_string());
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
     /// Filter results to only include specified rules
     ///
     /// Keeps only the rules whose names are in the provided list.
@@ -100,6 +313,9 @@ impl RuleResult {
             missing_facts: None,
             veto_message: None,
             operations: Vec::new(),
+            description: None,
+            unknown: None,
+            limit_exceeded: None,
         }
     }
 
@@ -116,6 +332,9 @@ impl RuleResult {
             missing_facts: None,
             veto_message: None,
             operations,
+            description: None,
+            unknown: None,
+            limit_exceeded: None,
         }
     }
 
@@ -127,6 +346,9 @@ impl RuleResult {
             missing_facts: None,
             veto_message: None,
             operations: Vec::new(),
+            description: None,
+            unknown: None,
+            limit_exceeded: None,
         }
     }
 
@@ -138,6 +360,9 @@ impl RuleResult {
             missing_facts: Some(facts),
             veto_message: None,
             operations: Vec::new(),
+            description: None,
+            unknown: None,
+            limit_exceeded: None,
         }
     }
 
@@ -149,6 +374,30 @@ impl RuleResult {
             missing_facts: None,
             veto_message: message,
             operations: Vec::new(),
+            description: None,
+            unknown: None,
+            limit_exceeded: None,
+        }
+    }
+
+    /// A rule without a value under `on division by zero unknown`
+    pub fn unknown(rule_name: String, reason: String) -> Self {
+        Self {
+            unknown: Some(reason),
+            ..Self::no_match(rule_name)
+        }
+    }
+
+    /// A rule left without a value because evaluation hit `limit_name`
+    pub fn limit_exceeded(rule_name: String, limit_name: String) -> Self {
+        Self {
+            limit_exceeded: Some(limit_name),
+            ..Self::no_match(rule_name)
         }
     }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
 }
@@ -0,0 +1,343 @@
+//! Scaffolding a document from a JSON Schema
+//!
+//! Turns the properties of an object schema (an API contract, a form
+//! definition) into the fact declarations of a new Lemma document, so rules
+//! can be written against existing contracts straight away.
+//!
+//! - `string` becomes `[text]`, or `[date]` / `[regex]` for the `date`,
+//!   `date-time` and `regex` formats
+//! - `number` and `integer` become `[number]`, `boolean` becomes `[boolean]`
+//! - `x-lemma-type` names the Lemma type directly (`"mass"`, `"money"`), and
+//!   `x-lemma-unit` (or the first of `x-lemma-units`) gives it by unit
+//! - nested objects become fact groups (`fact customer.name = [text]`)
+//! - a `default` becomes the fact's value
+//! - an `enum` adds a rule `valid_<fact>` that vetoes any other value
+//!
+//! Property names are converted to snake case (`orderId` → `order_id`).
+//! Local references (`#/$defs/...`) are followed; arrays are not supported.
+//! Schemas written by `schema::fact_schema` scaffold back to facts with the
+//! same paths and types.
+
+use crate::parser::facts::parse_fact_type_annotation;
+use crate::parser::{LemmaParser, Rule};
+use crate::table::normalize_name;
+use crate::{FactValue, LemmaError, LemmaResult, LemmaType, TypeAnnotation};
+use pest::Parser;
+use rust_decimal::Decimal;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// How deep references and nested objects are followed
+const MAX_SCHEMA_DEPTH: usize = 32;
+
+/// Generate Lemma source code for a document with the facts of `schema`
+pub fn scaffold_document(schema: &Value, doc_name: &str) -> LemmaResult<String> {
+    if !matches_fully(Rule::doc_name, doc_name) {
+        return Err(LemmaError::Engine(format!(
+            "Invalid document name '{}' for scaffolded document",
+            doc_name
+        )));
+    }
+
+    let mut scaffold = Scaffold {
+        root: schema,
+        facts: Vec::new(),
+        rules: Vec::new(),
+        seen: HashSet::new(),
+    };
+    let properties = scaffold.object_properties(schema, "schema", 0)?;
+    scaffold.collect(properties, &[], 0)?;
+
+    let mut code = format!("doc {}\n", doc_name);
+    if let Some(description) = schema.get("description").and_then(Value::as_str) {
+        if !description.contains("\"\"\"") && !description.trim().is_empty() {
+            code.push_str(&format!("\"\"\"\n{}\n\"\"\"\n", description.trim()));
+        }
+    }
+    code.push('\n');
+    for fact in &scaffold.facts {
+        code.push_str(fact);
+        code.push('\n');
+    }
+    for rule in &scaffold.rules {
+        code.push('\n');
+        code.push_str(rule);
+        code.push('\n');
+    }
+    Ok(code)
+}
+
+struct Scaffold<'a> {
+    root: &'a Value,
+    facts: Vec<String>,
+    rules: Vec<String>,
+    seen: HashSet<String>,
+}
+
+impl<'a> Scaffold<'a> {
+    /// The properties of an object schema; `location` names it in errors
+    fn object_properties(
+        &self,
+        schema: &'a Value,
+        location: &str,
+        depth: usize,
+    ) -> LemmaResult<&'a Map<String, Value>> {
+        let schema = self.resolve(schema, location, depth)?;
+        schema
+            .get("properties")
+            .and_then(Value::as_object)
+            .ok_or_else(|| {
+                LemmaError::Engine(format!("{} is not an object with properties", location))
+            })
+    }
+
+    fn collect(
+        &mut self,
+        properties: &'a Map<String, Value>,
+        prefix: &[String],
+        depth: usize,
+    ) -> LemmaResult<()> {
+        for (key, property) in properties {
+            // Dotted keys are fact paths, as written by `schema::fact_schema`
+            let mut path = prefix.to_vec();
+            for segment in key.split('.') {
+                let name = fact_name(segment);
+                if !matches_fully(Rule::label, &name) {
+                    return Err(LemmaError::Engine(format!(
+                        "Property '{}' has no valid fact name",
+                        key
+                    )));
+                }
+                path.push(name);
+            }
+            let location = format!("Property '{}'", prefix_key(prefix, key));
+
+            let property = self.resolve(property, &location, depth)?;
+            if json_type(property) == Some("object") && property.get("properties").is_some() {
+                if depth >= MAX_SCHEMA_DEPTH {
+                    return Err(LemmaError::Engine(format!(
+                        "{} is nested more than {} levels deep",
+                        location, MAX_SCHEMA_DEPTH
+                    )));
+                }
+                let nested = self.object_properties(property, &location, depth + 1)?;
+                self.collect(nested, &path, depth + 1)?;
+                continue;
+            }
+
+            let fact = path.join(".");
+            if !self.seen.insert(fact.clone()) {
+                return Err(LemmaError::Engine(format!(
+                    "{} is a duplicate of fact '{}'",
+                    location, fact
+                )));
+            }
+
+            let lemma_type = lemma_type(property, &location)?;
+            let value = match property.get("default") {
+                Some(default) => render_value(default, &lemma_type, &location)?,
+                None => format!("[{}]", lemma_type),
+            };
+            self.facts.push(format!("fact {} = {}", fact, value));
+
+            if let Some(values) = property.get("enum").and_then(Value::as_array) {
+                self.rules
+                    .push(allowed_values_rule(&fact, values, &lemma_type, &location)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow local `$ref`s to the schema they point at
+    fn resolve(&self, schema: &'a Value, location: &str, depth: usize) -> LemmaResult<&'a Value> {
+        let mut schema = schema;
+        for _ in depth..MAX_SCHEMA_DEPTH {
+            let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+                return Ok(schema);
+            };
+            schema = reference
+                .strip_prefix('#')
+                .and_then(|pointer| self.root.pointer(pointer))
+                .ok_or_else(|| {
+                    LemmaError::Engine(format!(
+                        "{} refers to '{}', which is not in the schema",
+                        location, reference
+                    ))
+                })?;
+        }
+        Err(LemmaError::Engine(format!(
+            "{} has references nested more than {} levels deep",
+            location, MAX_SCHEMA_DEPTH
+        )))
+    }
+}
+
+/// The JSON type of a schema, ignoring `null` in a list of types
+fn json_type(schema: &Value) -> Option<&str> {
+    match schema.get("type")? {
+        Value::String(name) => Some(name),
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null"),
+        _ => None,
+    }
+}
+
+/// The Lemma type for a property schema
+fn lemma_type(schema: &Value, location: &str) -> LemmaResult<LemmaType> {
+    if let Some(name) = schema.get("x-lemma-type").and_then(Value::as_str) {
+        return parse_type_name(name).ok_or_else(|| {
+            LemmaError::Engine(format!("{} has unknown x-lemma-type '{}'", location, name))
+        });
+    }
+
+    let unit = schema
+        .get("x-lemma-unit")
+        .and_then(Value::as_str)
+        .or_else(|| {
+            schema
+                .get("x-lemma-units")
+                .and_then(Value::as_array)
+                .and_then(|units| units.first())
+                .and_then(Value::as_str)
+        });
+    if let Some(unit) = unit {
+        return crate::parse_literal(&format!("1 {}", unit))
+            .map(|value| value.to_type())
+            .map_err(|_| LemmaError::Engine(format!("{} has unknown unit '{}'", location, unit)));
+    }
+
+    let format = schema.get("format").and_then(Value::as_str);
+    match json_type(schema) {
+        Some("string") => Ok(match format {
+            Some("date") | Some("date-time") => LemmaType::Date,
+            Some("regex") => LemmaType::Regex,
+            _ => LemmaType::Text,
+        }),
+        Some("number") | Some("integer") => Ok(LemmaType::Number),
+        Some("boolean") => Ok(LemmaType::Boolean),
+        Some(other) => Err(LemmaError::Engine(format!(
+            "{} has type '{}', which has no fact type",
+            location, other
+        ))),
+        None => Err(LemmaError::Engine(format!("{} has no type", location))),
+    }
+}
+
+fn parse_type_name(name: &str) -> Option<LemmaType> {
+    let annotation = format!("[{}]", name);
+    let pair = LemmaParser::parse(Rule::type_annotation, &annotation)
+        .ok()?
+        .next()
+        .filter(|pair| pair.as_str().len() == annotation.len())?;
+    match parse_fact_type_annotation(pair).ok()? {
+        FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => Some(lemma_type),
+        _ => None,
+    }
+}
+
+/// Render a schema value (a `default` or `enum` entry) as a Lemma literal
+fn render_value(value: &Value, lemma_type: &LemmaType, location: &str) -> LemmaResult<String> {
+    let invalid = || {
+        LemmaError::Engine(format!(
+            "{} has value {}, which is not a valid {}",
+            location, value, lemma_type
+        ))
+    };
+    let rendered = match (lemma_type, value) {
+        (LemmaType::Text, Value::String(text)) => {
+            if text.contains('"') {
+                return Err(LemmaError::Engine(format!(
+                    "{} has text value '{}', which cannot contain double quotes",
+                    location, text
+                )));
+            }
+            format!("\"{}\"", text)
+        }
+        (LemmaType::Number, Value::Number(number)) => number.to_string(),
+        // Percentages are fractions in JSON: 0.21 is 21%
+        (LemmaType::Percentage, Value::Number(number)) => {
+            let fraction = Decimal::from_str(&number.to_string())
+                .or_else(|_| Decimal::from_scientific(&number.to_string()))
+                .map_err(|_| invalid())?;
+            format!("{}%", (fraction * Decimal::ONE_HUNDRED).normalize())
+        }
+        (LemmaType::Boolean, Value::Bool(flag)) => flag.to_string(),
+        (_, Value::String(literal)) => literal.clone(),
+        _ => return Err(invalid()),
+    };
+
+    match crate::parse_literal(&rendered) {
+        Ok(literal) if literal.to_type() == *lemma_type => Ok(rendered),
+        _ => Err(invalid()),
+    }
+}
+
+/// A rule returning the fact, vetoed when it is none of `values`
+fn allowed_values_rule(
+    fact: &str,
+    values: &[Value],
+    lemma_type: &LemmaType,
+    location: &str,
+) -> LemmaResult<String> {
+    let literals = values
+        .iter()
+        .map(|value| render_value(value, lemma_type, location))
+        .collect::<LemmaResult<Vec<_>>>()?;
+    if literals.is_empty() {
+        return Err(LemmaError::Engine(format!(
+            "{} has an empty enum",
+            location
+        )));
+    }
+    let condition = literals
+        .iter()
+        .map(|literal| format!("{} is not {}", fact, literal))
+        .collect::<Vec<_>>()
+        .join(" and ");
+    let listed = literals
+        .iter()
+        .map(|literal| literal.trim_matches('"'))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(format!(
+        "rule valid_{} = {}\n  unless {} then veto \"{} must be one of: {}\"",
+        fact.replace('.', "_"),
+        fact,
+        condition,
+        fact,
+        listed
+    ))
+}
+
+/// Turn a property name (`orderId`, `Order Date`) into a fact name
+fn fact_name(property: &str) -> String {
+    let mut split = String::new();
+    let mut previous_lower = false;
+    for c in property.chars() {
+        if c.is_ascii_uppercase() && previous_lower {
+            split.push('_');
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        split.push(c);
+    }
+    normalize_name(&split)
+}
+
+fn prefix_key(prefix: &[String], key: &str) -> String {
+    prefix
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(key))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn matches_fully(rule: Rule, input: &str) -> bool {
+    LemmaParser::parse(rule, input)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .is_some_and(|pair| pair.as_str().len() == input.len())
+}
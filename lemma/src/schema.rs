@@ -0,0 +1,231 @@
+//! JSON Schema for the fact overrides of a document
+//!
+//! The schema describes the JSON object accepted by `serializers::json`: one
+//! property per overridable fact path (`quantity`, `item.price`), typed the way
+//! that serializer reads it. Facts without a value are required, facts with a
+//! value carry it as `default`, typed facts with an `example` as `examples`. Grouped facts (`customer.name`) are listed
+//! under their dotted path like any other. Every property names its Lemma
+//! type in `x-lemma-type`, unit types list their units in `x-lemma-units`.
+
+use crate::analysis::{fact_group, MAX_INSTANCE_DEPTH};
+use crate::parser::units::unit_names;
+use crate::{FactType, FactValue, LemmaDoc, LemmaType, LiteralValue, TypeAnnotation};
+use rust_decimal::Decimal;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Build the JSON Schema for the overridable facts of `doc`
+///
+/// Facts of referenced documents are included under their path; collections
+/// are left out, their instances are not known up front.
+pub fn fact_schema(doc: &LemmaDoc, all_documents: &HashMap<String, LemmaDoc>) -> Value {
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for fact in overridable_facts(doc, all_documents) {
+        let mut property = type_schema(&fact.lemma_type, fact.doc);
+        let key = fact.path.join(".");
+        match fact.default {
+            Some(value) => {
+                property.insert("default".to_string(), default_value(value));
+            }
+            None => required.push(key.clone()),
+        }
+        if let Some(example) = fact.example {
+            property.insert("examples".to_string(), json!([default_value(example)]));
+        }
+        properties.insert(key, Value::Object(property));
+    }
+
+    let mut schema = Map::new();
+    schema.insert("$schema".to_string(), json!(JSON_SCHEMA_DIALECT));
+    schema.insert("title".to_string(), json!(doc.name));
+    if let Some(commentary) = &doc.commentary {
+        schema.insert("description".to_string(), json!(commentary.trim()));
+    }
+    schema.insert("type".to_string(), json!("object"));
+    schema.insert("properties".to_string(), Value::Object(properties));
+    schema.insert("required".to_string(), json!(required));
+    schema.insert("additionalProperties".to_string(), json!(false));
+    Value::Object(schema)
+}
+
+/// A fact that can be overridden when evaluating a document
+pub(crate) struct OverridableFact<'a> {
+    /// Path from the root document, e.g. `["item", "price"]`
+    pub path: Vec<String>,
+    /// The document declaring the fact
+    pub doc: &'a LemmaDoc,
+    pub lemma_type: LemmaType,
+    /// The fact's own value, or the one an enclosing document gives it
+    pub default: Option<&'a LiteralValue>,
+    pub example: Option<&'a LiteralValue>,
+}
+
+/// The overridable facts of `doc` in declaration order, see `fact_schema`
+pub(crate) fn overridable_facts<'a>(
+    doc: &'a LemmaDoc,
+    all_documents: &'a HashMap<String, LemmaDoc>,
+) -> Vec<OverridableFact<'a>> {
+    let mut facts = Vec::new();
+    collect_facts(doc, &[], &mut Vec::new(), all_documents, &mut facts, 0);
+    facts
+}
+
+/// Add the facts of `doc`, found at `prefix` from the root document
+///
+/// `overrides` holds the values enclosing documents give to nested facts,
+/// outermost first, as paths from the root document.
+fn collect_facts<'a>(
+    doc: &'a LemmaDoc,
+    prefix: &[String],
+    overrides: &mut Vec<(Vec<String>, &'a LiteralValue)>,
+    all_documents: &'a HashMap<String, LemmaDoc>,
+    facts: &mut Vec<OverridableFact<'a>>,
+    depth: usize,
+) {
+    if depth > MAX_INSTANCE_DEPTH {
+        return;
+    }
+
+    let inherited = overrides.len();
+    for fact in &doc.facts {
+        if let (FactType::Foreign(foreign), FactValue::Literal(value)) =
+            (&fact.fact_type, &fact.value)
+        {
+            let path = prefix.iter().chain(&foreign.reference).cloned().collect();
+            overrides.push((path, value));
+        }
+    }
+
+    for fact in &doc.facts {
+        let mut path = prefix.to_vec();
+        match &fact.fact_type {
+            FactType::Local(name) => path.push(name.clone()),
+            FactType::Foreign(foreign) if fact_group(fact, doc).is_some() => {
+                path.extend(foreign.reference.iter().cloned())
+            }
+            FactType::Foreign(_) => continue,
+        }
+
+        let (lemma_type, own_value) = match &fact.value {
+            FactValue::DocumentReference(target) => {
+                if let Some(referenced) = all_documents.get(target) {
+                    collect_facts(
+                        referenced,
+                        &path,
+                        overrides,
+                        all_documents,
+                        facts,
+                        depth + 1,
+                    );
+                }
+                continue;
+            }
+            FactValue::DocumentCollection(_) => continue,
+            FactValue::Literal(value) => (value.to_type(), Some(value)),
+            FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => {
+                (lemma_type.clone(), None)
+            }
+        };
+
+        let default = overrides
+            .iter()
+            .find(|(override_path, _)| *override_path == path)
+            .map(|(_, value)| *value)
+            .or(own_value);
+
+        facts.push(OverridableFact {
+            path,
+            doc,
+            lemma_type,
+            default,
+            example: fact.example.as_ref(),
+        });
+    }
+
+    overrides.truncate(inherited);
+}
+
+/// Schema of one fact value, as `serializers::json` reads it
+fn type_schema(lemma_type: &LemmaType, doc: &LemmaDoc) -> Map<String, Value> {
+    let mut schema = Map::new();
+    match lemma_type {
+        LemmaType::Text => {
+            schema.insert("type".to_string(), json!("string"));
+        }
+        LemmaType::Number => {
+            schema.insert("type".to_string(), json!("number"));
+        }
+        LemmaType::Percentage => {
+            // A fraction: 0.21 is 21%
+            schema.insert("type".to_string(), json!("number"));
+        }
+        LemmaType::Boolean => {
+            schema.insert("type".to_string(), json!("boolean"));
+        }
+        LemmaType::Date => {
+            schema.insert("type".to_string(), json!("string"));
+            schema.insert(
+                "anyOf".to_string(),
+                json!([{ "format": "date" }, { "format": "date-time" }]),
+            );
+        }
+        LemmaType::Regex => {
+            schema.insert("type".to_string(), json!("string"));
+            schema.insert("format".to_string(), json!("regex"));
+        }
+        LemmaType::Mass
+        | LemmaType::Length
+        | LemmaType::Volume
+        | LemmaType::Duration
+        | LemmaType::Temperature
+        | LemmaType::Power
+        | LemmaType::Energy
+        | LemmaType::Force
+        | LemmaType::Pressure
+        | LemmaType::Frequency
+        | LemmaType::Data
+        | LemmaType::Money => {
+            // A number followed by its unit, e.g. "75 kilogram"
+            let units: Vec<String> = match (lemma_type, &doc.currency) {
+                (LemmaType::Money, Some(currency)) => vec![currency.to_string()],
+                _ => unit_names(lemma_type),
+            };
+            schema.insert("type".to_string(), json!("string"));
+            schema.insert(
+                "pattern".to_string(),
+                json!("^-?[0-9]+(\\.[0-9]+)? +[A-Za-z_]+$"),
+            );
+            schema.insert("examples".to_string(), json!([lemma_type.example_value()]));
+            schema.insert("x-lemma-units".to_string(), json!(units));
+        }
+    }
+    schema.insert("x-lemma-type".to_string(), json!(lemma_type.to_string()));
+    schema
+}
+
+/// A fact value in the JSON form `serializers::json` reads back
+fn default_value(value: &LiteralValue) -> Value {
+    match value {
+        LiteralValue::Number(n) => decimal_value(*n),
+        LiteralValue::Percentage(p) => decimal_value(*p / Decimal::ONE_HUNDRED),
+        LiteralValue::Boolean(b) => json!(b),
+        LiteralValue::Text(s) => json!(s),
+        LiteralValue::PercentagePoints(_)
+        | LiteralValue::Date(_)
+        | LiteralValue::Time(_)
+        | LiteralValue::Unit(_)
+        | LiteralValue::Regex(_) => json!(value.to_string()),
+    }
+}
+
+fn decimal_value(value: Decimal) -> Value {
+    value
+        .normalize()
+        .to_string()
+        .parse::<serde_json::Number>()
+        .map(Value::Number)
+        .unwrap_or_else(|_| json!(value.to_string()))
+}
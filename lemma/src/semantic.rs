@@ -1,5 +1,8 @@
+pub mod value_ops;
+
 use crate::ast::{ExpressionId, Span};
 use rust_decimal::Decimal;
+use serde::ser::SerializeMap;
 use serde::Serialize;
 use std::fmt;
 
@@ -9,15 +12,139 @@ pub struct LemmaDoc {
     pub name: String,
     pub source: Option<String>,
     pub start_line: usize,
+    /// Declared as `partial doc`: one part of a document split across files
+    pub partial: bool,
+    /// Parameters of a template, `doc tariff(region, currency)`
+    pub parameters: Vec<String>,
+    /// The template this document is a copy of, named after its arguments: `tariff("EU", EUR)`
+    pub template: Option<String>,
     pub commentary: Option<String>,
+    /// Version of the document's interface (`version 2.1.0`), see `crate::compatibility`
+    pub declared_version: Option<SemanticVersion>,
+    /// The only currency money values may use (`currency EUR`)
+    pub currency: Option<MoneyUnit>,
+    /// How `is`, `is not`, `==` and `!=` compare text (`compare text ignoring case`)
+    pub text_comparison: TextComparison,
+    /// What rules give when they divide by zero (`on division by zero unknown`)
+    pub division_by_zero: DivisionByZero,
     pub facts: Vec<LemmaFact>,
     pub rules: Vec<LemmaRule>,
+    /// Checklists, whose derived rules are part of `rules`
+    pub checklists: Vec<LemmaChecklist>,
+    /// Invariants, whose derived rules are part of `rules`
+    pub invariants: Vec<LemmaInvariant>,
+    /// Record rules, whose field rules are part of `rules`
+    pub records: Vec<LemmaRecord>,
+}
+
+/// A version number `MAJOR.MINOR.PATCH`
+///
+/// Versions order by major, then minor, then patch number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct SemanticVersion {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl SemanticVersion {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for SemanticVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl std::str::FromStr for SemanticVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let numbers: Vec<&str> = s.trim().split('.').collect();
+        let [major, minor, patch] = numbers.as_slice() else {
+            return Err(format!(
+                "Invalid version '{}': expected MAJOR.MINOR.PATCH",
+                s
+            ));
+        };
+        let number = |part: &str| {
+            part.parse::<u64>()
+                .map_err(|_| format!("Invalid version '{}': '{}' is not a number", s, part))
+        };
+        Ok(Self::new(number(major)?, number(minor)?, number(patch)?))
+    }
+}
+
+/// Differences ignored when a document compares text
+///
+/// The default compares text exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct TextComparison {
+    /// `"Gold"` equals `"gold"`
+    pub ignore_case: bool,
+    /// `"café"` equals `"cafe"`
+    pub ignore_accents: bool,
+}
+
+impl TextComparison {
+    pub fn is_exact(&self) -> bool {
+        !self.ignore_case && !self.ignore_accents
+    }
+}
+
+impl fmt::Display for TextComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.ignore_case, self.ignore_accents) {
+            (true, true) => write!(f, "compare text ignoring case and accents"),
+            (true, false) => write!(f, "compare text ignoring case"),
+            (false, true) => write!(f, "compare text ignoring accents"),
+            (false, false) => write!(f, "compare text exactly"),
+        }
+    }
+}
+
+/// What a document's rules give when they divide by zero
+///
+/// The default fails the evaluation with an error.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivisionByZero {
+    #[default]
+    Error,
+    /// The rule is vetoed, with the message if there is one
+    Veto(Option<String>),
+    /// The rule has no value, and neither do the rules using it
+    Unknown,
+}
+
+impl fmt::Display for DivisionByZero {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DivisionByZero::Error => write!(f, "on division by zero error"),
+            DivisionByZero::Veto(Some(message)) => {
+                write!(f, "on division by zero veto \"{}\"", message)
+            }
+            DivisionByZero::Veto(None) => write!(f, "on division by zero veto"),
+            DivisionByZero::Unknown => write!(f, "on division by zero unknown"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LemmaFact {
     pub fact_type: FactType,
     pub value: FactValue,
+    /// Sample value of a typed fact, written as `example ...` after the type
+    pub example: Option<LiteralValue>,
+    /// Written as `sensitive` after the value: its value is masked in traces
+    pub sensitive: bool,
     pub span: Option<Span>,
 }
 
@@ -51,9 +178,132 @@ pub struct LemmaRule {
     pub name: String,
     pub expression: Expression,
     pub unless_clauses: Vec<UnlessClause>,
+    pub unless_mode: UnlessMode,
+    /// Explanation for end users, written as `"""..."""` after the rule
+    pub description: Option<String>,
+    pub visibility: RuleVisibility,
+    pub span: Option<Span>,
+}
+
+/// Whether a rule is exposed to clients or only an intermediate calculation
+///
+/// Internal rules evaluate like any other, but are left out of responses
+/// evaluated with `EvaluationOptions::public_rules_only`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleVisibility {
+    #[default]
+    Public,
+    Internal,
+}
+
+/// Boolean items that must all hold, e.g. the steps of an onboarding
+///
+/// `checklist onboarding = has_id, has_contract?` derives two rules:
+/// `onboarding.complete` is true when every item is true, and
+/// `onboarding.missing` lists the items that aren't, e.g. "has_contract".
+#[derive(Debug, Clone, PartialEq)]
+pub struct LemmaChecklist {
+    pub name: String,
+    /// Fact or rule references, each evaluating to a boolean
+    pub items: Vec<Expression>,
+    pub span: Option<Span>,
+}
+
+impl LemmaChecklist {
+    /// Name of the rule that is true when every item is true
+    pub fn complete_rule_name(&self) -> String {
+        format!("{}.complete", self.name)
+    }
+
+    /// Name of the rule listing the items that aren't true
+    pub fn missing_rule_name(&self) -> String {
+        format!("{}.missing", self.name)
+    }
+
+    /// Whether the rule is one of the rules derived from this checklist
+    pub fn derives(&self, rule_name: &str) -> bool {
+        rule_name == self.complete_rule_name() || rule_name == self.missing_rule_name()
+    }
+}
+
+/// A rule giving several related values, e.g. `rule quote = { total: base + fees, currency: "EUR" }`
+///
+/// Each field is the rule `quote.total`, `quote.currency` and so on, so it is
+/// evaluated once however many rules reference it, as `quote?.total` or
+/// `quote.total?`. The field rules share the record's visibility and description.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LemmaRecord {
+    pub name: String,
+    /// Field names, in the order of the record
+    pub fields: Vec<String>,
+    pub span: Option<Span>,
+}
+
+impl LemmaRecord {
+    /// Name of the rule holding a field
+    pub fn field_rule_name(&self, field: &str) -> String {
+        format!("{}.{}", self.name, field)
+    }
+
+    /// Whether the rule is one of the record's field rules
+    pub fn derives(&self, rule_name: &str) -> bool {
+        rule_name
+            .strip_prefix(self.name.as_str())
+            .and_then(|rest| rest.strip_prefix('.'))
+            .is_some_and(|field| self.fields.iter().any(|f| f == field))
+    }
+}
+
+/// A condition that must hold whatever the facts, e.g. `invariant pricing.total? >= 0 EUR`
+///
+/// The condition is the internal rule `invariant.N`, numbered from 1 in the
+/// order of the document. Evaluating the document reports an
+/// `invariant_violated` diagnostic when it is false, and
+/// `Engine::prove_invariants` looks for facts that would make it false.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LemmaInvariant {
+    pub number: usize,
+    pub condition: Expression,
     pub span: Option<Span>,
 }
 
+impl LemmaInvariant {
+    /// Name of the rule holding the condition
+    pub fn rule_name(&self) -> String {
+        format!("invariant.{}", self.number)
+    }
+}
+
+/// Name of a checklist item as listed by `checklist.missing?`
+pub fn checklist_item_name(item: &Expression) -> String {
+    match &item.kind {
+        ExpressionKind::FactReference(fact_ref) => fact_ref.reference.join("."),
+        ExpressionKind::RuleReference(rule_ref) => rule_ref.reference.join("."),
+        _ => item.to_string(),
+    }
+}
+
+/// Which unless clause wins when several conditions match
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnlessMode {
+    /// The last matching clause wins (the language default)
+    #[default]
+    LastMatch,
+    /// The first matching clause wins (`rule name first match = ...`)
+    FirstMatch,
+}
+
+impl UnlessMode {
+    /// Clause indices in the order they are tried, highest priority first
+    pub fn evaluation_order(&self, clause_count: usize) -> Vec<usize> {
+        match self {
+            UnlessMode::LastMatch => (0..clause_count).rev().collect(),
+            UnlessMode::FirstMatch => (0..clause_count).collect(),
+        }
+    }
+}
+
 /// An expression that can be evaluated, with source location and unique ID
 #[derive(Debug, Clone, PartialEq)]
 pub struct Expression {
@@ -69,6 +319,110 @@ impl Expression {
     }
 }
 
+/// Expressions serialize as a tree of nodes tagged with `type`
+impl Serialize for Expression {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut st = serializer.serialize_map(None)?;
+        match &self.kind {
+            ExpressionKind::Literal(value) => {
+                st.serialize_entry("type", "literal")?;
+                st.serialize_entry("value", value)?;
+                st.serialize_entry("text", &value.to_string())?;
+            }
+            ExpressionKind::FactReference(reference) => {
+                st.serialize_entry("type", "fact")?;
+                st.serialize_entry("reference", reference)?;
+            }
+            ExpressionKind::RuleReference(reference) => {
+                st.serialize_entry("type", "rule")?;
+                st.serialize_entry("reference", &reference.reference.join("."))?;
+            }
+            ExpressionKind::LogicalAnd(left, right) | ExpressionKind::LogicalOr(left, right) => {
+                let kind = match self.kind {
+                    ExpressionKind::LogicalAnd(..) => "and",
+                    _ => "or",
+                };
+                st.serialize_entry("type", kind)?;
+                st.serialize_entry("left", left)?;
+                st.serialize_entry("right", right)?;
+            }
+            ExpressionKind::Arithmetic(left, op, right) => {
+                st.serialize_entry("type", "arithmetic")?;
+                st.serialize_entry("operator", &op.to_string())?;
+                st.serialize_entry("left", left)?;
+                st.serialize_entry("right", right)?;
+            }
+            ExpressionKind::Comparison(left, op, right) => {
+                st.serialize_entry("type", "comparison")?;
+                st.serialize_entry("operator", &op.to_string())?;
+                st.serialize_entry("left", left)?;
+                st.serialize_entry("right", right)?;
+            }
+            ExpressionKind::Range(range) => {
+                st.serialize_entry("type", "range")?;
+                st.serialize_entry("value", &range.value)?;
+                st.serialize_entry("low_operator", &range.low_operator.to_string())?;
+                st.serialize_entry("low", &range.low)?;
+                st.serialize_entry("high_operator", &range.high_operator.to_string())?;
+                st.serialize_entry("high", &range.high)?;
+            }
+            ExpressionKind::FactHasAnyValue(reference) => {
+                st.serialize_entry("type", "has_value")?;
+                st.serialize_entry("fact", reference)?;
+            }
+            ExpressionKind::RuleHasValue(reference) => {
+                st.serialize_entry("type", "has_value")?;
+                st.serialize_entry("rule", &reference.reference.join("."))?;
+            }
+            ExpressionKind::MissingItems(items) => {
+                st.serialize_entry("type", "missing_items")?;
+                st.serialize_entry("items", items)?;
+            }
+            ExpressionKind::Selection(function, candidates) => {
+                st.serialize_entry("type", "selection")?;
+                st.serialize_entry("function", &function.to_string())?;
+                st.serialize_entry("candidates", candidates)?;
+            }
+            ExpressionKind::FunctionCall(name, arguments) => {
+                st.serialize_entry("type", "call")?;
+                st.serialize_entry("name", name)?;
+                st.serialize_entry("arguments", arguments)?;
+            }
+            ExpressionKind::UnitConversion(value, target) => {
+                st.serialize_entry("type", "unit_conversion")?;
+                st.serialize_entry("value", value)?;
+                st.serialize_entry("unit", &target.to_string())?;
+            }
+            ExpressionKind::LogicalNegation(operand, _) => {
+                st.serialize_entry("type", "not")?;
+                st.serialize_entry("operand", operand)?;
+            }
+            ExpressionKind::MathematicalOperator(op, operand) => {
+                st.serialize_entry("type", "function")?;
+                st.serialize_entry("name", &format!("{:?}", op).to_lowercase())?;
+                st.serialize_entry("operand", operand)?;
+            }
+            ExpressionKind::Veto(veto) => {
+                st.serialize_entry("type", "veto")?;
+                st.serialize_entry("message", &veto.message)?;
+            }
+            ExpressionKind::Aggregate(aggregate) => {
+                st.serialize_entry("type", "aggregate")?;
+                st.serialize_entry("function", &aggregate.function.to_string())?;
+                st.serialize_entry("collection", &aggregate.collection.join("."))?;
+                st.serialize_entry("rule", &aggregate.rule)?;
+                if let Some(fact) = &aggregate.fact {
+                    st.serialize_entry("fact", fact)?;
+                }
+            }
+        }
+        st.end()
+    }
+}
+
 /// The kind/type of expression
 #[derive(Debug, Clone, PartialEq)]
 pub enum ExpressionKind {
@@ -79,19 +433,120 @@ pub enum ExpressionKind {
     LogicalOr(Box<Expression>, Box<Expression>),
     Arithmetic(Box<Expression>, ArithmeticOperation, Box<Expression>),
     Comparison(Box<Expression>, ComparisonOperator, Box<Expression>),
+    /// `age between 18 and 65`, `age in 18..65` or `0 < quantity <= 100`
+    Range(Box<RangeCondition>),
     FactHasAnyValue(FactReference),
+    /// `have rule?`: true when the rule produced a value, false when it was vetoed
+    RuleHasValue(RuleReference),
+    /// Names of the checklist items that are false, as comma-separated text
+    MissingItems(Vec<Expression>),
     UnitConversion(Box<Expression>, ConversionTarget),
     LogicalNegation(Box<Expression>, NegationType),
     MathematicalOperator(MathematicalOperator, Box<Expression>),
     Veto(VetoExpression),
+    Aggregate(AggregateExpression),
+    /// `lowest of quote_a?, quote_b?`: the candidate that wins the comparison
+    Selection(SelectionFunction, Vec<Expression>),
+    /// Call of a function registered with `Engine::register_function`: `creditgrade(score)`
+    FunctionCall(String, Vec<Expression>),
+}
+
+/// A value within two bounds, compared with both
+///
+/// Unlike `value >= low and value <= high` written out, the value is evaluated
+/// once and the range is a single condition: a veto in any of the three
+/// operands vetoes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeCondition {
+    pub value: Expression,
+    /// `GreaterThanOrEqual` when the lower bound is included, `GreaterThan` when not
+    pub low_operator: ComparisonOperator,
+    pub low: Expression,
+    /// `LessThanOrEqual` when the upper bound is included, `LessThan` when not
+    pub high_operator: ComparisonOperator,
+    pub high: Expression,
+}
+
+impl RangeCondition {
+    /// Name of the operation in the trace
+    pub fn operation(&self) -> &'static str {
+        match (&self.low_operator, &self.high_operator) {
+            (ComparisonOperator::GreaterThanOrEqual, ComparisonOperator::LessThanOrEqual) => {
+                "between"
+            }
+            (ComparisonOperator::GreaterThan, ComparisonOperator::LessThan) => "strictly between",
+            (ComparisonOperator::GreaterThan, _) => "between, excluding low",
+            _ => "between, excluding high",
+        }
+    }
+
+    /// The value and the bounds, in source order of `value between low and high`
+    pub fn operands(&self) -> [&Expression; 3] {
+        [&self.value, &self.low, &self.high]
+    }
+
+    pub fn operands_mut(&mut self) -> [&mut Expression; 3] {
+        [&mut self.value, &mut self.low, &mut self.high]
+    }
+
+    /// `value >= low and value <= high`, for analyses that reason about comparisons
+    ///
+    /// The comparisons and the conjunction take the span and id of the range.
+    pub fn to_conjunction(&self, span: Option<Span>, id: ExpressionId) -> Expression {
+        let comparison = |operator: &ComparisonOperator, bound: &Expression| {
+            Expression::new(
+                ExpressionKind::Comparison(
+                    Box::new(self.value.clone()),
+                    operator.clone(),
+                    Box::new(bound.clone()),
+                ),
+                span.clone(),
+                id,
+            )
+        };
+        Expression::new(
+            ExpressionKind::LogicalAnd(
+                Box::new(comparison(&self.low_operator, &self.low)),
+                Box::new(comparison(&self.high_operator, &self.high)),
+            ),
+            span.clone(),
+            id,
+        )
+    }
 }
 
 /// Reference to a fact
+///
+/// A reference starting with `caller` reads a fact of the document that references
+/// the current one: in a document referenced through `fact order = doc discounts`,
+/// `caller.quantity` is `quantity` of the referencing document.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FactReference {
     pub reference: Vec<String>, // ["file", "size"]
 }
 
+/// First segment of fact references into the referencing document: `caller.quantity`
+pub const CALLER_KEYWORD: &str = "caller";
+
+impl FactReference {
+    /// The fact path after `caller.`, if this reference reads from the referencing document
+    pub fn caller_fact(&self) -> Option<&[String]> {
+        match self.reference.split_first() {
+            Some((first, rest)) if first == CALLER_KEYWORD && !rest.is_empty() => Some(rest),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for FactReference {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.reference.join("."))
+    }
+}
+
 /// Reference to a rule
 ///
 /// Rule references use a question mark suffix to distinguish them from fact references.
@@ -190,9 +645,182 @@ pub enum NegationType {
 /// validation and constraint enforcement.
 ///
 /// Example: `veto "Must be over 18"` - blocks the rule entirely with a message
+///
+/// Translations of the message can follow the default message, each tagged with
+/// a locale: `veto "too heavy" @nl "te zwaar" @de "zu schwer"`.
+///
+/// Messages can interpolate facts and rules, resolved when the veto applies:
+/// `veto "weight {weight} exceeds {limit?}"`. `{{` and `}}` are literal braces.
 #[derive(Debug, Clone, PartialEq)]
 pub struct VetoExpression {
+    /// The default message, with placeholders as written
     pub message: Option<String>,
+    pub translations: Vec<LocalizedMessage>,
+    /// The references used by placeholders in any of the messages
+    pub arguments: Vec<MessageArgument>,
+}
+
+/// A reference interpolated into a veto message, e.g. `{weight}`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MessageArgument {
+    /// The placeholder text between the braces, trimmed
+    pub placeholder: String,
+    pub expression: Expression,
+}
+
+/// A message translated into a specific locale (e.g. `@nl "te zwaar"`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalizedMessage {
+    pub locale: String,
+    pub message: String,
+}
+
+impl VetoExpression {
+    /// Create a veto expression without translations
+    pub fn new(message: Option<String>) -> Self {
+        Self {
+            message,
+            translations: Vec::new(),
+            arguments: Vec::new(),
+        }
+    }
+
+    /// Select the message for the first of `locales` with a translation,
+    /// falling back to the default message
+    pub fn message_for(&self, locales: &[String]) -> Option<String> {
+        match crate::localization::select_preferred(&self.translations, locales) {
+            Some(message) => Some(message.to_string()),
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// A piece of a veto message: literal text or a `{placeholder}`
+#[derive(Debug, Clone, PartialEq)]
+pub enum MessagePart<'a> {
+    Text(String),
+    Placeholder(&'a str),
+}
+
+/// Split a veto message into text and placeholders
+///
+/// `{{` and `}}` are literal braces. A `{` without a closing brace, or with
+/// something other than a reference before it (`{1 + 1}`), is kept as text.
+pub fn message_parts(message: &str) -> Vec<MessagePart<'_>> {
+    let mut parts = Vec::new();
+    let mut text = String::new();
+    let mut rest = message;
+    while let Some(c) = rest.chars().next() {
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            text.push(c);
+            rest = &rest[2..];
+        } else if let Some(close) = rest
+            .find('}')
+            .filter(|&close| c == '{' && is_reference_like(rest[1..close].trim()))
+        {
+            if !text.is_empty() {
+                parts.push(MessagePart::Text(std::mem::take(&mut text)));
+            }
+            parts.push(MessagePart::Placeholder(rest[1..close].trim()));
+            rest = &rest[close + 1..];
+        } else {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if !text.is_empty() {
+        parts.push(MessagePart::Text(text));
+    }
+    parts
+}
+
+/// Whether `text` is written like a fact or rule reference: `weight`, `order.total?`
+fn is_reference_like(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '?'))
+}
+
+/// Fill in the placeholders of a veto message
+///
+/// Placeholders `value` has no value for are left as written.
+pub fn render_message(message: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    message_parts(message)
+        .into_iter()
+        .map(|part| match part {
+            MessagePart::Text(text) => text,
+            MessagePart::Placeholder(name) => {
+                value(name).unwrap_or_else(|| format!("{{{}}}", name))
+            }
+        })
+        .collect()
+}
+
+/// Whether `message` is `template` as written or with its placeholders filled in
+pub fn message_matches(template: &str, message: &str) -> bool {
+    if template == message {
+        return true;
+    }
+    let mut pattern = String::from("^");
+    for part in message_parts(template) {
+        match part {
+            MessagePart::Text(text) => pattern.push_str(&regex::escape(&text)),
+            MessagePart::Placeholder(_) => pattern.push_str("(?s:.*?)"),
+        }
+    }
+    pattern.push('$');
+    regex::Regex::new(&pattern).is_ok_and(|re| re.is_match(message))
+}
+
+/// Aggregation over all instances of a document collection
+///
+/// Example: `sum of line_items.total?` where `line_items` is a fact with value
+/// `many doc line_item` evaluates `total` once per line item and adds the results.
+/// `count of line_items` counts the instances. `total of line_items.price`
+/// adds a money fact or rule of every instance, in a single currency.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AggregateExpression {
+    pub function: AggregateFunction,
+    /// Fact path to the collection, e.g. ["line_items"] or ["order", "line_items"]
+    pub collection: Vec<String>,
+    /// Rule evaluated per instance, None for `count`
+    pub rule: Option<String>,
+    /// Fact read per instance, only for `total of line_items.price`
+    pub fact: Option<String>,
+}
+
+/// Aggregate functions over collection instances
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AggregateFunction {
+    Sum,
+    Count,
+    Min,
+    Max,
+    /// Sum of money values, all in the same currency
+    Total,
+}
+
+/// Ways to pick one of several candidates, `lowest of a?, b?`
+///
+/// Ties go to the first candidate. `earliest` and `latest` compare dates and
+/// times, `lowest` and `highest` everything else that can be compared.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SelectionFunction {
+    Lowest,
+    Highest,
+    Earliest,
+    Latest,
+}
+
+impl SelectionFunction {
+    /// Whether the candidates are dates and times
+    pub fn selects_dates(&self) -> bool {
+        matches!(
+            self,
+            SelectionFunction::Earliest | SelectionFunction::Latest
+        )
+    }
 }
 
 /// Mathematical operators
@@ -217,6 +845,8 @@ pub enum MathematicalOperator {
 pub enum FactValue {
     Literal(LiteralValue),
     DocumentReference(String),
+    /// Zero or more instances of a document (`many doc line_item`)
+    DocumentCollection(String),
     TypeAnnotation(TypeAnnotation),
 }
 
@@ -248,6 +878,9 @@ pub enum LemmaType {
     Money,
 }
 
+/// Stands in for the values of sensitive facts in traces and listings
+pub const REDACTED: &str = "***";
+
 /// A literal value
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum LiteralValue {
@@ -257,8 +890,9 @@ pub enum LiteralValue {
     Time(TimeValue),     // Standalone time with optional timezone
     Boolean(bool),
     Percentage(Decimal),
-    Unit(NumericUnit), // All physical units and money
-    Regex(String),     // e.g., "/pattern/"
+    PercentagePoints(Decimal), // An additive change of a percentage, e.g. `2 percentage points`
+    Unit(NumericUnit),         // All physical units and money
+    Regex(String),             // e.g., "/pattern/"
 }
 
 impl LiteralValue {
@@ -271,7 +905,9 @@ impl LiteralValue {
     pub fn byte_size(&self) -> usize {
         match self {
             LiteralValue::Text(s) | LiteralValue::Regex(s) => s.len(),
-            LiteralValue::Number(d) | LiteralValue::Percentage(d) => {
+            LiteralValue::Number(d)
+            | LiteralValue::Percentage(d)
+            | LiteralValue::PercentagePoints(d) => {
                 // Decimal internal representation size
                 std::mem::size_of_val(d)
             }
@@ -282,6 +918,15 @@ impl LiteralValue {
         }
     }
 
+    /// The value shown in place of a sensitive fact's value, see `LemmaFact::sensitive`
+    pub fn redacted() -> Self {
+        LiteralValue::Text(REDACTED.to_string())
+    }
+
+    pub fn is_redacted(&self) -> bool {
+        matches!(self, LiteralValue::Text(text) if text == REDACTED)
+    }
+
     /// Convert a LiteralValue to its corresponding LemmaType
     pub fn to_type(&self) -> LemmaType {
         match self {
@@ -290,7 +935,9 @@ impl LiteralValue {
             LiteralValue::Date(_) => LemmaType::Date,
             LiteralValue::Time(_) => LemmaType::Date,
             LiteralValue::Boolean(_) => LemmaType::Boolean,
-            LiteralValue::Percentage(_) => LemmaType::Percentage,
+            LiteralValue::Percentage(_) | LiteralValue::PercentagePoints(_) => {
+                LemmaType::Percentage
+            }
             LiteralValue::Regex(_) => LemmaType::Regex,
             LiteralValue::Unit(unit) => match unit {
                 NumericUnit::Mass(_, _) => LemmaType::Mass,
@@ -326,7 +973,34 @@ pub struct TimezoneValue {
     pub offset_minutes: u8,
 }
 
+/// How precisely a date literal was written
+///
+/// `2026`, `2026-02` and `2026-W08` stand for a whole year, month or ISO week.
+/// Comparisons treat them as that period: `order_date is 2026-02` holds for
+/// any moment in February 2026, `order_date > 2026-02` only from March on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateGranularity {
+    /// A date or date-time, e.g. `2026-02-14`
+    #[default]
+    Exact,
+    /// An ISO week, e.g. `2026-W08`
+    Week,
+    /// A month, e.g. `2026-02`
+    Month,
+    /// A year, e.g. `2026`
+    Year,
+}
+
+impl DateGranularity {
+    pub fn is_exact(&self) -> bool {
+        *self == DateGranularity::Exact
+    }
+}
+
 /// A datetime value that preserves timezone information
+///
+/// For a year, month or week the date is the first day of that period.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct DateTimeValue {
     pub year: i32,
@@ -336,6 +1010,58 @@ pub struct DateTimeValue {
     pub minute: u32,
     pub second: u32,
     pub timezone: Option<TimezoneValue>,
+    #[serde(skip_serializing_if = "DateGranularity::is_exact")]
+    pub granularity: DateGranularity,
+}
+
+impl DateTimeValue {
+    fn period(year: i32, month: u32, day: u32, granularity: DateGranularity) -> Self {
+        DateTimeValue {
+            year,
+            month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            timezone: None,
+            granularity,
+        }
+    }
+
+    /// The whole year `year`, e.g. `2026`
+    pub fn of_year(year: i32) -> Self {
+        Self::period(year, 1, 1, DateGranularity::Year)
+    }
+
+    /// The whole month, e.g. `2026-02`; None for a month outside 1-12
+    pub fn of_month(year: i32, month: u32) -> Option<Self> {
+        (1..=12)
+            .contains(&month)
+            .then(|| Self::period(year, month, 1, DateGranularity::Month))
+    }
+
+    /// The whole ISO week, e.g. `2026-W08`; None for a week the year doesn't have
+    pub fn of_week(year: i32, week: u32) -> Option<Self> {
+        let monday = chrono::NaiveDate::from_isoywd_opt(year, week, chrono::Weekday::Mon)?;
+        Some(Self::period(
+            chrono::Datelike::year(&monday),
+            chrono::Datelike::month(&monday),
+            chrono::Datelike::day(&monday),
+            DateGranularity::Week,
+        ))
+    }
+
+    /// The year a bare number stands for where a date is expected, e.g. `2026`
+    ///
+    /// Only whole numbers with four digits are years.
+    pub fn of_year_number(number: Decimal) -> Option<Self> {
+        if !number.fract().is_zero() {
+            return None;
+        }
+        rust_decimal::prelude::ToPrimitive::to_i32(&number)
+            .filter(|year| (1000..=9999).contains(year))
+            .map(Self::of_year)
+    }
 }
 
 /// Unit types for different physical quantities
@@ -543,6 +1269,24 @@ impl NumericUnit {
         }
     }
 
+    /// The conversion target for this unit, e.g. `kilograms` for `5 kilograms`
+    pub fn conversion_target(&self) -> ConversionTarget {
+        match self {
+            NumericUnit::Mass(_, u) => ConversionTarget::Mass(u.clone()),
+            NumericUnit::Length(_, u) => ConversionTarget::Length(u.clone()),
+            NumericUnit::Volume(_, u) => ConversionTarget::Volume(u.clone()),
+            NumericUnit::Duration(_, u) => ConversionTarget::Duration(u.clone()),
+            NumericUnit::Temperature(_, u) => ConversionTarget::Temperature(u.clone()),
+            NumericUnit::Power(_, u) => ConversionTarget::Power(u.clone()),
+            NumericUnit::Force(_, u) => ConversionTarget::Force(u.clone()),
+            NumericUnit::Pressure(_, u) => ConversionTarget::Pressure(u.clone()),
+            NumericUnit::Energy(_, u) => ConversionTarget::Energy(u.clone()),
+            NumericUnit::Frequency(_, u) => ConversionTarget::Frequency(u.clone()),
+            NumericUnit::Data(_, u) => ConversionTarget::Data(u.clone()),
+            NumericUnit::Money(_, u) => ConversionTarget::Money(u.clone()),
+        }
+    }
+
     /// Check if two units are the same category
     pub fn same_category(&self, other: &NumericUnit) -> bool {
         std::mem::discriminant(self) == std::mem::discriminant(other)
@@ -607,10 +1351,32 @@ impl LemmaRule {
             name,
             expression,
             unless_clauses: Vec::new(),
+            unless_mode: UnlessMode::default(),
+            description: None,
+            visibility: RuleVisibility::default(),
             span: None,
         }
     }
 
+    pub fn with_visibility(mut self, visibility: RuleVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    pub fn is_internal(&self) -> bool {
+        self.visibility == RuleVisibility::Internal
+    }
+
+    pub fn with_description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn with_unless_mode(mut self, unless_mode: UnlessMode) -> Self {
+        self.unless_mode = unless_mode;
+        self
+    }
+
     pub fn add_unless_clause(mut self, unless_clause: UnlessClause) -> Self {
         self.unless_clauses.push(unless_clause);
         self
@@ -622,10 +1388,22 @@ impl LemmaFact {
         Self {
             fact_type,
             value,
+            example: None,
+            sensitive: false,
             span: None,
         }
     }
 
+    pub fn with_sensitive(mut self, sensitive: bool) -> Self {
+        self.sensitive = sensitive;
+        self
+    }
+
+    pub fn with_example(mut self, example: LiteralValue) -> Self {
+        self.example = Some(example);
+        self
+    }
+
     pub fn with_span(mut self, span: Span) -> Self {
         self.span = Some(span);
         self
@@ -638,9 +1416,19 @@ impl LemmaDoc {
             name,
             source: None,
             start_line: 1,
+            partial: false,
+            parameters: Vec::new(),
+            template: None,
             commentary: None,
+            declared_version: None,
+            currency: None,
+            text_comparison: TextComparison::default(),
+            division_by_zero: DivisionByZero::default(),
             facts: Vec::new(),
             rules: Vec::new(),
+            checklists: Vec::new(),
+            invariants: Vec::new(),
+            records: Vec::new(),
         }
     }
 
@@ -654,11 +1442,58 @@ impl LemmaDoc {
         self
     }
 
+    pub fn with_partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
+    }
+
+    pub fn with_parameters(mut self, parameters: Vec<String>) -> Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Whether the document is a template, only evaluated through its instances
+    pub fn is_template(&self) -> bool {
+        !self.parameters.is_empty()
+    }
+
     pub fn set_commentary(mut self, commentary: String) -> Self {
         self.commentary = Some(commentary);
         self
     }
 
+    pub fn with_declared_version(mut self, version: SemanticVersion) -> Self {
+        self.declared_version = Some(version);
+        self
+    }
+
+    pub fn with_currency(mut self, currency: MoneyUnit) -> Self {
+        self.currency = Some(currency);
+        self
+    }
+
+    pub fn with_text_comparison(mut self, text_comparison: TextComparison) -> Self {
+        self.text_comparison = text_comparison;
+        self
+    }
+
+    pub fn with_division_by_zero(mut self, division_by_zero: DivisionByZero) -> Self {
+        self.division_by_zero = division_by_zero;
+        self
+    }
+
+    /// Turn a number given for a money fact into money in the document currency
+    ///
+    /// Returns None when the document has no currency or the value needs no coercion.
+    pub fn coerce_to_currency(&self, value: &LiteralValue) -> Option<LiteralValue> {
+        match (&self.currency, value) {
+            (Some(currency), LiteralValue::Number(n)) => {
+                Some(LiteralValue::Unit(NumericUnit::Money(*n, currency.clone())))
+            }
+            _ => None,
+        }
+    }
+
     pub fn add_fact(mut self, fact: LemmaFact) -> Self {
         self.facts.push(fact);
         self
@@ -669,6 +1504,38 @@ impl LemmaDoc {
         self
     }
 
+    /// Add a checklist; its derived rules are added with `add_rule`
+    pub fn add_checklist(mut self, checklist: LemmaChecklist) -> Self {
+        self.checklists.push(checklist);
+        self
+    }
+
+    /// Add an invariant; its rule is added with `add_rule`
+    pub fn add_invariant(mut self, invariant: LemmaInvariant) -> Self {
+        self.invariants.push(invariant);
+        self
+    }
+
+    /// Add a record rule; its field rules are added with `add_rule`
+    pub fn add_record(mut self, record: LemmaRecord) -> Self {
+        self.records.push(record);
+        self
+    }
+
+    /// Whether the rule is derived from a checklist, invariant or record
+    pub fn is_derived_rule(&self, rule_name: &str) -> bool {
+        self.checklists.iter().any(|c| c.derives(rule_name))
+            || self.invariant_of(rule_name).is_some()
+            || self.records.iter().any(|r| r.derives(rule_name))
+    }
+
+    /// The invariant whose condition is the rule `rule_name`
+    pub fn invariant_of(&self, rule_name: &str) -> Option<&LemmaInvariant> {
+        self.invariants
+            .iter()
+            .find(|invariant| invariant.rule_name() == rule_name)
+    }
+
     /// Get the expected type for a fact by path
     /// Returns None if the fact is not found in this document or if the fact is a document reference
     pub fn get_fact_type(&self, fact_ref: &FactReference) -> Option<LemmaType> {
@@ -685,7 +1552,7 @@ impl LemmaDoc {
                 FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => {
                     Some(lemma_type.clone())
                 }
-                FactValue::DocumentReference(_) => {
+                FactValue::DocumentReference(_) | FactValue::DocumentCollection(_) => {
                     // Document references don't have a single type
                     // They import all facts from the referenced document
                     None
@@ -696,7 +1563,13 @@ impl LemmaDoc {
 
 impl fmt::Display for LemmaDoc {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.partial {
+            write!(f, "partial ")?;
+        }
         write!(f, "doc {}", self.name)?;
+        if self.is_template() {
+            write!(f, "({})", self.parameters.join(", "))?;
+        }
         writeln!(f)?;
 
         if let Some(ref commentary) = self.commentary {
@@ -704,27 +1577,124 @@ impl fmt::Display for LemmaDoc {
             writeln!(f, "\"\"\"")?;
         }
 
+        if let Some(version) = self.declared_version {
+            writeln!(f, "version {}", version)?;
+        }
+
+        if let Some(ref currency) = self.currency {
+            writeln!(f, "currency {}", currency)?;
+        }
+
+        if !self.text_comparison.is_exact() {
+            writeln!(f, "{}", self.text_comparison)?;
+        }
+
+        if self.division_by_zero != DivisionByZero::Error {
+            writeln!(f, "{}", self.division_by_zero)?;
+        }
+
         for fact in &self.facts {
             write!(f, "{}", fact)?;
         }
 
+        for checklist in &self.checklists {
+            write!(f, "{}", checklist)?;
+        }
+
+        // Rules derived from a checklist, invariant or record are written as those
         for rule in &self.rules {
-            write!(f, "{}", rule)?;
+            if !self.is_derived_rule(&rule.name) {
+                write!(f, "{}", rule)?;
+            }
+        }
+
+        for record in &self.records {
+            self.write_record(record, f)?;
+        }
+
+        for invariant in &self.invariants {
+            writeln!(f, "invariant {}", invariant.condition)?;
         }
 
         Ok(())
     }
 }
 
+impl LemmaDoc {
+    /// A record rule as Lemma code, written from its field rules
+    pub fn record_code(&self, record: &LemmaRecord) -> String {
+        let mut code = String::new();
+        let _ = self.write_record(record, &mut code);
+        code
+    }
+
+    fn write_record(&self, record: &LemmaRecord, f: &mut impl fmt::Write) -> fmt::Result {
+        let fields: Vec<&LemmaRule> = record
+            .fields
+            .iter()
+            .filter_map(|field| {
+                let name = record.field_rule_name(field);
+                self.rules.iter().find(|rule| rule.name == name)
+            })
+            .collect();
+        let Some(first) = fields.first() else {
+            return Ok(());
+        };
+        if first.is_internal() {
+            write!(f, "internal ")?;
+        }
+        write!(f, "rule {} = {{ ", record.name)?;
+        for (index, (field, rule)) in record.fields.iter().zip(&fields).enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", field, rule.expression)?;
+        }
+        write!(f, " }}")?;
+        if let Some(description) = &first.description {
+            write!(f, " \"\"\"{}\"\"\"", description)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl fmt::Display for LemmaChecklist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items: Vec<String> = self.items.iter().map(|item| item.to_string()).collect();
+        writeln!(f, "checklist {} = {}", self.name, items.join(", "))
+    }
+}
+
 impl fmt::Display for LemmaFact {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        writeln!(f, "fact {} = {}", self.fact_type, self.value)
+        write!(f, "fact {} = {}", self.fact_type, self.value)?;
+        if let Some(example) = &self.example {
+            write!(f, " example {}", example)?;
+        }
+        if self.sensitive {
+            write!(f, " sensitive")?;
+        }
+        writeln!(f)
     }
 }
 
 impl fmt::Display for LemmaRule {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "rule {} = {}", self.name, self.expression)?;
+        if self.is_internal() {
+            write!(f, "internal ")?;
+        }
+        write!(f, "rule {}", self.name)?;
+        if self.unless_mode == UnlessMode::FirstMatch {
+            write!(f, " first match")?;
+        }
+        // A vetoing default can only be written last, as the `else` branch
+        let veto_default = matches!(self.expression.kind, ExpressionKind::Veto(_))
+            && !self.unless_clauses.is_empty();
+        if !veto_default {
+            write!(f, " = {}", self.expression)?;
+        } else {
+            write!(f, " =")?;
+        }
 
         for unless_clause in &self.unless_clauses {
             write!(
@@ -733,29 +1703,137 @@ impl fmt::Display for LemmaRule {
                 unless_clause.condition, unless_clause.result
             )?;
         }
+        if veto_default {
+            write!(f, " else {}", self.expression)?;
+        }
+        if let Some(description) = &self.description {
+            write!(f, " \"\"\"{}\"\"\"", description)?;
+        }
 
         writeln!(f)?;
         Ok(())
     }
 }
 
+impl Expression {
+    /// How tightly the expression binds when written out, following the grammar
+    ///
+    /// Operands that bind looser than their operator are written in
+    /// parentheses. Expressions ending in an open list of operands, like
+    /// `sqrt x` or `lowest of a, b`, bind loosest so they are always
+    /// parenthesized as operands.
+    fn binding(&self) -> u8 {
+        match &self.kind {
+            ExpressionKind::MathematicalOperator(..)
+            | ExpressionKind::Selection(..)
+            | ExpressionKind::MissingItems(..) => 0,
+            ExpressionKind::LogicalOr(..) => 1,
+            ExpressionKind::LogicalAnd(..) => 2,
+            ExpressionKind::LogicalNegation(..)
+            | ExpressionKind::FactHasAnyValue(..)
+            | ExpressionKind::RuleHasValue(..) => 3,
+            ExpressionKind::Comparison(..) | ExpressionKind::Range(..) => 4,
+            ExpressionKind::UnitConversion(..) => 5,
+            ExpressionKind::Arithmetic(_, op, _) => match op {
+                ArithmeticOperation::Add | ArithmeticOperation::Subtract => 6,
+                ArithmeticOperation::Multiply
+                | ArithmeticOperation::Divide
+                | ArithmeticOperation::Modulo => 7,
+                ArithmeticOperation::Power => 8,
+            },
+            ExpressionKind::Literal(..)
+            | ExpressionKind::FactReference(..)
+            | ExpressionKind::RuleReference(..)
+            | ExpressionKind::Veto(..)
+            | ExpressionKind::Aggregate(..)
+            | ExpressionKind::FunctionCall(..) => ATOM_BINDING,
+        }
+    }
+}
+
+const ATOM_BINDING: u8 = 9;
+
+/// An operand written in parentheses when it binds looser than `min_binding`
+struct Operand<'a>(&'a Expression, u8);
+
+impl fmt::Display for Operand<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Operand(expr, min_binding) = self;
+        if expr.binding() < *min_binding {
+            write!(f, "({})", expr)
+        } else {
+            write!(f, "{}", expr)
+        }
+    }
+}
+
 impl fmt::Display for Expression {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let binding = self.binding();
         match &self.kind {
             ExpressionKind::Literal(lit) => write!(f, "{}", lit),
             ExpressionKind::FactReference(fact_ref) => write!(f, "{}", fact_ref),
             ExpressionKind::RuleReference(rule_ref) => write!(f, "{}", rule_ref),
             ExpressionKind::Arithmetic(left, op, right) => {
-                write!(f, "{} {} {}", left, op, right)
+                // `^` groups to the right, the other operators to the left
+                let (left_binding, right_binding) = match op {
+                    ArithmeticOperation::Power => (binding + 1, binding),
+                    _ => (binding, binding + 1),
+                };
+                write!(
+                    f,
+                    "{} {} {}",
+                    Operand(left, left_binding),
+                    op,
+                    Operand(right, right_binding)
+                )
             }
             ExpressionKind::Comparison(left, op, right) => {
-                write!(f, "{} {} {}", left, op, right)
+                write!(
+                    f,
+                    "{} {} {}",
+                    Operand(left, binding + 1),
+                    op,
+                    Operand(right, binding + 1)
+                )
+            }
+            ExpressionKind::Range(range) => {
+                let (value, low, high) = (
+                    Operand(&range.value, binding + 1),
+                    Operand(&range.low, binding + 1),
+                    Operand(&range.high, binding + 1),
+                );
+                if range.operation() == "between" {
+                    write!(f, "{} between {} and {}", value, low, high)
+                } else {
+                    // `0 < quantity <= 100`: the lower bound's operator flipped
+                    let low_operator = match range.low_operator {
+                        ComparisonOperator::GreaterThan => ComparisonOperator::LessThan,
+                        _ => ComparisonOperator::LessThanOrEqual,
+                    };
+                    write!(
+                        f,
+                        "{} {} {} {} {}",
+                        low, low_operator, value, range.high_operator, high
+                    )
+                }
             }
             ExpressionKind::FactHasAnyValue(fact_ref) => {
                 write!(f, "have {}", fact_ref)
             }
+            ExpressionKind::RuleHasValue(rule_ref) => {
+                write!(f, "have {}", rule_ref)
+            }
+            ExpressionKind::MissingItems(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "missing of {}", items.join(", "))
+            }
+            ExpressionKind::FunctionCall(name, arguments) => {
+                let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+                write!(f, "{}({})", name, arguments.join(", "))
+            }
             ExpressionKind::UnitConversion(value, target) => {
-                write!(f, "{} in {}", value, target)
+                write!(f, "{} in {}", Operand(value, binding + 1), target)
             }
             ExpressionKind::LogicalNegation(expr, negation_type) => {
                 let prefix = match negation_type {
@@ -763,36 +1841,88 @@ impl fmt::Display for Expression {
                     NegationType::HaveNot => "have not",
                     NegationType::NotHave => "not have",
                 };
-                write!(f, "{} {}", prefix, expr)
+                match (&expr.kind, negation_type) {
+                    // `have not rule?` already reads as a value check
+                    (ExpressionKind::RuleHasValue(rule_ref), NegationType::HaveNot)
+                    | (ExpressionKind::RuleHasValue(rule_ref), NegationType::NotHave) => {
+                        write!(f, "{} {}", prefix, rule_ref)
+                    }
+                    _ => write!(f, "{} {}", prefix, Operand(expr, ATOM_BINDING)),
+                }
             }
             ExpressionKind::LogicalAnd(left, right) => {
-                write!(f, "{} and {}", left, right)
+                write!(
+                    f,
+                    "{} and {}",
+                    Operand(left, binding),
+                    Operand(right, binding + 1)
+                )
             }
             ExpressionKind::LogicalOr(left, right) => {
-                write!(f, "{} or {}", left, right)
+                write!(
+                    f,
+                    "{} or {}",
+                    Operand(left, binding),
+                    Operand(right, binding + 1)
+                )
             }
             ExpressionKind::MathematicalOperator(op, operand) => {
-                let op_name = match op {
-                    MathematicalOperator::Sqrt => "sqrt",
-                    MathematicalOperator::Sin => "sin",
-                    MathematicalOperator::Cos => "cos",
-                    MathematicalOperator::Tan => "tan",
-                    MathematicalOperator::Asin => "asin",
-                    MathematicalOperator::Acos => "acos",
-                    MathematicalOperator::Atan => "atan",
-                    MathematicalOperator::Log => "log",
-                    MathematicalOperator::Exp => "exp",
-                    MathematicalOperator::Abs => "abs",
-                    MathematicalOperator::Floor => "floor",
-                    MathematicalOperator::Ceil => "ceil",
-                    MathematicalOperator::Round => "round",
-                };
-                write!(f, "{} {}", op_name, operand)
+                write!(f, "{} {}", op, Operand(operand, 6))
             }
-            ExpressionKind::Veto(veto) => match &veto.message {
-                Some(msg) => write!(f, "veto \"{}\"", msg),
-                None => write!(f, "veto"),
-            },
+            ExpressionKind::Veto(veto) => {
+                match &veto.message {
+                    Some(msg) => write!(f, "veto \"{}\"", msg)?,
+                    None => write!(f, "veto")?,
+                }
+                for translation in &veto.translations {
+                    write!(f, " @{} \"{}\"", translation.locale, translation.message)?;
+                }
+                Ok(())
+            }
+            ExpressionKind::Aggregate(aggregate) => write!(f, "{}", aggregate),
+            ExpressionKind::Selection(function, candidates) => {
+                let candidates: Vec<String> = candidates
+                    .iter()
+                    .map(|c| Operand(c, 6).to_string())
+                    .collect();
+                write!(f, "{} of {}", function, candidates.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for AggregateExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {}", self.function, self.collection.join("."))?;
+        if let Some(rule) = &self.rule {
+            write!(f, ".{}?", rule)?;
+        }
+        if let Some(fact) = &self.fact {
+            write!(f, ".{}", fact)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AggregateFunction::Sum => write!(f, "sum"),
+            AggregateFunction::Count => write!(f, "count"),
+            AggregateFunction::Min => write!(f, "min"),
+            AggregateFunction::Max => write!(f, "max"),
+            AggregateFunction::Total => write!(f, "total"),
+        }
+    }
+}
+
+impl fmt::Display for SelectionFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionFunction::Lowest => write!(f, "lowest"),
+            SelectionFunction::Highest => write!(f, "highest"),
+            SelectionFunction::Earliest => write!(f, "earliest"),
+            SelectionFunction::Latest => write!(f, "latest"),
         }
     }
 }
@@ -805,6 +1935,7 @@ impl fmt::Display for LiteralValue {
             LiteralValue::Date(dt) => write!(f, "{}", dt),
             LiteralValue::Boolean(b) => write!(f, "{}", b),
             LiteralValue::Percentage(p) => write!(f, "{}%", p),
+            LiteralValue::PercentagePoints(p) => write!(f, "{} percentage points", p),
             LiteralValue::Unit(unit) => write!(f, "{}", unit),
             LiteralValue::Regex(s) => write!(f, "{}", s),
             LiteralValue::Time(time) => {
@@ -822,6 +1953,7 @@ impl LiteralValue {
             LiteralValue::Number(n) => format!("number {}", n),
             LiteralValue::Boolean(b) => format!("boolean {}", b),
             LiteralValue::Percentage(p) => format!("percentage {}%", p),
+            LiteralValue::PercentagePoints(p) => format!("{} percentage points", p),
             LiteralValue::Date(_) => "date value".to_string(),
             LiteralValue::Unit(unit) => {
                 format!(
@@ -1007,6 +2139,27 @@ impl fmt::Display for MoneyUnit {
     }
 }
 
+impl fmt::Display for MathematicalOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            MathematicalOperator::Sqrt => "sqrt",
+            MathematicalOperator::Sin => "sin",
+            MathematicalOperator::Cos => "cos",
+            MathematicalOperator::Tan => "tan",
+            MathematicalOperator::Asin => "asin",
+            MathematicalOperator::Acos => "acos",
+            MathematicalOperator::Atan => "atan",
+            MathematicalOperator::Log => "log",
+            MathematicalOperator::Exp => "exp",
+            MathematicalOperator::Abs => "abs",
+            MathematicalOperator::Floor => "floor",
+            MathematicalOperator::Ceil => "ceil",
+            MathematicalOperator::Round => "round",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 impl fmt::Display for ConversionTarget {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -1101,6 +2254,7 @@ impl fmt::Display for FactValue {
             FactValue::Literal(lit) => write!(f, "{}", lit),
             FactValue::TypeAnnotation(type_ann) => write!(f, "[{}]", type_ann),
             FactValue::DocumentReference(doc_name) => write!(f, "doc {}", doc_name),
+            FactValue::DocumentCollection(doc_name) => write!(f, "many doc {}", doc_name),
         }
     }
 }
@@ -1180,6 +2334,19 @@ impl fmt::Display for TimezoneValue {
 
 impl fmt::Display for DateTimeValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.granularity {
+            DateGranularity::Year => return write!(f, "{:04}", self.year),
+            DateGranularity::Month => return write!(f, "{:04}-{:02}", self.year, self.month),
+            DateGranularity::Week => {
+                if let Some(monday) =
+                    chrono::NaiveDate::from_ymd_opt(self.year, self.month, self.day)
+                {
+                    let week = chrono::Datelike::iso_week(&monday);
+                    return write!(f, "{:04}-W{:02}", week.year(), week.week());
+                }
+            }
+            DateGranularity::Exact => {}
+        }
         write!(
             f,
             "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
@@ -1221,7 +2388,16 @@ impl RulePath {
         let mut doc = current_doc;
         let mut segments = Vec::new();
 
-        for fact_name in &reference[..reference.len() - 1] {
+        for (index, fact_name) in reference[..reference.len() - 1].iter().enumerate() {
+            // Rules derived from a checklist have dotted names: `onboarding.complete`
+            let dotted = reference[index..].join(".");
+            if doc.rules.iter().any(|rule| rule.name == dotted) {
+                return Ok(RulePath {
+                    rule: dotted,
+                    segments,
+                });
+            }
+
             let fact = doc
                 .facts
                 .iter()
@@ -1264,6 +2440,16 @@ impl RulePath {
         })
     }
 
+    /// Prepend the segments leading to the document this path was resolved in
+    pub fn with_prefix(mut self, prefix: &[RulePathSegment]) -> Self {
+        if !prefix.is_empty() {
+            let mut segments = prefix.to_vec();
+            segments.append(&mut self.segments);
+            self.segments = segments;
+        }
+        self
+    }
+
     pub fn target_doc<'a>(&'a self, main_doc: &'a str) -> &'a str {
         self.segments
             .last()
@@ -1272,6 +2458,21 @@ impl RulePath {
     }
 }
 
+/// Name of one instance of a collection fact, e.g. `line_items[0]`
+///
+/// Instances are addressed by this name in fact overrides
+/// (`line_items[0].quantity=3`) and in rule paths.
+pub fn collection_instance_name(collection: &str, index: usize) -> String {
+    format!("{}[{}]", collection, index)
+}
+
+/// Split an instance name like `line_items[0]` into the collection and index
+pub fn parse_collection_instance(segment: &str) -> Option<(&str, usize)> {
+    let (collection, rest) = segment.split_once('[')?;
+    let index = rest.strip_suffix(']')?.parse().ok()?;
+    Some((collection, index))
+}
+
 impl fmt::Display for RulePath {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         for seg in &self.segments {
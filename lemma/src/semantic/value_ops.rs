@@ -0,0 +1,168 @@
+//! Equality and ordering of values
+//!
+//! Evaluation, inversion and validation all compare values through this
+//! module, so an inverted rule holds for exactly the values evaluation accepts:
+//!
+//! - Numbers compare with numbers, and with unit values by their amount
+//! - Percentages compare with percentages, and with numbers by their number
+//!   of percent: `10%` is `10`, as inversion reads a plain number target on a
+//!   percentage rule
+//! - Unit values of one category are converted to the left unit first, so
+//!   `1 kilogram` is `1000 grams`; money only compares within one currency.
+//!   Values of different categories compare by amount, as arithmetic does
+//! - Dates compare by the period they cover: `2024-03-15` is `2024-03`, and
+//!   is before `2024-04`. A whole four-digit number compared with a date is
+//!   a year
+//! - Text and booleans only compare for (in)equality; text folds case and
+//!   accents as the document's `compare text` directive asks
+
+use crate::evaluator::datetime::datetime_comparison;
+use crate::evaluator::operations::fold_text;
+use crate::{
+    ComparisonOperator, DateTimeValue, LemmaError, LemmaResult, LiteralValue, NumericUnit,
+    TextComparison,
+};
+use rust_decimal::Decimal;
+use std::cmp::Ordering;
+
+/// Whether `left op right` holds
+///
+/// Errors for values that can't be compared, such as text with a number, or
+/// money in different currencies.
+pub fn compare(
+    left: &LiteralValue,
+    op: &ComparisonOperator,
+    right: &LiteralValue,
+    text_comparison: TextComparison,
+) -> LemmaResult<bool> {
+    if let Some((left, right)) = year_operands(left, right) {
+        return datetime_comparison(&left, op, &right);
+    }
+
+    match (left, right) {
+        (LiteralValue::Number(l), LiteralValue::Number(r))
+        | (LiteralValue::Number(l), LiteralValue::Percentage(r))
+        | (LiteralValue::Percentage(l), LiteralValue::Number(r)) => Ok(compare_decimals(*l, op, r)),
+
+        (LiteralValue::Unit(l_unit), LiteralValue::Unit(r_unit)) => {
+            let r_value = normalized_amount(l_unit, r_unit)?;
+            Ok(compare_decimals(l_unit.value(), op, &r_value))
+        }
+
+        (LiteralValue::Unit(unit), LiteralValue::Number(n)) => {
+            Ok(compare_decimals(unit.value(), op, n))
+        }
+        (LiteralValue::Number(n), LiteralValue::Unit(unit)) => {
+            Ok(compare_decimals(*n, op, &unit.value()))
+        }
+
+        (LiteralValue::Percentage(l), LiteralValue::Percentage(r))
+        | (LiteralValue::PercentagePoints(l), LiteralValue::PercentagePoints(r)) => {
+            Ok(compare_decimals(*l, op, r))
+        }
+
+        (LiteralValue::Boolean(l), LiteralValue::Boolean(r)) => match op {
+            ComparisonOperator::Equal | ComparisonOperator::Is => Ok(l == r),
+            ComparisonOperator::NotEqual | ComparisonOperator::IsNot => Ok(l != r),
+            _ => Err(LemmaError::Engine(
+                "Can only use == and != with booleans".to_string(),
+            )),
+        },
+
+        (LiteralValue::Text(l), LiteralValue::Text(r)) => match op {
+            ComparisonOperator::Equal | ComparisonOperator::Is => {
+                Ok(fold_text(l, text_comparison) == fold_text(r, text_comparison))
+            }
+            ComparisonOperator::NotEqual | ComparisonOperator::IsNot => {
+                Ok(fold_text(l, text_comparison) != fold_text(r, text_comparison))
+            }
+            _ => Err(LemmaError::Engine(
+                "Can only use == and != with text".to_string(),
+            )),
+        },
+
+        (LiteralValue::Date(_), LiteralValue::Date(_)) => datetime_comparison(left, op, right),
+
+        _ => Err(LemmaError::Engine(format!(
+            "Comparison {:?} not supported for types {:?} and {:?}",
+            op,
+            left.to_type().to_string(),
+            right.to_type().to_string()
+        ))),
+    }
+}
+
+/// How `left` orders against `right`, or `None` when they can't be ordered
+///
+/// Text and booleans only order when equal. Dates covering overlapping
+/// periods, like `2024` and `2024-03`, are equal.
+pub fn ordering(left: &LiteralValue, right: &LiteralValue) -> Option<Ordering> {
+    let holds = |op| compare(left, &op, right, TextComparison::default()).unwrap_or(false);
+    if holds(ComparisonOperator::Equal) {
+        Some(Ordering::Equal)
+    } else if holds(ComparisonOperator::LessThan) {
+        Some(Ordering::Less)
+    } else if holds(ComparisonOperator::GreaterThan) {
+        Some(Ordering::Greater)
+    } else {
+        None
+    }
+}
+
+/// Whether `left is right`, with exact text comparison
+///
+/// Unlike `==` on `LiteralValue`, this is the equality of rules:
+/// `1 kilogram` equals `1000 grams`.
+pub fn values_equal(left: &LiteralValue, right: &LiteralValue) -> bool {
+    ordering(left, right) == Some(Ordering::Equal)
+}
+
+/// The amount of `right` in the unit of `left`
+fn normalized_amount(left: &NumericUnit, right: &NumericUnit) -> LemmaResult<Decimal> {
+    left.validate_same_currency(right)?;
+    if !left.same_category(right) {
+        return Ok(right.value());
+    }
+    let converted = crate::evaluator::units::convert_unit_for_arithmetic(
+        &LiteralValue::Unit(right.clone()),
+        &left.conversion_target(),
+    )?;
+    match converted {
+        LiteralValue::Unit(unit) => Ok(unit.value()),
+        // Duration conversion returns a number
+        LiteralValue::Number(n) => Ok(n),
+        _ => Err(LemmaError::Engine("Invalid unit conversion".to_string())),
+    }
+}
+
+/// A date compared with a bare year, e.g. `order_date is 2026`, with the year as a date
+///
+/// The grammar reads `2026` as a number, so whole four-digit numbers compared
+/// with a date are taken as the year.
+fn year_operands(
+    left: &LiteralValue,
+    right: &LiteralValue,
+) -> Option<(LiteralValue, LiteralValue)> {
+    match (left, right) {
+        (LiteralValue::Date(_), LiteralValue::Number(year)) => Some((
+            left.clone(),
+            LiteralValue::Date(DateTimeValue::of_year_number(*year)?),
+        )),
+        (LiteralValue::Number(year), LiteralValue::Date(_)) => Some((
+            LiteralValue::Date(DateTimeValue::of_year_number(*year)?),
+            right.clone(),
+        )),
+        _ => None,
+    }
+}
+
+fn compare_decimals(left: Decimal, op: &ComparisonOperator, right: &Decimal) -> bool {
+    match op {
+        ComparisonOperator::GreaterThan => left > *right,
+        ComparisonOperator::LessThan => left < *right,
+        ComparisonOperator::GreaterThanOrEqual => left >= *right,
+        ComparisonOperator::LessThanOrEqual => left <= *right,
+        ComparisonOperator::Equal | ComparisonOperator::Is => left == *right,
+        ComparisonOperator::NotEqual | ComparisonOperator::IsNot => left != *right,
+    }
+}
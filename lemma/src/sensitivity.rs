@@ -0,0 +1,299 @@
+//! How much a rule result moves per unit change of its inputs
+//!
+//! For each numeric fact the sensitivity is the derivative of the rule result
+//! with respect to that fact, at the given fact values. Rules that are plain
+//! arithmetic over numbers are differentiated exactly from their expression;
+//! all other rules (unless clauses, units, percentages) are differentiated
+//! numerically, by evaluating the rule just below and just above the value.
+
+use crate::evaluator::context::build_fact_map;
+use crate::{
+    ArithmeticOperation, Engine, Expression, ExpressionKind, FactReference, FactType, FactValue,
+    ForeignFact, LemmaDoc, LemmaError, LemmaFact, LemmaResult, LiteralValue,
+};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Deepest chain of rule references followed when differentiating exactly
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+/// How a sensitivity was computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensitivityMethod {
+    /// Differentiated from the rule's expression
+    Symbolic,
+    /// Estimated from evaluations around the fact value
+    FiniteDifference,
+}
+
+/// The sensitivity of a rule result to one fact
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FactSensitivity {
+    pub fact: String,
+    /// The fact value the sensitivity was computed at
+    pub value: LiteralValue,
+    /// Change of the result per unit change of the fact; None when the
+    /// result has no numeric value around the fact value
+    pub sensitivity: Option<Decimal>,
+    pub method: SensitivityMethod,
+    /// Why no sensitivity could be computed
+    pub reason: Option<String>,
+}
+
+/// Sensitivities of one rule, the most influential fact first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SensitivityReport {
+    pub doc_name: String,
+    pub rule_name: String,
+    /// The rule result at the given fact values
+    pub result: Option<LiteralValue>,
+    pub facts: Vec<FactSensitivity>,
+}
+
+/// Compute the sensitivity of `rule_name` to each of `facts`, see `Engine::sensitivity`
+pub fn sensitivity(
+    engine: &Engine,
+    doc_name: &str,
+    rule_name: &str,
+    facts: &[&str],
+    at_facts: Vec<LemmaFact>,
+) -> LemmaResult<SensitivityReport> {
+    let documents = engine.get_all_documents();
+    let doc = engine
+        .get_document(doc_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+    if !doc.rules.iter().any(|r| r.name == rule_name) {
+        return Err(LemmaError::Engine(format!(
+            "Rule '{}' not found in document '{}'",
+            rule_name, doc_name
+        )));
+    }
+
+    let values = build_fact_map(doc, &doc.facts, &at_facts, documents)?;
+    let result = evaluate_at(engine, doc_name, rule_name, &at_facts)?;
+
+    let mut report = Vec::new();
+    for fact in facts {
+        let reference: Vec<String> = fact.split('.').map(str::to_string).collect();
+        if crate::analysis::declared_fact_type(doc, &reference, documents).is_none() {
+            let candidates = crate::analysis::fact_paths(doc, documents);
+            let mut message = format!("Document '{}' has no fact '{}'", doc_name, fact);
+            if let Some(suggestion) = crate::analysis::closest_match(fact, &candidates) {
+                message.push_str(&format!(", did you mean '{}'?", suggestion));
+            }
+            return Err(LemmaError::Engine(message));
+        }
+
+        let fact_ref = FactReference { reference };
+        let value = values.get(&fact_ref).cloned().ok_or_else(|| {
+            LemmaError::Engine(format!(
+                "Fact '{}' has no value to compute the sensitivity at",
+                fact
+            ))
+        })?;
+        if numeric_value(&value).is_none() {
+            return Err(LemmaError::Engine(format!(
+                "Fact '{}' is {}, sensitivity needs a numeric fact",
+                fact,
+                value.to_type()
+            )));
+        }
+
+        let symbolic = result
+            .as_ref()
+            .and_then(|result| symbolic_sensitivity(doc, rule_name, &fact_ref, &values, result));
+        let entry = match symbolic {
+            Some(sensitivity) => FactSensitivity {
+                fact: fact.to_string(),
+                value,
+                sensitivity: Some(sensitivity),
+                method: SensitivityMethod::Symbolic,
+                reason: None,
+            },
+            None => finite_difference(engine, doc_name, rule_name, &fact_ref, value, &at_facts)?,
+        };
+        report.push(entry);
+    }
+
+    report.sort_by(|a, b| {
+        let magnitude = |s: &FactSensitivity| s.sensitivity.map(|d| d.abs());
+        magnitude(b).cmp(&magnitude(a))
+    });
+
+    Ok(SensitivityReport {
+        doc_name: doc_name.to_string(),
+        rule_name: rule_name.to_string(),
+        result,
+        facts: report,
+    })
+}
+
+/// The rule result with the given overrides; None when vetoed or missing facts
+fn evaluate_at(
+    engine: &Engine,
+    doc_name: &str,
+    rule_name: &str,
+    overrides: &[LemmaFact],
+) -> LemmaResult<Option<LiteralValue>> {
+    let response = engine.evaluate(
+        doc_name,
+        Some(vec![rule_name.to_string()]),
+        Some(overrides.to_vec()),
+    )?;
+    Ok(response
+        .results
+        .into_iter()
+        .find(|r| r.rule_name == rule_name)
+        .and_then(|r| r.result))
+}
+
+/// The number a value counts with: the amount of a unit, the points of a percentage
+fn numeric_value(value: &LiteralValue) -> Option<Decimal> {
+    match value {
+        LiteralValue::Number(n) | LiteralValue::Percentage(n) => Some(*n),
+        LiteralValue::Unit(unit) => Some(unit.value()),
+        _ => None,
+    }
+}
+
+/// `value` with its number replaced, keeping its type and unit
+fn with_number(value: &LiteralValue, number: Decimal) -> LiteralValue {
+    match value {
+        LiteralValue::Percentage(_) => LiteralValue::Percentage(number),
+        LiteralValue::Unit(unit) => LiteralValue::Unit(unit.with_value(number)),
+        _ => LiteralValue::Number(number),
+    }
+}
+
+/// Central difference around the fact value, one-sided when one side has no result
+fn finite_difference(
+    engine: &Engine,
+    doc_name: &str,
+    rule_name: &str,
+    fact_ref: &FactReference,
+    value: LiteralValue,
+    at_facts: &[LemmaFact],
+) -> LemmaResult<FactSensitivity> {
+    let fact_type = match fact_ref.reference.as_slice() {
+        [name] => FactType::Local(name.clone()),
+        _ => FactType::Foreign(ForeignFact {
+            reference: fact_ref.reference.clone(),
+        }),
+    };
+    let x = numeric_value(&value).unwrap_or_default();
+    let step = x.abs().max(Decimal::ONE) / Decimal::from(1000);
+
+    let at = |input: Decimal| -> LemmaResult<Option<Decimal>> {
+        let mut overrides: Vec<LemmaFact> = at_facts
+            .iter()
+            .filter(|f| f.fact_type != fact_type)
+            .cloned()
+            .collect();
+        overrides.push(LemmaFact::new(
+            fact_type.clone(),
+            FactValue::Literal(with_number(&value, input)),
+        ));
+        Ok(evaluate_at(engine, doc_name, rule_name, &overrides)?
+            .as_ref()
+            .and_then(numeric_value))
+    };
+
+    let below = at(x - step)?;
+    let above = at(x + step)?;
+    let center = at(x)?;
+    let (sensitivity, reason) = match (below, center, above) {
+        (Some(below), _, Some(above)) => (Some((above - below) / (step * Decimal::from(2))), None),
+        (None, Some(center), Some(above)) => (Some((above - center) / step), None),
+        (Some(below), Some(center), None) => (Some((center - below) / step), None),
+        _ => (
+            None,
+            Some("the rule has no numeric result around this value".to_string()),
+        ),
+    };
+
+    Ok(FactSensitivity {
+        fact: fact_ref.reference.join("."),
+        value,
+        sensitivity: sensitivity.map(|s| s.normalize()),
+        method: SensitivityMethod::FiniteDifference,
+        reason,
+    })
+}
+
+/// The exact derivative of a rule without unless clauses over plain numbers
+///
+/// Returns None when the rule uses anything else, or when the expression
+/// doesn't reproduce the evaluated result.
+fn symbolic_sensitivity(
+    doc: &LemmaDoc,
+    rule_name: &str,
+    fact_ref: &FactReference,
+    values: &HashMap<FactReference, LiteralValue>,
+    result: &LiteralValue,
+) -> Option<Decimal> {
+    let LiteralValue::Number(expected) = result else {
+        return None;
+    };
+    let rule = doc.rules.iter().find(|r| r.name == rule_name)?;
+    if !rule.unless_clauses.is_empty() {
+        return None;
+    }
+    let (value, derivative) = differentiate(&rule.expression, doc, fact_ref, values, 0)?;
+    let tolerance = expected.abs().max(Decimal::ONE) * Decimal::new(1, 9);
+    ((value - expected).abs() <= tolerance).then(|| derivative.normalize())
+}
+
+/// Value and derivative of an expression, with respect to `fact_ref`
+fn differentiate(
+    expr: &Expression,
+    doc: &LemmaDoc,
+    fact_ref: &FactReference,
+    values: &HashMap<FactReference, LiteralValue>,
+    depth: usize,
+) -> Option<(Decimal, Decimal)> {
+    match &expr.kind {
+        ExpressionKind::Literal(LiteralValue::Number(n)) => Some((*n, Decimal::ZERO)),
+        ExpressionKind::FactReference(reference) => match values.get(reference)? {
+            LiteralValue::Number(n) if reference == fact_ref => Some((*n, Decimal::ONE)),
+            LiteralValue::Number(n) => Some((*n, Decimal::ZERO)),
+            _ => None,
+        },
+        ExpressionKind::RuleReference(reference) if depth < MAX_EXPANSION_DEPTH => {
+            let [name] = reference.reference.as_slice() else {
+                return None;
+            };
+            let rule = doc.rules.iter().find(|r| r.name == *name)?;
+            if !rule.unless_clauses.is_empty() {
+                return None;
+            }
+            differentiate(&rule.expression, doc, fact_ref, values, depth + 1)
+        }
+        ExpressionKind::Arithmetic(left, operation, right) => {
+            let (a, da) = differentiate(left, doc, fact_ref, values, depth)?;
+            let (b, db) = differentiate(right, doc, fact_ref, values, depth)?;
+            match operation {
+                ArithmeticOperation::Add => Some((a.checked_add(b)?, da.checked_add(db)?)),
+                ArithmeticOperation::Subtract => Some((a.checked_sub(b)?, da.checked_sub(db)?)),
+                ArithmeticOperation::Multiply => {
+                    Some((a.checked_mul(b)?, da.checked_mul(b)? + a.checked_mul(db)?))
+                }
+                ArithmeticOperation::Divide if !b.is_zero() => Some((
+                    a.checked_div(b)?,
+                    (da.checked_mul(b)? - a.checked_mul(db)?).checked_div(b.checked_mul(b)?)?,
+                )),
+                // A constant exponent only: d(a^n) = n * a^(n-1) * da
+                ArithmeticOperation::Power if db.is_zero() => {
+                    let (base, exponent) = (a.to_f64()?, b.to_f64()?);
+                    let value = Decimal::from_f64(base.powf(exponent))?;
+                    let slope = Decimal::from_f64(exponent * base.powf(exponent - 1.0))?;
+                    Some((value, slope.checked_mul(da)?))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
@@ -1,6 +1,8 @@
-use crate::{LemmaDoc, LemmaError, LemmaType};
+use crate::{LemmaDoc, LemmaError, LemmaType, LiteralValue};
+use rust_decimal::Decimal;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::str::FromStr;
 
 /// Serialize a JSON value to Lemma syntax based on expected type
 fn serialize_value(value: &Value, fact_type: &LemmaType) -> Result<String, LemmaError> {
@@ -114,7 +116,17 @@ pub fn to_lemma_syntax(
 ) -> Result<Vec<String>, crate::LemmaError> {
     let map: HashMap<String, Value> = serde_json::from_slice(json)
         .map_err(|e| crate::LemmaError::Engine(format!("JSON parse error: {}", e)))?;
+    map_to_lemma_syntax(map, doc, all_docs)
+}
 
+/// Convert decoded fact overrides to Lemma syntax strings
+///
+/// Shared with the MsgPack serializer, which decodes into the same values.
+pub(super) fn map_to_lemma_syntax(
+    map: HashMap<String, Value>,
+    doc: &LemmaDoc,
+    all_docs: &HashMap<String, LemmaDoc>,
+) -> Result<Vec<String>, crate::LemmaError> {
     let mut lemma_strings = Vec::new();
 
     for (name, value) in map {
@@ -126,6 +138,66 @@ pub fn to_lemma_syntax(
     Ok(lemma_strings)
 }
 
+/// Convert a JSON fact value straight to a literal of the declared type
+///
+/// Unlike `to_lemma_syntax`, values are not written as Lemma syntax to be
+/// parsed again:
+/// - Numbers and booleans are taken as they are; a number given for a
+///   percentage is a fraction (0.21 is 21%)
+/// - Money and unit values can be objects, `{"amount": 100, "currency": "USD"}`
+///   or `{"value": 5, "unit": "kilogram"}`
+/// - Strings are text for text facts and parsed as a literal otherwise
+///
+/// Without a declared type the value's own type is used. The result is not
+/// checked against the declared type, see `Engine::parse_json_facts`.
+pub fn to_literal(value: &Value, fact_type: Option<&LemmaType>) -> Result<LiteralValue, String> {
+    match (fact_type, value) {
+        (Some(LemmaType::Text), Value::String(s)) => Ok(LiteralValue::Text(s.clone())),
+        (Some(LemmaType::Regex), Value::String(s)) if !s.starts_with('/') => {
+            crate::parse_literal(&format!("/{}/", s)).map_err(|e| e.to_string())
+        }
+        (Some(LemmaType::Percentage), Value::Number(n)) => {
+            Ok(LiteralValue::Percentage(decimal(n)? * Decimal::from(100)))
+        }
+        (_, Value::Number(n)) => Ok(LiteralValue::Number(decimal(n)?)),
+        (_, Value::Bool(b)) => Ok(LiteralValue::Boolean(*b)),
+        (_, Value::Object(fields)) => {
+            let amount = fields
+                .get("amount")
+                .or_else(|| fields.get("value"))
+                .ok_or_else(|| "expected an 'amount' or 'value' field".to_string())?;
+            let amount = match amount {
+                Value::Number(n) => decimal(n)?,
+                Value::String(s) => {
+                    Decimal::from_str(s.trim()).map_err(|_| format!("invalid number: '{}'", s))?
+                }
+                other => return Err(format!("expected a number, got {}", other)),
+            };
+            let unit = fields
+                .get("currency")
+                .or_else(|| fields.get("unit"))
+                .and_then(Value::as_str)
+                .ok_or_else(|| "expected a 'currency' or 'unit' field".to_string())?;
+            crate::parser::units::resolve_unit(amount, unit).map_err(|e| e.to_string())
+        }
+        (None, Value::String(s)) => {
+            Ok(crate::parse_literal(s).unwrap_or_else(|_| LiteralValue::Text(s.clone())))
+        }
+        (Some(fact_type), Value::String(s)) => {
+            crate::parse_literal(s).map_err(|_| format!("invalid {} value: '{}'", fact_type, s))
+        }
+        (Some(fact_type), other) => Err(format!("expected {}, got {}", fact_type, other)),
+        (None, other) => Err(format!("unsupported fact value: {}", other)),
+    }
+}
+
+fn decimal(n: &serde_json::Number) -> Result<Decimal, String> {
+    let text = n.to_string();
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|_| format!("invalid number: {}", text))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
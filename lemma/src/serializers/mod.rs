@@ -3,7 +3,10 @@ mod msgpack;
 mod protobuf;
 
 pub use json::to_lemma_syntax as from_json;
+pub use json::to_literal as literal_from_json;
 pub use msgpack::to_lemma_syntax as from_msgpack;
+pub use msgpack::{decode as decode_msgpack, encode as encode_msgpack};
+pub use protobuf::proto_schema;
 pub use protobuf::to_lemma_syntax as from_protobuf;
 
 use crate::{FactValue, LemmaDoc, LemmaError, LemmaType, TypeAnnotation};
@@ -21,19 +24,26 @@ pub(crate) fn find_fact_type(
             return match &fact.value {
                 FactValue::Literal(lit) => Ok(lit.to_type()),
                 FactValue::TypeAnnotation(TypeAnnotation::LemmaType(t)) => Ok(t.clone()),
-                FactValue::DocumentReference(ref_doc) => {
-                    if let Some((_, field)) = name.split_once('.') {
-                        if let Some(referenced) = all_docs.get(ref_doc) {
-                            return find_fact_type(field, referenced, all_docs);
-                        }
-                    }
-                    Err(LemmaError::Engine(format!(
-                        "Cannot override document reference '{}'",
-                        name
-                    )))
-                }
+                FactValue::DocumentReference(_) => Err(LemmaError::Engine(format!(
+                    "Cannot override document reference '{}'",
+                    name
+                ))),
+                FactValue::DocumentCollection(_) => Err(LemmaError::Engine(format!(
+                    "Cannot override document collection '{}'",
+                    name
+                ))),
             };
         }
+        // `rates.base` is `base` of the document `rates` refers to
+        if let (Some(field), FactValue::DocumentReference(ref_doc)) = (
+            name.strip_prefix(fact_name.as_str())
+                .and_then(|rest| rest.strip_prefix('.')),
+            &fact.value,
+        ) {
+            if let Some(referenced) = all_docs.get(ref_doc) {
+                return find_fact_type(field, referenced, all_docs);
+            }
+        }
     }
     Err(LemmaError::Engine(format!(
         "Fact '{}' not found in document",
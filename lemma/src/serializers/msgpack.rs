@@ -1,25 +1,89 @@
-use crate::LemmaDoc;
+use crate::{LemmaDoc, LemmaError};
+use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashMap;
 
 /// Convert MsgPack fact overrides to Lemma syntax strings
 ///
-/// MsgPack provides typed values, which we convert to Lemma syntax:
-/// - Strings: quoted text
-/// - Numbers: numeric literals
+/// The MsgPack payload is a map from fact name to value, with the same
+/// value formats as `from_json`:
+/// - Strings: quoted text, dates, regexes or values with units (`"100 kilogram"`)
+/// - Integers and floats: numeric literals (fractions for percentages)
 /// - Booleans: true/false
-/// - Binary data: base64 encoded strings for appropriate types
-///
-/// This is a stub implementation. Full MsgPack support requires:
-/// - Add rmp-serde dependency
-/// - Deserialize MsgPack to intermediate format
-/// - Map to Lemma types using schema
-/// - Serialize to Lemma syntax strings
 pub fn to_lemma_syntax(
-    _msgpack: &[u8],
-    _doc: &LemmaDoc,
-    _all_docs: &HashMap<String, LemmaDoc>,
-) -> Result<Vec<String>, crate::LemmaError> {
-    Err(crate::LemmaError::Engine(
-        "MsgPack serialization not yet implemented".to_string(),
-    ))
+    msgpack: &[u8],
+    doc: &LemmaDoc,
+    all_docs: &HashMap<String, LemmaDoc>,
+) -> Result<Vec<String>, LemmaError> {
+    let map: HashMap<String, serde_json::Value> = decode(msgpack)?;
+    super::json::map_to_lemma_syntax(map, doc, all_docs)
+}
+
+/// Decode a MsgPack payload
+pub fn decode<T: DeserializeOwned>(msgpack: &[u8]) -> Result<T, LemmaError> {
+    rmp_serde::from_slice(msgpack)
+        .map_err(|e| LemmaError::Engine(format!("MsgPack parse error: {}", e)))
+}
+
+/// Encode a value as MsgPack
+///
+/// Structs are written as maps keyed by field name, so the output decodes to
+/// the same shape as the value's JSON.
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, LemmaError> {
+    rmp_serde::to_vec_named(value)
+        .map_err(|e| LemmaError::Engine(format!("MsgPack encode error: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, LemmaResult, Response};
+
+    #[test]
+    fn test_fact_overrides() -> LemmaResult<()> {
+        let mut engine = Engine::new();
+        engine.add_lemma_code(
+            r#"
+            doc test
+            fact name = [text]
+            fact quantity = [number]
+            fact discount = 10%
+            fact active = [boolean]
+            "#,
+            "test.lemma",
+        )?;
+
+        let doc = engine.get_document("test").unwrap();
+        let all_docs = engine.get_all_documents();
+
+        let mut overrides = HashMap::new();
+        overrides.insert("name", serde_json::json!("Gold"));
+        overrides.insert("quantity", serde_json::json!(3));
+        overrides.insert("discount", serde_json::json!(0.25));
+        overrides.insert("active", serde_json::json!(true));
+        let msgpack = encode(&overrides)?;
+
+        let mut result = to_lemma_syntax(&msgpack, doc, all_docs)?;
+        result.sort();
+        assert_eq!(
+            result,
+            vec!["active=true", "discount=25%", "name=\"Gold\"", "quantity=3"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        let engine = Engine::new();
+        let doc = LemmaDoc::new("test".to_string());
+        let error = to_lemma_syntax(&[0xc1], &doc, engine.get_all_documents()).unwrap_err();
+        assert!(error.to_string().contains("MsgPack parse error"));
+    }
+
+    #[test]
+    fn test_encode_uses_field_names() -> LemmaResult<()> {
+        let response = Response::new("pricing".to_string());
+        let decoded: serde_json::Value = decode(&encode(&response)?)?;
+        assert_eq!(decoded["doc_name"], "pricing");
+        Ok(())
+    }
 }
@@ -1,25 +1,446 @@
-use crate::LemmaDoc;
+//! Protobuf fact overrides
+//!
+//! `proto_schema` generates a proto3 message for a document, with one field
+//! per overridable fact, and `to_lemma_syntax` reads payloads of that message.
+//! Upstream services compile the generated `.proto` instead of keeping their
+//! own copy in sync with the document.
+//!
+//! Fields are numbered in declaration order, so add new facts at the end of
+//! a document to keep existing payloads readable. Every field is `optional`:
+//! a fact that isn't set keeps the document's value. Field types:
+//! - Number: `double`; Percentage: `double` as a fraction (0.21 is 21%)
+//! - Boolean: `bool`
+//! - Text, Date, Regex and unit types: `string` in Lemma syntax (`"75 kilogram"`)
+//! - Fact groups and referenced documents: a nested message
+
+use crate::schema::overridable_facts;
+use crate::{LemmaDoc, LemmaError, LemmaType};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::fmt::Write;
 
-/// Convert Protobuf fact overrides to Lemma syntax strings
+/// Package of the generated messages
+const PACKAGE: &str = "lemma";
+
+/// A message: its fields in field number order, starting at 1
+struct Message {
+    name: String,
+    fields: Vec<Field>,
+}
+
+enum Field {
+    Scalar { name: String, lemma_type: LemmaType },
+    Message(Message),
+}
+
+impl Field {
+    fn name(&self) -> &str {
+        match self {
+            Field::Scalar { name, .. } => name,
+            Field::Message(message) => &message.name,
+        }
+    }
+}
+
+/// The message for the overridable facts of `doc`, see `schema::fact_schema`
+fn document_message(doc: &LemmaDoc, all_docs: &HashMap<String, LemmaDoc>) -> Message {
+    let mut root = Message {
+        name: doc.name.clone(),
+        fields: Vec::new(),
+    };
+    for fact in overridable_facts(doc, all_docs) {
+        let Some((name, parents)) = fact.path.split_last() else {
+            continue;
+        };
+        let mut message = &mut root;
+        for parent in parents {
+            let index =
+                match message.fields.iter().position(
+                    |field| matches!(field, Field::Message(nested) if nested.name == *parent),
+                ) {
+                    Some(index) => index,
+                    None => {
+                        message.fields.push(Field::Message(Message {
+                            name: parent.clone(),
+                            fields: Vec::new(),
+                        }));
+                        message.fields.len() - 1
+                    }
+                };
+            let Field::Message(nested) = &mut message.fields[index] else {
+                unreachable!("position matched a message");
+            };
+            message = nested;
+        }
+        message.fields.push(Field::Scalar {
+            name: name.clone(),
+            lemma_type: fact.lemma_type,
+        });
+    }
+    root
+}
+
+/// Generate the proto3 definition of a document's fact overrides
+///
+/// ```text
+/// syntax = "proto3";
 ///
-/// Protobuf provides strongly-typed structured data. The implementation would:
-/// - Define .proto schemas for Lemma fact overrides
-/// - Deserialize binary Protobuf data
-/// - Map fields to Lemma types using document schema
-/// - Serialize to Lemma syntax strings
+/// package lemma;
 ///
-/// This is a stub implementation. Full Protobuf support requires:
-/// - Add prost dependency
-/// - Define .proto message formats
-/// - Generate Rust code from .proto files
-/// - Implement deserialization and mapping logic
+/// // Fact overrides for the Lemma document 'shipping'
+/// message Shipping {
+///   optional string weight = 1; // [mass], e.g. "5.5 kilograms"
+///   optional double distance = 2; // [number]
+/// }
+/// ```
+pub fn proto_schema(doc: &LemmaDoc, all_docs: &HashMap<String, LemmaDoc>) -> String {
+    let message = document_message(doc, all_docs);
+    let mut proto = String::new();
+    let _ = writeln!(proto, "syntax = \"proto3\";\n");
+    let _ = writeln!(proto, "package {};\n", PACKAGE);
+    let _ = writeln!(
+        proto,
+        "// Fact overrides for the Lemma document '{}'",
+        doc.name
+    );
+    write_message(&mut proto, &message, 0);
+    proto
+}
+
+fn write_message(proto: &mut String, message: &Message, depth: usize) {
+    let indent = "  ".repeat(depth);
+    let _ = writeln!(proto, "{}message {} {{", indent, type_name(&message.name));
+    for (index, field) in message.fields.iter().enumerate() {
+        let number = index + 1;
+        match field {
+            Field::Scalar { name, lemma_type } => {
+                let _ = write!(
+                    proto,
+                    "{}  optional {} {} = {}; // [{}]",
+                    indent,
+                    scalar_type(lemma_type),
+                    name,
+                    number,
+                    lemma_type
+                );
+                if scalar_type(lemma_type) == "string" && *lemma_type != LemmaType::Text {
+                    let _ = write!(proto, ", e.g. {:?}", lemma_type.example_value());
+                } else if *lemma_type == LemmaType::Percentage {
+                    let _ = write!(proto, " as a fraction, 0.21 is 21%");
+                }
+                proto.push('\n');
+            }
+            Field::Message(nested) => {
+                write_message(proto, nested, depth + 1);
+                let _ = writeln!(
+                    proto,
+                    "{}  optional {} {} = {};",
+                    indent,
+                    type_name(&nested.name),
+                    nested.name,
+                    number
+                );
+            }
+        }
+    }
+    let _ = writeln!(proto, "{}}}", indent);
+}
+
+/// Protobuf type of a fact, in the form `serializers::json` reads it back
+fn scalar_type(lemma_type: &LemmaType) -> &'static str {
+    match lemma_type {
+        LemmaType::Number | LemmaType::Percentage => "double",
+        LemmaType::Boolean => "bool",
+        _ => "string",
+    }
+}
+
+/// Message name for a document or fact name: `pricing/standard` is `PricingStandard`
+fn type_name(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Convert Protobuf fact overrides to Lemma syntax strings
+///
+/// The payload is the message `proto_schema` generates for `doc`. Fields
+/// of nested messages become dotted overrides (`customer.age=42`); unknown
+/// field numbers are skipped, as protobuf readers do.
 pub fn to_lemma_syntax(
-    _protobuf: &[u8],
-    _doc: &LemmaDoc,
-    _all_docs: &HashMap<String, LemmaDoc>,
-) -> Result<Vec<String>, crate::LemmaError> {
-    Err(crate::LemmaError::Engine(
-        "Protobuf serialization not yet implemented".to_string(),
-    ))
+    protobuf: &[u8],
+    doc: &LemmaDoc,
+    all_docs: &HashMap<String, LemmaDoc>,
+) -> Result<Vec<String>, LemmaError> {
+    let message = document_message(doc, all_docs);
+    let mut values = HashMap::new();
+    decode_message(protobuf, &message, &[], &mut values)?;
+    super::json::map_to_lemma_syntax(values, doc, all_docs)
+}
+
+fn decode_message(
+    bytes: &[u8],
+    message: &Message,
+    prefix: &[&str],
+    values: &mut HashMap<String, Value>,
+) -> Result<(), LemmaError> {
+    let mut reader = Reader { bytes, position: 0 };
+    while !reader.is_empty() {
+        let tag = reader.varint()?;
+        let (number, wire_type) = (tag >> 3, tag & 0x7);
+        let field = usize::try_from(number)
+            .ok()
+            .and_then(|number| number.checked_sub(1))
+            .and_then(|index| message.fields.get(index));
+        let Some(field) = field else {
+            reader.skip(wire_type)?;
+            continue;
+        };
+
+        let mut path = prefix.to_vec();
+        path.push(field.name());
+        let value = match (field, wire_type) {
+            (Field::Message(nested), WIRE_LEN) => {
+                let bytes = reader.length_delimited()?;
+                decode_message(bytes, nested, &path, values)?;
+                continue;
+            }
+            (Field::Scalar { lemma_type, .. }, WIRE_FIXED64)
+                if scalar_type(lemma_type) == "double" =>
+            {
+                let number = f64::from_le_bytes(reader.fixed64()?);
+                serde_json::Number::from_f64(number)
+                    .map(Value::Number)
+                    .ok_or_else(|| {
+                        LemmaError::Engine(format!(
+                            "Protobuf parse error: field '{}' is not a finite number",
+                            path.join(".")
+                        ))
+                    })?
+            }
+            (Field::Scalar { lemma_type, .. }, WIRE_VARINT)
+                if scalar_type(lemma_type) == "bool" =>
+            {
+                Value::Bool(reader.varint()? != 0)
+            }
+            (Field::Scalar { lemma_type, .. }, WIRE_LEN) if scalar_type(lemma_type) == "string" => {
+                let text = std::str::from_utf8(reader.length_delimited()?).map_err(|_| {
+                    LemmaError::Engine(format!(
+                        "Protobuf parse error: field '{}' is not valid UTF-8",
+                        path.join(".")
+                    ))
+                })?;
+                Value::String(text.to_string())
+            }
+            _ => {
+                return Err(LemmaError::Engine(format!(
+                    "Protobuf parse error: field '{}' (number {}) has unexpected wire type {}",
+                    path.join("."),
+                    number,
+                    wire_type
+                )))
+            }
+        };
+        values.insert(path.join("."), value);
+    }
+    Ok(())
+}
+
+const WIRE_VARINT: u64 = 0;
+const WIRE_FIXED64: u64 = 1;
+const WIRE_LEN: u64 = 2;
+const WIRE_FIXED32: u64 = 5;
+
+/// Reads the protobuf wire format
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], LemmaError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| {
+                LemmaError::Engine("Protobuf parse error: unexpected end of payload".to_string())
+            })?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn varint(&mut self) -> Result<u64, LemmaError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.take(1)?[0];
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(LemmaError::Engine(
+            "Protobuf parse error: varint is too long".to_string(),
+        ))
+    }
+
+    fn fixed64(&mut self) -> Result<[u8; 8], LemmaError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(bytes)
+    }
+
+    fn length_delimited(&mut self) -> Result<&'a [u8], LemmaError> {
+        let len = self.varint()?;
+        let len = usize::try_from(len).map_err(|_| {
+            LemmaError::Engine("Protobuf parse error: field is too long".to_string())
+        })?;
+        self.take(len)
+    }
+
+    /// Skip a field this document doesn't declare
+    fn skip(&mut self, wire_type: u64) -> Result<(), LemmaError> {
+        match wire_type {
+            WIRE_VARINT => self.varint().map(|_| ()),
+            WIRE_FIXED64 => self.take(8).map(|_| ()),
+            WIRE_LEN => self.length_delimited().map(|_| ()),
+            WIRE_FIXED32 => self.take(4).map(|_| ()),
+            _ => Err(LemmaError::Engine(format!(
+                "Protobuf parse error: unsupported wire type {}",
+                wire_type
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, LemmaResult};
+
+    const CODE: &str = r#"
+        doc shipping
+        fact weight = [mass]
+        fact distance = [number]
+        fact express = false
+        fact surcharge = 5%
+        fact customer.name = [text]
+        fact customer.vip = [boolean]
+        fact rates = doc rates
+
+        doc rates
+        fact base = [money]
+    "#;
+
+    fn engine() -> Engine {
+        let mut engine = Engine::new();
+        engine.add_lemma_code(CODE, "shipping.lemma").unwrap();
+        engine
+    }
+
+    fn tag(number: u8, wire_type: u64) -> u8 {
+        (number << 3) | wire_type as u8
+    }
+
+    fn string_field(number: u8, text: &str) -> Vec<u8> {
+        let mut bytes = vec![tag(number, WIRE_LEN), text.len() as u8];
+        bytes.extend(text.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_proto_schema() {
+        let engine = engine();
+        let doc = engine.get_document("shipping").unwrap();
+        let proto = proto_schema(doc, engine.get_all_documents());
+        assert!(proto.starts_with("syntax = \"proto3\";"), "{}", proto);
+        assert!(proto.contains("message Shipping {"), "{}", proto);
+        assert!(
+            proto.contains("  optional string weight = 1; // [mass], e.g. \"5.5 kilograms\""),
+            "{}",
+            proto
+        );
+        assert!(
+            proto.contains("  optional double distance = 2; // [number]\n"),
+            "{}",
+            proto
+        );
+        assert!(proto.contains("  optional bool express = 3;"), "{}", proto);
+        assert!(
+            proto.contains("  optional double surcharge = 4; // [percentage] as a fraction"),
+            "{}",
+            proto
+        );
+        assert!(
+            proto.contains(
+                "  message Customer {\n    optional string name = 1; // [text]\n    optional bool vip = 2; // [boolean]\n  }\n  optional Customer customer = 5;"
+            ),
+            "{}",
+            proto
+        );
+        assert!(proto.contains("optional Rates rates = 6;"), "{}", proto);
+    }
+
+    #[test]
+    fn test_fact_overrides() -> LemmaResult<()> {
+        let engine = engine();
+        let doc = engine.get_document("shipping").unwrap();
+
+        let mut payload = string_field(1, "12 kilogram");
+        payload.push(tag(2, WIRE_FIXED64));
+        payload.extend(250.5f64.to_le_bytes());
+        payload.extend([tag(3, WIRE_VARINT), 1]);
+        payload.push(tag(4, WIRE_FIXED64));
+        payload.extend(0.25f64.to_le_bytes());
+        let customer = string_field(1, "Ada");
+        payload.extend([tag(5, WIRE_LEN), customer.len() as u8]);
+        payload.extend(customer);
+        let rates = string_field(1, "10 EUR");
+        payload.extend([tag(6, WIRE_LEN), rates.len() as u8]);
+        payload.extend(rates);
+        // Unknown fields are skipped
+        payload.extend([tag(15, WIRE_VARINT), 7]);
+
+        let mut result = to_lemma_syntax(&payload, doc, engine.get_all_documents())?;
+        result.sort();
+        assert_eq!(
+            result,
+            vec![
+                "customer.name=\"Ada\"",
+                "distance=250.5",
+                "express=true",
+                "rates.base=10 EUR",
+                "surcharge=25%",
+                "weight=12 kilogram",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_payload() {
+        let engine = engine();
+        let doc = engine.get_document("shipping").unwrap();
+        let all_docs = engine.get_all_documents();
+
+        let truncated = [tag(1, WIRE_LEN), 10, b'x'];
+        let error = to_lemma_syntax(&truncated, doc, all_docs).unwrap_err();
+        assert!(error.to_string().contains("unexpected end"), "{}", error);
+
+        let wrong_type = [tag(2, WIRE_VARINT), 1];
+        let error = to_lemma_syntax(&wrong_type, doc, all_docs).unwrap_err();
+        assert!(error.to_string().contains("'distance'"), "{}", error);
+    }
 }
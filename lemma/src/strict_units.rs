@@ -0,0 +1,244 @@
+//! Strict unit checking
+//!
+//! Arithmetic between different kinds of units, like `price * weight` with a
+//! money price and a mass weight, evaluates to a plain number and only leaves
+//! a `unit_degraded` diagnostic. With `Engine::with_strict_units` such rules
+//! are rejected when their document is loaded instead.
+//!
+//! The kind of unit of an expression is read from unit literals, facts given
+//! a unit or declared as `[mass]`, `[money]` and so on, `in` conversions and
+//! the rules it references. Expressions whose unit can't be told this way,
+//! like function calls and aggregates, are not checked.
+
+use crate::{
+    ArithmeticOperation, ConversionTarget, Expression, ExpressionKind, FactReference, FactType,
+    FactValue, LemmaDoc, LemmaError, LemmaResult, LemmaRule, LemmaType, MathematicalOperator,
+    RuleReference, Span,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Reject arithmetic that combines different kinds of units in `docs`
+pub fn check_unit_arithmetic(docs: &[LemmaDoc]) -> LemmaResult<()> {
+    let mut units = UnitKinds {
+        docs: docs.iter().map(|doc| (doc.name.as_str(), doc)).collect(),
+        rules: HashMap::new(),
+    };
+    for doc in docs {
+        for rule in &doc.rules {
+            for expression in rule_expressions(rule) {
+                units.check(expression, rule, doc)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn rule_expressions(rule: &LemmaRule) -> impl Iterator<Item = &Expression> {
+    std::iter::once(&rule.expression).chain(
+        rule.unless_clauses
+            .iter()
+            .flat_map(|clause| [&clause.condition, &clause.result]),
+    )
+}
+
+/// Infers the kind of unit of expressions, remembering the unit of each rule
+struct UnitKinds<'a> {
+    docs: HashMap<&'a str, &'a LemmaDoc>,
+    rules: HashMap<(String, String), Option<LemmaType>>,
+}
+
+impl<'a> UnitKinds<'a> {
+    fn check(&mut self, expr: &Expression, rule: &LemmaRule, doc: &'a LemmaDoc) -> LemmaResult<()> {
+        match &expr.kind {
+            ExpressionKind::Arithmetic(left, op, right) => {
+                self.check(left, rule, doc)?;
+                self.check(right, rule, doc)?;
+                if let (Some(l), Some(r)) = (self.unit(left, doc), self.unit(right, doc)) {
+                    if l != r {
+                        return Err(unit_error(expr.span.clone(), rule, doc, &l, op, &r));
+                    }
+                }
+                Ok(())
+            }
+            ExpressionKind::LogicalAnd(left, right)
+            | ExpressionKind::LogicalOr(left, right)
+            | ExpressionKind::Comparison(left, _, right) => {
+                self.check(left, rule, doc)?;
+                self.check(right, rule, doc)
+            }
+            ExpressionKind::UnitConversion(inner, _)
+            | ExpressionKind::LogicalNegation(inner, _)
+            | ExpressionKind::MathematicalOperator(_, inner) => self.check(inner, rule, doc),
+            ExpressionKind::Range(range) => range
+                .operands()
+                .into_iter()
+                .try_for_each(|operand| self.check(operand, rule, doc)),
+            ExpressionKind::MissingItems(items)
+            | ExpressionKind::Selection(_, items)
+            | ExpressionKind::FunctionCall(_, items) => items
+                .iter()
+                .try_for_each(|item| self.check(item, rule, doc)),
+            ExpressionKind::Veto(veto) => veto
+                .arguments
+                .iter()
+                .try_for_each(|argument| self.check(&argument.expression, rule, doc)),
+            ExpressionKind::Literal(_)
+            | ExpressionKind::FactReference(_)
+            | ExpressionKind::RuleReference(_)
+            | ExpressionKind::FactHasAnyValue(_)
+            | ExpressionKind::RuleHasValue(_)
+            | ExpressionKind::Aggregate(_) => Ok(()),
+        }
+    }
+
+    /// The kind of unit `expr` evaluates to, None for plain numbers and unknown units
+    fn unit(&mut self, expr: &Expression, doc: &'a LemmaDoc) -> Option<LemmaType> {
+        match &expr.kind {
+            ExpressionKind::Literal(value) => unit_kind(value.to_type()),
+            ExpressionKind::FactReference(reference) => self.fact_unit(reference, doc),
+            ExpressionKind::RuleReference(reference) => self.rule_reference_unit(reference, doc),
+            ExpressionKind::UnitConversion(_, target) => conversion_unit(target),
+            ExpressionKind::Arithmetic(left, _, right) => {
+                match (self.unit(left, doc), self.unit(right, doc)) {
+                    (Some(l), Some(r)) if l != r => None,
+                    (Some(unit), _) | (None, Some(unit)) => Some(unit),
+                    (None, None) => None,
+                }
+            }
+            ExpressionKind::MathematicalOperator(
+                MathematicalOperator::Abs
+                | MathematicalOperator::Floor
+                | MathematicalOperator::Ceil
+                | MathematicalOperator::Round,
+                inner,
+            ) => self.unit(inner, doc),
+            ExpressionKind::Selection(_, candidates) => candidates
+                .iter()
+                .find_map(|candidate| self.unit(candidate, doc)),
+            _ => None,
+        }
+    }
+
+    fn fact_unit(&self, reference: &FactReference, doc: &'a LemmaDoc) -> Option<LemmaType> {
+        if reference.caller_fact().is_some() {
+            return None;
+        }
+        let (name, path) = reference.reference.split_last()?;
+        let doc = self.referenced_doc(path, doc)?;
+        let local = FactReference {
+            reference: vec![name.clone()],
+        };
+        doc.get_fact_type(&local).and_then(unit_kind)
+    }
+
+    fn rule_reference_unit(
+        &mut self,
+        reference: &RuleReference,
+        doc: &'a LemmaDoc,
+    ) -> Option<LemmaType> {
+        // Rules derived from records and checklists have dotted names: `quote.total`
+        for split in 0..reference.reference.len() {
+            let (path, name) = reference.reference.split_at(split);
+            let Some(doc) = self.referenced_doc(path, doc) else {
+                break;
+            };
+            let name = name.join(".");
+            if doc.rules.iter().any(|rule| rule.name == name) {
+                return self.rule_unit(doc, &name);
+            }
+        }
+        None
+    }
+
+    fn rule_unit(&mut self, doc: &'a LemmaDoc, name: &str) -> Option<LemmaType> {
+        let key = (doc.name.clone(), name.to_string());
+        if let Some(unit) = self.rules.get(&key) {
+            return unit.clone();
+        }
+        // Stands in while the rule is inferred, so cycles end
+        self.rules.insert(key.clone(), None);
+        let rule = doc.rules.iter().find(|rule| rule.name == name)?;
+        let unit = std::iter::once(&rule.expression)
+            .chain(rule.unless_clauses.iter().map(|clause| &clause.result))
+            .find_map(|expression| self.unit(expression, doc));
+        self.rules.insert(key, unit.clone());
+        unit
+    }
+
+    /// The document reached through the document reference facts in `path`
+    fn referenced_doc(&self, path: &[String], mut doc: &'a LemmaDoc) -> Option<&'a LemmaDoc> {
+        for fact_name in path {
+            let fact = doc
+                .facts
+                .iter()
+                .find(|f| matches!(&f.fact_type, FactType::Local(name) if name == fact_name))?;
+            let FactValue::DocumentReference(target) = &fact.value else {
+                return None;
+            };
+            doc = self.docs.get(target.as_str())?;
+        }
+        Some(doc)
+    }
+}
+
+/// `lemma_type` if it is a kind of unit
+fn unit_kind(lemma_type: LemmaType) -> Option<LemmaType> {
+    match lemma_type {
+        LemmaType::Text
+        | LemmaType::Number
+        | LemmaType::Date
+        | LemmaType::Boolean
+        | LemmaType::Regex
+        | LemmaType::Percentage => None,
+        unit => Some(unit),
+    }
+}
+
+fn conversion_unit(target: &ConversionTarget) -> Option<LemmaType> {
+    Some(match target {
+        ConversionTarget::Mass(_) => LemmaType::Mass,
+        ConversionTarget::Length(_) => LemmaType::Length,
+        ConversionTarget::Volume(_) => LemmaType::Volume,
+        ConversionTarget::Duration(_) => LemmaType::Duration,
+        ConversionTarget::Temperature(_) => LemmaType::Temperature,
+        ConversionTarget::Power(_) => LemmaType::Power,
+        ConversionTarget::Force(_) => LemmaType::Force,
+        ConversionTarget::Pressure(_) => LemmaType::Pressure,
+        ConversionTarget::Energy(_) => LemmaType::Energy,
+        ConversionTarget::Frequency(_) => LemmaType::Frequency,
+        ConversionTarget::Data(_) => LemmaType::Data,
+        ConversionTarget::Money(_) => LemmaType::Money,
+        ConversionTarget::Percentage => return None,
+    })
+}
+
+fn unit_error(
+    span: Option<Span>,
+    rule: &LemmaRule,
+    doc: &LemmaDoc,
+    left: &LemmaType,
+    op: &ArithmeticOperation,
+    right: &LemmaType,
+) -> LemmaError {
+    LemmaError::semantic_with_suggestion(
+        format!(
+            "Rule '{}' computes {} {} {}, which drops both units and gives a plain number",
+            rule.name, left, op, right
+        ),
+        span.or(rule.span.clone()).unwrap_or(Span {
+            start: 0,
+            end: 0,
+            line: 0,
+            col: 0,
+        }),
+        doc.source.clone().unwrap_or_else(|| "<input>".to_string()),
+        Arc::from(""),
+        doc.name.clone(),
+        doc.start_line,
+        format!(
+            "Convert one side explicitly with 'in' so both are {}, or declare it as a plain [number] if it is a rate or factor",
+            left
+        ),
+    )
+}
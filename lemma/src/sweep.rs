@@ -0,0 +1,183 @@
+//! Evaluating a rule across a range of one fact
+//!
+//! A sweep evaluates one rule for evenly spaced values of one fact, keeping
+//! every other fact fixed, to show where a result changes as the input grows.
+
+use crate::{
+    Engine, FactType, FactValue, ForeignFact, LemmaError, LemmaFact, LemmaResult, LiteralValue,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::ops::RangeInclusive;
+
+/// Most points a single sweep evaluates
+pub const MAX_SWEEP_POINTS: usize = 10_000;
+
+/// The result of a rule for one swept input value
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SweepPoint {
+    pub input: LiteralValue,
+    /// None when the rule was vetoed or has no value
+    pub result: Option<LiteralValue>,
+    pub veto_message: Option<String>,
+}
+
+impl SweepPoint {
+    fn same_outcome(&self, other: &SweepPoint) -> bool {
+        self.result == other.result && self.veto_message == other.veto_message
+    }
+}
+
+/// Results of a rule across a range of one fact, in input order
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sweep {
+    pub doc_name: String,
+    pub rule_name: String,
+    pub fact: String,
+    pub points: Vec<SweepPoint>,
+}
+
+impl Sweep {
+    /// Consecutive points where the result differs, e.g. where eligibility flips
+    pub fn changes(&self) -> impl Iterator<Item = (&SweepPoint, &SweepPoint)> {
+        self.points
+            .windows(2)
+            .filter(|pair| !pair[0].same_outcome(&pair[1]))
+            .map(|pair| (&pair[0], &pair[1]))
+    }
+}
+
+/// Evenly spaced values from the start of `range` up to its end
+///
+/// Both ends must be of the same type and unit; the values keep that unit.
+pub fn sweep_values(
+    range: &RangeInclusive<LiteralValue>,
+    step: Decimal,
+) -> LemmaResult<Vec<LiteralValue>> {
+    let (start, end) = (range.start(), range.end());
+    let (from, to, rebuild): (Decimal, Decimal, Box<dyn Fn(Decimal) -> LiteralValue>) =
+        match (start, end) {
+            (LiteralValue::Number(from), LiteralValue::Number(to)) => {
+                (*from, *to, Box::new(LiteralValue::Number))
+            }
+            (LiteralValue::Percentage(from), LiteralValue::Percentage(to)) => {
+                (*from, *to, Box::new(LiteralValue::Percentage))
+            }
+            (LiteralValue::Unit(from), LiteralValue::Unit(to))
+                if from.with_value(to.value()) == *to =>
+            {
+                let unit = from.clone();
+                (
+                    from.value(),
+                    to.value(),
+                    Box::new(move |value| LiteralValue::Unit(unit.with_value(value))),
+                )
+            }
+            _ => {
+                return Err(LemmaError::Engine(format!(
+                    "Sweep range {} to {} must use numbers of one type and unit",
+                    start, end
+                )))
+            }
+        };
+
+    if step <= Decimal::ZERO {
+        return Err(LemmaError::Engine(format!(
+            "Sweep step must be positive, got {}",
+            step
+        )));
+    }
+    if from > to {
+        return Err(LemmaError::Engine(format!(
+            "Sweep range start {} is after its end {}",
+            start, end
+        )));
+    }
+
+    let count = ((to - from) / step).floor() + Decimal::ONE;
+    if count > Decimal::from(MAX_SWEEP_POINTS) {
+        return Err(LemmaError::Engine(format!(
+            "Sweep would evaluate {} points, at most {} are allowed; use a larger step",
+            count, MAX_SWEEP_POINTS
+        )));
+    }
+
+    let mut values = Vec::new();
+    let mut value = from;
+    while value <= to {
+        values.push(rebuild(value.normalize()));
+        value += step;
+    }
+    Ok(values)
+}
+
+/// Evaluate `rule_name` for each value of `fact` in `range`, see `Engine::sweep`
+pub fn sweep(
+    engine: &Engine,
+    doc_name: &str,
+    rule_name: &str,
+    fact: &str,
+    range: RangeInclusive<LiteralValue>,
+    step: Decimal,
+    fact_overrides: Vec<LemmaFact>,
+) -> LemmaResult<Sweep> {
+    let doc = engine
+        .get_document(doc_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Document '{}' not found", doc_name)))?;
+    if !doc.rules.iter().any(|r| r.name == rule_name) {
+        return Err(LemmaError::Engine(format!(
+            "Rule '{}' not found in document '{}'",
+            rule_name, doc_name
+        )));
+    }
+
+    let reference: Vec<String> = fact.split('.').map(str::to_string).collect();
+    let documents = engine.get_all_documents();
+    if crate::analysis::declared_fact_type(doc, &reference, documents).is_none() {
+        let candidates = crate::analysis::fact_paths(doc, documents);
+        let mut message = format!("Document '{}' has no fact '{}'", doc_name, fact);
+        if let Some(suggestion) = crate::analysis::closest_match(fact, &candidates) {
+            message.push_str(&format!(", did you mean '{}'?", suggestion));
+        }
+        return Err(LemmaError::Engine(message));
+    }
+
+    let fact_type = match reference.as_slice() {
+        [name] => FactType::Local(name.clone()),
+        _ => FactType::Foreign(ForeignFact {
+            reference: reference.clone(),
+        }),
+    };
+    let fixed: Vec<LemmaFact> = fact_overrides
+        .into_iter()
+        .filter(|f| f.fact_type != fact_type)
+        .collect();
+
+    let mut points = Vec::new();
+    for input in sweep_values(&range, step)? {
+        let mut overrides = fixed.clone();
+        overrides.push(LemmaFact::new(
+            fact_type.clone(),
+            FactValue::Literal(input.clone()),
+        ));
+
+        let response =
+            engine.evaluate(doc_name, Some(vec![rule_name.to_string()]), Some(overrides))?;
+        let result = response
+            .results
+            .into_iter()
+            .find(|r| r.rule_name == rule_name);
+        points.push(SweepPoint {
+            input,
+            result: result.as_ref().and_then(|r| r.result.clone()),
+            veto_message: result.and_then(|r| r.veto_message),
+        });
+    }
+
+    Ok(Sweep {
+        doc_name: doc_name.to_string(),
+        rule_name: rule_name.to_string(),
+        fact: fact.to_string(),
+        points,
+    })
+}
@@ -0,0 +1,229 @@
+//! Tabular fact import
+//!
+//! Converts reference tables (exchange rates, tariffs, lookup lists) into a
+//! generated Lemma document so large tables don't have to be hand-written.
+//!
+//! The first column holds the row key. A table with two columns produces one
+//! fact per row (`usd,1.08` → `fact usd = 1.08`). Wider tables produce one fact
+//! per cell, named after the row key and the column header
+//! (`usd,1.08,2024-01-15` with header `currency,rate,date` → `fact usd_rate = 1.08`
+//! and `fact usd_date = 2024-01-15`).
+//!
+//! Cells are typed by the Lemma literal parser: `1.08`, `100 EUR`, `15%` and
+//! `2024-01-15` keep their type, anything else becomes text. Empty cells are skipped.
+
+use crate::parser::{LemmaParser, Rule};
+use crate::{LemmaError, LemmaResult};
+use pest::Parser;
+use std::collections::HashSet;
+
+/// A table of rows with named columns
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FactTable {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Line of the CSV input each row starts on, for error messages
+    ///
+    /// Empty for tables built with `new`, whose rows are numbered as if the
+    /// header is line 1 and every row one line.
+    pub row_lines: Vec<usize>,
+}
+
+impl FactTable {
+    pub fn new(columns: Vec<String>, rows: Vec<Vec<String>>) -> Self {
+        Self {
+            columns,
+            rows,
+            row_lines: Vec::new(),
+        }
+    }
+
+    /// Parse CSV input; the first record is the header
+    ///
+    /// Supports quoted fields (`"a, b"`), escaped quotes (`""`) and CRLF line endings.
+    pub fn from_csv(input: &str) -> LemmaResult<Self> {
+        let mut records = parse_csv_records(input)?.into_iter();
+
+        let (_, columns) = records
+            .next()
+            .ok_or_else(|| LemmaError::Engine("CSV input has no header row".to_string()))?;
+        let (row_lines, rows): (Vec<_>, Vec<_>) = records.unzip();
+
+        Ok(Self {
+            columns,
+            rows,
+            row_lines,
+        })
+    }
+
+    /// Generate Lemma source code for a document holding the table's facts
+    pub fn to_lemma(&self, doc_name: &str) -> LemmaResult<String> {
+        if !is_label(doc_name) {
+            return Err(LemmaError::Engine(format!(
+                "Invalid document name '{}' for imported table",
+                doc_name
+            )));
+        }
+        if self.columns.len() < 2 {
+            return Err(LemmaError::Engine(
+                "Table must have a key column and at least one value column".to_string(),
+            ));
+        }
+
+        let value_columns: Vec<String> = self.columns[1..]
+            .iter()
+            .map(|column| normalize_name(column))
+            .collect();
+
+        let mut code = format!("doc {}\n", doc_name);
+        let mut seen = HashSet::new();
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let line = self.row_lines.get(index).copied().unwrap_or(index + 2);
+
+            if row.len() != self.columns.len() {
+                return Err(LemmaError::Engine(format!(
+                    "Row {} has {} columns, expected {}",
+                    line,
+                    row.len(),
+                    self.columns.len()
+                )));
+            }
+
+            let key = normalize_name(&row[0]);
+
+            for (cell, column) in row[1..].iter().zip(&value_columns) {
+                let cell = cell.trim();
+                if cell.is_empty() {
+                    continue;
+                }
+
+                let fact_name = if value_columns.len() == 1 {
+                    key.clone()
+                } else {
+                    format!("{}_{}", key, column)
+                };
+
+                if !is_label(&fact_name) {
+                    return Err(LemmaError::Engine(format!(
+                        "Row {}: '{}' is not a valid fact name",
+                        line, fact_name
+                    )));
+                }
+                if !seen.insert(fact_name.clone()) {
+                    return Err(LemmaError::Engine(format!(
+                        "Row {}: duplicate fact '{}'",
+                        line, fact_name
+                    )));
+                }
+
+                code.push_str(&format!(
+                    "fact {} = {}\n",
+                    fact_name,
+                    render_value(cell, line)?
+                ));
+            }
+        }
+
+        Ok(code)
+    }
+}
+
+/// Render a cell as a Lemma literal, falling back to text
+fn render_value(cell: &str, line: usize) -> LemmaResult<String> {
+    // The unit resolution in parse_facts rejects cells like "3 apples"
+    if matches_fully(Rule::literal, cell)
+        && crate::parse_facts(&[&format!("value = {}", cell)]).is_ok()
+    {
+        return Ok(cell.to_string());
+    }
+    if cell.contains('"') {
+        return Err(LemmaError::Engine(format!(
+            "Row {}: text value '{}' cannot contain double quotes",
+            line, cell
+        )));
+    }
+    Ok(format!("\"{}\"", cell))
+}
+
+fn is_label(name: &str) -> bool {
+    matches_fully(Rule::label, name)
+}
+
+fn matches_fully(rule: Rule, input: &str) -> bool {
+    LemmaParser::parse(rule, input)
+        .ok()
+        .and_then(|mut pairs| pairs.next())
+        .is_some_and(|pair| pair.as_str().len() == input.len())
+}
+
+/// Turn a header or key (e.g. "Exchange Rate") into a fact name ("exchange_rate")
+pub(crate) fn normalize_name(raw: &str) -> String {
+    let mut name = String::new();
+    for c in raw.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// Split CSV input into records, each with the line it starts on
+///
+/// Quoted fields can span lines, so a record's line isn't its index.
+fn parse_csv_records(input: &str) -> LemmaResult<Vec<(usize, Vec<String>)>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut start = 1;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut record)));
+                line += 1;
+                start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(LemmaError::Engine(
+            "CSV input has an unterminated quoted field".to_string(),
+        ));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push((start, record));
+    }
+
+    // Drop blank lines
+    records.retain(|(_, r)| !(r.len() == 1 && r[0].trim().is_empty()));
+    Ok(records)
+}
@@ -0,0 +1,194 @@
+//! Document templates
+//!
+//! A template declares parameters, `doc tariff(region, currency)`, and is
+//! referenced with an argument for each: `fact tariff = doc tariff("EU", EUR)`.
+//! Every distinct instantiation becomes a copy of the template named after its
+//! arguments, `tariff("EU", EUR)`, in which each parameter is a fact with its
+//! argument as value. The copies are validated and evaluated like any
+//! document; the template itself is neither.
+
+use crate::ast::Span;
+use crate::{
+    FactType, FactValue, LemmaDoc, LemmaError, LemmaFact, LemmaResult, LiteralValue, MoneyUnit,
+};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+
+/// An argument of a template instantiation
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TemplateArgument {
+    Value(LiteralValue),
+    /// A currency code, which also sets the copy's currency like `currency EUR`
+    Currency(MoneyUnit),
+}
+
+impl TemplateArgument {
+    fn value(&self) -> LiteralValue {
+        match self {
+            TemplateArgument::Value(value) => value.clone(),
+            TemplateArgument::Currency(currency) => LiteralValue::Text(currency.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for TemplateArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateArgument::Value(value) => write!(f, "{}", value),
+            TemplateArgument::Currency(currency) => write!(f, "{}", currency),
+        }
+    }
+}
+
+/// Split `docs` into the documents to validate, including a fresh copy of
+/// every template instantiation they reference, and the templates
+///
+/// Copies from an earlier call are dropped and made again, so they follow
+/// changes to their template.
+pub(crate) fn instantiate_templates(
+    docs: Vec<LemmaDoc>,
+) -> LemmaResult<(Vec<LemmaDoc>, Vec<LemmaDoc>)> {
+    let (templates, mut docs): (Vec<LemmaDoc>, Vec<LemmaDoc>) = docs
+        .into_iter()
+        .filter(|doc| doc.template.is_none())
+        .partition(LemmaDoc::is_template);
+    let mut names: HashSet<String> = docs.iter().map(|doc| doc.name.clone()).collect();
+
+    // Copies can instantiate templates in turn
+    let mut index = 0;
+    while index < docs.len() {
+        let mut copies = Vec::new();
+        for fact in &docs[index].facts {
+            let (FactValue::DocumentReference(referenced)
+            | FactValue::DocumentCollection(referenced)) = &fact.value
+            else {
+                continue;
+            };
+            if let Some(template) = templates.iter().find(|t| &t.name == referenced) {
+                return Err(fact_error(
+                    &docs[index],
+                    fact,
+                    format!("Template '{}' is referenced without arguments", referenced),
+                    format!(
+                        "Pass an argument for each parameter: doc {}({})",
+                        template.name,
+                        template.parameters.join(", ")
+                    ),
+                ));
+            }
+            if referenced.contains('(') && names.insert(referenced.clone()) {
+                copies.push(instantiate(
+                    &docs[index],
+                    fact,
+                    referenced,
+                    &templates,
+                    &names,
+                )?);
+            }
+        }
+        docs.extend(copies);
+        index += 1;
+    }
+    Ok((docs, templates))
+}
+
+/// The copy of a template named `instance`, referenced by `fact` of `doc`
+fn instantiate(
+    doc: &LemmaDoc,
+    fact: &LemmaFact,
+    instance: &str,
+    templates: &[LemmaDoc],
+    names: &HashSet<String>,
+) -> LemmaResult<LemmaDoc> {
+    let (name, arguments) = crate::parser::parse_template_instance(instance)?;
+    let Some(template) = templates.iter().find(|t| t.name == name) else {
+        let message = if names.contains(&name) {
+            format!(
+                "Document '{}' has no parameters, so it can't be instantiated as '{}'",
+                name, instance
+            )
+        } else {
+            format!("Template '{}' does not exist", name)
+        };
+        return Err(fact_error(
+            doc,
+            fact,
+            message,
+            format!("Declare the parameters of the template: doc {}(...)", name),
+        ));
+    };
+    if arguments.len() != template.parameters.len() {
+        return Err(fact_error(
+            doc,
+            fact,
+            format!(
+                "Template '{}' has {} parameter(s), but '{}' passes {} argument(s)",
+                name,
+                template.parameters.len(),
+                instance,
+                arguments.len()
+            ),
+            format!(
+                "Pass an argument for each parameter: doc {}({})",
+                name,
+                template.parameters.join(", ")
+            ),
+        ));
+    }
+
+    let mut copy = template.clone();
+    copy.name = instance.to_string();
+    copy.parameters = Vec::new();
+    copy.template = Some(template.name.clone());
+    copy.partial = false;
+
+    let mut parameter_facts = Vec::new();
+    for (parameter, argument) in template.parameters.iter().zip(&arguments) {
+        if let TemplateArgument::Currency(currency) = argument {
+            if copy
+                .currency
+                .as_ref()
+                .is_some_and(|declared| declared != currency)
+            {
+                return Err(fact_error(
+                    doc,
+                    fact,
+                    format!(
+                        "Template '{}' declares currency {}, but '{}' passes {}",
+                        name,
+                        copy.currency.as_ref().map(ToString::to_string).unwrap_or_default(),
+                        instance,
+                        currency
+                    ),
+                    "Remove the currency directive from the template to pass the currency as an argument".to_string(),
+                ));
+            }
+            copy.currency = Some(currency.clone());
+        }
+        parameter_facts.push(LemmaFact::new(
+            FactType::Local(parameter.clone()),
+            FactValue::Literal(argument.value()),
+        ));
+    }
+    copy.facts.splice(0..0, parameter_facts);
+    Ok(copy)
+}
+
+/// A semantic error pointing at `fact` of `doc`
+fn fact_error(doc: &LemmaDoc, fact: &LemmaFact, message: String, suggestion: String) -> LemmaError {
+    LemmaError::Semantic(Box::new(crate::error::ErrorDetails {
+        message,
+        span: fact.span.clone().unwrap_or(Span {
+            start: 0,
+            end: 0,
+            line: doc.start_line,
+            col: 1,
+        }),
+        source_id: doc.source.clone().unwrap_or_else(|| "<input>".to_string()),
+        source_text: Arc::from(""),
+        doc_name: doc.name.clone(),
+        doc_start_line: doc.start_line,
+        suggestion: Some(suggestion),
+    }))
+}
@@ -0,0 +1,241 @@
+//! Golden-file snapshot tests for documents
+//!
+//! A snapshot is the pretty-printed JSON of an evaluation `Response`, stored
+//! as `<name>.json` in a snapshot directory. `Snapshots::assert_evaluation`
+//! evaluates a document with a fact set and fails with a line diff when the
+//! response no longer matches its snapshot:
+//!
+//! ```no_run
+//! use lemma::{testing::Snapshots, Engine};
+//!
+//! let mut engine = Engine::new();
+//! engine.add_lemma_code("doc pricing\nfact quantity = [number]\nrule total = quantity * 10", "pricing.lemma").unwrap();
+//! Snapshots::new("tests/snapshots").assert_evaluation(&engine, "pricing_bulk", "pricing", &["quantity=100"]);
+//! ```
+//!
+//! Run the tests with `LEMMA_UPDATE_SNAPSHOTS=1` to write the current
+//! responses as the new snapshots, including ones that don't exist yet.
+//! Object keys are sorted, so snapshots don't change with hash map order.
+
+use crate::{Engine, LemmaError, Response};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Environment variable that makes snapshot checks write instead of compare
+pub const UPDATE_SNAPSHOTS_ENV: &str = "LEMMA_UPDATE_SNAPSHOTS";
+
+/// Lines of unchanged context around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+/// A directory of snapshots and whether checks update them
+#[derive(Debug, Clone)]
+pub struct Snapshots {
+    dir: PathBuf,
+    update: bool,
+}
+
+/// Why a snapshot check failed
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// There is no snapshot yet and the check doesn't update
+    Missing { path: PathBuf },
+    /// The snapshot differs, with a line diff from the snapshot to the actual output
+    Mismatch { path: PathBuf, diff: String },
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The document could not be evaluated
+    Evaluation(LemmaError),
+}
+
+impl Snapshots {
+    /// Snapshots in `dir`, updated when `LEMMA_UPDATE_SNAPSHOTS` is set to anything but `0`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        let update = std::env::var(UPDATE_SNAPSHOTS_ENV).is_ok_and(|value| value != "0");
+        Self {
+            dir: dir.into(),
+            update,
+        }
+    }
+
+    pub fn with_update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Path of the snapshot called `name`
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", name))
+    }
+
+    /// Compare `actual` with the snapshot called `name`, or store it when updating
+    pub fn check(&self, name: &str, actual: &str) -> Result<(), SnapshotError> {
+        let path = self.path(name);
+        if self.update {
+            let write = std::fs::create_dir_all(&self.dir)
+                .and_then(|_| std::fs::write(&path, with_final_newline(actual)));
+            return write.map_err(|error| SnapshotError::Io { path, error });
+        }
+
+        let expected = match std::fs::read_to_string(&path) {
+            Ok(expected) => expected,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Err(SnapshotError::Missing { path });
+            }
+            Err(error) => return Err(SnapshotError::Io { path, error }),
+        };
+        let actual = with_final_newline(actual);
+        if expected.replace("\r\n", "\n") == actual {
+            Ok(())
+        } else {
+            Err(SnapshotError::Mismatch {
+                diff: diff(&expected, &actual),
+                path,
+            })
+        }
+    }
+
+    /// Compare a response with the snapshot called `name`, see `to_snapshot`
+    pub fn check_response(&self, name: &str, response: &Response) -> Result<(), SnapshotError> {
+        self.check(name, &to_snapshot(response))
+    }
+
+    /// Evaluate `doc_name` with the fact overrides and compare the response with the snapshot called `name`
+    ///
+    /// The overrides are parsed as by `Engine::parse_facts`.
+    pub fn check_evaluation(
+        &self,
+        engine: &Engine,
+        name: &str,
+        doc_name: &str,
+        fact_overrides: &[&str],
+    ) -> Result<(), SnapshotError> {
+        let facts = engine
+            .parse_facts(doc_name, fact_overrides)
+            .map_err(SnapshotError::Evaluation)?;
+        let response = engine
+            .evaluate(doc_name, None, Some(facts))
+            .map_err(SnapshotError::Evaluation)?;
+        self.check_response(name, &response)
+    }
+
+    /// Like `check_evaluation`, but panics with the diff when the snapshot doesn't match
+    #[track_caller]
+    pub fn assert_evaluation(
+        &self,
+        engine: &Engine,
+        name: &str,
+        doc_name: &str,
+        fact_overrides: &[&str],
+    ) {
+        if let Err(error) = self.check_evaluation(engine, name, doc_name, fact_overrides) {
+            panic!("{}", error);
+        }
+    }
+}
+
+/// The snapshot text of a response: pretty JSON with sorted object keys and
+/// results sorted by rule name
+pub fn to_snapshot(response: &Response) -> String {
+    // The order of results isn't stable between runs
+    let mut response = response.clone();
+    response
+        .results
+        .sort_by(|a, b| a.rule_name.cmp(&b.rule_name));
+    // Going through `Value` sorts the keys of bindings and other maps
+    let value = serde_json::to_value(&response).unwrap_or(serde_json::Value::Null);
+    serde_json::to_string_pretty(&value).unwrap_or_default()
+}
+
+/// Line diff from `expected` to `actual`
+///
+/// Removed lines start with `-`, added lines with `+` and unchanged context
+/// with a space. Each group of changes starts with an `@@ -line +line @@` header.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+
+    // Longest common subsequence lengths of every pair of suffixes
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', i, j, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(('-', i, j, old[i]));
+            i += 1;
+        } else {
+            lines.push(('+', i, j, new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = (0..lines.len())
+        .filter(|&index| lines[index].0 != ' ')
+        .collect();
+    // Print each change with its context, starting a group where context doesn't join up
+    let mut output = String::new();
+    let mut shown_until = 0;
+    for &index in &changed {
+        let start = index.saturating_sub(DIFF_CONTEXT).max(shown_until);
+        let end = (index + DIFF_CONTEXT + 1).min(lines.len()).max(shown_until);
+        if output.is_empty() || start > shown_until {
+            let (_, old_line, new_line, _) = lines[start];
+            output.push_str(&format!("@@ -{} +{} @@\n", old_line + 1, new_line + 1));
+        }
+        for &(marker, _, _, text) in &lines[start..end] {
+            output.push_str(&format!("{}{}\n", marker, text));
+        }
+        shown_until = end;
+    }
+    output
+}
+
+fn with_final_newline(text: &str) -> String {
+    let text = text.replace("\r\n", "\n");
+    if text.ends_with('\n') {
+        text
+    } else {
+        format!("{}\n", text)
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Missing { path } => write!(
+                f,
+                "Snapshot {} does not exist; run with {}=1 to create it",
+                path.display(),
+                UPDATE_SNAPSHOTS_ENV
+            ),
+            SnapshotError::Mismatch { path, diff } => write!(
+                f,
+                "Snapshot {} does not match; run with {}=1 to accept the changes\n{}",
+                path.display(),
+                UPDATE_SNAPSHOTS_ENV,
+                diff
+            ),
+            SnapshotError::Io { path, error } => {
+                write!(f, "Failed to access snapshot {}: {}", path.display(), error)
+            }
+            SnapshotError::Evaluation(error) => write!(f, "Evaluation failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
@@ -1,7 +1,7 @@
 use crate::analysis::*;
 use crate::{
     Expression, ExpressionKind, FactReference, FactType, FactValue, LemmaFact, LemmaRule,
-    LemmaType, RuleReference,
+    LemmaType, RuleReference, RuleVisibility, UnlessMode,
 };
 
 #[test]
@@ -54,6 +54,9 @@ fn test_recursive_fact_finding() {
             crate::ExpressionId::new(0),
         ),
         unless_clauses: vec![],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -67,6 +70,9 @@ fn test_recursive_fact_finding() {
             crate::ExpressionId::new(1),
         ),
         unless_clauses: vec![],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -80,6 +86,9 @@ fn test_recursive_fact_finding() {
             crate::ExpressionId::new(2),
         ),
         unless_clauses: vec![],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -1,9 +1,10 @@
 use crate::evaluator::context::EvaluationContext;
 use crate::evaluator::expression::evaluate_expression;
 use crate::evaluator::timeout::TimeoutTracker;
+use crate::functions::FunctionRegistry;
 use crate::{
-    ArithmeticOperation, Expression, ExpressionId, ExpressionKind, FactReference, LemmaDoc,
-    LiteralValue, OperationResult, ResourceLimits,
+    ArithmeticOperation, EvaluationOptions, Expression, ExpressionId, ExpressionKind,
+    FactReference, LemmaDoc, LiteralValue, OperationResult, ResourceLimits,
 };
 use rust_decimal::Decimal;
 use std::collections::HashMap;
@@ -15,8 +16,19 @@ fn create_test_context(facts: HashMap<FactReference, LiteralValue>) -> Evaluatio
     let doc = Box::leak(Box::new(LemmaDoc::new("test".to_string())));
     let limits = Box::leak(Box::new(ResourceLimits::default()));
     let timeout_tracker = Box::leak(Box::new(TimeoutTracker::new()));
+    let options = Box::leak(Box::new(EvaluationOptions::default()));
+    let functions = Box::leak(Box::new(FunctionRegistry::new()));
 
-    EvaluationContext::new(doc, docs, sources, facts, timeout_tracker, limits)
+    EvaluationContext::new(
+        doc,
+        docs,
+        sources,
+        facts,
+        timeout_tracker,
+        limits,
+        functions,
+        options,
+    )
 }
 
 #[test]
@@ -1,9 +1,11 @@
 use crate::evaluator::context::EvaluationContext;
 use crate::evaluator::rules::evaluate_rule;
 use crate::evaluator::timeout::TimeoutTracker;
+use crate::functions::FunctionRegistry;
 use crate::{
-    Expression, ExpressionId, ExpressionKind, FactReference, LemmaDoc, LemmaRule, LiteralValue,
-    OperationResult, ResourceLimits, UnlessClause,
+    EvaluationOptions, Expression, ExpressionId, ExpressionKind, FactReference, LemmaDoc,
+    LemmaRule, LiteralValue, OperationResult, ResourceLimits, RuleVisibility, UnlessClause,
+    UnlessMode,
 };
 use rust_decimal::Decimal;
 use std::collections::HashMap;
@@ -15,8 +17,19 @@ fn create_test_context(facts: HashMap<FactReference, LiteralValue>) -> Evaluatio
     let doc = Box::leak(Box::new(LemmaDoc::new("test".to_string())));
     let limits = Box::leak(Box::new(ResourceLimits::default()));
     let timeout_tracker = Box::leak(Box::new(TimeoutTracker::new()));
+    let options = Box::leak(Box::new(EvaluationOptions::default()));
+    let functions = Box::leak(Box::new(FunctionRegistry::new()));
 
-    EvaluationContext::new(doc, docs, sources, facts, timeout_tracker, limits)
+    EvaluationContext::new(
+        doc,
+        docs,
+        sources,
+        facts,
+        timeout_tracker,
+        limits,
+        functions,
+        options,
+    )
 }
 
 #[test]
@@ -31,6 +44,9 @@ fn test_evaluate_rule_no_unless() {
             ExpressionId::new(0),
         ),
         unless_clauses: vec![],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -65,6 +81,9 @@ fn test_evaluate_rule_with_unless_no_match() {
             ),
             span: None,
         }],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -99,6 +118,9 @@ fn test_evaluate_rule_with_unless_match() {
             ),
             span: None,
         }],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -148,6 +170,9 @@ fn test_evaluate_rule_last_matching_wins() {
                 span: None,
             },
         ],
+        unless_mode: UnlessMode::LastMatch,
+        description: None,
+        visibility: RuleVisibility::Public,
         span: None,
     };
 
@@ -49,6 +49,7 @@ fn test_literal_value_to_type() {
         minute: 0,
         second: 0,
         timezone: None,
+        granularity: DateGranularity::Exact,
     };
     assert_eq!(LiteralValue::Date(dt).to_type(), LemmaType::Date);
     assert_eq!(
@@ -349,6 +350,7 @@ fn test_datetime_value_display() {
             offset_hours: 1,
             offset_minutes: 0,
         }),
+        granularity: DateGranularity::Exact,
     };
     let display = format!("{}", dt);
     assert!(display.contains("2024"));
@@ -418,14 +420,66 @@ fn test_negation_types() {
 
 #[test]
 fn test_veto_expression() {
-    let veto_with_message = VetoExpression {
-        message: Some("Must be over 18".to_string()),
-    };
+    let veto_with_message = VetoExpression::new(Some("Must be over 18".to_string()));
     assert_eq!(
         veto_with_message.message,
         Some("Must be over 18".to_string())
     );
 
-    let veto_without_message = VetoExpression { message: None };
+    let veto_without_message = VetoExpression::new(None);
     assert!(veto_without_message.message.is_none());
 }
+
+#[test]
+fn test_veto_expression_message_for_locale() {
+    let veto = VetoExpression {
+        message: Some("too heavy".to_string()),
+        translations: vec![LocalizedMessage {
+            locale: "nl".to_string(),
+            message: "te zwaar".to_string(),
+        }],
+        arguments: Vec::new(),
+    };
+
+    let message_for = |locales: &[&str]| {
+        let locales: Vec<String> = locales.iter().map(|l| l.to_string()).collect();
+        veto.message_for(&locales)
+    };
+    assert_eq!(message_for(&[]), Some("too heavy".to_string()));
+    assert_eq!(message_for(&["nl"]), Some("te zwaar".to_string()));
+    assert_eq!(message_for(&["nl-BE"]), Some("te zwaar".to_string()));
+    assert_eq!(message_for(&["de"]), Some("too heavy".to_string()));
+    assert_eq!(message_for(&["de", "nl"]), Some("te zwaar".to_string()));
+}
+
+#[test]
+fn test_veto_message_placeholders() {
+    let parts = message_parts("weight {weight} exceeds { limit? } {{kg}}");
+    assert_eq!(
+        parts,
+        vec![
+            MessagePart::Text("weight ".to_string()),
+            MessagePart::Placeholder("weight"),
+            MessagePart::Text(" exceeds ".to_string()),
+            MessagePart::Placeholder("limit?"),
+            MessagePart::Text(" {kg}".to_string()),
+        ]
+    );
+
+    let rendered = render_message("weight {weight} exceeds {limit?}", |name| {
+        (name == "weight").then(|| "120".to_string())
+    });
+    assert_eq!(rendered, "weight 120 exceeds {limit?}");
+}
+
+#[test]
+fn test_veto_message_matches_template() {
+    let template = "weight {weight} exceeds 100";
+    assert!(message_matches(template, template));
+    assert!(message_matches(
+        template,
+        "weight 120 kilograms exceeds 100"
+    ));
+    assert!(!message_matches(template, "weight 120 exceeds 200"));
+    assert!(!message_matches("too heavy", "too heavy!"));
+}
@@ -0,0 +1,303 @@
+//! Translating rules to other languages
+//!
+//! `to_sql` turns a boolean rule into a SQL condition for a `WHERE` clause, so
+//! rows can be pre-filtered in a database with the same logic the engine
+//! evaluates. Only a subset translates: comparisons and boolean logic over
+//! facts and literals, references to other rules in the same document, and
+//! unless clauses. Anything else is an error rather than an approximation.
+//!
+//! Facts declared with a type (`fact age = [number]`) become columns named
+//! after the fact. Facts with a value in the document are inlined. A `NULL`
+//! column behaves like a missing fact: the rule has no value, so the row is
+//! left out. Vetoes are `FALSE`. Percentages are written as their
+//! number, so a percentage column holds `20` for 20%. Documents that compare
+//! text ignoring case compare `LOWER()` of both sides.
+
+use crate::{
+    ComparisonOperator, Expression, ExpressionKind, FactValue, LemmaDoc, LemmaError, LemmaResult,
+    LemmaRule, LemmaType, LiteralValue, TypeAnnotation,
+};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// How deep rule references are followed before giving up
+const MAX_RULE_DEPTH: usize = 32;
+
+/// The SQL flavour to generate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlDialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl SqlDialect {
+    fn identifier(&self, name: &str) -> String {
+        match self {
+            SqlDialect::MySql => format!("`{}`", name.replace('`', "``")),
+            SqlDialect::Postgres | SqlDialect::Sqlite => {
+                format!("\"{}\"", name.replace('"', "\"\""))
+            }
+        }
+    }
+
+    fn text(&self, value: &str) -> String {
+        let escaped = value.replace('\'', "''");
+        match self {
+            SqlDialect::MySql => format!("'{}'", escaped.replace('\\', "\\\\")),
+            SqlDialect::Postgres | SqlDialect::Sqlite => format!("'{}'", escaped),
+        }
+    }
+
+    fn boolean(&self, value: bool) -> &'static str {
+        match (self, value) {
+            (SqlDialect::Sqlite, true) => "1",
+            (SqlDialect::Sqlite, false) => "0",
+            (_, true) => "TRUE",
+            (_, false) => "FALSE",
+        }
+    }
+
+    fn timestamp(&self, value: &str) -> String {
+        match self {
+            // SQLite has no date type, dates are compared as ISO 8601 text
+            SqlDialect::Sqlite => format!("'{}'", value),
+            SqlDialect::Postgres | SqlDialect::MySql => format!("TIMESTAMP '{}'", value),
+        }
+    }
+}
+
+impl std::str::FromStr for SqlDialect {
+    type Err = LemmaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(SqlDialect::Postgres),
+            "mysql" => Ok(SqlDialect::MySql),
+            "sqlite" => Ok(SqlDialect::Sqlite),
+            other => Err(LemmaError::Engine(format!(
+                "Unknown SQL dialect '{}', expected postgres, mysql or sqlite",
+                other
+            ))),
+        }
+    }
+}
+
+/// Translate `rule` in `doc` to a SQL condition
+pub fn to_sql(
+    doc_name: &str,
+    rule_name: &str,
+    dialect: SqlDialect,
+    documents: &HashMap<String, LemmaDoc>,
+) -> LemmaResult<String> {
+    let doc = documents
+        .get(doc_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Document not found: {}", doc_name)))?;
+    let rule = doc
+        .rules
+        .iter()
+        .find(|r| r.name == rule_name)
+        .ok_or_else(|| LemmaError::Engine(format!("Rule not found: {}.{}", doc_name, rule_name)))?;
+
+    let transpiler = SqlTranspiler { doc, dialect };
+    transpiler.rule(rule, 0).map_err(|reason| {
+        LemmaError::Engine(format!(
+            "Cannot translate {}.{} to SQL: {}",
+            doc_name, rule_name, reason
+        ))
+    })
+}
+
+struct SqlTranspiler<'a> {
+    doc: &'a LemmaDoc,
+    dialect: SqlDialect,
+}
+
+impl SqlTranspiler<'_> {
+    fn rule(&self, rule: &LemmaRule, depth: usize) -> Result<String, String> {
+        if depth > MAX_RULE_DEPTH {
+            return Err(format!(
+                "rule references nest deeper than {}",
+                MAX_RULE_DEPTH
+            ));
+        }
+
+        let default = self.expression(&rule.expression, depth)?;
+        if rule.unless_clauses.is_empty() {
+            return Ok(default);
+        }
+
+        let mut sql = String::from("CASE");
+        for index in rule.unless_mode.evaluation_order(rule.unless_clauses.len()) {
+            let clause = &rule.unless_clauses[index];
+            let condition = self.expression(&clause.condition, depth)?;
+            // A condition on a missing fact leaves the rule without a value
+            let _ = write!(
+                sql,
+                " WHEN ({}) IS NULL THEN NULL WHEN {} THEN {}",
+                condition,
+                condition,
+                self.expression(&clause.result, depth)?
+            );
+        }
+        let _ = write!(sql, " ELSE {} END", default);
+        Ok(sql)
+    }
+
+    fn expression(&self, expr: &Expression, depth: usize) -> Result<String, String> {
+        match &expr.kind {
+            ExpressionKind::Literal(value) => self.literal(value),
+            ExpressionKind::FactReference(reference) => self.fact(&reference.reference),
+            ExpressionKind::RuleReference(reference) => {
+                let name = reference.reference.join(".");
+                let rule = self
+                    .doc
+                    .rules
+                    .iter()
+                    .find(|r| r.name == name)
+                    .ok_or_else(|| {
+                        format!("'{}?' is not a rule in document '{}'", name, self.doc.name)
+                    })?;
+                Ok(format!("({})", self.rule(rule, depth + 1)?))
+            }
+            ExpressionKind::LogicalAnd(left, right) => Ok(format!(
+                "({} AND {})",
+                self.expression(left, depth)?,
+                self.expression(right, depth)?
+            )),
+            ExpressionKind::LogicalOr(left, right) => Ok(format!(
+                "({} OR {})",
+                self.expression(left, depth)?,
+                self.expression(right, depth)?
+            )),
+            ExpressionKind::LogicalNegation(inner, _) => {
+                Ok(format!("(NOT {})", self.expression(inner, depth)?))
+            }
+            ExpressionKind::Range(range) => {
+                self.expression(&range.to_conjunction(expr.span.clone(), expr.id), depth)
+            }
+            ExpressionKind::Comparison(left, op, right) => {
+                let op = match op {
+                    ComparisonOperator::GreaterThan => ">",
+                    ComparisonOperator::LessThan => "<",
+                    ComparisonOperator::GreaterThanOrEqual => ">=",
+                    ComparisonOperator::LessThanOrEqual => "<=",
+                    ComparisonOperator::Equal | ComparisonOperator::Is => "=",
+                    ComparisonOperator::NotEqual | ComparisonOperator::IsNot => "<>",
+                };
+                let mut left_sql = self.expression(left, depth)?;
+                let mut right_sql = self.expression(right, depth)?;
+                let text = self.doc.text_comparison;
+                if !text.is_exact() && (self.is_text(left) || self.is_text(right)) {
+                    if text.ignore_accents {
+                        return Err("comparing text ignoring accents is not supported".to_string());
+                    }
+                    left_sql = format!("LOWER({})", left_sql);
+                    right_sql = format!("LOWER({})", right_sql);
+                }
+                Ok(format!("({} {} {})", left_sql, op, right_sql))
+            }
+            ExpressionKind::FactHasAnyValue(reference) => {
+                match self.fact_value(&reference.reference)? {
+                    FactValue::Literal(_) => Ok(self.dialect.boolean(true).to_string()),
+                    _ => Ok(format!(
+                        "({} IS NOT NULL)",
+                        self.dialect.identifier(&reference.reference[0])
+                    )),
+                }
+            }
+            ExpressionKind::Veto(_) => Ok(self.dialect.boolean(false).to_string()),
+            ExpressionKind::Arithmetic(..) => Err("arithmetic is not supported".to_string()),
+            ExpressionKind::RuleHasValue(reference) => Err(format!(
+                "'have {}?' is not supported",
+                reference.reference.join(".")
+            )),
+            ExpressionKind::MissingItems(_) => Err("checklist items are not supported".to_string()),
+            ExpressionKind::FunctionCall(name, _) => {
+                Err(format!("function '{}' is not available in SQL", name))
+            }
+            ExpressionKind::UnitConversion(..) => {
+                Err("unit conversions are not supported".to_string())
+            }
+            ExpressionKind::MathematicalOperator(..) => {
+                Err("mathematical operators are not supported".to_string())
+            }
+            ExpressionKind::Aggregate(_) => Err("aggregates are not supported".to_string()),
+            ExpressionKind::Selection(function, _) => {
+                Err(format!("'{} of' is not supported", function))
+            }
+        }
+    }
+
+    fn fact_value(&self, reference: &[String]) -> Result<&FactValue, String> {
+        let name = match reference {
+            [name] => name,
+            _ => {
+                return Err(format!(
+                    "'{}' refers to another document",
+                    reference.join(".")
+                ))
+            }
+        };
+        self.doc
+            .facts
+            .iter()
+            .find(|f| matches!(&f.fact_type, crate::FactType::Local(n) if n == name))
+            .map(|f| &f.value)
+            .ok_or_else(|| format!("'{}' is not a fact in document '{}'", name, self.doc.name))
+    }
+
+    fn is_text(&self, expr: &Expression) -> bool {
+        match &expr.kind {
+            ExpressionKind::Literal(LiteralValue::Text(_)) => true,
+            ExpressionKind::FactReference(reference) => matches!(
+                self.fact_value(&reference.reference),
+                Ok(FactValue::Literal(LiteralValue::Text(_)))
+                    | Ok(FactValue::TypeAnnotation(TypeAnnotation::LemmaType(
+                        LemmaType::Text
+                    )))
+            ),
+            _ => false,
+        }
+    }
+
+    fn fact(&self, reference: &[String]) -> Result<String, String> {
+        match self.fact_value(reference)? {
+            FactValue::Literal(value) => self.literal(value),
+            FactValue::TypeAnnotation(_) => Ok(self.dialect.identifier(&reference[0])),
+            FactValue::DocumentReference(_) | FactValue::DocumentCollection(_) => {
+                Err(format!("'{}' is a document, not a column", reference[0]))
+            }
+        }
+    }
+
+    fn literal(&self, value: &LiteralValue) -> Result<String, String> {
+        match value {
+            LiteralValue::Number(n)
+            | LiteralValue::Percentage(n)
+            | LiteralValue::PercentagePoints(n) => Ok(n.normalize().to_string()),
+            LiteralValue::Text(s) => Ok(self.dialect.text(s)),
+            LiteralValue::Boolean(b) => Ok(self.dialect.boolean(*b).to_string()),
+            LiteralValue::Date(date) if !date.granularity.is_exact() => Err(format!(
+                "dates covering a whole period ({}) are not supported",
+                date
+            )),
+            LiteralValue::Date(date) if date.timezone.is_none() => {
+                Ok(self.dialect.timestamp(&format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                    date.year, date.month, date.day, date.hour, date.minute, date.second
+                )))
+            }
+            LiteralValue::Date(date) => Err(format!(
+                "dates with a timezone ({}) are not supported",
+                date
+            )),
+            LiteralValue::Unit(unit) => {
+                Err(format!("values with units ({}) are not supported", unit))
+            }
+            LiteralValue::Time(_) | LiteralValue::Regex(_) => {
+                Err(format!("'{}' is not supported", value))
+            }
+        }
+    }
+}
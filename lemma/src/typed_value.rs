@@ -0,0 +1,195 @@
+//! Values as structured JSON for API consumers
+//!
+//! `TypedValue` serializes as an object discriminated by `kind`, so clients
+//! can read amounts and units without parsing display strings:
+//!
+//! ```json
+//! {"kind": "money", "amount": "100.00", "currency": "USD"}
+//! {"kind": "mass", "amount": "5", "unit": "kilogram"}
+//! {"kind": "percentage", "value": "21"}
+//! {"kind": "percentage_points", "value": "2"}
+//! ```
+//!
+//! Numbers and amounts are decimal strings, so no precision is lost in
+//! JavaScript. Dates are ISO 8601.
+
+use crate::{LiteralValue, NumericUnit, TimeValue};
+use serde::Serialize;
+
+/// A value tagged with its kind, see the module documentation
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypedValue {
+    Number {
+        value: String,
+    },
+    /// `value` in percentage points: "21" for 21%
+    Percentage {
+        value: String,
+    },
+    /// An additive change of a percentage: "2" for 2 percentage points
+    PercentagePoints {
+        value: String,
+    },
+    Text {
+        value: String,
+    },
+    Boolean {
+        value: bool,
+    },
+    /// `value` as `2024-01-31T00:00:00`, with a timezone when known
+    Date {
+        value: String,
+    },
+    /// `value` as `14:30:00`, with a timezone when known
+    Time {
+        value: String,
+    },
+    Regex {
+        pattern: String,
+    },
+    Money {
+        amount: String,
+        currency: String,
+    },
+    Mass {
+        amount: String,
+        unit: String,
+    },
+    Length {
+        amount: String,
+        unit: String,
+    },
+    Volume {
+        amount: String,
+        unit: String,
+    },
+    Duration {
+        amount: String,
+        unit: String,
+    },
+    Temperature {
+        amount: String,
+        unit: String,
+    },
+    Power {
+        amount: String,
+        unit: String,
+    },
+    Force {
+        amount: String,
+        unit: String,
+    },
+    Pressure {
+        amount: String,
+        unit: String,
+    },
+    Energy {
+        amount: String,
+        unit: String,
+    },
+    Frequency {
+        amount: String,
+        unit: String,
+    },
+    Data {
+        amount: String,
+        unit: String,
+    },
+}
+
+impl From<&LiteralValue> for TypedValue {
+    fn from(value: &LiteralValue) -> Self {
+        match value {
+            LiteralValue::Number(n) => TypedValue::Number {
+                value: n.to_string(),
+            },
+            LiteralValue::Percentage(p) => TypedValue::Percentage {
+                value: p.to_string(),
+            },
+            LiteralValue::PercentagePoints(p) => TypedValue::PercentagePoints {
+                value: p.to_string(),
+            },
+            LiteralValue::Text(s) => TypedValue::Text { value: s.clone() },
+            LiteralValue::Boolean(b) => TypedValue::Boolean { value: *b },
+            LiteralValue::Date(date) => TypedValue::Date {
+                value: date.to_string(),
+            },
+            LiteralValue::Time(time) => TypedValue::Time {
+                value: time_string(time),
+            },
+            LiteralValue::Regex(pattern) => TypedValue::Regex {
+                pattern: pattern.clone(),
+            },
+            LiteralValue::Unit(unit) => unit_value(unit),
+        }
+    }
+}
+
+impl From<LiteralValue> for TypedValue {
+    fn from(value: LiteralValue) -> Self {
+        TypedValue::from(&value)
+    }
+}
+
+fn unit_value(unit: &NumericUnit) -> TypedValue {
+    let amount = unit.value().to_string();
+    match unit {
+        NumericUnit::Money(_, currency) => TypedValue::Money {
+            amount,
+            currency: currency.to_string(),
+        },
+        NumericUnit::Mass(_, u) => TypedValue::Mass {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Length(_, u) => TypedValue::Length {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Volume(_, u) => TypedValue::Volume {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Duration(_, u) => TypedValue::Duration {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Temperature(_, u) => TypedValue::Temperature {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Power(_, u) => TypedValue::Power {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Force(_, u) => TypedValue::Force {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Pressure(_, u) => TypedValue::Pressure {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Energy(_, u) => TypedValue::Energy {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Frequency(_, u) => TypedValue::Frequency {
+            amount,
+            unit: u.to_string(),
+        },
+        NumericUnit::Data(_, u) => TypedValue::Data {
+            amount,
+            unit: u.to_string(),
+        },
+    }
+}
+
+fn time_string(time: &TimeValue) -> String {
+    let mut value = format!("{:02}:{:02}:{:02}", time.hour, time.minute, time.second);
+    if let Some(timezone) = &time.timezone {
+        value.push_str(&timezone.to_string());
+    }
+    value
+}
@@ -8,6 +8,7 @@ enum ExpressionType {
     Boolean,
     Number,
     Percentage,
+    PercentagePoints,
     Text,
     Money,
     Mass,
@@ -38,6 +39,7 @@ impl ExpressionType {
             ExpressionType::Boolean => "boolean",
             ExpressionType::Number => "number",
             ExpressionType::Percentage => "percentage",
+            ExpressionType::PercentagePoints => "percentage points",
             ExpressionType::Text => "text",
             ExpressionType::Money => "money",
             ExpressionType::Mass => "mass",
@@ -63,6 +65,7 @@ impl ExpressionType {
             crate::LiteralValue::Boolean(_) => ExpressionType::Boolean,
             crate::LiteralValue::Number(_) => ExpressionType::Number,
             crate::LiteralValue::Percentage(_) => ExpressionType::Percentage,
+            crate::LiteralValue::PercentagePoints(_) => ExpressionType::PercentagePoints,
             crate::LiteralValue::Text(_) => ExpressionType::Text,
             crate::LiteralValue::Unit(unit) => match unit {
                 crate::NumericUnit::Money(_, _) => ExpressionType::Money,
@@ -85,8 +88,8 @@ impl ExpressionType {
 }
 
 use crate::{
-    ConversionTarget, Expression, ExpressionKind, FactType, FactValue, LemmaDoc, LemmaError,
-    LemmaResult, LemmaRule, Span,
+    AggregateFunction, ConversionTarget, Expression, ExpressionKind, FactType, FactValue, LemmaDoc,
+    LemmaError, LemmaResult, LemmaRule, LemmaType, Span, TypeAnnotation,
 };
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -95,6 +98,9 @@ use std::sync::Arc;
 #[derive(Debug, Clone)]
 pub struct ValidatedDocuments {
     pub documents: Vec<LemmaDoc>,
+    /// Non-fatal findings per document, e.g. unless clauses that can never
+    /// apply; documents without any are left out
+    pub warnings: HashMap<String, Vec<String>>,
 }
 
 /// Comprehensive semantic validator that runs after parsing but before evaluation
@@ -108,7 +114,19 @@ impl Validator {
     }
 
     /// Validate all documents and return validated documents
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(documents = docs.len()), err(level = "debug"))
+    )]
     pub fn validate_all(&self, docs: Vec<LemmaDoc>) -> LemmaResult<ValidatedDocuments> {
+        // Phase 0: Reject documents declared twice, merge the parts of partial documents
+        let mut docs = self.merge_partial_documents(docs)?;
+        docs.iter_mut()
+            .for_each(keep_unresolved_placeholders_as_text);
+
+        // Templates are validated through the copies their instantiations make
+        let (docs, templates) = crate::templates::instantiate_templates(docs)?;
+
         // Phase 1: Check for duplicate facts and rules within each document
         self.validate_duplicates(&docs)?;
 
@@ -124,7 +142,368 @@ impl Validator {
         // Phase 5: Validate expression types
         self.validate_expression_types(&docs)?;
 
-        Ok(ValidatedDocuments { documents: docs })
+        // Phase 6: Check money against documents' declared currency
+        self.validate_document_currency(&docs)?;
+
+        let warnings = docs
+            .iter()
+            .map(|doc| {
+                let found = self.warnings(doc, |name| docs.iter().find(|d| d.name == name));
+                (doc.name.clone(), found)
+            })
+            .filter(|(_, found)| !found.is_empty())
+            .collect();
+
+        let mut documents = docs;
+        documents.extend(templates);
+        Ok(ValidatedDocuments {
+            documents,
+            warnings,
+        })
+    }
+
+    /// Merge the parts of each `partial doc` into one document
+    ///
+    /// A document name may only be declared more than once when every
+    /// declaration is partial. Parts are merged in order: facts and rules are
+    /// appended, the first commentary is kept, and a fact or rule may only be
+    /// defined in one part. Invariants are numbered on from the earlier parts.
+    /// Directives must agree between the parts. The engine keeps the parts of
+    /// each source and passes them all again, so a part loaded again is
+    /// merged afresh.
+    fn merge_partial_documents(&self, docs: Vec<LemmaDoc>) -> LemmaResult<Vec<LemmaDoc>> {
+        let mut merged: Vec<LemmaDoc> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+
+        for mut doc in docs {
+            let Some(&position) = positions.get(&doc.name) else {
+                positions.insert(doc.name.clone(), merged.len());
+                merged.push(doc);
+                continue;
+            };
+            let first = &mut merged[position];
+            renumber_invariants(&mut doc, first.invariants.len());
+
+            if !(first.partial && doc.partial) {
+                return Err(document_error(
+                    &doc,
+                    format!(
+                        "Document '{}' is defined in both {} and {}",
+                        doc.name,
+                        doc_location(first, first.start_line),
+                        doc_location(&doc, doc.start_line)
+                    ),
+                    format!(
+                        "Rename one of the documents, or declare every part as 'partial doc {}' to split the document across files.",
+                        doc.name
+                    ),
+                ));
+            }
+
+            if doc.currency.is_some() && first.currency.is_some() && doc.currency != first.currency
+                || doc.declared_version.is_some()
+                    && first.declared_version.is_some()
+                    && doc.declared_version != first.declared_version
+                || doc.text_comparison != first.text_comparison
+                || doc.division_by_zero != first.division_by_zero
+            {
+                return Err(document_error(
+                    &doc,
+                    format!(
+                        "Parts of document '{}' in {} and {} use different directives",
+                        doc.name,
+                        doc_location(first, first.start_line),
+                        doc_location(&doc, doc.start_line)
+                    ),
+                    "Give every part the same 'version', 'currency', 'compare text' and 'on division by zero' directives, or set them in one part only.".to_string(),
+                ));
+            }
+
+            for fact in &doc.facts {
+                let name = crate::analysis::fact_display_name(fact);
+                if let Some(existing) = first
+                    .facts
+                    .iter()
+                    .find(|f| crate::analysis::fact_display_name(f) == name)
+                {
+                    return Err(part_conflict(
+                        first,
+                        &doc,
+                        "Fact",
+                        &name,
+                        &existing.span,
+                        &fact.span,
+                    ));
+                }
+            }
+            for rule in &doc.rules {
+                if let Some(existing) = first.rules.iter().find(|r| r.name == rule.name) {
+                    return Err(part_conflict(
+                        first,
+                        &doc,
+                        "Rule",
+                        &rule.name,
+                        &existing.span,
+                        &rule.span,
+                    ));
+                }
+            }
+
+            if first.currency.is_none() {
+                first.currency = doc.currency;
+            }
+            if first.declared_version.is_none() {
+                first.declared_version = doc.declared_version;
+            }
+            if first.commentary.is_none() {
+                first.commentary = doc.commentary;
+            }
+            first.facts.extend(doc.facts);
+            first.rules.extend(doc.rules);
+            first.checklists.extend(doc.checklists);
+            first.invariants.extend(doc.invariants);
+            first.records.extend(doc.records);
+        }
+
+        Ok(merged)
+    }
+
+    /// Non-fatal findings in a document, reported alongside its results
+    ///
+    /// `find_doc` looks up the documents it references, for the findings
+    /// about its overrides.
+    pub fn warnings<'a>(
+        &self,
+        doc: &LemmaDoc,
+        find_doc: impl Fn(&str) -> Option<&'a LemmaDoc>,
+    ) -> Vec<String> {
+        let mut warnings = self.unreachable_clause_warnings(doc);
+        warnings.extend(self.percentage_change_warnings(doc));
+        warnings.extend(self.override_warnings(doc, find_doc));
+        warnings
+    }
+
+    /// Find unless clauses of referenced documents that the overrides decide
+    ///
+    /// With `fact config.max_weight = 0`, a clause `unless max_weight > 0`
+    /// in `config` never applies. Conditions are folded with the literal
+    /// overrides of one referenced document; other facts are unknown, so
+    /// only clauses that don't depend on them are reported.
+    pub fn override_warnings<'a>(
+        &self,
+        doc: &LemmaDoc,
+        find_doc: impl Fn(&str) -> Option<&'a LemmaDoc>,
+    ) -> Vec<String> {
+        // The literal overrides per referenced document, by its path
+        let mut overrides: Vec<(&[String], HashMap<String, crate::LiteralValue>)> = Vec::new();
+        for fact in &doc.facts {
+            let (FactType::Foreign(foreign), FactValue::Literal(value)) =
+                (&fact.fact_type, &fact.value)
+            else {
+                continue;
+            };
+            let Some((name, path)) = foreign.reference.split_last() else {
+                continue;
+            };
+            match overrides.iter_mut().find(|(p, _)| *p == path) {
+                Some((_, values)) => {
+                    values.insert(name.clone(), value.clone());
+                }
+                None => overrides.push((path, HashMap::from([(name.clone(), value.clone())]))),
+            }
+        }
+
+        let mut warnings = Vec::new();
+        for (path, values) in overrides {
+            let Some(target) = referenced_doc(doc, path, &find_doc) else {
+                continue;
+            };
+            let values: HashMap<String, crate::LiteralValue> = values
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = target.coerce_to_currency(&value).unwrap_or(value);
+                    (name, value)
+                })
+                .collect();
+
+            for rule in &target.rules {
+                for (index, clause) in rule.unless_clauses.iter().enumerate() {
+                    let mut used: Vec<String> =
+                        crate::analysis::extract_references(&clause.condition)
+                            .facts
+                            .into_iter()
+                            .filter_map(|fact| match fact.reference.as_slice() {
+                                [name] => values.get(name).map(|value| {
+                                    format!("{}.{} = {}", path.join("."), name, value)
+                                }),
+                                _ => None,
+                            })
+                            .collect();
+                    if used.is_empty() {
+                        continue;
+                    }
+                    used.sort();
+                    let outcome = match fold_constant(&clause.condition, &values, target) {
+                        Some(crate::LiteralValue::Boolean(false)) => "never applies",
+                        Some(crate::LiteralValue::Boolean(true)) => {
+                            "always applies, so the rule's default expression is never used"
+                        }
+                        _ => continue,
+                    };
+                    warnings.push(format!(
+                        "Document '{}': overriding {} means unless clause {} of rule '{}' in document '{}' {}",
+                        doc.name,
+                        used.join(", "),
+                        index + 1,
+                        rule.name,
+                        target.name,
+                        outcome
+                    ));
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Find percentages added to or subtracted from percentages
+    ///
+    /// `5% + 2%` changes 5% by 2% of itself (5.1%), which is easily mistaken
+    /// for adding percentage points (7%).
+    pub fn percentage_change_warnings(&self, doc: &LemmaDoc) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for rule in &doc.rules {
+            let mut changes = Vec::new();
+            self.find_percentage_changes(&rule.expression, doc, &mut changes);
+            for clause in &rule.unless_clauses {
+                self.find_percentage_changes(&clause.condition, doc, &mut changes);
+                self.find_percentage_changes(&clause.result, doc, &mut changes);
+            }
+            for expr in changes {
+                let line = expr
+                    .span
+                    .as_ref()
+                    .map(|span| format!(" on line {}", span.line))
+                    .unwrap_or_default();
+                warnings.push(format!(
+                    "Rule '{}' in document '{}': {}{} changes a percentage relatively (5% + 2% is 5.1%), write percentage points for an absolute change (5% + 2 percentage points is 7%)",
+                    rule.name, doc.name, expr, line
+                ));
+            }
+        }
+        warnings
+    }
+
+    fn find_percentage_changes<'a>(
+        &self,
+        expr: &'a Expression,
+        doc: &LemmaDoc,
+        changes: &mut Vec<&'a Expression>,
+    ) {
+        match &expr.kind {
+            ExpressionKind::Arithmetic(left, op, right) => {
+                let is_percentage = |operand: &Expression| {
+                    matches!(
+                        self.infer_expression_type_with_context(operand, Some(doc)),
+                        Ok(ExpressionType::Percentage)
+                    )
+                };
+                if matches!(
+                    op,
+                    crate::ArithmeticOperation::Add | crate::ArithmeticOperation::Subtract
+                ) && is_percentage(left)
+                    && is_percentage(right)
+                {
+                    changes.push(expr);
+                }
+                self.find_percentage_changes(left, doc, changes);
+                self.find_percentage_changes(right, doc, changes);
+            }
+            ExpressionKind::Comparison(left, _, right)
+            | ExpressionKind::LogicalAnd(left, right)
+            | ExpressionKind::LogicalOr(left, right) => {
+                self.find_percentage_changes(left, doc, changes);
+                self.find_percentage_changes(right, doc, changes);
+            }
+            ExpressionKind::LogicalNegation(inner, _)
+            | ExpressionKind::MathematicalOperator(_, inner)
+            | ExpressionKind::UnitConversion(inner, _) => {
+                self.find_percentage_changes(inner, doc, changes)
+            }
+            ExpressionKind::Range(range) => {
+                for operand in range.operands() {
+                    self.find_percentage_changes(operand, doc, changes);
+                }
+            }
+            ExpressionKind::FunctionCall(_, arguments)
+            | ExpressionKind::Selection(_, arguments) => {
+                for argument in arguments {
+                    self.find_percentage_changes(argument, doc, changes);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Find unless clauses that can never decide a rule's result
+    ///
+    /// A clause is unreachable when a clause checked before it (per the rule's
+    /// unless mode) always matches or has the same condition. The default
+    /// expression is unreachable when any clause always matches.
+    pub fn unreachable_clause_warnings(&self, doc: &LemmaDoc) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        for rule in &doc.rules {
+            let before = match rule.unless_mode {
+                crate::UnlessMode::LastMatch => "a later",
+                crate::UnlessMode::FirstMatch => "an earlier",
+            };
+            let mut conditions: HashSet<String> = HashSet::new();
+            let mut always_matches = false;
+
+            for index in rule.unless_mode.evaluation_order(rule.unless_clauses.len()) {
+                let clause = &rule.unless_clauses[index];
+                let condition = clause.condition.to_string();
+                let line = clause
+                    .span
+                    .as_ref()
+                    .map(|span| format!(" on line {}", span.line))
+                    .unwrap_or_default();
+
+                let reason = if always_matches {
+                    Some("always matches")
+                } else if conditions.contains(&condition) {
+                    Some("has the same condition")
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    warnings.push(format!(
+                        "Rule '{}' in document '{}': unless clause {}{} is unreachable, {} clause {}",
+                        rule.name,
+                        doc.name,
+                        index + 1,
+                        line,
+                        before,
+                        reason
+                    ));
+                }
+
+                always_matches |= matches!(
+                    clause.condition.kind,
+                    ExpressionKind::Literal(crate::LiteralValue::Boolean(true))
+                );
+                conditions.insert(condition);
+            }
+
+            if always_matches {
+                warnings.push(format!(
+                    "Rule '{}' in document '{}': default expression is unreachable, an unless clause always matches",
+                    rule.name, doc.name
+                ));
+            }
+        }
+
+        warnings
     }
 
     /// Check for duplicate facts and rules within each document
@@ -243,6 +622,43 @@ impl Validator {
             })));
                 }
             }
+
+            // Check for fact groups sharing a name with a fact or rule
+            for fact in &doc.facts {
+                let Some(group) = crate::analysis::fact_group(fact, doc) else {
+                    continue;
+                };
+                let clashes_with = if fact_names.contains_key(group) {
+                    "fact"
+                } else if rule_names.contains_key(group) {
+                    "rule"
+                } else {
+                    continue;
+                };
+                return Err(LemmaError::Semantic(Box::new(crate::error::ErrorDetails {
+                    message: format!(
+                        "Name conflict: '{}' is used both as a fact group and as a {}",
+                        group, clashes_with
+                    ),
+                    span: fact.span.clone().unwrap_or(Span {
+                        start: 0,
+                        end: 0,
+                        line: 0,
+                        col: 0,
+                    }),
+                    source_id: doc.source.clone().unwrap_or_else(|| "<input>".to_string()),
+                    source_text: Arc::from(""),
+                    doc_name: doc.name.clone(),
+                    doc_start_line: doc.start_line,
+                    suggestion: Some(format!(
+                        "Fact '{}' groups its fields under '{}'. Rename the group or the {} '{}'.",
+                        crate::analysis::fact_display_name(fact),
+                        group,
+                        clashes_with,
+                        group
+                    )),
+                })));
+            }
         }
         Ok(())
     }
@@ -251,7 +667,9 @@ impl Validator {
     fn validate_document_references(&self, docs: &[LemmaDoc]) -> LemmaResult<()> {
         for doc in docs {
             for fact in &doc.facts {
-                if let FactValue::DocumentReference(ref_doc_name) = &fact.value {
+                if let FactValue::DocumentReference(ref_doc_name)
+                | FactValue::DocumentCollection(ref_doc_name) = &fact.value
+                {
                     // Check if the referenced document exists
                     if !docs.iter().any(|d| d.name == *ref_doc_name) {
                         return Err(LemmaError::Semantic(Box::new(crate::error::ErrorDetails {
@@ -334,7 +752,7 @@ impl Validator {
             ExpressionKind::FactReference(fact_ref) => {
                 self.validate_fact_reference(fact_ref, expr, current_doc, all_docs)
             }
-            ExpressionKind::RuleReference(rule_ref) => {
+            ExpressionKind::RuleReference(rule_ref) | ExpressionKind::RuleHasValue(rule_ref) => {
                 self.validate_rule_reference(rule_ref, expr, current_doc, all_docs)
             }
             // Recursively validate nested expressions
@@ -352,6 +770,20 @@ impl Validator {
             | ExpressionKind::UnitConversion(inner, _) => {
                 self.validate_expression_references(inner, current_doc, all_docs)
             }
+            ExpressionKind::Range(range) => range.operands().into_iter().try_for_each(|operand| {
+                self.validate_expression_references(operand, current_doc, all_docs)
+            }),
+            ExpressionKind::Aggregate(aggregate) => {
+                self.validate_aggregate(aggregate, expr, current_doc, all_docs)
+            }
+            ExpressionKind::MissingItems(items)
+            | ExpressionKind::FunctionCall(_, items)
+            | ExpressionKind::Selection(_, items) => items.iter().try_for_each(|item| {
+                self.validate_expression_references(item, current_doc, all_docs)
+            }),
+            ExpressionKind::Veto(veto) => veto.arguments.iter().try_for_each(|argument| {
+                self.validate_expression_references(&argument.expression, current_doc, all_docs)
+            }),
             ExpressionKind::FactHasAnyValue(_fact_ref) => {
                 // For "have" expressions, we don't validate the fact reference as it's a dynamic check
                 Ok(())
@@ -370,6 +802,10 @@ impl Validator {
     ) -> LemmaResult<()> {
         let ref_name = fact_ref.reference.join(".");
 
+        if let Some(path) = fact_ref.caller_fact() {
+            return self.validate_caller_fact_ref(&path.join("."), expr, current_doc, all_docs);
+        }
+
         // Single-segment reference
         if fact_ref.reference.len() == 1 {
             return self.validate_single_segment_fact_ref(&ref_name, expr, current_doc);
@@ -393,6 +829,45 @@ impl Validator {
         )
     }
 
+    /// Validate a `caller.` fact reference against every document referencing the current one
+    ///
+    /// A document is referenced by a fact holding it, a collection of it, or,
+    /// for a copy of a template, a fact holding that instantiation.
+    fn validate_caller_fact_ref(
+        &self,
+        fact_name: &str,
+        expr: &Expression,
+        current_doc: &LemmaDoc,
+        all_docs: &[LemmaDoc],
+    ) -> LemmaResult<()> {
+        for doc in all_docs {
+            for fact in &doc.facts {
+                let (FactValue::DocumentReference(ref_doc_name)
+                | FactValue::DocumentCollection(ref_doc_name)) = &fact.value
+                else {
+                    continue;
+                };
+                if *ref_doc_name != current_doc.name || self.is_fact_in_doc(fact_name, doc) {
+                    continue;
+                }
+                return Err(self.create_reference_error(
+                    format!(
+                        "Reference error: 'caller.{}' in document '{}' needs a fact '{}' in document '{}', which references it through '{}'",
+                        fact_name,
+                        current_doc.name,
+                        fact_name,
+                        doc.name,
+                        crate::analysis::fact_display_name(fact)
+                    ),
+                    format!("Add a fact '{}' to document '{}'", fact_name, doc.name),
+                    expr,
+                    current_doc,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Validate a single-segment fact reference
     fn validate_single_segment_fact_ref(
         &self,
@@ -437,6 +912,11 @@ impl Validator {
             return Ok(());
         }
 
+        // A grouped fact such as `customer.name` is a fact of the current document
+        if self.is_fact_in_doc(ref_name, current_doc) {
+            return Ok(());
+        }
+
         // Check if it's a rule in the current document
         if self.is_rule_in_doc(field_name, current_doc) {
             return Err(self.create_reference_error(
@@ -510,6 +990,11 @@ impl Validator {
         current_doc: &LemmaDoc,
         all_docs: &[LemmaDoc],
     ) -> LemmaResult<()> {
+        // Rules derived from a checklist have dotted names
+        if self.is_rule_in_doc(ref_name, current_doc) {
+            return Ok(());
+        }
+
         // Check if first segment is a fact that references a document
         if let Some(referenced_doc) = self.get_referenced_doc(doc_ref, current_doc, all_docs) {
             if self.is_fact_in_doc(field_name, referenced_doc) {
@@ -535,6 +1020,123 @@ impl Validator {
         Ok(())
     }
 
+    /// Validate that an aggregate ranges over a collection whose documents have the rule
+    ///
+    /// `total of` a fact also needs the fact to be money.
+    fn validate_aggregate(
+        &self,
+        aggregate: &crate::AggregateExpression,
+        expr: &Expression,
+        current_doc: &LemmaDoc,
+        all_docs: &[LemmaDoc],
+    ) -> LemmaResult<()> {
+        let ref_name = aggregate.collection.join(".");
+        let Some((collection_name, doc_path)) = aggregate.collection.split_last() else {
+            return Ok(());
+        };
+
+        let mut doc = current_doc;
+        for fact_name in doc_path {
+            match self.get_referenced_doc(fact_name, doc, all_docs) {
+                Some(referenced) => doc = referenced,
+                None => return Ok(()), // Reported by the document reference checks
+            }
+        }
+
+        let item_doc_name = doc
+            .facts
+            .iter()
+            .find_map(|f| match (&f.fact_type, &f.value) {
+                (FactType::Local(name), FactValue::DocumentCollection(item_doc))
+                    if name == collection_name =>
+                {
+                    Some(item_doc)
+                }
+                _ => None,
+            });
+        let Some(item_doc_name) = item_doc_name else {
+            return Err(self.create_reference_error(
+                format!(
+                    "Reference error: '{}' in '{}' is not a document collection",
+                    ref_name, aggregate
+                ),
+                format!(
+                    "Declare the collection with 'fact {} = many doc <document>'",
+                    collection_name
+                ),
+                expr,
+                current_doc,
+            ));
+        };
+
+        if let Some(rule_name) = &aggregate.rule {
+            let item_doc = all_docs.iter().find(|d| d.name == *item_doc_name);
+            if item_doc.is_some_and(|d| !self.is_rule_in_doc(rule_name, d)) {
+                return Err(self.create_reference_error(
+                    format!(
+                        "Reference error: document '{}' has no rule '{}' to aggregate in '{}'",
+                        item_doc_name, rule_name, aggregate
+                    ),
+                    format!(
+                        "Add 'rule {} = ...' to document '{}'",
+                        rule_name, item_doc_name
+                    ),
+                    expr,
+                    current_doc,
+                ));
+            }
+        }
+
+        if let Some(fact_name) = &aggregate.fact {
+            let Some(item_doc) = all_docs.iter().find(|d| d.name == *item_doc_name) else {
+                return Ok(());
+            };
+            let fact = item_doc
+                .facts
+                .iter()
+                .find(|f| matches!(&f.fact_type, FactType::Local(name) if name == fact_name));
+            let fact_type = match fact.map(|f| &f.value) {
+                None => {
+                    return Err(self.create_reference_error(
+                        format!(
+                            "Reference error: document '{}' has no fact '{}' to total in '{}'",
+                            item_doc_name, fact_name, aggregate
+                        ),
+                        format!(
+                            "Add 'fact {} = [money]' to document '{}'",
+                            fact_name, item_doc_name
+                        ),
+                        expr,
+                        current_doc,
+                    ));
+                }
+                Some(FactValue::Literal(value)) => Some(value.to_type()),
+                Some(FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type))) => {
+                    Some(lemma_type.clone())
+                }
+                Some(_) => None,
+            };
+            if fact_type != Some(LemmaType::Money) {
+                let described = fact_type
+                    .map(|t| t.to_string())
+                    .unwrap_or_else(|| "a document".to_string());
+                return Err(self.create_reference_error(
+                    format!(
+                        "Type error: '{}' adds money, but fact '{}' of document '{}' is {}",
+                        aggregate, fact_name, item_doc_name, described
+                    ),
+                    format!(
+                        "Use 'sum of {}.<rule>?' to add other values",
+                        aggregate.collection.join(".")
+                    ),
+                    expr,
+                    current_doc,
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Helper to create a semantic error for reference validation
     fn create_reference_error(
         &self,
@@ -562,6 +1164,125 @@ impl Validator {
         }))
     }
 
+    /// Reject money in other currencies than the one a document declares
+    ///
+    /// Applies to money literals in facts and rules, and to `in <currency>` conversions.
+    fn validate_document_currency(&self, docs: &[LemmaDoc]) -> LemmaResult<()> {
+        for doc in docs {
+            let Some(currency) = &doc.currency else {
+                continue;
+            };
+
+            for fact in &doc.facts {
+                if let FactValue::Literal(crate::LiteralValue::Unit(crate::NumericUnit::Money(
+                    _,
+                    used,
+                ))) = &fact.value
+                {
+                    if used != currency {
+                        return Err(self.currency_error(
+                            format!(
+                                "Fact '{}' uses {}, but document '{}' declares currency {}",
+                                crate::analysis::fact_display_name(fact),
+                                used,
+                                doc.name,
+                                currency
+                            ),
+                            fact.span.clone(),
+                            currency,
+                            doc,
+                        ));
+                    }
+                }
+            }
+
+            for rule in &doc.rules {
+                let expressions = std::iter::once(&rule.expression).chain(
+                    rule.unless_clauses
+                        .iter()
+                        .flat_map(|clause| [&clause.condition, &clause.result]),
+                );
+                for expression in expressions {
+                    if let Some((used, expr)) = self.find_foreign_currency(expression, currency) {
+                        return Err(self.currency_error(
+                            format!(
+                                "Rule '{}' uses {}, but document '{}' declares currency {}",
+                                rule.name, used, doc.name, currency
+                            ),
+                            expr.span.clone(),
+                            currency,
+                            doc,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Find the first money literal or conversion in a currency other than `currency`
+    fn find_foreign_currency<'a>(
+        &self,
+        expr: &'a Expression,
+        currency: &crate::MoneyUnit,
+    ) -> Option<(crate::MoneyUnit, &'a Expression)> {
+        match &expr.kind {
+            ExpressionKind::Literal(crate::LiteralValue::Unit(crate::NumericUnit::Money(
+                _,
+                used,
+            ))) if used != currency => Some((used.clone(), expr)),
+            ExpressionKind::UnitConversion(inner, target) => match target {
+                ConversionTarget::Money(used) if used != currency => Some((used.clone(), expr)),
+                _ => self.find_foreign_currency(inner, currency),
+            },
+            ExpressionKind::Arithmetic(left, _, right)
+            | ExpressionKind::Comparison(left, _, right)
+            | ExpressionKind::LogicalAnd(left, right)
+            | ExpressionKind::LogicalOr(left, right) => self
+                .find_foreign_currency(left, currency)
+                .or_else(|| self.find_foreign_currency(right, currency)),
+            ExpressionKind::LogicalNegation(inner, _)
+            | ExpressionKind::MathematicalOperator(_, inner) => {
+                self.find_foreign_currency(inner, currency)
+            }
+            ExpressionKind::Range(range) => range
+                .operands()
+                .into_iter()
+                .find_map(|operand| self.find_foreign_currency(operand, currency)),
+            ExpressionKind::FunctionCall(_, arguments)
+            | ExpressionKind::Selection(_, arguments) => arguments
+                .iter()
+                .find_map(|argument| self.find_foreign_currency(argument, currency)),
+            _ => None,
+        }
+    }
+
+    fn currency_error(
+        &self,
+        message: String,
+        span: Option<Span>,
+        currency: &crate::MoneyUnit,
+        doc: &LemmaDoc,
+    ) -> LemmaError {
+        LemmaError::semantic_with_suggestion(
+            message,
+            span.unwrap_or(Span {
+                start: 0,
+                end: 0,
+                line: 0,
+                col: 0,
+            }),
+            doc.source.clone().unwrap_or_else(|| "<input>".to_string()),
+            Arc::from(""),
+            doc.name.clone(),
+            doc.start_line,
+            format!(
+                "Write the amount in {}, or give a plain number and convert it with 'in {}'",
+                currency, currency
+            ),
+        )
+    }
+
     /// Check for circular dependencies in rules (moved from document transpiler)
     fn check_circular_dependencies(&self, docs: &[LemmaDoc]) -> LemmaResult<()> {
         // Build dependency graph from all rules across all documents
@@ -692,6 +1413,9 @@ impl Validator {
                 }
                 self.validate_rule_type_consistency(rule, doc)?;
             }
+            for invariant in &doc.invariants {
+                self.validate_logical_operand(&invariant.condition, doc, "invariant")?;
+            }
         }
         Ok(())
     }
@@ -716,10 +1440,19 @@ impl Validator {
                 self.validate_expression_type(right, doc)?;
                 self.validate_money_arithmetic(left, right, doc)?;
             }
-            ExpressionKind::Comparison(left, _op, right) => {
+            ExpressionKind::Comparison(left, op, right) => {
                 self.validate_expression_type(left, doc)?;
                 self.validate_expression_type(right, doc)?;
                 self.validate_money_comparison(left, right, doc)?;
+                self.validate_constant_comparison(left, op, right, doc)?;
+                self.validate_comparison_kinds(left, op, right, doc)?;
+            }
+            ExpressionKind::Range(range) => {
+                // Checked as the comparisons it makes
+                self.validate_expression_type(
+                    &range.to_conjunction(expr.span.clone(), expr.id),
+                    doc,
+                )?;
             }
             ExpressionKind::LogicalNegation(inner, _negation_type) => {
                 self.validate_expression_type(inner, doc)?;
@@ -730,11 +1463,90 @@ impl Validator {
             ExpressionKind::UnitConversion(value, _target) => {
                 self.validate_expression_type(value, doc)?;
             }
+            ExpressionKind::MissingItems(items) => {
+                for item in items {
+                    self.validate_logical_operand(item, doc, "checklist")?;
+                }
+            }
+            ExpressionKind::FunctionCall(_, arguments) => {
+                for argument in arguments {
+                    self.validate_expression_type(argument, doc)?;
+                }
+            }
+            ExpressionKind::Selection(function, candidates) => {
+                self.validate_selection(*function, candidates, doc)?;
+            }
             _ => {}
         }
         Ok(())
     }
 
+    /// Validate that the candidates of `lowest of a, b` and the like can be compared
+    ///
+    /// `earliest` and `latest` take dates, the others anything else, and the
+    /// candidates must have the same type. Only literals and facts are checked:
+    /// the inferred type of arithmetic doesn't keep units.
+    fn validate_selection(
+        &self,
+        function: crate::SelectionFunction,
+        candidates: &[Expression],
+        doc: &LemmaDoc,
+    ) -> LemmaResult<()> {
+        let mut first: Option<ExpressionType> = None;
+        for candidate in candidates {
+            self.validate_expression_type(candidate, doc)?;
+            if !matches!(
+                candidate.kind,
+                ExpressionKind::Literal(_) | ExpressionKind::FactReference(_)
+            ) {
+                continue;
+            }
+            let candidate_type = self.infer_expression_type_with_context(candidate, Some(doc))?;
+            if matches!(
+                candidate_type,
+                ExpressionType::Unknown | ExpressionType::Never
+            ) {
+                continue;
+            }
+
+            let message = if (candidate_type == ExpressionType::Date) != function.selects_dates() {
+                let expected = if function.selects_dates() {
+                    "dates"
+                } else {
+                    "values other than dates (use 'earliest' or 'latest' for dates)"
+                };
+                Some(format!(
+                    "Type error: '{} of' compares {}, but '{}' is {}",
+                    function,
+                    expected,
+                    candidate,
+                    candidate_type.name()
+                ))
+            } else {
+                match &first {
+                    Some(first) if *first != candidate_type => Some(format!(
+                        "Type error: '{} of' cannot compare {} with {} ('{}')",
+                        function,
+                        first.name(),
+                        candidate_type.name(),
+                        candidate
+                    )),
+                    _ => None,
+                }
+            };
+            if let Some(message) = message {
+                return Err(self.create_reference_error(
+                    message,
+                    "Give every candidate the same type".to_string(),
+                    candidate,
+                    doc,
+                ));
+            }
+            first.get_or_insert(candidate_type);
+        }
+        Ok(())
+    }
+
     /// Helper to validate that an operand is boolean for logical operators
     fn validate_logical_operand(
         &self,
@@ -870,6 +1682,127 @@ impl Validator {
         Ok(())
     }
 
+    /// Validate that a comparison of literal values can be evaluated
+    ///
+    /// Facts count as literal when they have a value: an override keeps the
+    /// type, and comparability only depends on the types.
+    fn validate_constant_comparison(
+        &self,
+        left: &Expression,
+        op: &crate::ComparisonOperator,
+        right: &Expression,
+        doc: &LemmaDoc,
+    ) -> LemmaResult<()> {
+        let (Some(left_value), Some(right_value)) = (
+            self.extract_literal(left, doc),
+            self.extract_literal(right, doc),
+        ) else {
+            return Ok(());
+        };
+        crate::semantic::value_ops::compare(
+            left_value,
+            op,
+            right_value,
+            crate::TextComparison::default(),
+        )
+        .map(|_| ())
+        .map_err(|error| {
+            let reason = match error {
+                LemmaError::Engine(message) => message,
+                other => other.to_string(),
+            };
+            self.create_reference_error(
+                format!(
+                    "Type error: cannot evaluate '{} {} {}': {}",
+                    left, op, right, reason
+                ),
+                "Compare values of compatible types".to_string(),
+                left,
+                doc,
+            )
+        })
+    }
+
+    /// Validate that text and booleans are only compared with their own kind
+    ///
+    /// Unlike `validate_constant_comparison` this also covers typed facts, so
+    /// `weight > limit` is rejected when `weight` is `[number]` and a template
+    /// argument makes `limit` text.
+    fn validate_comparison_kinds(
+        &self,
+        left: &Expression,
+        op: &crate::ComparisonOperator,
+        right: &Expression,
+        doc: &LemmaDoc,
+    ) -> LemmaResult<()> {
+        let (Some(left_type), Some(right_type)) = (
+            self.comparison_operand_type(left, doc),
+            self.comparison_operand_type(right, doc),
+        ) else {
+            return Ok(());
+        };
+        let textual = |t: &LemmaType| matches!(t, LemmaType::Text | LemmaType::Regex);
+        let mismatched = textual(&left_type) != textual(&right_type)
+            || (left_type == LemmaType::Boolean) != (right_type == LemmaType::Boolean);
+        if !mismatched {
+            return Ok(());
+        }
+        Err(self.create_reference_error(
+            format!(
+                "Type error: cannot compare {} with {} in '{} {} {}'",
+                left_type, right_type, left, op, right
+            ),
+            "Compare values of compatible types".to_string(),
+            left,
+            doc,
+        ))
+    }
+
+    /// The type of a literal or of a local fact with a value or declared type
+    fn comparison_operand_type(&self, expr: &Expression, doc: &LemmaDoc) -> Option<LemmaType> {
+        match &expr.kind {
+            ExpressionKind::Literal(value) => Some(value.to_type()),
+            ExpressionKind::FactReference(fact_ref) => {
+                let name = fact_ref.reference.join(".");
+                let fact = doc
+                    .facts
+                    .iter()
+                    .find(|fact| crate::analysis::fact_display_name(fact) == name)?;
+                match &fact.value {
+                    FactValue::Literal(value) => Some(value.to_type()),
+                    FactValue::TypeAnnotation(TypeAnnotation::LemmaType(lemma_type)) => {
+                        Some(lemma_type.clone())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The literal an expression is, directly or as a local fact with a value
+    fn extract_literal<'a>(
+        &self,
+        expr: &'a Expression,
+        doc: &'a LemmaDoc,
+    ) -> Option<&'a crate::LiteralValue> {
+        match &expr.kind {
+            ExpressionKind::Literal(value) => Some(value),
+            ExpressionKind::FactReference(fact_ref) if fact_ref.reference.len() == 1 => doc
+                .facts
+                .iter()
+                .find_map(|fact| match (&fact.fact_type, &fact.value) {
+                    (crate::FactType::Local(name), crate::FactValue::Literal(value))
+                        if *name == fact_ref.reference[0] =>
+                    {
+                        Some(value)
+                    }
+                    _ => None,
+                }),
+            _ => None,
+        }
+    }
+
     /// Extract currency from an expression if it's a Money type
     fn extract_currency(&self, expr: &Expression, doc: &LemmaDoc) -> Option<crate::MoneyUnit> {
         match &expr.kind {
@@ -911,10 +1844,13 @@ impl Validator {
         match &expr.kind {
             ExpressionKind::Literal(lit) => Ok(ExpressionType::from_literal(lit)),
             ExpressionKind::Comparison(_, _, _) => Ok(ExpressionType::Boolean),
+            ExpressionKind::Range(_) => Ok(ExpressionType::Boolean),
             ExpressionKind::LogicalAnd(_, _) => Ok(ExpressionType::Boolean),
             ExpressionKind::LogicalOr(_, _) => Ok(ExpressionType::Boolean),
             ExpressionKind::LogicalNegation(_, _) => Ok(ExpressionType::Boolean),
             ExpressionKind::FactHasAnyValue(_) => Ok(ExpressionType::Boolean),
+            ExpressionKind::RuleHasValue(_) => Ok(ExpressionType::Boolean),
+            ExpressionKind::MissingItems(_) => Ok(ExpressionType::Text),
             ExpressionKind::Veto(_) => Ok(ExpressionType::Never),
             ExpressionKind::FactReference(fact_ref) => {
                 // Try to resolve fact type from document
@@ -948,6 +1884,25 @@ impl Validator {
                 Ok(ExpressionType::Number)
             }
             ExpressionKind::MathematicalOperator(_, _) => Ok(ExpressionType::Number),
+            // Only known once the host application's function returns
+            ExpressionKind::FunctionCall(_, _) => Ok(ExpressionType::Unknown),
+            ExpressionKind::Selection(_, candidates) => {
+                let mut types = Vec::with_capacity(candidates.len());
+                for candidate in candidates {
+                    types.push(self.infer_expression_type_with_context(candidate, doc)?);
+                }
+                // The winner's type, when every candidate has the same one
+                match types.split_first() {
+                    Some((first, rest)) if rest.iter().all(|t| t == first) => Ok(first.clone()),
+                    _ => Ok(ExpressionType::Unknown),
+                }
+            }
+            ExpressionKind::Aggregate(aggregate) => match aggregate.function {
+                AggregateFunction::Count => Ok(ExpressionType::Number),
+                AggregateFunction::Total => Ok(ExpressionType::Money),
+                // Like rule references, depends on the referenced rule's type
+                _ => Ok(ExpressionType::Unknown),
+            },
             ExpressionKind::UnitConversion(value_expr, target) => {
                 let value_type = self.infer_expression_type_with_context(value_expr, doc)?;
                 Ok(self.infer_conversion_result_type(&value_type, target))
@@ -985,3 +1940,191 @@ impl Validator {
         }
     }
 }
+
+/// Where a document or one of its definitions is declared, e.g. `'a.lemma' (line 3)`
+fn doc_location(doc: &LemmaDoc, line: usize) -> String {
+    format!(
+        "'{}' (line {})",
+        doc.source.as_deref().unwrap_or("<input>"),
+        line
+    )
+}
+
+/// A semantic error pointing at the declaration of `doc`
+fn document_error(doc: &LemmaDoc, message: String, suggestion: String) -> LemmaError {
+    LemmaError::Semantic(Box::new(crate::error::ErrorDetails {
+        message,
+        span: Span {
+            start: 0,
+            end: 0,
+            line: doc.start_line,
+            col: 1,
+        },
+        source_id: doc.source.clone().unwrap_or_else(|| "<input>".to_string()),
+        source_text: Arc::from(""),
+        doc_name: doc.name.clone(),
+        doc_start_line: doc.start_line,
+        suggestion: Some(suggestion),
+    }))
+}
+
+/// Leave placeholders of veto messages that name no fact of `doc` as text
+///
+/// In a message like "see {details}" the braces may be meant literally. Only
+/// `{rule?}`, `{caller.x}` and names of facts, or of facts holding referenced
+/// documents, are filled in.
+fn keep_unresolved_placeholders_as_text(doc: &mut LemmaDoc) {
+    let fact_names: HashSet<String> = doc
+        .facts
+        .iter()
+        .map(crate::analysis::fact_display_name)
+        .collect();
+    let resolves = |argument: &crate::MessageArgument| match &argument.expression.kind {
+        ExpressionKind::FactReference(fact_ref) => {
+            fact_ref.caller_fact().is_some()
+                || fact_names.contains(&fact_ref.reference.join("."))
+                || fact_names.contains(&fact_ref.reference[0])
+        }
+        _ => true,
+    };
+    for rule in &mut doc.rules {
+        let results = std::iter::once(&mut rule.expression).chain(
+            rule.unless_clauses
+                .iter_mut()
+                .map(|clause| &mut clause.result),
+        );
+        for result in results {
+            if let ExpressionKind::Veto(veto) = &mut result.kind {
+                veto.arguments.retain(resolves);
+            }
+        }
+    }
+}
+
+/// Number the invariants of a part after the `earlier` ones of the parts before it
+fn renumber_invariants(doc: &mut LemmaDoc, earlier: usize) {
+    // Highest first, so a new name is never one still to be renamed
+    for invariant in doc.invariants.iter_mut().rev() {
+        let rule_name = invariant.rule_name();
+        invariant.number += earlier;
+        if let Some(rule) = doc.rules.iter_mut().find(|rule| rule.name == rule_name) {
+            rule.name = invariant.rule_name();
+        }
+    }
+}
+
+/// A fact or rule defined in two parts of a partial document
+fn part_conflict(
+    first: &LemmaDoc,
+    part: &LemmaDoc,
+    kind: &str,
+    name: &str,
+    first_span: &Option<Span>,
+    span: &Option<Span>,
+) -> LemmaError {
+    let line = |span: &Option<Span>, doc: &LemmaDoc| {
+        span.as_ref().map_or(doc.start_line, |span| span.line)
+    };
+    document_error(
+        part,
+        format!(
+            "{} '{}' of document '{}' is defined in both {} and {}",
+            kind,
+            name,
+            part.name,
+            doc_location(first, line(first_span, first)),
+            doc_location(part, line(span, part))
+        ),
+        format!(
+            "Each {} of a partial document can only be defined in one of its parts.",
+            kind.to_lowercase()
+        ),
+    )
+}
+
+/// The document `path` leads to through document references, e.g. `order.config`
+fn referenced_doc<'a>(
+    doc: &LemmaDoc,
+    path: &[String],
+    find_doc: &impl Fn(&str) -> Option<&'a LemmaDoc>,
+) -> Option<&'a LemmaDoc> {
+    let doc_reference = |doc: &LemmaDoc, name: &str| {
+        doc.facts
+            .iter()
+            .find_map(|fact| match (&fact.fact_type, &fact.value) {
+                (FactType::Local(fact_name), FactValue::DocumentReference(doc_name))
+                    if fact_name == name =>
+                {
+                    Some(doc_name.clone())
+                }
+                _ => None,
+            })
+    };
+    let (first, rest) = path.split_first()?;
+    let mut target = find_doc(&doc_reference(doc, first)?)?;
+    for segment in rest {
+        target = find_doc(&doc_reference(target, segment)?)?;
+    }
+    Some(target)
+}
+
+/// The value of `expr` when its facts are `values`, if that decides it
+///
+/// `false and x` is false and `true or x` is true whatever `x` is.
+fn fold_constant(
+    expr: &Expression,
+    values: &HashMap<String, crate::LiteralValue>,
+    doc: &LemmaDoc,
+) -> Option<crate::LiteralValue> {
+    use crate::LiteralValue::Boolean;
+    let fold = |expr: &Expression| fold_constant(expr, values, doc);
+    match &expr.kind {
+        ExpressionKind::Literal(value) => Some(value.clone()),
+        ExpressionKind::FactReference(fact) => match fact.reference.as_slice() {
+            [name] => values.get(name).cloned(),
+            _ => None,
+        },
+        ExpressionKind::Arithmetic(left, op, right) => {
+            crate::evaluator::operations::arithmetic_operation(&fold(left)?, op, &fold(right)?).ok()
+        }
+        ExpressionKind::Comparison(left, op, right) => crate::semantic::value_ops::compare(
+            &fold(left)?,
+            op,
+            &fold(right)?,
+            doc.text_comparison,
+        )
+        .ok()
+        .map(Boolean),
+        ExpressionKind::Range(range) => {
+            let value = fold(&range.value)?;
+            let within = |operator, bound| {
+                crate::semantic::value_ops::compare(
+                    &value,
+                    operator,
+                    &fold(bound)?,
+                    doc.text_comparison,
+                )
+                .ok()
+            };
+            Some(Boolean(
+                within(&range.low_operator, &range.low)?
+                    && within(&range.high_operator, &range.high)?,
+            ))
+        }
+        ExpressionKind::LogicalAnd(left, right) => match (fold(left), fold(right)) {
+            (Some(Boolean(false)), _) | (_, Some(Boolean(false))) => Some(Boolean(false)),
+            (Some(Boolean(true)), Some(Boolean(true))) => Some(Boolean(true)),
+            _ => None,
+        },
+        ExpressionKind::LogicalOr(left, right) => match (fold(left), fold(right)) {
+            (Some(Boolean(true)), _) | (_, Some(Boolean(true))) => Some(Boolean(true)),
+            (Some(Boolean(false)), Some(Boolean(false))) => Some(Boolean(false)),
+            _ => None,
+        },
+        ExpressionKind::LogicalNegation(inner, crate::NegationType::Not) => match fold(inner)? {
+            Boolean(value) => Some(Boolean(!value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
@@ -0,0 +1,100 @@
+//! Content hashes of documents
+//!
+//! A document's version is the SHA-256 of its canonical text (its `Display`
+//! form, so layout and comments outside the commentary block don't matter)
+//! together with the versions of the documents it references. Editing a
+//! referenced document changes the version of every document using it.
+
+use crate::{FactValue, LemmaDoc};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Versions of all documents, keyed by document name
+pub fn document_versions(documents: &HashMap<String, LemmaDoc>) -> HashMap<String, String> {
+    let mut versions = HashMap::new();
+    for name in documents.keys() {
+        version_of(name, documents, &mut versions, 0);
+    }
+    versions
+}
+
+fn version_of(
+    name: &str,
+    documents: &HashMap<String, LemmaDoc>,
+    versions: &mut HashMap<String, String>,
+    depth: usize,
+) -> Option<String> {
+    if let Some(version) = versions.get(name) {
+        return Some(version.clone());
+    }
+    // Validation rejects reference cycles; the limit only guards against them
+    if depth > crate::analysis::MAX_INSTANCE_DEPTH {
+        return None;
+    }
+    let doc = documents.get(name)?;
+
+    let mut referenced: Vec<&str> = doc
+        .facts
+        .iter()
+        .filter_map(|fact| match &fact.value {
+            FactValue::DocumentReference(name) | FactValue::DocumentCollection(name) => {
+                Some(name.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    referenced.sort_unstable();
+    referenced.dedup();
+
+    let mut hasher = Sha256::new();
+    hasher.update(doc.to_string().as_bytes());
+    for reference in referenced {
+        let version = version_of(reference, documents, versions, depth + 1).unwrap_or_default();
+        hasher.update(format!("\n{} {}", reference, version).as_bytes());
+    }
+
+    let mut version = String::with_capacity(64);
+    for byte in hasher.finalize() {
+        let _ = write!(version, "{:02x}", byte);
+    }
+    versions.insert(name.to_string(), version.clone());
+    Some(version)
+}
+
+/// The documents as they were loaded when tagged, see `Engine::tag_version`
+#[derive(Debug, Clone)]
+pub(crate) struct TaggedVersion {
+    pub label: String,
+    pub documents: HashMap<String, LemmaDoc>,
+    pub sources: HashMap<String, String>,
+    pub versions: HashMap<String, String>,
+    pub warnings: HashMap<String, Vec<String>>,
+}
+
+/// The tagged version to evaluate as of `as_of`
+///
+/// A tag with exactly that label wins. Otherwise, when `as_of` is a date
+/// (`2024`, `2024-06` or `2024-06-15`), the latest version tagged with a date
+/// on or before it: the one in force at that time. ISO dates compare as text,
+/// and `2024-06` counts as the start of June.
+pub(crate) fn select_tag<'a>(tags: &'a [TaggedVersion], as_of: &str) -> Option<&'a TaggedVersion> {
+    if let Some(tag) = tags.iter().find(|tag| tag.label == as_of) {
+        return Some(tag);
+    }
+    if !is_date_label(as_of) {
+        return None;
+    }
+    tags.iter()
+        .filter(|tag| is_date_label(&tag.label) && tag.label.as_str() <= as_of)
+        .max_by(|a, b| a.label.cmp(&b.label))
+}
+
+fn is_date_label(label: &str) -> bool {
+    let parts: Vec<&str> = label.split('-').collect();
+    let widths = [4, 2, 2];
+    parts.len() <= widths.len()
+        && parts.iter().zip(widths).all(|(part, width)| {
+            part.len() == width && part.bytes().all(|byte| byte.is_ascii_digit())
+        })
+}
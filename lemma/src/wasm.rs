@@ -29,6 +29,28 @@ impl WasmEngine {
         }
     }
 
+    /// Replace lines `start_line..end_line` (1-based, end exclusive) of an added source
+    #[wasm_bindgen(js_name = updateDocumentRange)]
+    pub fn update_document_range(
+        &mut self,
+        source: &str,
+        start_line: usize,
+        end_line: usize,
+        text: &str,
+    ) -> String {
+        match self
+            .engine
+            .update_document_range(source, start_line..end_line, text)
+        {
+            Ok(_) => r#"{"success":true,"message":"Document updated successfully","error":null}"#
+                .to_string(),
+            Err(e) => format!(
+                r#"{{"success":false,"message":null,"error":"{}"}}"#,
+                format_error(&e).replace('"', "\\\"")
+            ),
+        }
+    }
+
     #[wasm_bindgen(js_name = evaluate)]
     pub fn evaluate(&mut self, doc_name: &str, fact_values_json: &str) -> String {
         // Convert JSON object to Lemma syntax strings using serializers
@@ -76,14 +98,16 @@ impl WasmEngine {
                 for result in response.results {
                     let mut rule_obj = serde_json::Map::new();
 
-                    // Transform the result to clean type/value format
+                    // The result as a `kind`-tagged value, see `TypedValue`
                     if let Some(ref lit_val) = result.result {
                         rule_obj.insert(
                             "result".to_string(),
-                            serde_json::json!({
-                                "type": lit_val.to_type().to_string(),
-                                "value": lit_val.display_value()
-                            }),
+                            serde_json::to_value(crate::TypedValue::from(lit_val))
+                                .unwrap_or(serde_json::Value::Null),
+                        );
+                        rule_obj.insert(
+                            "display".to_string(),
+                            serde_json::Value::String(lit_val.display_value()),
                         );
                     } else {
                         rule_obj.insert("result".to_string(), serde_json::Value::Null);
@@ -94,6 +118,24 @@ impl WasmEngine {
                         rule_obj.insert("veto".to_string(), serde_json::Value::String(veto_msg));
                     }
 
+                    if let Some(reason) = result.unknown {
+                        rule_obj.insert("unknown".to_string(), serde_json::Value::String(reason));
+                    }
+
+                    if let Some(limit) = result.limit_exceeded {
+                        rule_obj.insert(
+                            "limit_exceeded".to_string(),
+                            serde_json::Value::String(limit),
+                        );
+                    }
+
+                    if let Some(description) = result.description {
+                        rule_obj.insert(
+                            "description".to_string(),
+                            serde_json::Value::String(description),
+                        );
+                    }
+
                     // Include missing facts if present
                     if let Some(missing) = result.missing_facts {
                         if !missing.is_empty() {
@@ -212,6 +254,185 @@ impl WasmEngine {
     }
 }
 
+/// The browser playground: files being edited, with examples and permalinks
+///
+/// Every call answers with JSON holding `success` and `errors`, an array of
+/// errors with a line and column, see `playground::errors_json`.
+#[cfg(feature = "playground")]
+#[wasm_bindgen]
+pub struct WasmPlayground {
+    state: crate::playground::PlaygroundState,
+    engine: Engine,
+    errors: Vec<LemmaError>,
+}
+
+#[cfg(feature = "playground")]
+#[wasm_bindgen]
+impl WasmPlayground {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        console_error_panic_hook::set_once();
+
+        WasmPlayground {
+            state: Default::default(),
+            engine: Engine::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// The bundled examples, by name with their file names
+    #[wasm_bindgen(js_name = examples)]
+    pub fn examples() -> String {
+        let examples: Vec<serde_json::Value> = crate::playground::examples()
+            .iter()
+            .map(|example| {
+                let files: Vec<&str> = example.files.iter().map(|(name, _)| *name).collect();
+                serde_json::json!({ "name": example.name, "files": files })
+            })
+            .collect();
+        serde_json::json!({ "success": true, "examples": examples, "errors": [] }).to_string()
+    }
+
+    #[wasm_bindgen(js_name = loadExample)]
+    pub fn load_example(&mut self, name: &str) -> String {
+        match crate::playground::example(name) {
+            Some(example) => {
+                self.state = example.state();
+                self.reload()
+            }
+            None => failure(&LemmaError::Engine(format!("Unknown example '{}'", name))),
+        }
+    }
+
+    /// Restore a shared playground from its link or `#w=...` fragment
+    #[wasm_bindgen(js_name = loadPermalink)]
+    pub fn load_permalink(&mut self, link: &str) -> String {
+        match crate::playground::PlaygroundState::from_permalink(link) {
+            Ok(state) => {
+                self.state = state;
+                self.reload()
+            }
+            Err(e) => failure(&e),
+        }
+    }
+
+    /// The fragment restoring the current files, document and facts
+    #[wasm_bindgen(js_name = permalink)]
+    pub fn permalink(&self) -> String {
+        self.state.to_permalink()
+    }
+
+    /// Files, picked document and facts, as the page shows them
+    #[wasm_bindgen(js_name = state)]
+    pub fn state(&self) -> String {
+        serde_json::json!({ "success": true, "state": self.state, "errors": [] }).to_string()
+    }
+
+    /// Add or replace a file, then parse and validate all files
+    #[wasm_bindgen(js_name = setFile)]
+    pub fn set_file(&mut self, name: &str, code: &str) -> String {
+        self.state.set_file(name, code);
+        self.reload()
+    }
+
+    #[wasm_bindgen(js_name = removeFile)]
+    pub fn remove_file(&mut self, name: &str) -> String {
+        self.state.remove_file(name);
+        self.reload()
+    }
+
+    /// Evaluate `doc_name` with facts as a JSON object, keeping both for the permalink
+    #[wasm_bindgen(js_name = evaluate)]
+    pub fn evaluate(&mut self, doc_name: &str, fact_values_json: &str) -> String {
+        let facts = if fact_values_json.trim().is_empty() {
+            serde_json::Map::new()
+        } else {
+            match serde_json::from_str(fact_values_json) {
+                Ok(facts) => facts,
+                Err(e) => {
+                    return failure(&LemmaError::Engine(format!("Invalid facts JSON: {}", e)))
+                }
+            }
+        };
+        self.state.document = Some(doc_name.to_string());
+        self.state.facts = facts;
+
+        match self.state.evaluate(&self.engine) {
+            Ok(response) => serde_json::json!({
+                "success": true,
+                "response": response,
+                "errors": crate::playground::errors_json(&self.errors),
+            })
+            .to_string(),
+            Err(e) => failure(&e),
+        }
+    }
+
+    /// Like `WasmEngine::invert`, with JSON errors
+    #[wasm_bindgen(js_name = invert)]
+    pub fn invert(
+        &self,
+        doc_name: &str,
+        rule_name: &str,
+        target_json: &str,
+        given_facts_json: &str,
+    ) -> String {
+        let target = match parse_target_from_json(target_json) {
+            Ok(target) => target,
+            Err(e) => return failure(&LemmaError::Engine(format!("Invalid target: {}", e))),
+        };
+        let given_facts = if given_facts_json.is_empty() || given_facts_json == "{}" {
+            std::collections::HashMap::new()
+        } else {
+            match parse_given_facts_from_json(given_facts_json, doc_name, &self.engine) {
+                Ok(facts) => facts,
+                Err(e) => {
+                    return failure(&LemmaError::Engine(format!("Invalid given facts: {}", e)))
+                }
+            }
+        };
+
+        match self.engine.invert(doc_name, rule_name, target, given_facts) {
+            Ok(solutions) => {
+                let solutions: Vec<serde_json::Value> = solutions
+                    .iter()
+                    .map(|solution| {
+                        solution
+                            .iter()
+                            .map(|(fact_path, domain)| {
+                                (fact_path.to_string(), domain_to_json(domain))
+                            })
+                            .collect::<serde_json::Map<_, _>>()
+                            .into()
+                    })
+                    .collect();
+                serde_json::json!({ "success": true, "solutions": solutions, "errors": [] })
+                    .to_string()
+            }
+            Err(e) => failure(&e),
+        }
+    }
+
+    fn reload(&mut self) -> String {
+        (self.engine, self.errors) = self.state.load();
+        serde_json::json!({
+            "success": self.errors.is_empty(),
+            "documents": self.engine.list_documents(),
+            "errors": crate::playground::errors_json(&self.errors),
+        })
+        .to_string()
+    }
+}
+
+#[cfg(feature = "playground")]
+fn failure(error: &LemmaError) -> String {
+    serde_json::json!({
+        "success": false,
+        "errors": crate::playground::errors_json(std::slice::from_ref(error)),
+    })
+    .to_string()
+}
+
 fn parse_target_from_json(target_json: &str) -> Result<crate::Target, String> {
     use crate::{OperationResult, Target, TargetOp};
 
@@ -288,7 +509,7 @@ fn parse_given_facts_from_json(
 }
 
 fn json_to_literal_value(value: &serde_json::Value) -> Result<crate::LiteralValue, String> {
-    use crate::{LiteralValue, MoneyUnit, NumericUnit};
+    use crate::LiteralValue;
     use rust_decimal::Decimal;
 
     match value {
@@ -298,30 +519,9 @@ fn json_to_literal_value(value: &serde_json::Value) -> Result<crate::LiteralValu
                 .map_err(|e| format!("Invalid number: {}", e))?;
             Ok(LiteralValue::Number(decimal))
         }
+        // Lemma literals like "10%" or "100 EUR", anything else is text
         serde_json::Value::String(s) => {
-            // Try to parse as percentage
-            if s.ends_with('%') {
-                let num_str = &s[..s.len() - 1];
-                let decimal = Decimal::from_str_exact(num_str)
-                    .map_err(|e| format!("Invalid percentage: {}", e))?;
-                Ok(LiteralValue::Percentage(decimal))
-            } else if s.ends_with("EUR") || s.ends_with("USD") {
-                // Parse money
-                let parts: Vec<&str> = s.split_whitespace().collect();
-                if parts.len() != 2 {
-                    return Err(format!("Invalid money format: {}", s));
-                }
-                let amount = Decimal::from_str_exact(parts[0])
-                    .map_err(|e| format!("Invalid money amount: {}", e))?;
-                let unit = match parts[1] {
-                    "EUR" => MoneyUnit::Eur,
-                    "USD" => MoneyUnit::Usd,
-                    _ => return Err(format!("Unknown currency: {}", parts[1])),
-                };
-                Ok(LiteralValue::Unit(NumericUnit::Money(amount, unit)))
-            } else {
-                Ok(LiteralValue::Text(s.clone()))
-            }
+            Ok(crate::parse_literal(s).unwrap_or_else(|_| LiteralValue::Text(s.clone())))
         }
         _ => Err(format!("Unsupported value type: {:?}", value)),
     }
@@ -403,6 +603,12 @@ fn format_error(error: &LemmaError) -> String {
                 limit_name, limit_value, actual_value, suggestion
             )
         }
+        LemmaError::FactParse(errors) => {
+            let error_messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            format!("Fact Parse Error: {}", error_messages.join("; "))
+        }
+        LemmaError::VersionMismatch { .. } => format!("Version Mismatch: {}", error),
+        LemmaError::UnknownResult(_) | LemmaError::Cancelled { .. } => error.to_string(),
         LemmaError::MultipleErrors(errors) => {
             let error_messages: Vec<String> = errors.iter().map(format_error).collect();
             format!("Multiple Errors:\n{}", error_messages.join("\n"))
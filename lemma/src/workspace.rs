@@ -0,0 +1,112 @@
+//! Loading directories of `.lemma` files
+//!
+//! Files are read and parsed on all available cores. Results are put back in
+//! file path order, so the documents and errors that come out don't depend on
+//! which thread finished first.
+
+use crate::{parse, LemmaDoc, LemmaError, LemmaResult, ResourceLimits};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// Progress reported while loading a directory
+#[derive(Debug, Clone)]
+pub struct LoadProgress {
+    /// The file that was just parsed
+    pub path: PathBuf,
+    /// Number of files parsed so far, including `path`
+    pub parsed: usize,
+    /// Number of files being loaded
+    pub total: usize,
+}
+
+/// A file read and parsed by `parse_files`
+pub(crate) struct ParsedFile {
+    pub source: String,
+    pub code: String,
+    pub docs: LemmaResult<Vec<LemmaDoc>>,
+}
+
+/// All `.lemma` files below `dir`, sorted by path
+pub(crate) fn lemma_files(dir: &Path) -> LemmaResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_lemma_files(dir, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_lemma_files(dir: &Path, files: &mut Vec<PathBuf>) -> LemmaResult<()> {
+    let entries = std::fs::read_dir(dir).map_err(|e| read_error(dir, e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| read_error(dir, e))?;
+        let path = entry.path();
+        let file_type = entry.file_type().map_err(|e| read_error(&path, e))?;
+        if file_type.is_dir() {
+            collect_lemma_files(&path, files)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some("lemma") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Read and parse `files` in parallel, returning them in the same order
+pub(crate) fn parse_files(
+    files: &[PathBuf],
+    limits: &ResourceLimits,
+    progress: &mut dyn FnMut(&LoadProgress),
+) -> Vec<ParsedFile> {
+    let workers = std::thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(files.len());
+    let next = AtomicUsize::new(0);
+    let mut parsed: Vec<Option<ParsedFile>> = files.iter().map(|_| None).collect();
+
+    std::thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(path) = files.get(index) else {
+                    break;
+                };
+                if sender.send((index, parse_file(path, limits))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        for (count, (index, file)) in receiver.into_iter().enumerate() {
+            parsed[index] = Some(file);
+            progress(&LoadProgress {
+                path: files[index].clone(),
+                parsed: count + 1,
+                total: files.len(),
+            });
+        }
+    });
+
+    parsed.into_iter().flatten().collect()
+}
+
+fn parse_file(path: &Path, limits: &ResourceLimits) -> ParsedFile {
+    let source = path.to_string_lossy().to_string();
+    match std::fs::read_to_string(path) {
+        Ok(code) => {
+            let docs = parse(&code, Some(source.clone()), limits);
+            ParsedFile { source, code, docs }
+        }
+        Err(e) => ParsedFile {
+            source,
+            code: String::new(),
+            docs: Err(read_error(path, e)),
+        },
+    }
+}
+
+fn read_error(path: &Path, error: std::io::Error) -> LemmaError {
+    LemmaError::Engine(format!("Failed to read '{}': {}", path.display(), error))
+}
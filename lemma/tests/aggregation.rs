@@ -0,0 +1,197 @@
+//! Document collection and aggregation tests
+//!
+//! Key behaviors:
+//! 1. `fact line_items = many doc line_item` declares a collection of documents
+//! 2. Instances are created by overrides like `line_items[0].quantity=3`
+//! 3. `sum`, `min` and `max` aggregate a rule across instances, `count` counts them
+//! 4. Instance rules can reference rules of their own document
+//! 5. A vetoed instance vetoes the aggregate
+//! 6. Aggregating something that is not a collection is a semantic error
+
+mod common;
+
+use common::{result, value};
+use lemma::{Engine, LemmaError};
+
+const CODE: &str = r#"
+doc line_item
+fact quantity = 1
+fact unit_price = 10 EUR
+rule subtotal = quantity * unit_price
+rule total = subtotal?
+    unless quantity < 0 then veto "Quantity cannot be negative"
+
+doc order
+fact line_items = many doc line_item
+fact line_items[0].quantity = 2
+fact line_items[1].quantity = 3
+fact line_items[1].unit_price = 5 EUR
+rule order_total = sum of line_items.total?
+rule item_count = count of line_items
+rule cheapest = min of line_items.total?
+rule most_expensive = max of line_items.total?
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_sum_over_document_instances() {
+    let engine = engine();
+    assert_eq!(value(&engine, "order", &[], "order_total"), "35 EUR");
+}
+
+#[test]
+fn test_count_min_max() {
+    let engine = engine();
+    assert_eq!(value(&engine, "order", &[], "item_count"), "2");
+    assert_eq!(value(&engine, "order", &[], "cheapest"), "15 EUR");
+    assert_eq!(value(&engine, "order", &[], "most_expensive"), "20 EUR");
+}
+
+#[test]
+fn test_overrides_add_and_change_instances() {
+    let engine = engine();
+    let overrides = ["line_items[1].quantity=1", "line_items[4].quantity=10"];
+
+    assert_eq!(value(&engine, "order", &overrides, "item_count"), "3");
+    assert_eq!(
+        value(&engine, "order", &overrides, "order_total"),
+        "125 EUR"
+    );
+}
+
+#[test]
+fn test_empty_collection() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            r#"
+doc item
+fact price = 10
+rule price_total = price
+
+doc basket
+fact items = many doc item
+rule total = sum of items.price_total?
+rule items_count = count of items
+rule highest = max of items.price_total?
+"#,
+            "test.lemma",
+        )
+        .unwrap();
+
+    assert_eq!(value(&engine, "basket", &[], "total"), "0");
+    assert_eq!(value(&engine, "basket", &[], "items_count"), "0");
+
+    let highest = result(&engine, "basket", &[], "highest");
+    assert_eq!(highest.result, None);
+    assert_eq!(
+        highest.veto_message.as_deref(),
+        Some("items has no instances")
+    );
+}
+
+#[test]
+fn test_vetoed_instance_vetoes_aggregate() {
+    let engine = engine();
+    let total = result(
+        &engine,
+        "order",
+        &["line_items[0].quantity=-1"],
+        "order_total",
+    );
+
+    assert_eq!(total.result, None);
+    assert_eq!(
+        total.veto_message.as_deref(),
+        Some("Quantity cannot be negative")
+    );
+}
+
+#[test]
+fn test_aggregate_through_document_reference() {
+    let mut engine = engine();
+    engine
+        .add_lemma_code(
+            r#"
+doc invoice
+fact order = doc order
+rule amount_due = order.order_total? + 5 EUR
+"#,
+            "invoice.lemma",
+        )
+        .unwrap();
+
+    assert_eq!(value(&engine, "invoice", &[], "amount_due"), "40 EUR");
+    assert_eq!(
+        value(
+            &engine,
+            "invoice",
+            &["order.line_items[2].quantity=1"],
+            "amount_due"
+        ),
+        "50 EUR"
+    );
+}
+
+#[test]
+fn test_aggregate_of_non_collection_is_rejected() {
+    let mut engine = Engine::new();
+    let result = engine.add_lemma_code(
+        r#"
+doc item
+fact price = 10
+rule price_total = price
+
+doc basket
+fact item = doc item
+rule total = sum of item.price_total?
+"#,
+        "test.lemma",
+    );
+
+    match result {
+        Err(LemmaError::Semantic(details)) => {
+            assert!(details.message.contains("not a document collection"));
+        }
+        other => panic!("Expected semantic error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_aggregate_of_unknown_rule_is_rejected() {
+    let mut engine = Engine::new();
+    let result = engine.add_lemma_code(
+        r#"
+doc item
+fact price = 10
+
+doc basket
+fact items = many doc item
+rule total = sum of items.price_total?
+"#,
+        "test.lemma",
+    );
+
+    match result {
+        Err(LemmaError::Semantic(details)) => {
+            assert!(details.message.contains("has no rule 'price_total'"));
+        }
+        other => panic!("Expected semantic error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_aggregate_display() {
+    let engine = engine();
+    let doc = engine.get_document("order").unwrap();
+    let rule = doc.rules.iter().find(|r| r.name == "order_total").unwrap();
+    assert_eq!(rule.expression.to_string(), "sum of line_items.total?");
+
+    let fact = doc.facts.first().unwrap();
+    assert_eq!(fact.value.to_string(), "many doc line_item");
+}
@@ -0,0 +1,104 @@
+//! JSON export of the document AST
+//!
+//! Key behaviors:
+//! 1. Facts are exported with their path, value and span
+//! 2. Rules are exported with their expression tree, unless clauses and mode
+//! 3. Every expression carries its ID and span, pointing into the source
+//! 4. Literals are typed values
+//! 5. Unknown documents are an error
+
+use lemma::Engine;
+use serde_json::json;
+
+const CODE: &str = r#"doc pricing
+fact price = [money]
+fact customer.vip = false
+rule total = price * 2
+  unless customer.vip then veto "No VIP pricing"
+rule band first match = "low"
+  unless total? > 100 EUR then "high"
+"#;
+
+fn export() -> serde_json::Value {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine.export_ast("pricing").unwrap()
+}
+
+#[test]
+fn test_facts_are_exported() {
+    let ast = export();
+    assert_eq!(ast["format"], "lemma-ast");
+    assert_eq!(ast["version"], 1);
+
+    let facts = &ast["document"]["facts"];
+    assert_eq!(facts[0]["value"], json!({"kind": "type", "type": "money"}));
+    assert_eq!(facts[0]["path"], json!(["price"]));
+    assert_eq!(facts[1]["path"], json!(["customer", "vip"]));
+    assert_eq!(facts[1]["local"], false);
+    assert_eq!(
+        facts[1]["value"],
+        json!({"kind": "literal", "value": {"kind": "boolean", "value": false}})
+    );
+    assert_eq!(facts[1]["span"]["line"], 3);
+}
+
+#[test]
+fn test_rules_are_exported_as_trees() {
+    let ast = export();
+    let total = &ast["document"]["rules"][0];
+    assert_eq!(total["name"], "total");
+    assert_eq!(total["unless_mode"], "last_match");
+
+    let expression = &total["expression"];
+    assert_eq!(expression["kind"], "arithmetic");
+    assert_eq!(expression["operator"], "*");
+    assert_eq!(expression["left"]["kind"], "fact_reference");
+    assert_eq!(expression["left"]["reference"], json!(["price"]));
+    assert_eq!(
+        expression["right"]["value"],
+        json!({"kind": "number", "value": "2"})
+    );
+
+    let clause = &total["unless_clauses"][0];
+    assert_eq!(clause["condition"]["reference"], json!(["customer", "vip"]));
+    assert_eq!(clause["result"]["kind"], "veto");
+    assert_eq!(clause["result"]["message"], "No VIP pricing");
+
+    let band = &ast["document"]["rules"][1];
+    assert_eq!(band["unless_mode"], "first_match");
+    let condition = &band["unless_clauses"][0]["condition"];
+    assert_eq!(condition["kind"], "comparison");
+    assert_eq!(condition["left"]["kind"], "rule_reference");
+}
+
+#[test]
+fn test_expressions_point_into_the_source() {
+    let ast = export();
+    let expression = &ast["document"]["rules"][0]["expression"];
+    let span = &expression["span"];
+    let start = span["start"].as_u64().unwrap() as usize;
+    let end = span["end"].as_u64().unwrap() as usize;
+    assert_eq!(CODE[start..end].trim(), "price * 2");
+    assert_eq!(span["line"], 4);
+
+    let ids = [
+        expression["id"].as_str().unwrap(),
+        expression["left"]["id"].as_str().unwrap(),
+        expression["right"]["id"].as_str().unwrap(),
+    ];
+    assert!(ids.iter().all(|id| id.starts_with("expr_")), "{:?}", ids);
+    assert_ne!(ids[0], ids[1]);
+    assert_ne!(ids[1], ids[2]);
+}
+
+#[test]
+fn test_unknown_document() {
+    let engine = Engine::new();
+    let error = engine.export_ast("missing").unwrap_err();
+    assert!(
+        error.to_string().contains("'missing' not found"),
+        "{}",
+        error
+    );
+}
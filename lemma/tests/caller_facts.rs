@@ -0,0 +1,155 @@
+//! Reading facts of the referencing document with `caller.`
+//!
+//! Key behaviors:
+//! 1. `caller.x` in a referenced document is `x` of the document holding the reference
+//! 2. Each referencing document supplies its own value, including overrides
+//! 3. Evaluated on its own, the document reports `caller.x` as a missing fact
+//! 4. A referencing document without the fact is rejected when loading
+//! 5. Items of a document collection and copies of a template read their caller too
+//! 6. `caller` is reserved: no fact or template parameter can be named so
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LiteralValue};
+use rust_decimal::Decimal;
+
+const LIBRARY: &str = r#"
+doc volume_discount
+rule discount = 0
+  unless caller.quantity >= 100 then 50
+"#;
+
+const ORDERS: &str = r#"
+doc small_order
+fact quantity = 10
+fact pricing = doc volume_discount
+rule total = quantity * 5 - pricing.discount?
+
+doc bulk_order
+fact quantity = [number]
+fact pricing = doc volume_discount
+rule total = quantity * 5 - pricing.discount?
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(LIBRARY, "library.lemma").unwrap();
+    engine.add_lemma_code(ORDERS, "orders.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_caller_reads_the_referencing_document() {
+    let engine = engine();
+    let response = engine.evaluate("small_order", None, None).unwrap();
+    assert_eq!(
+        rule_result(&response, "total").result,
+        Some(LiteralValue::Number(Decimal::from(50)))
+    );
+
+    let facts = engine.parse_facts("bulk_order", &["quantity=200"]).unwrap();
+    let response = engine.evaluate("bulk_order", None, Some(facts)).unwrap();
+    assert_eq!(
+        rule_result(&response, "total").result,
+        Some(LiteralValue::Number(Decimal::from(950)))
+    );
+}
+
+#[test]
+fn test_without_caller_the_fact_is_missing() {
+    let response = engine().evaluate("volume_discount", None, None).unwrap();
+    let discount = rule_result(&response, "discount");
+    assert_eq!(discount.result, None);
+    assert_eq!(
+        discount.missing_facts,
+        Some(vec!["caller.quantity".to_string()])
+    );
+}
+
+#[test]
+fn test_referencing_document_must_declare_the_fact() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(LIBRARY, "library.lemma").unwrap();
+    let error = engine
+        .add_lemma_code(
+            "doc order\nfact pricing = doc volume_discount\nrule discount = pricing.discount?",
+            "order.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("caller.quantity"), "{}", error);
+    assert!(error.to_string().contains("'order'"), "{}", error);
+}
+
+const COLLECTION: &str = r#"
+doc seat
+fact base_price = 100
+rule price = base_price * caller.multiplier
+
+doc flight
+fact multiplier = 2
+fact seats = many doc seat
+fact seats[0].base_price = 100
+fact seats[1].base_price = 50
+rule revenue = sum of seats.price?
+"#;
+
+const TEMPLATE: &str = r#"
+doc surcharge(factor)
+rule amount = caller.base * factor
+
+doc shipment
+fact base = 200
+fact fee = doc surcharge(3)
+rule total = base + fee.amount?
+"#;
+
+#[test]
+fn test_collection_items_read_the_referencing_document() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(COLLECTION, "flight.lemma").unwrap();
+    let response = engine.evaluate("flight", None, None).unwrap();
+    assert_eq!(
+        rule_result(&response, "revenue").result,
+        Some(LiteralValue::Number(Decimal::from(300)))
+    );
+
+    let error = Engine::new()
+        .add_lemma_code(
+            &COLLECTION.replace("fact multiplier = 2\n", ""),
+            "flight.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("caller.multiplier"), "{}", error);
+    assert!(error.to_string().contains("'flight'"), "{}", error);
+}
+
+#[test]
+fn test_template_copies_read_the_referencing_document() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(TEMPLATE, "shipment.lemma").unwrap();
+    let response = engine.evaluate("shipment", None, None).unwrap();
+    assert_eq!(
+        rule_result(&response, "total").result,
+        Some(LiteralValue::Number(Decimal::from(800)))
+    );
+
+    let error = Engine::new()
+        .add_lemma_code(&TEMPLATE.replace("fact base = 200\n", ""), "shipment.lemma")
+        .unwrap_err();
+    assert!(error.to_string().contains("caller.base"), "{}", error);
+    assert!(error.to_string().contains("'shipment'"), "{}", error);
+}
+
+#[test]
+fn test_caller_is_reserved() {
+    for code in ["doc a\nfact caller = 1", "doc a(caller)\nrule b = caller"] {
+        let error = Engine::new().add_lemma_code(code, "a.lemma").unwrap_err();
+        assert!(error.to_string().contains("reserved"), "{}", error);
+    }
+    // Longer names starting with it are fine
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code("doc a\nfact callers = 1\nrule b = callers", "a.lemma")
+        .unwrap();
+}
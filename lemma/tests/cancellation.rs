@@ -0,0 +1,126 @@
+//! Cancelling evaluations and attributing timeouts to rules
+//!
+//! Key behaviors:
+//! 1. A cancelled token stops evaluation with `LemmaError::Cancelled`
+//! 2. Cancelling from another thread stops a running evaluation
+//! 3. An uncancelled token evaluates as usual
+//! 4. Timeouts name the rule that was being evaluated
+
+use lemma::{
+    CancellationToken, Engine, EvaluationOptions, LemmaError, LiteralValue, ResourceLimits,
+};
+use rust_decimal::Decimal;
+use std::thread;
+use std::time::Duration;
+
+const CODE: &str = r#"
+doc pricing
+fact price = 10
+rule quick = price * 2
+rule slow = pause(quick?)
+"#;
+
+/// An engine whose `pause` function sleeps `pause_ms` before returning its argument
+fn engine(limits: ResourceLimits, pause_ms: u64) -> Engine {
+    let mut engine = Engine::with_limits(limits);
+    engine
+        .register_function("pause", 1, move |args| {
+            thread::sleep(Duration::from_millis(pause_ms));
+            Ok(args[0].clone())
+        })
+        .unwrap();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_cancelled_token_stops_evaluation() {
+    let engine = engine(ResourceLimits::default(), 0);
+    let token = CancellationToken::new();
+    token.cancel();
+
+    let error = engine
+        .evaluate_cancellable("pricing", None, None, &EvaluationOptions::default(), &token)
+        .unwrap_err();
+    assert!(
+        matches!(error, LemmaError::Cancelled { rule: Some(_) }),
+        "{:?}",
+        error
+    );
+    assert!(
+        error.to_string().starts_with("Evaluation cancelled"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_cancel_from_another_thread() {
+    let mut engine = engine(ResourceLimits::default(), 20);
+    let code = (0..50)
+        .map(|i| format!("rule step_{} = pause(price)\n", i))
+        .collect::<String>();
+    engine
+        .add_lemma_code(
+            &format!("doc steps\nfact price = 1\n{}", code),
+            "steps.lemma",
+        )
+        .unwrap();
+
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    let handle = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        canceller.cancel();
+    });
+
+    let error = engine
+        .evaluate_cancellable("steps", None, None, &EvaluationOptions::default(), &token)
+        .unwrap_err();
+    handle.join().unwrap();
+    assert!(matches!(error, LemmaError::Cancelled { .. }), "{:?}", error);
+}
+
+#[test]
+fn test_uncancelled_token_evaluates() {
+    let engine = engine(ResourceLimits::default(), 0);
+    let response = engine
+        .evaluate_cancellable(
+            "pricing",
+            Some(vec!["slow".to_string()]),
+            None,
+            &EvaluationOptions::default(),
+            &CancellationToken::new(),
+        )
+        .unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(20)))
+    );
+}
+
+#[test]
+fn test_timeout_names_the_rule() {
+    let limits = ResourceLimits {
+        max_evaluation_time_ms: 20,
+        ..ResourceLimits::default()
+    };
+    // `+ 1` is checked after the pause, past the limit
+    let mut engine = engine(limits, 50);
+    engine
+        .add_lemma_code(
+            "doc slow_total\nfact price = 10\nrule total = pause(price) + 1\n",
+            "slow_total.lemma",
+        )
+        .unwrap();
+
+    let response = engine.evaluate("slow_total", None, None).unwrap();
+    let limit = response.limit_exceeded.expect("the timeout is reported");
+    assert_eq!(limit.limit_name, "max_evaluation_time_ms");
+    assert_eq!(limit.rule.as_deref(), Some("total"));
+    assert!(
+        limit.message.starts_with("Rule 'total' exceeded 20ms"),
+        "{}",
+        limit.message
+    );
+}
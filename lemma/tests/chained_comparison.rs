@@ -0,0 +1,100 @@
+//! Chained comparisons: `0 <= quantity <= 100`
+//!
+//! Key behaviors:
+//! 1. `low op value op high` is a range, printed with the lower bound first
+//! 2. Strict and inclusive bounds can be mixed, and the chain can descend
+//! 3. The trace records a single operation naming the bounds
+//! 4. Operators pointing different ways are rejected
+
+use lemma::{Engine, LiteralValue, OperationRecord, RuleResult};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc order
+fact quantity = [number]
+rule valid = 0 <= quantity <= 100
+rule small = 0 < quantity < 10
+rule bulk = 1000 > quantity >= 100
+"#;
+
+fn evaluate(quantity: i64, rule: &str) -> RuleResult {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    let quantity = format!("quantity={}", quantity);
+    let facts = engine.parse_facts("order", &[&quantity]).unwrap();
+    let response = engine
+        .evaluate("order", Some(vec![rule.to_string()]), Some(facts))
+        .unwrap();
+    response.results.into_iter().next().unwrap()
+}
+
+fn holds(quantity: i64, rule: &str) -> bool {
+    evaluate(quantity, rule).result == Some(LiteralValue::Boolean(true))
+}
+
+#[test]
+fn test_inclusive_chain() {
+    assert!(holds(0, "valid"));
+    assert!(holds(100, "valid"));
+    assert!(!holds(-1, "valid"));
+    assert!(!holds(101, "valid"));
+}
+
+#[test]
+fn test_strict_and_descending_chains() {
+    assert!(!holds(0, "small"));
+    assert!(holds(9, "small"));
+    assert!(!holds(10, "small"));
+
+    assert!(holds(100, "bulk"));
+    assert!(holds(999, "bulk"));
+    assert!(!holds(1000, "bulk"));
+}
+
+#[test]
+fn test_chain_is_a_range() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    let printed = engine.get_document("order").unwrap().to_string();
+    assert!(printed.contains("rule valid = quantity between 0 and 100"));
+    assert!(printed.contains("rule small = 0 < quantity < 10"));
+    assert!(printed.contains("rule bulk = 100 <= quantity < 1000"));
+}
+
+#[test]
+fn test_chain_is_one_trace_operation() {
+    let executed = |rule: &str| -> Vec<(String, Vec<LiteralValue>)> {
+        evaluate(5, rule)
+            .operations
+            .into_iter()
+            .filter_map(|op| match op {
+                OperationRecord::OperationExecuted {
+                    operation, inputs, ..
+                } => Some((operation, inputs)),
+                _ => None,
+            })
+            .collect()
+    };
+    let number = |n: i64| LiteralValue::Number(Decimal::from(n));
+    assert_eq!(
+        executed("valid"),
+        vec![(
+            "between".to_string(),
+            vec![number(5), number(0), number(100)]
+        )]
+    );
+    assert_eq!(executed("small")[0].0, "strictly between");
+    assert_eq!(executed("bulk")[0].0, "between, excluding high");
+}
+
+#[test]
+fn test_mixed_directions_are_rejected() {
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            "doc order\nfact quantity = [number]\nrule odd = 0 < quantity > 10",
+            "order.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("must compare in one direction"));
+}
@@ -0,0 +1,103 @@
+//! `check` as shorthand for boolean rules that veto
+//!
+//! Key behaviors:
+//! 1. A check is true when its condition holds and vetoed with its message otherwise
+//! 2. It is the rule `unless condition then true else veto ...`, also when printed
+//! 3. Without `else veto` the veto has no message
+//! 4. Checks are referenced like rules, also from checklists and other documents
+//! 5. `check` stays usable as a fact or rule name
+
+mod common;
+
+use common::result;
+use lemma::{Engine, LiteralValue};
+
+const CODE: &str = r#"
+doc applicant
+fact age = [number]
+fact country = "NL"
+check adult = age >= 18 else veto "must be 18+"
+check resident = country is "NL"
+checklist eligible = adult?, resident?
+
+doc application
+fact applicant = doc applicant
+rule accepted = applicant.adult?
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_check_passes_or_vetoes() {
+    let engine = engine();
+    let passed = result(&engine, "applicant", &["age=30"], "adult");
+    assert_eq!(passed.result, Some(LiteralValue::Boolean(true)));
+
+    let failed = result(&engine, "applicant", &["age=16"], "adult");
+    assert_eq!(failed.result, None);
+    assert_eq!(failed.veto_message, Some("must be 18+".to_string()));
+}
+
+#[test]
+fn test_check_without_message() {
+    let engine = engine();
+    let failed = result(
+        &engine,
+        "applicant",
+        &["age=30", "country=\"BE\""],
+        "resident",
+    );
+    assert_eq!(failed.result, None);
+    assert_eq!(failed.veto_message, None);
+}
+
+#[test]
+fn test_check_is_referenced_like_a_rule() {
+    let engine = engine();
+    assert_eq!(
+        result(&engine, "applicant", &["age=30"], "eligible.complete").result,
+        Some(LiteralValue::Boolean(true))
+    );
+    assert_eq!(
+        result(&engine, "application", &["applicant.age=30"], "accepted").result,
+        Some(LiteralValue::Boolean(true))
+    );
+    let vetoed = result(&engine, "application", &["applicant.age=12"], "accepted");
+    assert_eq!(vetoed.veto_message, Some("must be 18+".to_string()));
+}
+
+#[test]
+fn test_check_prints_as_rule() {
+    let engine = engine();
+    let printed = engine.get_document("applicant").unwrap().to_string();
+    assert!(
+        printed.contains("rule adult = unless age >= 18 then true else veto \"must be 18+\"\n"),
+        "{}",
+        printed
+    );
+
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+    let doc = reparsed.get_document("applicant").unwrap();
+    assert!(doc.rules.iter().any(|rule| rule.name == "adult"));
+}
+
+#[test]
+fn test_check_as_a_name() {
+    let code = r#"
+doc test
+fact check = 5
+check positive = check > 0 else veto "not positive"
+"#;
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+    let response = engine.evaluate("test", None, None).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Boolean(true))
+    );
+}
@@ -0,0 +1,136 @@
+//! Checklist tests
+//!
+//! Key behaviors:
+//! 1. `checklist name = a, b?, c` derives `name.complete` and `name.missing` rules
+//! 2. `missing` lists the items that are false, in checklist order
+//! 3. Derived rules can be referenced locally and through document references
+//! 4. A vetoed item vetoes both derived rules
+//! 5. Checklists survive a Display roundtrip
+
+mod common;
+
+use common::literal;
+use lemma::{parse_facts, Engine, LiteralValue};
+
+const CODE: &str = r#"
+doc onboarding
+fact has_id = true
+fact has_contract = false
+fact account_number = [text]
+rule has_bank_account = have account_number
+checklist steps = has_id, has_contract, has_bank_account?
+rule status = "pending"
+  unless steps.complete? then "done"
+
+doc employee
+fact onboarding = doc onboarding
+rule ready = onboarding.steps.complete?
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_checklist_lists_missing_items() {
+    let engine = engine();
+    assert_eq!(
+        literal(&engine, "onboarding", &[], "steps.complete"),
+        LiteralValue::Boolean(false)
+    );
+    assert_eq!(
+        literal(&engine, "onboarding", &[], "steps.missing"),
+        LiteralValue::Text("has_contract, has_bank_account".to_string())
+    );
+    assert_eq!(
+        literal(&engine, "onboarding", &[], "status"),
+        LiteralValue::Text("pending".to_string())
+    );
+}
+
+#[test]
+fn test_checklist_complete() {
+    let engine = engine();
+    let overrides = ["has_contract=true", "account_number=\"NL01\""];
+    assert_eq!(
+        literal(&engine, "onboarding", &overrides, "steps.complete"),
+        LiteralValue::Boolean(true)
+    );
+    assert_eq!(
+        literal(&engine, "onboarding", &overrides, "steps.missing"),
+        LiteralValue::Text(String::new())
+    );
+    assert_eq!(
+        literal(&engine, "onboarding", &overrides, "status"),
+        LiteralValue::Text("done".to_string())
+    );
+}
+
+#[test]
+fn test_checklist_rule_through_document_reference() {
+    let engine = engine();
+    assert_eq!(
+        literal(&engine, "employee", &[], "ready"),
+        LiteralValue::Boolean(false)
+    );
+    let overrides = [
+        "onboarding.has_contract=true",
+        "onboarding.account_number=\"NL01\"",
+    ];
+    assert_eq!(
+        literal(&engine, "employee", &overrides, "ready"),
+        LiteralValue::Boolean(true)
+    );
+}
+
+#[test]
+fn test_vetoed_item_vetoes_checklist() {
+    let code = r#"
+doc test
+fact age = 10
+rule adult = age >= 18
+  unless age < 0 then veto "Invalid age"
+checklist eligibility = adult?
+"#;
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+
+    let facts = Some(parse_facts(&["age=-1"]).unwrap());
+    let response = engine.evaluate("test", None, facts).unwrap();
+    for name in ["eligibility.complete", "eligibility.missing"] {
+        let result = response
+            .results
+            .iter()
+            .find(|r| r.rule_name == name)
+            .unwrap();
+        assert_eq!(result.veto_message, Some("Invalid age".to_string()));
+    }
+}
+
+#[test]
+fn test_checklist_display_roundtrip() {
+    let engine = engine();
+    let doc = engine.get_document("onboarding").unwrap();
+    let printed = doc.to_string();
+    assert!(printed.contains("checklist steps = has_id, has_contract, has_bank_account?\n"));
+    assert!(!printed.contains("rule steps."));
+
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+    let reparsed_doc = reparsed.get_document("onboarding").unwrap();
+    assert_eq!(reparsed_doc.rules.len(), doc.rules.len());
+}
+
+#[test]
+fn test_duplicate_checklist_is_rejected() {
+    let code = r#"
+doc test
+fact a = true
+checklist steps = a
+checklist steps = a
+"#;
+    let mut engine = Engine::new();
+    assert!(engine.add_lemma_code(code, "test.lemma").is_err());
+}
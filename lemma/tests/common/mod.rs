@@ -0,0 +1,35 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test file is its own crate and uses only some of these.
+#![allow(dead_code)]
+
+use lemma::{parse_facts, Engine, LiteralValue, Response, RuleResult};
+
+/// The result of `rule` in `response`
+pub fn rule_result<'a>(response: &'a Response, rule: &str) -> &'a RuleResult {
+    response
+        .results
+        .iter()
+        .find(|r| r.rule_name == rule)
+        .unwrap_or_else(|| panic!("no result for rule '{}'", rule))
+}
+
+/// The result of `rule` after evaluating `doc` with `overrides`
+pub fn result(engine: &Engine, doc: &str, overrides: &[&str], rule: &str) -> RuleResult {
+    let facts = parse_facts(overrides).unwrap();
+    let response = engine.evaluate(doc, None, Some(facts)).unwrap();
+    rule_result(&response, rule).clone()
+}
+
+/// The value of `rule`, failing the test when it is vetoed
+pub fn literal(engine: &Engine, doc: &str, overrides: &[&str], rule: &str) -> LiteralValue {
+    let result = result(engine, doc, overrides, rule);
+    result
+        .result
+        .unwrap_or_else(|| panic!("{} was vetoed: {:?}", rule, result.veto_message))
+}
+
+/// The value of `rule` as Lemma prints it, e.g. `105 EUR`
+pub fn value(engine: &Engine, doc: &str, overrides: &[&str], rule: &str) -> String {
+    literal(engine, doc, overrides, rule).to_string()
+}
@@ -0,0 +1,137 @@
+//! Document version and compatibility tests
+//!
+//! Key behaviors:
+//! 1. `version 2.1.0` is parsed, written back by Display and must agree between partial documents
+//! 2. Removing or renaming a fact or public rule, or changing a fact's type, needs a major bump
+//! 3. Adding a fact or rule needs a minor bump, changing a rule's expression a patch
+//! 4. The report's problem names the version the document should declare
+
+use lemma::{Bump, CompatibilityReport, Engine, LemmaDoc, SemanticVersion};
+
+const OLD: &str = r#"
+doc pricing
+version 2.1.0
+fact price = 10
+fact quantity = 2
+rule total = price * quantity
+internal rule subtotal = price
+"#;
+
+fn doc(code: &str) -> LemmaDoc {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "pricing.lemma").unwrap();
+    engine.get_document("pricing").unwrap().clone()
+}
+
+fn report(new: &str) -> CompatibilityReport {
+    Engine::check_compatibility(&doc(OLD), &doc(new))
+}
+
+#[test]
+fn test_version_declaration_roundtrip() {
+    let doc = doc(OLD);
+    assert_eq!(doc.declared_version, Some(SemanticVersion::new(2, 1, 0)));
+    let code = doc.to_string();
+    assert!(code.contains("version 2.1.0\n"));
+
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&code, "roundtrip.lemma").unwrap();
+    assert_eq!(
+        reparsed.get_document("pricing").unwrap().declared_version,
+        doc.declared_version
+    );
+}
+
+#[test]
+fn test_invalid_version_is_rejected() {
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            "doc pricing\nversion 1.99999999999999999999.0\nfact price = 10",
+            "pricing.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("Invalid version"));
+}
+
+#[test]
+fn test_partial_documents_must_agree_on_version() {
+    let mut engine = Engine::new();
+    let result = engine.add_lemma_code(
+        "partial doc pricing\nversion 1.0.0\nfact price = 10\n\npartial doc pricing\nversion 2.0.0\nrule total = price",
+        "pricing.lemma",
+    );
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("use different directives"));
+}
+
+#[test]
+fn test_removed_rule_needs_major_bump() {
+    let report = report(
+        "doc pricing\nversion 2.2.0\nfact price = 10\nfact quantity = 2\ninternal rule subtotal = price",
+    );
+    assert_eq!(report.required, Some(Bump::Major));
+    let problem = report.problem().unwrap();
+    assert!(problem.contains("rule 'total' was removed or renamed"));
+    assert!(problem.contains("declare version 3.0.0"));
+}
+
+#[test]
+fn test_renamed_fact_needs_major_bump() {
+    let report = report(
+        "doc pricing\nversion 3.0.0\nfact price = 10\nfact amount = 2\nrule total = price * amount\ninternal rule subtotal = price",
+    );
+    assert_eq!(report.required, Some(Bump::Major));
+    assert!(report.is_compatible());
+    assert!(report
+        .changes
+        .iter()
+        .any(|c| c.name == "quantity" && c.bump == Bump::Major));
+}
+
+#[test]
+fn test_fact_type_change_needs_major_bump() {
+    let report = report(
+        "doc pricing\nversion 2.1.1\nfact price = 10 EUR\nfact quantity = 2\nrule total = price * quantity\ninternal rule subtotal = price",
+    );
+    assert_eq!(report.required, Some(Bump::Major));
+    assert!(report.changes[0].reason.contains("from number to money"));
+    assert!(!report.is_compatible());
+}
+
+#[test]
+fn test_added_fact_needs_minor_bump() {
+    let new = "doc pricing\nversion 2.2.0\nfact price = 10\nfact quantity = 2\nfact discount = 1\nrule total = price * quantity - discount\ninternal rule subtotal = price";
+    let minor = report(new);
+    assert_eq!(minor.required, Some(Bump::Minor));
+    assert!(minor.is_compatible());
+
+    let patch = report(&new.replace("2.2.0", "2.1.1"));
+    assert!(patch.problem().unwrap().contains("declare version 2.2.0"));
+}
+
+#[test]
+fn test_changed_expression_and_internal_rules_need_patch_bump() {
+    let report = report(
+        "doc pricing\nversion 2.1.1\nfact price = 10\nfact quantity = 2\nrule total = quantity * price\ninternal rule net = price",
+    );
+    assert_eq!(report.required, Some(Bump::Patch));
+    assert!(report.is_compatible());
+}
+
+#[test]
+fn test_unversioned_documents_are_not_checked() {
+    let old = doc("doc pricing\nfact price = 10\nrule total = price");
+    let new = doc("doc pricing\nfact price = 10");
+    let report = Engine::check_compatibility(&old, &new);
+    assert_eq!(report.required, Some(Bump::Major));
+    assert!(report.is_compatible());
+
+    let report = Engine::check_compatibility(&doc(OLD), &new);
+    assert!(report
+        .problem()
+        .unwrap()
+        .contains("no longer declares a version"));
+}
@@ -0,0 +1,179 @@
+//! Functions registered by the host application
+//!
+//! Key behaviors:
+//! 1. Registered functions are callable with any number of arguments
+//! 2. Calls to unknown functions or with the wrong arity fail when loading
+//! 3. Typed functions reject arguments of another type when called
+//! 4. Errors from the function fail the rule, vetoed arguments veto the call
+//! 5. Function names must be valid Lemma names and not keywords
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LemmaType, LiteralValue, OperationRecord};
+use rust_decimal::Decimal;
+
+fn creditgrade(args: &[LiteralValue]) -> Result<LiteralValue, String> {
+    match &args[0] {
+        LiteralValue::Number(score) if *score >= Decimal::from(700) => {
+            Ok(LiteralValue::Text("A".to_string()))
+        }
+        LiteralValue::Number(score) if *score >= Decimal::ZERO => {
+            Ok(LiteralValue::Text("B".to_string()))
+        }
+        LiteralValue::Number(_) => Err("score can't be negative".to_string()),
+        other => Err(format!("expected a number, got {}", other)),
+    }
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .register_typed_function("creditgrade", vec![LemmaType::Number], creditgrade)
+        .unwrap();
+    engine
+        .register_function("larger", 2, |args| match (&args[0], &args[1]) {
+            (LiteralValue::Number(a), LiteralValue::Number(b)) => {
+                Ok(LiteralValue::Number(*a.max(b)))
+            }
+            _ => Err("expected numbers".to_string()),
+        })
+        .unwrap();
+    engine
+}
+
+const CODE: &str = r#"
+doc loan
+fact score = [number]
+rule grade = creditgrade(score)
+rule best = larger(score, 650) + 1
+rule is_prime = creditgrade(score + 100) is "A"
+rule valid_score = score
+  unless score > 900 then veto "score out of range"
+rule checked_grade = creditgrade(valid_score?)
+"#;
+
+fn evaluate(score: &str) -> lemma::Response {
+    let mut engine = engine();
+    engine.add_lemma_code(CODE, "loan.lemma").unwrap();
+    let facts = engine.parse_facts("loan", &[score]).unwrap();
+    engine.evaluate("loan", None, Some(facts)).unwrap()
+}
+
+fn result(response: &lemma::Response, rule: &str) -> Option<LiteralValue> {
+    rule_result(response, rule).result.clone()
+}
+
+fn text(value: &str) -> Option<LiteralValue> {
+    Some(LiteralValue::Text(value.to_string()))
+}
+
+fn number(value: i64) -> Option<LiteralValue> {
+    Some(LiteralValue::Number(Decimal::from(value)))
+}
+
+#[test]
+fn test_calls_registered_functions() {
+    let response = evaluate("score=720");
+    assert_eq!(result(&response, "grade"), text("A"));
+    assert_eq!(result(&response, "best"), number(721));
+
+    let response = evaluate("score=610");
+    assert_eq!(result(&response, "grade"), text("B"));
+    assert_eq!(result(&response, "best"), number(651));
+    assert_eq!(
+        result(&response, "is_prime"),
+        Some(LiteralValue::Boolean(true))
+    );
+}
+
+#[test]
+fn test_call_is_traced() {
+    let response = evaluate("score=720");
+    let grade = rule_result(&response, "grade");
+    assert!(grade.operations.iter().any(|op| matches!(
+        op,
+        OperationRecord::OperationExecuted { operation, .. } if operation == "creditgrade"
+    )));
+}
+
+#[test]
+fn test_unknown_function_rejected_on_load() {
+    let mut engine = engine();
+    let error = engine
+        .add_lemma_code("doc d\nrule r = riskscore(1)\n", "d.lemma")
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Unknown function 'riskscore'"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_wrong_arity_rejected_on_load() {
+    let mut engine = engine();
+    let error = engine
+        .add_lemma_code("doc d\nrule r = creditgrade(1, 2)\n", "d.lemma")
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("takes 1 argument, got 2"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_argument_types_checked() {
+    let mut engine = engine();
+    engine
+        .add_lemma_code(
+            "doc d\nfact label = \"x\"\nrule r = creditgrade(label)\n",
+            "d.lemma",
+        )
+        .unwrap();
+    let error = engine.evaluate("d", None, None).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("expects number as argument 1, got text"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_function_errors_and_vetoes() {
+    let mut engine = engine();
+    engine.add_lemma_code(CODE, "loan.lemma").unwrap();
+    let facts = engine.parse_facts("loan", &["score=-5"]).unwrap();
+    let error = engine
+        .evaluate("loan", Some(vec!["grade".to_string()]), Some(facts))
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("score can't be negative"),
+        "{}",
+        error
+    );
+
+    let facts = engine.parse_facts("loan", &["score=950"]).unwrap();
+    let response = engine
+        .evaluate("loan", Some(vec!["checked_grade".to_string()]), Some(facts))
+        .unwrap();
+    let checked = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "checked_grade")
+        .unwrap();
+    assert_eq!(checked.veto_message.as_deref(), Some("score out of range"));
+}
+
+#[test]
+fn test_invalid_function_names() {
+    let mut engine = Engine::new();
+    let ok = |_: &[LiteralValue]| Ok(LiteralValue::Boolean(true));
+    assert!(engine.register_function("sqrt", 1, ok).is_err());
+    assert!(engine.register_function("credit grade", 1, ok).is_err());
+    assert!(engine.register_function("2fast", 1, ok).is_err());
+    assert!(engine.register_function("is_eligible", 0, ok).is_ok());
+}
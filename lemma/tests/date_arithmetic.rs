@@ -0,0 +1,117 @@
+//! Arithmetic and comparisons with dates and durations
+//!
+//! Key behaviors:
+//! 1. Months and years move the month and clamp the day to the end of the month
+//! 2. Negative durations move dates backwards, and durations can come first
+//! 3. Fractional months and years are rejected
+//! 4. `(end - start) in years` counts completed years, for ages and tenures
+//! 5. Durations compare across units; months and years compare with each other only
+
+use lemma::{Engine, LiteralValue};
+
+fn evaluate(code: &str, rule: &str) -> Result<LiteralValue, String> {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(code, "dates.lemma")
+        .map_err(|e| e.to_string())?;
+    let response = engine
+        .evaluate("dates", Some(vec![rule.to_string()]), None)
+        .map_err(|e| e.to_string())?;
+    Ok(response.results[0].result.clone().unwrap())
+}
+
+fn ymd(code: &str, rule: &str) -> (i32, u32, u32) {
+    match evaluate(code, rule).unwrap() {
+        LiteralValue::Date(date) => (date.year, date.month, date.day),
+        other => panic!("expected a date, got {}", other),
+    }
+}
+
+#[test]
+fn test_months_clamp_to_end_of_month() {
+    let code = r#"
+doc dates
+fact start = 2024-01-31
+rule plus_one = start + 1 month
+rule plus_two = start + 2 months
+rule plus_one_twice = start + 1 month + 1 month
+rule leap_plus_year = 2024-02-29 + 1 year
+"#;
+    assert_eq!(ymd(code, "plus_one"), (2024, 2, 29));
+    assert_eq!(ymd(code, "plus_two"), (2024, 3, 31));
+    // Each step clamps, so two steps of a month can end before two months
+    assert_eq!(ymd(code, "plus_one_twice"), (2024, 3, 29));
+    assert_eq!(ymd(code, "leap_plus_year"), (2025, 2, 28));
+}
+
+#[test]
+fn test_negative_and_leading_durations() {
+    let code = r#"
+doc dates
+fact start = 2024-03-31
+fact shift = -1 month
+rule back = start + shift
+rule forward = start - shift
+rule leading = 10 days + start
+"#;
+    assert_eq!(ymd(code, "back"), (2024, 2, 29));
+    assert_eq!(ymd(code, "forward"), (2024, 4, 30));
+    assert_eq!(ymd(code, "leading"), (2024, 4, 10));
+}
+
+#[test]
+fn test_fractional_months_are_rejected() {
+    let error = evaluate(
+        "doc dates\nfact start = 2024-01-15\nrule later = start + 1.5 months",
+        "later",
+    )
+    .unwrap_err();
+    assert!(error.contains("must be whole numbers"), "{}", error);
+}
+
+#[test]
+fn test_whole_years_between_dates() {
+    let code = r#"
+doc dates
+fact birth_date = 2004-02-29
+fact day_before = 2022-02-27
+fact birthday = 2022-02-28
+fact hire_date = 2020-03-31
+rule age_day_before = (day_before - birth_date) in years
+rule age_on_birthday = (birthday - birth_date) in years
+rule tenure_months = (birthday - hire_date) in months
+rule negative_years = (birth_date - birthday) in years
+"#;
+    let number = |rule: &str| evaluate(code, rule).unwrap().to_string();
+    assert_eq!(number("age_day_before"), "17");
+    assert_eq!(number("age_on_birthday"), "18");
+    assert_eq!(number("tenure_months"), "23");
+    assert_eq!(number("negative_years"), "-18");
+}
+
+#[test]
+fn test_mixed_unit_duration_comparisons() {
+    let code = r#"
+doc dates
+fact start = 2024-01-01
+fact end = 2024-01-02T12:00:00Z
+rule longer_than_a_day = end - start > 1 day
+rule hours = 90 minutes > 1 hour
+rule year_in_months = 1 year is 12 months
+"#;
+    let boolean = |rule: &str| evaluate(code, rule).unwrap();
+    assert_eq!(boolean("longer_than_a_day"), LiteralValue::Boolean(true));
+    assert_eq!(boolean("hours"), LiteralValue::Boolean(true));
+    assert_eq!(boolean("year_in_months"), LiteralValue::Boolean(true));
+
+    // Rejected when the document loads, not when the rule is evaluated
+    let code = r#"
+doc dates
+rule year_in_days = 1 year > 300 days
+"#;
+    let error = Engine::new()
+        .add_lemma_code(code, "dates.lemma")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("calendar units"), "{}", error);
+}
@@ -0,0 +1,157 @@
+//! Dates in fact overrides that aren't ISO 8601
+//!
+//! Key behaviors:
+//! 1. `2025-12-31 14:30` and `2025/12/31` are read in every date format
+//! 2. Day-first and month-first dates need `DateFormat::DayMonthYear` or `MonthDayYear`
+//! 3. Dates that read differently in the two orders, and two-digit years, are errors
+//! 4. The engine's date format also applies to JSON date strings, and can be
+//!    overridden for one call
+
+use lemma::{
+    parse_facts, parse_facts_with_date_format, DateFormat, DateTimeValue, Engine, FactValue,
+    LemmaError, LiteralValue,
+};
+use std::collections::HashMap;
+
+fn date(input: &str, format: DateFormat) -> DateTimeValue {
+    let facts = parse_facts_with_date_format(&[input], format).unwrap();
+    match &facts[0].value {
+        FactValue::Literal(LiteralValue::Date(date)) => date.clone(),
+        other => panic!("expected a date, got {:?}", other),
+    }
+}
+
+fn error(input: &str, format: DateFormat) -> String {
+    match parse_facts_with_date_format(&[input], format) {
+        Err(LemmaError::FactParse(errors)) => {
+            assert_eq!(errors[0].input, input);
+            errors[0].message.clone()
+        }
+        other => panic!("expected a fact parse error, got {:?}", other),
+    }
+}
+
+fn ymd(date: &DateTimeValue) -> (i32, u32, u32) {
+    (date.year, date.month, date.day)
+}
+
+#[test]
+fn test_iso_with_space_before_time() {
+    let start = date("start=2025-12-31 14:30", DateFormat::Iso);
+    assert_eq!(ymd(&start), (2025, 12, 31));
+    assert_eq!((start.hour, start.minute, start.second), (14, 30, 0));
+
+    let slashes = date("start=2025/12/31", DateFormat::Iso);
+    assert_eq!(ymd(&slashes), (2025, 12, 31));
+}
+
+#[test]
+fn test_plain_iso_dates_are_unchanged() {
+    assert_eq!(
+        parse_facts(&["start=2025-12-31"]).unwrap()[0].value,
+        parse_facts_with_date_format(&["start=2025-12-31"], DateFormat::MonthDayYear).unwrap()[0]
+            .value
+    );
+}
+
+#[test]
+fn test_day_first_and_month_first() {
+    let day_first = date("start=31-12-2025", DateFormat::DayMonthYear);
+    assert_eq!(ymd(&day_first), (2025, 12, 31));
+    let dotted = date("order.start=5.6.2025 09:15", DateFormat::DayMonthYear);
+    assert_eq!(ymd(&dotted), (2025, 6, 5));
+    assert_eq!(dotted.hour, 9);
+
+    let month_first = date("start=12/31/2025", DateFormat::MonthDayYear);
+    assert_eq!(ymd(&month_first), (2025, 12, 31));
+}
+
+#[test]
+fn test_day_first_without_date_format_is_rejected() {
+    assert!(error("start=31-12-2025", DateFormat::Iso).contains("not in ISO format"));
+
+    let message = error("start=05/06/2025", DateFormat::Iso);
+    assert!(message.contains("ambiguous"));
+    assert!(message.contains("2025-06-05 as day-month-year"));
+    assert!(message.contains("2025-05-06 as month-day-year"));
+}
+
+#[test]
+fn test_wrong_order_is_explained() {
+    let message = error("start=12/31/2025", DateFormat::DayMonthYear);
+    assert!(message.contains("no day 12 in month 31"));
+    assert!(message.contains("looks month-day-year"));
+
+    assert!(error("start=31-02-2025", DateFormat::DayMonthYear).contains("no day 31 in month 2"));
+    assert!(error("start=31-12-25", DateFormat::DayMonthYear).contains("Two-digit year"));
+    assert!(error("start=2025-12-31 25:00", DateFormat::Iso).contains("hours must be 0-23"));
+}
+
+#[test]
+fn test_other_values_are_left_to_the_grammar() {
+    let facts = parse_facts_with_date_format(
+        &["price=1.5", "weight=100 kilograms", "name=\"31-12-2025\""],
+        DateFormat::DayMonthYear,
+    )
+    .unwrap();
+    assert_eq!(facts.len(), 3);
+    assert!(matches!(
+        facts[2].value,
+        FactValue::Literal(LiteralValue::Text(_))
+    ));
+}
+
+#[test]
+fn test_date_format_names() {
+    assert_eq!("dmy".parse::<DateFormat>(), Ok(DateFormat::DayMonthYear));
+    assert_eq!(
+        "month-day-year".parse::<DateFormat>(),
+        Ok(DateFormat::MonthDayYear)
+    );
+    assert!("yyyy".parse::<DateFormat>().is_err());
+    assert_eq!(DateFormat::default().to_string(), "iso");
+}
+
+#[test]
+fn test_engine_date_format_applies_to_overrides_and_json() {
+    let mut engine = Engine::new().with_date_format(DateFormat::MonthDayYear);
+    engine
+        .add_lemma_code(
+            "doc contract\nfact start = [date]\nrule late = start > 2025-12-01",
+            "test.lemma",
+        )
+        .unwrap();
+
+    let facts = engine
+        .parse_facts("contract", &["start=12/31/2025"])
+        .unwrap();
+    let response = engine.evaluate("contract", None, Some(facts)).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Boolean(true))
+    );
+
+    let mut json = HashMap::new();
+    json.insert("start".to_string(), serde_json::json!("12/31/2025 08:00"));
+    let facts = engine.parse_json_facts("contract", &json).unwrap();
+    let FactValue::Literal(LiteralValue::Date(start)) = &facts[0].value else {
+        panic!("expected a date");
+    };
+    assert_eq!((start.month, start.day, start.hour), (12, 31, 8));
+
+    let iso = engine
+        .parse_facts_with_date_format("contract", &["start=12/31/2025"], DateFormat::Iso)
+        .unwrap_err();
+    assert!(iso.to_string().contains("not in ISO format"));
+
+    // A JSON date format for one call overrides the engine's
+    json.insert("start".to_string(), serde_json::json!("31/12/2025"));
+    let facts = engine
+        .parse_json_facts_with_date_format("contract", &json, DateFormat::DayMonthYear)
+        .unwrap();
+    let FactValue::Literal(LiteralValue::Date(start)) = &facts[0].value else {
+        panic!("expected a date");
+    };
+    assert_eq!((start.month, start.day), (12, 31));
+    assert!(engine.parse_json_facts("contract", &json).is_err());
+}
@@ -0,0 +1,111 @@
+//! Year, month and ISO week date literals
+//!
+//! Key behaviors:
+//! 1. `2026-02` and `2026-W08` parse as a month and a week, and display as written
+//! 2. `is` holds for any date within the period, `is not` outside it
+//! 3. `<` and `>` hold before the period starts and after it ends
+//! 4. A bare year compared with a date, or given for a date fact, is that year
+//! 5. Months and weeks that don't exist are rejected
+
+use lemma::{parse_facts, DateGranularity, Engine, LiteralValue};
+
+const CODE: &str = r#"
+doc orders
+fact order_date = 2026-02-14T09:30:00Z
+rule in_february = order_date is 2026-02
+rule not_in_march = order_date is not 2026-03
+rule in_week_7 = order_date is 2026-W07
+rule before_march = order_date < 2026-03
+rule after_january = order_date > 2026-01
+rule not_after_february = order_date <= 2026-02
+rule after_february = order_date > 2026-02
+rule this_year = order_date is 2026
+rule last_year = order_date is 2025
+rule month_in_year = 2026-02 is 2026
+rule february = 2026-02
+rule week = 2026-W08
+rule next_month = 2026-02 + 1 month
+"#;
+
+fn evaluate(overrides: &[&str], rule: &str) -> LiteralValue {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "orders.lemma").unwrap();
+    let facts = (!overrides.is_empty()).then(|| parse_facts(overrides).unwrap());
+    let response = engine
+        .evaluate("orders", Some(vec![rule.to_string()]), facts)
+        .unwrap();
+    response.results[0].result.clone().unwrap()
+}
+
+fn holds(overrides: &[&str], rule: &str) -> bool {
+    evaluate(overrides, rule) == LiteralValue::Boolean(true)
+}
+
+#[test]
+fn test_periods_parse_and_display() {
+    match evaluate(&[], "february") {
+        LiteralValue::Date(date) => {
+            assert_eq!(date.granularity, DateGranularity::Month);
+            assert_eq!((date.year, date.month, date.day), (2026, 2, 1));
+            assert_eq!(date.to_string(), "2026-02");
+        }
+        other => panic!("expected a date, got {}", other),
+    }
+    match evaluate(&[], "week") {
+        LiteralValue::Date(date) => {
+            assert_eq!(date.granularity, DateGranularity::Week);
+            assert_eq!((date.year, date.month, date.day), (2026, 2, 16));
+            assert_eq!(date.to_string(), "2026-W08");
+        }
+        other => panic!("expected a date, got {}", other),
+    }
+}
+
+#[test]
+fn test_containment() {
+    assert!(holds(&[], "in_february"));
+    assert!(holds(&[], "not_in_march"));
+    assert!(holds(&[], "in_week_7"));
+    assert!(holds(&[], "month_in_year"));
+    assert!(!holds(&["order_date=2026-03-01"], "in_february"));
+    assert!(holds(&["order_date=2026-02-28T23:59:59Z"], "in_february"));
+}
+
+#[test]
+fn test_ordering_against_periods() {
+    assert!(holds(&[], "before_march"));
+    assert!(holds(&[], "after_january"));
+    assert!(holds(&[], "not_after_february"));
+    assert!(!holds(&[], "after_february"));
+    assert!(holds(&["order_date=2026-03-01"], "after_february"));
+}
+
+#[test]
+fn test_bare_years() {
+    assert!(holds(&[], "this_year"));
+    assert!(!holds(&[], "last_year"));
+    assert!(holds(&["order_date=2026"], "this_year"));
+}
+
+#[test]
+fn test_period_arithmetic_starts_at_first_day() {
+    match evaluate(&[], "next_month") {
+        LiteralValue::Date(date) => {
+            assert_eq!(date.granularity, DateGranularity::Exact);
+            assert_eq!((date.year, date.month, date.day), (2026, 3, 1));
+        }
+        other => panic!("expected a date, got {}", other),
+    }
+}
+
+#[test]
+fn test_invalid_periods_are_rejected() {
+    for code in [
+        "doc bad\nrule month = 2026-13",
+        "doc bad\nrule week = 2026-W54",
+    ] {
+        let mut engine = Engine::new();
+        let error = engine.add_lemma_code(code, "bad.lemma").unwrap_err();
+        assert!(error.to_string().contains("Invalid date"), "{}", error);
+    }
+}
@@ -0,0 +1,111 @@
+//! Non-fatal diagnostics in evaluation responses
+//!
+//! Key behaviors:
+//! 1. Evaluating `accept` or `reject` reports a deprecated alias
+//! 2. A plain number meeting a unit reports an implicit coercion
+//! 3. Combining different kinds of units reports a degraded unit
+//! 4. Overrides for facts the document doesn't have are reported as unused
+//! 5. Diagnostics don't change results and are listed in `warnings` once
+
+mod common;
+
+use common::rule_result;
+use lemma::{Diagnostic, DiagnosticKind, Engine, Response};
+
+const CODE: &str = r#"
+doc shipping
+fact weight = [mass]
+fact distance = 12 kilometers
+fact blocked = false
+rule allowed = accept
+  unless blocked then reject
+rule heavy = weight > 100
+rule padded = weight + 2
+rule ratio = weight / distance
+rule light = weight < 10 kilograms
+"#;
+
+fn evaluate(overrides: &[&str]) -> Response {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shipping.lemma").unwrap();
+    let facts = engine.parse_facts("shipping", overrides).unwrap();
+    engine.evaluate("shipping", None, Some(facts)).unwrap()
+}
+
+fn of_kind(response: &Response, kind: DiagnosticKind) -> Vec<&Diagnostic> {
+    response
+        .diagnostics
+        .iter()
+        .filter(|d| d.kind == kind)
+        .collect()
+}
+
+#[test]
+fn test_deprecated_boolean_alias() {
+    let response = evaluate(&["weight=5 kilograms"]);
+    let deprecated = of_kind(&response, DiagnosticKind::DeprecatedAlias);
+    assert_eq!(deprecated.len(), 1, "{:?}", deprecated);
+    assert_eq!(deprecated[0].rule.as_deref(), Some("allowed"));
+    assert!(deprecated[0].message.contains("'accept'"));
+    assert!(deprecated[0].message.contains("'true'"));
+
+    let response = evaluate(&["weight=5 kilograms", "blocked=true"]);
+    let deprecated = of_kind(&response, DiagnosticKind::DeprecatedAlias);
+    assert!(
+        deprecated.iter().any(|d| d.message.contains("'reject'")),
+        "{:?}",
+        deprecated
+    );
+}
+
+#[test]
+fn test_implicit_coercion() {
+    let response = evaluate(&["weight=5 kilograms"]);
+    let coerced = of_kind(&response, DiagnosticKind::ImplicitCoercion);
+    let rules: Vec<&str> = coerced.iter().filter_map(|d| d.rule.as_deref()).collect();
+    assert!(rules.contains(&"heavy"), "{:?}", coerced);
+    assert!(rules.contains(&"padded"), "{:?}", coerced);
+    assert!(!rules.contains(&"light"), "{:?}", coerced);
+}
+
+#[test]
+fn test_unit_degraded_to_number() {
+    let response = evaluate(&["weight=6 kilograms"]);
+    let degraded = of_kind(&response, DiagnosticKind::UnitDegraded);
+    assert_eq!(degraded.len(), 1, "{:?}", degraded);
+    assert_eq!(degraded[0].rule.as_deref(), Some("ratio"));
+
+    let ratio = rule_result(&response, "ratio");
+    assert_eq!(ratio.result.as_ref().unwrap().to_string(), "0.5");
+}
+
+#[test]
+fn test_unused_override() {
+    let response = evaluate(&["weight=5 kilograms", "wieght=5 kilograms"]);
+    let unused = of_kind(&response, DiagnosticKind::UnusedOverride);
+    assert_eq!(unused.len(), 1, "{:?}", unused);
+    assert_eq!(unused[0].fact.as_deref(), Some("wieght"));
+    assert_eq!(unused[0].rule, None);
+}
+
+#[test]
+fn test_diagnostics_are_listed_as_warnings() {
+    let response = evaluate(&["weight=5 kilograms"]);
+    assert!(!response.diagnostics.is_empty());
+    for diagnostic in &response.diagnostics {
+        let text = diagnostic.to_string();
+        assert_eq!(
+            response.warnings.iter().filter(|w| **w == text).count(),
+            1,
+            "{}",
+            text
+        );
+    }
+
+    let json = serde_json::to_value(&response).unwrap();
+    assert!(json["diagnostics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|d| d["kind"] == "deprecated_alias"));
+}
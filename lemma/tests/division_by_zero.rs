@@ -0,0 +1,112 @@
+//! Choosing what rules give on division by zero: `on division by zero ...`
+//!
+//! Key behaviors:
+//! 1. Without a directive, dividing by zero fails the evaluation
+//! 2. `veto "message"` vetoes the dividing rule with the message
+//! 3. `unknown` leaves the rule and the rules using it without a value
+//! 4. Modulo by zero follows the same policy, and non-zero divisors are unaffected
+//! 5. The directive round-trips through `Display`
+
+mod common;
+
+use common::rule_result;
+use lemma::{DivisionByZero, Engine, LiteralValue, Response};
+use rust_decimal::Decimal;
+
+fn code(directive: &str) -> String {
+    format!(
+        "doc margins\n{}\nfact revenue = [number]\nfact units = [number]\n\
+         rule per_unit = revenue / units\nrule remainder = revenue % units\n\
+         rule doubled = per_unit? * 2\n",
+        directive
+    )
+}
+
+fn evaluate(directive: &str, units: &str) -> lemma::LemmaResult<Response> {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(&code(directive), "margins.lemma")
+        .unwrap();
+    let units = format!("units={}", units);
+    let facts = engine
+        .parse_facts("margins", &["revenue=100", &units])
+        .unwrap();
+    engine.evaluate("margins", None, Some(facts))
+}
+
+#[test]
+fn test_division_by_zero_fails_by_default() {
+    let error = evaluate("", "0").unwrap_err();
+    assert!(error.to_string().contains("Division by zero"), "{}", error);
+    assert!(evaluate("on division by zero error", "0").is_err());
+}
+
+#[test]
+fn test_veto_policy() {
+    let response = evaluate("on division by zero veto \"No units sold\"", "0").unwrap();
+    for rule in ["per_unit", "remainder", "doubled"] {
+        let result = rule_result(&response, rule);
+        assert_eq!(result.result, None);
+        assert_eq!(
+            result.veto_message.as_deref(),
+            Some("No units sold"),
+            "{}",
+            rule
+        );
+    }
+}
+
+#[test]
+fn test_unknown_policy() {
+    let response = evaluate("on division by zero unknown", "0").unwrap();
+    for rule in ["per_unit", "remainder", "doubled"] {
+        let result = rule_result(&response, rule);
+        assert_eq!(result.result, None);
+        assert_eq!(result.veto_message, None);
+        assert_eq!(result.missing_facts, None);
+        assert_eq!(
+            result.unknown.as_deref(),
+            Some("division by zero"),
+            "{}",
+            rule
+        );
+    }
+}
+
+#[test]
+fn test_non_zero_divisors_are_unaffected() {
+    let response = evaluate("on division by zero unknown", "8").unwrap();
+    assert_eq!(
+        rule_result(&response, "per_unit").result,
+        Some(LiteralValue::Number(Decimal::new(125, 1)))
+    );
+    assert_eq!(rule_result(&response, "per_unit").unknown, None);
+}
+
+#[test]
+fn test_directive_round_trips() {
+    for (directive, policy) in [
+        (
+            "on division by zero veto \"No units sold\"",
+            DivisionByZero::Veto(Some("No units sold".to_string())),
+        ),
+        ("on division by zero veto", DivisionByZero::Veto(None)),
+        ("on division by zero unknown", DivisionByZero::Unknown),
+    ] {
+        let mut engine = Engine::new();
+        engine
+            .add_lemma_code(&code(directive), "margins.lemma")
+            .unwrap();
+        let doc = engine.get_document("margins").unwrap();
+        assert_eq!(doc.division_by_zero, policy);
+
+        let printed = doc.to_string();
+        assert!(printed.contains(directive), "{}", printed);
+        let mut reparsed = Engine::new();
+        reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+        assert_eq!(
+            reparsed.get_document("margins").unwrap().division_by_zero,
+            policy
+        );
+    }
+}
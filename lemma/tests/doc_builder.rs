@@ -0,0 +1,119 @@
+//! Building documents in code with `DocBuilder` and `Expr`
+//!
+//! Key behaviors:
+//! 1. A built document loads and evaluates like the same code written by hand
+//! 2. Operands that bind looser than their operator are parenthesized
+//! 3. `expr!` and `Expr::parse` take Lemma syntax
+//! 4. Invalid names, duplicates and broken expressions are reported by `build`
+
+mod common;
+
+use common::rule_result;
+use lemma::builder::{DocBuilder, Expr, RuleBuilder};
+use lemma::{expr, Engine, LemmaType, LiteralValue};
+use rust_decimal::Decimal;
+
+fn number(value: i64) -> Option<LiteralValue> {
+    Some(LiteralValue::Number(Decimal::from(value)))
+}
+
+#[test]
+fn test_built_document_evaluates() {
+    let doc = DocBuilder::new("pricing")
+        .fact("price", LemmaType::Number)
+        .fact("quantity", LemmaType::Number)
+        .rule("total", expr! { price * quantity })
+        .rule_with(
+            RuleBuilder::new("shipping", 5)
+                .unless(Expr::rule("total").gt(100), 0)
+                .unless(Expr::fact("quantity").gt(50), Expr::veto("Too many"))
+                .description("Free above 100"),
+        )
+        .build()
+        .unwrap();
+
+    let mut engine = Engine::new();
+    let delta = engine.add_document(&doc, "pricing").unwrap();
+    assert_eq!(delta.added, vec!["pricing".to_string()]);
+
+    let facts = engine
+        .parse_facts("pricing", &["price=30", "quantity=4"])
+        .unwrap();
+    let response = engine.evaluate("pricing", None, Some(facts)).unwrap();
+    assert_eq!(rule_result(&response, "total").result, number(120));
+    assert_eq!(rule_result(&response, "shipping").result, number(0));
+}
+
+#[test]
+fn test_operands_are_parenthesized() {
+    let grouped = (Expr::fact("a") + Expr::fact("b")) * 2;
+    let doc = DocBuilder::new("math")
+        .fact_value("a", LiteralValue::Number(Decimal::from(1)))
+        .fact_value("b", LiteralValue::Number(Decimal::from(2)))
+        .rule("grouped", grouped)
+        .rule(
+            "subtracted",
+            Expr::number(10) - (Expr::fact("a") - Expr::fact("b")),
+        )
+        .rule("negated", !Expr::fact("a").gt(Expr::fact("b")))
+        .build()
+        .unwrap();
+
+    let code = doc.to_string();
+    assert!(code.contains("rule grouped = (a + b) * 2"), "{}", code);
+    assert!(code.contains("rule subtracted = 10 - (a - b)"), "{}", code);
+    assert!(code.contains("rule negated = not (a > b)"), "{}", code);
+
+    let mut engine = Engine::new();
+    engine.add_document(&doc, "math").unwrap();
+    let response = engine.evaluate("math", None, None).unwrap();
+    assert_eq!(rule_result(&response, "grouped").result, number(6));
+    assert_eq!(rule_result(&response, "subtracted").result, number(11));
+    assert_eq!(
+        rule_result(&response, "negated").result,
+        Some(LiteralValue::Boolean(true))
+    );
+}
+
+#[test]
+fn test_parsed_expressions_compose() {
+    let doc = DocBuilder::new("order")
+        .fact_doc("pricing", "pricing")
+        .rule("doubled", Expr::parse("pricing.price * 2") + 1)
+        .build()
+        .unwrap();
+
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code("doc pricing\nfact price = 10", "pricing.lemma")
+        .unwrap();
+    engine.add_document(&doc, "order").unwrap();
+    let response = engine.evaluate("order", None, None).unwrap();
+    assert_eq!(rule_result(&response, "doubled").result, number(21));
+}
+
+#[test]
+fn test_mistakes_are_reported_by_build() {
+    let error = |builder: DocBuilder| builder.build().unwrap_err().to_string();
+
+    assert!(error(DocBuilder::new("2nd")).contains("not a valid name"));
+    assert!(
+        error(DocBuilder::new("d").fact("rule", LemmaType::Number)).contains("not a valid name")
+    );
+    assert!(error(
+        DocBuilder::new("d")
+            .fact("x", LemmaType::Number)
+            .rule("x", Expr::fact("x"))
+    )
+    .contains("already has a fact or rule named 'x'"));
+    assert!(
+        error(DocBuilder::new("d").rule("r", Expr::parse("1 +"))).contains("Invalid expression")
+    );
+    assert!(
+        error(DocBuilder::new("d").rule("r", Expr::parse("1\nrule s = 2")))
+            .contains("expected a single expression")
+    );
+    assert!(
+        error(DocBuilder::new("d").rule("r", Expr::fact("a..b") + 1)).contains("not a valid name")
+    );
+}
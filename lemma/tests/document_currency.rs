@@ -0,0 +1,201 @@
+//! Document currency directive tests
+//!
+//! Key behaviors:
+//! 1. `currency EUR` after the doc declaration sets the document currency
+//! 2. Money literals and conversions in other currencies are rejected by the validator
+//! 3. Numbers given for money facts are coerced to the document currency
+//! 4. Plain numbers in arithmetic and comparisons with its money are read in the document
+//!    currency, without an implicit coercion warning
+//! 5. Referenced documents coerce to their own currency
+//! 6. Unknown currency codes are parse errors
+//! 7. The directive survives a Display roundtrip
+
+mod common;
+
+use common::rule_result;
+use lemma::{DiagnosticKind, Engine, LemmaError, MoneyUnit};
+
+const CODE: &str = r#"
+doc invoice
+currency EUR
+fact net = [money]
+fact shipping = 5 EUR
+rule total = net + shipping
+rule is_large = net > 1000 EUR
+rule rounded = 12.5 in EUR
+"#;
+
+fn engine(code: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+    engine
+}
+
+fn value(engine: &Engine, doc: &str, overrides: &[&str], rule: &str) -> String {
+    let facts = engine.parse_facts(doc, overrides).unwrap();
+    let response = engine.evaluate(doc, None, Some(facts)).unwrap();
+    rule_result(&response, rule)
+        .result
+        .as_ref()
+        .unwrap()
+        .to_string()
+}
+
+fn semantic_error(code: &str) -> String {
+    let mut engine = Engine::new();
+    match engine.add_lemma_code(code, "test.lemma") {
+        Err(LemmaError::Semantic(details)) => details.message,
+        other => panic!("Expected semantic error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_directive_sets_document_currency() {
+    let engine = engine(CODE);
+    let doc = engine.get_document("invoice").unwrap();
+    assert_eq!(doc.currency, Some(MoneyUnit::Eur));
+}
+
+#[test]
+fn test_numbers_are_coerced_to_document_currency() {
+    let engine = engine(CODE);
+    assert_eq!(value(&engine, "invoice", &["net=100"], "total"), "105 EUR");
+    assert_eq!(value(&engine, "invoice", &["net=2000"], "is_large"), "true");
+    assert_eq!(value(&engine, "invoice", &[], "rounded"), "12.5 EUR");
+}
+
+#[test]
+fn test_plain_numbers_next_to_money_are_in_document_currency() {
+    let code = r#"
+doc invoice
+currency EUR
+fact net = [money]
+rule total = net + 5
+rule is_large = net > 1000
+"#;
+    let engine = engine(code);
+    let facts = engine.parse_facts("invoice", &["net=100"]).unwrap();
+    let response = engine.evaluate("invoice", None, Some(facts)).unwrap();
+
+    let total = rule_result(&response, "total").result.as_ref().unwrap();
+    assert_eq!(total.to_string(), "105 EUR");
+    let is_large = rule_result(&response, "is_large").result.as_ref().unwrap();
+    assert_eq!(is_large.to_string(), "false");
+    assert!(
+        !response
+            .diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::ImplicitCoercion),
+        "{:?}",
+        response.diagnostics
+    );
+}
+
+#[test]
+fn test_money_in_document_currency_is_accepted() {
+    let engine = engine(CODE);
+    assert_eq!(
+        value(&engine, "invoice", &["net=100 EUR"], "total"),
+        "105 EUR"
+    );
+}
+
+#[test]
+fn test_override_in_other_currency_is_rejected() {
+    let engine = engine(CODE);
+    let error = engine.parse_facts("invoice", &["net=100 USD"]).unwrap_err();
+    match error {
+        LemmaError::FactParse(errors) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].fact.as_deref(), Some("net"));
+        }
+        other => panic!("Expected fact parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_literal_in_other_currency_is_rejected() {
+    let message = semantic_error(
+        r#"
+doc invoice
+currency EUR
+fact net = [money]
+rule total = net + 5 USD
+"#,
+    );
+    assert!(message.contains("Rule 'total' uses USD"), "{}", message);
+    assert!(message.contains("declares currency EUR"), "{}", message);
+}
+
+#[test]
+fn test_fact_in_other_currency_is_rejected() {
+    let message = semantic_error(
+        r#"
+doc invoice
+currency EUR
+fact shipping = 5 GBP
+"#,
+    );
+    assert!(message.contains("Fact 'shipping' uses GBP"), "{}", message);
+}
+
+#[test]
+fn test_conversion_to_other_currency_is_rejected() {
+    let message = semantic_error(
+        r#"
+doc invoice
+currency EUR
+fact amount = 10
+rule price = amount in USD
+"#,
+    );
+    assert!(message.contains("Rule 'price' uses USD"), "{}", message);
+}
+
+#[test]
+fn test_referenced_document_uses_its_own_currency() {
+    let engine = engine(
+        r#"
+doc us_order
+currency USD
+fact amount = [money]
+rule total = amount * 2
+
+doc summary
+fact order = doc us_order
+rule order_total = order.total?
+"#,
+    );
+    assert_eq!(
+        value(&engine, "summary", &["order.amount=10"], "order_total"),
+        "20 USD"
+    );
+}
+
+#[test]
+fn test_unknown_currency_is_parse_error() {
+    let mut engine = Engine::new();
+    let result = engine.add_lemma_code("doc invoice\ncurrency ABC\n", "test.lemma");
+    match result {
+        Err(LemmaError::Parse(details)) => {
+            assert!(details.message.contains("Unknown currency 'ABC'"));
+        }
+        other => panic!("Expected parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_directive_display_roundtrip() {
+    let engine = engine(CODE);
+    let printed = engine.get_document("invoice").unwrap().to_string();
+    assert!(printed.contains("currency EUR\n"), "{}", printed);
+
+    let mut reparsed = Engine::new();
+    reparsed
+        .add_lemma_code(&printed, "roundtrip.lemma")
+        .unwrap();
+    assert_eq!(
+        reparsed.get_document("invoice").unwrap().currency,
+        Some(MoneyUnit::Eur)
+    );
+}
@@ -0,0 +1,120 @@
+//! Loading referenced documents on demand with a `DocumentLoader`
+//!
+//! Key behaviors:
+//! 1. Adding code loads the documents it references, and theirs, from the loader
+//! 2. Documents the loader doesn't have are reported as missing references
+//! 3. `load_document` loads a document by name
+//! 4. `DirectoryLoader` reads `a/b.lemma` for document `a/b`
+
+use lemma::{DirectoryLoader, DocumentLoader, Engine, LemmaResult, LiteralValue, LoadedCode};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Serves documents from memory and remembers what was asked for
+#[derive(Clone, Default)]
+struct MemoryLoader {
+    documents: HashMap<String, String>,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl MemoryLoader {
+    fn with(mut self, name: &str, code: &str) -> Self {
+        self.documents.insert(name.to_string(), code.to_string());
+        self
+    }
+
+    fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl DocumentLoader for MemoryLoader {
+    fn load(&self, doc_name: &str) -> LemmaResult<Option<LoadedCode>> {
+        self.requests.lock().unwrap().push(doc_name.to_string());
+        Ok(self.documents.get(doc_name).map(|code| LoadedCode {
+            source: format!("{}.lemma", doc_name),
+            code: code.clone(),
+        }))
+    }
+}
+
+fn library() -> MemoryLoader {
+    MemoryLoader::default()
+        .with(
+            "pricing",
+            "doc pricing\nfact tax = doc tax\nfact base = 100\nrule price = base + tax.amount?",
+        )
+        .with("tax", "doc tax\nfact rate = 21%\nrule amount = 100 * rate")
+        .with("unused", "doc unused\nrule never = 1")
+}
+
+#[test]
+fn test_references_are_loaded_transitively() {
+    let loader = library();
+    let mut engine = Engine::new().with_document_loader(loader.clone());
+    let delta = engine
+        .add_lemma_code(
+            "doc order\nfact pricing = doc pricing\nrule total = pricing.price?",
+            "order.lemma",
+        )
+        .unwrap();
+
+    let mut added = delta.added.clone();
+    added.sort();
+    assert_eq!(added, vec!["order", "pricing", "tax"]);
+    assert_eq!(loader.requests(), vec!["pricing", "tax"]);
+    assert!(engine.get_document("unused").is_none());
+
+    let response = engine.evaluate("order", None, None).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(121)))
+    );
+}
+
+#[test]
+fn test_unknown_references_are_still_errors() {
+    let mut engine = Engine::new().with_document_loader(library());
+    let error = engine
+        .add_lemma_code("doc order\nfact other = doc nowhere", "order.lemma")
+        .unwrap_err();
+    assert!(error.to_string().contains("nowhere"), "{}", error);
+    assert!(engine.list_documents().is_empty());
+}
+
+#[test]
+fn test_load_document_by_name() {
+    let mut engine = Engine::new().with_document_loader(library());
+    engine.load_document("pricing").unwrap();
+    assert!(engine.get_document("tax").is_some());
+    assert!(engine.load_document("pricing").unwrap().is_empty());
+    assert!(engine.load_document("nowhere").is_err());
+    assert!(Engine::new().load_document("pricing").is_err());
+}
+
+#[test]
+fn test_directory_loader() {
+    let dir = std::env::temp_dir().join(format!("lemma_loader_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("rates")).unwrap();
+    std::fs::write(
+        dir.join("rates/vat.lemma"),
+        "doc rates/vat\nfact rate = 21%",
+    )
+    .unwrap();
+
+    let loader = DirectoryLoader::new(&dir);
+    assert!(loader.load("rates/vat").unwrap().is_some());
+    assert!(loader.load("rates/none").unwrap().is_none());
+    assert!(loader.load("../rates/vat").unwrap().is_none());
+
+    let mut engine = Engine::new().with_document_loader(loader);
+    engine
+        .add_lemma_code(
+            "doc invoice\nfact vat = doc rates/vat\nrule rate = vat.rate",
+            "invoice.lemma",
+        )
+        .unwrap();
+    assert!(engine.get_document("rates/vat").is_some());
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,115 @@
+//! Content-addressed document versions
+//!
+//! Key behaviors:
+//! 1. Every loaded document has a SHA-256 version that ignores layout
+//! 2. Changing a rule, or a referenced document, changes the version
+//! 3. Responses carry the version of the evaluated document
+//! 4. Evaluation pinned to another version fails with `VersionMismatch`
+
+use lemma::{Engine, EvaluationOptions, LemmaError};
+
+const CODE: &str = r#"
+doc rates
+fact vat = 21%
+
+doc invoice
+fact net = 100
+fact tax = doc rates
+rule total = net + net * tax.vat
+"#;
+
+fn engine(code: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "invoice.lemma").unwrap();
+    engine
+}
+
+fn version(engine: &Engine, doc: &str) -> String {
+    engine.document_version(doc).unwrap().to_string()
+}
+
+#[test]
+fn test_versions_are_content_hashes() {
+    let original = engine(CODE);
+    let invoice = version(&original, "invoice");
+    assert_eq!(invoice.len(), 64);
+    assert!(invoice.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_ne!(invoice, version(&original, "rates"));
+    assert_eq!(original.document_version("missing"), None);
+
+    let relaid = CODE.replace(
+        "rule total = net + net * tax.vat",
+        "rule total =   net + net * tax.vat\n\n",
+    );
+    assert_eq!(version(&engine(&relaid), "invoice"), invoice);
+}
+
+#[test]
+fn test_changes_change_the_version() {
+    let original = engine(CODE);
+
+    let rule_changed = engine(&CODE.replace("net + net", "net * 2 + net"));
+    assert_ne!(
+        version(&rule_changed, "invoice"),
+        version(&original, "invoice")
+    );
+    assert_eq!(version(&rule_changed, "rates"), version(&original, "rates"));
+
+    let reference_changed = engine(&CODE.replace("21%", "9%"));
+    assert_ne!(
+        version(&reference_changed, "invoice"),
+        version(&original, "invoice")
+    );
+}
+
+#[test]
+fn test_versions_follow_edits_and_removal() {
+    let mut engine = engine(CODE);
+    let before = version(&engine, "rates");
+
+    // Line 3 holds `fact vat = 21%`
+    engine
+        .update_document_range("invoice.lemma", 3..4, "fact vat = 9%\n")
+        .unwrap();
+    assert_ne!(version(&engine, "rates"), before);
+
+    engine.remove_document("invoice");
+    assert_eq!(engine.document_version("invoice"), None);
+}
+
+#[test]
+fn test_response_carries_version() {
+    let engine = engine(CODE);
+    let response = engine.evaluate("invoice", None, None).unwrap();
+    assert_eq!(
+        response.doc_version.as_deref(),
+        engine.document_version("invoice")
+    );
+
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["doc_version"], version(&engine, "invoice"));
+}
+
+#[test]
+fn test_pinned_version() {
+    let engine = engine(CODE);
+
+    let pinned = EvaluationOptions::default().with_pinned_version(version(&engine, "invoice"));
+    assert!(engine
+        .evaluate_with_options("invoice", None, None, &pinned)
+        .is_ok());
+
+    let stale = EvaluationOptions::default().with_pinned_version("0123abcd");
+    match engine.evaluate_with_options("invoice", None, None, &stale) {
+        Err(LemmaError::VersionMismatch {
+            doc_name,
+            expected,
+            actual,
+        }) => {
+            assert_eq!(doc_name, "invoice");
+            assert_eq!(expected, "0123abcd");
+            assert_eq!(actual, version(&engine, "invoice"));
+        }
+        other => panic!("Expected VersionMismatch, got {:?}", other),
+    }
+}
@@ -150,16 +150,25 @@ fn test_05_date_handling() {
     let engine = load_examples();
 
     // Document uses [date] type annotation for current_date, need to provide it
-    // Note: This document has a bug - employee_age rule tries to convert calendar units
-    // which is not supported. We just test that the document loads.
     let facts = lemma::parser::parse_facts(&["current_date = 2024-06-15"]).unwrap();
 
-    let result = engine.evaluate("examples/date_handling", None, Some(facts));
+    let response = engine
+        .evaluate("examples/date_handling", None, Some(facts))
+        .expect("Evaluation failed");
 
-    // Document will fail due to calendar unit conversion bug in employee_age rule
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("calendar units"));
+    let value = |rule: &str| {
+        response
+            .results
+            .iter()
+            .find(|r| r.rule_name == rule)
+            .and_then(|r| r.result.as_ref())
+            .map(|v| v.to_string())
+    };
+    assert_eq!(value("employee_age").as_deref(), Some("34"));
+    assert_eq!(value("is_adult").as_deref(), Some("true"));
+    assert_eq!(value("years_employed").as_deref(), Some("0"));
 }
+
 #[test]
 fn test_06_tax_calculation() {
     let engine = load_examples();
@@ -315,9 +324,10 @@ fn test_09_stress_test_extended() {
 
     let result = engine.evaluate("examples/stress_test_extended", None, Some(facts));
 
-    // Document will fail due to cross-document rule reference bugs
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("does not exist"));
+    // Cross-document rule references resolve, but the example still has a unit error
+    let error = result.unwrap_err().to_string();
+    assert!(!error.contains("does not exist"));
+    assert!(error.contains("Mismatched unit type"));
 }
 
 #[test]
@@ -346,15 +356,23 @@ fn test_10_compensation_policy() {
         .iter()
         .any(|r| r.rule_name == "total_package"));
 
-    // Test senior_engineer document - has cross-document rule reference bugs
-    let result = engine.evaluate("examples/compensation/senior_engineer", None, None);
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("does not exist"));
+    // Test senior_engineer document - rules of referenced documents use their own rules
+    let response = engine
+        .evaluate("examples/compensation/senior_engineer", None, None)
+        .expect("Evaluation failed");
+    assert!(response
+        .results
+        .iter()
+        .any(|r| r.rule_name == "compensation_tier" && r.result.is_some()));
 
-    // Test principal_engineer document - also has cross-document rule reference bugs
-    let result = engine.evaluate("examples/compensation/principal_engineer", None, None);
-    assert!(result.is_err());
-    assert!(result.unwrap_err().to_string().contains("does not exist"));
+    // Test principal_engineer document
+    let response = engine
+        .evaluate("examples/compensation/principal_engineer", None, None)
+        .expect("Evaluation failed");
+    assert!(response
+        .results
+        .iter()
+        .any(|r| r.rule_name == "net_compensation" && r.result.is_some()));
 }
 
 #[test]
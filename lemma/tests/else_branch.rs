@@ -0,0 +1,104 @@
+//! Trailing `else` as the default value of a rule
+//!
+//! Key behaviors:
+//! 1. `unless ... then ... else x` parses into the same rule as `x unless ... then ...`
+//! 2. `otherwise` is accepted in place of `else`
+//! 3. Unless modes work with the trailing default
+//! 4. With both a leading default and an else branch, the else branch is the default
+
+use lemma::{Engine, LiteralValue};
+use rust_decimal::Decimal;
+
+fn evaluate(code: &str, doc: &str, facts: &[&str]) -> Vec<(String, Option<LiteralValue>)> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+    let facts = engine.parse_facts(doc, facts).unwrap();
+    engine
+        .evaluate(doc, None, Some(facts))
+        .unwrap()
+        .results
+        .into_iter()
+        .map(|r| (r.rule_name, r.result))
+        .collect()
+}
+
+#[test]
+fn test_else_is_the_default() {
+    let code = r#"
+doc pricing
+fact quantity = [number]
+rule leading = 0%
+  unless quantity >= 10 then 5%
+rule trailing =
+  unless quantity >= 10 then 5%
+  else 0%
+"#;
+    for quantity in ["3", "12"] {
+        let results = evaluate(code, "pricing", &[&format!("quantity={}", quantity)]);
+        assert_eq!(results[0].1, results[1].1, "quantity={}", quantity);
+    }
+
+    let mut leading = Engine::new();
+    leading.add_lemma_code(code, "test.lemma").unwrap();
+    let doc = leading.get_document("pricing").unwrap();
+    assert_eq!(
+        doc.rules[0].unless_clauses.len(),
+        doc.rules[1].unless_clauses.len()
+    );
+    assert_eq!(
+        doc.rules[0].expression.to_string(),
+        doc.rules[1].expression.to_string()
+    );
+}
+
+#[test]
+fn test_otherwise_on_one_line() {
+    let code = r#"
+doc pricing
+fact quantity = [number]
+rule discount = unless quantity >= 10 then 5% otherwise 0%
+"#;
+    let results = evaluate(code, "pricing", &["quantity=3"]);
+    assert_eq!(results[0].1.as_ref().unwrap().to_string(), "0%");
+    let results = evaluate(code, "pricing", &["quantity=10"]);
+    assert_eq!(results[0].1.as_ref().unwrap().to_string(), "5%");
+}
+
+#[test]
+fn test_first_match_with_else() {
+    let code = r#"
+doc shipping
+fact weight = [number]
+rule cost first match =
+  unless weight > 10 then 20
+  unless weight > 5 then 10
+  else 5
+"#;
+    let results = evaluate(code, "shipping", &["weight=12"]);
+    assert_eq!(results[0].1, Some(LiteralValue::Number(Decimal::from(20))));
+    let results = evaluate(code, "shipping", &["weight=1"]);
+    assert_eq!(results[0].1, Some(LiteralValue::Number(Decimal::from(5))));
+}
+
+#[test]
+fn test_else_wins_over_leading_default() {
+    let code = r#"
+doc pricing
+fact quantity = [number]
+rule discount = 1% unless quantity >= 10 then 5% else 0%
+"#;
+    let results = evaluate(code, "pricing", &["quantity=3"]);
+    assert_eq!(results[0].1.as_ref().unwrap().to_string(), "0%");
+    let results = evaluate(code, "pricing", &["quantity=10"]);
+    assert_eq!(results[0].1.as_ref().unwrap().to_string(), "5%");
+}
+
+#[test]
+fn test_else_is_reserved() {
+    let code = r#"
+doc pricing
+fact else = 1
+"#;
+    let mut engine = Engine::new();
+    assert!(engine.add_lemma_code(code, "test.lemma").is_err());
+}
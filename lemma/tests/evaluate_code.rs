@@ -0,0 +1,102 @@
+//! Stateless evaluation of inline code with `Engine::evaluate_code`
+//!
+//! Key behaviors:
+//! 1. Code is parsed, validated and evaluated in one call
+//! 2. The engine is not changed
+//! 3. The first document is evaluated unless one is named
+//! 4. Fact overrides are parsed against the code's documents
+//! 5. The engine's resource limits and functions apply
+
+use lemma::{Engine, LemmaError, LiteralValue, ResourceLimits};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = [number]
+fact price = 10
+rule total = quantity * price
+
+doc shipping
+fact weight = 2
+rule cost = weight * 5
+"#;
+
+fn number(value: i64) -> Option<LiteralValue> {
+    Some(LiteralValue::Number(Decimal::from(value)))
+}
+
+#[test]
+fn test_code_is_evaluated_without_loading_it() {
+    let engine = Engine::new();
+    let response = engine
+        .evaluate_code(CODE, None, None, &["quantity=3"])
+        .unwrap();
+    assert_eq!(response.doc_name, "pricing");
+    assert_eq!(response.results[0].result, number(30));
+    assert!(engine.list_documents().is_empty());
+
+    let response = engine
+        .evaluate_code(CODE, Some("shipping"), Some(vec!["cost".to_string()]), &[])
+        .unwrap();
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].result, number(10));
+}
+
+#[test]
+fn test_loaded_documents_are_untouched() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc pricing\nfact price = 99\nrule total = price",
+            "a.lemma",
+        )
+        .unwrap();
+
+    engine
+        .evaluate_code(CODE, Some("pricing"), None, &["quantity=1"])
+        .unwrap();
+    let response = engine.evaluate("pricing", None, None).unwrap();
+    assert_eq!(response.results[0].result, number(99));
+}
+
+#[test]
+fn test_errors_are_reported() {
+    let engine = Engine::new();
+    // An unknown fact is only missing, an unknown rule is an error
+    let error = engine
+        .evaluate_code("doc broken\nrule x = y?", None, None, &[])
+        .unwrap_err();
+    assert!(error.to_string().contains("does not exist"), "{}", error);
+    assert!(matches!(
+        engine.evaluate_code(CODE, None, None, &["quantity=\"many\""]),
+        Err(LemmaError::FactParse(_))
+    ));
+    assert!(engine
+        .evaluate_code(CODE, Some("missing"), None, &[])
+        .is_err());
+}
+
+#[test]
+fn test_engine_limits_and_functions_apply() {
+    let limits = ResourceLimits {
+        max_file_size_bytes: 10,
+        ..ResourceLimits::default()
+    };
+    let engine = Engine::with_limits(limits);
+    assert!(matches!(
+        engine.evaluate_code(CODE, None, None, &[]),
+        Err(LemmaError::ResourceLimitExceeded { .. })
+    ));
+
+    let mut engine = Engine::new();
+    engine
+        .register_function("double", 1, |args| match &args[0] {
+            LiteralValue::Number(n) => Ok(LiteralValue::Number(n * Decimal::from(2))),
+            _ => Err("expected a number".to_string()),
+        })
+        .unwrap();
+    let response = engine
+        .evaluate_code("doc calc\nrule x = double(21)", None, None, &[])
+        .unwrap();
+    assert_eq!(response.results[0].result, number(42));
+}
@@ -0,0 +1,87 @@
+//! Evaluating several documents with one set of facts
+//!
+//! Key behaviors:
+//! 1. Each document gets the overrides for the facts it declares
+//! 2. An override declared by no document is reported as unused by each
+//! 3. An unknown document fails the whole evaluation
+
+mod common;
+
+use common::rule_result;
+use lemma::{
+    parse_facts, DiagnosticKind, Engine, EvaluationOptions, LemmaError, Response, UnusedOverrides,
+};
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = [number]
+fact unit_price = 5
+rule total = quantity * unit_price
+
+doc eligibility
+fact quantity = [number]
+fact age = [number]
+rule bulk = quantity >= 10
+rule adult = age >= 18
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shop.lemma").unwrap();
+    engine
+}
+
+fn result(response: &Response, rule: &str) -> String {
+    rule_result(response, rule)
+        .result
+        .as_ref()
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_shared_overrides_reach_each_document() {
+    let facts = parse_facts(&["quantity=12", "age=30"]).unwrap();
+    let responses = engine()
+        .evaluate_many(&["pricing", "eligibility"], Some(facts))
+        .unwrap();
+
+    assert_eq!(responses.len(), 2);
+    assert_eq!(result(&responses["pricing"], "total"), "60");
+    assert_eq!(result(&responses["eligibility"], "bulk"), "true");
+    assert_eq!(result(&responses["eligibility"], "adult"), "true");
+    // `age` belongs to eligibility only, so pricing doesn't report it
+    assert!(responses["pricing"].diagnostics.is_empty());
+}
+
+#[test]
+fn test_unclaimed_override_is_reported() {
+    let facts = parse_facts(&["quantity=2", "age=30", "quanity=3"]).unwrap();
+    let responses = engine()
+        .evaluate_many(&["pricing", "eligibility"], Some(facts.clone()))
+        .unwrap();
+    for response in responses.values() {
+        assert!(response
+            .diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::UnusedOverride && d.message.contains("quanity")));
+    }
+
+    let options = EvaluationOptions::default().with_unused_overrides(UnusedOverrides::Strict);
+    let error = engine()
+        .evaluate_many_with_options(&["pricing", "eligibility"], Some(facts), &options)
+        .unwrap_err();
+    assert!(matches!(error, LemmaError::FactParse(_)), "{}", error);
+}
+
+#[test]
+fn test_unknown_document_fails() {
+    let error = engine()
+        .evaluate_many(&["pricing", "shipping"], None)
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Document 'shipping' not found"),
+        "{}",
+        error
+    );
+}
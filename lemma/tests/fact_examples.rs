@@ -0,0 +1,99 @@
+//! Sample values for typed facts: `fact weight = [mass] example 12 kilograms`
+//!
+//! Key behaviors:
+//! 1. The example is parsed, kept on the fact and round-trips through `Display`
+//! 2. It is never a value: without overrides the fact is still missing
+//! 3. `Engine::example_facts` turns the examples into overrides
+//! 4. The fact schema lists the example under `examples`
+//! 5. Examples must match the declared type and need a typed fact
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LiteralValue};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc shipping
+fact weight = [mass] example 12 kilograms
+fact distance = [number] example 250
+fact express = false
+rule cost = distance * 2
+rule heavy = weight > 10 kilograms
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shipping.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_example_is_parsed_and_round_trips() {
+    let engine = engine();
+    let facts = engine.get_document_facts("shipping");
+    assert_eq!(
+        facts[1].example,
+        Some(LiteralValue::Number(Decimal::from(250)))
+    );
+    assert_eq!(facts[2].example, None);
+
+    let printed = engine.get_document("shipping").unwrap().to_string();
+    assert!(
+        printed.contains("fact distance = [number] example 250"),
+        "{}",
+        printed
+    );
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+    assert_eq!(
+        reparsed.get_document_facts("shipping")[0].example,
+        facts[0].example
+    );
+}
+
+#[test]
+fn test_example_is_not_a_value() {
+    let response = engine().evaluate("shipping", None, None).unwrap();
+    assert_eq!(rule_result(&response, "cost").result, None);
+}
+
+#[test]
+fn test_example_facts_demonstrate_the_document() {
+    let engine = engine();
+    let examples = engine.example_facts("shipping");
+    assert_eq!(examples.len(), 2);
+    let response = engine.evaluate("shipping", None, Some(examples)).unwrap();
+    assert_eq!(
+        rule_result(&response, "cost").result,
+        Some(LiteralValue::Number(Decimal::from(500)))
+    );
+    assert_eq!(
+        rule_result(&response, "heavy").result,
+        Some(LiteralValue::Boolean(true))
+    );
+}
+
+#[test]
+fn test_schema_lists_examples() {
+    let schema = engine().fact_schema("shipping").unwrap();
+    assert_eq!(
+        schema["properties"]["distance"]["examples"],
+        serde_json::json!([250])
+    );
+    assert!(schema["properties"]["express"].get("examples").is_none());
+}
+
+#[test]
+fn test_invalid_examples_are_rejected() {
+    for (code, expected) in [
+        (
+            "doc d\nfact age = [number] example 3 kilograms",
+            "must be a",
+        ),
+        ("doc d\nfact age = 30 example 40", "only typed facts"),
+    ] {
+        let error = Engine::new().add_lemma_code(code, "d.lemma").unwrap_err();
+        assert!(error.to_string().contains(expected), "{}", error);
+    }
+}
@@ -0,0 +1,169 @@
+//! Dotted facts grouped into a record within one document
+//!
+//! Key behaviors:
+//! 1. `fact customer.name = [text]` declares a fact without a `customer` document
+//! 2. Grouped facts are referenced and overridden by their full path
+//! 3. Misspelled group fields get a suggestion, and the schema lists them
+//! 4. A group name can't also be a fact or rule of the same document
+//! 5. Dotted facts under a document reference still override that document
+
+mod common;
+
+use common::rule_result;
+use lemma::{DiagnosticKind, Engine, EvaluationOptions, LemmaError, LiteralValue, UnusedOverrides};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc shop
+fact customer.name = [text]
+fact customer.age = [number]
+fact customer.country = "NL"
+
+rule is_adult = customer.age >= 18
+rule is_local = customer.country is "NL"
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shop.lemma").unwrap();
+    engine
+}
+
+fn result(engine: &Engine, facts: &[&str], rule: &str) -> Option<LiteralValue> {
+    let facts = engine.parse_facts("shop", facts).unwrap();
+    let response = engine.evaluate("shop", None, Some(facts)).unwrap();
+    rule_result(&response, rule).result.clone()
+}
+
+#[test]
+fn test_grouped_facts_are_referenced_by_path() {
+    let engine = engine();
+    assert_eq!(
+        result(&engine, &["customer.age=42"], "is_adult"),
+        Some(LiteralValue::Boolean(true))
+    );
+    assert_eq!(
+        result(&engine, &["customer.age=12"], "is_adult"),
+        Some(LiteralValue::Boolean(false))
+    );
+    assert_eq!(result(&engine, &[], "is_adult"), None);
+}
+
+#[test]
+fn test_grouped_facts_can_be_overridden() {
+    let engine = engine();
+    assert_eq!(
+        result(&engine, &[], "is_local"),
+        Some(LiteralValue::Boolean(true))
+    );
+    assert_eq!(
+        result(&engine, &["customer.country=\"BE\""], "is_local"),
+        Some(LiteralValue::Boolean(false))
+    );
+}
+
+#[test]
+fn test_override_type_is_checked() {
+    let engine = engine();
+    assert!(engine.parse_facts("shop", &["customer.age=old"]).is_err());
+    let facts = engine.parse_facts("shop", &["customer.age=30"]).unwrap();
+    let age = facts.iter().find_map(|fact| match &fact.value {
+        lemma::FactValue::Literal(LiteralValue::Number(n)) => Some(*n),
+        _ => None,
+    });
+    assert_eq!(age, Some(Decimal::from(30)));
+}
+
+#[test]
+fn test_misspelled_field_gets_suggestion() {
+    let engine = engine();
+    let facts = engine.parse_facts("shop", &["customer.aeg=42"]).unwrap();
+    let options = EvaluationOptions::default().with_unused_overrides(UnusedOverrides::Warn);
+    let response = engine
+        .evaluate_with_options("shop", None, Some(facts), &options)
+        .unwrap();
+    let message = response
+        .diagnostics
+        .iter()
+        .find(|d| d.kind == DiagnosticKind::UnusedOverride)
+        .map(|d| d.message.clone())
+        .unwrap();
+    assert!(message.contains("customer.age"), "{}", message);
+}
+
+#[test]
+fn test_schema_lists_grouped_facts() {
+    let schema = engine().fact_schema("shop").unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    assert_eq!(properties["customer.name"]["type"], "string");
+    assert_eq!(properties["customer.country"]["default"], "NL");
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|v| v.as_str())
+        .collect();
+    assert!(required.contains(&"customer.age"));
+    assert!(!required.contains(&"customer.country"));
+}
+
+#[test]
+fn test_group_name_conflicts() {
+    for code in [
+        "doc shop\nfact customer = [text]\nfact customer.name = [text]",
+        "doc shop\nfact customer.name = [text]\nrule customer = 1",
+    ] {
+        let mut engine = Engine::new();
+        match engine.add_lemma_code(code, "shop.lemma") {
+            Err(LemmaError::Semantic(details)) => {
+                assert!(
+                    details.message.contains("Name conflict"),
+                    "{}",
+                    details.message
+                )
+            }
+            other => panic!("Expected name conflict, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_field_named_like_a_rule() {
+    let code = r#"
+doc shop
+fact customer.name = [text]
+rule name = customer.name
+"#;
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "shop.lemma").unwrap();
+    let facts = engine
+        .parse_facts("shop", &["customer.name=\"Ada\""])
+        .unwrap();
+    let response = engine.evaluate("shop", None, Some(facts)).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Text("Ada".to_string()))
+    );
+}
+
+#[test]
+fn test_dotted_fact_under_document_reference_overrides() {
+    let code = r#"
+doc product
+fact price = 10
+
+doc order
+fact item = doc product
+fact item.price = 12
+rule price = item.price
+"#;
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "order.lemma").unwrap();
+    let response = engine.evaluate("order", None, None).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(12)))
+    );
+    let schema = engine.fact_schema("order").unwrap();
+    assert!(schema["properties"].get("item.price").is_some());
+}
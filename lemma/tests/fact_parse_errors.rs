@@ -0,0 +1,94 @@
+//! Structured fact override parse errors
+//!
+//! Key behaviors:
+//! 1. Every rejected input yields its own FactParseError
+//! 2. Errors carry the input, fact name, position and what was expected
+//! 3. Engine::parse_facts adds the declared fact type, also through document references
+//! 4. Values of the wrong type are rejected with the same structured error
+
+use lemma::{parse_facts, Engine, FactParseError, LemmaError, LemmaType};
+
+fn fact_errors(result: Result<Vec<lemma::LemmaFact>, LemmaError>) -> Vec<FactParseError> {
+    match result {
+        Err(LemmaError::FactParse(errors)) => errors,
+        other => panic!("Expected fact parse errors, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_unparseable_value_reports_position_and_expected_types() {
+    let errors = fact_errors(parse_facts(&["price=abc"]));
+
+    assert_eq!(errors.len(), 1);
+    let error = &errors[0];
+    assert_eq!(error.input, "price=abc");
+    assert_eq!(error.fact.as_deref(), Some("price"));
+    assert_eq!(error.position, 6);
+    assert!(error.expected.contains(&"number".to_string()));
+    assert!(error.expected.contains(&"text".to_string()));
+    assert_eq!(error.declared_type, None);
+}
+
+#[test]
+fn test_each_bad_input_is_reported() {
+    let errors = fact_errors(parse_facts(&["a=1", "b=", "c 5", "d=3 apples"]));
+
+    let inputs: Vec<&str> = errors.iter().map(|e| e.input.as_str()).collect();
+    assert_eq!(inputs, vec!["b=", "c 5", "d=3 apples"]);
+
+    assert_eq!(errors[1].position, 1);
+    assert_eq!(errors[1].expected, vec!["="]);
+
+    assert_eq!(errors[2].position, 2);
+    assert!(errors[2].message.contains("apples"));
+}
+
+#[test]
+fn test_trailing_input_is_rejected() {
+    let errors = fact_errors(parse_facts(&["flag=true maybe"]));
+    assert_eq!(errors[0].position, 9);
+    assert!(errors[0].message.contains("maybe"));
+}
+
+#[test]
+fn test_display_keeps_input() {
+    let error = parse_facts(&["price=abc"]).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("Failed to parse fact 'price=abc' at position 6"));
+}
+
+#[test]
+fn test_engine_adds_declared_types() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            r#"
+doc customer
+fact age = [number]
+
+doc order
+fact quantity = [number]
+fact price = 10 EUR
+fact customer = doc customer
+rule total = quantity * price
+"#,
+            "test.lemma",
+        )
+        .unwrap();
+
+    let errors = fact_errors(engine.parse_facts("order", &["quantity=lots", "customer.age=old"]));
+    assert_eq!(errors[0].declared_type, Some(LemmaType::Number));
+    assert_eq!(errors[1].fact.as_deref(), Some("customer.age"));
+    assert_eq!(errors[1].declared_type, Some(LemmaType::Number));
+
+    let errors = fact_errors(engine.parse_facts("order", &["price=\"cheap\""]));
+    assert_eq!(errors[0].position, 6);
+    assert_eq!(errors[0].declared_type, Some(LemmaType::Money));
+    assert_eq!(errors[0].expected, vec![LemmaType::Money.to_string()]);
+
+    let facts = engine
+        .parse_facts("order", &["quantity=3", "price=12 EUR"])
+        .unwrap();
+    assert_eq!(facts.len(), 2);
+}
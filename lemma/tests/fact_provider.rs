@@ -0,0 +1,142 @@
+//! Facts supplied by a `FactProvider`
+//!
+//! Key behaviors:
+//! 1. Facts without a value that the evaluated rules reference are fetched before evaluating
+//! 2. Only facts of the requested rules are fetched, and never ones given as overrides
+//! 3. Lookups run concurrently
+//! 4. Facts the provider doesn't know stay missing; provider errors fail the evaluation
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, FactProvider, FactRequest, LemmaError, LemmaResult, LiteralValue};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+const CODE: &str = r#"
+doc customer
+fact tier = [text]
+fact years = [number]
+
+doc order
+fact customer = doc customer
+fact quantity = [number]
+fact unit_price = [number]
+fact rate = [number]
+rule subtotal = quantity * unit_price
+rule loyal = customer.years >= 5
+rule converted = subtotal? * rate
+"#;
+
+#[derive(Default)]
+struct Lookups {
+    requested: Mutex<Vec<String>>,
+    in_flight: AtomicUsize,
+    most_in_flight: AtomicUsize,
+}
+
+struct Provider {
+    values: HashMap<&'static str, LiteralValue>,
+    lookups: Arc<Lookups>,
+}
+
+impl FactProvider for Provider {
+    fn fetch(&self, request: &FactRequest) -> LemmaResult<Option<LiteralValue>> {
+        let lookups = &self.lookups;
+        lookups.requested.lock().unwrap().push(request.path.clone());
+        let in_flight = lookups.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        lookups
+            .most_in_flight
+            .fetch_max(in_flight, Ordering::SeqCst);
+        thread::sleep(Duration::from_millis(50));
+        lookups.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        if request.path == "rate" {
+            return Err(LemmaError::Engine("Rate service unavailable".to_string()));
+        }
+        Ok(self.values.get(request.path.as_str()).cloned())
+    }
+}
+
+fn number(n: i64) -> LiteralValue {
+    LiteralValue::Number(Decimal::from(n))
+}
+
+fn engine() -> (Engine, Arc<Lookups>) {
+    let lookups = Arc::new(Lookups::default());
+    let provider = Provider {
+        values: HashMap::from([
+            ("quantity", number(3)),
+            ("unit_price", number(10)),
+            ("customer.years", number(7)),
+        ]),
+        lookups: lookups.clone(),
+    };
+    let mut engine = Engine::new().with_fact_provider(provider);
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    (engine, lookups)
+}
+
+fn requested(lookups: &Lookups) -> Vec<String> {
+    let mut requested = lookups.requested.lock().unwrap().clone();
+    requested.sort();
+    requested
+}
+
+#[test]
+fn test_missing_facts_are_fetched_concurrently() {
+    let (engine, lookups) = engine();
+    let rules = vec!["subtotal".to_string(), "loyal".to_string()];
+    let response = engine.evaluate("order", Some(rules), None).unwrap();
+
+    assert_eq!(rule_result(&response, "subtotal").result, Some(number(30)));
+    assert_eq!(
+        rule_result(&response, "loyal").result,
+        Some(LiteralValue::Boolean(true))
+    );
+    assert_eq!(
+        requested(&lookups),
+        vec!["customer.years", "quantity", "unit_price"]
+    );
+    assert!(lookups.most_in_flight.load(Ordering::SeqCst) > 1);
+}
+
+#[test]
+fn test_only_needed_facts_are_fetched() {
+    let (engine, lookups) = engine();
+    let facts = engine.parse_facts("order", &["quantity=4"]).unwrap();
+    let response = engine
+        .evaluate("order", Some(vec!["subtotal".to_string()]), Some(facts))
+        .unwrap();
+
+    assert_eq!(rule_result(&response, "subtotal").result, Some(number(40)));
+    assert_eq!(requested(&lookups), vec!["unit_price"]);
+}
+
+#[test]
+fn test_unknown_facts_stay_missing() {
+    let mut engine = Engine::new().with_fact_provider(Provider {
+        values: HashMap::new(),
+        lookups: Arc::default(),
+    });
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    let response = engine
+        .evaluate("order", Some(vec!["subtotal".to_string()]), None)
+        .unwrap();
+    let subtotal = rule_result(&response, "subtotal");
+    assert_eq!(subtotal.result, None);
+    assert!(subtotal.missing_facts.is_some());
+}
+
+#[test]
+fn test_provider_error_fails_evaluation() {
+    let (engine, _) = engine();
+    let error = engine
+        .evaluate("order", Some(vec!["converted".to_string()]), None)
+        .unwrap_err();
+    assert!(error.to_string().contains("Rate service unavailable"));
+}
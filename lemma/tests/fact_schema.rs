@@ -0,0 +1,108 @@
+//! JSON Schema export of overridable facts
+//!
+//! Key behaviors:
+//! 1. Each fact is a property keyed by its path, typed as the JSON serializer reads it
+//! 2. Facts declared with a type are required, facts with a value carry a default
+//! 3. Facts of referenced documents are included, with overrides as their default
+//! 4. Unit types list their units; money lists only the document currency if set
+//! 5. Unknown documents are an error
+
+use lemma::Engine;
+use serde_json::json;
+
+const CODE: &str = r#"
+doc product
+fact price = [money]
+fact weight = 2 kilograms
+
+doc order
+"""
+An order of one product
+"""
+currency EUR
+fact quantity = [number]
+fact express = false
+fact discount = 10%
+fact ordered = [date]
+fact note = "none"
+fact item = doc product
+fact item.weight = 3 kilograms
+rule total = quantity * item.price
+"#;
+
+fn schema() -> serde_json::Value {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    engine.fact_schema("order").unwrap()
+}
+
+#[test]
+fn test_properties_and_types() {
+    let schema = schema();
+    assert_eq!(schema["type"], "object");
+    assert_eq!(schema["title"], "order");
+    assert_eq!(schema["description"], "An order of one product");
+    assert_eq!(schema["additionalProperties"], false);
+
+    let properties = &schema["properties"];
+    assert_eq!(properties["quantity"]["type"], "number");
+    assert_eq!(properties["express"]["type"], "boolean");
+    assert_eq!(properties["discount"]["type"], "number");
+    assert_eq!(properties["discount"]["x-lemma-type"], "percentage");
+    assert_eq!(properties["ordered"]["type"], "string");
+    assert_eq!(properties["note"]["type"], "string");
+    assert!(properties.get("item").is_none());
+}
+
+#[test]
+fn test_required_and_defaults() {
+    let schema = schema();
+    let properties = &schema["properties"];
+    assert_eq!(
+        schema["required"],
+        json!(["quantity", "ordered", "item.price"])
+    );
+    assert_eq!(properties["express"]["default"], false);
+    assert_eq!(properties["discount"]["default"], json!(0.1));
+    assert_eq!(properties["note"]["default"], "none");
+    assert!(properties["quantity"].get("default").is_none());
+}
+
+#[test]
+fn test_referenced_document_facts() {
+    let schema = schema();
+    let properties = &schema["properties"];
+    assert_eq!(properties["item.price"]["x-lemma-type"], "money");
+    assert_eq!(properties["item.weight"]["x-lemma-type"], "mass");
+    assert_eq!(properties["item.weight"]["default"], "3 kilogram");
+    assert!(properties["item.weight"]["x-lemma-units"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("kilogram")));
+}
+
+#[test]
+fn test_money_units_follow_currency() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc priced\ncurrency EUR\nfact net = [money]\n",
+            "priced.lemma",
+        )
+        .unwrap();
+    let schema = engine.fact_schema("priced").unwrap();
+    assert_eq!(schema["properties"]["net"]["x-lemma-units"], json!(["EUR"]));
+
+    // Without a directive every supported currency is listed
+    let properties = &self::schema()["properties"];
+    let units = properties["item.price"]["x-lemma-units"]
+        .as_array()
+        .unwrap();
+    assert!(units.len() > 1);
+}
+
+#[test]
+fn test_unknown_document() {
+    let engine = Engine::new();
+    assert!(engine.fact_schema("missing").is_err());
+}
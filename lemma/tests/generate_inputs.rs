@@ -0,0 +1,160 @@
+//! Random fact sets for a rule target with `Engine::generate_inputs`
+//!
+//! Key behaviors:
+//! 1. Every generated fact set meets the target
+//! 2. With `violating`, every fact set misses it
+//! 3. Facts no domain constrains still get a value of their type
+//! 4. The same seed gives the same fact sets, another seed others
+//! 5. Veto targets and unknown rules are handled
+//! 6. Falling short of the requested number of fact sets is an error
+
+use lemma::{Engine, GenerateOptions, LemmaFact, LiteralValue, Target};
+
+const CODE: &str = r#"
+doc loan
+fact age = [number]
+fact income = [money]
+fact name = [text]
+rule eligible = age >= 18 and age <= 65 and income > 30000 EUR
+rule greeting = name
+rule approved = true
+  unless age < 18 then veto "too young"
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "loan.lemma").unwrap();
+    engine
+}
+
+fn outcome(
+    engine: &Engine,
+    rule: &str,
+    facts: &[LemmaFact],
+) -> (Option<LiteralValue>, Option<String>) {
+    let response = engine
+        .evaluate("loan", Some(vec![rule.to_string()]), Some(facts.to_vec()))
+        .unwrap();
+    let result = response.results.into_iter().next().unwrap();
+    assert!(result.missing_facts.is_none(), "{:?}", facts);
+    (result.result, result.veto_message)
+}
+
+#[test]
+fn test_inputs_meet_the_target() {
+    let engine = engine();
+    let inputs = engine
+        .generate_inputs(
+            "loan",
+            "eligible",
+            Target::value(LiteralValue::Boolean(true)),
+            25,
+        )
+        .unwrap();
+    assert_eq!(inputs.len(), 25);
+    for facts in &inputs {
+        assert_eq!(
+            outcome(&engine, "eligible", facts).0,
+            Some(LiteralValue::Boolean(true))
+        );
+    }
+}
+
+#[test]
+fn test_violating_inputs_miss_the_target() {
+    let engine = engine();
+    let options = GenerateOptions::default().with_violating(true);
+    let inputs = engine
+        .generate_inputs_with_options(
+            "loan",
+            "eligible",
+            Target::value(LiteralValue::Boolean(true)),
+            25,
+            &options,
+        )
+        .unwrap();
+    assert!(!inputs.is_empty());
+    for facts in &inputs {
+        assert_ne!(
+            outcome(&engine, "eligible", facts).0,
+            Some(LiteralValue::Boolean(true))
+        );
+    }
+}
+
+#[test]
+fn test_unconstrained_facts_get_values() {
+    let engine = engine();
+    let inputs = engine
+        .generate_inputs("loan", "greeting", Target::any_value(), 5)
+        .unwrap();
+    assert_eq!(inputs.len(), 5);
+    for facts in &inputs {
+        assert!(matches!(
+            outcome(&engine, "greeting", facts).0,
+            Some(LiteralValue::Text(_))
+        ));
+    }
+}
+
+#[test]
+fn test_seed_makes_inputs_reproducible() {
+    let engine = engine();
+    let generate = |seed| {
+        let options = GenerateOptions::default().with_seed(seed);
+        engine
+            .generate_inputs_with_options(
+                "loan",
+                "eligible",
+                Target::value(LiteralValue::Boolean(true)),
+                10,
+                &options,
+            )
+            .unwrap()
+    };
+    assert_eq!(generate(7), generate(7));
+    assert_ne!(generate(7), generate(8));
+}
+
+#[test]
+fn test_veto_targets_and_errors() {
+    let engine = engine();
+    let inputs = engine
+        .generate_inputs(
+            "loan",
+            "approved",
+            Target::veto(Some("too young".to_string())),
+            10,
+        )
+        .unwrap();
+    assert_eq!(inputs.len(), 10);
+    for facts in &inputs {
+        assert_eq!(
+            outcome(&engine, "approved", facts).1.as_deref(),
+            Some("too young")
+        );
+    }
+
+    assert!(engine
+        .generate_inputs("loan", "missing", Target::any_value(), 1)
+        .is_err());
+    assert!(engine
+        .generate_inputs("loan", "eligible", Target::any_value(), 1_000_000)
+        .is_err());
+}
+
+#[test]
+fn test_too_few_inputs_is_an_error() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc draws\nfact pattern = [regex]\nrule shown = pattern",
+            "draws.lemma",
+        )
+        .unwrap();
+    // No value can be drawn for a regex fact
+    let error = engine
+        .generate_inputs("draws", "shown", Target::any_value(), 3)
+        .unwrap_err();
+    assert!(error.to_string().contains("0 of 3"), "{}", error);
+}
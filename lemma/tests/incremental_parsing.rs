@@ -0,0 +1,167 @@
+//! Incremental re-parsing with `Engine::update_document_range`
+//!
+//! Key behaviors:
+//! 1. An edit inside a document re-parses it and keeps the other documents
+//! 2. Documents below an edit have their spans moved as if the file was parsed again
+//! 3. Adding or removing a `doc` line re-parses the whole source
+//! 4. A failed edit leaves the engine unchanged and reports lines of the whole file
+
+mod common;
+
+use common::value;
+use lemma::{Engine, LemmaDoc, LemmaError, Span};
+
+const CODE: &str = r#"doc pricing
+fact price = 100
+rule total = price * 2
+
+doc shipping
+fact weight = 5
+rule cost = weight * 3
+  unless weight > 10 then 50
+
+doc summary
+fact pricing = doc pricing
+rule total = pricing.total? + 1
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+fn spans(doc: &LemmaDoc) -> Vec<Option<Span>> {
+    let mut spans: Vec<Option<Span>> = doc.facts.iter().map(|f| f.span.clone()).collect();
+    for rule in &doc.rules {
+        spans.push(rule.span.clone());
+        spans.push(rule.expression.span.clone());
+        for clause in &rule.unless_clauses {
+            spans.push(clause.span.clone());
+            spans.push(clause.condition.span.clone());
+            spans.push(clause.result.span.clone());
+        }
+    }
+    spans
+}
+
+/// Check every document matches a fresh parse of `code`
+fn assert_matches_full_parse(engine: &Engine, code: &str) {
+    let mut fresh = Engine::new();
+    fresh.add_lemma_code(code, "test.lemma").unwrap();
+
+    let mut names = engine.list_documents();
+    names.sort();
+    let mut fresh_names = fresh.list_documents();
+    fresh_names.sort();
+    assert_eq!(names, fresh_names);
+
+    for name in names {
+        let doc = engine.get_document(&name).unwrap();
+        let fresh_doc = fresh.get_document(&name).unwrap();
+        assert_eq!(
+            doc.start_line, fresh_doc.start_line,
+            "start line of {}",
+            name
+        );
+        assert_eq!(spans(doc), spans(fresh_doc), "spans of {}", name);
+        assert_eq!(doc.to_string(), fresh_doc.to_string());
+    }
+}
+
+#[test]
+fn test_edit_rule_in_place() {
+    let mut engine = engine();
+    engine
+        .update_document_range("test.lemma", 7..8, "rule cost = weight * 4\n")
+        .unwrap();
+
+    assert_eq!(value(&engine, "shipping", &[], "cost"), "20");
+    assert_eq!(value(&engine, "summary", &[], "total"), "201");
+    assert_matches_full_parse(&engine, &CODE.replace("weight * 3", "weight * 4"));
+}
+
+#[test]
+fn test_inserted_lines_move_documents_below() {
+    let mut engine = engine();
+    engine
+        .update_document_range(
+            "test.lemma",
+            3..3,
+            "fact discount = 10\nrule discounted = price - discount\n",
+        )
+        .unwrap();
+
+    assert_eq!(value(&engine, "pricing", &[], "discounted"), "90");
+    assert_eq!(engine.get_document("shipping").unwrap().start_line, 7);
+    assert_eq!(engine.get_document("summary").unwrap().start_line, 12);
+
+    let code = CODE.replace(
+        "fact price = 100\n",
+        "fact price = 100\nfact discount = 10\nrule discounted = price - discount\n",
+    );
+    assert_matches_full_parse(&engine, &code);
+}
+
+#[test]
+fn test_deleted_lines_move_documents_below() {
+    let mut engine = engine();
+    engine
+        .update_document_range("test.lemma", 8..9, "")
+        .unwrap();
+
+    assert_eq!(engine.get_document("summary").unwrap().start_line, 9);
+    assert_matches_full_parse(&engine, &CODE.replace("  unless weight > 10 then 50\n", ""));
+}
+
+#[test]
+fn test_adding_doc_line_reparses_source() {
+    let mut engine = engine();
+    engine
+        .update_document_range("test.lemma", 10..10, "doc handling\nrule fee = 2\n\n")
+        .unwrap();
+
+    assert_eq!(value(&engine, "handling", &[], "fee"), "2");
+    let code = CODE.replace("doc summary", "doc handling\nrule fee = 2\n\ndoc summary");
+    assert_matches_full_parse(&engine, &code);
+}
+
+#[test]
+fn test_removing_doc_removes_document() {
+    let mut engine = engine();
+    engine
+        .update_document_range("test.lemma", 5..10, "")
+        .unwrap();
+
+    assert!(engine.get_document("shipping").is_none());
+    assert_eq!(value(&engine, "summary", &[], "total"), "201");
+}
+
+#[test]
+fn test_failed_edit_leaves_engine_unchanged() {
+    let mut engine = engine();
+    let result = engine.update_document_range("test.lemma", 7..8, "rule cost = = 4\n");
+
+    match result {
+        Err(LemmaError::Parse(details)) => assert_eq!(details.span.line, 7),
+        other => panic!("Expected parse error, got {:?}", other),
+    }
+    assert_eq!(value(&engine, "shipping", &[], "cost"), "15");
+
+    // Later edits still apply to the last accepted source
+    engine
+        .update_document_range("test.lemma", 6..7, "fact weight = 20\n")
+        .unwrap();
+    assert_eq!(value(&engine, "shipping", &[], "cost"), "50");
+}
+
+#[test]
+fn test_edit_outside_source_is_rejected() {
+    let mut engine = engine();
+    assert!(engine
+        .update_document_range("test.lemma", 40..41, "")
+        .is_err());
+    assert!(engine
+        .update_document_range("other.lemma", 1..1, "")
+        .is_err());
+}
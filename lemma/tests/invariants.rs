@@ -0,0 +1,172 @@
+//! Invariants: conditions that must hold whatever the facts
+//!
+//! Key behaviors:
+//! 1. An invariant that is false for the facts is an `invariant_violated` diagnostic
+//! 2. Invariants are checked whichever rules are requested, and never show up as results
+//! 3. Invariants that can't be evaluated, e.g. for missing facts, aren't reported
+//! 4. `prove_invariants` proves invariants no facts can make false
+//! 5. Invariants of partial documents are numbered across the parts
+
+use lemma::{DiagnosticKind, Engine, LemmaFact, ProofOutcome};
+
+const CODE: &str = r#"
+doc pricing
+fact price = [money]
+fact quantity = [number]
+rule total = price * quantity
+
+doc shipping
+fact weight = [mass]
+rule fee = 5 EUR
+invariant fee? >= 0 EUR
+
+doc checkout
+fact pricing = doc pricing
+fact shipping = doc shipping
+rule total = pricing.total? + shipping.fee?
+invariant pricing.total? >= 0 EUR
+invariant total? < 10000 EUR
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shop.lemma").unwrap();
+    engine
+}
+
+fn facts(engine: &Engine, price: &str) -> Vec<LemmaFact> {
+    engine
+        .parse_facts(
+            "checkout",
+            &[&format!("pricing.price={}", price), "pricing.quantity=2"],
+        )
+        .unwrap()
+}
+
+fn violations(response: &lemma::Response) -> Vec<String> {
+    response
+        .diagnostics
+        .iter()
+        .filter(|d| d.kind == DiagnosticKind::InvariantViolated)
+        .map(|d| d.message.clone())
+        .collect()
+}
+
+#[test]
+fn test_violated_invariant_is_a_diagnostic() {
+    let engine = engine();
+    let response = engine
+        .evaluate("checkout", None, Some(facts(&engine, "-3 EUR")))
+        .unwrap();
+
+    assert_eq!(
+        violations(&response),
+        vec!["Invariant 'pricing.total? >= 0 EUR' does not hold"]
+    );
+    let diagnostic = response
+        .diagnostics
+        .iter()
+        .find(|d| d.kind == DiagnosticKind::InvariantViolated)
+        .unwrap();
+    assert_eq!(diagnostic.rule.as_deref(), Some("invariant.1"));
+
+    let names: Vec<&str> = response
+        .results
+        .iter()
+        .map(|r| r.rule_name.as_str())
+        .collect();
+    assert_eq!(names, vec!["total"]);
+}
+
+#[test]
+fn test_invariants_hold() {
+    let engine = engine();
+    let response = engine
+        .evaluate("checkout", None, Some(facts(&engine, "20 EUR")))
+        .unwrap();
+    assert!(violations(&response).is_empty());
+}
+
+#[test]
+fn test_invariants_checked_for_requested_rules() {
+    let engine = engine();
+    let response = engine
+        .evaluate(
+            "checkout",
+            Some(vec!["total".to_string()]),
+            Some(facts(&engine, "6000 EUR")),
+        )
+        .unwrap();
+    assert_eq!(
+        violations(&response),
+        vec!["Invariant 'total? < 10000 EUR' does not hold"]
+    );
+    assert_eq!(response.results.len(), 1);
+}
+
+#[test]
+fn test_missing_facts_are_not_violations() {
+    let response = engine().evaluate("checkout", None, None).unwrap();
+    assert!(violations(&response).is_empty());
+    assert!(response
+        .results
+        .iter()
+        .all(|r| !r.rule_name.starts_with("invariant")));
+}
+
+#[test]
+fn test_prove_invariants() {
+    let proofs = engine().prove_invariants();
+    let found: Vec<(&str, &str)> = proofs
+        .iter()
+        .map(|p| (p.doc_name.as_str(), p.invariant.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("checkout", "pricing.total? >= 0 EUR"),
+            ("checkout", "total? < 10000 EUR"),
+            ("shipping", "fee? >= 0 EUR"),
+        ]
+    );
+
+    // A negative price makes the total negative
+    assert_ne!(proofs[0].outcome, ProofOutcome::Proven);
+    assert_eq!(proofs[2].outcome, ProofOutcome::Proven);
+}
+
+#[test]
+fn test_invariants_are_written_back() {
+    let engine = engine();
+    let code = engine.get_document("checkout").unwrap().to_string();
+    assert!(code.contains("invariant pricing.total? >= 0 EUR\n"));
+    assert!(!code.contains("rule invariant"));
+}
+
+#[test]
+fn test_partial_document_invariants_are_numbered_across_parts() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "partial doc limits\nfact x = [number]\ninvariant x >= 0",
+            "a.lemma",
+        )
+        .unwrap();
+    engine
+        .add_lemma_code("partial doc limits\ninvariant x < 100", "b.lemma")
+        .unwrap();
+
+    let doc = engine.get_document("limits").unwrap();
+    let rules: Vec<String> = doc.invariants.iter().map(|i| i.rule_name()).collect();
+    assert_eq!(rules, vec!["invariant.1", "invariant.2"]);
+
+    let facts = engine.parse_facts("limits", &["x=200"]).unwrap();
+    let response = engine.evaluate("limits", None, Some(facts)).unwrap();
+    let violated: Vec<Option<&str>> = response
+        .diagnostics
+        .iter()
+        .filter(|d| d.kind == DiagnosticKind::InvariantViolated)
+        .map(|d| d.rule.as_deref())
+        .collect();
+    assert_eq!(violated, vec![Some("invariant.2")]);
+}
@@ -27,6 +27,7 @@ fn display_equation() {
         vec![ShapeBranch {
             condition: expr_lit(lit_bool(true)),
             outcome: BranchOutcome::Value(expr_lit(lit_num(42))),
+            origins: vec![],
         }],
         vec![],
     );
@@ -58,6 +59,7 @@ fn serialize_equation() {
         vec![ShapeBranch {
             condition: expr_lit(lit_bool(true)),
             outcome: BranchOutcome::Value(expr_lit(lit_num(7))),
+            origins: vec![],
         }],
         vec![FactReference {
             reference: vec!["doc".to_string(), "y".to_string()],
@@ -0,0 +1,165 @@
+//! Inversion with targets on intermediate rules
+//!
+//! Key behaviors:
+//! 1. `Target::with_rule_target` constrains a rule referenced by the inverted rule
+//! 2. The constraint narrows the domains of the facts behind the referenced rule
+//! 3. Rule targets work for rules in referenced documents (`risk.score?`)
+//! 4. A rule target that cannot be met is an error, the one of inverting the referenced rule
+//! 5. Facts of the referenced document are named and given through the reference (`risk.points`)
+
+use lemma::inversion::domain_ops::value_within;
+use lemma::{Domain, Engine, FactReference, LiteralValue, OperationResult, Target, TargetOp};
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc risk
+fact points = [number]
+rule level = "low"
+  unless points > 40 then "medium"
+  unless points > 80 then "high"
+
+doc order
+fact amount = [number]
+fact risk = doc risk
+rule approved = amount <= 1000
+  unless risk.level? is "high" then veto "too risky"
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+fn text(value: &str) -> LiteralValue {
+    LiteralValue::Text(value.to_string())
+}
+
+fn number(n: i64) -> LiteralValue {
+    LiteralValue::Number(n.into())
+}
+
+fn contains(domain: &Domain, value: &LiteralValue) -> bool {
+    match domain {
+        Domain::Range { min, max } => value_within(value, min, max),
+        Domain::Union(parts) => parts.iter().any(|d| contains(d, value)),
+        Domain::Enumeration(values) => values.contains(value),
+        Domain::Complement(inner) => !contains(inner, value),
+        Domain::Unconstrained => true,
+    }
+}
+
+/// Whether some solution allows `fact = value`, with `fact` named from the inverted document
+fn allows(solutions: &[HashMap<FactReference, Domain>], fact: &str, value: i64) -> bool {
+    solutions.iter().any(|solution| {
+        solution
+            .iter()
+            .find(|(reference, _)| reference.reference.join(".") == fact)
+            .is_none_or(|(_, domain)| contains(domain, &number(value)))
+    })
+}
+
+#[test]
+fn test_without_rule_target() {
+    let engine = engine();
+    let solutions = engine
+        .invert("order", "approved", Target::any_value(), HashMap::new())
+        .unwrap();
+
+    assert!(allows(&solutions, "risk.points", 60));
+}
+
+#[test]
+fn test_rule_target_narrows_domain() {
+    let engine = engine();
+    let target = Target::any_value().with_rule_target("risk.level?", Target::value(text("low")));
+    let solutions = engine
+        .invert("order", "approved", target, HashMap::new())
+        .unwrap();
+
+    assert!(allows(&solutions, "risk.points", 20));
+    assert!(allows(&solutions, "risk.points", 40));
+    assert!(!allows(&solutions, "risk.points", 60));
+    assert!(!allows(&solutions, "risk.points", 90));
+}
+
+#[test]
+fn test_rule_target_with_operator() {
+    let engine = engine();
+    let target = Target::any_value().with_rule_target(
+        "risk.level",
+        Target::with_op(TargetOp::Neq, OperationResult::Value(text("low"))),
+    );
+    let solutions = engine
+        .invert("order", "approved", target, HashMap::new())
+        .unwrap();
+
+    assert!(!allows(&solutions, "risk.points", 20));
+    assert!(allows(&solutions, "risk.points", 60));
+}
+
+#[test]
+fn test_rule_target_in_same_document() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            r#"
+doc pricing
+fact quantity = [number]
+rule tier = "standard"
+  unless quantity >= 100 then "bulk"
+rule price = 10
+  unless tier? is "bulk" then 8
+"#,
+            "test.lemma",
+        )
+        .unwrap();
+
+    let target = Target::any_value().with_rule_target("tier", Target::value(text("bulk")));
+    let solutions = engine
+        .invert("pricing", "price", target, HashMap::new())
+        .unwrap();
+
+    assert!(allows(&solutions, "quantity", 150));
+    assert!(!allows(&solutions, "quantity", 50));
+}
+
+#[test]
+fn test_unreachable_rule_target_is_error() {
+    let engine = engine();
+    let target = Target::value(LiteralValue::Boolean(true))
+        .with_rule_target("risk.level", Target::any_veto());
+
+    let error = engine
+        .invert("order", "approved", target, HashMap::new())
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("for target = any veto"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_with_rule_target_parses_reference() {
+    let target = Target::any_value().with_rule_target(" risk.score? ", Target::any_veto());
+    assert_eq!(target.rule_targets[0].rule, vec!["risk", "score"]);
+}
+
+#[test]
+fn test_rule_target_uses_given_facts_of_the_referenced_document() {
+    let engine = engine();
+    let target = Target::any_value().with_rule_target("risk.level?", Target::value(text("low")));
+    let given = HashMap::from([("risk.points".to_string(), number(90))]);
+
+    let error = engine
+        .invert("order", "approved", target, given)
+        .unwrap_err();
+    let message = error.to_string();
+    assert!(
+        message.contains("for target = value \"low\""),
+        "{}",
+        message
+    );
+    assert!(message.contains("1: value \"high\""), "{}", message);
+}
@@ -0,0 +1,180 @@
+//! Inversion targets in other units than the rule's result
+//!
+//! Key behaviors:
+//! 1. A target in another unit of the same kind is converted to the rule's unit
+//! 2. A plain number is read in the rule's unit, including percentages
+//! 3. A target that can't be compared with the result is an error
+//! 4. Target values parse as Lemma literals with `parse_literal`
+//! 5. Percentages and plain numbers compare by their number of percent, also
+//!    for percentage facts
+
+use lemma::inversion::domain_ops::value_within;
+use lemma::{
+    parse_literal, Domain, Engine, FactReference, LiteralValue, OperationResult, Target, TargetOp,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc shipping
+fact express = [boolean]
+fact member = [boolean]
+rule billed_weight = 2 kilograms
+  unless express then 5 kilograms
+rule discount = 5%
+  unless member then 15%
+rule loyalty_bonus = 0
+  unless member then 15
+
+doc quote
+fact rate = [percentage]
+rule applied_rate = rate
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+fn contains(domain: &Domain, value: &LiteralValue) -> bool {
+    match domain {
+        Domain::Range { min, max } => value_within(value, min, max),
+        Domain::Union(parts) => parts.iter().any(|d| contains(d, value)),
+        Domain::Enumeration(values) => values.contains(value),
+        Domain::Complement(inner) => !contains(inner, value),
+        Domain::Unconstrained => true,
+    }
+}
+
+/// Whether some solution allows `fact = value`
+fn allows(solutions: &[HashMap<FactReference, Domain>], fact: &str, value: bool) -> bool {
+    solutions.iter().any(|solution| {
+        solution
+            .iter()
+            .find(|(reference, _)| reference.reference.last().map(String::as_str) == Some(fact))
+            .is_none_or(|(_, domain)| contains(domain, &LiteralValue::Boolean(value)))
+    })
+}
+
+fn at_least(value: &str) -> Target {
+    Target::with_op(
+        TargetOp::Gte,
+        OperationResult::Value(parse_literal(value).unwrap()),
+    )
+}
+
+#[test]
+fn test_target_in_other_unit_is_converted() {
+    let engine = engine();
+    let solutions = engine
+        .invert(
+            "shipping",
+            "billed_weight",
+            at_least("4000 grams"),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    assert!(allows(&solutions, "express", true));
+    assert!(!allows(&solutions, "express", false));
+}
+
+#[test]
+fn test_plain_number_uses_rule_unit() {
+    let engine = engine();
+    let solutions = engine
+        .invert("shipping", "billed_weight", at_least("4"), HashMap::new())
+        .unwrap();
+    assert!(allows(&solutions, "express", true));
+    assert!(!allows(&solutions, "express", false));
+
+    let solutions = engine
+        .invert("shipping", "discount", at_least("10"), HashMap::new())
+        .unwrap();
+    assert!(allows(&solutions, "member", true));
+    assert!(!allows(&solutions, "member", false));
+}
+
+#[test]
+fn test_percentage_target() {
+    let engine = engine();
+    let solutions = engine
+        .invert("shipping", "discount", at_least("10%"), HashMap::new())
+        .unwrap();
+    assert!(allows(&solutions, "member", true));
+    assert!(!allows(&solutions, "member", false));
+}
+
+#[test]
+fn test_percentage_target_on_plain_numbers() {
+    let engine = engine();
+    for rule in ["discount", "loyalty_bonus"] {
+        for target in ["10%", "10"] {
+            let solutions = engine
+                .invert("shipping", rule, at_least(target), HashMap::new())
+                .unwrap();
+            assert!(allows(&solutions, "member", true), "{} {}", rule, target);
+            assert!(!allows(&solutions, "member", false), "{} {}", rule, target);
+        }
+    }
+}
+
+#[test]
+fn test_plain_number_on_percentage_fact() {
+    let engine = engine();
+    let solutions = engine
+        .invert("quote", "applied_rate", at_least("10"), HashMap::new())
+        .unwrap();
+    let rate = |value: i64| {
+        solutions.iter().any(|solution| {
+            solution
+                .values()
+                .any(|domain| contains(domain, &LiteralValue::Percentage(Decimal::from(value))))
+        })
+    };
+    assert!(rate(10));
+    assert!(rate(25));
+    assert!(!rate(5));
+}
+
+#[test]
+fn test_incompatible_target_unit_is_rejected() {
+    let engine = engine();
+    let error = engine
+        .invert(
+            "shipping",
+            "billed_weight",
+            at_least("10 EUR"),
+            HashMap::new(),
+        )
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("cannot be compared"),
+        "{}",
+        error
+    );
+
+    assert!(engine
+        .invert(
+            "shipping",
+            "discount",
+            at_least("3 kilograms"),
+            HashMap::new()
+        )
+        .is_err());
+}
+
+#[test]
+fn test_parse_literal() {
+    assert_eq!(
+        parse_literal(" 10% ").unwrap(),
+        LiteralValue::Percentage(Decimal::from(10))
+    );
+    assert_eq!(parse_literal("true").unwrap(), LiteralValue::Boolean(true));
+    assert!(matches!(
+        parse_literal("100 EUR").unwrap(),
+        LiteralValue::Unit(_)
+    ));
+    assert!(parse_literal("100 EUR extra").is_err());
+}
@@ -0,0 +1,100 @@
+//! Inverting rules that compare text facts with `is` and `is not`
+//!
+//! Key behaviors:
+//! 1. The domain of such a text fact is a flat enumeration of allowed values
+//! 2. Negations give the complement of an enumeration: the disallowed values
+//! 3. `is not` comparisons invert too
+
+use lemma::{Domain, Engine, LiteralValue, Target};
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc membership
+fact tier = [text]
+rule discount = 0
+  unless tier is "silver" then 10
+  unless tier is "gold" or tier is "platinum" then 20
+
+doc shipping
+fact country = [text]
+rule rate = 5
+  unless country is not "NL" then 15
+"#;
+
+fn text(value: &str) -> LiteralValue {
+    LiteralValue::Text(value.to_string())
+}
+
+/// The domains of `fact` in the solutions for `rule` = `value`
+fn domains(doc: &str, rule: &str, value: i64, fact: &str) -> Vec<Domain> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    let solutions = engine
+        .invert(
+            doc,
+            rule,
+            Target::value(LiteralValue::Number(value.into())),
+            HashMap::new(),
+        )
+        .unwrap();
+    solutions
+        .into_iter()
+        .filter_map(|solution| {
+            solution.into_iter().find_map(|(reference, domain)| {
+                (reference.reference.last().map(String::as_str) == Some(fact)).then_some(domain)
+            })
+        })
+        .collect()
+}
+
+fn sorted(values: &[LiteralValue]) -> Vec<String> {
+    let mut values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+    values.sort();
+    values
+}
+
+#[test]
+fn test_allowed_values_are_enumerated() {
+    let domains = domains("membership", "discount", 20, "tier");
+    assert_eq!(domains.len(), 1);
+    let Domain::Enumeration(values) = &domains[0] else {
+        panic!("expected an enumeration, got {}", domains[0]);
+    };
+    assert_eq!(sorted(values), sorted(&[text("gold"), text("platinum")]));
+
+    let domains = self::domains("membership", "discount", 10, "tier");
+    assert_eq!(domains, vec![Domain::Enumeration(vec![text("silver")])]);
+}
+
+#[test]
+fn test_negations_are_disallowed_values() {
+    let domains = domains("membership", "discount", 0, "tier");
+    assert_eq!(domains.len(), 1);
+    let Domain::Complement(inner) = &domains[0] else {
+        panic!("expected a complement, got {}", domains[0]);
+    };
+    let Domain::Enumeration(values) = inner.as_ref() else {
+        panic!(
+            "expected a complement of an enumeration, got {}",
+            domains[0]
+        );
+    };
+    assert_eq!(
+        sorted(values),
+        sorted(&[text("gold"), text("platinum"), text("silver")])
+    );
+}
+
+#[test]
+fn test_is_not_inverts() {
+    assert_eq!(
+        domains("shipping", "rate", 15, "country"),
+        vec![Domain::Complement(Box::new(Domain::Enumeration(vec![
+            text("NL")
+        ])))]
+    );
+    assert_eq!(
+        domains("shipping", "rate", 5, "country"),
+        vec![Domain::Enumeration(vec![text("NL")])]
+    );
+}
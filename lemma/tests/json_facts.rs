@@ -0,0 +1,169 @@
+//! Fact overrides given as a JSON object, `Engine::parse_json_facts`
+//!
+//! Key behaviors:
+//! 1. Values are converted to the declared types without Lemma syntax
+//! 2. Money and units can be objects with an amount and a currency or unit
+//! 3. Values that don't fit the declared type are field errors
+//! 4. Inline code and several documents take JSON facts too
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, EvaluationOptions, LemmaError, LemmaType, LiteralValue};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc order
+fact quantity = [number]
+fact price = [money]
+fact weight = [mass]
+fact discount = [percentage]
+fact express = [boolean]
+fact note = [text]
+rule total = quantity * price
+rule shipping = (weight in kilograms) * 2
+"#;
+
+fn facts(value: Value) -> HashMap<String, Value> {
+    serde_json::from_value(value).unwrap()
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    engine
+}
+
+fn value_of(name: &str, facts: &[lemma::LemmaFact]) -> LiteralValue {
+    facts
+        .iter()
+        .find(|fact| fact.fact_type == lemma::FactType::Local(name.to_string()))
+        .and_then(|fact| match &fact.value {
+            lemma::FactValue::Literal(value) => Some(value.clone()),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("no {} in {:?}", name, facts))
+}
+
+#[test]
+fn test_values_take_declared_types() {
+    let engine = engine();
+    let parsed = engine
+        .parse_json_facts(
+            "order",
+            &facts(json!({
+                "quantity": 5,
+                "price": {"amount": 100, "currency": "USD"},
+                "weight": {"value": 2.5, "unit": "kilogram"},
+                "discount": 0.21,
+                "express": true,
+                "note": "100 USD"
+            })),
+        )
+        .unwrap();
+
+    assert_eq!(
+        value_of("quantity", &parsed),
+        LiteralValue::Number(Decimal::from(5))
+    );
+    assert_eq!(value_of("price", &parsed).to_type(), LemmaType::Money);
+    assert_eq!(value_of("weight", &parsed).to_type(), LemmaType::Mass);
+    assert_eq!(
+        value_of("discount", &parsed),
+        LiteralValue::Percentage(Decimal::from(21))
+    );
+    assert_eq!(value_of("express", &parsed), LiteralValue::Boolean(true));
+    assert_eq!(
+        value_of("note", &parsed),
+        LiteralValue::Text("100 USD".to_string())
+    );
+
+    let response = engine.evaluate("order", None, Some(parsed)).unwrap();
+    assert_eq!(
+        rule_result(&response, "total")
+            .result
+            .as_ref()
+            .map(LiteralValue::to_type),
+        Some(LemmaType::Money)
+    );
+}
+
+#[test]
+fn test_strings_are_parsed_as_literals() {
+    let engine = engine();
+    let parsed = engine
+        .parse_json_facts(
+            "order",
+            &facts(json!({"price": "100 USD", "weight": "3 kilograms"})),
+        )
+        .unwrap();
+    assert_eq!(value_of("price", &parsed).to_type(), LemmaType::Money);
+    assert_eq!(value_of("weight", &parsed).to_type(), LemmaType::Mass);
+}
+
+#[test]
+fn test_mismatched_values_are_field_errors() {
+    let engine = engine();
+    let result = engine.parse_json_facts(
+        "order",
+        &facts(json!({
+            "quantity": "many",
+            "weight": {"amount": 2, "currency": "USD"},
+            "price": {"amount": 100}
+        })),
+    );
+    let Err(LemmaError::FactParse(errors)) = result else {
+        panic!("expected fact errors, got {:?}", result);
+    };
+    let mut failed: Vec<_> = errors
+        .iter()
+        .map(|error| (error.fact.clone().unwrap(), error.declared_type.clone()))
+        .collect();
+    failed.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        failed,
+        vec![
+            ("price".to_string(), Some(LemmaType::Money)),
+            ("quantity".to_string(), Some(LemmaType::Number)),
+            ("weight".to_string(), Some(LemmaType::Mass)),
+        ]
+    );
+}
+
+#[test]
+fn test_inline_code_and_many_documents() {
+    let engine = Engine::new();
+    let response = engine
+        .evaluate_code_with_json_facts(
+            CODE,
+            None,
+            Some(vec!["shipping".to_string()]),
+            &facts(json!({"weight": {"value": 3, "unit": "kilogram"}})),
+            &EvaluationOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(6)))
+    );
+
+    let mut engine = self::engine();
+    engine
+        .add_lemma_code(
+            "doc stock\nfact quantity = [number]\nrule low = quantity < 10",
+            "stock.lemma",
+        )
+        .unwrap();
+    let parsed = engine
+        .parse_json_facts_many(&["stock", "order"], &facts(json!({"quantity": 4})))
+        .unwrap();
+    let responses = engine
+        .evaluate_many(&["stock", "order"], Some(parsed))
+        .unwrap();
+    assert_eq!(
+        responses["stock"].results[0].result,
+        Some(LiteralValue::Boolean(true))
+    );
+}
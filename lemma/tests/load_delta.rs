@@ -0,0 +1,89 @@
+//! Idempotent `add_lemma_code` reporting what changed
+//!
+//! Key behaviors:
+//! 1. Adding code a source is already loaded with changes nothing
+//! 2. New documents are reported as added, changed ones as updated
+//! 3. Documents referencing an updated document are updated too
+//! 4. Layout-only changes don't update documents
+//! 5. Template copies no longer referenced are reported as removed
+//! 6. Code is loaded again once a document it declared was removed
+
+use lemma::{Engine, LoadDelta};
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+const PRICING: &str = r#"
+doc pricing
+fact price = 10
+rule total = price
+
+doc order
+fact pricing = doc pricing
+rule amount = pricing.total?
+"#;
+
+#[test]
+fn test_adding_the_same_code_again_changes_nothing() {
+    let mut engine = Engine::new();
+    let delta = engine.add_lemma_code(PRICING, "pricing.lemma").unwrap();
+    assert_eq!(delta.added, names(&["order", "pricing"]));
+    assert!(delta.updated.is_empty());
+
+    let delta = engine.add_lemma_code(PRICING, "pricing.lemma").unwrap();
+    assert!(delta.is_empty());
+    assert_eq!(delta, LoadDelta::default());
+}
+
+#[test]
+fn test_changed_documents_are_updated() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(PRICING, "pricing.lemma").unwrap();
+
+    let changed = PRICING.replace("fact price = 10", "fact price = 12");
+    let delta = engine.add_lemma_code(&changed, "pricing.lemma").unwrap();
+    assert!(delta.added.is_empty());
+    assert_eq!(delta.updated, names(&["order", "pricing"]));
+
+    let changed = changed.replace(
+        "rule amount = pricing.total?",
+        "rule amount = pricing.total? * 2",
+    );
+    let delta = engine.add_lemma_code(&changed, "pricing.lemma").unwrap();
+    assert_eq!(delta.updated, names(&["order"]));
+
+    let relayout = changed.replace("\ndoc order", "\n\n\ndoc order");
+    let delta = engine.add_lemma_code(&relayout, "pricing.lemma").unwrap();
+    assert!(delta.is_empty(), "{:?}", delta);
+}
+
+#[test]
+fn test_unreferenced_template_copies_are_removed() {
+    let template = "doc fee(rate)\nfact base = 100\nrule amount = base * rate\n";
+    let mut engine = Engine::new();
+    engine.add_lemma_code(template, "fee.lemma").unwrap();
+
+    let delta = engine
+        .add_lemma_code("doc order\nfact fee = doc fee(2)", "order.lemma")
+        .unwrap();
+    assert_eq!(delta.added, names(&["order"]));
+    assert!(delta.removed.is_empty());
+
+    let delta = engine
+        .add_lemma_code("doc order\nfact fee = doc fee(3)", "order.lemma")
+        .unwrap();
+    assert_eq!(delta.updated, names(&["order"]));
+    assert_eq!(delta.removed, names(&["fee(2)"]));
+}
+
+#[test]
+fn test_removed_documents_are_loaded_again() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(PRICING, "pricing.lemma").unwrap();
+    engine.remove_document("order");
+
+    let delta = engine.add_lemma_code(PRICING, "pricing.lemma").unwrap();
+    assert_eq!(delta.added, names(&["order"]));
+    assert!(engine.get_document("order").is_some());
+}
@@ -0,0 +1,135 @@
+//! Loading a directory with `Engine::load_dir`
+//!
+//! Key behaviors:
+//! 1. Every `.lemma` file below the directory is loaded, other files are ignored
+//! 2. Documents may reference documents in other files
+//! 3. Parse errors are reported in file path order and leave the engine unchanged
+//! 4. Progress is reported once per file
+
+use lemma::{Engine, LemmaError};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A fresh directory under the system temp dir, filled with `files`
+fn workspace(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lemma_load_dir_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, code) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, code).unwrap();
+    }
+    dir
+}
+
+fn source_of(engine: &Engine, doc: &str) -> String {
+    engine.get_document(doc).unwrap().source.clone().unwrap()
+}
+
+#[test]
+fn test_load_dir_loads_nested_files() {
+    let dir = workspace(
+        "nested",
+        &[
+            (
+                "pricing.lemma",
+                "doc pricing\nfact price = 100\nrule total = price * 2\n",
+            ),
+            (
+                "orders/order.lemma",
+                "doc order\nfact pricing = doc pricing\nrule total = pricing.total? + 1\n",
+            ),
+            ("notes.txt", "doc ignored\n"),
+        ],
+    );
+
+    let mut engine = Engine::new();
+    engine.load_dir(&dir).unwrap();
+
+    let mut documents = engine.list_documents();
+    documents.sort();
+    assert_eq!(documents, vec!["order", "pricing"]);
+    assert!(Path::new(&source_of(&engine, "order")).ends_with("orders/order.lemma"));
+
+    let response = engine.evaluate("order", None, None).unwrap();
+    let total = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "total")
+        .unwrap();
+    assert_eq!(total.result.as_ref().unwrap().to_string(), "201");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_dir_reports_errors_in_path_order() {
+    let mut files = vec![("a_valid.lemma", "doc valid\nfact x = 1\n")];
+    let broken: Vec<(String, String)> = (0..20)
+        .map(|i| {
+            (
+                format!("broken_{:02}.lemma", i),
+                format!("doc broken_{}\nrule = =\n", i),
+            )
+        })
+        .collect();
+    files.extend(broken.iter().map(|(p, c)| (p.as_str(), c.as_str())));
+    let dir = workspace("errors", &files);
+
+    let mut engine = Engine::new();
+    let errors = match engine.load_dir(&dir) {
+        Err(LemmaError::MultipleErrors(errors)) => errors,
+        other => panic!("Expected multiple errors, got {:?}", other),
+    };
+    let sources: Vec<String> = errors
+        .iter()
+        .map(|e| match e {
+            LemmaError::Parse(details) => details.source_id.clone(),
+            other => panic!("Expected parse error, got {:?}", other),
+        })
+        .collect();
+    let mut sorted = sources.clone();
+    sorted.sort();
+    assert_eq!(sources.len(), 20);
+    assert_eq!(sources, sorted);
+    assert!(engine.list_documents().is_empty());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_dir_reports_progress() {
+    let files: Vec<(String, String)> = (0..10)
+        .map(|i| {
+            (
+                format!("doc_{}.lemma", i),
+                format!("doc doc_{}\nfact x = {}\n", i, i),
+            )
+        })
+        .collect();
+    let refs: Vec<(&str, &str)> = files
+        .iter()
+        .map(|(p, c)| (p.as_str(), c.as_str()))
+        .collect();
+    let dir = workspace("progress", &refs);
+
+    let mut engine = Engine::new();
+    let mut reported = Vec::new();
+    engine
+        .load_dir_with_progress(&dir, |progress| {
+            assert_eq!(progress.total, 10);
+            reported.push(progress.parsed);
+        })
+        .unwrap();
+
+    assert_eq!(reported, (1..=10).collect::<Vec<_>>());
+    assert_eq!(engine.list_documents().len(), 10);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_load_dir_missing_directory() {
+    let mut engine = Engine::new();
+    assert!(engine.load_dir("/nonexistent/lemma/workspace").is_err());
+}
@@ -0,0 +1,131 @@
+//! Tiering a value by ranges with `lookup`
+//!
+//! Key behaviors:
+//! 1. A value maps to the result of the first range containing it, bounds included
+//! 2. Open-ended ranges (`65..`, `..0`) and unit bounds are supported
+//! 3. Values outside every range are vetoed, unless the lookup has an `else`
+//! 4. Inverting a lookup gives the exact range of a result
+//! 5. A lookup can't be combined with `last match`
+
+use lemma::inversion::domain_ops::value_within;
+use lemma::{Domain, Engine, FactReference, LiteralValue, OperationResult, Target};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc person
+fact age = [number]
+fact weight = [mass]
+
+rule band = lookup age in (
+  0..17 -> "minor",
+  18..64 -> "adult",
+  65.. -> "senior"
+)
+
+rule size = lookup weight in (..10 kilograms -> "light", 10 kilograms.. -> "heavy")
+
+rule rate = lookup age in (0..17 -> 0%, 18..64 -> 20%) else 10%
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "person.lemma").unwrap();
+    engine
+}
+
+fn evaluate(rule: &str, facts: &[&str]) -> OperationResult {
+    let engine = engine();
+    let facts = engine.parse_facts("person", facts).unwrap();
+    let response = engine
+        .evaluate("person", Some(vec![rule.to_string()]), Some(facts))
+        .unwrap();
+    let result = &response.results[0];
+    match &result.veto_message {
+        Some(message) => OperationResult::Veto(Some(message.clone())),
+        None => OperationResult::Value(result.result.clone().unwrap()),
+    }
+}
+
+fn text(value: &str) -> OperationResult {
+    OperationResult::Value(LiteralValue::Text(value.to_string()))
+}
+
+fn contains(domain: &Domain, value: &LiteralValue) -> bool {
+    match domain {
+        Domain::Range { min, max } => value_within(value, min, max),
+        Domain::Union(parts) => parts.iter().any(|d| contains(d, value)),
+        Domain::Enumeration(values) => values.contains(value),
+        Domain::Complement(inner) => !contains(inner, value),
+        Domain::Unconstrained => true,
+    }
+}
+
+/// Whether some solution allows `age = value`
+fn allows(solutions: &[HashMap<FactReference, Domain>], value: i64) -> bool {
+    let age = LiteralValue::Number(value.into());
+    solutions.iter().any(|solution| {
+        solution
+            .iter()
+            .find(|(reference, _)| reference.reference == ["age"])
+            .is_none_or(|(_, domain)| contains(domain, &age))
+    })
+}
+
+#[test]
+fn test_values_map_to_their_range() {
+    assert_eq!(evaluate("band", &["age=10"]), text("minor"));
+    assert_eq!(evaluate("band", &["age=17"]), text("minor"));
+    assert_eq!(evaluate("band", &["age=18"]), text("adult"));
+    assert_eq!(evaluate("band", &["age=64"]), text("adult"));
+    assert_eq!(evaluate("band", &["age=90"]), text("senior"));
+}
+
+#[test]
+fn test_unit_bounds() {
+    assert_eq!(evaluate("size", &["weight=4 kilograms"]), text("light"));
+    assert_eq!(evaluate("size", &["weight=12000 grams"]), text("heavy"));
+}
+
+#[test]
+fn test_values_outside_every_range() {
+    assert_eq!(
+        evaluate("band", &["age=-1"]),
+        OperationResult::Veto(Some("age is outside the lookup ranges".to_string()))
+    );
+    assert_eq!(
+        evaluate("rate", &["age=70"]),
+        OperationResult::Value(LiteralValue::Percentage(Decimal::from(10)))
+    );
+}
+
+#[test]
+fn test_inverting_a_lookup_gives_the_range() {
+    let engine = engine();
+    let solutions = engine
+        .invert(
+            "person",
+            "band",
+            Target::value(LiteralValue::Text("adult".to_string())),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    assert!(allows(&solutions, 18));
+    assert!(allows(&solutions, 40));
+    assert!(allows(&solutions, 64));
+    assert!(!allows(&solutions, 17));
+    assert!(!allows(&solutions, 65));
+}
+
+#[test]
+fn test_lookup_with_last_match_is_rejected() {
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            "doc d\nfact x = 1\nrule r last match = lookup x in (0..1 -> 1)",
+            "d.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("last match"), "{}", error);
+}
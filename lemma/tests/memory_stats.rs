@@ -0,0 +1,109 @@
+//! Memory introspection and dropping the code of least recently used sources
+//!
+//! Key behaviors:
+//! 1. `Engine::memory_stats` lists every document with its AST size and every retained source
+//! 2. Larger documents report larger AST sizes
+//! 3. With `with_retained_sources`, the least recently used source code is dropped
+//! 4. Evaluating a document counts as using its source
+//! 5. Documents of a dropped source still evaluate; editing it asks to add the code again
+
+use lemma::Engine;
+
+const SMALL: &str = "doc small\nfact x = 1\nrule y = x";
+const LARGE: &str = r#"
+doc large
+"""
+A document with more facts and rules
+"""
+fact price = 100
+fact quantity = [number]
+rule subtotal = price * quantity
+rule discount = 0%
+  unless quantity >= 10 then 5%
+  unless quantity >= 50 then 10%
+  unless quantity >= 100 then veto "order too large"
+rule total = subtotal? - subtotal? * discount?
+"#;
+
+#[test]
+fn test_stats_list_documents_and_sources() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(SMALL, "small.lemma").unwrap();
+    engine.add_lemma_code(LARGE, "large.lemma").unwrap();
+
+    let stats = engine.memory_stats();
+    let names: Vec<&str> = stats.documents.iter().map(|d| d.name.as_str()).collect();
+    assert_eq!(names, vec!["large", "small"]);
+    assert!(stats.documents[0].ast_bytes > stats.documents[1].ast_bytes);
+    assert_eq!(stats.documents[1].source.as_deref(), Some("small.lemma"));
+    assert_eq!(
+        stats.ast_bytes,
+        stats.documents.iter().map(|d| d.ast_bytes).sum::<usize>()
+    );
+
+    let sources: Vec<(&str, usize)> = stats
+        .sources
+        .iter()
+        .map(|s| (s.source.as_str(), s.bytes))
+        .collect();
+    assert_eq!(
+        sources,
+        vec![("large.lemma", LARGE.len()), ("small.lemma", SMALL.len())]
+    );
+    assert_eq!(stats.source_bytes, LARGE.len() + SMALL.len());
+    assert!(stats.evicted_sources.is_empty());
+}
+
+#[test]
+fn test_least_recently_used_source_is_dropped() {
+    let mut engine = Engine::new().with_retained_sources(2);
+    engine
+        .add_lemma_code("doc a\nrule x = 1", "a.lemma")
+        .unwrap();
+    engine
+        .add_lemma_code("doc b\nrule x = 2", "b.lemma")
+        .unwrap();
+    // Using `a` makes `b` the least recently used
+    engine.evaluate("a", None, None).unwrap();
+    engine
+        .add_lemma_code("doc c\nrule x = 3", "c.lemma")
+        .unwrap();
+
+    let stats = engine.memory_stats();
+    let retained: Vec<&str> = stats.sources.iter().map(|s| s.source.as_str()).collect();
+    assert_eq!(retained, vec!["a.lemma", "c.lemma"]);
+    assert_eq!(stats.evicted_sources, vec!["b.lemma".to_string()]);
+    assert_eq!(stats.documents.len(), 3);
+}
+
+#[test]
+fn test_dropped_source_documents_still_work() {
+    let mut engine = Engine::new().with_retained_sources(1);
+    engine
+        .add_lemma_code("doc a\nrule x = 1", "a.lemma")
+        .unwrap();
+    engine
+        .add_lemma_code("doc b\nrule x = 2", "b.lemma")
+        .unwrap();
+
+    let response = engine.evaluate("a", None, None).unwrap();
+    assert_eq!(response.results.len(), 1);
+
+    let error = engine
+        .update_document_range("a.lemma", 2..3, "rule x = 5\n")
+        .unwrap_err();
+    assert!(error.to_string().contains("add it again"), "{}", error);
+
+    // Adding the code again retains it, and drops `b`
+    let delta = engine
+        .add_lemma_code("doc a\nrule x = 1", "a.lemma")
+        .unwrap();
+    assert!(delta.is_empty());
+    assert_eq!(
+        engine.memory_stats().evicted_sources,
+        vec!["b.lemma".to_string()]
+    );
+    engine
+        .update_document_range("a.lemma", 2..3, "rule x = 5\n")
+        .unwrap();
+}
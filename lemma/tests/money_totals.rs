@@ -0,0 +1,143 @@
+//! Totals of money across a document collection
+//!
+//! Key behaviors:
+//! 1. `total of items.price` adds a money fact of every instance
+//! 2. `total of items.rule?` adds a money rule of every instance
+//! 3. Mixing currencies is an error
+//! 4. An empty collection totals zero in the document currency, or is vetoed without one
+//! 5. The trace has an entry per instance
+//! 6. Totalling a fact that isn't money is a semantic error
+
+mod common;
+
+use common::{result, value};
+use lemma::{parse_facts, Engine, EvaluationOptions, OperationRecord, TraceLevel};
+
+const CODE: &str = r#"
+doc line_item
+fact quantity = 1
+fact price = 10 EUR
+rule subtotal = quantity * price
+
+doc basket
+fact items = many doc line_item
+fact items[0].quantity = 2
+fact items[1].price = 5 EUR
+rule basket_total = total of items.price
+rule basket_subtotal = total of items.subtotal?
+
+doc empty_basket
+currency EUR
+fact items = many doc line_item
+rule basket_total = total of items.price
+
+doc empty_basket_without_currency
+fact items = many doc line_item
+rule basket_total = total of items.price
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "basket.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_total_of_a_fact() {
+    let engine = engine();
+    assert_eq!(value(&engine, "basket", &[], "basket_total"), "15 EUR");
+    assert_eq!(
+        value(
+            &engine,
+            "basket",
+            &["items[2].price=2.50 EUR"],
+            "basket_total"
+        ),
+        "17.50 EUR"
+    );
+}
+
+#[test]
+fn test_total_of_a_rule() {
+    let engine = engine();
+    assert_eq!(value(&engine, "basket", &[], "basket_subtotal"), "25 EUR");
+}
+
+#[test]
+fn test_mixed_currencies_are_an_error() {
+    let engine = engine();
+    let facts = parse_facts(&["items[1].price=5 USD"]).unwrap();
+    let error = engine
+        .evaluate(
+            "basket",
+            Some(vec!["basket_total".to_string()]),
+            Some(facts),
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("cannot add 5 USD"), "{}", error);
+}
+
+#[test]
+fn test_empty_collections() {
+    let engine = engine();
+    assert_eq!(value(&engine, "empty_basket", &[], "basket_total"), "0 EUR");
+
+    let result = result(
+        &engine,
+        "empty_basket_without_currency",
+        &[],
+        "basket_total",
+    );
+    assert_eq!(
+        result.veto_message.as_deref(),
+        Some("items has no instances to total")
+    );
+}
+
+#[test]
+fn test_trace_has_an_entry_per_instance() {
+    let options = EvaluationOptions {
+        trace_level: TraceLevel::Full,
+        ..EvaluationOptions::default()
+    };
+    let response = engine()
+        .evaluate_with_options(
+            "basket",
+            Some(vec!["basket_total".to_string()]),
+            None,
+            &options,
+        )
+        .unwrap();
+    let added: Vec<&str> = response.results[0]
+        .operations
+        .iter()
+        .filter_map(|operation| match operation {
+            OperationRecord::OperationExecuted { operation, .. }
+                if operation.starts_with("add ") =>
+            {
+                Some(operation.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(added, vec!["add items[0]", "add items[1]"]);
+}
+
+#[test]
+fn test_total_of_a_non_money_fact_is_rejected() {
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            r#"
+doc line_item
+fact quantity = 1
+
+doc basket
+fact items = many doc line_item
+rule total_quantity = total of items.quantity
+"#,
+            "basket.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("adds money"), "{}", error);
+}
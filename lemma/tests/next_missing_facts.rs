@@ -0,0 +1,81 @@
+//! Asking for facts one step at a time with `Engine::next_missing_facts`
+//!
+//! Key behaviors:
+//! 1. Each unfinished rule contributes the first fact it needs
+//! 2. Facts behind a decided `and`/`or` are not asked for
+//! 3. Rules failing on another rule don't repeat that rule's fact
+//! 4. Facts of referenced documents are named by their path
+//! 5. An empty list means the requested rules all have results
+
+use lemma::{Engine, LemmaResult};
+
+const CODE: &str = r#"
+doc employer
+fact size = [number]
+
+doc loan
+fact age = [number]
+fact income = [number]
+fact debt = [number]
+fact employer = doc employer
+
+rule adult = age >= 18
+rule eligible = adult? and income > 30000
+rule risk = debt / income
+rule large_employer = employer.size > 250
+"#;
+
+fn next(facts: &[&str], rules: Option<&[&str]>) -> LemmaResult<Vec<String>> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "loan.lemma").unwrap();
+    let facts = engine.parse_facts("loan", facts).unwrap();
+    let rules = rules.map(|rules| rules.iter().map(|rule| rule.to_string()).collect());
+    engine.next_missing_facts("loan", rules, facts)
+}
+
+#[test]
+fn test_first_needed_fact_per_rule() {
+    let mut missing = next(&[], None).unwrap();
+    missing.sort();
+    assert_eq!(missing, vec!["age", "debt", "employer.size"]);
+    assert_eq!(
+        next(&["age=30"], Some(&["eligible"])).unwrap(),
+        vec!["income"]
+    );
+}
+
+#[test]
+fn test_decided_conditions_are_skipped() {
+    assert!(next(&["age=16"], Some(&["eligible"])).unwrap().is_empty());
+}
+
+#[test]
+fn test_progress_until_done() {
+    assert_eq!(next(&["age=30"], Some(&["risk"])).unwrap(), vec!["debt"]);
+    assert_eq!(
+        next(&["age=30", "debt=1000"], Some(&["risk"])).unwrap(),
+        vec!["income"]
+    );
+    assert!(next(&["debt=1000", "income=50000"], Some(&["risk"]))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_referenced_document_facts() {
+    assert_eq!(
+        next(&[], Some(&["large_employer"])).unwrap(),
+        vec!["employer.size"]
+    );
+    assert!(next(&["employer.size=300"], Some(&["large_employer"]))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_unknown_document() {
+    let engine = Engine::new();
+    assert!(engine
+        .next_missing_facts("missing", None, Vec::new())
+        .is_err());
+}
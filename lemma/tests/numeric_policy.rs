@@ -0,0 +1,129 @@
+//! Rounding and overflow of number arithmetic, see `NumericPolicy`
+//!
+//! Key behaviors:
+//! 1. By default division keeps every digit that fits
+//! 2. Significant digits round division and `^` results, with the chosen rounding
+//! 3. Overflow fails with an error naming the operation, or saturates
+//! 4. The policy applies to units and percentages too
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LiteralValue, NumericPolicy, Overflow, Response, Rounding};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const CODE: &str = r#"
+doc numbers
+fact a = [number]
+fact b = [number]
+rule quotient = a / b
+rule power = a ^ b
+rule product = a * b
+rule share = 100 EUR / b
+rule discounted = a - 12.5%
+rule doubled = a * 200%
+"#;
+
+fn evaluate(policy: NumericPolicy, a: &str, b: &str) -> Response {
+    evaluate_rules(policy, a, b, None)
+}
+
+fn evaluate_rules(policy: NumericPolicy, a: &str, b: &str, rules: Option<Vec<String>>) -> Response {
+    let mut engine = Engine::new().with_numeric_policy(policy);
+    engine.add_lemma_code(CODE, "numbers.lemma").unwrap();
+    let facts = engine
+        .parse_facts("numbers", &[&format!("a={}", a), &format!("b={}", b)])
+        .unwrap();
+    engine.evaluate("numbers", rules, Some(facts)).unwrap()
+}
+
+fn value(response: &Response, rule: &str) -> Option<LiteralValue> {
+    rule_result(response, rule).result.clone()
+}
+
+fn number(value: &str) -> Option<LiteralValue> {
+    Some(LiteralValue::Number(Decimal::from_str(value).unwrap()))
+}
+
+#[test]
+fn test_default_keeps_all_digits() {
+    let response = evaluate(NumericPolicy::default(), "2", "3");
+    assert_eq!(
+        value(&response, "quotient"),
+        number("0.6666666666666666666666666667")
+    );
+}
+
+#[test]
+fn test_significant_digits_round_division_and_power() {
+    let policy = NumericPolicy::new().with_significant_digits(4, Rounding::HalfEven);
+    let response = evaluate(policy, "2", "3");
+    assert_eq!(value(&response, "quotient"), number("0.6667"));
+    assert_eq!(value(&response, "product"), number("6"));
+    assert_eq!(value(&response, "share").unwrap().to_string(), "33.33 EUR");
+
+    let policy = NumericPolicy::new().with_significant_digits(4, Rounding::TowardZero);
+    assert_eq!(
+        value(&evaluate(policy, "2", "3"), "quotient"),
+        number("0.6666")
+    );
+
+    let policy = NumericPolicy::new().with_significant_digits(2, Rounding::HalfUp);
+    assert_eq!(value(&evaluate(policy, "1.5", "3"), "power"), number("3.4"));
+}
+
+#[test]
+fn test_overflow_errors_or_saturates() {
+    let huge = "70000000000000000000000000000";
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "numbers.lemma").unwrap();
+    let facts = engine
+        .parse_facts("numbers", &[&format!("a={}", huge), "b=2"])
+        .unwrap();
+    let error = engine
+        .evaluate("numbers", Some(vec!["product".to_string()]), Some(facts))
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("Arithmetic overflow"),
+        "{}",
+        error
+    );
+
+    let policy = NumericPolicy::new().with_overflow(Overflow::Saturate);
+    let response = evaluate(policy, huge, "2");
+    assert_eq!(
+        value(&response, "power"),
+        Some(LiteralValue::Number(Decimal::MAX))
+    );
+    assert_eq!(
+        value(&response, "product"),
+        Some(LiteralValue::Number(Decimal::MAX))
+    );
+    assert_eq!(
+        value(&evaluate(policy, &format!("-{}", huge), "3"), "power"),
+        Some(LiteralValue::Number(Decimal::MIN))
+    );
+}
+
+#[test]
+fn test_percentages_follow_the_policy() {
+    let huge = "70000000000000000000000000000";
+    let response = evaluate_rules(
+        NumericPolicy::default(),
+        huge,
+        "2",
+        Some(vec!["discounted".to_string()]),
+    );
+    assert_eq!(
+        value(&response, "discounted"),
+        number("61250000000000000000000000000")
+    );
+
+    let policy = NumericPolicy::new().with_overflow(Overflow::Saturate);
+    let response = evaluate(policy, huge, "2");
+    assert_eq!(
+        value(&response, "doubled"),
+        Some(LiteralValue::Number(Decimal::MAX))
+    );
+}
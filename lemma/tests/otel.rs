@@ -0,0 +1,128 @@
+//! OpenTelemetry spans of an evaluation
+//!
+//! Key behaviors:
+//! 1. A W3C `traceparent` header gives the trace and parent span; malformed ones are ignored
+//! 2. The evaluation is one span, with one child span per rule result
+//! 3. A rule's operations are events of its span
+//! 4. `export_request` produces the OTLP/HTTP JSON body
+
+use lemma::otel::{evaluation_spans, export_request, AttributeValue, TraceContext};
+use lemma::Engine;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+fn response() -> lemma::Response {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc pricing\nfact price = 10\nfact quantity = 3\nrule total = price * quantity\nrule free = total? > 100",
+            "pricing.lemma",
+        )
+        .unwrap();
+    engine.evaluate("pricing", None, None).unwrap()
+}
+
+fn attribute<'a>(
+    attributes: &'a [(String, AttributeValue)],
+    key: &str,
+) -> Option<&'a AttributeValue> {
+    attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+}
+
+#[test]
+fn test_traceparent() {
+    let context = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+    assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(context.parent_span_id.as_deref(), Some("00f067aa0ba902b7"));
+    assert!(context.sampled);
+
+    let unsampled =
+        TraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00")
+            .unwrap();
+    assert!(!unsampled.sampled);
+
+    for malformed in [
+        "",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+    ] {
+        assert_eq!(
+            TraceContext::from_traceparent(malformed),
+            None,
+            "{}",
+            malformed
+        );
+    }
+}
+
+#[test]
+fn test_new_trace() {
+    let context = TraceContext::new_trace();
+    assert_eq!(context.trace_id.len(), 32);
+    assert_eq!(context.parent_span_id, None);
+    assert_ne!(context.trace_id, TraceContext::new_trace().trace_id);
+}
+
+#[test]
+fn test_rule_spans_are_children_of_the_evaluation() {
+    let context = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+    let spans = evaluation_spans(&response(), &context, 1_000, 2_000);
+
+    let names: Vec<&str> = spans.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["evaluate pricing", "rule total", "rule free"]);
+
+    let evaluation = &spans[0];
+    assert_eq!(
+        evaluation.parent_span_id.as_deref(),
+        Some("00f067aa0ba902b7")
+    );
+    for rule in &spans[1..] {
+        assert_eq!(rule.trace_id, context.trace_id);
+        assert_eq!(rule.parent_span_id.as_ref(), Some(&evaluation.span_id));
+        assert_eq!((rule.start_unix_nano, rule.end_unix_nano), (1_000, 2_000));
+    }
+    assert_eq!(
+        attribute(&spans[1].attributes, "lemma.result"),
+        Some(&AttributeValue::Text("30".to_string()))
+    );
+}
+
+#[test]
+fn test_operations_are_events() {
+    let context = TraceContext::new_trace();
+    let spans = evaluation_spans(&response(), &context, 1_000, 2_000);
+
+    let events: Vec<&str> = spans[1].events.iter().map(|e| e.name.as_str()).collect();
+    assert!(events.contains(&"fact_used"));
+    assert!(events.contains(&"operation_executed"));
+    assert_eq!(events.last(), Some(&"final_result"));
+
+    let fact = spans[1]
+        .events
+        .iter()
+        .find(|e| e.name == "fact_used")
+        .unwrap();
+    assert!(attribute(&fact.attributes, "lemma.fact").is_some());
+}
+
+#[test]
+fn test_export_request() {
+    let context = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+    let spans = evaluation_spans(&response(), &context, 1_000, 2_000);
+    let body = export_request(&spans, "pricing-service");
+
+    let resource = &body["resourceSpans"][0];
+    assert_eq!(
+        resource["resource"]["attributes"][0],
+        serde_json::json!({"key": "service.name", "value": {"stringValue": "pricing-service"}})
+    );
+    let exported = resource["scopeSpans"][0]["spans"].as_array().unwrap();
+    assert_eq!(exported.len(), 3);
+    assert_eq!(exported[0]["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(exported[0]["parentSpanId"], "00f067aa0ba902b7");
+    assert_eq!(exported[0]["startTimeUnixNano"], "1000");
+    assert_eq!(exported[1]["parentSpanId"], exported[0]["spanId"]);
+    assert_eq!(exported[1]["status"]["code"], 1);
+}
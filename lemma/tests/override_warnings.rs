@@ -0,0 +1,78 @@
+//! Warnings for overrides that decide unless clauses of referenced documents
+//!
+//! Key behaviors:
+//! 1. An override that makes a clause's condition false reports it never applies
+//! 2. One that makes it true reports the default expression is never used
+//! 3. Clauses that still depend on other facts aren't reported
+//! 4. Overrides reach through several document references
+
+use lemma::Engine;
+
+const CODE: &str = r#"
+doc config
+fact max_weight = 30 kilograms
+fact express = false
+fact weight = [mass]
+rule allowed = true
+  unless max_weight <= 0 kilograms then veto "Nothing can be shipped"
+  unless weight > max_weight then veto "Too heavy"
+rule surcharge = 0
+  unless express and max_weight > 10 kilograms then 5
+
+doc shipping
+fact config = doc config
+fact config.max_weight = 0 kilograms
+rule allowed = config.allowed?
+
+doc express
+fact config = doc config
+fact config.express = false
+rule surcharge = config.surcharge?
+
+doc order
+fact shipping = doc shipping
+fact shipping.config.max_weight = 0 kilograms
+rule allowed = shipping.allowed?
+"#;
+
+fn warnings(doc: &str) -> Vec<String> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shipping.lemma").unwrap();
+    let facts = engine.parse_facts(doc, &[]).unwrap();
+    engine.evaluate(doc, None, Some(facts)).unwrap().warnings
+}
+
+#[test]
+fn test_override_makes_clause_always_apply() {
+    let warnings = warnings("shipping");
+    // The weight check still depends on the weight, the surcharge needs a positive limit
+    assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    assert!(warnings
+        .iter()
+        .all(|w| w.contains("overriding config.max_weight = 0 kilogram")));
+    assert!(warnings[0].contains("unless clause 1 of rule 'allowed' in document 'config'"));
+    assert!(warnings[0].contains("always applies"));
+    assert!(warnings[1].contains("unless clause 1 of rule 'surcharge'"));
+    assert!(warnings[1].contains("never applies"));
+}
+
+#[test]
+fn test_override_makes_clause_never_apply() {
+    let warnings = warnings("express");
+    assert_eq!(warnings.len(), 1, "{:?}", warnings);
+    assert!(warnings[0].contains("overriding config.express = false"));
+    assert!(warnings[0].contains("unless clause 1 of rule 'surcharge'"));
+    assert!(warnings[0].contains("never applies"));
+}
+
+#[test]
+fn test_override_through_nested_references() {
+    let warnings = warnings("order");
+    assert_eq!(warnings.len(), 2, "{:?}", warnings);
+    assert!(warnings[0].contains("overriding shipping.config.max_weight = 0 kilogram"));
+}
+
+#[test]
+fn test_no_warnings_without_overrides() {
+    assert!(warnings("config").is_empty());
+}
@@ -0,0 +1,166 @@
+//! Documents declared in more than one file
+//!
+//! Key behaviors:
+//! 1. Declaring a document twice is an error naming both files
+//! 2. The parts of a `partial doc` are merged into one document
+//! 3. A fact or rule defined in two parts is an error naming both files
+//! 4. Adding a source again replaces the documents it declared
+//! 5. Adding or editing a source with a part again replaces only that part
+
+use lemma::{Engine, LemmaError, LiteralValue};
+use rust_decimal::Decimal;
+
+fn total(engine: &Engine) -> Option<LiteralValue> {
+    let response = engine.evaluate("pricing", None, None).unwrap();
+    response
+        .results
+        .into_iter()
+        .find(|r| r.rule_name == "total")
+        .and_then(|r| r.result)
+}
+
+#[test]
+fn test_duplicate_document_names_both_files() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code("doc pricing\nfact price = 10", "a.lemma")
+        .unwrap();
+    let error = engine
+        .add_lemma_code("\ndoc pricing\nfact price = 12", "b.lemma")
+        .unwrap_err();
+
+    let LemmaError::Semantic(details) = &error else {
+        panic!("expected a semantic error, got {:?}", error);
+    };
+    assert_eq!(
+        details.message,
+        "Document 'pricing' is defined in both 'a.lemma' (line 1) and 'b.lemma' (line 2)"
+    );
+    assert!(details.suggestion.as_ref().unwrap().contains("partial doc"));
+}
+
+#[test]
+fn test_partial_documents_are_merged() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code("partial doc pricing\nfact price = 10", "facts.lemma")
+        .unwrap();
+    engine
+        .add_lemma_code(
+            "partial doc pricing\nfact quantity = 3\nrule total = price * quantity",
+            "rules.lemma",
+        )
+        .unwrap();
+
+    assert_eq!(
+        total(&engine),
+        Some(LiteralValue::Number(Decimal::from(30)))
+    );
+    let doc = engine.get_document("pricing").unwrap();
+    assert_eq!(doc.facts.len(), 2);
+}
+
+#[test]
+fn test_partial_and_full_document_conflict() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code("partial doc pricing\nfact price = 10", "a.lemma")
+        .unwrap();
+    let error = engine
+        .add_lemma_code("doc pricing\nfact quantity = 3", "b.lemma")
+        .unwrap_err();
+    assert!(error.to_string().contains("defined in both"), "{}", error);
+}
+
+#[test]
+fn test_duplicate_definitions_across_parts() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "partial doc pricing\nfact price = 10\nrule total = price",
+            "a.lemma",
+        )
+        .unwrap();
+
+    let error = engine
+        .add_lemma_code("partial doc pricing\n\nfact price = 12", "b.lemma")
+        .unwrap_err();
+    let LemmaError::Semantic(details) = &error else {
+        panic!("expected a semantic error, got {:?}", error);
+    };
+    assert_eq!(
+        details.message,
+        "Fact 'price' of document 'pricing' is defined in both 'a.lemma' (line 2) and 'b.lemma' (line 3)"
+    );
+
+    let error = engine
+        .add_lemma_code("partial doc pricing\nrule total = 1", "c.lemma")
+        .unwrap_err();
+    assert!(error.to_string().contains("Rule 'total'"), "{}", error);
+}
+
+#[test]
+fn test_adding_a_source_again_replaces_its_documents() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc pricing\nfact price = 10\nrule total = price",
+            "a.lemma",
+        )
+        .unwrap();
+    engine
+        .add_lemma_code(
+            "doc pricing\nfact price = 12\nrule total = price",
+            "a.lemma",
+        )
+        .unwrap();
+
+    assert_eq!(
+        total(&engine),
+        Some(LiteralValue::Number(Decimal::from(12)))
+    );
+}
+
+fn two_part_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code("partial doc pricing\nfact price = 10", "a.lemma")
+        .unwrap();
+    engine
+        .add_lemma_code(
+            "partial doc pricing\nfact quantity = 3\nrule total = price * quantity",
+            "b.lemma",
+        )
+        .unwrap();
+    engine
+}
+
+#[test]
+fn test_adding_a_part_again_replaces_only_that_part() {
+    let mut engine = two_part_engine();
+    engine
+        .add_lemma_code(
+            "partial doc pricing\nfact quantity = 4\nrule total = price * quantity",
+            "b.lemma",
+        )
+        .unwrap();
+
+    assert_eq!(
+        total(&engine),
+        Some(LiteralValue::Number(Decimal::from(40)))
+    );
+    assert_eq!(engine.get_document("pricing").unwrap().facts.len(), 2);
+}
+
+#[test]
+fn test_editing_a_part_keeps_the_other_parts() {
+    let mut engine = two_part_engine();
+    engine
+        .update_document_range("a.lemma", 2..3, "fact price = 20\n")
+        .unwrap();
+
+    assert_eq!(
+        total(&engine),
+        Some(LiteralValue::Number(Decimal::from(60)))
+    );
+}
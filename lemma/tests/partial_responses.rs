@@ -0,0 +1,84 @@
+//! Partial responses when evaluation hits a resource limit
+//!
+//! Key behaviors:
+//! 1. Rules evaluated before the limit keep their results
+//! 2. The rule in progress and the rules after it are marked `limit_exceeded`
+//! 3. `Response::limit_exceeded` names the limit and the rule in progress
+//! 4. Without hitting a limit nothing is marked
+
+use lemma::{Engine, LiteralValue, ResourceLimits};
+use rust_decimal::Decimal;
+use std::thread;
+use std::time::Duration;
+
+const CODE: &str = r#"
+doc dashboard
+fact price = 10
+rule quick = price * 2
+rule slow = pause(quick?) + 1
+rule after = slow? * 2
+"#;
+
+fn engine(max_evaluation_time_ms: u64) -> Engine {
+    let mut engine = Engine::with_limits(ResourceLimits {
+        max_evaluation_time_ms,
+        ..ResourceLimits::default()
+    });
+    engine
+        .register_function("pause", 1, |args| {
+            thread::sleep(Duration::from_millis(50));
+            Ok(args[0].clone())
+        })
+        .unwrap();
+    engine.add_lemma_code(CODE, "dashboard.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_limit_returns_partial_response() {
+    let response = engine(20).evaluate("dashboard", None, None).unwrap();
+
+    let quick = &response.results[0];
+    assert_eq!(quick.rule_name, "quick");
+    assert_eq!(quick.result, Some(LiteralValue::Number(Decimal::from(20))));
+    assert_eq!(quick.limit_exceeded, None);
+
+    for (result, name) in response.results[1..].iter().zip(["slow", "after"]) {
+        assert_eq!(result.rule_name, name);
+        assert_eq!(result.result, None);
+        assert_eq!(
+            result.limit_exceeded.as_deref(),
+            Some("max_evaluation_time_ms")
+        );
+    }
+
+    let limit = response.limit_exceeded.unwrap();
+    assert_eq!(limit.limit_name, "max_evaluation_time_ms");
+    assert_eq!(limit.rule.as_deref(), Some("slow"));
+}
+
+#[test]
+fn test_partial_response_serializes_status() {
+    let response = engine(20).evaluate("dashboard", None, None).unwrap();
+    let json = serde_json::to_value(&response).unwrap();
+    assert_eq!(json["limit_exceeded"]["rule"], "slow");
+    assert_eq!(
+        json["results"][2]["limit_exceeded"],
+        "max_evaluation_time_ms"
+    );
+    assert!(json["results"][0].get("limit_exceeded").is_none());
+}
+
+#[test]
+fn test_no_limit_hit() {
+    let response = engine(10_000).evaluate("dashboard", None, None).unwrap();
+    assert!(response.limit_exceeded.is_none());
+    assert!(response
+        .results
+        .iter()
+        .all(|result| result.limit_exceeded.is_none()));
+    assert_eq!(
+        response.results[2].result,
+        Some(LiteralValue::Number(Decimal::from(42)))
+    );
+}
@@ -0,0 +1,153 @@
+//! Percentage points versus relative percentage changes
+//!
+//! Key behaviors:
+//! 1. `5% + 2 percentage points` is 7%
+//! 2. `5% + 2%` changes 5% by 2% of itself: 5.1%
+//! 3. Adding percentages without percentage points is warned about at validation
+//! 4. The trace records the percentage points a relative change comes down to
+//! 5. Percentage points combine with each other and scale with numbers
+//! 6. A percentage fact can't be given percentage points, nor the other way around
+
+mod common;
+
+use common::rule_result;
+use lemma::{
+    parse_facts, DiagnosticKind, Engine, EvaluationOptions, LiteralValue, OperationRecord,
+    TraceLevel,
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const CODE: &str = r#"
+doc rates
+fact rate = 5%
+fact raise = 2 percentage points
+
+rule absolute = rate + raise
+rule relative = rate + 2%
+rule lowered = rate - 1 percentage point
+rule doubled_raise = raise * 2 + 1 percentage point
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "rates.lemma").unwrap();
+    engine
+}
+
+fn result(response: &lemma::Response, rule: &str) -> Option<LiteralValue> {
+    rule_result(response, rule).result.clone()
+}
+
+fn decimal(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+#[test]
+fn test_percentage_points_add_directly() {
+    let response = engine().evaluate("rates", None, None).unwrap();
+    assert_eq!(
+        result(&response, "absolute"),
+        Some(LiteralValue::Percentage(decimal("7")))
+    );
+    assert_eq!(
+        result(&response, "lowered"),
+        Some(LiteralValue::Percentage(decimal("4")))
+    );
+    assert_eq!(
+        result(&response, "doubled_raise"),
+        Some(LiteralValue::PercentagePoints(decimal("5")))
+    );
+}
+
+#[test]
+fn test_percentages_change_relatively() {
+    let response = engine().evaluate("rates", None, None).unwrap();
+    assert_eq!(
+        result(&response, "relative"),
+        Some(LiteralValue::Percentage(decimal("5.1")))
+    );
+}
+
+#[test]
+fn test_adding_percentages_is_warned_about() {
+    let response = engine().evaluate("rates", None, None).unwrap();
+    let warnings: Vec<&String> = response
+        .warnings
+        .iter()
+        .filter(|w| w.contains("changes a percentage relatively"))
+        .collect();
+    assert_eq!(warnings.len(), 1, "{:?}", response.warnings);
+    assert!(warnings[0].contains("Rule 'relative'"), "{}", warnings[0]);
+
+    let diagnostic = response
+        .diagnostics
+        .iter()
+        .find(|d| d.kind == DiagnosticKind::AmbiguousPercentage)
+        .unwrap();
+    assert_eq!(diagnostic.rule.as_deref(), Some("relative"));
+}
+
+#[test]
+fn test_relative_change_is_traced() {
+    let options = EvaluationOptions {
+        trace_level: TraceLevel::Full,
+        ..EvaluationOptions::default()
+    };
+    let response = engine()
+        .evaluate_with_options("rates", Some(vec!["relative".to_string()]), None, &options)
+        .unwrap();
+    let operations = &response.results[0].operations;
+    let conversion = operations.iter().find_map(|operation| match operation {
+        OperationRecord::OperationExecuted {
+            operation, result, ..
+        } if operation == "to_percentage_points" => Some(result.clone()),
+        _ => None,
+    });
+    assert_eq!(
+        conversion,
+        Some(LiteralValue::PercentagePoints(decimal("0.1")))
+    );
+}
+
+#[test]
+fn test_overrides_keep_their_kind_of_percentage() {
+    let engine = engine();
+    let evaluate =
+        |overrides: &[&str]| engine.evaluate("rates", None, Some(parse_facts(overrides).unwrap()));
+
+    let error = evaluate(&["rate=2 percentage points"]).unwrap_err();
+    assert!(
+        error.to_string().contains("expected a percentage like 5%"),
+        "{}",
+        error
+    );
+    let error = evaluate(&["raise=3%"]).unwrap_err();
+    assert!(
+        error.to_string().contains("expected percentage points"),
+        "{}",
+        error
+    );
+
+    let response = evaluate(&["rate=6%", "raise=3 percentage points"]).unwrap();
+    assert_eq!(
+        result(&response, "absolute"),
+        Some(LiteralValue::Percentage(decimal("9")))
+    );
+}
+
+#[test]
+fn test_percentage_example_must_be_a_percentage() {
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            "doc rates\nfact rate = [percentage] example 2 percentage points",
+            "rates.lemma",
+        )
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("must be a percentage like 5%"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,122 @@
+//! The browser playground's state, behind the `playground` feature
+//!
+//! Key behaviors:
+//! 1. Every bundled example loads without errors
+//! 2. The state round-trips through a permalink, also inside a whole URL
+//! 3. Files may reference documents in files after them
+//! 4. Errors come out as JSON with a file, line and column
+//! 5. Evaluation uses the picked document and the facts filled in
+
+#![cfg(feature = "playground")]
+
+use lemma::playground::{errors_json, example, examples, PlaygroundState};
+use lemma::LiteralValue;
+use rust_decimal::Decimal;
+
+#[test]
+fn test_examples_load() {
+    assert!(!examples().is_empty());
+    for example in examples() {
+        let state = example.state();
+        let (engine, errors) = state.load();
+        assert!(errors.is_empty(), "{}: {:?}", example.name, errors);
+        let document = state.document.expect("an example picks a document");
+        assert!(engine.get_document(&document).is_some(), "{}", document);
+    }
+    assert!(example("tax_calculation").is_some());
+    assert!(example("nope").is_none());
+}
+
+#[test]
+fn test_permalink_round_trips() {
+    let mut state = PlaygroundState::default();
+    state.set_file(
+        "order.lemma",
+        "doc order\nfact price = [number]\nrule total = price * 2",
+    );
+    state.document = Some("order".to_string());
+    state
+        .facts
+        .insert("price".to_string(), serde_json::json!(21));
+
+    let link = state.to_permalink();
+    assert!(link.starts_with("w="));
+    assert_eq!(PlaygroundState::from_permalink(&link).unwrap(), state);
+    assert_eq!(
+        PlaygroundState::from_permalink(&format!("https://example.com/play#{}", link)).unwrap(),
+        state
+    );
+
+    for link in ["x=abc", "w=!!!", "w=e30"] {
+        let error = PlaygroundState::from_permalink(link).unwrap_err();
+        assert!(
+            error.to_string().contains("Invalid playground link"),
+            "{}",
+            error
+        );
+    }
+}
+
+#[test]
+fn test_files_reference_later_files() {
+    let mut state = PlaygroundState::default();
+    state.set_file(
+        "order.lemma",
+        "doc order\nfact pricing = doc pricing\nrule total = pricing.price * 2",
+    );
+    state.set_file("pricing.lemma", "doc pricing\nfact price = 10");
+    let (engine, errors) = state.load();
+    assert!(errors.is_empty(), "{:?}", errors);
+
+    state.document = Some("order".to_string());
+    let response = state.evaluate(&engine).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(20)))
+    );
+
+    assert!(state.remove_file("pricing.lemma"));
+    assert!(!state.remove_file("pricing.lemma"));
+    assert_eq!(state.load().1.len(), 1);
+}
+
+#[test]
+fn test_errors_are_json_with_positions() {
+    let mut state = PlaygroundState::default();
+    state.set_file("broken.lemma", "doc broken\nfact x = 1\nrule y = x +");
+    let (_, errors) = state.load();
+    let json = errors_json(&errors);
+    let error = &json[0];
+    assert_eq!(error["kind"], "parse");
+    assert_eq!(error["file"], "broken.lemma");
+    assert_eq!(error["line"], 3);
+    assert!(error["column"].as_u64().unwrap() > 0);
+    assert!(error["message"].is_string());
+}
+
+#[test]
+fn test_evaluate_with_facts() {
+    let mut state = PlaygroundState::default();
+    state.set_file(
+        "order.lemma",
+        "doc order\nfact price = [number]\nrule total = price * 2",
+    );
+    let (engine, _) = state.load();
+    assert!(state.evaluate(&engine).is_err());
+
+    state.document = Some("order".to_string());
+    state
+        .facts
+        .insert("price".to_string(), serde_json::json!(21));
+    let response = state.evaluate(&engine).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(42)))
+    );
+
+    state
+        .facts
+        .insert("price".to_string(), serde_json::json!("lots"));
+    let error = state.evaluate(&engine).unwrap_err();
+    assert_eq!(errors_json(&[error])[0]["kind"], "fact");
+}
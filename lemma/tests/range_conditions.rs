@@ -0,0 +1,164 @@
+//! Range conditions: `between ... and ...` and `in low..high`
+//!
+//! Key behaviors:
+//! 1. Both syntaxes include their bounds
+//! 2. A range is a condition of its own, printed as `between`
+//! 3. The trace records a single `between` operation
+//! 4. A veto in a bound vetoes the range, but `and` written out still short-circuits
+//! 5. Inverting gives the closed interval of the range
+//! 6. `in` still converts units
+
+use lemma::{
+    Bound, Domain, Engine, LiteralValue, OperationRecord, OperationResult, RuleResult, Target,
+};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc insurance
+fact age = [number]
+fact distance = [length]
+rule premium = 100
+  unless age between 30 and 40 then 80
+rule discounted = age in 30..40
+rule eligible = age >= 18 and age in 18..65 and distance in kilometers in 1 kilometer..5 kilometers
+rule maximum = 65
+  unless age < 18 then veto "no maximum age"
+rule ranged = age between 18 and maximum?
+rule written_out = age >= 18 and age <= maximum?
+"#;
+
+fn evaluate(overrides: &[&str], rule: &str) -> RuleResult {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "insurance.lemma").unwrap();
+    let facts = engine.parse_facts("insurance", overrides).unwrap();
+    let response = engine
+        .evaluate("insurance", Some(vec![rule.to_string()]), Some(facts))
+        .unwrap();
+    response.results.into_iter().next().unwrap()
+}
+
+fn number(value: i64) -> LiteralValue {
+    LiteralValue::Number(Decimal::from(value))
+}
+
+#[test]
+fn test_bounds_are_included() {
+    for (age, premium, discounted) in [
+        (29, 100, false),
+        (30, 80, true),
+        (40, 80, true),
+        (41, 100, false),
+    ] {
+        let age = format!("age={}", age);
+        assert_eq!(evaluate(&[&age], "premium").result, Some(number(premium)));
+        assert_eq!(
+            evaluate(&[&age], "discounted").result,
+            Some(LiteralValue::Boolean(discounted))
+        );
+    }
+}
+
+#[test]
+fn test_range_prints_as_between() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "insurance.lemma").unwrap();
+    let printed = engine.get_document("insurance").unwrap().to_string();
+    assert!(
+        printed.contains("rule discounted = age between 30 and 40"),
+        "{}",
+        printed
+    );
+    assert!(
+        printed.contains("rule written_out = age >= 18 and age <= maximum?"),
+        "{}",
+        printed
+    );
+}
+
+#[test]
+fn test_range_is_one_trace_operation() {
+    let result = evaluate(&["age=35"], "discounted");
+    let executed: Vec<_> = result
+        .operations
+        .iter()
+        .filter_map(|op| match op {
+            OperationRecord::OperationExecuted {
+                operation, inputs, ..
+            } => Some((operation.as_str(), inputs.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(
+        executed,
+        vec![("between", vec![number(35), number(30), number(40)])]
+    );
+}
+
+#[test]
+fn test_veto_in_bound_vetoes_range() {
+    let result = evaluate(&["age=10"], "ranged");
+    assert_eq!(result.result, None);
+    assert_eq!(result.veto_message.as_deref(), Some("no maximum age"));
+}
+
+#[test]
+fn test_written_out_and_short_circuits_before_vetoing_bound() {
+    // `age >= 18` is false, so `age <= maximum?` is never evaluated
+    let result = evaluate(&["age=10"], "written_out");
+    assert_eq!(result.result, Some(LiteralValue::Boolean(false)));
+    assert!(result.veto_message.is_none());
+
+    let executed: Vec<_> = result
+        .operations
+        .iter()
+        .filter_map(|op| match op {
+            OperationRecord::OperationExecuted { operation, .. } => Some(operation.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(executed, vec!["greater_than_or_equal"]);
+    assert!(result
+        .operations
+        .iter()
+        .any(|op| matches!(op, OperationRecord::ShortCircuit { .. })));
+}
+
+#[test]
+fn test_in_still_converts_units() {
+    let result = evaluate(&["age=30", "distance=3000 meters"], "eligible");
+    assert_eq!(result.result, Some(LiteralValue::Boolean(true)));
+    let result = evaluate(&["age=30", "distance=6000 meters"], "eligible");
+    assert_eq!(result.result, Some(LiteralValue::Boolean(false)));
+}
+
+#[test]
+fn test_range_inverts_to_closed_interval() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "insurance.lemma").unwrap();
+    let solutions = engine
+        .invert(
+            "insurance",
+            "premium",
+            Target::with_op(lemma::TargetOp::Eq, OperationResult::Value(number(80))),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    let domains: Vec<&Domain> = solutions
+        .iter()
+        .filter_map(|solution| {
+            solution
+                .iter()
+                .find(|(reference, _)| reference.reference == ["age"])
+                .map(|(_, domain)| domain)
+        })
+        .collect();
+    assert_eq!(
+        domains,
+        vec![&Domain::Range {
+            min: Bound::Inclusive(number(30)),
+            max: Bound::Inclusive(number(40)),
+        }]
+    );
+}
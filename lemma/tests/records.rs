@@ -0,0 +1,110 @@
+//! Record rule tests
+//!
+//! Key behaviors:
+//! 1. `rule quote = { total: ..., currency: ... }` derives the rules `quote.total` and `quote.currency`
+//! 2. Fields are referenced as `quote?.total` or `quote.total?`, locally and through document references
+//! 3. Field rules share the record's visibility and description
+//! 4. Records survive a Display roundtrip
+
+mod common;
+
+use common::literal;
+use lemma::{Engine, LiteralValue};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc pricing
+fact base = 100
+fact fees = 5
+rule quote = {
+  total: base + fees,
+  currency: "EUR",
+  discounted: quote?.total * 90%
+} """The offer sent to the customer"""
+rule summary = quote.total? > 100
+
+doc order
+fact pricing = doc pricing
+rule payable = pricing.quote?.total
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+fn number(n: i64) -> LiteralValue {
+    LiteralValue::Number(Decimal::from(n))
+}
+
+#[test]
+fn test_record_fields_are_rules() {
+    let engine = engine();
+    assert_eq!(literal(&engine, "pricing", &[], "quote.total"), number(105));
+    assert_eq!(
+        literal(&engine, "pricing", &[], "quote.currency"),
+        LiteralValue::Text("EUR".to_string())
+    );
+    assert_eq!(
+        literal(&engine, "pricing", &[], "quote.discounted"),
+        LiteralValue::Number(Decimal::new(945, 1))
+    );
+    assert_eq!(
+        literal(&engine, "pricing", &[], "summary"),
+        LiteralValue::Boolean(true)
+    );
+}
+
+#[test]
+fn test_record_field_through_document_reference() {
+    let engine = engine();
+    assert_eq!(
+        literal(&engine, "order", &["pricing.fees=10"], "payable"),
+        number(110)
+    );
+}
+
+#[test]
+fn test_field_rules_share_description() {
+    let engine = engine();
+    let doc = engine.get_document("pricing").unwrap();
+    let rule = doc.rules.iter().find(|r| r.name == "quote.total").unwrap();
+    assert_eq!(
+        rule.description.as_deref(),
+        Some("The offer sent to the customer")
+    );
+    assert_eq!(doc.records[0].fields, ["total", "currency", "discounted"]);
+}
+
+#[test]
+fn test_internal_record() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc shipping\nfact weight = 3\ninternal rule parcel = { size: weight * 2 }",
+            "test.lemma",
+        )
+        .unwrap();
+    let doc = engine.get_document("shipping").unwrap();
+    assert!(doc.rules[0].is_internal());
+    assert!(doc.is_derived_rule("parcel.size"));
+}
+
+#[test]
+fn test_record_display_roundtrip() {
+    let engine = engine();
+    let code = engine.get_document("pricing").unwrap().to_string();
+    assert!(code.contains(
+        "rule quote = { total: base + fees, currency: \"EUR\", discounted: quote.total? * "
+    ));
+    assert!(!code.contains("rule quote.total"));
+
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&code, "roundtrip.lemma").unwrap();
+    assert_eq!(
+        literal(&reparsed, "pricing", &[], "quote.total"),
+        number(105)
+    );
+    assert_eq!(reparsed.get_document("pricing").unwrap().to_string(), code);
+}
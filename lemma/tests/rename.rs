@@ -0,0 +1,141 @@
+//! Renaming facts and rules across the workspace
+//!
+//! Key behaviors:
+//! 1. The definition and local references are renamed
+//! 2. References through document references, overrides and aggregates are renamed
+//! 3. Renaming a fact that references a document renames the paths through it
+//! 4. Applying the edits gives a workspace that loads and evaluates the same
+//! 5. Unknown names, invalid or taken new names are rejected
+
+use lemma::{Engine, SourceEdit};
+use std::collections::HashMap;
+
+const PRICING: &str = r#"doc pricing
+fact price = 10
+fact quantity = 2
+rule total = price * quantity
+  unless price > 100 then veto "Price too high"
+"#;
+
+const ORDER: &str = r#"doc order
+fact line = doc pricing
+fact line.price = 12
+fact lines = many doc pricing
+fact lines[0].price = 3
+rule due = line.total? + 5
+rule all_lines = sum of lines.total?
+rule has_price = have line.price
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(PRICING, "pricing.lemma").unwrap();
+    engine.add_lemma_code(ORDER, "order.lemma").unwrap();
+    engine
+}
+
+/// The sources after applying `edits`
+fn apply(edits: &[SourceEdit]) -> HashMap<&'static str, String> {
+    let mut sources = HashMap::from([
+        ("pricing.lemma", PRICING.to_string()),
+        ("order.lemma", ORDER.to_string()),
+    ]);
+    for edit in edits.iter().rev() {
+        let code = sources.get_mut(edit.source.as_str()).unwrap();
+        code.replace_range(edit.span.start..edit.span.end, &edit.new_text);
+    }
+    sources
+}
+
+#[test]
+fn test_rename_fact_everywhere() {
+    let edits = engine()
+        .rename_symbol("pricing", "price", "unit_price")
+        .unwrap();
+    let sources = apply(&edits);
+
+    assert_eq!(
+        sources["pricing.lemma"],
+        PRICING.replace("price", "unit_price")
+    );
+    let order = &sources["order.lemma"];
+    assert!(order.contains("fact line.unit_price = 12"), "{}", order);
+    assert!(order.contains("fact lines[0].unit_price = 3"), "{}", order);
+    assert!(order.contains("have line.unit_price"), "{}", order);
+
+    let first = edits.iter().find(|e| e.source == "pricing.lemma").unwrap();
+    assert_eq!((first.span.line, first.span.col), (2, 6));
+}
+
+#[test]
+fn test_rename_rule_everywhere() {
+    let edits = engine()
+        .rename_symbol("pricing", "total", "line_total")
+        .unwrap();
+    let sources = apply(&edits);
+
+    assert!(sources["pricing.lemma"].contains("rule line_total = price * quantity"));
+    let order = &sources["order.lemma"];
+    assert!(
+        order.contains("rule due = line.line_total? + 5"),
+        "{}",
+        order
+    );
+    assert!(order.contains("sum of lines.line_total?"), "{}", order);
+}
+
+#[test]
+fn test_rename_document_reference_fact() {
+    let edits = engine().rename_symbol("order", "line", "item").unwrap();
+    let sources = apply(&edits);
+
+    let order = &sources["order.lemma"];
+    assert!(order.contains("fact item = doc pricing"), "{}", order);
+    assert!(order.contains("fact item.price = 12"), "{}", order);
+    assert!(order.contains("item.total?"), "{}", order);
+    assert!(order.contains("fact lines = many doc pricing"), "{}", order);
+}
+
+#[test]
+fn test_renamed_workspace_evaluates_the_same() {
+    let edits = engine()
+        .rename_symbol("pricing", "price", "unit_price")
+        .unwrap();
+    let sources = apply(&edits);
+
+    let mut renamed = Engine::new();
+    renamed
+        .add_lemma_code(&sources["pricing.lemma"], "pricing.lemma")
+        .unwrap();
+    renamed
+        .add_lemma_code(&sources["order.lemma"], "order.lemma")
+        .unwrap();
+
+    let before = engine().evaluate("order", None, None).unwrap();
+    let after = renamed.evaluate("order", None, None).unwrap();
+    let values = |response: &lemma::Response| -> Vec<String> {
+        let mut values: Vec<String> = response
+            .results
+            .iter()
+            .map(|r| format!("{}={:?}", r.rule_name, r.result))
+            .collect();
+        values.sort();
+        values
+    };
+    assert_eq!(values(&before), values(&after));
+}
+
+#[test]
+fn test_invalid_renames() {
+    let engine = engine();
+    let error = |old: &str, new: &str| {
+        engine
+            .rename_symbol("pricing", old, new)
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error("cost", "price2").contains("no fact or rule named 'cost'"));
+    assert!(error("price", "quantity").contains("already has a fact or rule named"));
+    assert!(error("price", "rule").contains("not a valid name"));
+    assert!(error("price", "2price").contains("not a valid name"));
+}
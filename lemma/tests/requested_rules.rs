@@ -0,0 +1,85 @@
+//! Evaluating only the requested rules of a document
+//!
+//! Key behaviors:
+//! 1. Requested rules and the rules they depend on are evaluated
+//! 2. Rules the requested rules don't depend on are not evaluated
+//! 3. Dependencies in referenced documents are evaluated
+//! 4. Only the requested rules are in the response
+
+use lemma::{Engine, LiteralValue};
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+
+const CODE: &str = r#"
+doc rates
+fact rate = 2
+rule factor = track("factor", rate)
+
+doc pricing
+fact price = 10
+fact rates = doc rates
+rule base = track("base", price)
+rule total = base? * rates.factor?
+  unless price > 100 then veto "Too expensive"
+rule shipping = track("shipping", 5)
+rule summary = track("summary", shipping?)
+"#;
+
+fn engine(calls: &Arc<Mutex<Vec<String>>>) -> Engine {
+    let calls = Arc::clone(calls);
+    let mut engine = Engine::new();
+    engine
+        .register_function("track", 2, move |args| {
+            if let LiteralValue::Text(name) = &args[0] {
+                calls.lock().unwrap().push(name.clone());
+            }
+            Ok(args[1].clone())
+        })
+        .unwrap();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_only_dependencies_are_evaluated() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let engine = engine(&calls);
+
+    let response = engine
+        .evaluate("pricing", Some(vec!["total".to_string()]), None)
+        .unwrap();
+
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(response.results[0].rule_name, "total");
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(20)))
+    );
+    let mut calls = calls.lock().unwrap().clone();
+    calls.sort();
+    assert_eq!(calls, vec!["base", "factor"]);
+}
+
+#[test]
+fn test_all_rules_are_evaluated_without_a_request() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let engine = engine(&calls);
+
+    let response = engine.evaluate("pricing", None, None).unwrap();
+
+    assert_eq!(response.results.len(), 4);
+    assert_eq!(calls.lock().unwrap().len(), 4);
+}
+
+#[test]
+fn test_chained_dependencies_are_evaluated() {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let engine = engine(&calls);
+
+    let response = engine
+        .evaluate("pricing", Some(vec!["summary".to_string()]), None)
+        .unwrap();
+
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(*calls.lock().unwrap(), vec!["shipping", "summary"]);
+}
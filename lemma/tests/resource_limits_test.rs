@@ -83,13 +83,14 @@ fn test_evaluation_timeout() {
 
     engine.add_lemma_code(&code, "test.lemma").unwrap();
 
-    let result = engine.evaluate("test", None, None);
+    let response = engine.evaluate("test", None, None).unwrap();
 
     // Note: This might not always trigger depending on system speed
     // But the infrastructure should be in place
-    if let Err(LemmaError::ResourceLimitExceeded { limit_name, .. }) = result {
-        assert_eq!(limit_name, "max_evaluation_time_ms");
+    if let Some(limit) = response.limit_exceeded {
+        assert_eq!(limit.limit_name, "max_evaluation_time_ms");
     }
+    assert_eq!(response.results.len(), 1000);
 }
 
 #[test]
@@ -120,3 +121,114 @@ fn test_expression_depth_limit() {
         _ => panic!("Expected ResourceLimitExceeded error for deep nesting"),
     }
 }
+
+fn rule_chain(length: usize) -> String {
+    let mut code = String::from("doc test\nfact x = 1\nrule r0 = x\n");
+    for i in 1..length {
+        code.push_str(&format!("rule r{} = r{}? + 1\n", i, i - 1));
+    }
+    code
+}
+
+#[test]
+fn test_rule_dependency_depth_limit() {
+    let limits = ResourceLimits {
+        max_rule_dependency_depth: 5,
+        ..ResourceLimits::default()
+    };
+    let mut engine = Engine::with_limits(limits);
+    engine
+        .add_lemma_code(&rule_chain(10), "test.lemma")
+        .unwrap();
+
+    match engine.evaluate("test", None, None) {
+        Err(LemmaError::ResourceLimitExceeded {
+            limit_name,
+            actual_value,
+            suggestion,
+            ..
+        }) => {
+            assert_eq!(limit_name, "max_rule_dependency_depth");
+            assert_eq!(actual_value, "6 rules");
+            assert!(suggestion.contains("r6? -> r5? -> r4? -> r3? -> ..."));
+        }
+        other => panic!("Expected ResourceLimitExceeded error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_rule_dependency_depth_at_limit() {
+    let limits = ResourceLimits {
+        max_rule_dependency_depth: 5,
+        ..ResourceLimits::default()
+    };
+    let mut engine = Engine::with_limits(limits);
+    engine.add_lemma_code(&rule_chain(6), "test.lemma").unwrap();
+
+    let response = engine.evaluate("test", None, None).unwrap();
+    assert_eq!(response.results.len(), 6);
+}
+
+/// Every level references the level below twice, doubling the document instances
+fn doubling_documents(levels: usize) -> String {
+    let mut code = String::from("doc level0\nfact x = 1\nrule value = x\n");
+    for i in 1..=levels {
+        code.push_str(&format!(
+            "\ndoc level{i}\nfact left = doc level{prev}\nfact right = doc level{prev}\nrule value = left.value? + right.value?\n",
+            i = i,
+            prev = i - 1
+        ));
+    }
+    code
+}
+
+#[test]
+fn test_documents_per_evaluation_limit() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(&doubling_documents(20), "test.lemma")
+        .unwrap();
+
+    match engine.evaluate("level20", None, None) {
+        Err(LemmaError::ResourceLimitExceeded {
+            limit_name,
+            suggestion,
+            ..
+        }) => {
+            assert_eq!(limit_name, "max_documents_per_evaluation");
+            assert!(suggestion.contains("Document 'level20' reaches document"));
+        }
+        other => panic!("Expected ResourceLimitExceeded error, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_documents_per_evaluation_within_limit() {
+    let limits = ResourceLimits {
+        max_documents_per_evaluation: 7,
+        ..ResourceLimits::default()
+    };
+    let mut engine = Engine::with_limits(limits);
+    engine
+        .add_lemma_code(&doubling_documents(2), "test.lemma")
+        .unwrap();
+
+    let response = engine.evaluate("level2", None, None).unwrap();
+    assert_eq!(
+        response.results[0].result.as_ref().unwrap().to_string(),
+        "4"
+    );
+
+    let limits = ResourceLimits {
+        max_documents_per_evaluation: 6,
+        ..ResourceLimits::default()
+    };
+    let mut engine = Engine::with_limits(limits);
+    engine
+        .add_lemma_code(&doubling_documents(2), "test.lemma")
+        .unwrap();
+    assert!(matches!(
+        engine.evaluate("level2", None, None),
+        Err(LemmaError::ResourceLimitExceeded { .. })
+    ));
+}
@@ -0,0 +1,88 @@
+//! Rule descriptions: `"""..."""` after a rule
+//!
+//! Key behaviors:
+//! 1. The description is stored on the rule, trimmed
+//! 2. Evaluation results carry the rule's description, also when vetoed or missing facts
+//! 3. Rules without a description have none
+//! 4. The printed rule keeps its description
+
+mod common;
+
+use common::rule_result;
+use lemma::Engine;
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = [number]
+fact price = 10
+rule subtotal = quantity * price """ Price of all items """
+rule total = subtotal?
+  unless quantity > 100 then veto "too many"
+  """Total price incl. discounts"""
+rule shipping = 5
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_description_is_stored_on_the_rule() {
+    let engine = engine();
+    let descriptions: Vec<(&str, Option<&str>)> = engine
+        .get_document_rules("pricing")
+        .into_iter()
+        .map(|rule| (rule.name.as_str(), rule.description.as_deref()))
+        .collect();
+    assert_eq!(
+        descriptions,
+        vec![
+            ("subtotal", Some("Price of all items")),
+            ("total", Some("Total price incl. discounts")),
+            ("shipping", None),
+        ]
+    );
+}
+
+#[test]
+fn test_results_carry_the_description() {
+    let engine = engine();
+    for (overrides, vetoed) in [(vec!["quantity=2"], false), (vec!["quantity=200"], true)] {
+        let facts = engine.parse_facts("pricing", &overrides).unwrap();
+        let response = engine.evaluate("pricing", None, Some(facts)).unwrap();
+        let total = rule_result(&response, "total");
+        assert_eq!(total.veto_message.is_some(), vetoed);
+        assert_eq!(
+            total.description.as_deref(),
+            Some("Total price incl. discounts")
+        );
+        let shipping = rule_result(&response, "shipping");
+        assert_eq!(shipping.description, None);
+    }
+
+    let response = engine.evaluate("pricing", None, None).unwrap();
+    let subtotal = rule_result(&response, "subtotal");
+    assert!(subtotal.missing_facts.is_some());
+    assert_eq!(subtotal.description.as_deref(), Some("Price of all items"));
+}
+
+#[test]
+fn test_printed_rule_keeps_description() {
+    let engine = engine();
+    let printed = engine.get_document("pricing").unwrap().to_string();
+    assert!(
+        printed.contains("rule subtotal = quantity * price \"\"\"Price of all items\"\"\""),
+        "{}",
+        printed
+    );
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+    assert_eq!(
+        reparsed.get_document_rules("pricing")[1]
+            .description
+            .as_deref(),
+        Some("Total price incl. discounts")
+    );
+}
@@ -0,0 +1,115 @@
+//! Public and internal rules
+//!
+//! Key behaviors:
+//! 1. Rules are public unless marked `internal`; checks can be marked too
+//! 2. Internal rules are evaluated and returned by default
+//! 3. `public_rules_only` leaves them out, but public rules still use them
+//! 4. Requesting an internal rule by name fails under `public_rules_only`
+//! 5. The modifier round-trips through `Display`
+
+use lemma::{Engine, EvaluationOptions, LiteralValue, RuleVisibility};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc pricing
+fact cost = 100
+internal rule base_margin = cost * 15%
+public rule price = cost + base_margin?
+internal check positive = cost > 0 else veto "cost must be positive"
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+fn names(response: &lemma::Response) -> Vec<&str> {
+    let mut names: Vec<&str> = response
+        .results
+        .iter()
+        .map(|result| result.rule_name.as_str())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_visibility_is_parsed() {
+    let engine = engine();
+    let visibility: Vec<_> = engine
+        .get_document_rules("pricing")
+        .iter()
+        .map(|rule| (rule.name.as_str(), rule.visibility))
+        .collect();
+    assert_eq!(
+        visibility,
+        vec![
+            ("base_margin", RuleVisibility::Internal),
+            ("price", RuleVisibility::Public),
+            ("positive", RuleVisibility::Internal),
+        ]
+    );
+}
+
+#[test]
+fn test_internal_rules_are_returned_by_default() {
+    let response = engine().evaluate("pricing", None, None).unwrap();
+    assert_eq!(names(&response), vec!["base_margin", "positive", "price"]);
+}
+
+#[test]
+fn test_public_rules_only_hides_internal_rules() {
+    let options = EvaluationOptions::new().with_public_rules_only(true);
+    let response = engine()
+        .evaluate_with_options("pricing", None, None, &options)
+        .unwrap();
+    assert_eq!(names(&response), vec!["price"]);
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(115)))
+    );
+}
+
+#[test]
+fn test_requesting_internal_rule_fails() {
+    let options = EvaluationOptions::new().with_public_rules_only(true);
+    let error = engine()
+        .evaluate_with_options(
+            "pricing",
+            Some(vec!["base_margin".to_string()]),
+            None,
+            &options,
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("is internal"), "{}", error);
+
+    let response = engine()
+        .evaluate_with_options(
+            "pricing",
+            Some(vec!["base_margin".to_string()]),
+            None,
+            &EvaluationOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(names(&response), vec!["base_margin"]);
+}
+
+#[test]
+fn test_modifier_round_trips() {
+    let engine = engine();
+    let printed = engine.get_document("pricing").unwrap().to_string();
+    assert!(
+        printed.contains("internal rule base_margin = "),
+        "{}",
+        printed
+    );
+    assert!(printed.contains("\nrule price = "), "{}", printed);
+
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+    let rules = reparsed.get_document_rules("pricing");
+    assert!(rules[0].is_internal());
+    assert!(!rules[1].is_internal());
+    assert!(rules[2].is_internal());
+}
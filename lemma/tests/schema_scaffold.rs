@@ -0,0 +1,151 @@
+//! Scaffolding fact declarations from a JSON Schema
+//!
+//! Key behaviors:
+//! 1. JSON types map to fact types, `x-lemma-type` and `x-lemma-unit` to unit types
+//! 2. Nested objects become fact groups and property names become snake case
+//! 3. Defaults become fact values, enums a rule vetoing other values
+//! 4. Local `$ref`s are followed
+//! 5. Exported fact schemas scaffold back to the same fact types
+//! 6. Properties without a fact type are an error
+
+use lemma::{scaffold_document, Engine, LiteralValue};
+use serde_json::json;
+
+fn scaffold(schema: serde_json::Value) -> String {
+    scaffold_document(&schema, "order").unwrap()
+}
+
+#[test]
+fn test_types_map_to_fact_types() {
+    let code = scaffold(json!({
+        "type": "object",
+        "properties": {
+            "note": {"type": "string"},
+            "quantity": {"type": "integer"},
+            "express": {"type": "boolean"},
+            "ordered": {"type": "string", "format": "date"},
+            "weight": {"type": "number", "x-lemma-unit": "kilogram"},
+            "price": {"type": "string", "x-lemma-type": "money"}
+        }
+    }));
+    for line in [
+        "fact note = [text]",
+        "fact quantity = [number]",
+        "fact express = [boolean]",
+        "fact ordered = [date]",
+        "fact weight = [mass]",
+        "fact price = [money]",
+    ] {
+        assert!(code.contains(line), "missing '{}' in:\n{}", line, code);
+    }
+}
+
+#[test]
+fn test_nested_objects_become_fact_groups() {
+    let code = scaffold(json!({
+        "type": "object",
+        "properties": {
+            "orderId": {"type": "string"},
+            "customer": {
+                "type": "object",
+                "properties": {"firstName": {"type": "string"}}
+            }
+        }
+    }));
+    assert!(code.contains("fact order_id = [text]"), "{}", code);
+    assert!(
+        code.contains("fact customer.first_name = [text]"),
+        "{}",
+        code
+    );
+
+    let mut engine = Engine::new();
+    engine.add_lemma_code(&code, "order.lemma").unwrap();
+}
+
+#[test]
+fn test_defaults_and_enums() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "status": {"type": "string", "enum": ["open", "paid"], "default": "open"},
+            "discount": {"type": "number", "x-lemma-type": "percentage", "default": 0.1}
+        }
+    });
+    let mut engine = Engine::new();
+    let code = engine
+        .add_document_from_schema("order", &schema.to_string(), "order.json")
+        .unwrap();
+    assert!(code.contains("fact status = \"open\""), "{}", code);
+    assert!(code.contains("fact discount = 10%"), "{}", code);
+
+    let valid = |status: &str| {
+        let facts = engine
+            .parse_facts("order", &[&format!("status=\"{}\"", status)])
+            .unwrap();
+        let response = engine
+            .evaluate("order", Some(vec!["valid_status".to_string()]), Some(facts))
+            .unwrap();
+        response.results[0].clone()
+    };
+    assert_eq!(
+        valid("paid").result,
+        Some(LiteralValue::Text("paid".to_string()))
+    );
+    assert_eq!(
+        valid("lost").veto_message.as_deref(),
+        Some("status must be one of: open, paid")
+    );
+}
+
+#[test]
+fn test_references_are_followed() {
+    let code = scaffold(json!({
+        "type": "object",
+        "properties": {"shipping": {"$ref": "#/$defs/address"}},
+        "$defs": {
+            "address": {"type": "object", "properties": {"city": {"type": "string"}}}
+        }
+    }));
+    assert!(code.contains("fact shipping.city = [text]"), "{}", code);
+}
+
+#[test]
+fn test_exported_schema_scaffolds_back() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc order\nfact quantity = [number]\nfact weight = 2 kilograms\nfact discount = 10%",
+            "order.lemma",
+        )
+        .unwrap();
+    let schema = engine.fact_schema("order").unwrap();
+
+    let code = scaffold_document(&schema, "copy").unwrap();
+    assert!(code.contains("fact quantity = [number]"), "{}", code);
+    assert!(code.contains("fact weight = 2 kilogram"), "{}", code);
+    assert!(code.contains("fact discount = 10%"), "{}", code);
+}
+
+#[test]
+fn test_unsupported_properties_are_errors() {
+    let error = scaffold_document(
+        &json!({"type": "object", "properties": {"items": {"type": "array"}}}),
+        "order",
+    )
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("Property 'items' has type 'array'"),
+        "{}",
+        error
+    );
+
+    let error = scaffold_document(
+        &json!({"type": "object", "properties": {"weight": {"type": "number", "x-lemma-unit": "furlongs per fortnight"}}}),
+        "order",
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("unknown unit"), "{}", error);
+}
@@ -0,0 +1,142 @@
+//! Picking one of several candidates: `lowest of`, `highest of`, `earliest of`, `latest of`
+//!
+//! Key behaviors:
+//! 1. The winning candidate's value is the result, ties go to the first
+//! 2. The trace records all candidate values and which one won
+//! 3. Dates use `earliest`/`latest`; mixing them up is rejected
+//! 4. A vetoed candidate vetoes the selection
+//! 5. The expression round-trips through `Display`
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LiteralValue, OperationRecord, Response};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc quotes
+fact quote_a = 120 EUR
+fact quote_b = [money]
+fact start = 2024-03-01
+fact notice = 2024-02-10
+rule offer_c = 130 EUR
+  unless quote_b > 1000 EUR then veto "Quote out of range"
+rule best_price = lowest of quote_a, quote_b, offer_c?
+rule worst_price = highest of quote_a, quote_b, offer_c?
+rule deadline = earliest of start, notice + 30 days
+rule begin = latest of start, notice
+"#;
+
+fn evaluate(quote_b: &str) -> Response {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "quotes.lemma").unwrap();
+    let quote_b = format!("quote_b={}", quote_b);
+    let facts = engine.parse_facts("quotes", &[&quote_b]).unwrap();
+    engine.evaluate("quotes", None, Some(facts)).unwrap()
+}
+
+fn eur(amount: i64) -> String {
+    LiteralValue::Unit(lemma::NumericUnit::Money(
+        Decimal::from(amount),
+        lemma::MoneyUnit::Eur,
+    ))
+    .to_string()
+}
+
+#[test]
+fn test_winner_is_the_result() {
+    let response = evaluate("110 EUR");
+    let best = rule_result(&response, "best_price")
+        .result
+        .as_ref()
+        .unwrap();
+    assert_eq!(best.to_string(), eur(110));
+    let worst = rule_result(&response, "worst_price")
+        .result
+        .as_ref()
+        .unwrap();
+    assert_eq!(worst.to_string(), eur(130));
+
+    let response = evaluate("120 EUR");
+    let selected = rule_result(&response, "best_price")
+        .operations
+        .iter()
+        .find_map(|operation| match operation {
+            OperationRecord::CandidateSelected { winner, index, .. } => Some((winner, *index)),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(selected, (&"quote_a".to_string(), 0));
+}
+
+#[test]
+fn test_trace_records_candidates() {
+    let response = evaluate("110 EUR");
+    let operation = rule_result(&response, "best_price")
+        .operations
+        .iter()
+        .find(|operation| matches!(operation, OperationRecord::CandidateSelected { .. }))
+        .unwrap();
+    let OperationRecord::CandidateSelected {
+        operation,
+        inputs,
+        winner,
+        index,
+        ..
+    } = operation
+    else {
+        unreachable!();
+    };
+    assert_eq!(operation, "lowest");
+    assert_eq!(inputs.len(), 3);
+    assert_eq!(winner, "quote_b");
+    assert_eq!(*index, 1);
+}
+
+#[test]
+fn test_dates() {
+    let response = evaluate("110 EUR");
+    assert!(rule_result(&response, "deadline")
+        .result
+        .as_ref()
+        .unwrap()
+        .to_string()
+        .starts_with("2024-03-01"));
+    assert!(rule_result(&response, "begin")
+        .result
+        .as_ref()
+        .unwrap()
+        .to_string()
+        .starts_with("2024-03-01"));
+
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            "doc dates\nfact a = 2024-01-01\nfact b = 2024-02-01\nrule first = lowest of a, b",
+            "dates.lemma",
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("earliest"), "{}", error);
+}
+
+#[test]
+fn test_vetoed_candidate_vetoes() {
+    let response = evaluate("2000 EUR");
+    let best = rule_result(&response, "best_price");
+    assert_eq!(best.result, None);
+    assert_eq!(best.veto_message.as_deref(), Some("Quote out of range"));
+}
+
+#[test]
+fn test_round_trips() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "quotes.lemma").unwrap();
+    let printed = engine.get_document("quotes").unwrap().to_string();
+    assert!(
+        printed.contains("rule best_price = lowest of quote_a, quote_b, offer_c?"),
+        "{}",
+        printed
+    );
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+}
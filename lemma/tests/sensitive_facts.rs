@@ -0,0 +1,177 @@
+//! Masking sensitive facts: `fact salary = [money] sensitive`
+//!
+//! Key behaviors:
+//! 1. Sensitive values are used in computation; rule results are not masked
+//! 2. The trace shows `***` for them and for values derived from them, also
+//!    through other rules; other values equal to a sensitive one stay visible
+//! 3. Facts of referenced documents can be sensitive, or be marked by an override
+//! 4. Override errors for sensitive facts don't repeat the value
+//! 5. The marker round-trips through `Display`
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LiteralValue, OperationRecord, Response, REDACTED};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc employee
+fact salary = [number] sensitive
+fact hours = 40
+rule hourly_rate = salary / hours
+rule well_paid = salary > 5000
+rule yearly = salary * 12
+rule yearly_bonus = yearly? / 12 + bonus
+fact bonus = 6000
+rule bonus_plus_hours = bonus + hours
+
+doc payroll
+fact staff = doc employee
+fact staff.hours = 32 sensitive
+rule part_time = staff.hours < 36
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "payroll.lemma").unwrap();
+    engine
+}
+
+fn evaluate(doc: &str, facts: &[&str]) -> Response {
+    let engine = engine();
+    let facts = engine.parse_facts(doc, facts).unwrap();
+    engine.evaluate(doc, None, Some(facts)).unwrap()
+}
+
+fn operations<'a>(response: &'a Response, rule: &str) -> &'a [OperationRecord] {
+    &rule_result(response, rule).operations
+}
+
+fn trace_values(operations: &[OperationRecord]) -> Vec<&LiteralValue> {
+    operations
+        .iter()
+        .flat_map(|operation| match operation {
+            OperationRecord::FactUsed { value, .. } => vec![value],
+            OperationRecord::OperationExecuted { inputs, result, .. } => {
+                inputs.iter().chain(std::iter::once(result)).collect()
+            }
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_values_are_used_but_masked() {
+    let response = evaluate("employee", &["salary=6000"]);
+    assert_eq!(
+        rule_result(&response, "hourly_rate").result,
+        Some(LiteralValue::Number(Decimal::from(150)))
+    );
+    assert_eq!(
+        rule_result(&response, "well_paid").result,
+        Some(LiteralValue::Boolean(true))
+    );
+
+    let values = trace_values(operations(&response, "hourly_rate"));
+    assert!(values.iter().any(|value| value.is_redacted()));
+    assert!(!values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(6000))));
+    // Other facts stay visible
+    assert!(values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(40))));
+    assert_eq!(
+        LiteralValue::redacted().to_string(),
+        format!("\"{}\"", REDACTED)
+    );
+}
+
+#[test]
+fn test_derived_values_are_masked_by_origin() {
+    let response = evaluate("employee", &["salary=6000"]);
+    let values = trace_values(operations(&response, "yearly"));
+    // The product isn't the fact's value, but derives from it
+    assert!(!values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(72000))));
+    assert!(values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(12))));
+
+    let used = operations(&response, "yearly_bonus");
+    assert!(used.iter().any(|operation| matches!(
+        operation,
+        OperationRecord::RuleUsed { value, .. } if value.is_redacted()
+    )));
+    let values = trace_values(used);
+    assert!(!values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(72000))));
+    // `bonus` holds the same value as `salary` but isn't sensitive
+    assert!(values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(6000))));
+
+    let values = trace_values(operations(&response, "bonus_plus_hours"));
+    assert!(
+        !values.iter().any(|value| value.is_redacted()),
+        "{:?}",
+        values
+    );
+}
+
+#[test]
+fn test_referenced_and_overridden_facts() {
+    let response = evaluate("payroll", &[]);
+    assert_eq!(
+        rule_result(&response, "part_time").result,
+        Some(LiteralValue::Boolean(true))
+    );
+    let values = trace_values(operations(&response, "part_time"));
+    assert!(
+        values.iter().any(|value| value.is_redacted()),
+        "{:?}",
+        values
+    );
+    assert!(!values
+        .iter()
+        .any(|value| **value == LiteralValue::Number(Decimal::from(32))));
+}
+
+#[test]
+fn test_override_errors_hide_the_value() {
+    let error = engine()
+        .parse_facts("employee", &["salary=lots of money", "hours=many"])
+        .unwrap_err();
+    let message = error.to_string();
+    assert!(!message.contains("lots of money"), "{}", message);
+    assert!(message.contains("salary=***"), "{}", message);
+    assert!(message.contains("hours=many"), "{}", message);
+}
+
+#[test]
+fn test_marker_round_trips() {
+    let engine = engine();
+    let printed = engine.get_document("employee").unwrap().to_string();
+    assert!(
+        printed.contains("fact salary = [number] sensitive"),
+        "{}",
+        printed
+    );
+    let mut reparsed = Engine::new();
+    reparsed.add_lemma_code(&printed, "printed.lemma").unwrap();
+    assert!(reparsed.get_document_facts("employee")[0].sensitive);
+
+    let error = Engine::new()
+        .add_lemma_code(
+            "doc a\nfact b = doc employee sensitive\ndoc employee\nfact x = 1",
+            "a.lemma",
+        )
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("can't be sensitive"),
+        "{}",
+        error
+    );
+}
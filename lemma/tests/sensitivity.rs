@@ -0,0 +1,121 @@
+//! Sensitivity of a rule result to its numeric facts
+//!
+//! Key behaviors:
+//! 1. Plain arithmetic over numbers is differentiated exactly, through rule references
+//! 2. Rules with unless clauses or units are differentiated numerically
+//! 3. Facts are reported with the most influential first
+//! 4. A rule without a result around the value gets no sensitivity, with a reason
+//! 5. Unknown and non-numeric facts are rejected
+
+use lemma::{Engine, LemmaFact, SensitivityMethod, SensitivityReport};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = 10
+fact price = 4
+fact shipping = 5
+fact weight = 2 kilograms
+fact name = "order"
+
+rule subtotal = quantity * price
+rule total = subtotal? + shipping
+rule squared = quantity ^ 2
+rule discounted = total?
+  unless quantity >= 100 then total? * 0.9
+rule heavy = (weight in grams) * 3
+rule limited = quantity * 2
+  unless quantity > 10 then veto "Too many"
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+fn report(rule: &str, facts: &[&str], at: &[&str]) -> SensitivityReport {
+    let engine = engine();
+    let at_facts: Vec<LemmaFact> = engine.parse_facts("pricing", at).unwrap();
+    engine
+        .sensitivity("pricing", rule, facts, at_facts)
+        .unwrap()
+}
+
+#[test]
+fn test_symbolic_through_rule_references() {
+    let report = report("total", &["shipping", "quantity", "price"], &["price=6"]);
+    let facts: Vec<(&str, Option<Decimal>, SensitivityMethod)> = report
+        .facts
+        .iter()
+        .map(|f| (f.fact.as_str(), f.sensitivity, f.method))
+        .collect();
+    assert_eq!(
+        facts,
+        vec![
+            (
+                "price",
+                Some(Decimal::from(10)),
+                SensitivityMethod::Symbolic
+            ),
+            (
+                "quantity",
+                Some(Decimal::from(6)),
+                SensitivityMethod::Symbolic
+            ),
+            ("shipping", Some(Decimal::ONE), SensitivityMethod::Symbolic),
+        ]
+    );
+}
+
+#[test]
+fn test_symbolic_power() {
+    let report = report("squared", &["quantity"], &["quantity=3"]);
+    assert_eq!(report.facts[0].sensitivity, Some(Decimal::from(6)));
+    assert_eq!(report.facts[0].method, SensitivityMethod::Symbolic);
+}
+
+#[test]
+fn test_finite_difference_for_unless_clauses() {
+    let report = report("discounted", &["price"], &["quantity=200"]);
+    let fact = &report.facts[0];
+    assert_eq!(fact.method, SensitivityMethod::FiniteDifference);
+    assert_eq!(fact.sensitivity, Some(Decimal::from(180)));
+}
+
+#[test]
+fn test_finite_difference_for_units() {
+    let report = report("heavy", &["weight"], &[]);
+    let fact = &report.facts[0];
+    assert_eq!(fact.method, SensitivityMethod::FiniteDifference);
+    assert_eq!(fact.sensitivity, Some(Decimal::from(3000)));
+}
+
+#[test]
+fn test_one_sided_at_a_veto_boundary() {
+    let inside = report("limited", &["quantity"], &[]);
+    assert_eq!(inside.facts[0].sensitivity, Some(Decimal::from(2)));
+
+    let at_boundary = report("limited", &["quantity"], &["quantity=50"]);
+    assert_eq!(at_boundary.result, None);
+    assert_eq!(at_boundary.facts[0].sensitivity, None);
+    assert!(at_boundary.facts[0].reason.is_some());
+}
+
+#[test]
+fn test_invalid_facts() {
+    let engine = engine();
+    let error = engine
+        .sensitivity("pricing", "total", &["quantiy"], Vec::new())
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("did you mean 'quantity'?"),
+        "{}",
+        error
+    );
+
+    let error = engine
+        .sensitivity("pricing", "total", &["name"], Vec::new())
+        .unwrap_err();
+    assert!(error.to_string().contains("numeric"), "{}", error);
+}
@@ -0,0 +1,132 @@
+//! Shadow evaluation: trialling a changed document on the same inputs
+//!
+//! Key behaviors:
+//! 1. The primary results are returned as without a shadow
+//! 2. The shadow's results and the rules that differ are in `Response::shadow`
+//! 3. Values compare by meaning, so `1 kilogram` and `1000 grams` don't differ
+//! 4. A failing shadow is reported, it never fails the evaluation
+//! 5. Without a shadow there is nothing to compare
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, EvaluationOptions, LiteralValue, RuleResult, ShadowDiff};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = [number]
+rule price = quantity * 10
+rule allowed = true
+  unless quantity > 100 then veto "Too many"
+rule weight = 1 kilogram
+
+doc pricing_next
+fact quantity = [number]
+rule price = quantity * 9
+rule allowed = true
+  unless quantity > 50 then veto "Too many"
+rule weight = 1000 grams
+
+doc incomplete
+fact amount = [number]
+rule price = amount * 10
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+/// The value of `rule` among the shadow's results
+fn shadow_value(results: &[RuleResult], rule: &str) -> Option<LiteralValue> {
+    results
+        .iter()
+        .find(|result| result.rule_name == rule)
+        .unwrap_or_else(|| panic!("no shadow result for rule '{}'", rule))
+        .result
+        .clone()
+}
+
+fn number(value: i64) -> Option<LiteralValue> {
+    Some(LiteralValue::Number(Decimal::from(value)))
+}
+
+#[test]
+fn test_shadow_is_compared_with_primary() {
+    let engine = engine();
+    let facts = engine.parse_facts("pricing", &["quantity=60"]).unwrap();
+    let options = EvaluationOptions::new().with_shadow("pricing_next");
+    let response = engine
+        .evaluate_with_options("pricing", None, Some(facts), &options)
+        .unwrap();
+
+    assert_eq!(response.doc_name, "pricing");
+    assert_eq!(rule_result(&response, "price").result, number(600));
+
+    let mut shadow = response.shadow.expect("a shadow response");
+    assert_eq!(shadow.doc_name, "pricing_next");
+    assert_eq!(shadow.error, None);
+    assert_eq!(shadow_value(&shadow.results, "price"), number(540));
+    shadow.diffs.sort_by(|a, b| a.rule_name.cmp(&b.rule_name));
+    assert_eq!(
+        shadow.diffs,
+        vec![
+            ShadowDiff {
+                rule_name: "allowed".to_string(),
+                primary: Some(LiteralValue::Boolean(true)),
+                shadow: None,
+                primary_veto: None,
+                shadow_veto: Some("Too many".to_string()),
+            },
+            ShadowDiff {
+                rule_name: "price".to_string(),
+                primary: number(600),
+                shadow: number(540),
+                primary_veto: None,
+                shadow_veto: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_failing_shadow_is_reported() {
+    let engine = engine();
+    let facts = engine.parse_facts("pricing", &["quantity=1"]).unwrap();
+    let evaluate = |shadow: &str| {
+        let options = EvaluationOptions::new().with_shadow(shadow);
+        let response = engine
+            .evaluate_with_options("pricing", None, Some(facts.clone()), &options)
+            .unwrap();
+        assert_eq!(rule_result(&response, "price").result, number(10));
+        response.shadow.unwrap()
+    };
+
+    let missing = evaluate("missing");
+    assert!(missing.error.unwrap().contains("missing"));
+    assert!(missing.results.is_empty());
+
+    // The shadow lacks the fact, so its price has no value
+    let incomplete = evaluate("incomplete");
+    assert_eq!(incomplete.error, None);
+    let price = incomplete
+        .diffs
+        .iter()
+        .find(|diff| diff.rule_name == "price")
+        .expect("a diff for price");
+    assert_eq!(price.shadow, None);
+}
+
+#[test]
+fn test_no_shadow_by_default() {
+    let engine = engine();
+    let facts = engine.parse_facts("pricing", &["quantity=1"]).unwrap();
+    let response = engine.evaluate("pricing", None, Some(facts)).unwrap();
+    assert!(response.shadow.is_none());
+    assert!(serde_json::to_value(&response)
+        .unwrap()
+        .get("shadow")
+        .is_none());
+}
@@ -0,0 +1,101 @@
+//! Shape JSON with branch provenance
+//!
+//! Key behaviors:
+//! 1. Each branch lists the unless clause (index and span) it came from
+//! 2. The default expression is an origin without a clause index
+//! 3. Merged branches keep every origin
+//! 4. Conditions serialize as Lemma text with grouping and as an AST tree
+
+use lemma::{Engine, Target};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc shipping
+fact weight = [number]
+fact express = [boolean]
+rule cost = 5
+  unless weight > 10 then 20
+  unless express then 20
+  unless weight > 100 then veto "Too heavy"
+"#;
+
+fn shape_json(target: Target) -> Value {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shipping.lemma").unwrap();
+    let shape = engine
+        .invert_shape("shipping", "cost", target, HashMap::new())
+        .unwrap();
+    serde_json::to_value(&shape).unwrap()
+}
+
+#[test]
+fn test_branch_origin_points_at_unless_clause() {
+    let shape = shape_json(Target::veto(None));
+    let branches = shape["branches"].as_array().unwrap();
+    assert_eq!(branches.len(), 1);
+
+    let origins = branches[0]["origins"].as_array().unwrap();
+    assert_eq!(origins.len(), 1);
+    assert_eq!(origins[0]["clause"], json!(2));
+    assert!(origins[0]["span"]["line"].as_u64().unwrap() > 0);
+    assert!(origins[0]["span"]["end"].as_u64() > origins[0]["span"]["start"].as_u64());
+}
+
+#[test]
+fn test_default_and_merged_origins() {
+    let shape = shape_json(Target::any_value());
+    let branches = shape["branches"].as_array().unwrap();
+
+    let default = branches
+        .iter()
+        .find(|b| b["outcome"]["expression"] == json!("5"))
+        .unwrap();
+    assert_eq!(default["origins"][0]["clause"], Value::Null);
+
+    let twenty = branches
+        .iter()
+        .find(|b| b["outcome"]["expression"] == json!("20"))
+        .unwrap();
+    let clauses: Vec<&Value> = twenty["origins"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|o| &o["clause"])
+        .collect();
+    assert_eq!(clauses, vec![&json!(0), &json!(1)]);
+}
+
+#[test]
+fn test_condition_text_and_ast() {
+    let shape = shape_json(Target::veto(None));
+    let branch = &shape["branches"][0];
+
+    assert_eq!(branch["condition"], json!("weight > 100"));
+    let ast = &branch["condition_ast"];
+    assert_eq!(ast["type"], json!("comparison"));
+    assert_eq!(ast["operator"], json!(">"));
+    assert_eq!(ast["left"], json!({"type": "fact", "reference": "weight"}));
+    assert_eq!(ast["right"]["type"], json!("literal"));
+    assert_eq!(ast["right"]["text"], json!("100"));
+}
+
+#[test]
+fn test_condition_text_keeps_grouping() {
+    let shape = shape_json(Target::any_value());
+    let default = shape["branches"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|b| b["outcome"]["expression"] == json!("5"))
+        .unwrap();
+    let condition = default["condition"].as_str().unwrap();
+
+    // The text parses back as Lemma
+    let code = format!(
+        "doc check\nfact weight = [number]\nfact express = [boolean]\nrule applies = {}\n",
+        condition
+    );
+    let mut engine = Engine::new();
+    engine.add_lemma_code(&code, "check.lemma").unwrap();
+}
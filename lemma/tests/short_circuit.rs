@@ -0,0 +1,114 @@
+//! Short-circuit evaluation of `and` and `or`
+//!
+//! Key behaviors:
+//! 1. Facts on the right aren't needed once the left operand decides
+//! 2. A veto in a skipped operand doesn't apply
+//! 3. Skipped operands are recorded as a `ShortCircuit` operation
+//! 4. Long chains stop at the first deciding operand
+//! 5. A rule with missing facts only fails rules that actually reach it
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, LiteralValue, OperationRecord, Response, RuleResult};
+
+const CODE: &str = r#"
+doc access
+fact is_admin = [boolean]
+fact is_owner = [boolean]
+fact is_active = [boolean]
+fact age = [number]
+
+rule adult = age >= 18
+rule blocked = true
+  unless not is_admin then veto "account blocked"
+
+rule can_edit = is_admin or is_owner
+rule can_vote = is_active and adult?
+rule guarded = is_admin or blocked?
+rule chain = is_active and is_owner and is_admin and age > 10
+"#;
+
+fn evaluate(overrides: &[&str]) -> Response {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "access.lemma").unwrap();
+    let facts = engine.parse_facts("access", overrides).unwrap();
+    engine.evaluate("access", None, Some(facts)).unwrap()
+}
+
+fn boolean(result: &RuleResult) -> Option<bool> {
+    match &result.result {
+        Some(LiteralValue::Boolean(b)) => Some(*b),
+        _ => None,
+    }
+}
+
+#[test]
+fn test_right_operand_facts_not_required() {
+    let response = evaluate(&["is_admin=true"]);
+    assert_eq!(boolean(rule_result(&response, "can_edit")), Some(true));
+
+    let response = evaluate(&["is_active=false"]);
+    let can_vote = rule_result(&response, "can_vote");
+    assert_eq!(boolean(can_vote), Some(false));
+    assert!(can_vote.missing_facts.is_none());
+}
+
+#[test]
+fn test_missing_fact_still_reported_when_reached() {
+    let response = evaluate(&["is_admin=false"]);
+    let can_edit = rule_result(&response, "can_edit");
+    assert!(can_edit.result.is_none());
+    assert_eq!(
+        can_edit.missing_facts.as_deref(),
+        Some(&["is_owner".to_string()][..])
+    );
+
+    let response = evaluate(&["is_active=true"]);
+    let can_vote = rule_result(&response, "can_vote");
+    assert!(can_vote.result.is_none());
+    assert!(can_vote.missing_facts.is_some());
+}
+
+#[test]
+fn test_veto_in_skipped_operand_does_not_apply() {
+    let response = evaluate(&["is_admin=true"]);
+    let guarded = rule_result(&response, "guarded");
+    assert_eq!(boolean(guarded), Some(true));
+    assert!(guarded.veto_message.is_none());
+
+    let response = evaluate(&["is_admin=false"]);
+    let guarded = rule_result(&response, "guarded");
+    assert_eq!(guarded.veto_message.as_deref(), Some("account blocked"));
+}
+
+#[test]
+fn test_short_circuit_is_traced() {
+    let response = evaluate(&["is_active=false"]);
+    let chain = rule_result(&response, "chain");
+    assert_eq!(boolean(chain), Some(false));
+
+    let skipped = chain.operations.iter().find_map(|op| match op {
+        OperationRecord::ShortCircuit {
+            operation,
+            skipped_operands,
+            ..
+        } => Some((operation.as_str(), *skipped_operands)),
+        _ => None,
+    });
+    assert_eq!(skipped, Some(("and", 3)));
+}
+
+#[test]
+fn test_chain_evaluates_all_operands_when_needed() {
+    let response = evaluate(&["is_active=true", "is_owner=true", "is_admin=true", "age=30"]);
+    let chain = rule_result(&response, "chain");
+    assert_eq!(boolean(chain), Some(true));
+    assert!(!chain
+        .operations
+        .iter()
+        .any(|op| matches!(op, OperationRecord::ShortCircuit { .. })));
+
+    let response = evaluate(&["is_active=true", "is_owner=true", "is_admin=false"]);
+    assert_eq!(boolean(rule_result(&response, "chain")), Some(false));
+}
@@ -0,0 +1,112 @@
+//! Golden-file snapshots of evaluation responses with `lemma::testing`
+//!
+//! Key behaviors:
+//! 1. Updating writes the response as pretty JSON with results sorted by rule
+//!    name, creating the directory
+//! 2. A matching response passes, a changed one fails with a line diff
+//! 3. A missing snapshot fails unless updating
+//! 4. Evaluation errors are reported as such
+
+use lemma::testing::{diff, SnapshotError, Snapshots};
+use lemma::Engine;
+use std::path::PathBuf;
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = [number]
+fact price = 10
+rule total = quantity * price
+rule bulk = quantity >= 100
+"#;
+
+fn engine(code: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "pricing.lemma").unwrap();
+    engine
+}
+
+fn snapshot_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lemma_snapshots_{}_{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_update_writes_and_check_matches() {
+    let dir = snapshot_dir("update");
+    let engine = engine(CODE);
+    let updating = Snapshots::new(dir.join("nested")).with_update(true);
+    updating
+        .check_evaluation(&engine, "bulk", "pricing", &["quantity=100"])
+        .unwrap();
+
+    let written = std::fs::read_to_string(updating.path("bulk")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&written).unwrap();
+    assert_eq!(json["doc_name"], "pricing");
+    let rules: Vec<&str> = json["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|result| result["rule_name"].as_str().unwrap())
+        .collect();
+    assert_eq!(rules, ["bulk", "total"]);
+    assert!(written.ends_with('\n'));
+
+    Snapshots::new(dir.join("nested"))
+        .with_update(false)
+        .assert_evaluation(&engine, "bulk", "pricing", &["quantity=100"]);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_changed_response_fails_with_diff() {
+    let dir = snapshot_dir("mismatch");
+    let snapshots = Snapshots::new(&dir).with_update(true);
+    snapshots
+        .check_evaluation(&engine(CODE), "bulk", "pricing", &["quantity=100"])
+        .unwrap();
+
+    let changed = engine(&CODE.replace("fact price = 10", "fact price = 12"));
+    let error = snapshots
+        .with_update(false)
+        .check_evaluation(&changed, "bulk", "pricing", &["quantity=100"])
+        .unwrap_err();
+    let SnapshotError::Mismatch { diff, .. } = &error else {
+        panic!("expected a mismatch, got {}", error);
+    };
+    assert!(diff.starts_with("@@ -"), "{}", diff);
+    assert!(diff
+        .lines()
+        .any(|line| line.starts_with('-') && line.contains("1000")));
+    assert!(diff
+        .lines()
+        .any(|line| line.starts_with('+') && line.contains("1200")));
+    assert!(error.to_string().contains("LEMMA_UPDATE_SNAPSHOTS=1"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_missing_snapshot_and_evaluation_errors() {
+    let dir = snapshot_dir("missing");
+    let snapshots = Snapshots::new(&dir).with_update(false);
+    let engine = engine(CODE);
+    assert!(matches!(
+        snapshots.check_evaluation(&engine, "none", "pricing", &["quantity=1"]),
+        Err(SnapshotError::Missing { .. })
+    ));
+    assert!(matches!(
+        snapshots.check_evaluation(&engine, "none", "unknown_doc", &[]),
+        Err(SnapshotError::Evaluation(_))
+    ));
+}
+
+#[test]
+fn test_diff_shows_changes_with_context() {
+    let expected = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+    let actual = "a\nB\nc\nd\ne\nf\ng\nh\ni\nJ\n";
+    assert_eq!(
+        diff(expected, actual),
+        "@@ -1 +1 @@\n a\n-b\n+B\n c\n d\n e\n@@ -7 +7 @@\n g\n h\n i\n-j\n+J\n"
+    );
+    assert_eq!(diff("same\n", "same\n"), "");
+}
@@ -0,0 +1,90 @@
+//! Evaluating only the rules of referenced documents that are used
+//!
+//! Key behaviors:
+//! 1. Of a referenced document, only the rules used from it, directly or through its other rules, are evaluated
+//! 2. With requested rules, documents only the other rules reach don't count towards `max_documents_per_evaluation`
+//! 3. Invariants are still checked, with the rules they use
+
+mod common;
+
+use common::rule_result;
+use lemma::{DiagnosticKind, Engine, LemmaError, ResourceLimits};
+
+const CODE: &str = r#"
+doc library
+fact weight = 4
+fact zero = 0
+rule base_cost = 5
+rule shipping_cost = base_cost? + weight
+rule broken = weight / zero
+
+doc far
+fact amount = 1
+rule value = amount
+
+doc middle
+fact far = doc far
+rule value = far.value?
+
+doc order
+fact library = doc library
+fact middle = doc middle
+rule shipping = library.shipping_cost?
+rule remote = middle.value?
+invariant shipping? < 100
+"#;
+
+fn engine(limits: ResourceLimits) -> Engine {
+    let mut engine = Engine::with_limits(limits);
+    engine.add_lemma_code(CODE, "library.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_unused_rules_of_referenced_documents_are_not_evaluated() {
+    // `library.broken` divides by zero, which fails the evaluation if it runs
+    let response = engine(ResourceLimits::default())
+        .evaluate("order", None, None)
+        .unwrap();
+    let shipping = rule_result(&response, "shipping");
+    assert_eq!(shipping.result.as_ref().unwrap().to_string(), "9");
+}
+
+#[test]
+fn test_requested_rules_only_reach_the_documents_they_use() {
+    // order and library, while `remote` would reach middle and far too
+    let limits = ResourceLimits {
+        max_documents_per_evaluation: 2,
+        ..ResourceLimits::default()
+    };
+    let engine = engine(limits);
+
+    let response = engine
+        .evaluate("order", Some(vec!["shipping".to_string()]), None)
+        .unwrap();
+    assert_eq!(response.results.len(), 1);
+    assert_eq!(
+        response.results[0].result.as_ref().unwrap().to_string(),
+        "9"
+    );
+
+    assert!(matches!(
+        engine.evaluate("order", Some(vec!["remote".to_string()]), None),
+        Err(LemmaError::ResourceLimitExceeded { .. })
+    ));
+}
+
+#[test]
+fn test_invariants_are_checked_with_requested_rules() {
+    let engine = engine(ResourceLimits::default());
+    let facts = engine
+        .parse_facts("order", &["library.weight=200"])
+        .unwrap();
+    let response = engine
+        .evaluate("order", Some(vec!["remote".to_string()]), Some(facts))
+        .unwrap();
+    assert!(response
+        .diagnostics
+        .iter()
+        .any(|d| d.kind == DiagnosticKind::InvariantViolated));
+}
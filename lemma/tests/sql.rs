@@ -0,0 +1,81 @@
+//! Translating rules to SQL with `Engine::to_sql`
+//!
+//! Key behaviors:
+//! 1. Typed facts become quoted columns, facts with values are inlined
+//! 2. Rule references are inlined and unless clauses become `CASE` expressions
+//! 3. Literals and identifiers follow the dialect
+//! 4. Rules outside the supported subset are rejected
+
+use lemma::{Engine, SqlDialect};
+
+const CODE: &str = r#"
+doc eligibility
+fact age = [number]
+fact country = [text]
+fact member = [boolean]
+fact minimum_age = 18
+rule adult = age >= minimum_age
+rule eligible = adult? and (country is "NL" or member)
+  unless country is "O'Brien's land" then veto "Not served"
+rule score = age * 2
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_simple_comparison() {
+    let sql = engine()
+        .to_sql("eligibility", "adult", SqlDialect::Postgres)
+        .unwrap();
+    assert_eq!(sql, r#"("age" >= 18)"#);
+}
+
+#[test]
+fn test_rule_references_and_unless_clauses() {
+    let sql = engine()
+        .to_sql("eligibility", "eligible", SqlDialect::Postgres)
+        .unwrap();
+    assert_eq!(
+        sql,
+        r#"CASE WHEN (("country" = 'O''Brien''s land')) IS NULL THEN NULL WHEN ("country" = 'O''Brien''s land') THEN FALSE ELSE ((("age" >= 18)) AND (("country" = 'NL') OR "member")) END"#
+    );
+}
+
+#[test]
+fn test_dialects() {
+    let engine = engine();
+    let mysql = engine
+        .to_sql("eligibility", "eligible", SqlDialect::MySql)
+        .unwrap();
+    assert!(mysql.contains("(`country` = 'NL')"), "{}", mysql);
+    assert!(mysql.contains("THEN FALSE"), "{}", mysql);
+
+    let sqlite = engine
+        .to_sql("eligibility", "eligible", SqlDialect::Sqlite)
+        .unwrap();
+    assert!(sqlite.contains(r#"("country" = 'NL')"#), "{}", sqlite);
+    assert!(sqlite.contains("THEN 0"), "{}", sqlite);
+
+    assert_eq!(
+        "postgresql".parse::<SqlDialect>().unwrap(),
+        SqlDialect::Postgres
+    );
+    assert!("oracle".parse::<SqlDialect>().is_err());
+}
+
+#[test]
+fn test_unsupported_expressions_are_rejected() {
+    let engine = engine();
+    let error = engine
+        .to_sql("eligibility", "score", SqlDialect::Postgres)
+        .unwrap_err();
+    assert!(error.to_string().contains("arithmetic"), "{}", error);
+
+    assert!(engine
+        .to_sql("eligibility", "missing", SqlDialect::Postgres)
+        .is_err());
+}
@@ -0,0 +1,93 @@
+//! Strict unit mode tests
+//!
+//! Key behaviors:
+//! 1. Without strict units, `money * mass` evaluates to a plain number
+//! 2. With strict units, arithmetic across kinds of units is rejected at load time
+//! 3. Units are followed through typed facts, rule references and referenced documents
+//! 4. Same-kind units, plain numbers and explicit conversions stay allowed
+
+use lemma::{Engine, LemmaError};
+
+const SHIPPING: &str = r#"
+doc shipping
+fact rate = 2 USD
+fact weight = 5 kilograms
+rule cost = rate * weight
+"#;
+
+fn strict() -> Engine {
+    Engine::new().with_strict_units(true)
+}
+
+fn strict_error(code: &str) -> String {
+    match strict().add_lemma_code(code, "test.lemma") {
+        Err(LemmaError::Semantic(details)) => {
+            assert!(details.suggestion.is_some());
+            details.message
+        }
+        other => panic!("expected a semantic error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_unit_dropping_allowed_by_default() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(SHIPPING, "test.lemma").unwrap();
+    assert!(engine.evaluate("shipping", None, None).is_ok());
+}
+
+#[test]
+fn test_strict_units_rejects_money_times_mass() {
+    let message = strict_error(SHIPPING);
+    assert!(message.contains("Rule 'cost'"));
+    assert!(message.contains("money * mass"));
+}
+
+#[test]
+fn test_strict_units_rejects_adding_unrelated_units() {
+    let message = strict_error(
+        "doc trip\nfact distance = [length]\nfact duration = [duration]\nrule total = distance + duration",
+    );
+    assert!(message.contains("length + duration"));
+}
+
+#[test]
+fn test_strict_units_follows_rules_and_documents() {
+    let message = strict_error(
+        r#"
+doc parcel
+fact weight = [mass]
+rule gross = weight * 1.1
+
+doc invoice
+fact parcel = doc parcel
+fact price = 10 EUR
+rule charge = price
+rule total = charge? + parcel.gross?
+"#,
+    );
+    assert!(message.contains("Rule 'total'"));
+    assert!(message.contains("money + mass"));
+}
+
+#[test]
+fn test_strict_units_allows_consistent_arithmetic() {
+    let mut engine = strict();
+    engine
+        .add_lemma_code(
+            r#"
+doc order
+fact price = 10 EUR
+fact quantity = 3
+fact rate = [number]
+fact weight = 2 kilograms
+rule subtotal = price * quantity
+rule shipping = (rate * weight) in grams
+rule heavier = weight + 500 grams
+rule discounted = subtotal? - subtotal? * 10%
+"#,
+            "test.lemma",
+        )
+        .unwrap();
+    assert!(engine.get_document("order").is_some());
+}
@@ -0,0 +1,153 @@
+//! Sweeping a rule across a range of one fact with `Engine::sweep`
+//!
+//! Key behaviors:
+//! 1. The rule is evaluated for each value from start to end in steps
+//! 2. Other facts keep their overrides, an override for the swept fact is ignored
+//! 3. Units of the range are kept and facts in referenced documents can be swept
+//! 4. `changes` lists where a result flips, including to and from a veto
+//! 5. Invalid ranges, steps and unknown facts are rejected
+
+use lemma::{Engine, LiteralValue, Sweep};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const CODE: &str = r#"
+doc customer
+fact age = [number]
+
+doc pricing
+fact quantity = [number]
+fact price = [number]
+fact weight = [mass]
+fact buyer = doc customer
+rule total = quantity * price
+rule bulk = quantity >= 20
+rule shippable = weight <= 10 kilograms
+  unless weight > 15 kilograms then veto "Too heavy"
+rule adult = buyer.age >= 18
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "pricing.lemma").unwrap();
+    engine
+}
+
+fn literal(text: &str) -> LiteralValue {
+    lemma::parse_literal(text).unwrap()
+}
+
+fn sweep(rule: &str, fact: &str, from: &str, to: &str, step: &str, facts: &[&str]) -> Sweep {
+    let engine = engine();
+    let fixed = engine.parse_facts("pricing", facts).unwrap();
+    engine
+        .sweep(
+            "pricing",
+            rule,
+            fact,
+            literal(from)..=literal(to),
+            Decimal::from_str(step).unwrap(),
+            fixed,
+        )
+        .unwrap()
+}
+
+fn results(sweep: &Sweep) -> Vec<String> {
+    sweep
+        .points
+        .iter()
+        .map(|p| match (&p.result, &p.veto_message) {
+            (Some(LiteralValue::Number(n)), _) => n.normalize().to_string(),
+            (Some(value), _) => value.to_string(),
+            (None, Some(message)) => format!("veto {}", message),
+            (None, None) => "none".to_string(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_numeric_series() {
+    let sweep = sweep("total", "quantity", "0", "10", "2.5", &["price=4"]);
+    let inputs: Vec<String> = sweep.points.iter().map(|p| p.input.to_string()).collect();
+    assert_eq!(inputs, vec!["0", "2.5", "5", "7.5", "10"]);
+    assert_eq!(results(&sweep), vec!["0", "10", "20", "30", "40"]);
+}
+
+#[test]
+fn test_override_for_swept_fact_is_ignored() {
+    let sweep = sweep(
+        "total",
+        "quantity",
+        "1",
+        "3",
+        "1",
+        &["price=2", "quantity=100"],
+    );
+    assert_eq!(results(&sweep), vec!["2", "4", "6"]);
+}
+
+#[test]
+fn test_changes_show_where_result_flips() {
+    let sweep = sweep("bulk", "quantity", "0", "40", "10", &[]);
+    let changes: Vec<(String, String)> = sweep
+        .changes()
+        .map(|(before, after)| (before.input.to_string(), after.input.to_string()))
+        .collect();
+    assert_eq!(changes, vec![("10".to_string(), "20".to_string())]);
+}
+
+#[test]
+fn test_units_and_vetoes() {
+    let sweep = sweep(
+        "shippable",
+        "weight",
+        "5 kilograms",
+        "20 kilograms",
+        "5",
+        &[],
+    );
+    assert_eq!(
+        sweep.points[1].input,
+        literal("10 kilograms"),
+        "{:?}",
+        sweep.points
+    );
+    assert_eq!(
+        results(&sweep),
+        vec!["true", "true", "false", "veto Too heavy"]
+    );
+    assert_eq!(sweep.changes().count(), 2);
+}
+
+#[test]
+fn test_fact_in_referenced_document() {
+    let sweep = sweep("adult", "buyer.age", "16", "19", "1", &[]);
+    assert_eq!(results(&sweep), vec!["false", "false", "true", "true"]);
+}
+
+#[test]
+fn test_invalid_sweeps_are_rejected() {
+    let engine = engine();
+    let run = |fact: &str, from: &str, to: &str, step: &str| {
+        engine.sweep(
+            "pricing",
+            "total",
+            fact,
+            literal(from)..=literal(to),
+            Decimal::from_str(step).unwrap(),
+            Vec::new(),
+        )
+    };
+
+    assert!(run("quantity", "0", "10", "0").is_err());
+    assert!(run("quantity", "10", "0", "1").is_err());
+    assert!(run("quantity", "0", "10 kilograms", "1").is_err());
+    assert!(run("quantity", "0", "1000000", "1").is_err());
+
+    let error = run("quanity", "0", "10", "1").unwrap_err();
+    assert!(
+        error.to_string().contains("did you mean 'quantity'?"),
+        "{}",
+        error
+    );
+}
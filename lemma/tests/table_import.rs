@@ -0,0 +1,152 @@
+//! Tabular fact import tests
+//!
+//! Key behaviors:
+//! 1. Two-column tables become one fact per row
+//! 2. Wider tables become one fact per cell named <key>_<column>
+//! 3. Cells keep their literal type, anything else becomes text
+//! 4. Invalid names, duplicates and ragged rows are rejected
+//! 5. Errors name the CSV line of the row, also after blank lines and multi-line fields
+
+use lemma::{Engine, FactTable, FactValue, LiteralValue};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+fn fact_value(engine: &Engine, doc: &str, name: &str) -> LiteralValue {
+    let fact = engine
+        .get_document_facts(doc)
+        .into_iter()
+        .find(|f| matches!(&f.fact_type, lemma::FactType::Local(n) if n == name))
+        .unwrap_or_else(|| panic!("fact {} not found", name));
+    match &fact.value {
+        FactValue::Literal(value) => value.clone(),
+        other => panic!("expected literal for {}, got {:?}", name, other),
+    }
+}
+
+#[test]
+fn test_two_column_table() {
+    let csv = "currency,rate\nUSD,1.08\nGBP,0.86\n";
+    let table = FactTable::from_csv(csv).unwrap();
+
+    let mut engine = Engine::new();
+    let code = engine
+        .add_facts_from_table("fx_rates", &table, "rates.csv")
+        .unwrap();
+
+    assert_eq!(code, "doc fx_rates\nfact usd = 1.08\nfact gbp = 0.86\n");
+    assert_eq!(
+        fact_value(&engine, "fx_rates", "usd"),
+        LiteralValue::Number(Decimal::from_str("1.08").unwrap())
+    );
+}
+
+#[test]
+fn test_wide_table_names_facts_by_key_and_column() {
+    let csv = "Product,Unit Price,Label,Active\r\nwidget,10 EUR,\"Widget, large\",true\r\ngadget,25 EUR,Gadget,false\r\n";
+    let table = FactTable::from_csv(csv).unwrap();
+
+    let mut engine = Engine::new();
+    engine
+        .add_facts_from_table("catalog", &table, "catalog.csv")
+        .unwrap();
+
+    assert_eq!(engine.get_document_facts("catalog").len(), 6);
+    assert!(matches!(
+        fact_value(&engine, "catalog", "widget_unit_price"),
+        LiteralValue::Unit(_)
+    ));
+    assert_eq!(
+        fact_value(&engine, "catalog", "widget_label"),
+        LiteralValue::Text("Widget, large".to_string())
+    );
+    assert_eq!(
+        fact_value(&engine, "catalog", "gadget_active"),
+        LiteralValue::Boolean(false)
+    );
+}
+
+#[test]
+fn test_imported_facts_usable_from_other_documents() {
+    let table = FactTable::from_csv("currency,rate\nusd,1.5\n").unwrap();
+
+    let mut engine = Engine::new();
+    engine
+        .add_facts_from_table("fx_rates", &table, "rates.csv")
+        .unwrap();
+    engine
+        .add_lemma_code(
+            r#"
+doc invoice
+fact rates = doc fx_rates
+fact amount = 10
+rule converted = amount * rates.usd
+"#,
+            "invoice.lemma",
+        )
+        .unwrap();
+
+    let response = engine.evaluate("invoice", None, None).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Number(Decimal::from(15)))
+    );
+}
+
+#[test]
+fn test_unknown_units_and_empty_cells() {
+    let table = FactTable::from_csv("item,count\napples,3 apples\npears,\n").unwrap();
+    let code = table.to_lemma("stock").unwrap();
+
+    assert_eq!(code, "doc stock\nfact apples = \"3 apples\"\n");
+}
+
+#[test]
+fn test_large_table() {
+    let mut csv = String::from("key,value\n");
+    for i in 0..2000 {
+        csv.push_str(&format!("k{},{}\n", i, i));
+    }
+    let table = FactTable::from_csv(&csv).unwrap();
+
+    let mut engine = Engine::new();
+    engine
+        .add_facts_from_table("lookup", &table, "lookup.csv")
+        .unwrap();
+    assert_eq!(engine.get_document_facts("lookup").len(), 2000);
+}
+
+#[test]
+fn test_rejects_invalid_tables() {
+    let duplicate = FactTable::from_csv("k,v\nusd,1\nUSD,2\n").unwrap();
+    let err = duplicate.to_lemma("fx").unwrap_err().to_string();
+    assert!(err.contains("duplicate fact 'usd'"), "{}", err);
+
+    let ragged = FactTable::from_csv("k,v\nusd,1,2\n").unwrap();
+    let err = ragged.to_lemma("fx").unwrap_err().to_string();
+    assert!(err.contains("Row 2 has 3 columns"), "{}", err);
+
+    let keyword = FactTable::from_csv("k,v\nveto,1\n").unwrap();
+    assert!(keyword.to_lemma("fx").is_err());
+
+    let single_column = FactTable::from_csv("k\nusd\n").unwrap();
+    assert!(single_column.to_lemma("fx").is_err());
+
+    assert!(FactTable::from_csv("k,v\n\"open,1\n").is_err());
+    assert!(FactTable::from_csv("").is_err());
+    assert!(FactTable::new(vec!["k".into(), "v".into()], vec![])
+        .to_lemma("bad name")
+        .is_err());
+}
+
+#[test]
+fn test_errors_name_the_csv_line() {
+    let csv = "k,v\n\nusd,\"two\nlines\"\n\neur,1,2\n";
+    let table = FactTable::from_csv(csv).unwrap();
+    assert_eq!(table.row_lines, vec![3, 6]);
+    let err = table.to_lemma("fx").unwrap_err().to_string();
+    assert!(err.contains("Row 6 has 3 columns"), "{}", err);
+
+    let crlf = FactTable::from_csv("k,v\r\n\r\nusd,1\r\nusd,2\r\n").unwrap();
+    let err = crlf.to_lemma("fx").unwrap_err().to_string();
+    assert!(err.contains("Row 4: duplicate fact 'usd'"), "{}", err);
+}
@@ -0,0 +1,139 @@
+//! Document templates with parameters
+//!
+//! Key behaviors:
+//! 1. `doc tariff(region, currency)` is instantiated with `doc tariff("EU", EUR)`
+//! 2. Each parameter is a fact of the copy, with its argument as value
+//! 3. A currency code argument sets the copy's currency
+//! 4. Each instantiation is validated on its own
+//! 5. Templates can't be evaluated or referenced without the right arguments
+
+mod common;
+
+use common::value;
+use lemma::{Engine, LiteralValue};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc tariff(region, currency)
+fact base_fee = [money]
+rule surcharge = 5%
+  unless region is "EU" then 0%
+rule fee = base_fee + surcharge?
+
+doc eu_order
+fact tariff = doc tariff("EU", EUR)
+fact tariff.base_fee = 10 EUR
+rule fee = tariff.fee?
+
+doc us_order
+fact tariff = doc tariff("US", USD)
+fact tariff.base_fee = 10 USD
+rule fee = tariff.fee?
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "tariff.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_instances_take_their_arguments() {
+    let engine = engine();
+    let amount = |doc: &str| {
+        let fee = value(&engine, doc, &[], "fee");
+        let (number, currency) = fee.split_once(' ').unwrap();
+        (
+            number.parse::<Decimal>().unwrap().normalize().to_string(),
+            currency.to_string(),
+        )
+    };
+    assert_eq!(amount("eu_order"), ("10".to_string(), "EUR".to_string()));
+    assert_eq!(amount("us_order"), ("10.5".to_string(), "USD".to_string()));
+}
+
+#[test]
+fn test_instances_are_copies() {
+    let engine = engine();
+    let copy = engine.get_document("tariff(\"EU\", EUR)").unwrap();
+    assert_eq!(copy.template.as_deref(), Some("tariff"));
+    assert!(copy.parameters.is_empty());
+    assert_eq!(copy.currency.as_ref().unwrap().to_string(), "EUR");
+    assert!(copy.facts.iter().any(|fact| {
+        fact.fact_type == lemma::FactType::Local("region".to_string())
+            && fact.value == lemma::FactValue::Literal(LiteralValue::Text("EU".to_string()))
+    }));
+
+    let template = engine.get_document("tariff").unwrap();
+    assert!(template.is_template());
+    assert!(template
+        .to_string()
+        .starts_with("doc tariff(region, currency)\n"));
+}
+
+#[test]
+fn test_copy_currency_is_checked() {
+    let template = "doc fee(currency)\nrule minimum = 5 EUR\n";
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            &format!("{}\ndoc eu\nfact fee = doc fee(EUR)", template),
+            "eu.lemma",
+        )
+        .unwrap();
+
+    let mut engine = Engine::new();
+    let error = engine
+        .add_lemma_code(
+            &format!("{}\ndoc us\nfact fee = doc fee(USD)", template),
+            "us.lemma",
+        )
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("declares currency USD"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_each_instantiation_is_validated() {
+    let code = r#"
+doc scale(limit)
+fact weight = [number]
+rule over = weight > limit
+
+doc parcel
+fact scale = doc scale("heavy")
+rule over = scale.over?
+"#;
+    let mut engine = Engine::new();
+    let error = engine.add_lemma_code(code, "scale.lemma").unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("cannot compare number with text"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn test_template_misuse_is_rejected() {
+    let error = engine().evaluate("tariff", None, None).unwrap_err();
+    assert!(error.to_string().contains("is a template"), "{}", error);
+
+    for (code, expected) in [
+        ("doc a\nfact t = doc tariff", "referenced without arguments"),
+        ("doc a\nfact t = doc tariff(\"EU\")", "has 2 parameter(s)"),
+        ("doc a\nfact t = doc eu_order(\"EU\")", "has no parameters"),
+        (
+            "doc a\nfact t = doc tariff(\"EU\", XYZ)",
+            "Unknown currency",
+        ),
+    ] {
+        let mut engine = self::engine();
+        let error = engine.add_lemma_code(code, "a.lemma").unwrap_err();
+        assert!(error.to_string().contains(expected), "{}", error);
+    }
+}
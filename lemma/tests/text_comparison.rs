@@ -0,0 +1,144 @@
+//! Comparing text ignoring case and accents
+//!
+//! Key behaviors:
+//! 1. Text compares exactly unless the document has a `compare text` directive
+//! 2. `ignoring case` folds Unicode case, `ignoring accents` strips Latin accents
+//! 3. Both can be combined, and precomposed and combining accents fold alike
+//! 4. The directive only applies to the document's own rules
+//! 5. The directive round-trips through `Display` and translates to SQL `LOWER()`
+
+mod common;
+
+use common::rule_result;
+use lemma::{Engine, SqlDialect};
+
+fn evaluate(code: &str, doc: &str, rule: &str, overrides: &[&str]) -> String {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+    let facts = engine.parse_facts(doc, overrides).unwrap();
+    let response = engine.evaluate(doc, None, Some(facts)).unwrap();
+    rule_result(&response, rule)
+        .result
+        .as_ref()
+        .unwrap()
+        .to_string()
+}
+
+fn with_directive(directive: &str) -> String {
+    format!(
+        "doc membership\n{}\nfact tier = [text]\nrule gold = tier is \"Gold\"\nrule not_gold = tier is not \"Gold\"\n",
+        directive
+    )
+}
+
+#[test]
+fn test_exact_by_default() {
+    let code = with_directive("");
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"Gold\""]),
+        "true"
+    );
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"gold\""]),
+        "false"
+    );
+}
+
+#[test]
+fn test_ignoring_case() {
+    let code = with_directive("compare text ignoring case");
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"GOLD\""]),
+        "true"
+    );
+    assert_eq!(
+        evaluate(&code, "membership", "not_gold", &["tier=\"gold\""]),
+        "false"
+    );
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"Göld\""]),
+        "false"
+    );
+
+    let code = "doc school\ncompare text ignoring case\nfact name = [text]\nrule match = name == \"ÉCOLE\"\n";
+    assert_eq!(
+        evaluate(code, "school", "match", &["name=\"école\""]),
+        "true"
+    );
+}
+
+#[test]
+fn test_ignoring_accents() {
+    let code = with_directive("compare text ignoring accents");
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"Göld\""]),
+        "true"
+    );
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"gold\""]),
+        "false"
+    );
+}
+
+#[test]
+fn test_ignoring_case_and_accents() {
+    let code = with_directive("compare text ignoring case and accents");
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"GÖLD\""]),
+        "true"
+    );
+    // `o` followed by a combining diaeresis
+    assert_eq!(
+        evaluate(&code, "membership", "gold", &["tier=\"go\u{0308}ld\""]),
+        "true"
+    );
+    assert_eq!(
+        evaluate(&code, "membership", "not_gold", &["tier=\"silver\""]),
+        "true"
+    );
+}
+
+#[test]
+fn test_directive_is_per_document() {
+    let code = r#"
+doc base
+fact tier = [text]
+rule gold = tier is "Gold"
+
+doc lenient
+compare text ignoring case
+fact member = doc base
+rule gold = member.gold?
+rule own = member.tier is "Gold"
+"#;
+    assert_eq!(
+        evaluate(code, "lenient", "gold", &["member.tier=\"gold\""]),
+        "false"
+    );
+    assert_eq!(
+        evaluate(code, "lenient", "own", &["member.tier=\"gold\""]),
+        "true"
+    );
+}
+
+#[test]
+fn test_display_and_sql() {
+    let code = with_directive("compare text ignoring case");
+    let mut engine = Engine::new();
+    engine.add_lemma_code(&code, "test.lemma").unwrap();
+
+    let doc = engine.get_document("membership").unwrap();
+    assert!(doc.to_string().contains("compare text ignoring case\n"));
+
+    let sql = engine
+        .to_sql("membership", "gold", SqlDialect::Postgres)
+        .unwrap();
+    assert_eq!(sql, r#"(LOWER("tier") = LOWER('Gold'))"#);
+
+    let code = with_directive("compare text ignoring accents");
+    let mut engine = Engine::new();
+    engine.add_lemma_code(&code, "test.lemma").unwrap();
+    assert!(engine
+        .to_sql("membership", "gold", SqlDialect::Postgres)
+        .is_err());
+}
@@ -0,0 +1,85 @@
+//! Trace level tests
+//!
+//! Key behaviors:
+//! 1. Full records every step (default)
+//! 2. RuleResultsOnly records just the final result of each rule
+//! 3. Off records nothing but still produces the same results
+
+use lemma::{Engine, EvaluationOptions, OperationRecord, TraceLevel};
+
+const CODE: &str = r#"
+doc pricing
+fact price = 100
+fact quantity = 3
+rule subtotal = price * quantity
+rule total = subtotal? + 10
+    unless quantity > 10 then subtotal?
+"#;
+
+fn evaluate(trace_level: TraceLevel) -> lemma::Response {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+
+    let options = EvaluationOptions::new().with_trace_level(trace_level);
+    engine
+        .evaluate_with_options("pricing", None, None, &options)
+        .unwrap()
+}
+
+#[test]
+fn test_full_trace_is_default() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    let response = engine.evaluate("pricing", None, None).unwrap();
+
+    let total = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "total")
+        .unwrap();
+    assert!(total
+        .operations
+        .iter()
+        .any(|op| matches!(op, OperationRecord::RuleUsed { .. })));
+    assert!(total
+        .operations
+        .iter()
+        .any(|op| matches!(op, OperationRecord::UnlessClauseEvaluated { .. })));
+    assert!(matches!(
+        total.operations.last(),
+        Some(OperationRecord::FinalResult { .. })
+    ));
+}
+
+#[test]
+fn test_rule_results_only() {
+    let response = evaluate(TraceLevel::RuleResultsOnly);
+
+    for result in &response.results {
+        assert_eq!(result.operations.len(), 1, "{}", result.rule_name);
+        assert!(matches!(
+            result.operations[0],
+            OperationRecord::FinalResult { .. }
+        ));
+    }
+}
+
+#[test]
+fn test_trace_off() {
+    let response = evaluate(TraceLevel::Off);
+
+    for result in &response.results {
+        assert!(result.operations.is_empty(), "{}", result.rule_name);
+    }
+}
+
+#[test]
+fn test_results_independent_of_trace_level() {
+    let full = evaluate(TraceLevel::Full);
+    let off = evaluate(TraceLevel::Off);
+
+    for (a, b) in full.results.iter().zip(&off.results) {
+        assert_eq!(a.rule_name, b.rule_name);
+        assert_eq!(a.result, b.result);
+    }
+}
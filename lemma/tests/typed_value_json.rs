@@ -0,0 +1,73 @@
+//! Structured JSON for rule results
+//!
+//! Key behaviors:
+//! 1. Money serializes with its amount and currency
+//! 2. Other units serialize with their amount and unit, tagged with the quantity
+//! 3. Numbers and amounts are decimal strings, keeping their precision
+//! 4. Scalars, dates and times carry a `value`
+
+use lemma::{Engine, TypedValue};
+use serde_json::json;
+
+fn results(code: &str) -> serde_json::Value {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "typed.lemma").unwrap();
+    let response = engine.evaluate("typed", None, None).unwrap();
+    let results: serde_json::Map<String, serde_json::Value> = response
+        .results
+        .into_iter()
+        .map(|r| {
+            let value = TypedValue::from(r.result.unwrap());
+            (r.rule_name, serde_json::to_value(value).unwrap())
+        })
+        .collect();
+    serde_json::Value::Object(results)
+}
+
+#[test]
+fn test_units_are_structured() {
+    let results = results(
+        r#"
+doc typed
+rule price = 100.00 USD
+rule weight = 5 kilograms
+rule discount = 21%
+"#,
+    );
+    assert_eq!(
+        results["price"],
+        json!({"kind": "money", "amount": "100.00", "currency": "USD"})
+    );
+    assert_eq!(
+        results["weight"],
+        json!({"kind": "mass", "amount": "5", "unit": "kilogram"})
+    );
+    assert_eq!(
+        results["discount"],
+        json!({"kind": "percentage", "value": "21"})
+    );
+}
+
+#[test]
+fn test_scalars_carry_a_value() {
+    let results = results(
+        r#"
+doc typed
+rule ratio = 10 / 4
+rule name = "Lemma"
+rule eligible = true
+rule start = 2024-01-15
+"#,
+    );
+    assert_eq!(results["ratio"], json!({"kind": "number", "value": "2.5"}));
+    assert_eq!(results["name"], json!({"kind": "text", "value": "Lemma"}));
+    assert_eq!(
+        results["eligible"],
+        json!({"kind": "boolean", "value": true})
+    );
+    assert_eq!(results["start"]["kind"], "date");
+    assert!(results["start"]["value"]
+        .as_str()
+        .unwrap()
+        .starts_with("2024-01-15T00:00:00"));
+}
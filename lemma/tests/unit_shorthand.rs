@@ -0,0 +1,171 @@
+//! Unit abbreviations, currency symbols and compound durations
+//!
+//! Key behaviors:
+//! 1. Abbreviations resolve to the same units as the full names
+//! 2. `€`, `$` and `£` before an amount are EUR, USD and GBP
+//! 3. Currency codes are case-insensitive
+//! 4. `5m30s` adds up its parts, in the smallest part's unit
+//! 5. Fact overrides accept the same shorthand
+//! 6. A unit is only taken from the number's own line
+
+mod common;
+
+use common::rule_result;
+use lemma::{DurationUnit, Engine, LengthUnit, LiteralValue, MassUnit, MoneyUnit, NumericUnit};
+use rust_decimal::Decimal;
+
+const CODE: &str = r#"
+doc shorthand
+fact weight = 100 kg
+fact portion = 250 g
+fact distance = 5 km
+fact height = 2 m
+fact euros = €100
+fact dollars = $100
+fact pounds = £50
+fact lowercase = 25 eur
+fact timeout = 5m30s
+fact shift = 1h30m
+rule heavy = weight > 99000 grams
+rule far = distance in meters
+rule timeout_seconds = timeout in seconds
+rule total = euros + lowercase
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "shorthand.lemma").unwrap();
+    engine
+}
+
+fn fact_value(engine: &Engine, name: &str) -> LiteralValue {
+    let doc = engine.get_document("shorthand").unwrap();
+    doc.facts
+        .iter()
+        .find(|fact| fact.fact_type.to_string() == name)
+        .and_then(|fact| match &fact.value {
+            lemma::FactValue::Literal(value) => Some(value.clone()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+fn rule_value(engine: &Engine, rule: &str) -> LiteralValue {
+    let response = engine
+        .evaluate("shorthand", Some(vec![rule.to_string()]), None)
+        .unwrap();
+    response.results[0].result.clone().unwrap()
+}
+
+#[test]
+fn test_abbreviations_resolve_to_units() {
+    let engine = engine();
+    assert_eq!(
+        fact_value(&engine, "weight"),
+        LiteralValue::Unit(NumericUnit::Mass(Decimal::from(100), MassUnit::Kilogram))
+    );
+    assert_eq!(
+        fact_value(&engine, "portion"),
+        LiteralValue::Unit(NumericUnit::Mass(Decimal::from(250), MassUnit::Gram))
+    );
+    assert_eq!(
+        fact_value(&engine, "distance"),
+        LiteralValue::Unit(NumericUnit::Length(Decimal::from(5), LengthUnit::Kilometer))
+    );
+    assert_eq!(
+        fact_value(&engine, "height"),
+        LiteralValue::Unit(NumericUnit::Length(Decimal::from(2), LengthUnit::Meter))
+    );
+    assert_eq!(rule_value(&engine, "heavy"), LiteralValue::Boolean(true));
+    // Converting with `in` gives the number in that unit
+    assert_eq!(
+        rule_value(&engine, "far"),
+        LiteralValue::Number(Decimal::from(5000))
+    );
+}
+
+#[test]
+fn test_currency_symbols_and_codes() {
+    let engine = engine();
+    assert_eq!(
+        fact_value(&engine, "euros"),
+        LiteralValue::Unit(NumericUnit::Money(Decimal::from(100), MoneyUnit::Eur))
+    );
+    assert_eq!(
+        fact_value(&engine, "dollars"),
+        LiteralValue::Unit(NumericUnit::Money(Decimal::from(100), MoneyUnit::Usd))
+    );
+    assert_eq!(
+        fact_value(&engine, "pounds"),
+        LiteralValue::Unit(NumericUnit::Money(Decimal::from(50), MoneyUnit::Gbp))
+    );
+    assert_eq!(
+        rule_value(&engine, "total"),
+        LiteralValue::Unit(NumericUnit::Money(Decimal::from(125), MoneyUnit::Eur))
+    );
+}
+
+#[test]
+fn test_compound_durations() {
+    let engine = engine();
+    assert_eq!(
+        fact_value(&engine, "timeout"),
+        LiteralValue::Unit(NumericUnit::Duration(
+            Decimal::from(330),
+            DurationUnit::Second
+        ))
+    );
+    assert_eq!(
+        fact_value(&engine, "shift"),
+        LiteralValue::Unit(NumericUnit::Duration(
+            Decimal::from(90),
+            DurationUnit::Minute
+        ))
+    );
+    assert_eq!(
+        rule_value(&engine, "timeout_seconds"),
+        LiteralValue::Number(Decimal::from(330))
+    );
+}
+
+#[test]
+fn test_overrides_accept_shorthand() {
+    let engine = engine();
+    let facts = engine
+        .parse_facts("shorthand", &["timeout=2min", "euros=€5"])
+        .unwrap();
+    let response = engine
+        .evaluate(
+            "shorthand",
+            Some(vec!["timeout_seconds".to_string(), "total".to_string()]),
+            Some(facts),
+        )
+        .unwrap();
+    assert_eq!(
+        rule_result(&response, "timeout_seconds").result,
+        Some(LiteralValue::Number(Decimal::from(120)))
+    );
+    assert_eq!(
+        rule_result(&response, "total").result,
+        Some(LiteralValue::Unit(NumericUnit::Money(
+            Decimal::from(30),
+            MoneyUnit::Eur
+        )))
+    );
+}
+
+#[test]
+fn test_unit_does_not_cross_lines() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc lines\nfact limit = 5\ncheck positive = limit > 0 else veto \"not positive\"",
+            "lines.lemma",
+        )
+        .unwrap();
+    let response = engine.evaluate("lines", None, None).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Boolean(true))
+    );
+}
@@ -0,0 +1,202 @@
+//! Unless clause evaluation order tests
+//!
+//! Key behaviors:
+//! 1. Unless clauses are last-match-wins by default
+//! 2. `rule x first match = ...` makes the first matching clause win
+//! 3. The modifier survives a Display roundtrip
+//! 4. Unreachable clauses under the rule's mode are reported as warnings,
+//!    found once when the document loads and copied into every response
+//! 5. Inversion honors the rule's clause order
+
+mod common;
+
+use common::value;
+use lemma::inversion::domain_ops::value_within;
+use lemma::{Domain, Engine, LiteralValue, Target};
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc pricing
+fact quantity = 75
+fact is_vip = true
+rule last_discount = 0%
+  unless quantity >= 10 then 10%
+  unless quantity >= 50 then 20%
+  unless is_vip then 25%
+rule first_discount first match = 0%
+  unless quantity >= 50 then 20%
+  unless quantity >= 10 then 10%
+  unless is_vip then 25%
+"#;
+
+fn engine(code: &str) -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_last_match_is_default() {
+    let engine = engine(CODE);
+    assert_eq!(value(&engine, "pricing", &[], "last_discount"), "25%");
+}
+
+#[test]
+fn test_first_match_picks_first_matching_clause() {
+    let engine = engine(CODE);
+    assert_eq!(value(&engine, "pricing", &[], "first_discount"), "20%");
+    assert_eq!(
+        value(&engine, "pricing", &["quantity=20"], "first_discount"),
+        "10%"
+    );
+    assert_eq!(
+        value(&engine, "pricing", &["quantity=5"], "first_discount"),
+        "25%"
+    );
+    assert_eq!(
+        value(
+            &engine,
+            "pricing",
+            &["quantity=5", "is_vip=false"],
+            "first_discount"
+        ),
+        "0%"
+    );
+}
+
+#[test]
+fn test_first_match_display_roundtrip() {
+    let engine = engine(CODE);
+    let doc = engine.get_document("pricing").unwrap();
+    let rule = doc
+        .rules
+        .iter()
+        .find(|r| r.name == "first_discount")
+        .unwrap();
+    let printed = rule.to_string();
+    assert!(printed.starts_with("rule first_discount first match = 0%"));
+
+    let reparsed = engine_with_rule(&printed);
+    let doc = reparsed.get_document("roundtrip").unwrap();
+    assert_eq!(doc.rules[0].unless_mode, lemma::UnlessMode::FirstMatch);
+}
+
+fn engine_with_rule(rule: &str) -> Engine {
+    engine(&format!(
+        "doc roundtrip\nfact quantity = 1\nfact is_vip = false\n{}\n",
+        rule
+    ))
+}
+
+#[test]
+fn test_unreachable_clause_warnings() {
+    let engine = engine(
+        r#"
+doc rates
+fact amount = 10
+rule first_rate first match = 1
+  unless amount > 5 then 2
+  unless true then 3
+  unless amount > 100 then 4
+rule last_rate = 1
+  unless amount > 5 then 2
+  unless amount > 5 then 3
+"#,
+    );
+    let response = engine.evaluate("rates", None, None).unwrap();
+
+    assert_eq!(response.warnings.len(), 3, "{:?}", response.warnings);
+    assert!(response.warnings.iter().any(|w| w.contains("first_rate")
+        && w.contains("unless clause 3")
+        && w.contains("an earlier clause always matches")));
+    assert!(response
+        .warnings
+        .iter()
+        .any(|w| w.contains("first_rate") && w.contains("default expression is unreachable")));
+    assert!(response.warnings.iter().any(|w| w.contains("last_rate")
+        && w.contains("unless clause 1")
+        && w.contains("a later clause has the same condition")));
+}
+
+#[test]
+fn test_warnings_are_found_when_loading() {
+    let mut engine = engine(
+        r#"
+doc rates
+fact amount = 10
+rule rate = 1
+  unless amount > 5 then 2
+  unless amount > 5 then 3
+"#,
+    );
+    let loaded = engine.document_warnings("rates").to_vec();
+    assert_eq!(loaded.len(), 1, "{:?}", loaded);
+    for _ in 0..2 {
+        let response = engine.evaluate("rates", None, None).unwrap();
+        assert_eq!(response.warnings, loaded);
+    }
+
+    // Reloading the document finds them again
+    engine
+        .add_lemma_code("doc rates\nfact amount = 10\nrule rate = 1\n", "test.lemma")
+        .unwrap();
+    assert!(engine.document_warnings("rates").is_empty());
+    let response = engine.evaluate("rates", None, None).unwrap();
+    assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+}
+
+#[test]
+fn test_no_warnings_for_reachable_clauses() {
+    let engine = engine(CODE);
+    let response = engine.evaluate("pricing", None, None).unwrap();
+    assert!(response.warnings.is_empty(), "{:?}", response.warnings);
+}
+
+#[test]
+fn test_inversion_honors_first_match() {
+    let engine = engine(
+        r#"
+doc shipping
+fact weight = [number]
+rule cost first match = 10
+  unless weight > 30 then 50
+  unless weight > 10 then 20
+"#,
+    );
+
+    let solutions = engine
+        .invert(
+            "shipping",
+            "cost",
+            Target::value(LiteralValue::Number(20.into())),
+            HashMap::new(),
+        )
+        .unwrap();
+
+    // Heavier packages hit the first clause, so 20 only covers (10, 30]
+    assert_eq!(solutions.len(), 1);
+    let weight = solutions[0]
+        .iter()
+        .find(|(fact, _)| fact.reference == ["weight"])
+        .map(|(_, domain)| domain.clone())
+        .unwrap();
+    for (value, expected) in [(5, false), (11, true), (30, true), (31, false)] {
+        assert_eq!(
+            contains(&weight, &LiteralValue::Number(value.into())),
+            expected,
+            "weight {} in {:?}",
+            value,
+            weight
+        );
+    }
+}
+
+fn contains(domain: &Domain, value: &LiteralValue) -> bool {
+    match domain {
+        Domain::Range { min, max } => value_within(value, min, max),
+        Domain::Union(parts) => parts.iter().any(|d| contains(d, value)),
+        Domain::Enumeration(values) => values.contains(value),
+        Domain::Complement(inner) => !contains(inner, value),
+        Domain::Unconstrained => true,
+    }
+}
@@ -0,0 +1,103 @@
+//! Overrides for facts the document doesn't have
+//!
+//! Key behaviors:
+//! 1. By default the override is ignored and reported as a diagnostic
+//! 2. The closest fact name is suggested, including nested fact paths
+//! 3. Names that aren't close to any fact get no suggestion
+//! 4. In strict mode evaluation fails with a `FactParse` error
+
+use lemma::{
+    DiagnosticKind, Engine, EvaluationOptions, LemmaError, LemmaResult, Response, UnusedOverrides,
+};
+
+const CODE: &str = r#"
+doc product
+fact price = [number]
+
+doc order
+fact quantity = [number]
+fact item = doc product
+rule total = quantity * item.price
+"#;
+
+fn evaluate(overrides: &[&str], mode: UnusedOverrides) -> LemmaResult<Response> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "order.lemma").unwrap();
+    let facts = engine.parse_facts("order", overrides).unwrap();
+    let options = EvaluationOptions::default().with_unused_overrides(mode);
+    engine.evaluate_with_options("order", None, Some(facts), &options)
+}
+
+fn unused_messages(response: &Response) -> Vec<&str> {
+    response
+        .diagnostics
+        .iter()
+        .filter(|d| d.kind == DiagnosticKind::UnusedOverride)
+        .map(|d| d.message.as_str())
+        .collect()
+}
+
+#[test]
+fn test_warns_with_suggestion() {
+    let response = evaluate(
+        &["quantity=2", "item.price=3", "quanity=5"],
+        UnusedOverrides::Warn,
+    )
+    .unwrap();
+
+    let messages = unused_messages(&response);
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    assert!(
+        messages[0].contains("did you mean 'quantity'?"),
+        "{}",
+        messages[0]
+    );
+
+    let total = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "total")
+        .unwrap();
+    assert_eq!(total.result.as_ref().unwrap().to_string(), "6");
+}
+
+#[test]
+fn test_suggests_nested_fact_path() {
+    let response = evaluate(
+        &["quantity=2", "item.price=3", "item.prise=4"],
+        UnusedOverrides::Warn,
+    )
+    .unwrap();
+
+    let messages = unused_messages(&response);
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    assert!(
+        messages[0].contains("did you mean 'item.price'?"),
+        "{}",
+        messages[0]
+    );
+}
+
+#[test]
+fn test_no_suggestion_for_distant_names() {
+    let response = evaluate(&["quantity=2", "discount=10"], UnusedOverrides::Warn).unwrap();
+
+    let messages = unused_messages(&response);
+    assert_eq!(messages.len(), 1, "{:?}", messages);
+    assert!(!messages[0].contains("did you mean"), "{}", messages[0]);
+}
+
+#[test]
+fn test_strict_mode_fails() {
+    let error = evaluate(&["quantity=2", "quanity=5"], UnusedOverrides::Strict).unwrap_err();
+    match error {
+        LemmaError::FactParse(errors) => {
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].fact.as_deref(), Some("quanity"));
+            assert_eq!(errors[0].expected, vec!["quantity".to_string()]);
+        }
+        other => panic!("Expected FactParse error, got {:?}", other),
+    }
+
+    assert!(evaluate(&["quantity=2", "item.price=3"], UnusedOverrides::Strict).is_ok());
+}
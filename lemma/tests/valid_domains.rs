@@ -0,0 +1,134 @@
+//! Per-fact valid domain summaries
+//!
+//! Key behaviors:
+//! 1. Veto boundaries of one fact merge into normalized intervals
+//! 2. `is not` comparisons of text facts list the excluded values
+//! 3. Facts compared with each other name each other in `depends_jointly_on`
+//! 4. Given facts are left out, and facts no condition mentions are unconstrained
+
+use lemma::{DomainSummary, Engine, LiteralValue};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc shipping
+fact weight = [mass]
+fact country = [text]
+rule can_ship = true
+  unless weight < 0 kilograms then veto "negative weight"
+  unless weight > 100 kilograms then veto "too heavy"
+  unless country is "NK" then veto "embargo"
+
+doc booking
+fact first_night = [number]
+fact last_night = [number]
+fact guests = [number]
+rule nights = last_night - first_night
+  unless first_night > last_night then veto "ends before it starts"
+  unless guests < 1 then veto "no guests"
+
+doc pricing
+fact price = [number]
+rule double = price * 2
+"#;
+
+fn summaries(
+    doc: &str,
+    rule: &str,
+    given: HashMap<String, LiteralValue>,
+) -> HashMap<String, DomainSummary> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+        .valid_domains(doc, rule, given)
+        .unwrap()
+        .into_iter()
+        .map(|(fact, summary)| (fact.reference.last().unwrap().clone(), summary))
+        .collect()
+}
+
+fn kilograms(value: i64) -> LiteralValue {
+    LiteralValue::Unit(lemma::NumericUnit::Mass(
+        Decimal::from(value),
+        lemma::MassUnit::Kilogram,
+    ))
+}
+
+fn number(value: i64) -> LiteralValue {
+    LiteralValue::Number(Decimal::from(value))
+}
+
+fn text(value: &str) -> LiteralValue {
+    LiteralValue::Text(value.to_string())
+}
+
+fn names(summary: &DomainSummary) -> Vec<String> {
+    summary
+        .depends_jointly_on
+        .iter()
+        .map(|fact| fact.reference.last().unwrap().clone())
+        .collect()
+}
+
+#[test]
+fn test_veto_boundaries_merge_into_one_interval() {
+    let summaries = summaries("shipping", "can_ship", HashMap::new());
+    let weight = &summaries["weight"];
+    assert_eq!(weight.intervals.len(), 1);
+    assert!(weight.values.is_empty());
+    assert!(weight.allows(&kilograms(0)));
+    assert!(weight.allows(&kilograms(40)));
+    assert!(weight.allows(&kilograms(100)));
+    assert!(!weight.allows(&kilograms(-1)));
+    assert!(!weight.allows(&kilograms(101)));
+    assert!(weight.depends_jointly_on.is_empty());
+}
+
+#[test]
+fn test_excluded_text_values_are_listed() {
+    let summaries = summaries("shipping", "can_ship", HashMap::new());
+    let country = &summaries["country"];
+    assert_eq!(country.excluded, vec![text("NK")]);
+    assert!(country.allows(&text("NL")));
+    assert!(!country.allows(&text("NK")));
+}
+
+#[test]
+fn test_compared_facts_depend_jointly() {
+    let summaries = summaries("booking", "nights", HashMap::new());
+    assert_eq!(names(&summaries["first_night"]), vec!["last_night"]);
+    assert_eq!(names(&summaries["last_night"]), vec!["first_night"]);
+    assert!(summaries["guests"].depends_jointly_on.is_empty());
+    assert!(summaries["guests"].allows(&number(1)));
+    assert!(!summaries["guests"].allows(&number(0)));
+}
+
+#[test]
+fn test_given_facts_are_left_out() {
+    let mut given = HashMap::new();
+    given.insert("booking.last_night".to_string(), number(10));
+    let summaries = summaries("booking", "nights", given);
+    assert!(!summaries.contains_key("last_night"));
+    assert!(summaries["first_night"].depends_jointly_on.is_empty());
+    assert!(summaries["first_night"].allows(&number(10)));
+    assert!(!summaries["first_night"].allows(&number(11)));
+}
+
+#[test]
+fn test_unmentioned_fact_is_unconstrained() {
+    let summaries = summaries("pricing", "double", HashMap::new());
+    let price = &summaries["price"];
+    assert!(price.is_unconstrained());
+    assert_eq!(price.intervals.len(), 1);
+    assert_eq!(price.intervals[0].to_string(), "(-∞, +∞)");
+    assert!(price.allows(&number(-5)));
+}
+
+#[test]
+fn test_summary_serializes_for_forms() {
+    let summaries = summaries("shipping", "can_ship", HashMap::new());
+    let json = serde_json::to_value(&summaries["weight"]).unwrap();
+    assert_eq!(json["domain"]["type"], "range");
+    assert!(json["intervals"].is_array());
+    assert!(json["depends_jointly_on"].as_array().unwrap().is_empty());
+}
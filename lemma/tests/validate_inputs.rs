@@ -0,0 +1,116 @@
+//! Checking inputs against the vetoes of a document
+//!
+//! Key behaviors:
+//! 1. `Engine::validate_inputs` returns the vetoes the inputs trigger, with their messages
+//! 2. Rule values no veto condition depends on are not computed
+//! 3. Rules a veto condition refers to are computed
+//! 4. A later matching value clause wins over a veto, as in a full evaluation
+//! 5. Facts a veto condition needs without a value are reported as missing
+
+use lemma::{Engine, LemmaResult, LiteralValue, TriggeredVeto};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const CODE: &str = r#"
+doc applicant
+fact age = [number]
+fact income = [number]
+fact exempt = false
+
+rule premium = quote(income)
+  unless age < 18 then veto "Applicant must be an adult"
+  unless age > 120 then veto "Invalid age {age}"
+
+rule yearly_income = income * 12
+rule affordable = true
+  unless yearly_income? < 10000 then veto "Income too low"
+  unless exempt then true
+
+rule discount = quote(age)
+"#;
+
+fn engine(calls: &Arc<AtomicUsize>) -> Engine {
+    let calls = Arc::clone(calls);
+    let mut engine = Engine::new();
+    engine
+        .register_function("quote", 1, move |args| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(args[0].clone())
+        })
+        .unwrap();
+    engine.add_lemma_code(CODE, "applicant.lemma").unwrap();
+    engine
+}
+
+fn validate(engine: &Engine, facts: &[&str]) -> LemmaResult<lemma::InputValidation> {
+    let facts = engine.parse_facts("applicant", facts).unwrap();
+    engine.validate_inputs("applicant", facts)
+}
+
+#[test]
+fn test_acceptable_inputs() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = engine(&calls);
+    let validation = validate(&engine, &["age=30", "income=2000"]).unwrap();
+    assert!(validation.is_valid(), "{:?}", validation);
+    assert_eq!(validation.doc_name, "applicant");
+}
+
+#[test]
+fn test_triggered_vetoes_are_returned() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = engine(&calls);
+    let validation = validate(&engine, &["age=150", "income=500"]).unwrap();
+    assert!(!validation.is_valid());
+    assert_eq!(
+        validation.vetoes,
+        vec![
+            TriggeredVeto {
+                rule: "premium".to_string(),
+                message: Some("Invalid age 150".to_string()),
+            },
+            TriggeredVeto {
+                rule: "affordable".to_string(),
+                message: Some("Income too low".to_string()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_values_are_not_computed() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = engine(&calls);
+    validate(&engine, &["age=30", "income=2000"]).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+    let facts = engine
+        .parse_facts("applicant", &["age=30", "income=2000"])
+        .unwrap();
+    let response = engine.evaluate("applicant", None, Some(facts)).unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    let premium = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "premium")
+        .unwrap();
+    assert!(matches!(premium.result, Some(LiteralValue::Number(_))));
+}
+
+#[test]
+fn test_later_value_clause_wins() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = engine(&calls);
+    let validation = validate(&engine, &["age=30", "income=500", "exempt=true"]).unwrap();
+    assert!(validation.is_valid(), "{:?}", validation);
+}
+
+#[test]
+fn test_missing_facts_are_reported() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let engine = engine(&calls);
+    let validation = validate(&engine, &["age=30"]).unwrap();
+    assert!(!validation.is_valid());
+    assert!(validation.vetoes.is_empty());
+    assert_eq!(validation.missing_facts, vec!["income".to_string()]);
+}
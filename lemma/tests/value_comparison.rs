@@ -0,0 +1,106 @@
+//! Comparison semantics shared by evaluation, inversion and validation
+//!
+//! Key behaviors:
+//! 1. Units of one kind are converted before comparing: `1 kilogram is 1000 grams`
+//! 2. Percentages compare with plain numbers by their number of percent
+//! 3. Dates compare by the period they cover, and with a bare year
+//! 4. Inversion treats equal values in other units as the same value
+//! 5. Literal comparisons that can't be evaluated are rejected on load
+
+use lemma::semantic::value_ops::{compare, ordering, values_equal};
+use lemma::{parse_literal, ComparisonOperator, Engine, LiteralValue, Target, TextComparison};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+fn value(literal: &str) -> LiteralValue {
+    parse_literal(literal).unwrap()
+}
+
+fn holds(left: &str, op: ComparisonOperator, right: &str) -> bool {
+    compare(&value(left), &op, &value(right), TextComparison::default()).unwrap()
+}
+
+#[test]
+fn test_units_are_converted() {
+    assert!(values_equal(&value("1 kilogram"), &value("1000 grams")));
+    assert_eq!(
+        ordering(&value("900 grams"), &value("1 kilogram")),
+        Some(Ordering::Less)
+    );
+    assert!(compare(
+        &value("10 EUR"),
+        &ComparisonOperator::LessThan,
+        &value("20 USD"),
+        TextComparison::default()
+    )
+    .is_err());
+}
+
+#[test]
+fn test_percentages_and_numbers() {
+    assert!(holds("10%", ComparisonOperator::Equal, "10"));
+    assert!(holds("12", ComparisonOperator::GreaterThan, "10%"));
+
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc d\nfact rate = [percentage]\nrule high = rate > 10",
+            "d.lemma",
+        )
+        .unwrap();
+    let facts = engine.parse_facts("d", &["rate=15%"]).unwrap();
+    let response = engine.evaluate("d", None, Some(facts)).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Boolean(true))
+    );
+}
+
+#[test]
+fn test_dates_compare_by_period() {
+    assert!(holds("2024-03-15", ComparisonOperator::Is, "2024-03"));
+    assert!(holds("2024-03-15", ComparisonOperator::LessThan, "2024-04"));
+    assert!(holds("2024-03-15", ComparisonOperator::Is, "2024"));
+    assert_eq!(
+        ordering(&value("2024-03-15"), &value("2023")),
+        Some(Ordering::Greater)
+    );
+}
+
+#[test]
+fn test_inversion_agrees_on_converted_units() {
+    let mut engine = Engine::new();
+    engine
+        .add_lemma_code(
+            "doc d\nfact weight = [mass]\nrule exact = weight is 1 kilogram and weight is 1000 grams",
+            "d.lemma",
+        )
+        .unwrap();
+    let facts = engine.parse_facts("d", &["weight=1000 grams"]).unwrap();
+    let response = engine.evaluate("d", None, Some(facts)).unwrap();
+    assert_eq!(
+        response.results[0].result,
+        Some(LiteralValue::Boolean(true))
+    );
+
+    let solutions = engine
+        .invert(
+            "d",
+            "exact",
+            Target::value(LiteralValue::Boolean(true)),
+            HashMap::new(),
+        )
+        .unwrap();
+    assert!(!solutions.is_empty());
+}
+
+#[test]
+fn test_invalid_literal_comparisons_are_rejected() {
+    for code in [
+        "doc d\nrule r = \"a\" < \"b\"",
+        "doc d\nfact name = \"Ada\"\nrule r = name > 3",
+    ] {
+        let error = Engine::new().add_lemma_code(code, "d.lemma").unwrap_err();
+        assert!(error.to_string().contains("cannot evaluate"), "{}", error);
+    }
+}
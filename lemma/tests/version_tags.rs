@@ -0,0 +1,103 @@
+//! Evaluating earlier versions of documents: `Engine::tag_version` and `EvaluationOptions::with_as_of`
+//!
+//! Key behaviors:
+//! 1. A tag keeps the documents as loaded; later changes don't affect it
+//! 2. `as_of` with a label evaluates exactly that version
+//! 3. `as_of` with a date evaluates the latest version tagged on or before it
+//! 4. Without a matching tag the evaluation fails, listing the tags
+
+use lemma::{Engine, EvaluationOptions, LiteralValue};
+use rust_decimal::Decimal;
+
+fn rates(rate: u32) -> String {
+    format!(
+        "doc benefit\nfact income = [number]\nrule allowance = income * {}%",
+        rate
+    )
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(&rates(10), "benefit.lemma").unwrap();
+    engine.tag_version("2024-01");
+    engine.add_lemma_code(&rates(12), "benefit.lemma").unwrap();
+    engine.tag_version("2024-07-01");
+    engine.add_lemma_code(&rates(15), "benefit.lemma").unwrap();
+    engine
+}
+
+fn allowance(engine: &Engine, options: &EvaluationOptions) -> lemma::LemmaResult<LiteralValue> {
+    let facts = engine.parse_facts("benefit", &["income=1000"]).unwrap();
+    let response = engine.evaluate_with_options("benefit", None, Some(facts), options)?;
+    Ok(response.results[0].result.clone().unwrap())
+}
+
+fn number(value: i64) -> LiteralValue {
+    LiteralValue::Number(Decimal::from(value))
+}
+
+#[test]
+fn test_loaded_documents_by_default() {
+    let engine = engine();
+    assert_eq!(
+        allowance(&engine, &EvaluationOptions::default()).unwrap(),
+        number(150)
+    );
+    assert_eq!(engine.version_tags(), vec!["2024-01", "2024-07-01"]);
+}
+
+#[test]
+fn test_as_of_label_and_date() {
+    let engine = engine();
+    for (as_of, expected) in [
+        ("2024-01", 100),
+        ("2024-03-15", 100),
+        ("2024-07", 100),
+        ("2024-07-01", 120),
+        ("2025", 120),
+    ] {
+        let options = EvaluationOptions::new().with_as_of(as_of);
+        assert_eq!(
+            allowance(&engine, &options).unwrap(),
+            number(expected),
+            "{}",
+            as_of
+        );
+    }
+}
+
+#[test]
+fn test_tagged_versions_keep_their_hash() {
+    let engine = engine();
+    let facts = engine.parse_facts("benefit", &["income=1000"]).unwrap();
+    let response = engine
+        .evaluate_with_options(
+            "benefit",
+            None,
+            Some(facts),
+            &EvaluationOptions::new().with_as_of("2024-01"),
+        )
+        .unwrap();
+    assert_ne!(
+        response.doc_version.as_deref(),
+        engine.document_version("benefit")
+    );
+}
+
+#[test]
+fn test_unknown_tags_fail() {
+    let mut engine = engine();
+    for as_of in ["2023-12-31", "v2"] {
+        let error = allowance(&engine, &EvaluationOptions::new().with_as_of(as_of)).unwrap_err();
+        assert!(
+            error.to_string().contains("2024-01, 2024-07-01"),
+            "{}",
+            error
+        );
+    }
+
+    assert!(engine.remove_version_tag("2024-07-01"));
+    assert!(!engine.remove_version_tag("2024-07-01"));
+    let options = EvaluationOptions::new().with_as_of("2025");
+    assert_eq!(allowance(&engine, &options).unwrap(), number(100));
+}
@@ -0,0 +1,93 @@
+//! Catching vetoes of referenced rules
+//!
+//! Key behaviors:
+//! 1. `have rule?` is true when the rule produced a value, false when it was vetoed
+//! 2. `unless have not other.rule? then ...` replaces a cascading veto with a fallback
+//! 3. Without a fallback the veto still applies to the caller
+//! 4. `have` on a rule reference orders the referenced rule first and survives a Display roundtrip
+
+mod common;
+
+use common::{result, value};
+use lemma::Engine;
+
+const CODE: &str = r#"
+doc supplier
+fact rating = 8
+rule approved = rating >= 5
+  unless rating < 3 then veto "Supplier is blacklisted"
+rule discount = 10%
+  unless not approved? then 0%
+
+doc order
+fact supplier = doc supplier
+fact price = 100
+rule discount = supplier.discount?
+  unless have not supplier.approved? then 0%
+rule discount_without_fallback = supplier.discount?
+rule supplier_checked = have supplier.approved?
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+#[test]
+fn test_have_rule_is_true_when_rule_has_value() {
+    let engine = engine();
+    assert_eq!(value(&engine, "order", &[], "supplier_checked"), "true");
+    assert_eq!(value(&engine, "order", &[], "discount"), "10%");
+}
+
+#[test]
+fn test_have_rule_is_false_when_rule_is_vetoed() {
+    let engine = engine();
+    assert_eq!(
+        value(&engine, "order", &["supplier.rating=1"], "supplier_checked"),
+        "false"
+    );
+}
+
+#[test]
+fn test_have_not_provides_fallback_for_veto() {
+    let engine = engine();
+    assert_eq!(
+        value(&engine, "order", &["supplier.rating=1"], "discount"),
+        "0%"
+    );
+}
+
+#[test]
+fn test_veto_still_applies_without_fallback() {
+    let engine = engine();
+    assert_eq!(
+        result(
+            &engine,
+            "order",
+            &["supplier.rating=1"],
+            "discount_without_fallback"
+        )
+        .veto_message,
+        Some("Supplier is blacklisted".to_string())
+    );
+}
+
+#[test]
+fn test_have_rule_display_roundtrip() {
+    let engine = engine();
+    let doc = engine.get_document("order").unwrap();
+    let rule = doc.rules.iter().find(|r| r.name == "discount").unwrap();
+    assert_eq!(
+        rule.unless_clauses[0].condition.to_string(),
+        "have not supplier.approved?"
+    );
+
+    let checked = doc
+        .rules
+        .iter()
+        .find(|r| r.name == "supplier_checked")
+        .unwrap();
+    assert_eq!(checked.expression.to_string(), "have supplier.approved?");
+}
@@ -0,0 +1,143 @@
+//! Localized veto message tests
+//!
+//! Key behaviors:
+//! 1. Without a locale the default message is returned
+//! 2. A matching translation replaces the default message
+//! 3. Region tags fall back to their primary language (nl-BE -> nl)
+//! 4. Unknown locales fall back to the default message
+//! 5. With several locales the first one with a translation is used
+
+use lemma::localization::parse_accept_language;
+use lemma::{Engine, EvaluationOptions};
+
+const CODE: &str = r#"
+doc shipping
+fact weight = 30
+rule allowed = weight <= 25
+    unless weight > 25 then veto "too heavy" @nl "te zwaar" @de-DE "zu schwer"
+"#;
+
+fn veto_message(locale: Option<&str>) -> Option<String> {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+
+    let mut options = EvaluationOptions::new();
+    if let Some(locale) = locale {
+        options = options.with_locale(locale);
+    }
+
+    let response = engine
+        .evaluate_with_options("shipping", None, None, &options)
+        .unwrap();
+    let rule_result = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "allowed")
+        .unwrap();
+
+    assert_eq!(rule_result.result, None);
+    rule_result.veto_message.clone()
+}
+
+#[test]
+fn test_default_message_without_locale() {
+    assert_eq!(veto_message(None), Some("too heavy".to_string()));
+}
+
+#[test]
+fn test_default_message_from_plain_evaluate() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+
+    let response = engine.evaluate("shipping", None, None).unwrap();
+    assert_eq!(
+        response.results[0].veto_message,
+        Some("too heavy".to_string())
+    );
+}
+
+#[test]
+fn test_exact_locale_match() {
+    assert_eq!(veto_message(Some("nl")), Some("te zwaar".to_string()));
+    assert_eq!(veto_message(Some("de-de")), Some("zu schwer".to_string()));
+}
+
+#[test]
+fn test_primary_language_fallback() {
+    assert_eq!(veto_message(Some("nl-BE")), Some("te zwaar".to_string()));
+    assert_eq!(veto_message(Some("de")), Some("zu schwer".to_string()));
+}
+
+#[test]
+fn test_unknown_locale_uses_default() {
+    assert_eq!(veto_message(Some("fr")), Some("too heavy".to_string()));
+}
+
+#[test]
+fn test_first_translated_locale_is_used() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+
+    let message = |header: &str| {
+        let options = EvaluationOptions::new().with_locales(parse_accept_language(header));
+        let response = engine
+            .evaluate_with_options("shipping", None, None, &options)
+            .unwrap();
+        response.results[0].veto_message.clone()
+    };
+    assert_eq!(message("fr-FR, nl;q=0.8"), Some("te zwaar".to_string()));
+    assert_eq!(
+        message("fr, de;q=0.5, nl;q=0.9"),
+        Some("te zwaar".to_string())
+    );
+    assert_eq!(message("fr, es"), Some("too heavy".to_string()));
+}
+
+#[test]
+fn test_localized_veto_propagates_to_dependent_rule() {
+    let code = r#"
+doc shipping
+fact weight = 30
+rule allowed = weight <= 25
+    unless weight > 25 then veto "too heavy" @nl "te zwaar"
+rule can_ship = allowed?
+"#;
+
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+
+    let options = EvaluationOptions::new().with_locale("nl");
+    let response = engine
+        .evaluate_with_options("shipping", None, None, &options)
+        .unwrap();
+    let rule_result = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "can_ship")
+        .unwrap();
+
+    assert_eq!(rule_result.veto_message, Some("te zwaar".to_string()));
+}
+
+#[test]
+fn test_translations_roundtrip_through_display() {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+
+    let doc = engine.get_document("shipping").unwrap();
+    let rendered = doc.rules[0].unless_clauses[0].result.to_string();
+    assert_eq!(
+        rendered,
+        r#"veto "too heavy" @nl "te zwaar" @de-DE "zu schwer""#
+    );
+}
+
+#[test]
+fn test_parse_accept_language() {
+    assert_eq!(
+        parse_accept_language("nl-BE,nl;q=0.9,en;q=0.8"),
+        vec!["nl-BE", "nl", "en"]
+    );
+    assert_eq!(parse_accept_language("en;q=0.5, de"), vec!["de", "en"]);
+    assert_eq!(parse_accept_language("*, fr;q=0"), Vec::<String>::new());
+}
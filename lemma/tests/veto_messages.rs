@@ -0,0 +1,147 @@
+//! Veto messages with interpolated values
+//!
+//! Key behaviors:
+//! 1. `{fact}` and `{rule?}` placeholders are filled in when the veto applies
+//! 2. Translated messages interpolate the same references
+//! 3. A vetoed rule used in a message vetoes with its own message
+//! 4. Inversion keeps the message as written and matches filled-in messages
+//! 5. Braces around anything but a reference, or around a name that isn't a fact, are kept as
+//!    text; a keyword in braces is rejected
+
+use lemma::{parse_facts, BranchOutcome, Engine, EvaluationOptions, Target};
+use std::collections::HashMap;
+
+const CODE: &str = r#"
+doc shipping
+fact weight = [mass]
+fact limit = 100 kilograms
+rule overweight = weight - limit
+rule allowed = weight <= limit
+  unless weight > limit then veto "weight {weight} exceeds limit of {limit} by {overweight?}" @nl "gewicht {weight} is te zwaar"
+  unless weight < 0 kilograms then veto "weight {{negative}}"
+"#;
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.add_lemma_code(CODE, "test.lemma").unwrap();
+    engine
+}
+
+fn veto_message(engine: &Engine, weight: &str, locale: Option<&str>) -> Option<String> {
+    let facts = Some(parse_facts(&[&format!("weight={}", weight)]).unwrap());
+    let options = EvaluationOptions {
+        locales: locale.into_iter().map(str::to_string).collect(),
+        ..EvaluationOptions::default()
+    };
+    let response = engine
+        .evaluate_with_options("shipping", None, facts, &options)
+        .unwrap();
+    response
+        .results
+        .into_iter()
+        .find(|r| r.rule_name == "allowed")
+        .unwrap()
+        .veto_message
+}
+
+#[test]
+fn test_veto_message_interpolates_facts_and_rules() {
+    let engine = engine();
+    let message = veto_message(&engine, "120 kilograms", None).unwrap();
+    assert!(message.starts_with("weight 120"), "{}", message);
+    assert!(message.contains("exceeds limit of 100"), "{}", message);
+    assert!(message.contains("by 20"), "{}", message);
+    assert!(!message.contains('{'), "{}", message);
+}
+
+#[test]
+fn test_translated_veto_message_interpolates() {
+    let engine = engine();
+    let message = veto_message(&engine, "120 kilograms", Some("nl")).unwrap();
+    assert!(message.starts_with("gewicht 120"), "{}", message);
+    assert!(message.ends_with("is te zwaar"), "{}", message);
+}
+
+#[test]
+fn test_escaped_braces_are_literal() {
+    let engine = engine();
+    assert_eq!(
+        veto_message(&engine, "-1 kilograms", None),
+        Some("weight {negative}".to_string())
+    );
+}
+
+#[test]
+fn test_vetoed_rule_in_message_vetoes() {
+    let code = r#"
+doc test
+fact age = [number]
+rule valid_age = age
+  unless age < 0 then veto "Invalid age"
+rule adult = age >= 18
+  unless age < 18 then veto "Only {valid_age?} years old"
+"#;
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+
+    let facts = Some(parse_facts(&["age=-3"]).unwrap());
+    let response = engine.evaluate("test", None, facts).unwrap();
+    let adult = response
+        .results
+        .iter()
+        .find(|r| r.rule_name == "adult")
+        .unwrap();
+    assert_eq!(adult.veto_message, Some("Invalid age".to_string()));
+}
+
+#[test]
+fn test_inversion_keeps_parameterized_message() {
+    let engine = engine();
+    let solutions = engine
+        .invert(
+            "shipping",
+            "allowed",
+            Target::veto(Some(
+                "weight 120 kilograms exceeds limit of 100 kilograms by 20 kilograms".to_string(),
+            )),
+            HashMap::new(),
+        )
+        .unwrap();
+    assert_eq!(solutions.len(), 1);
+
+    let outcome = BranchOutcome::Veto(Some("weight {weight} exceeds {limit}".to_string()));
+    let value = serde_json::to_value(&outcome).unwrap();
+    assert_eq!(value["message"], "weight {weight} exceeds {limit}");
+    assert_eq!(value["parameters"], serde_json::json!(["weight", "limit"]));
+}
+
+#[test]
+fn test_braces_around_other_text_are_kept() {
+    let code = r#"
+doc test
+fact age = 10
+rule adult = age >= 18
+  unless age < 18 then veto "Only {age} years old, {age + 1} next year, see {details}"
+"#;
+    let mut engine = Engine::new();
+    engine.add_lemma_code(code, "test.lemma").unwrap();
+
+    let response = engine.evaluate("test", None, None).unwrap();
+    assert_eq!(
+        response.results[0].veto_message,
+        Some("Only 10 years old, {age + 1} next year, see {details}".to_string())
+    );
+}
+
+#[test]
+fn test_keyword_placeholder_is_rejected() {
+    let code = r#"
+doc test
+fact age = 10
+rule adult = age >= 18
+  unless age < 18 then veto "Too young {unless} older"
+"#;
+    let mut engine = Engine::new();
+    let error = engine.add_lemma_code(code, "test.lemma").unwrap_err();
+    assert!(error.to_string().contains("'{{unless}}'"), "{}", error);
+}
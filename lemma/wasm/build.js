@@ -73,13 +73,17 @@ function parseCargoMetadata() {
 
 /**
  * Build WASM package
+ *
+ * With `playground`, the package also exports `WasmPlayground` with the
+ * bundled examples, for the hosted playground page.
  */
-export function build() {
-  console.log('Building WASM package...');
+export function build({ playground = false } = {}) {
+  console.log(`Building WASM package${playground ? ' with the playground' : ''}...`);
 
   // Run wasm-pack with web target (works in both browser and Node.js)
+  const features = playground ? ' -- --features playground' : '';
   try {
-    execSync('wasm-pack build --target web --out-dir pkg', {
+    execSync(`wasm-pack build --target web --out-dir pkg${features}`, {
       stdio: 'inherit',
       cwd: PROJECT_ROOT
     });
@@ -136,5 +140,5 @@ export function build() {
 
 // CLI interface
 if (import.meta.url === `file://${process.argv[1]}`) {
-  build();
+  build({ playground: process.argv.includes('--playground') });
 }
@@ -114,6 +114,7 @@ export async function test() {
       fact date_fact = 2024-01-15
 
       rule double_number = number_fact * 2
+      rule double_money = unit_fact * 2
     `, 'type_test.lemma');
 
     const typesParsed = JSON.parse(typesResult);
@@ -144,8 +145,12 @@ export async function test() {
     if (!doubleRule.result || doubleRule.result.value !== "100") {
       throw new Error(`Expected double_number to be 100 (50*2), got ${doubleRule.result?.value}`);
     }
-    if (doubleRule.result.type !== "number") {
-      throw new Error(`Expected type to be nmber, got ${doubleRule.result.type}`);
+    if (doubleRule.result.kind !== "number") {
+      throw new Error(`Expected kind to be number, got ${doubleRule.result.kind}`);
+    }
+    const moneyResult = typedFactsParsed.rules.double_money.result;
+    if (moneyResult.kind !== "money" || moneyResult.amount !== "400" || moneyResult.currency !== "EUR") {
+      throw new Error(`Expected double_money to be 400 EUR, got ${JSON.stringify(moneyResult)}`);
     }
     console.log('✓ Type handling in facts object successful');
 